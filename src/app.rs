@@ -4,14 +4,15 @@ use crate::autocomplete::AutoComplete;
 use crate::command::handlers::register_all_commands;
 use crate::command::parser::InputType;
 use crate::command::registry::Registry;
-use crate::llm::client::stream_llm_with_cancellation;
 use crate::logging::log;
 use crate::session::manager::SessionManager;
 
 use crate::push_toast;
 use crate::ui::components::chat::Chat;
+use crate::ui::components::confirm_dialog::{ConfirmAction, ConfirmDialog};
 use crate::ui::components::input::Input;
 use crate::ui::components::popup::Popup;
+use crate::ui::keymap::KeyHint;
 use crate::utils::git;
 
 use crate::views::chat::{init_chat, render_chat};
@@ -29,8 +30,8 @@ use crate::views::session_rename_dialog::{
     render_session_rename_dialog, RenameAction,
 };
 use crate::views::sessions_dialog::{
-    handle_sessions_dialog_key_event, handle_sessions_dialog_mouse_event, init_sessions_dialog,
-    render_sessions_dialog, SessionsDialogAction,
+    get_pending_delete, handle_sessions_dialog_key_event, handle_sessions_dialog_mouse_event,
+    init_sessions_dialog, render_sessions_dialog, SessionsDialogAction,
 };
 use crate::views::suggestions_popup::{
     clear_suggestions, get_selected_suggestion, handle_suggestions_popup_key_event,
@@ -42,10 +43,48 @@ use crate::views::{
 };
 
 use crate::{
-    get_toast_manager, render_toasts,
+    get_toast_manager,
     theme::{self, Theme},
 };
 
+/// Messages loaded when opening a session and per "load more" page.
+const SESSION_PAGE_SIZE: usize = 50;
+
+/// Frame interval of the "thinking…" placeholder while awaiting the first token.
+const THINKING_TICK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// How often the input placeholder changes on the home screen.
+const PLACEHOLDER_ROTATION_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// How long quitting waits for a cancelled turn to stop its tools.
+const SHUTDOWN_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// How long startup waits on models.dev before skipping the active model check.
+const MODEL_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// Lets the user know a theme was incomplete and which colors were filled in.
+fn defaulted_theme_keys_message(theme: &Theme) -> Option<String> {
+    if theme.defaulted_keys.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "Theme \"{}\" is missing {} color(s), using defaults: {}",
+        theme.name,
+        theme.defaulted_keys.len(),
+        theme.defaulted_keys.join(", ")
+    ))
+}
+
+fn warn_defaulted_theme_keys(theme: &Theme) {
+    if let Some(message) = defaulted_theme_keys_message(theme) {
+        push_toast(crate::ui::toast::new_toast(
+            message,
+            ratatui_toolkit::ToastLevel::Warning,
+            Some(std::time::Duration::from_secs(5)),
+        ));
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BaseFocus {
     Home,
@@ -62,6 +101,18 @@ pub enum OverlayFocus {
     SessionsDialog,
     SessionRenameDialog,
     WhichKey,
+    Confirm,
+    Help,
+    Notifications,
+    /// Selecting chat lines to copy
+    CopyMode,
+}
+
+/// A destructive action waiting on the confirm dialog.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PendingConfirm {
+    DeleteSession(String),
+    RunCommand(String),
 }
 
 pub struct App {
@@ -78,7 +129,11 @@ pub struct App {
     pub sessions_dialog_state: SessionsDialogState,
     pub session_rename_dialog_state: SessionRenameDialogState,
     pub which_key_state: crate::views::which_key::WhichKeyState,
+    pub help_state: crate::views::help::HelpState,
+    pub notifications_state: crate::views::notifications::NotificationsState,
     pub api_key_input: crate::ui::components::api_key_input::ApiKeyInput,
+    pub confirm_dialog: ConfirmDialog,
+    pending_confirm: Option<PendingConfirm>,
     pub prefs_dao: Option<crate::persistence::PrefsDAO>,
     pub agent: String,
     pub model: String,
@@ -89,19 +144,66 @@ pub struct App {
     ctrl_c_press_count: u8,
     last_ctrl_c_time: std::time::Instant,
     pub themes: Vec<Theme>,
+    /// File each entry of `themes` was loaded from
+    theme_paths: Vec<std::path::PathBuf>,
+    theme_watcher: Option<theme::ThemeWatcher>,
     pub current_theme_index: usize,
     pub dark_mode: bool,
-    pub is_streaming: bool,
-    chunk_sender: Option<crate::llm::ChunkSender>,
-    chunk_receiver: Option<crate::llm::ChunkReceiver>,
-    streaming_cancel_token: Option<tokio_util::sync::CancellationToken>,
+    /// Whether the terminal renders 24-bit color; otherwise theme colors are
+    /// mapped to the 256-color palette
+    truecolor: bool,
+    pub color_mode: crate::persistence::ColorMode,
+    /// Set by `--no-color`, so reloading preferences keeps monochrome
+    no_color: bool,
+    high_contrast_theme: Theme,
+    streaming: crate::llm::stream_state::StreamingState,
+    /// Where responses come from; swapped for a mock in tests
+    chat_stream: std::sync::Arc<dyn crate::llm::chat_stream::ChatStream>,
+    /// Messages sent while streaming, started when the current turn ends
+    message_queue: crate::session::queue::MessageQueue,
+    /// Request and raw chunks of the latest turn, for `/debug last`
+    last_turn: Option<crate::llm::debug_log::TurnLog>,
+    /// Transcript file for the running turn when `/tee` is on
+    tee: Option<crate::llm::tee::StreamTee>,
+    /// Set for `--prompt` and piped runs: replies go to stdout instead of
+    /// the chat view
+    plain_output: Option<crate::llm::plain::PlainOutput<std::io::Stdout, std::io::Stderr>>,
+    /// Session whose messages are still being read after switching to it
+    session_load: Option<crate::session::loader::SessionLoad>,
     last_frame_size: ratatui::layout::Rect,
-    streaming_model: Option<String>,
-    streaming_provider: Option<String>,
     last_animation_update: std::time::Instant,
+    last_thinking_tick: std::time::Instant,
+    /// Chunks received vs. applied this turn, to gauge how much coalescing
+    /// cuts re-layouts
+    streaming_chunk_stats: (usize, usize),
+    /// Context window of the last looked-up (provider, model)
+    context_limit_cache: Option<((String, String), Option<u32>)>,
+    /// Background models.dev fetch started when sizing a send found no
+    /// cached provider data
+    providers_fetch: Option<tokio::task::JoinHandle<()>>,
+    /// Estimated tokens of the messages not loaded into the chat, by session
+    /// and how many there are, so sizing a send doesn't read them again
+    older_tokens_cache: Option<((String, usize), usize)>,
     streaming_chat_len_before_assistant: usize,
     tool_call_message_indices: std::collections::HashMap<String, usize>,
     tool_call_order: Vec<String>,
+    /// When each tool call still waiting on its result was started
+    tool_call_started: std::collections::HashMap<String, std::time::Instant>,
+    /// Position in the input placeholder rotation
+    placeholder_index: usize,
+    last_placeholder_rotation: std::time::Instant,
+    /// When to save the input as a draft
+    draft: crate::session::draft::DraftAutosave,
+    /// Session (or home screen) whose draft the input holds
+    draft_key: Option<String>,
+    /// Hide the status bar, hints and scrollbar to leave more room for
+    /// reading
+    focus_mode: bool,
+    /// Turned off to select text with the terminal's own mouse selection
+    mouse_capture: bool,
+    /// Something on screen changed outside of input events, e.g. a chunk
+    /// arrived or a spinner moved
+    needs_redraw: bool,
 }
 
 impl App {
@@ -110,18 +212,23 @@ impl App {
         register_all_commands(&mut registry);
 
         let autocomplete = AutoComplete::new(crate::autocomplete::CommandAuto::new(&registry));
-        let placeholder = Self::get_random_placeholder();
-        let placeholder_static: &'static str = Box::leak(placeholder.into_boxed_str());
-        let mut input = Input::new().with_autocomplete(autocomplete);
-        input.set_placeholder(placeholder_static);
+        let input = Input::new().with_autocomplete(autocomplete);
+        let placeholder_index = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as usize);
 
         let cwd = std::env::current_dir()
             .ok()
             .and_then(|p| p.to_str().map(|s| s.to_string()))
             .unwrap_or_else(|| "?".to_string());
 
-        let theme = theme::Theme::load_from_file("src/theme.json")
-            .unwrap_or_else(|_| theme::Theme::load_from_file("src/themes/ayu.json").unwrap());
+        let mut theme_path = std::path::PathBuf::from("src/theme.json");
+        let theme = theme::Theme::load_from_file(&theme_path).unwrap_or_else(|_| {
+            theme_path = std::path::PathBuf::from("src/themes/ayu.json");
+            theme::Theme::load_from_file(&theme_path).unwrap()
+        });
+        let theme_watcher = theme::ThemeWatcher::from_env(vec![theme_path.clone()]);
+        warn_defaulted_theme_keys(&theme);
         let colors = theme.get_colors(true);
 
         let home_state = init_home();
@@ -135,11 +242,26 @@ impl App {
         let which_key_state = crate::views::which_key::init_which_key();
         let api_key_input = crate::ui::components::api_key_input::ApiKeyInput::new();
 
-        let session_manager = SessionManager::new()
-            .with_history()
-            .unwrap_or_else(|_| SessionManager::new());
-
-        let prefs_dao = match crate::persistence::PrefsDAO::new() {
+        // Tests get in-memory stores so they never touch the user's data
+        #[cfg(not(test))]
+        let (session_manager, prefs_dao) = (
+            SessionManager::new().with_history().unwrap_or_else(|e| {
+                push_toast(crate::ui::toast::new_toast(
+                    format!("History disabled: {}", e),
+                    ratatui_toolkit::ToastLevel::Error,
+                    None,
+                ));
+                SessionManager::new()
+            }),
+            crate::persistence::PrefsDAO::new(),
+        );
+        #[cfg(test)]
+        let (session_manager, prefs_dao) = (
+            SessionManager::new().with_in_memory_history(),
+            crate::persistence::PrefsDAO::new_in_memory(),
+        );
+
+        let prefs_dao = match prefs_dao {
             Ok(dao) => Some(dao),
             Err(e) => {
                 eprintln!("Warning: Failed to initialize preferences DAO: {}", e);
@@ -147,20 +269,7 @@ impl App {
             }
         };
 
-        let active_model_info = if let Some(ref dao) = prefs_dao {
-            dao.get_active_model().ok().flatten()
-        } else {
-            None
-        };
-
-        let (active_model, active_provider_name) =
-            if let Some((provider_id, model_id)) = active_model_info {
-                (model_id.clone(), provider_id.clone())
-            } else {
-                ("big-pickle".to_string(), "opencode".to_string())
-            };
-
-        Self {
+        let mut app = Self {
             running: true,
             version: env!("CARGO_PKG_VERSION").to_string(),
             input,
@@ -174,35 +283,205 @@ impl App {
             sessions_dialog_state,
             session_rename_dialog_state,
             which_key_state,
+            help_state: crate::views::help::HelpState::new(),
+            notifications_state: crate::views::notifications::NotificationsState::new(),
             api_key_input,
+            confirm_dialog: ConfirmDialog::new(),
+            pending_confirm: None,
             prefs_dao,
             agent,
-            model: active_model,
-            provider_name: active_provider_name,
+            model: crate::model::fallback::DEFAULT_MODEL.to_string(),
+            provider_name: crate::model::fallback::DEFAULT_PROVIDER.to_string(),
             cwd,
             base_focus: BaseFocus::Home,
             overlay_focus: OverlayFocus::None,
             ctrl_c_press_count: 0,
             last_ctrl_c_time: std::time::Instant::now(),
             themes: vec![theme],
+            theme_paths: vec![theme_path],
+            theme_watcher,
             current_theme_index: 0,
             dark_mode: true,
-            is_streaming: false,
-            chunk_sender: None,
-            chunk_receiver: None,
-            streaming_cancel_token: None,
+            truecolor: theme::supports_truecolor(),
+            color_mode: crate::persistence::ColorMode::default(),
+            no_color: false,
+            high_contrast_theme: theme::Theme::high_contrast(),
+            streaming: crate::llm::stream_state::StreamingState::Idle,
+            chat_stream: std::sync::Arc::new(crate::llm::chat_stream::AisdkChatStream),
+            message_queue: crate::session::queue::MessageQueue::new(),
+            last_turn: None,
+            tee: None,
+            plain_output: None,
+            session_load: None,
             last_frame_size: ratatui::layout::Rect::default(),
-            streaming_model: None,
-            streaming_provider: None,
             last_animation_update: std::time::Instant::now(),
+            last_thinking_tick: std::time::Instant::now(),
+            context_limit_cache: None,
+            providers_fetch: None,
+            older_tokens_cache: None,
+            streaming_chunk_stats: (0, 0),
             streaming_chat_len_before_assistant: 0,
             tool_call_message_indices: std::collections::HashMap::new(),
             tool_call_order: Vec::new(),
+            tool_call_started: std::collections::HashMap::new(),
+            placeholder_index,
+            last_placeholder_rotation: std::time::Instant::now(),
+            draft: crate::session::draft::DraftAutosave::default(),
+            draft_key: None,
+            focus_mode: false,
+            mouse_capture: true,
+            needs_redraw: true,
+        };
+        app.apply_preferences();
+        app.load_command_aliases();
+        app
+    }
+
+    /// Reads the user's command aliases, warning about the ones left out.
+    fn load_command_aliases(&mut self) {
+        let aliases = crate::command::aliases::CommandAliases::load();
+        for problem in self.command_registry.set_aliases(aliases) {
+            push_toast(crate::ui::toast::new_toast(
+                problem,
+                ratatui_toolkit::ToastLevel::Warning,
+                None,
+            ));
+        }
+    }
+
+    /// Applies the saved scroll, color, density and markdown preferences and
+    /// the active model. A missing preference keeps the current value.
+    fn apply_preferences(&mut self) {
+        let dao = self.prefs_dao.as_ref();
+        let scroll_prefs = dao.and_then(|dao| dao.get_scroll_preferences().ok());
+        let color_mode = if self.no_color || theme::no_color_requested() {
+            Some(crate::persistence::ColorMode::Monochrome)
+        } else {
+            dao.and_then(|dao| dao.get_color_mode().ok())
+        };
+        let density = dao.and_then(|dao| dao.get_density().ok());
+        let markdown = dao.and_then(|dao| dao.get_markdown_renderer().ok());
+        let active_model = dao.and_then(|dao| dao.get_active_model().ok().flatten());
+
+        if let Some(prefs) = scroll_prefs {
+            self.chat_state.chat.scroll_prefs = prefs;
+        }
+        if let Some(mode) = color_mode {
+            self.set_color_mode(mode);
+        }
+        if let Some(density) = density {
+            self.chat_state.chat.density = density;
+        }
+        if let Some(kind) = markdown {
+            self.chat_state.chat.markdown = crate::ui::markdown::renderer::renderer_for(kind);
+        }
+        if let Some((provider_id, model_id)) = active_model {
+            self.provider_name = provider_id;
+            self.model = model_id;
+        }
+        self.update_placeholder();
+        self.apply_history_scope();
+        self.apply_command_prefix();
+    }
+
+    /// Has the input, its suggestions and drafts treat text starting with
+    /// the preferred prefix as a command.
+    fn apply_command_prefix(&mut self) {
+        let prefix = self
+            .prefs_dao
+            .as_ref()
+            .and_then(|dao| dao.get_command_prefix().ok())
+            .unwrap_or_else(|| crate::command::parser::DEFAULT_PREFIX.to_string());
+        self.input.set_command_prefix(&prefix);
+        self.suggestions_popup_state.popup.prefix = prefix.clone();
+        self.draft.set_command_prefix(&prefix);
+    }
+
+    /// Points Up/Down in the input at the prompts of the working directory,
+    /// or of every directory when the preference says so.
+    fn apply_history_scope(&mut self) {
+        let scope = self
+            .prefs_dao
+            .as_ref()
+            .and_then(|dao| dao.get_history_scope().ok())
+            .unwrap_or_default();
+        self.input.set_history_scope(&self.cwd, scope);
+    }
+
+    /// `--no-color`: monochrome for the whole run, `/reload` included.
+    pub fn disable_color(&mut self) {
+        self.no_color = true;
+        self.set_color_mode(crate::persistence::ColorMode::Monochrome);
+    }
+
+    /// `/reload`: reads what startup reads from disk again. A running
+    /// response isn't touched and keeps the model it started with.
+    fn reload(&mut self) {
+        let failed_themes = self.reload_themes();
+        self.apply_preferences();
+        self.load_command_aliases();
+        let env_vars = crate::persistence::auth::reload_dotenv();
+        let providers = crate::persistence::AuthDAO::new()
+            .and_then(|dao| dao.load())
+            .map_or(0, |auth| auth.len());
+        self.context_limit_cache = None;
+
+        let themes = self.themes.len() - failed_themes.len();
+        push_toast(crate::ui::toast::new_toast(
+            format!(
+                "Reloaded preferences, {} theme{}, {} .env variable{} and {} connected provider{}",
+                themes,
+                if themes == 1 { "" } else { "s" },
+                env_vars,
+                if env_vars == 1 { "" } else { "s" },
+                providers,
+                if providers == 1 { "" } else { "s" }
+            ),
+            ratatui_toolkit::ToastLevel::Success,
+            Some(std::time::Duration::from_secs(3)),
+        ));
+        for error in failed_themes {
+            push_toast(crate::ui::toast::new_toast(
+                error,
+                ratatui_toolkit::ToastLevel::Error,
+                Some(std::time::Duration::from_secs(5)),
+            ));
+        }
+    }
+
+    /// Loads every theme file again; a theme that fails keeps its previous
+    /// version. Returns why each failed.
+    fn reload_themes(&mut self) -> Vec<String> {
+        let mut failed = Vec::new();
+        for (index, path) in self.theme_paths.iter().enumerate() {
+            match theme::Theme::load_from_file(path) {
+                Ok(theme) => {
+                    warn_defaulted_theme_keys(&theme);
+                    self.themes[index] = theme;
+                }
+                Err(e) => failed.push(format!("Theme {} not reloaded: {}", path.display(), e)),
+            }
         }
+        self.session_rename_dialog_state
+            .set_colors(self.get_current_theme_colors());
+        failed
     }
 
-    fn get_random_placeholder() -> String {
-        let suggestions = vec![
+    /// Hint `index` in the rotation. Every other one is about setup when
+    /// something's missing: no key connected, or still on the default model.
+    fn placeholder_hint(index: usize, has_key: bool, default_model: bool) -> String {
+        let mut tips = Vec::new();
+        if !has_key {
+            tips.push("Tip: run /connect to add an API key");
+        }
+        if default_model {
+            tips.push("Tip: using the free default model, pick another with /models");
+        }
+        if !tips.is_empty() && index.is_multiple_of(2) {
+            return tips[(index / 2) % tips.len()].to_string();
+        }
+
+        let suggestions = [
             "Fix a TODO in the codebase",
             "What is the tech stack of this project?",
             "Write unit tests for this module",
@@ -216,15 +495,39 @@ impl App {
             "Add type hints to this code",
             "Implement caching for this endpoint",
         ];
+        format!(
+            "Ask anything... \"{}\"",
+            suggestions[index % suggestions.len()]
+        )
+    }
 
-        use std::time::{SystemTime, UNIX_EPOCH};
-        let index = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_nanos() as usize
-            % suggestions.len();
+    fn update_placeholder(&mut self) {
+        let default_model = self.provider_name == crate::model::fallback::DEFAULT_PROVIDER
+            && self.model == crate::model::fallback::DEFAULT_MODEL;
+        let hint = Self::placeholder_hint(
+            self.placeholder_index,
+            crate::persistence::auth::has_any_key(),
+            default_model,
+        );
+        self.input.set_placeholder(hint);
+        self.last_placeholder_rotation = std::time::Instant::now();
+        self.needs_redraw = true;
+    }
 
-        format!("Ask anything... \"{}\"", suggestions[index])
+    /// Moves to the next hint while the home screen sits idle; the input
+    /// keeps its placeholder while there's text in it.
+    fn rotate_placeholder(&mut self) {
+        let idle = self.base_focus == BaseFocus::Home
+            && self.overlay_focus == OverlayFocus::None
+            && self.input.is_empty();
+        if !idle {
+            self.last_placeholder_rotation = std::time::Instant::now();
+            return;
+        }
+        if self.last_placeholder_rotation.elapsed() >= PLACEHOLDER_ROTATION_INTERVAL {
+            self.placeholder_index = self.placeholder_index.wrapping_add(1);
+            self.update_placeholder();
+        }
     }
 
     pub fn quit(&mut self) {
@@ -232,8 +535,14 @@ impl App {
     }
 
     pub fn get_current_theme_colors(&self) -> theme::ThemeColors {
-        if self.themes.is_empty() {
-            return theme::ThemeColors {
+        if self.color_mode == crate::persistence::ColorMode::Monochrome {
+            return theme::ThemeColors::monochrome();
+        }
+
+        let colors = if self.color_mode == crate::persistence::ColorMode::HighContrast {
+            self.high_contrast_theme.get_colors(self.dark_mode)
+        } else if self.themes.is_empty() {
+            theme::ThemeColors {
                 primary: ratatui::style::Color::Rgb(255, 140, 0),
                 background: ratatui::style::Color::Reset,
                 text: ratatui::style::Color::Reset,
@@ -247,11 +556,68 @@ impl App {
                 warning: ratatui::style::Color::Rgb(255, 255, 0),
                 error: ratatui::style::Color::Rgb(255, 0, 0),
                 info: ratatui::style::Color::Rgb(0, 255, 255),
+            }
+        } else {
+            let theme = &self.themes[self.current_theme_index];
+            theme.get_colors(self.dark_mode)
+        };
+
+        if self.truecolor {
+            colors
+        } else {
+            colors.degraded()
+        }
+    }
+
+    /// Reloads theme files edited on disk. A theme that fails to parse keeps
+    /// its previous version so a half-saved file can't break the UI.
+    pub fn reload_changed_themes(&mut self) {
+        let Some(watcher) = &self.theme_watcher else {
+            return;
+        };
+
+        for path in watcher.changed_paths() {
+            let Some(index) = self.theme_paths.iter().position(|p| *p == path) else {
+                continue;
             };
+            match theme::Theme::load_from_file(&path) {
+                Ok(theme) => {
+                    // One toast per reload, a warning when colors are missing
+                    let toast = match defaulted_theme_keys_message(&theme) {
+                        Some(missing) => crate::ui::toast::new_toast(
+                            format!("Reloaded theme {}. {}", path.display(), missing),
+                            ratatui_toolkit::ToastLevel::Warning,
+                            Some(std::time::Duration::from_secs(5)),
+                        ),
+                        None => crate::ui::toast::new_toast(
+                            format!("Reloaded theme {}", path.display()),
+                            ratatui_toolkit::ToastLevel::Success,
+                            Some(std::time::Duration::from_secs(2)),
+                        ),
+                    };
+                    self.themes[index] = theme;
+                    if index == self.current_theme_index {
+                        self.session_rename_dialog_state
+                            .set_colors(self.get_current_theme_colors());
+                    }
+                    push_toast(toast);
+                }
+                Err(e) => {
+                    push_toast(crate::ui::toast::new_toast(
+                        format!("Theme {} not reloaded: {}", path.display(), e),
+                        ratatui_toolkit::ToastLevel::Error,
+                        Some(std::time::Duration::from_secs(5)),
+                    ));
+                }
+            }
         }
+    }
 
-        let theme = &self.themes[self.current_theme_index];
-        theme.get_colors(self.dark_mode)
+    pub fn set_color_mode(&mut self, mode: crate::persistence::ColorMode) {
+        self.color_mode = mode;
+        self.chat_state.chat.plain_markdown = mode == crate::persistence::ColorMode::Monochrome;
+        self.session_rename_dialog_state
+            .set_colors(self.get_current_theme_colors());
     }
 
     pub fn cycle_theme(&mut self) {
@@ -264,13 +630,61 @@ impl App {
         self.dark_mode = !self.dark_mode;
     }
 
+    /// The keys `handle_keys` and `handle_base_keys` act on anywhere, for
+    /// the help overlay.
+    pub const GLOBAL_KEYS: &'static [KeyHint] = &[
+        KeyHint::ctrl('x', "Open the shortcuts menu"),
+        KeyHint::new(
+            KeyCode::Char('?'),
+            "Show this help (when the input is empty)",
+        ),
+        KeyHint::new(KeyCode::Tab, "Switch between Plan and Build agents"),
+        KeyHint::new(
+            KeyCode::Esc,
+            "Clear queued messages, then stop the response",
+        ),
+        KeyHint::ctrl(
+            'c',
+            "Clear input; twice quits, or first stops a running response (see /quit-guard)",
+        ),
+        KeyHint::ctrl('z', "Undo the last session delete"),
+    ];
+
+    /// The keys `handle_base_keys` acts on while the chat is showing.
+    pub const CHAT_KEYS: &'static [KeyHint] = &[
+        KeyHint::new(KeyCode::PageUp, "Scroll up a page"),
+        KeyHint::new(KeyCode::PageDown, "Scroll down a page"),
+        KeyHint::new(KeyCode::Home, "Jump to the top (when the input is empty)"),
+        KeyHint::new(KeyCode::End, "Jump to the bottom (when the input is empty)"),
+    ];
+
+    /// The keys `handle_copy_mode_keys` acts on.
+    pub const COPY_MODE_KEYS: &'static [KeyHint] = &[
+        KeyHint::new(KeyCode::Up, "Move the cursor up"),
+        KeyHint::new(KeyCode::Char('k'), "Move the cursor up"),
+        KeyHint::new(KeyCode::Down, "Move the cursor down"),
+        KeyHint::new(KeyCode::Char('j'), "Move the cursor down"),
+        KeyHint::new(KeyCode::PageUp, "Move the cursor up a page"),
+        KeyHint::new(KeyCode::PageDown, "Move the cursor down a page"),
+        KeyHint::new(KeyCode::Home, "Move the cursor to the top"),
+        KeyHint::new(KeyCode::Char('g'), "Move the cursor to the top"),
+        KeyHint::new(KeyCode::End, "Move the cursor to the bottom"),
+        KeyHint::new(KeyCode::Char('G'), "Move the cursor to the bottom"),
+        KeyHint::new(KeyCode::Enter, "Copy the selection"),
+        KeyHint::new(KeyCode::Char('y'), "Copy the selection"),
+        KeyHint::new(KeyCode::Esc, "Leave copy mode"),
+        KeyHint::new(KeyCode::Char('q'), "Leave copy mode"),
+    ];
+
     pub fn handle_keys(&mut self, key: KeyEvent) {
         match key.code {
             KeyCode::Char('c') if key.modifiers == event::KeyModifiers::CONTROL => {
                 let now = std::time::Instant::now();
                 if now.duration_since(self.last_ctrl_c_time).as_secs() < 1 {
                     self.ctrl_c_press_count += 1;
-                    if self.ctrl_c_press_count >= 2 {
+                    if self.ctrl_c_press_count == 2 && self.is_streaming() && self.quit_guard() {
+                        self.stop_streaming_keeping_reply();
+                    } else if self.ctrl_c_press_count >= 2 {
                         self.quit();
                     }
                 } else {
@@ -282,6 +696,11 @@ impl App {
                 }
                 return;
             }
+            KeyCode::Char('z')
+                if key.modifiers == event::KeyModifiers::CONTROL && self.undo_session_delete() =>
+            {
+                return;
+            }
             _ => {}
         }
 
@@ -315,7 +734,7 @@ impl App {
                             }
                         }
 
-                        push_toast(ratatui_toolkit::Toast::new(
+                        push_toast(crate::ui::toast::new_toast(
                             format!("Switched to: {}", model_id_clone),
                             ratatui_toolkit::ToastLevel::Info,
                             None,
@@ -332,7 +751,7 @@ impl App {
                             false
                         };
 
-                        push_toast(ratatui_toolkit::Toast::new(
+                        push_toast(crate::ui::toast::new_toast(
                             if is_favorite {
                                 "Added to favorites"
                             } else {
@@ -344,6 +763,22 @@ impl App {
 
                         self.refresh_models_dialog();
                     }
+                    crate::views::models_dialog::ModelsDialogAction::MoveFavorite {
+                        provider_id,
+                        model_id,
+                        up,
+                    } => {
+                        let moved = self.prefs_dao.as_ref().is_some_and(|dao| {
+                            dao.move_favorite(&provider_id, &model_id, up)
+                                .unwrap_or(false)
+                        });
+                        if moved {
+                            self.refresh_models_dialog();
+                            self.models_dialog_state
+                                .dialog
+                                .select_item(&provider_id, &model_id);
+                        }
+                    }
                     crate::views::models_dialog::ModelsDialogAction::None => {}
                 }
 
@@ -377,11 +812,12 @@ impl App {
                     } => {
                         if let Some(auth_dao) = crate::persistence::AuthDAO::new().ok() {
                             let _ = auth_dao.set_provider(
-                                provider_name,
+                                provider_name.clone(),
                                 crate::persistence::AuthConfig::Api { key: api_key },
                             );
                             self.connect_dialog_state = init_connect_dialog();
                         }
+                        self.select_provider(&provider_name);
                         self.overlay_focus = OverlayFocus::None;
                         true
                     }
@@ -405,25 +841,23 @@ impl App {
                     }
                     SessionsDialogAction::Select(id) => {
                         self.session_manager.switch_session(&id);
-                        if let Some(session) = self.session_manager.get_session(&id) {
-                            self.chat_state.chat.clear();
-                            for message in &session.messages {
-                                self.chat_state.chat.add_message(message.clone());
-                            }
-                        }
+                        self.load_session_into_chat(&id);
                         self.base_focus = BaseFocus::Chat;
                         self.sessions_dialog_state.dialog.hide();
                         self.overlay_focus = OverlayFocus::None;
                         true
                     }
                     SessionsDialogAction::Delete(id) => {
-                        self.session_manager.delete_session(&id);
-                        if let Some(pending) = crate::views::sessions_dialog::get_pending_delete(
-                            &mut self.sessions_dialog_state,
-                        ) {
-                            self.session_manager.delete_session(&pending);
-                        }
-                        self.refresh_sessions_dialog();
+                        let title = self
+                            .session_manager
+                            .get_session(&id)
+                            .map(|s| s.title.clone())
+                            .unwrap_or_else(|| id.clone());
+                        self.open_confirm(
+                            PendingConfirm::DeleteSession(id),
+                            "Delete session",
+                            format!("Delete \"{}\"?", title),
+                        );
                         true
                     }
                     SessionsDialogAction::Rename(id, title) => {
@@ -435,6 +869,22 @@ impl App {
                     }
                 }
             }
+            OverlayFocus::Confirm => match self.confirm_dialog.handle_key_event(key) {
+                ConfirmAction::Confirmed => {
+                    if let Some(pending) = self.pending_confirm.take() {
+                        self.run_confirmed(pending);
+                    }
+                    true
+                }
+                ConfirmAction::Cancelled => {
+                    if let Some(pending) = self.pending_confirm.take() {
+                        self.cancel_confirm(pending);
+                    }
+                    true
+                }
+                ConfirmAction::Handled => true,
+                ConfirmAction::NotHandled => false,
+            },
             OverlayFocus::SessionRenameDialog => {
                 let action = handle_session_rename_dialog_key_event(
                     &mut self.session_rename_dialog_state,
@@ -486,6 +936,14 @@ impl App {
                         self.overlay_focus = OverlayFocus::None;
                         self.quit();
                     }
+                    crate::views::which_key::WhichKeyAction::ToggleFocusMode => {
+                        self.overlay_focus = OverlayFocus::None;
+                        self.toggle_focus_mode();
+                    }
+                    crate::views::which_key::WhichKeyAction::ToggleMouseCapture => {
+                        self.overlay_focus = OverlayFocus::None;
+                        self.toggle_mouse_capture();
+                    }
                     crate::views::which_key::WhichKeyAction::ScrollUp => {
                         self.overlay_focus = OverlayFocus::None;
                         self.chat_state.chat.scroll_up(1);
@@ -494,12 +952,67 @@ impl App {
                         self.overlay_focus = OverlayFocus::None;
                         self.chat_state.chat.scroll_down(1);
                     }
+                    crate::views::which_key::WhichKeyAction::ExplainError => {
+                        self.overlay_focus = OverlayFocus::None;
+                        self.explain_last_tool_error();
+                    }
+                    crate::views::which_key::WhichKeyAction::Branch => {
+                        self.overlay_focus = OverlayFocus::None;
+                        tokio::task::block_in_place(|| {
+                            let rt = tokio::runtime::Handle::current();
+                            rt.block_on(self.process_input("/branch"));
+                        });
+                    }
+                    crate::views::which_key::WhichKeyAction::ToggleBookmark => {
+                        self.overlay_focus = OverlayFocus::None;
+                        self.toggle_bookmark();
+                    }
+                    crate::views::which_key::WhichKeyAction::PreviousBookmark => {
+                        self.overlay_focus = OverlayFocus::None;
+                        self.jump_to_bookmark(false);
+                    }
+                    crate::views::which_key::WhichKeyAction::NextBookmark => {
+                        self.overlay_focus = OverlayFocus::None;
+                        self.jump_to_bookmark(true);
+                    }
+                    crate::views::which_key::WhichKeyAction::CopyMode => {
+                        self.overlay_focus = OverlayFocus::None;
+                        self.begin_copy_mode();
+                    }
+                    crate::views::which_key::WhichKeyAction::ShowNotifications => {
+                        self.show_notifications();
+                    }
+                    crate::views::which_key::WhichKeyAction::ToggleDensity => {
+                        self.overlay_focus = OverlayFocus::None;
+                        tokio::task::block_in_place(|| {
+                            let rt = tokio::runtime::Handle::current();
+                            rt.block_on(self.process_input("/density toggle"));
+                        });
+                    }
                     crate::views::which_key::WhichKeyAction::None => {
                         self.overlay_focus = OverlayFocus::None;
                     }
                 }
                 true
             }
+            OverlayFocus::Help => {
+                if self.help_state.handle_key_event(key) == crate::views::help::HelpAction::Close {
+                    self.overlay_focus = OverlayFocus::None;
+                }
+                true
+            }
+            OverlayFocus::Notifications => {
+                if self.notifications_state.handle_key_event(key)
+                    == crate::views::notifications::NotificationsAction::Close
+                {
+                    self.overlay_focus = OverlayFocus::None;
+                }
+                true
+            }
+            OverlayFocus::CopyMode => {
+                self.handle_copy_mode_keys(key);
+                true
+            }
             OverlayFocus::None => {
                 if self.handle_base_keys(key) {
                     return;
@@ -538,6 +1051,35 @@ impl App {
                 self.which_key_state.show();
                 true
             }
+            KeyCode::Char('?') if self.input.get_text().is_empty() => {
+                let sections = crate::views::help::keymap(&self.which_key_state);
+                self.help_state.show(sections);
+                self.overlay_focus = OverlayFocus::Help;
+                true
+            }
+            KeyCode::PageUp if self.base_focus == BaseFocus::Chat => {
+                self.chat_state.chat.page_up();
+                true
+            }
+            KeyCode::PageDown if self.base_focus == BaseFocus::Chat => {
+                self.chat_state.chat.page_down();
+                true
+            }
+            // Home/End keep moving the cursor while there's text in the input
+            KeyCode::Home
+                if self.base_focus == BaseFocus::Chat && self.input.get_text().is_empty() =>
+            {
+                self.chat_state.chat.scroll_to_top();
+                true
+            }
+            KeyCode::End
+                if self.base_focus == BaseFocus::Chat
+                    && (self.input.get_text().is_empty()
+                        || self.chat_state.chat.new_lines_below() > 0) =>
+            {
+                self.chat_state.chat.scroll_to_bottom();
+                true
+            }
             KeyCode::Tab => {
                 if self.agent == "Plan" {
                     self.agent = "Build".to_string();
@@ -547,8 +1089,13 @@ impl App {
                 true
             }
             KeyCode::Esc => {
-                if self.is_streaming {
-                    self.cancel_streaming();
+                if self.is_streaming() {
+                    // Clear the queue first so the running turn can finish
+                    if !self.message_queue.is_empty() {
+                        self.clear_message_queue("Cleared");
+                    } else {
+                        self.cancel_streaming();
+                    }
                     return true;
                 }
                 if self.overlay_focus == OverlayFocus::SuggestionsPopup {
@@ -562,7 +1109,7 @@ impl App {
             }
             KeyCode::Enter if key.modifiers == event::KeyModifiers::NONE => {
                 if self.overlay_focus == OverlayFocus::SuggestionsPopup {
-                    if self.is_streaming {
+                    if self.is_streaming() {
                         return true;
                     }
                     self.autocomplete_and_submit();
@@ -578,14 +1125,15 @@ impl App {
     fn handle_input_and_app_keys(&mut self, key: KeyEvent) {
         match key.code {
             KeyCode::Enter if key.modifiers == event::KeyModifiers::NONE => {
-                if self.is_streaming {
+                if self.is_streaming() {
+                    self.queue_input();
                     return;
                 }
                 let input_text = self.input.get_text();
-                if !input_text.is_empty() {
-                    use crate::command::parser::parse_input;
+                if !input_text.is_empty() || !self.input.attachments().is_empty() {
+                    use crate::command::parser::parse_input_with_prefix;
 
-                    match parse_input(&input_text) {
+                    match parse_input_with_prefix(&input_text, self.input.command_prefix()) {
                         crate::command::parser::InputType::Command(parsed) => {
                             // Don't save commands to prompt history
                             tokio::task::block_in_place(|| {
@@ -596,7 +1144,16 @@ impl App {
                         crate::command::parser::InputType::Message(msg) => {
                             // Only save messages (not commands) to prompt history
                             self.input.save_current_to_history();
-                            self.handle_message_input(msg);
+                            self.discard_draft();
+                            let msg = crate::session::paste::with_attachments(
+                                &msg,
+                                self.input.attachments(),
+                            );
+                            if !self.handle_message_input(msg) {
+                                // Keep the refused message so it can be trimmed
+                                return;
+                            }
+                            self.input.take_attachments();
                         }
                     }
 
@@ -611,6 +1168,74 @@ impl App {
         }
     }
 
+    /// Queues the input as the next message while a response is streaming.
+    /// Commands still have to wait for the turn to end.
+    fn queue_input(&mut self) {
+        use crate::command::parser::{parse_input_with_prefix, InputType};
+
+        let input_text = self.input.get_text();
+        let msg = match parse_input_with_prefix(&input_text, self.input.command_prefix()) {
+            InputType::Message(msg) => {
+                crate::session::paste::with_attachments(&msg, self.input.attachments())
+            }
+            InputType::Command(_) => {
+                push_toast(crate::ui::toast::new_toast(
+                    "Commands can run once the response finishes",
+                    ratatui_toolkit::ToastLevel::Info,
+                    Some(std::time::Duration::from_secs(3)),
+                ));
+                return;
+            }
+        };
+        if msg.is_empty() || !self.check_message_size(&msg) {
+            return;
+        }
+
+        if self.queue_message(msg) {
+            self.input.save_current_to_history();
+            self.discard_draft();
+            self.input.take_attachments();
+            self.input.clear();
+            clear_suggestions(&mut self.suggestions_popup_state);
+        }
+    }
+
+    fn queue_message(&mut self, msg: String) -> bool {
+        if self.message_queue.push(msg).is_ok() {
+            return true;
+        }
+        push_toast(crate::ui::toast::new_toast(
+            format!(
+                "Queue is full ({} messages)",
+                crate::session::queue::MAX_QUEUED_MESSAGES
+            ),
+            ratatui_toolkit::ToastLevel::Warning,
+            Some(std::time::Duration::from_secs(3)),
+        ));
+        false
+    }
+
+    fn clear_message_queue(&mut self, verb: &str) {
+        let count = self.message_queue.clear();
+        if count > 0 {
+            push_toast(crate::ui::toast::new_toast(
+                format!("{} {}", verb, crate::session::queue::queued_label(count)),
+                ratatui_toolkit::ToastLevel::Info,
+                Some(std::time::Duration::from_secs(3)),
+            ));
+        }
+    }
+
+    /// Starts the oldest queued message, if any. A message that no longer
+    /// fits the context window goes back to the input to be trimmed.
+    fn send_next_queued_message(&mut self) {
+        if let Some(msg) = self.message_queue.pop() {
+            if !self.handle_message_input(msg.clone()) {
+                self.input.set_text(&msg);
+            }
+        }
+    }
+
     fn update_suggestions(&mut self) {
         if self.input.should_show_suggestions() {
             let suggestions = self.input.get_autocomplete_suggestions();
@@ -634,35 +1259,26 @@ impl App {
             handle_connect_dialog_mouse_event(&mut self.connect_dialog_state, mouse);
         } else if self.overlay_focus == OverlayFocus::SessionsDialog {
             handle_sessions_dialog_mouse_event(&mut self.sessions_dialog_state, mouse);
+        } else if self.overlay_focus == OverlayFocus::Help {
+            match mouse.kind {
+                event::MouseEventKind::ScrollUp => self.help_state.scroll_by(-3),
+                event::MouseEventKind::ScrollDown => self.help_state.scroll_by(3),
+                _ => {}
+            }
+        } else if self.overlay_focus == OverlayFocus::Notifications {
+            match mouse.kind {
+                event::MouseEventKind::ScrollUp => self.notifications_state.scroll_by(-3),
+                event::MouseEventKind::ScrollDown => self.notifications_state.scroll_by(3),
+                _ => {}
+            }
         } else if self.overlay_focus == OverlayFocus::None {
             // Handle mouse events for chat scrolling when in chat mode
             if self.base_focus == BaseFocus::Chat {
-                let size = self.last_frame_size;
-                // We need to calculate the chat area similar to render_chat
-                let main_chunks = ratatui::layout::Layout::default()
-                    .direction(ratatui::layout::Direction::Vertical)
-                    .constraints(
-                        [
-                            ratatui::layout::Constraint::Min(0),
-                            ratatui::layout::Constraint::Length(1),
-                        ]
-                        .as_ref(),
-                    )
-                    .split(size);
-                let input_height = self.input.get_height() as u16;
-                let above_status_chunks = ratatui::layout::Layout::default()
-                    .direction(ratatui::layout::Direction::Vertical)
-                    .constraints(
-                        [
-                            ratatui::layout::Constraint::Min(0),
-                            ratatui::layout::Constraint::Length(input_height),
-                            ratatui::layout::Constraint::Length(1),
-                            ratatui::layout::Constraint::Length(1),
-                        ]
-                        .as_ref(),
-                    )
-                    .split(main_chunks[0]);
-                let chat_area = above_status_chunks[0];
+                let chat_area = crate::views::chat::chat_area(
+                    self.last_frame_size,
+                    self.input.get_height(),
+                    self.focus_mode,
+                );
 
                 if self.chat_state.chat.handle_mouse_event(mouse, chat_area) {
                     return;
@@ -680,7 +1296,7 @@ impl App {
         const MAX_PASTE_SIZE: usize = 20 * 1024 * 1024;
 
         if text.len() > MAX_PASTE_SIZE {
-            push_toast(ratatui_toolkit::Toast::new(
+            push_toast(crate::ui::toast::new_toast(
                 format!(
                     "Paste content too large ({}MB). Maximum is 20MB.",
                     text.len() / 1024 / 1024
@@ -690,10 +1306,29 @@ impl App {
             ));
             return;
         }
+        let text = crate::session::paste::normalize_newlines(&text);
 
         match (self.base_focus, self.overlay_focus) {
             (BaseFocus::Home, OverlayFocus::None) | (BaseFocus::Chat, OverlayFocus::None) => {
-                self.input.insert_str(&text);
+                let threshold = self
+                    .prefs_dao
+                    .as_ref()
+                    .and_then(|dao| dao.get_paste_attach_lines().ok())
+                    .unwrap_or(crate::session::paste::DEFAULT_ATTACH_LINES);
+                if crate::session::paste::should_attach(&text, threshold) {
+                    let attachment = crate::session::paste::PastedText::new(text);
+                    push_toast(crate::ui::toast::new_toast(
+                        format!(
+                            "Attached {}; Backspace on an empty input removes it",
+                            attachment.label()
+                        ),
+                        ratatui_toolkit::ToastLevel::Info,
+                        Some(std::time::Duration::from_secs(4)),
+                    ));
+                    self.input.attach(attachment);
+                } else {
+                    self.input.insert_str(&text);
+                }
             }
             (_, OverlayFocus::ModelsDialog) => {
                 self.models_dialog_state
@@ -754,11 +1389,16 @@ impl App {
     }
 
     fn autocomplete_and_submit(&mut self) {
-        if self.is_streaming {
+        if self.is_streaming() {
             return;
         }
         if let Some(selected) = get_selected_suggestion(&self.suggestions_popup_state) {
-            let command = format!("/{}", selected.name);
+            // Run as an internal command line, whatever the user's prefix
+            let command = format!(
+                "{}{}",
+                crate::command::parser::DEFAULT_PREFIX,
+                selected.name
+            );
 
             tokio::task::block_in_place(|| {
                 let rt = tokio::runtime::Handle::current();
@@ -770,11 +1410,14 @@ impl App {
         clear_suggestions(&mut self.suggestions_popup_state);
     }
 
+    /// Runs a command line built inside crabcode, which always starts with
+    /// `DEFAULT_PREFIX`, or sends it as a message.
     async fn process_input(&mut self, input: &str) {
         use crate::command::parser::parse_input;
 
         match parse_input(input) {
             InputType::Command(mut parsed) => {
+                self.command_registry.resolve_alias(&mut parsed);
                 parsed.prefs_dao = self.prefs_dao.as_ref();
                 parsed.active_model_id = Some(self.model.clone());
 
@@ -792,6 +1435,11 @@ impl App {
                             && parsed.name != "refreshmodels"
                         {
                             self.base_focus = BaseFocus::Chat;
+                        } else if parsed.name == "sessions"
+                            && self.session_manager.get_current_session_id().is_none()
+                        {
+                            // The open session was pruned; don't keep showing it.
+                            self.chat_state.chat.clear();
                         }
                         // Only add non-empty messages to the chat, and don't add exit message
                         if parsed.name != "exit" && !msg.is_empty() {
@@ -808,7 +1456,7 @@ impl App {
                     }
                     crate::command::registry::CommandResult::Error(msg) => {
                         if msg.starts_with("Unknown command:") {
-                            push_toast(ratatui_toolkit::Toast::new(
+                            push_toast(crate::ui::toast::new_toast(
                                 msg,
                                 ratatui_toolkit::ToastLevel::Error,
                                 Some(std::time::Duration::from_secs(3)),
@@ -879,10 +1527,38 @@ impl App {
                             self.overlay_focus = OverlayFocus::ModelsDialog;
                         }
                     }
+                    crate::command::registry::CommandResult::Confirm {
+                        title,
+                        message,
+                        command,
+                    } => {
+                        self.open_confirm(PendingConfirm::RunCommand(command), title, message);
+                    }
+                    crate::command::registry::CommandResult::ChangeDirectory(path) => {
+                        self.change_directory(&path);
+                    }
+                    crate::command::registry::CommandResult::DebugLastTurn => {
+                        self.dump_last_turn();
+                    }
+                    crate::command::registry::CommandResult::Reload => {
+                        self.reload();
+                    }
+                    crate::command::registry::CommandResult::ShowNotifications => {
+                        self.show_notifications();
+                    }
+                    crate::command::registry::CommandResult::PastedContent(action) => {
+                        self.apply_paste_action(action);
+                    }
+                    crate::command::registry::CommandResult::OpenSession { id, resend } => {
+                        self.open_session(&id, resend);
+                    }
+                    crate::command::registry::CommandResult::ScrollToMessage(index) => {
+                        self.scroll_to_message(index);
+                    }
                 }
             }
             InputType::Message(msg) => {
-                self.handle_message_input(msg);
+                let _ = self.handle_message_input(msg);
             }
         }
     }
@@ -891,6 +1567,7 @@ impl App {
         &mut self,
         mut parsed: crate::command::parser::ParsedCommand<'_>,
     ) {
+        self.command_registry.resolve_alias(&mut parsed);
         parsed.prefs_dao = self.prefs_dao.as_ref();
         parsed.active_model_id = Some(self.model.clone());
 
@@ -906,6 +1583,60 @@ impl App {
                     self.session_manager.clear_current_session();
                 } else if self.base_focus == BaseFocus::Home && parsed.name != "refreshmodels" {
                     self.base_focus = BaseFocus::Chat;
+                } else if parsed.name == "sessions"
+                    && self.session_manager.get_current_session_id().is_none()
+                {
+                    // The open session was pruned; don't keep showing it.
+                    self.chat_state.chat.clear();
+                }
+                if parsed.name == "scroll" {
+                    if let Some(prefs) = self
+                        .prefs_dao
+                        .as_ref()
+                        .and_then(|dao| dao.get_scroll_preferences().ok())
+                    {
+                        self.chat_state.chat.scroll_prefs = prefs;
+                    }
+                } else if parsed.name == "color" {
+                    if let Some(mode) = self
+                        .prefs_dao
+                        .as_ref()
+                        .and_then(|dao| dao.get_color_mode().ok())
+                    {
+                        // `parsed` still borrows the prefs DAO, so this can't
+                        // go through `set_color_mode`
+                        self.color_mode = mode;
+                        self.chat_state.chat.plain_markdown =
+                            mode == crate::persistence::ColorMode::Monochrome;
+                        let colors = self.get_current_theme_colors();
+                        self.session_rename_dialog_state.set_colors(colors);
+                    }
+                } else if parsed.name == "density" {
+                    if let Some(density) = self
+                        .prefs_dao
+                        .as_ref()
+                        .and_then(|dao| dao.get_density().ok())
+                    {
+                        self.chat_state.chat.density = density;
+                    }
+                } else if parsed.name == "input-history" {
+                    // Same as `apply_history_scope`, which `parsed` borrowing
+                    // the prefs DAO rules out
+                    let scope = self
+                        .prefs_dao
+                        .as_ref()
+                        .and_then(|dao| dao.get_history_scope().ok())
+                        .unwrap_or_default();
+                    self.input.set_history_scope(&self.cwd, scope);
+                } else if parsed.name == "markdown" {
+                    if let Some(kind) = self
+                        .prefs_dao
+                        .as_ref()
+                        .and_then(|dao| dao.get_markdown_renderer().ok())
+                    {
+                        self.chat_state.chat.markdown =
+                            crate::ui::markdown::renderer::renderer_for(kind);
+                    }
                 }
                 // Don't add exit message to chat
                 if parsed.name != "exit" && !msg.is_empty() {
@@ -915,13 +1646,16 @@ impl App {
                         .add_message_to_current_session(&assistant_message);
                     self.chat_state.chat.add_assistant_message(msg);
                 }
+                // Last, where `parsed` no longer borrows the prefs DAO
                 if parsed.name == "exit" {
                     self.quit();
+                } else if parsed.name == "command-prefix" {
+                    self.apply_command_prefix();
                 }
             }
             crate::command::registry::CommandResult::Error(msg) => {
                 if msg.starts_with("Unknown command:") {
-                    push_toast(ratatui_toolkit::Toast::new(
+                    push_toast(crate::ui::toast::new_toast(
                         msg,
                         ratatui_toolkit::ToastLevel::Error,
                         Some(std::time::Duration::from_secs(3)),
@@ -985,52 +1719,741 @@ impl App {
                     self.overlay_focus = OverlayFocus::ModelsDialog;
                 }
             }
+            crate::command::registry::CommandResult::Confirm {
+                title,
+                message,
+                command,
+            } => {
+                self.open_confirm(PendingConfirm::RunCommand(command), title, message);
+            }
+            crate::command::registry::CommandResult::ChangeDirectory(path) => {
+                self.change_directory(&path);
+            }
+            crate::command::registry::CommandResult::DebugLastTurn => {
+                self.dump_last_turn();
+            }
+            crate::command::registry::CommandResult::Reload => {
+                self.reload();
+            }
+            crate::command::registry::CommandResult::ShowNotifications => {
+                self.show_notifications();
+            }
+            crate::command::registry::CommandResult::PastedContent(action) => {
+                self.apply_paste_action(action);
+            }
+            crate::command::registry::CommandResult::OpenSession { id, resend } => {
+                self.open_session(&id, resend);
+            }
+            crate::command::registry::CommandResult::ScrollToMessage(index) => {
+                self.scroll_to_message(index);
+            }
         }
     }
 
-    fn generate_title_from_message(message: &str) -> String {
-        message
-            .chars()
-            .take(30)
-            .collect::<String>()
-            .trim_end()
-            .to_string()
+    /// Shows a session a command switched to, such as a new branch, and
+    /// sends `resend` in it once its history is loaded.
+    fn open_session(&mut self, id: &str, resend: Option<String>) {
+        self.load_session_into_chat(id);
+        self.base_focus = BaseFocus::Chat;
+        if let Some(prompt) = resend {
+            self.wait_for_session_load();
+            self.handle_message_input(prompt);
+        }
     }
 
-    fn refresh_sessions_dialog(&mut self) {
-        use chrono::{DateTime, Local, Timelike, Utc};
-
-        let mut sessions = self.session_manager.list_sessions();
-        sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    /// Lists recent toasts, including ones that already expired.
+    fn show_notifications(&mut self) {
+        self.notifications_state.show(crate::toast_history());
+        self.overlay_focus = OverlayFocus::Notifications;
+    }
 
-        let items: Vec<crate::ui::components::dialog::DialogItem> = sessions
-            .into_iter()
-            .map(|session| {
-                let date_group = {
-                    let datetime: DateTime<Local> = session.updated_at.into();
-                    let now: DateTime<Local> = Utc::now().into();
-                    let duration = now.signed_duration_since(datetime);
+    /// Starts selecting chat lines to copy. Mouse capture is off meanwhile,
+    /// so the terminal's own selection works too.
+    fn begin_copy_mode(&mut self) {
+        if !self.chat_state.chat.begin_copy_mode() {
+            return;
+        }
+        self.overlay_focus = OverlayFocus::CopyMode;
+        push_toast(crate::ui::toast::new_toast(
+            "Copy mode: ↑/↓ to select lines, Enter to copy, Esc to leave",
+            ratatui_toolkit::ToastLevel::Info,
+            Some(std::time::Duration::from_secs(4)),
+        ));
+    }
 
-                    if duration.num_days() == 0 {
-                        "Today".to_string()
-                    } else {
-                        datetime.format("%a %b %d %Y").to_string()
-                    }
-                };
+    fn handle_copy_mode_keys(&mut self, key: KeyEvent) {
+        let chat = &mut self.chat_state.chat;
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => chat.move_copy_cursor(-1),
+            KeyCode::Down | KeyCode::Char('j') => chat.move_copy_cursor(1),
+            KeyCode::PageUp => chat.move_copy_cursor_by_page(false),
+            KeyCode::PageDown => chat.move_copy_cursor_by_page(true),
+            KeyCode::Home | KeyCode::Char('g') => {
+                chat.move_copy_cursor(-(chat.content_height as isize))
+            }
+            KeyCode::End | KeyCode::Char('G') => {
+                chat.move_copy_cursor(chat.content_height as isize)
+            }
+            KeyCode::Enter | KeyCode::Char('y') => self.copy_selection(),
+            KeyCode::Esc | KeyCode::Char('q') => chat.end_copy_mode(),
+            _ => {}
+        }
+        if !self.chat_state.chat.is_copy_mode() {
+            self.overlay_focus = OverlayFocus::None;
+        }
+    }
 
-                let time = {
-                    let datetime: DateTime<Local> = session.updated_at.into();
-                    let hour = datetime.time().hour12();
-                    let am_pm = if hour.0 { "PM" } else { "AM" };
-                    format!("{}:{:02} {}", hour.1, datetime.time().minute(), am_pm)
+    fn copy_selection(&mut self) {
+        let colors = self.get_current_theme_colors();
+        let text = self.chat_state.chat.selected_text(&self.model, &colors);
+        self.chat_state.chat.end_copy_mode();
+        if text.is_empty() {
+            return;
+        }
+
+        let lines = text.lines().count();
+        let (message, level) = match crate::utils::clipboard::copy_to_clipboard(&text) {
+            Ok(()) => (
+                format!("Copied {} line{}", lines, if lines == 1 { "" } else { "s" }),
+                ratatui_toolkit::ToastLevel::Info,
+            ),
+            Err(e) => (
+                format!("Couldn't copy: {}", e),
+                ratatui_toolkit::ToastLevel::Error,
+            ),
+        };
+        push_toast(crate::ui::toast::new_toast(
+            message,
+            level,
+            Some(std::time::Duration::from_secs(3)),
+        ));
+    }
+
+    /// Whether state changed since the last call in a way input events
+    /// don't cover, so the screen needs drawing again.
+    pub fn take_needs_redraw(&mut self) -> bool {
+        std::mem::take(&mut self.needs_redraw)
+    }
+
+    /// Whether something is expected to change on its own soon: a response
+    /// streaming or a session loading.
+    pub fn is_animating(&self) -> bool {
+        self.is_streaming() || self.session_load.is_some()
+    }
+
+    /// Whether the terminal should report mouse events; off in copy mode
+    /// and when turned off, so text can be selected with the mouse.
+    pub fn wants_mouse_capture(&self) -> bool {
+        self.mouse_capture && self.overlay_focus != OverlayFocus::CopyMode
+    }
+
+    fn toggle_mouse_capture(&mut self) {
+        self.mouse_capture = !self.mouse_capture;
+        let message = if self.mouse_capture {
+            "Mouse capture on"
+        } else {
+            "Mouse capture off: select text with the mouse (Ctrl+X c to undo)"
+        };
+        push_toast(crate::ui::toast::new_toast(
+            message,
+            ratatui_toolkit::ToastLevel::Info,
+            Some(std::time::Duration::from_secs(3)),
+        ));
+    }
+
+    fn toggle_focus_mode(&mut self) {
+        self.focus_mode = !self.focus_mode;
+        let message = if self.focus_mode {
+            "Focus mode on (Ctrl+X f to leave)"
+        } else {
+            "Focus mode off"
+        };
+        push_toast(crate::ui::toast::new_toast(
+            message,
+            ratatui_toolkit::ToastLevel::Info,
+            Some(std::time::Duration::from_secs(2)),
+        ));
+    }
+
+    /// Bookmarks the message at the top of the chat, or removes its bookmark.
+    fn toggle_bookmark(&mut self) {
+        let Some(id) = self.session_manager.get_current_session_id().cloned() else {
+            return;
+        };
+        let Some(index) = self.chat_state.chat.message_at_top() else {
+            return;
+        };
+        let message = match self.session_manager.toggle_bookmark(&id, index) {
+            Ok(true) => format!("Bookmarked message {}", index + 1),
+            Ok(false) => format!("Removed the bookmark on message {}", index + 1),
+            // The reply at the top hasn't been saved yet
+            Err(e) => format!("Can't bookmark this message: {}", e),
+        };
+        self.chat_state.chat.bookmarks = self.session_manager.bookmarks(&id);
+        push_toast(crate::ui::toast::new_toast(
+            message,
+            ratatui_toolkit::ToastLevel::Info,
+            Some(std::time::Duration::from_secs(2)),
+        ));
+    }
+
+    fn jump_to_bookmark(&mut self, forward: bool) {
+        match self.chat_state.chat.adjacent_bookmark(forward) {
+            Some(index) => self.scroll_to_message(index),
+            None => push_toast(crate::ui::toast::new_toast(
+                if forward {
+                    "No bookmarks below"
+                } else {
+                    "No bookmarks above"
+                },
+                ratatui_toolkit::ToastLevel::Info,
+                Some(std::time::Duration::from_secs(2)),
+            )),
+        }
+    }
+
+    /// Scrolls the chat to a message of the current session, first paging
+    /// in the older messages down to it if it isn't loaded.
+    fn scroll_to_message(&mut self, index: usize) {
+        if self.chat_state.chat.scroll_to_message(index) {
+            return;
+        }
+        let Some(id) = self.session_manager.get_current_session_id().cloned() else {
+            return;
+        };
+        let older = self.chat_state.chat.older_messages_available;
+        if index >= older {
+            return;
+        }
+        let skip = self
+            .session_manager
+            .message_count(&id)
+            .saturating_sub(older);
+        let messages = self
+            .session_manager
+            .load_messages_page(&id, skip, older - index);
+        self.chat_state.chat.prepend_messages(messages, index);
+        self.chat_state.chat.scroll_to_message(index);
+    }
+
+    /// Where `/debug last` writes the last turn.
+    fn last_turn_path() -> std::path::PathBuf {
+        if cfg!(test) || std::env::var("CRABCODE_TEST_MODE").is_ok() {
+            std::env::temp_dir().join("crabcode_test_last_turn.txt")
+        } else {
+            crate::persistence::get_cache_dir().join("last_turn.txt")
+        }
+    }
+
+    /// Sends a follow-up asking the model to explain and fix the most recent
+    /// failed tool call. Queued like any message when a turn is running.
+    fn explain_last_tool_error(&mut self) -> bool {
+        let prompt = self.chat_state.chat.messages.iter().rev().find_map(|msg| {
+            if msg.role != crate::session::types::MessageRole::Tool {
+                return None;
+            }
+            let row = serde_json::from_str(&msg.content).ok()?;
+            crate::llm::tool_calls::explain_error_prompt(&row)
+        });
+        let Some(prompt) = prompt else {
+            push_toast(crate::ui::toast::new_toast(
+                "No failed tool call with output to explain",
+                ratatui_toolkit::ToastLevel::Info,
+                Some(std::time::Duration::from_secs(3)),
+            ));
+            return false;
+        };
+        self.handle_message_input(prompt)
+    }
+
+    /// Lists or drops the pastes attached to the input, for `/paste`.
+    fn apply_paste_action(&mut self, action: crate::command::registry::PasteAction) {
+        use crate::command::registry::PasteAction;
+
+        let message = match action {
+            PasteAction::List if self.input.attachments().is_empty() => {
+                "No pasted content attached".to_string()
+            }
+            PasteAction::List => self
+                .input
+                .attachments()
+                .iter()
+                .enumerate()
+                .map(|(i, a)| format!("{}. {}: {}", i + 1, a.label(), a.preview(40)))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            PasteAction::Remove(index) => {
+                let index =
+                    index.unwrap_or_else(|| self.input.attachments().len().saturating_sub(1));
+                match self.input.remove_attachment(index) {
+                    Some(removed) => format!("Removed {}", removed.label()),
+                    None => "No such pasted content".to_string(),
+                }
+            }
+        };
+        push_toast(crate::ui::toast::new_toast(
+            message,
+            ratatui_toolkit::ToastLevel::Info,
+            Some(std::time::Duration::from_secs(5)),
+        ));
+    }
+
+    /// Where `/tee` writes transcripts.
+    fn transcripts_dir() -> std::path::PathBuf {
+        if cfg!(test) || std::env::var("CRABCODE_TEST_MODE").is_ok() {
+            std::env::temp_dir().join("crabcode_test_transcripts")
+        } else {
+            crate::persistence::get_data_dir().join("transcripts")
+        }
+    }
+
+    /// Notifies the user of a finished turn if it took at least as long as
+    /// `/notify` asks for.
+    fn notify_turn_done(&self, elapsed: std::time::Duration) {
+        let after_secs = self
+            .prefs_dao
+            .as_ref()
+            .and_then(|dao| dao.get_notify_after_secs().ok())
+            .unwrap_or(0);
+        if crate::utils::notify::should_notify(elapsed, after_secs) {
+            crate::utils::notify::notify_turn_done(&format!(
+                "Reply ready after {}s",
+                elapsed.as_secs()
+            ));
+        }
+    }
+
+    /// Where the open session's draft is kept: stored sessions by database
+    /// id, since session ids change between runs. The home screen has one
+    /// of its own.
+    fn draft_key(&self) -> String {
+        match self.session_manager.get_current_session_id() {
+            Some(id) => match self.session_manager.history_location(id) {
+                Some((_, db_id)) => format!("session:{}", db_id),
+                None => format!("session:{}", id),
+            },
+            None => "home".to_string(),
+        }
+    }
+
+    /// Saves the input as the open session's draft once typing pauses, and
+    /// swaps in the other session's draft after switching.
+    pub fn autosave_draft(&mut self) {
+        let key = self.draft_key();
+        if self.draft_key.as_deref() != Some(key.as_str()) {
+            if let Some(previous) = self.draft_key.replace(key) {
+                let text = self.input.get_text();
+                if !text.trim().is_empty() {
+                    if let Some(change) = self.draft.flush(&text) {
+                        self.store_draft(&previous, change);
+                    }
+                }
+            }
+            self.restore_draft();
+            return;
+        }
+
+        let text = self.input.get_text();
+        if let Some(change) = self.draft.poll(&text, std::time::Instant::now()) {
+            self.store_draft(&key, change);
+        }
+    }
+
+    /// Saves the input as the open session's draft right away.
+    fn flush_draft(&mut self) {
+        let text = self.input.get_text();
+        if let Some(change) = self.draft.flush(&text) {
+            self.store_draft(&self.draft_key(), change);
+        }
+    }
+
+    fn store_draft(&self, key: &str, change: crate::session::draft::DraftChange) {
+        let Some(dao) = self.prefs_dao.as_ref() else {
+            return;
+        };
+        if !dao.get_draft_autosave().unwrap_or(true) {
+            return;
+        }
+        let result = match change {
+            crate::session::draft::DraftChange::Save(text) => dao.set_draft(key, &text),
+            crate::session::draft::DraftChange::Clear => dao.clear_draft(key),
+        };
+        if let Err(e) = result {
+            let _ = log(&format!("[DRAFT] failed to save draft {}: {:?}", key, e));
+        }
+    }
+
+    /// Puts the open session's saved draft in the input, or empties it.
+    /// Leaves the input alone while `/draft` is off.
+    fn restore_draft(&mut self) {
+        let Some(dao) = self.prefs_dao.as_ref() else {
+            return;
+        };
+        if !dao.get_draft_autosave().unwrap_or(true) {
+            self.draft.reset(&self.input.get_text());
+            return;
+        }
+        let draft = dao
+            .get_draft(&self.draft_key())
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        self.input.set_text(&draft);
+        self.draft.reset(&draft);
+        if !draft.is_empty() {
+            push_toast(crate::ui::toast::new_toast(
+                "Restored your unsent draft (Ctrl+C clears it)",
+                ratatui_toolkit::ToastLevel::Info,
+                Some(std::time::Duration::from_secs(4)),
+            ));
+        }
+    }
+
+    /// Forgets the draft of a message that's being sent.
+    fn discard_draft(&mut self) {
+        if let Some(dao) = self.prefs_dao.as_ref() {
+            let _ = dao.clear_draft(&self.draft_key());
+        }
+        self.draft.reset("");
+    }
+
+    /// Opens a transcript for the turn that's starting, if `/tee` is on.
+    fn open_tee(&mut self) {
+        self.tee = None;
+        let enabled = self
+            .prefs_dao
+            .as_ref()
+            .and_then(|dao| dao.get_tee_output().ok())
+            .unwrap_or(false);
+        if !enabled {
+            return;
+        }
+
+        let session = self
+            .session_manager
+            .get_current_session_id()
+            .cloned()
+            .unwrap_or_default();
+        let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+        match crate::llm::tee::StreamTee::open(&Self::transcripts_dir(), &session, &timestamp) {
+            Ok(tee) => self.tee = Some(tee),
+            Err(e) => push_toast(crate::ui::toast::new_toast(
+                format!("Tee disabled for this turn: {}", e),
+                ratatui_toolkit::ToastLevel::Error,
+                None,
+            )),
+        }
+    }
+
+    /// Copies a chunk to the transcript, closing it once the turn ends. A
+    /// failed write stops the tee for the rest of the turn.
+    fn tee_chunk(&mut self, chunk: &crate::llm::ChunkMessage) {
+        let Some(tee) = self.tee.as_mut() else {
+            return;
+        };
+        if let Err(e) = tee.write_chunk(chunk) {
+            push_toast(crate::ui::toast::new_toast(
+                format!("Tee stopped, failed to write {}: {}", tee.path.display(), e),
+                ratatui_toolkit::ToastLevel::Error,
+                None,
+            ));
+            self.tee = None;
+        } else if crate::llm::stream_state::is_terminal(chunk) {
+            self.tee = None;
+        }
+    }
+
+    /// Writes the last turn's request and raw chunks, secrets masked, to a
+    /// file; it's usually too long to read in a toast or the chat.
+    fn dump_last_turn(&mut self) -> Option<std::path::PathBuf> {
+        let Some(turn) = self.last_turn.as_ref() else {
+            push_toast(crate::ui::toast::new_toast(
+                "Nothing to show: no message has been sent yet",
+                ratatui_toolkit::ToastLevel::Info,
+                Some(std::time::Duration::from_secs(3)),
+            ));
+            return None;
+        };
+
+        let text = turn.render(&crate::persistence::auth::known_secrets());
+        let path = Self::last_turn_path();
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&path, text));
+        match written {
+            Ok(()) => {
+                push_toast(crate::ui::toast::new_toast(
+                    format!(
+                        "Last turn ({} chunks) written to {}",
+                        turn.chunk_count(),
+                        path.display()
+                    ),
+                    ratatui_toolkit::ToastLevel::Info,
+                    Some(std::time::Duration::from_secs(8)),
+                ));
+                Some(path)
+            }
+            Err(e) => {
+                push_toast(crate::ui::toast::new_toast(
+                    format!("Failed to write {}: {}", path.display(), e),
+                    ratatui_toolkit::ToastLevel::Error,
+                    None,
+                ));
+                None
+            }
+        }
+    }
+
+    /// Points tools and the system prompt at `path`, resolved against the
+    /// current working directory.
+    fn change_directory(&mut self, path: &str) {
+        match crate::command::handlers::resolve_working_dir(&self.cwd, path) {
+            Ok(cwd) => {
+                push_toast(crate::ui::toast::new_toast(
+                    format!("Working directory: {}", cwd),
+                    ratatui_toolkit::ToastLevel::Info,
+                    Some(std::time::Duration::from_secs(3)),
+                ));
+                self.cwd = cwd;
+                self.apply_history_scope();
+            }
+            Err(msg) => {
+                let error_msg = format!("Error: {}", msg);
+                let error_message = crate::session::types::Message::assistant(error_msg.clone());
+                let _ = self
+                    .session_manager
+                    .add_message_to_current_session(&error_message);
+                self.chat_state.chat.add_assistant_message(error_msg);
+            }
+        }
+    }
+
+    fn open_confirm(
+        &mut self,
+        pending: PendingConfirm,
+        title: impl Into<String>,
+        message: impl Into<String>,
+    ) {
+        let (confirm_label, cancel_label) = match pending {
+            PendingConfirm::DeleteSession(_) => ("Delete", "Cancel"),
+            PendingConfirm::RunCommand(_) => ("Run", "Cancel"),
+        };
+        self.confirm_dialog = ConfirmDialog::new().with_labels(confirm_label, cancel_label);
+        self.confirm_dialog.show(title, message);
+        self.pending_confirm = Some(pending);
+        self.overlay_focus = OverlayFocus::Confirm;
+    }
+
+    fn run_confirmed(&mut self, pending: PendingConfirm) {
+        match pending {
+            PendingConfirm::DeleteSession(id) => {
+                // Only the row ctrl+d was pressed on gets deleted, and only once.
+                let pending = get_pending_delete(&mut self.sessions_dialog_state);
+                if pending.as_deref() == Some(id.as_str())
+                    && self.session_manager.soft_delete_session(&id)
+                {
+                    push_toast(crate::ui::toast::new_toast(
+                        "Session deleted — Ctrl+Z to undo",
+                        ratatui_toolkit::ToastLevel::Info,
+                        Some(crate::session::manager::UNDO_DELETE_WINDOW),
+                    ));
+                }
+                self.refresh_sessions_dialog();
+                self.overlay_focus = OverlayFocus::SessionsDialog;
+            }
+            PendingConfirm::RunCommand(command) => {
+                self.overlay_focus = OverlayFocus::None;
+                tokio::task::block_in_place(|| {
+                    let rt = tokio::runtime::Handle::current();
+                    rt.block_on(self.process_input(&command));
+                });
+            }
+        }
+    }
+
+    fn cancel_confirm(&mut self, pending: PendingConfirm) {
+        match pending {
+            PendingConfirm::DeleteSession(_) => {
+                get_pending_delete(&mut self.sessions_dialog_state);
+                self.overlay_focus = OverlayFocus::SessionsDialog;
+            }
+            PendingConfirm::RunCommand(_) => {
+                self.overlay_focus = OverlayFocus::None;
+            }
+        }
+    }
+
+    fn undo_session_delete(&mut self) -> bool {
+        let Some(id) = self.session_manager.undo_delete() else {
+            return false;
+        };
+
+        if self.session_manager.get_current_session_id() == Some(&id) {
+            self.load_session_into_chat(&id);
+        }
+        if self.sessions_dialog_state.dialog.is_visible() {
+            self.refresh_sessions_dialog();
+        }
+        push_toast(crate::ui::toast::new_toast(
+            "Session restored",
+            ratatui_toolkit::ToastLevel::Success,
+            Some(std::time::Duration::from_secs(3)),
+        ));
+        true
+    }
+
+    /// Shows the newest page of a session in the chat; older messages are
+    /// loaded on demand by `load_older_messages`. Stored sessions are read
+    /// in the background while the chat shows "Loading session…".
+    fn load_session_into_chat(&mut self, id: &str) {
+        self.chat_state.chat.clear();
+        self.session_load = None;
+
+        if let Some((path, db_id)) = self.session_manager.history_location(id) {
+            self.session_load = Some(crate::session::loader::SessionLoad::spawn(
+                id.to_string(),
+                path,
+                db_id,
+                SESSION_PAGE_SIZE,
+            ));
+            self.chat_state.chat.loading = true;
+            return;
+        }
+        self.load_session_page_now(id);
+    }
+
+    fn load_session_page_now(&mut self, id: &str) {
+        let total = self.session_manager.message_count(id);
+        let messages = self
+            .session_manager
+            .load_messages_page(id, 0, SESSION_PAGE_SIZE);
+        self.show_session_page(crate::session::loader::SessionPage { messages, total });
+    }
+
+    fn show_session_page(&mut self, page: crate::session::loader::SessionPage) {
+        self.chat_state.chat.clear();
+        self.chat_state.chat.older_messages_available =
+            page.total.saturating_sub(page.messages.len());
+        for message in page.messages {
+            self.chat_state.chat.add_message(message);
+        }
+        if let Some(id) = self.session_manager.get_current_session_id() {
+            self.chat_state.chat.bookmarks = self.session_manager.bookmarks(id);
+        }
+    }
+
+    /// Shows the page of a background session load once it has been read.
+    pub fn finish_session_load(&mut self) {
+        let Some(result) = self.session_load.as_ref().and_then(|load| load.poll()) else {
+            return;
+        };
+        if let Some(load) = self.session_load.take() {
+            self.apply_session_load(&load.id, result);
+            self.needs_redraw = true;
+        }
+    }
+
+    /// Blocks until a pending session load is done, so a message sent right
+    /// after switching sessions lands after the loaded history.
+    fn wait_for_session_load(&mut self) {
+        if let Some(load) = self.session_load.take() {
+            let id = load.id.clone();
+            self.apply_session_load(&id, load.wait());
+        }
+    }
+
+    fn apply_session_load(
+        &mut self,
+        id: &str,
+        result: anyhow::Result<crate::session::loader::SessionPage>,
+    ) {
+        // Switched away (or started a new session) while it was loading
+        let current = self.session_manager.get_current_session_id();
+        if current.map(String::as_str) != Some(id) {
+            return;
+        }
+        match result {
+            Ok(page) => self.show_session_page(page),
+            Err(_) => self.load_session_page_now(id),
+        }
+    }
+
+    /// Prepends the next page of older messages once the user scrolls to the
+    /// top of the chat. Deferred while streaming since the stream tracks
+    /// message indices.
+    pub fn load_older_messages(&mut self) {
+        if self.is_streaming() || !self.chat_state.chat.take_load_more_request() {
+            return;
+        }
+        let Some(id) = self.session_manager.get_current_session_id().cloned() else {
+            return;
+        };
+
+        let older = self.chat_state.chat.older_messages_available;
+        let total = self.session_manager.message_count(&id);
+        let skip = total.saturating_sub(older);
+        let limit = SESSION_PAGE_SIZE.min(older);
+        let messages = self.session_manager.load_messages_page(&id, skip, limit);
+        let remaining = older.saturating_sub(messages.len());
+        self.chat_state.chat.prepend_messages(messages, remaining);
+        self.needs_redraw = true;
+    }
+
+    /// Chat messages plus any older ones not paged into the chat yet, so the
+    /// model always sees the whole session.
+    fn context_messages(&self) -> Vec<crate::session::types::Message> {
+        let older = self.chat_state.chat.older_messages_available;
+        let mut messages = match self.session_manager.get_current_session_id() {
+            Some(id) if older > 0 => {
+                let skip = self.session_manager.message_count(id).saturating_sub(older);
+                self.session_manager.load_messages_page(id, skip, older)
+            }
+            _ => Vec::new(),
+        };
+        messages.extend(self.chat_state.chat.messages.iter().cloned());
+        messages
+    }
+
+    fn generate_title_from_message(message: &str) -> String {
+        crate::utils::truncate_words(message.trim(), 30)
+    }
+
+    fn refresh_sessions_dialog(&mut self) {
+        use crate::command::handlers::{format_date_group, format_time};
+
+        let time_format = self
+            .prefs_dao
+            .as_ref()
+            .and_then(|dao| dao.get_time_format().ok())
+            .unwrap_or_default();
+        let mut sessions = self.session_manager.list_sessions();
+        sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+
+        let items: Vec<crate::ui::components::dialog::DialogItem> = sessions
+            .into_iter()
+            .map(|session| {
+                let group = if session.pinned {
+                    "Pinned".to_string()
+                } else {
+                    format_date_group(session.updated_at)
                 };
+                let time = format_time(session.updated_at, time_format);
+
+                let tags = crate::command::handlers::format_session_tags(&session.tags);
+                let tip = crate::command::handlers::format_session_tip(
+                    &tags,
+                    session.message_count,
+                    time,
+                    session.updated,
+                );
 
                 crate::ui::components::dialog::DialogItem {
                     id: session.id.clone(),
                     name: session.title.clone(),
-                    group: date_group,
-                    description: String::new(),
-                    tip: Some(time),
+                    group,
+                    description: tags,
+                    tip: Some(tip),
                     provider_id: String::new(),
                 }
             })
@@ -1225,16 +2648,202 @@ impl App {
         self.models_dialog_state.refresh_items(items);
     }
 
-    fn cleanup_streaming(&mut self) {
-        self.chunk_sender = None;
-        self.chunk_receiver = None;
-        self.streaming_cancel_token = None;
+    /// Makes sure the saved active model still exists and its provider has a
+    /// key, switching to a fallback otherwise. Skipped when models.dev can't
+    /// be reached, since then there's nothing to check against.
+    pub async fn repair_active_model(&mut self) {
+        let Ok(discovery) = crate::model::discovery::Discovery::new() else {
+            return;
+        };
+        let providers =
+            match tokio::time::timeout(MODEL_CHECK_TIMEOUT, discovery.fetch_providers()).await {
+                Ok(Ok(providers)) => providers,
+                _ => return,
+            };
+
+        let mut connected: std::collections::HashSet<String> = crate::persistence::AuthDAO::new()
+            .and_then(|dao| dao.load())
+            .map(|providers| providers.into_keys().collect())
+            .unwrap_or_default();
+        connected.extend(providers.iter().filter_map(|(id, provider)| {
+            crate::persistence::auth::resolve_key(None, &provider.env).map(|_| id.clone())
+        }));
+
+        self.apply_model_repair(&providers, &connected);
     }
 
-    fn cancel_streaming(&mut self) {
-        if let Some(token) = &self.streaming_cancel_token {
-            token.cancel();
+    fn apply_model_repair(
+        &mut self,
+        providers: &std::collections::HashMap<String, crate::model::discovery::Provider>,
+        connected: &std::collections::HashSet<String>,
+    ) -> bool {
+        let recent = self
+            .prefs_dao
+            .as_ref()
+            .and_then(|dao| dao.get_model_preferences().ok())
+            .map(|prefs| prefs.recent)
+            .unwrap_or_default();
+        let Some((provider_id, model_id)) = crate::model::fallback::repair_active_model(
+            providers,
+            connected,
+            &recent,
+            &self.provider_name,
+            &self.model,
+        ) else {
+            return false;
+        };
+
+        let old_model = std::mem::replace(&mut self.model, model_id.clone());
+        self.provider_name = provider_id.clone();
+        if let Some(ref dao) = self.prefs_dao {
+            if let Err(e) = dao.set_active_model(provider_id, model_id.clone()) {
+                eprintln!("Failed to save active model: {}", e);
+            }
         }
+
+        push_toast(crate::ui::toast::new_toast(
+            format!("{} is unavailable, switched to: {}", old_model, model_id),
+            ratatui_toolkit::ToastLevel::Warning,
+            Some(std::time::Duration::from_secs(5)),
+        ));
+        true
+    }
+
+    /// Switches to the model last used with `provider_id`. Returns false,
+    /// leaving the current model alone, when none was ever picked for it.
+    fn select_provider(&mut self, provider_id: &str) -> bool {
+        let Some(model_id) = self
+            .prefs_dao
+            .as_ref()
+            .and_then(|dao| dao.get_provider_model(provider_id).ok().flatten())
+        else {
+            return false;
+        };
+
+        self.model = model_id.clone();
+        self.provider_name = provider_id.to_string();
+        if let Some(ref dao) = self.prefs_dao {
+            if let Err(e) = dao.set_active_model(provider_id.to_string(), model_id.clone()) {
+                eprintln!("Failed to save active model: {}", e);
+            }
+        }
+
+        push_toast(crate::ui::toast::new_toast(
+            format!("Switched to: {}", model_id),
+            ratatui_toolkit::ToastLevel::Info,
+            None,
+        ));
+        true
+    }
+
+    /// Saves the assistant and tool messages of the turn that just ended.
+    /// `keep_empty` is false on shutdown, so the placeholder of a reply that
+    /// never got any text isn't saved.
+    fn persist_turn(
+        &mut self,
+        stream: Option<&crate::llm::stream_state::ActiveStream>,
+        keep_empty: bool,
+    ) {
+        let start = self.streaming_chat_len_before_assistant;
+        for msg in self.chat_state.chat.messages.iter_mut().skip(start) {
+            match msg.role {
+                crate::session::types::MessageRole::Assistant
+                    if keep_empty || !msg.content.is_empty() =>
+                {
+                    if !msg.is_complete {
+                        msg.mark_complete();
+                    }
+                    msg.model = stream.map(|s| s.model.clone());
+                    msg.provider = stream.map(|s| s.provider.clone());
+                    let _ = self.session_manager.add_message_to_current_session(msg);
+                }
+                crate::session::types::MessageRole::Tool => {
+                    let _ = self.session_manager.add_message_to_current_session(msg);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Stops the running turn, keeps the reply it got so far and flushes
+    /// history. Called once the event loop exits; does nothing extra when
+    /// no turn is running.
+    pub async fn shutdown(&mut self) {
+        self.flush_draft();
+        self.message_queue.clear();
+
+        if self.streaming.cancel() {
+            let deadline = std::time::Instant::now() + SHUTDOWN_DRAIN_TIMEOUT;
+            while std::time::Instant::now() < deadline {
+                let chunks = self.streaming.drain();
+                if chunks.iter().any(crate::llm::stream_state::is_terminal) {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+
+            let stream = self.streaming.finish();
+            self.record_unfinished_tool_calls("cancelled");
+            self.persist_turn(stream.as_ref(), false);
+        }
+        self.tee = None;
+
+        if let Err(e) = self.session_manager.flush() {
+            let _ = log(&format!("[SHUTDOWN] failed to flush history: {:?}", e));
+        }
+    }
+
+    pub fn set_chat_stream(
+        &mut self,
+        chat_stream: std::sync::Arc<dyn crate::llm::chat_stream::ChatStream>,
+    ) {
+        self.chat_stream = chat_stream;
+    }
+
+    /// Prints replies to stdout as plain text from now on.
+    pub fn enable_plain_output(&mut self) {
+        self.plain_output = Some(crate::llm::plain::PlainOutput::new(
+            std::io::stdout(),
+            std::io::stderr(),
+        ));
+    }
+
+    /// Sends `prompt` as if it was typed in; `false` when it's refused for
+    /// being too large.
+    pub fn submit_prompt(&mut self, prompt: String) -> bool {
+        self.handle_message_input(prompt)
+    }
+
+    /// Why the last plain-output turn failed, if it did.
+    pub fn plain_output_failure(&self) -> Option<String> {
+        self.plain_output.as_ref()?.failure.clone()
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        self.streaming.is_active()
+    }
+
+    fn cancel_streaming(&mut self) {
+        self.streaming.cancel();
+    }
+
+    /// Whether Ctrl+C twice stops a running response instead of quitting.
+    fn quit_guard(&self) -> bool {
+        self.prefs_dao
+            .as_ref()
+            .and_then(|dao| dao.get_quit_guard().ok())
+            .unwrap_or(true)
+    }
+
+    /// Cancels the running turn like Esc, but saves the reply it got so far
+    /// instead of dropping it.
+    fn stop_streaming_keeping_reply(&mut self) {
+        self.streaming.cancel_keeping_reply();
+        push_toast(crate::ui::toast::new_toast(
+            "Response stopped. Ctrl+C again to quit",
+            ratatui_toolkit::ToastLevel::Info,
+            Some(std::time::Duration::from_secs(2)),
+        ));
     }
 
     pub fn update_animations(&mut self) {
@@ -1244,17 +2853,45 @@ impl App {
         if self.last_animation_update.elapsed() >= ANIMATION_INTERVAL {
             self.chat_state.wave_spinner.update();
             self.last_animation_update = std::time::Instant::now();
+            // The spinner and thinking indicator only show while streaming
+            self.needs_redraw |= self.is_streaming();
         }
+
+        if self.last_thinking_tick.elapsed() >= THINKING_TICK_INTERVAL {
+            self.chat_state.chat.tick_thinking_indicator();
+            self.last_thinking_tick = std::time::Instant::now();
+            self.needs_redraw |= self.is_streaming();
+        }
+
+        self.rotate_placeholder();
     }
 
     pub fn process_streaming_chunks(&mut self) {
-        let mut chunks = Vec::new();
-
-        if let Some(receiver) = &mut self.chunk_receiver {
-            while let Ok(chunk) = receiver.try_recv() {
-                chunks.push(chunk);
+        let chunks = self.streaming.drain();
+        if !chunks.is_empty() {
+            self.needs_redraw = true;
+        }
+        if let Some(turn) = self.last_turn.as_mut() {
+            for chunk in &chunks {
+                turn.record(chunk);
+            }
+        }
+        for chunk in &chunks {
+            self.tee_chunk(chunk);
+        }
+        if let Some(plain) = self.plain_output.as_mut() {
+            for chunk in &chunks {
+                // Stdout closed by the reader (e.g. `| head`); nothing left to show
+                if plain.write_chunk(chunk).is_err() {
+                    self.streaming.cancel();
+                    break;
+                }
             }
         }
+        let received = chunks.len();
+        let chunks = crate::llm::coalesce_chunks(chunks);
+        self.streaming_chunk_stats.0 += received;
+        self.streaming_chunk_stats.1 += chunks.len();
 
         for chunk in chunks {
             match chunk {
@@ -1267,13 +2904,19 @@ impl App {
                         .append_reasoning_to_last_assistant(&reasoning);
                 }
                 crate::llm::ChunkMessage::Warning(msg) => {
-                    push_toast(ratatui_toolkit::Toast::new(
+                    push_toast(crate::ui::toast::new_toast(
                         msg,
                         ratatui_toolkit::ToastLevel::Warning,
                         None,
                     ));
                 }
                 crate::llm::ChunkMessage::End => {
+                    let (received, applied) = std::mem::take(&mut self.streaming_chunk_stats);
+                    let _ = log(&format!(
+                        "[STREAM] {} chunks received, {} applied after coalescing",
+                        received, applied
+                    ));
+
                     // Capture end timestamp for TTFT/TPS/latency calculations.
                     self.chat_state.chat.mark_streaming_end();
 
@@ -1281,57 +2924,59 @@ impl App {
                     self.chat_state.chat.finalize_streaming_metrics();
 
                     // Persist all new assistant/tool messages for this streaming turn.
-                    let start = self.streaming_chat_len_before_assistant;
-                    for msg in self.chat_state.chat.messages.iter_mut().skip(start) {
-                        match msg.role {
-                            crate::session::types::MessageRole::Assistant => {
-                                if !msg.is_complete {
-                                    msg.mark_complete();
-                                }
-                                msg.model = self.streaming_model.clone();
-                                msg.provider = self.streaming_provider.clone();
-                                let _ = self.session_manager.add_message_to_current_session(msg);
-                            }
-                            crate::session::types::MessageRole::Tool => {
-                                let _ = self.session_manager.add_message_to_current_session(msg);
-                            }
-                            _ => {}
-                        }
+                    let stream = self.streaming.finish();
+                    self.persist_turn(stream.as_ref(), true);
+                    if let Some(stream) = stream.as_ref() {
+                        self.notify_turn_done(stream.started.elapsed());
                     }
-                    self.is_streaming = false;
-                    self.streaming_model = None;
-                    self.streaming_provider = None;
-                    self.cleanup_streaming();
+                    self.send_next_queued_message();
                 }
                 crate::llm::ChunkMessage::Failed(error) => {
-                    self.is_streaming = false;
+                    self.streaming.finish();
                     self.chat_state.chat.mark_streaming_end();
                     self.chat_state.chat.finalize_streaming_metrics();
-                    push_toast(ratatui_toolkit::Toast::new(
-                        format!("LLM error: {}", error),
+                    let _ = crate::logging::log(&format!("[LLM] request failed: {}", error));
+                    push_toast(crate::ui::toast::new_toast(
+                        crate::llm::provider_error::describe(&error, self.input.command_prefix()),
                         ratatui_toolkit::ToastLevel::Error,
                         None,
                     ));
+                    self.record_unfinished_tool_calls("failed");
+                    self.clear_message_queue("Dropped");
                     self.chat_state
                         .chat
                         .messages
                         .truncate(self.streaming_chat_len_before_assistant);
-                    self.cleanup_streaming();
                 }
                 crate::llm::ChunkMessage::Cancelled => {
-                    self.is_streaming = false;
+                    let stream = self.streaming.finish();
                     self.chat_state.chat.mark_streaming_end();
                     self.chat_state.chat.finalize_streaming_metrics();
-                    push_toast(ratatui_toolkit::Toast::new(
-                        "Streaming cancelled",
-                        ratatui_toolkit::ToastLevel::Info,
-                        None,
-                    ));
-                    self.chat_state
-                        .chat
-                        .messages
-                        .truncate(self.streaming_chat_len_before_assistant);
-                    self.cleanup_streaming();
+                    self.record_unfinished_tool_calls("cancelled");
+                    self.clear_message_queue("Dropped");
+                    if stream.as_ref().is_some_and(|s| s.keep_reply) {
+                        self.persist_turn(stream.as_ref(), false);
+                        // The placeholder of a reply that got no text
+                        let start = self.streaming_chat_len_before_assistant;
+                        let turn: Vec<_> = self.chat_state.chat.messages.drain(start..).collect();
+                        self.chat_state
+                            .chat
+                            .messages
+                            .extend(turn.into_iter().filter(|m| {
+                                m.role != crate::session::types::MessageRole::Assistant
+                                    || !m.content.is_empty()
+                            }));
+                    } else {
+                        push_toast(crate::ui::toast::new_toast(
+                            "Streaming cancelled",
+                            ratatui_toolkit::ToastLevel::Info,
+                            None,
+                        ));
+                        self.chat_state
+                            .chat
+                            .messages
+                            .truncate(self.streaming_chat_len_before_assistant);
+                    }
                 }
                 crate::llm::ChunkMessage::Metrics { .. } => {
                     // Metrics are now calculated locally from streaming data
@@ -1355,16 +3000,7 @@ impl App {
                     }
 
                     for call in tool_calls {
-                        let args_value: serde_json::Value = serde_json::from_str(&call.function.arguments)
-                            .unwrap_or_else(|_| serde_json::Value::String(call.function.arguments.clone()));
-
-                        let content = serde_json::json!({
-                            "id": call.id,
-                            "name": call.function.name,
-                            "status": "running",
-                            "args": args_value,
-                        })
-                        .to_string();
+                        let content = call.running_row().to_string();
 
                         self.chat_state
                             .chat
@@ -1372,9 +3008,23 @@ impl App {
 
                         let idx = self.chat_state.chat.messages.len().saturating_sub(1);
                         self.tool_call_message_indices.insert(call.id.clone(), idx);
+                        self.tool_call_started
+                            .insert(call.id.clone(), std::time::Instant::now());
                         self.tool_call_order.push(call.id);
                     }
                 }
+                crate::llm::ChunkMessage::ToolOutput { tool_call_id, text } => {
+                    let row = self
+                        .tool_call_message_indices
+                        .get(&tool_call_id)
+                        .and_then(|&idx| self.chat_state.chat.messages.get_mut(idx));
+                    if let Some(msg) = row {
+                        if let Ok(mut v) = serde_json::from_str::<serde_json::Value>(&msg.content) {
+                            crate::llm::ToolCall::append_live_output(&mut v, &text);
+                            msg.content = v.to_string();
+                        }
+                    }
+                }
                 crate::llm::ChunkMessage::ToolResult(result) => {
                     if let Some(idx) = self.tool_call_message_indices.get(&result.tool_call_id).copied() {
                         if let Some(msg) = self.chat_state.chat.messages.get_mut(idx) {
@@ -1382,6 +3032,11 @@ impl App {
                                 .unwrap_or_else(|_| serde_json::json!({}));
                             v["id"] = serde_json::Value::String(result.tool_call_id.clone());
                             v["name"] = serde_json::Value::String(result.name.clone());
+                            if let Some(started) =
+                                self.tool_call_started.remove(&result.tool_call_id)
+                            {
+                                v["duration_ms"] = (started.elapsed().as_millis() as u64).into();
+                            }
 
                             // Merge structured payloads from the AISDK bridge if present.
                             if let Ok(payload) = serde_json::from_str::<serde_json::Value>(&result.content) {
@@ -1423,7 +3078,14 @@ impl App {
                                 v["output_preview"] = serde_json::Value::String(result.content.clone());
                             }
 
+                            crate::llm::ToolCall::clear_live_output(&mut v);
                             msg.content = v.to_string();
+                            let record = crate::persistence::conversions::tool_call_from_message(
+                                &msg.content,
+                            );
+                            if let Some(record) = record {
+                                self.session_manager.record_tool_call(&record);
+                            }
                         }
                     } else {
                         let content = serde_json::json!({
@@ -1433,6 +3095,11 @@ impl App {
                             "output_preview": result.content,
                         })
                         .to_string();
+                        if let Some(record) =
+                            crate::persistence::conversions::tool_call_from_message(&content)
+                        {
+                            self.session_manager.record_tool_call(&record);
+                        }
                         self.chat_state
                             .chat
                             .add_message(crate::session::types::Message::tool(content));
@@ -1442,32 +3109,43 @@ impl App {
         }
     }
 
+    /// Logs tool calls that never got a result, since the tool may already
+    /// have run even though the turn is being rolled back.
+    fn record_unfinished_tool_calls(&mut self, status: &str) {
+        for (call_id, started) in self.tool_call_started.drain() {
+            let Some(msg) = self
+                .tool_call_message_indices
+                .get(&call_id)
+                .and_then(|&idx| self.chat_state.chat.messages.get(idx))
+            else {
+                continue;
+            };
+            let record = crate::persistence::conversions::tool_call_from_message(&msg.content);
+            if let Some(mut record) = record {
+                record.status = status.to_string();
+                record.duration_ms = Some(started.elapsed().as_millis() as i64);
+                self.session_manager.record_tool_call(&record);
+            }
+        }
+    }
+
     fn start_llm_streaming(
         &mut self,
         _user_message: &str,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        use tokio::sync::mpsc;
-
-        let (sender, receiver) = mpsc::unbounded_channel();
-        let sender_clone = sender.clone();
-        self.chunk_sender = Some(sender);
-        self.chunk_receiver = Some(receiver);
-
-        let cancel_token = tokio_util::sync::CancellationToken::new();
-        self.streaming_cancel_token = Some(cancel_token.clone());
-
-        self.is_streaming = true;
+        // Capture the current model and provider at the start of streaming
+        // so they don't change if the user switches models during streaming
+        let (sender_clone, cancel_token) = self
+            .streaming
+            .start(self.model.clone(), self.provider_name.clone());
 
         // Track the message boundary for this streaming turn so we can cleanly
         // roll back assistant/tool messages on failure or cancellation.
         self.streaming_chat_len_before_assistant = self.chat_state.chat.messages.len();
         self.tool_call_message_indices.clear();
         self.tool_call_order.clear();
-
-        // Capture the current model and provider at the start of streaming
-        // so they don't change if the user switches models during streaming
-        self.streaming_model = Some(self.model.clone());
-        self.streaming_provider = Some(self.provider_name.clone());
+        self.tool_call_started.clear();
+        self.streaming_chunk_stats = (0, 0);
 
         self.chat_state.chat.add_assistant_message("");
         if let Some(last_msg) = self.chat_state.chat.messages.last_mut() {
@@ -1483,7 +3161,7 @@ impl App {
         let is_git_repo = crate::utils::git::is_git_repo(&cwd).unwrap_or(false);
         
         // Build messages with system prompt
-        let mut messages = self.chat_state.chat.messages.clone();
+        let mut messages = self.context_messages();
         
         // Check if we already have a system message
         let has_system = messages.iter().any(|m| {
@@ -1491,13 +3169,19 @@ impl App {
         });
         
         if !has_system {
+            let tone = self
+                .prefs_dao
+                .as_ref()
+                .and_then(|dao| dao.get_tone().ok())
+                .unwrap_or_default();
             // Create system prompt with tools
             let composer = crate::prompt::SystemPromptComposer::new(
                 &model,
                 &cwd,
                 is_git_repo,
                 std::env::consts::OS,
-            );
+            )
+            .with_tone(tone);
             
             let system_prompt = tokio::task::block_in_place(|| {
                 tokio::runtime::Handle::current().block_on(async {
@@ -1508,22 +3192,31 @@ impl App {
             messages.insert(0, system_msg);
         }
 
+        self.last_turn = Some(crate::llm::debug_log::TurnLog::new(
+            &provider_name,
+            &model,
+            &messages,
+        ));
+        self.open_tee();
+        let request = crate::llm::chat_stream::StreamRequest {
+            provider_name,
+            model,
+            messages,
+            cwd,
+            sampling: self.session_manager.current_sampling(),
+            agent: self.agent.clone(),
+        };
+        let chat_stream = self.chat_stream.clone();
         tokio::spawn(async move {
             let result = tokio::time::timeout(
                 std::time::Duration::from_secs(300),
-                stream_llm_with_cancellation(
-                    cancel_token,
-                    provider_name,
-                    model,
-                    messages,
-                    sender_clone.clone(),
-                ),
+                chat_stream.stream(request, cancel_token, sender_clone.clone()),
             )
             .await;
 
             let _ = match result {
                 Ok(Ok(())) => sender_clone.send(crate::llm::ChunkMessage::End),
-                Ok(Err(e)) => sender_clone.send(crate::llm::ChunkMessage::Failed(e.to_string())),
+                Ok(Err(e)) => sender_clone.send(crate::llm::ChunkMessage::Failed(e)),
                 Err(_) => sender_clone.send(crate::llm::ChunkMessage::Failed(
                     "Timeout: No response within 5 minutes".to_string(),
                 )),
@@ -1533,7 +3226,147 @@ impl App {
         Ok(())
     }
 
-    fn handle_message_input(&mut self, msg: String) {
+    /// Looks up the active model's context window from the models.dev cache,
+    /// remembering the result per provider/model.
+    fn model_context_limit(&mut self) -> Option<u32> {
+        let key = (self.provider_name.clone(), self.model.clone());
+        if let Some((cached_key, limit)) = &self.context_limit_cache {
+            if *cached_key == key {
+                return *limit;
+            }
+        }
+
+        // Only cached models.dev data is used, so sending never waits on the
+        // network; the limit is unknown until a background fetch fills it
+        let Some(providers) = crate::model::discovery::Discovery::new()
+            .ok()
+            .and_then(|discovery| discovery.cached_providers())
+        else {
+            self.fetch_providers_in_background();
+            return None;
+        };
+        let limit = providers
+            .get(&key.0)
+            .and_then(|provider| provider.models.get(&key.1)?.limit.as_ref())
+            .map(|l| l.context);
+        self.context_limit_cache = Some((key, limit));
+        limit
+    }
+
+    fn fetch_providers_in_background(&mut self) {
+        if self
+            .providers_fetch
+            .as_ref()
+            .is_some_and(|fetch| !fetch.is_finished())
+        {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        self.providers_fetch = Some(runtime.spawn(async {
+            if let Ok(discovery) = crate::model::discovery::Discovery::new() {
+                let _ = discovery.fetch_providers().await;
+            }
+        }));
+    }
+
+    /// Estimated tokens of the messages `context_messages` reads from the
+    /// history rather than the chat, counted once per session and page.
+    fn older_messages_tokens(&mut self) -> usize {
+        use crate::llm::limits::estimate_tokens;
+
+        let older = self.chat_state.chat.older_messages_available;
+        let Some(id) = self.session_manager.get_current_session_id().cloned() else {
+            return 0;
+        };
+        if older == 0 {
+            return 0;
+        }
+        let key = (id, older);
+        if let Some((cached_key, tokens)) = &self.older_tokens_cache {
+            if *cached_key == key {
+                return *tokens;
+            }
+        }
+
+        let skip = self
+            .session_manager
+            .message_count(&key.0)
+            .saturating_sub(older);
+        let tokens = self
+            .session_manager
+            .load_messages_page(&key.0, skip, older)
+            .iter()
+            .map(|m| estimate_tokens(&m.content))
+            .sum();
+        self.older_tokens_cache = Some((key, tokens));
+        tokens
+    }
+
+    /// Returns `false` when the message is refused for exceeding the hard
+    /// cap; warns (but still sends) when the whole request looks too large.
+    fn check_message_size(&mut self, msg: &str) -> bool {
+        use crate::llm::limits::{check_request_size, estimate_tokens, hard_cap, RequestSize};
+
+        let context_limit = self.model_context_limit();
+        let message_tokens = estimate_tokens(msg);
+        let request_tokens = message_tokens
+            + self.older_messages_tokens()
+            + self
+                .chat_state
+                .chat
+                .messages
+                .iter()
+                .map(|m| estimate_tokens(&m.content))
+                .sum::<usize>();
+
+        match check_request_size(
+            message_tokens,
+            request_tokens,
+            context_limit,
+            hard_cap(context_limit),
+        ) {
+            RequestSize::Ok => true,
+            RequestSize::OverContext { estimated, limit } => {
+                push_toast(crate::ui::toast::new_toast(
+                    format!(
+                        "Request is ~{} tokens, over {}'s {} token context. It may be rejected; try /new.",
+                        estimated, self.model, limit
+                    ),
+                    ratatui_toolkit::ToastLevel::Warning,
+                    Some(std::time::Duration::from_secs(5)),
+                ));
+                true
+            }
+            RequestSize::OverHardCap { estimated, cap } => {
+                push_toast(crate::ui::toast::new_toast(
+                    format!(
+                        "Message not sent: ~{} tokens exceeds the {} token limit (set {} to override)",
+                        estimated,
+                        cap,
+                        crate::llm::limits::MAX_INPUT_TOKENS_ENV
+                    ),
+                    ratatui_toolkit::ToastLevel::Error,
+                    Some(std::time::Duration::from_secs(5)),
+                ));
+                false
+            }
+        }
+    }
+
+    fn handle_message_input(&mut self, msg: String) -> bool {
+        if !msg.is_empty() && !self.check_message_size(&msg) {
+            return false;
+        }
+        self.wait_for_session_load();
+
+        // Only one turn streams at a time; starting another would replace
+        // the channel under the running one
+        if !msg.is_empty() && self.is_streaming() {
+            return self.queue_message(msg);
+        }
+
         if !msg.is_empty() && self.base_focus == BaseFocus::Home {
             if self.session_manager.get_current_session_id().is_none() {
                 let session_title = Self::generate_title_from_message(&msg);
@@ -1552,7 +3385,7 @@ impl App {
             self.base_focus = BaseFocus::Chat;
 
             if let Err(e) = self.start_llm_streaming(&msg) {
-                push_toast(ratatui_toolkit::Toast::new(
+                push_toast(crate::ui::toast::new_toast(
                     format!("LLM error: {}", e),
                     ratatui_toolkit::ToastLevel::Error,
                     None,
@@ -1571,13 +3404,14 @@ impl App {
                 .add_user_message_with_agent_mode(&msg, self.agent.clone());
 
             if let Err(e) = self.start_llm_streaming(&msg) {
-                push_toast(ratatui_toolkit::Toast::new(
+                push_toast(crate::ui::toast::new_toast(
                     format!("LLM error: {}", e),
                     ratatui_toolkit::ToastLevel::Error,
                     None,
                 ));
             }
         }
+        true
     }
 
     pub fn render(&mut self, f: &mut ratatui::Frame) {
@@ -1592,11 +3426,13 @@ impl App {
                     &mut self.input,
                     self.version.clone(),
                     self.cwd.clone(),
-                    git::get_current_branch(),
+                    git::get_current_branch(&self.cwd),
                     self.agent.clone(),
                     self.model.clone(),
                     self.provider_name.clone(),
                     &colors,
+                    self.focus_mode,
+                    self.mouse_capture,
                 );
 
                 if is_suggestions_visible(&self.suggestions_popup_state)
@@ -1633,12 +3469,15 @@ impl App {
                     &mut self.input,
                     self.version.clone(),
                     self.cwd.clone(),
-                    git::get_current_branch(),
+                    git::get_current_branch(&self.cwd),
                     self.agent.clone(),
                     self.model.clone(),
                     self.provider_name.clone(),
                     &colors,
-                    self.is_streaming,
+                    self.streaming.is_active(),
+                    self.message_queue.len(),
+                    self.focus_mode,
+                    self.mouse_capture,
                 );
 
                 if is_suggestions_visible(&self.suggestions_popup_state)
@@ -1698,11 +3537,30 @@ impl App {
             render_session_rename_dialog(f, &mut self.session_rename_dialog_state, size, colors);
         }
 
+        if self.overlay_focus == OverlayFocus::Confirm {
+            if matches!(self.pending_confirm, Some(PendingConfirm::DeleteSession(_))) {
+                render_sessions_dialog(f, &mut self.sessions_dialog_state, size, colors);
+            }
+            self.confirm_dialog.render(f, size, &colors);
+        }
+
         if self.overlay_focus == OverlayFocus::WhichKey {
             crate::views::which_key::render_which_key(f, &self.which_key_state, &colors);
         }
 
-        render_toasts(f, &get_toast_manager().lock().unwrap());
+        if self.overlay_focus == OverlayFocus::Help {
+            crate::views::help::render_help(f, &mut self.help_state, &colors);
+        }
+
+        if self.overlay_focus == OverlayFocus::Notifications {
+            crate::views::notifications::render_notifications(
+                f,
+                &mut self.notifications_state,
+                &colors,
+            );
+        }
+
+        crate::ui::toast::render_toasts(f, &get_toast_manager(), &colors);
     }
 }
 
@@ -1711,3 +3569,665 @@ impl Default for App {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::chat_stream::{MockChatStream, MockOutcome};
+    use crate::session::types::MessageRole;
+
+    fn app_with_stream(stream: MockChatStream) -> App {
+        let mut app = App::new();
+        app.set_chat_stream(std::sync::Arc::new(stream));
+        app.base_focus = BaseFocus::Chat;
+        // Skip the models.dev lookup when sizing queued messages
+        app.context_limit_cache = Some(((app.provider_name.clone(), app.model.clone()), None));
+        app
+    }
+
+    async fn run_until_idle(app: &mut App) {
+        for _ in 0..400 {
+            app.process_streaming_chunks();
+            if !app.is_streaming() {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        panic!("stream never finished");
+    }
+
+    fn assistant_replies(app: &App) -> Vec<String> {
+        app.chat_state
+            .chat
+            .messages
+            .iter()
+            .filter(|m| m.role == MessageRole::Assistant)
+            .map(|m| m.content.clone())
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_stream_text_then_end() {
+        let mut app = app_with_stream(MockChatStream::new(
+            &["Hello", ", world"],
+            MockOutcome::Complete,
+        ));
+
+        app.start_llm_streaming("hi").unwrap();
+        assert!(app.is_streaming());
+        run_until_idle(&mut app).await;
+
+        let last = app.chat_state.chat.messages.last().unwrap();
+        assert_eq!(last.content, "Hello, world");
+        assert!(last.is_complete);
+        assert_eq!(last.model.as_deref(), Some(app.model.as_str()));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_failed_stream_rolls_back_reply() {
+        let mut app = app_with_stream(MockChatStream::new(
+            &["partial"],
+            MockOutcome::Fail("rate limited".to_string()),
+        ));
+        let before = app.chat_state.chat.messages.len();
+
+        app.start_llm_streaming("hi").unwrap();
+        run_until_idle(&mut app).await;
+
+        assert_eq!(app.chat_state.chat.messages.len(), before);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cancelled_stream_drops_queue() {
+        let mut app = app_with_stream(MockChatStream::new(
+            &["thinking"],
+            MockOutcome::WaitForCancel,
+        ));
+        let before = app.chat_state.chat.messages.len();
+
+        app.start_llm_streaming("hi").unwrap();
+        assert!(app.queue_message("next".to_string()));
+        app.cancel_streaming();
+        run_until_idle(&mut app).await;
+
+        assert_eq!(app.chat_state.chat.messages.len(), before);
+        assert!(app.message_queue.is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_ctrl_c_twice_stops_streaming_before_quitting() {
+        let mut app = app_with_stream(MockChatStream::new(
+            &["half an answer"],
+            MockOutcome::WaitForCancel,
+        ));
+        app.base_focus = BaseFocus::Home;
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), event::KeyModifiers::CONTROL);
+
+        assert!(app.handle_message_input("hi".to_string()));
+        for _ in 0..100 {
+            app.process_streaming_chunks();
+            if assistant_replies(&app) == vec!["half an answer"] {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+
+        app.handle_keys(ctrl_c);
+        app.handle_keys(ctrl_c);
+        assert!(app.running);
+        run_until_idle(&mut app).await;
+
+        // The partial reply stays, in the chat and in the session
+        assert_eq!(assistant_replies(&app), vec!["half an answer"]);
+        let id = app
+            .session_manager
+            .get_current_session_id()
+            .unwrap()
+            .clone();
+        assert_eq!(app.session_manager.message_count(&id), 2);
+
+        app.handle_keys(ctrl_c);
+        assert!(!app.running);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_queued_message_starts_after_end() {
+        let mut app = app_with_stream(MockChatStream::new(&["ok"], MockOutcome::Complete));
+
+        app.start_llm_streaming("first").unwrap();
+        assert!(app.handle_message_input("second".to_string()));
+        assert_eq!(app.message_queue.len(), 1);
+
+        run_until_idle(&mut app).await;
+        // The end of the first turn started the queued one
+        assert!(app.message_queue.is_empty());
+        run_until_idle(&mut app).await;
+
+        let users: Vec<_> = app
+            .chat_state
+            .chat
+            .messages
+            .iter()
+            .filter(|m| m.role == MessageRole::User)
+            .map(|m| m.content.clone())
+            .collect();
+        assert_eq!(users, vec!["second".to_string()]);
+        assert_eq!(assistant_replies(&app), vec!["ok", "ok"]);
+    }
+
+    #[test]
+    fn test_select_provider_restores_its_model() {
+        let mut app = App::new();
+        let dao = app.prefs_dao.as_ref().unwrap();
+        dao.set_active_model("openai".to_string(), "gpt-a".to_string())
+            .unwrap();
+        dao.set_active_model("anthropic".to_string(), "claude-a".to_string())
+            .unwrap();
+
+        assert!(app.select_provider("openai"));
+        assert_eq!(app.model, "gpt-a");
+        assert_eq!(app.provider_name, "openai");
+
+        // Nothing remembered: stay on the current model
+        assert!(!app.select_provider("google"));
+        assert_eq!(app.provider_name, "openai");
+    }
+
+    #[test]
+    fn test_apply_model_repair_replaces_missing_model() {
+        let providers: std::collections::HashMap<String, crate::model::discovery::Provider> =
+            serde_json::from_value(serde_json::json!({
+                "openai": {
+                    "id": "openai",
+                    "name": "OpenAI",
+                    "models": {"gpt-a": {"id": "gpt-a", "name": "GPT A"}}
+                }
+            }))
+            .unwrap();
+        let connected = std::collections::HashSet::from(["openai".to_string()]);
+
+        let mut app = App::new();
+        app.provider_name = "openai".to_string();
+        app.model = "gpt-retired".to_string();
+
+        assert!(app.apply_model_repair(&providers, &connected));
+        assert_eq!(app.model, "gpt-a");
+        let saved = app.prefs_dao.as_ref().unwrap().get_active_model().unwrap();
+        assert_eq!(saved, Some(("openai".to_string(), "gpt-a".to_string())));
+
+        // Now valid, so nothing changes
+        assert!(!app.apply_model_repair(&providers, &connected));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_dump_last_turn() {
+        let mut app = app_with_stream(MockChatStream::new(
+            &["Hi", " there"],
+            MockOutcome::Complete,
+        ));
+        assert!(app.dump_last_turn().is_none());
+
+        app.start_llm_streaming("hello").unwrap();
+        run_until_idle(&mut app).await;
+
+        let path = app.dump_last_turn().unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains(&format!("Model: {}", app.model)));
+        assert!(text.contains("[0] system\n"));
+        assert!(text.contains("text \"Hi\"\ntext \" there\"\nend"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_tee_writes_turn_to_file() {
+        let mut app = app_with_stream(MockChatStream::new(
+            &["Hi", " there"],
+            MockOutcome::Fail("boom".to_string()),
+        ));
+        let dao = app.prefs_dao.as_ref().unwrap();
+        dao.set_tee_output(true).unwrap();
+
+        app.start_llm_streaming("hello").unwrap();
+        let path = app.tee.as_ref().unwrap().path.clone();
+        run_until_idle(&mut app).await;
+
+        assert!(app.tee.is_none());
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(text, "Hi there\n\n[failed: boom]\n");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_placeholder_hint_alternates_setup_tips() {
+        let connect = App::placeholder_hint(0, false, false);
+        assert!(connect.contains("/connect"));
+        assert!(App::placeholder_hint(1, false, false).starts_with("Ask anything"));
+
+        assert!(App::placeholder_hint(0, true, true).contains("/models"));
+        assert!(App::placeholder_hint(2, false, true).contains("/models"));
+        assert!(App::placeholder_hint(4, false, true).contains("/connect"));
+
+        // Nothing missing, so only suggestions
+        for index in 0..4 {
+            assert!(App::placeholder_hint(index, true, false).starts_with("Ask anything"));
+        }
+    }
+
+    #[test]
+    fn test_placeholder_rotates_only_while_idle() {
+        let mut app = App::new();
+        app.base_focus = BaseFocus::Home;
+        let stale = std::time::Instant::now() - PLACEHOLDER_ROTATION_INTERVAL;
+
+        let index = app.placeholder_index;
+        app.last_placeholder_rotation = stale;
+        app.rotate_placeholder();
+        assert_eq!(app.placeholder_index, index.wrapping_add(1));
+
+        // Stays put while the user is typing
+        app.input.set_text("fix the");
+        let placeholder = app.input.placeholder().to_string();
+        app.last_placeholder_rotation = stale;
+        app.rotate_placeholder();
+        assert_eq!(app.placeholder_index, index.wrapping_add(1));
+        assert_eq!(app.input.placeholder(), placeholder);
+    }
+
+    #[test]
+    fn test_redraw_only_after_changes() {
+        let mut app = App::new();
+        app.base_focus = BaseFocus::Home;
+        assert!(app.take_needs_redraw());
+
+        // Idle: the spinner ticks but isn't on screen
+        let stale = std::time::Instant::now() - std::time::Duration::from_secs(1);
+        app.last_animation_update = stale;
+        app.last_thinking_tick = stale;
+        app.update_animations();
+        app.process_streaming_chunks();
+        assert!(!app.take_needs_redraw());
+        assert!(!app.is_animating());
+
+        app.last_placeholder_rotation = stale - PLACEHOLDER_ROTATION_INTERVAL;
+        app.update_animations();
+        assert!(app.take_needs_redraw());
+        assert!(!app.take_needs_redraw());
+    }
+
+    #[test]
+    fn test_stored_session_loads_in_background() {
+        let dir = std::env::temp_dir().join(format!("crabcode-switch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dao = crate::persistence::HistoryDAO::open(&dir.join("data.db")).unwrap();
+        let mut app = App::new();
+        app.session_manager = SessionManager::new().with_history_dao(dao).unwrap();
+        let id = app.session_manager.create_session(Some("long".to_string()));
+        for text in ["first", "second", "third"] {
+            let message = crate::session::types::Message::user(text);
+            app.session_manager
+                .add_message_to_current_session(&message)
+                .unwrap();
+        }
+
+        app.load_session_into_chat(&id);
+        assert!(app.chat_state.chat.loading);
+        assert!(app.chat_state.chat.messages.is_empty());
+
+        for _ in 0..400 {
+            app.finish_session_load();
+            if app.session_load.is_none() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert!(!app.chat_state.chat.loading);
+        let contents: Vec<&str> = app
+            .chat_state
+            .chat
+            .messages
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(contents, ["first", "second", "third"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_explain_last_tool_error() {
+        let mut app = app_with_stream(MockChatStream::new(&["ok"], MockOutcome::Complete));
+        let row = |status: &str, output: &str| {
+            crate::session::types::Message::tool(
+                serde_json::json!({"name": "read", "status": status, "output_preview": output})
+                    .to_string(),
+            )
+        };
+        app.chat_state.chat.add_message(row("ok", "contents"));
+        assert!(!app.explain_last_tool_error());
+
+        app.chat_state
+            .chat
+            .add_message(row("error", "No such file: a.rs"));
+        app.chat_state.chat.add_message(row("ok", "contents"));
+        assert!(app.explain_last_tool_error());
+        run_until_idle(&mut app).await;
+
+        let sent = app
+            .chat_state
+            .chat
+            .messages
+            .iter()
+            .find(|m| m.role == crate::session::types::MessageRole::User)
+            .unwrap();
+        assert!(sent.content.starts_with("The `read` tool call failed."));
+        assert!(sent.content.contains("No such file: a.rs"));
+    }
+
+    #[test]
+    fn test_draft_follows_its_session() {
+        let mut app = App::new();
+        app.autosave_draft();
+        app.input.set_text("Rename the loader module");
+        app.flush_draft();
+
+        app.session_manager
+            .create_session(Some("other".to_string()));
+        app.autosave_draft();
+        assert_eq!(app.input.get_text(), "");
+
+        app.session_manager.clear_current_session();
+        app.autosave_draft();
+        assert_eq!(app.input.get_text(), "Rename the loader module");
+
+        app.discard_draft();
+        let dao = app.prefs_dao.as_ref().unwrap();
+        assert_eq!(dao.get_draft("home").unwrap(), None);
+    }
+
+    #[test]
+    fn test_jump_to_bookmark_loads_older_messages() {
+        let mut app = App::new();
+        app.session_manager = SessionManager::new().with_in_memory_history();
+        let id = app.session_manager.create_session(None);
+        for i in 0..5 {
+            let message = crate::session::types::Message::user(format!("message {}", i));
+            app.session_manager
+                .add_message_to_current_session(&message)
+                .unwrap();
+        }
+        app.session_manager.toggle_bookmark(&id, 1).unwrap();
+
+        // Only the newest two are paged in
+        let newest = app.session_manager.load_messages_page(&id, 0, 2);
+        app.show_session_page(crate::session::loader::SessionPage {
+            messages: newest,
+            total: 5,
+        });
+        assert_eq!(app.chat_state.chat.bookmarks, vec![1]);
+
+        app.jump_to_bookmark(true);
+        assert_eq!(app.chat_state.chat.messages.len(), 2);
+        app.jump_to_bookmark(false);
+        assert_eq!(app.chat_state.chat.messages.len(), 4);
+        assert_eq!(app.chat_state.chat.older_messages_available, 1);
+        assert_eq!(app.chat_state.chat.messages[0].content, "message 1");
+    }
+
+    #[test]
+    fn test_older_messages_are_counted_once() {
+        use crate::llm::limits::estimate_tokens;
+
+        let mut app = App::new();
+        app.session_manager = SessionManager::new().with_in_memory_history();
+        let id = app.session_manager.create_session(None);
+        for i in 0..5 {
+            let message = crate::session::types::Message::user(format!("message {}", i));
+            app.session_manager
+                .add_message_to_current_session(&message)
+                .unwrap();
+        }
+        let newest = app.session_manager.load_messages_page(&id, 0, 2);
+        app.show_session_page(crate::session::loader::SessionPage {
+            messages: newest,
+            total: 5,
+        });
+
+        let expected: usize = (0..3)
+            .map(|i| estimate_tokens(&format!("message {}", i)))
+            .sum();
+        assert_eq!(app.older_messages_tokens(), expected);
+
+        // New messages land in the chat, so the older count stands
+        let message = crate::session::types::Message::user("message 5".to_string());
+        app.session_manager
+            .add_message_to_current_session(&message)
+            .unwrap();
+        assert_eq!(app.older_messages_tokens(), expected);
+        assert_eq!(app.older_tokens_cache, Some(((id, 3), expected)));
+    }
+
+    #[test]
+    fn test_reload_applies_saved_preferences() {
+        let mut app = App::new();
+        let dao = app.prefs_dao.as_ref().unwrap();
+        dao.set_density(crate::persistence::Density::Compact)
+            .unwrap();
+        dao.set_active_model("openai".to_string(), "gpt-a".to_string())
+            .unwrap();
+        app.disable_color();
+
+        app.reload();
+        assert_eq!(
+            app.chat_state.chat.density,
+            crate::persistence::Density::Compact
+        );
+        assert_eq!(
+            (app.provider_name.as_str(), app.model.as_str()),
+            ("openai", "gpt-a")
+        );
+        assert_eq!(app.color_mode, crate::persistence::ColorMode::Monochrome);
+    }
+
+    #[test]
+    fn test_focus_mode_hides_status_rows() {
+        use ratatui::{backend::TestBackend, layout::Rect, Terminal};
+
+        let mut app = App::new();
+        app.base_focus = BaseFocus::Chat;
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        let mut screen = |app: &mut App| {
+            terminal.draw(|f| app.render(f)).unwrap();
+            let buffer = terminal.backend().buffer();
+            buffer
+                .content()
+                .iter()
+                .map(|cell| cell.symbol())
+                .collect::<String>()
+        };
+
+        // Toasts cover the hints, so look for the status bar's version
+        let version = format!(" {} ", app.version);
+        assert!(screen(&mut app).contains(&version));
+        app.toggle_focus_mode();
+        assert!(!screen(&mut app).contains(&version));
+
+        let size = Rect::new(0, 0, 80, 24);
+        let input_height = app.input.get_height();
+        assert_eq!(
+            crate::views::chat::chat_area(size, input_height, true).height,
+            crate::views::chat::chat_area(size, input_height, false).height + 2
+        );
+
+        app.toggle_focus_mode();
+        assert!(screen(&mut app).contains(&version));
+    }
+
+    #[test]
+    fn test_copy_mode_releases_the_mouse() {
+        let mut app = App::new();
+        app.base_focus = BaseFocus::Chat;
+        app.chat_state.chat.add_user_message("hello");
+        app.chat_state.chat.add_assistant_message("hi there");
+
+        // Nothing laid out yet
+        app.begin_copy_mode();
+        assert!(app.wants_mouse_capture());
+
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(60, 20)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        app.begin_copy_mode();
+        assert_eq!(app.overlay_focus, OverlayFocus::CopyMode);
+        assert!(!app.wants_mouse_capture());
+
+        // Keys move the selection instead of typing
+        app.handle_keys(KeyEvent::new(KeyCode::Char('k'), event::KeyModifiers::NONE));
+        assert!(app.input.get_text().is_empty());
+        let selected = app.chat_state.chat.selected_rows();
+        assert_eq!(selected.map(|rows| rows.count()), Some(2));
+
+        app.handle_keys(KeyEvent::new(KeyCode::Esc, event::KeyModifiers::NONE));
+        assert_eq!(app.overlay_focus, OverlayFocus::None);
+        assert!(!app.chat_state.chat.is_copy_mode());
+        assert!(app.wants_mouse_capture());
+
+        // Turned off by hand, it stays off after copy mode
+        app.toggle_mouse_capture();
+        assert!(!app.wants_mouse_capture());
+        app.begin_copy_mode();
+        app.handle_keys(KeyEvent::new(KeyCode::Esc, event::KeyModifiers::NONE));
+        assert!(!app.wants_mouse_capture());
+        app.toggle_mouse_capture();
+        assert!(app.wants_mouse_capture());
+    }
+
+    #[test]
+    fn test_notifications_outlive_their_toasts() {
+        let mut app = App::new();
+        push_toast(crate::ui::toast::new_toast(
+            "Request failed: overloaded",
+            ratatui_toolkit::ToastLevel::Error,
+            Some(std::time::Duration::from_millis(1)),
+        ));
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        crate::remove_expired_toasts();
+
+        let ctrl_x = KeyEvent::new(KeyCode::Char('x'), event::KeyModifiers::CONTROL);
+        app.handle_keys(ctrl_x);
+        app.handle_keys(KeyEvent::new(KeyCode::Char('t'), event::KeyModifiers::NONE));
+        assert_eq!(app.overlay_focus, OverlayFocus::Notifications);
+        assert!(app
+            .notifications_state
+            .records
+            .iter()
+            .any(|record| record.message == "Request failed: overloaded"));
+
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(60, 20)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+
+        app.handle_keys(KeyEvent::new(KeyCode::Esc, event::KeyModifiers::NONE));
+        assert_eq!(app.overlay_focus, OverlayFocus::None);
+    }
+
+    #[test]
+    fn test_multiline_paste_keeps_lines_without_sending() {
+        let mut app = App::new();
+        app.base_focus = BaseFocus::Chat;
+        app.input.insert_str("see: ");
+
+        // Line breaks as sent by different terminals
+        app.handle_paste("fn main() {\r    run();\r\n}\n".to_string());
+
+        assert_eq!(app.input.get_text(), "see: fn main() {\n    run();\n}\n");
+        assert!(app.chat_state.chat.messages.is_empty());
+        assert!(!app.is_streaming());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_large_paste_is_sent_as_attachment() {
+        let mut app = app_with_stream(MockChatStream::new(&["ok"], MockOutcome::Complete));
+        let dao = app.prefs_dao.as_ref().unwrap();
+        dao.set_paste_attach_lines(3).unwrap();
+
+        app.handle_paste("one\ntwo\nthree".to_string());
+        app.handle_paste("why?".to_string());
+        assert_eq!(app.input.get_text(), "why?");
+        assert_eq!(app.input.attachments().len(), 1);
+
+        let enter = KeyEvent::new(KeyCode::Enter, event::KeyModifiers::NONE);
+        app.handle_input_and_app_keys(enter);
+        run_until_idle(&mut app).await;
+
+        assert!(app.input.attachments().is_empty());
+        let sent = app
+            .chat_state
+            .chat
+            .messages
+            .iter()
+            .find(|m| m.role == crate::session::types::MessageRole::User)
+            .unwrap();
+        assert_eq!(
+            sent.content,
+            "why?\n\nPasted content (3 lines):\n```\none\ntwo\nthree\n```"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_shutdown_when_idle() {
+        let mut app = app_with_stream(MockChatStream::new(&[], MockOutcome::Complete));
+        app.shutdown().await;
+        assert!(!app.is_streaming());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_shutdown_keeps_partial_reply() {
+        let mut app = app_with_stream(MockChatStream::new(
+            &["half an answer"],
+            MockOutcome::WaitForCancel,
+        ));
+        app.base_focus = BaseFocus::Home;
+
+        assert!(app.handle_message_input("hi".to_string()));
+        // Let the reply arrive before quitting
+        for _ in 0..100 {
+            app.process_streaming_chunks();
+            if assistant_replies(&app) == vec!["half an answer"] {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        assert!(app.queue_message("never sent".to_string()));
+
+        app.shutdown().await;
+
+        assert!(!app.is_streaming());
+        assert!(app.message_queue.is_empty());
+        let id = app
+            .session_manager
+            .get_current_session_id()
+            .unwrap()
+            .clone();
+        // The user message and the partial reply
+        assert_eq!(app.session_manager.message_count(&id), 2);
+    }
+
+    #[test]
+    fn test_confirm_buttons_name_the_action() {
+        let mut app = App::new();
+        app.open_confirm(
+            PendingConfirm::RunCommand("cargo test".to_string()),
+            "Run command",
+            "Run `cargo test`?",
+        );
+        assert_eq!(app.confirm_dialog.confirm_label, "Run");
+        assert_eq!(app.confirm_dialog.cancel_label, "Cancel");
+        assert!(app.confirm_dialog.is_visible());
+
+        app.open_confirm(
+            PendingConfirm::DeleteSession("1".to_string()),
+            "Delete session",
+            "Delete \"notes\"?",
+        );
+        assert_eq!(app.confirm_dialog.confirm_label, "Delete");
+        assert_eq!(app.confirm_dialog.title, "Delete session");
+    }
+}
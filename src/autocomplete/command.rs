@@ -1,3 +1,4 @@
+use crate::command::parser::DEFAULT_PREFIX;
 use crate::command::registry::Registry;
 
 #[derive(Clone)]
@@ -6,9 +7,19 @@ pub struct Suggestion {
     pub description: String,
 }
 
-#[derive(Default)]
 pub struct CommandAuto {
     commands: Vec<Suggestion>,
+    /// What typed commands start with
+    prefix: String,
+}
+
+impl Default for CommandAuto {
+    fn default() -> Self {
+        Self {
+            commands: Vec::new(),
+            prefix: DEFAULT_PREFIX.to_string(),
+        }
+    }
 }
 
 impl CommandAuto {
@@ -21,7 +32,23 @@ impl CommandAuto {
                 description: cmd.description.clone(),
             })
             .collect();
-        Self { commands }
+        Self {
+            commands,
+            ..Self::default()
+        }
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn set_prefix(&mut self, prefix: &str) {
+        self.prefix = prefix.to_string();
+    }
+
+    /// The part of `text` naming a command, when it starts with the prefix.
+    pub fn command_filter<'a>(&self, text: &'a str) -> Option<&'a str> {
+        text.strip_prefix(self.prefix.as_str())
     }
 
     pub fn get_suggestions(&self, input: &str) -> Vec<Suggestion> {
@@ -54,16 +81,19 @@ mod tests {
             name: "help".to_string(),
             description: "Show help".to_string(),
             handler: dummy_handler,
+            takes_rest: false,
         });
         registry.register(Command {
             name: "sessions".to_string(),
             description: "Manage sessions".to_string(),
             handler: dummy_handler,
+            takes_rest: false,
         });
         registry.register(Command {
             name: "exit".to_string(),
             description: "Exit the app".to_string(),
             handler: dummy_handler,
+            takes_rest: false,
         });
         registry
     }
@@ -107,6 +137,18 @@ mod tests {
         assert_eq!(suggestions[0].name, "help");
     }
 
+    #[test]
+    fn test_command_filter_follows_the_prefix() {
+        let mut auto = CommandAuto::new(&setup_registry());
+        assert_eq!(auto.command_filter("/se"), Some("se"));
+        assert_eq!(auto.command_filter(":se"), None);
+
+        auto.set_prefix(":");
+        assert_eq!(auto.command_filter("/se"), None);
+        let filter = auto.command_filter(":se").unwrap();
+        assert_eq!(auto.get_suggestions(filter)[0].name, "sessions");
+    }
+
     #[test]
     fn test_get_suggestions_no_match() {
         let registry = setup_registry();
@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Shorthands for commands, read from `<config dir>/crabcode/aliases.json`.
+///
+/// An alias stands for a command name with optional arguments, so `"m":
+/// "models"` makes `/m` open the models dialog and `"4o": "models gpt-4o"`
+/// passes an argument along. Aliases can point at other aliases.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandAliases {
+    pub aliases: BTreeMap<String, String>,
+    /// Let an alias replace the built-in command of the same name
+    pub allow_shadowing: bool,
+}
+
+impl CommandAliases {
+    pub fn load() -> Self {
+        let path = Self::config_path();
+        if !path.exists() {
+            return Self::default();
+        }
+
+        match fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+        {
+            Ok(aliases) => aliases,
+            Err(e) => {
+                let _ =
+                    crate::logging::log(&format!("[ALIASES] ignoring {}: {}", path.display(), e));
+                Self::default()
+            }
+        }
+    }
+
+    fn config_path() -> PathBuf {
+        if cfg!(test) || env::var("CRABCODE_TEST_MODE").is_ok() {
+            PathBuf::from("/tmp/crabcode_test_aliases.json")
+        } else {
+            dirs::config_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("crabcode")
+                .join("aliases.json")
+        }
+    }
+
+    /// Drops the aliases that can't be used: empty ones, ones named after a
+    /// built-in command (unless `allow_shadowing`), and ones that lead back
+    /// to themselves. Returns why each was dropped.
+    pub fn validate(&mut self, is_command: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut dropped = Vec::new();
+        for (name, target) in &self.aliases {
+            if command_line(target).is_empty() {
+                problems.push(format!("Alias /{} is empty", name));
+            } else if is_command(name) && !self.allow_shadowing {
+                problems.push(format!(
+                    "Alias /{} would replace the built-in command; set allow_shadowing to allow it",
+                    name
+                ));
+            } else {
+                continue;
+            }
+            dropped.push(name.clone());
+        }
+        for name in dropped {
+            self.aliases.remove(&name);
+        }
+
+        let looping: Vec<String> = self
+            .aliases
+            .keys()
+            .filter(|name| self.resolve(name, &is_command).is_none())
+            .cloned()
+            .collect();
+        for name in looping {
+            problems.push(format!("Alias /{} leads back to itself", name));
+            self.aliases.remove(&name);
+        }
+        problems
+    }
+
+    /// The command line (without the `/`) that `line` stands for once every
+    /// alias in the chain is expanded, the typed arguments kept as written
+    /// after the alias's own; lines that don't start with an alias come back
+    /// as they are. `None` when the chain loops.
+    ///
+    /// An alias that shadows a built-in can still run it: once expanding
+    /// reaches a name it has already expanded, that name is the built-in.
+    pub fn resolve(&self, line: &str, is_command: impl Fn(&str) -> bool) -> Option<String> {
+        let mut line = command_line(line).to_string();
+        let mut expanded: Vec<String> = Vec::new();
+        loop {
+            let (name, tail) = line.split_once(char::is_whitespace).unwrap_or((&line, ""));
+            let Some(target) = self.aliases.get(name) else {
+                break;
+            };
+            if expanded.iter().any(|n| n == name) {
+                if is_command(name) {
+                    break;
+                }
+                return None;
+            }
+            let target = command_line(target);
+            if target.is_empty() {
+                break;
+            }
+            expanded.push(name.to_string());
+            line = match tail.trim() {
+                "" => target.to_string(),
+                tail => format!("{} {}", target, tail),
+            };
+        }
+        Some(line)
+    }
+}
+
+/// A command line without surrounding whitespace or its leading `/`.
+fn command_line(line: &str) -> &str {
+    line.trim().trim_start_matches('/').trim_start()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUILT_IN: [&str; 3] = ["models", "new", "sessions"];
+
+    fn is_command(name: &str) -> bool {
+        BUILT_IN.contains(&name)
+    }
+
+    fn aliases(pairs: &[(&str, &str)]) -> CommandAliases {
+        CommandAliases {
+            aliases: pairs
+                .iter()
+                .map(|(name, target)| (name.to_string(), target.to_string()))
+                .collect(),
+            allow_shadowing: false,
+        }
+    }
+
+    fn resolve(aliases: &CommandAliases, line: &str) -> Option<String> {
+        aliases.resolve(line, is_command)
+    }
+
+    fn expanded(line: &str) -> Option<String> {
+        Some(line.to_string())
+    }
+
+    #[test]
+    fn test_alias_resolution() {
+        let aliases = aliases(&[("m", "models"), ("4o", "/m gpt-4o"), ("s", "sessions")]);
+        assert_eq!(resolve(&aliases, "m"), expanded("models"));
+        assert_eq!(resolve(&aliases, "s list"), expanded("sessions list"));
+        // Through another alias, its arguments ahead of the typed ones
+        assert_eq!(
+            resolve(&aliases, "4o extra"),
+            expanded("models gpt-4o extra")
+        );
+        assert_eq!(resolve(&aliases, "new"), expanded("new"));
+        // Typed arguments are passed on as written, quotes included
+        assert_eq!(
+            resolve(&aliases, r#"s "two  words""#),
+            expanded(r#"sessions "two  words""#)
+        );
+    }
+
+    #[test]
+    fn test_cycles_are_dropped() {
+        let mut aliases = aliases(&[("a", "b"), ("b", "c x"), ("c", "a"), ("m", "models")]);
+        assert_eq!(resolve(&aliases, "a"), None);
+
+        let problems = aliases.validate(is_command);
+        assert_eq!(problems.len(), 3);
+        assert!(problems[0].contains("/a leads back"));
+        assert_eq!(aliases.aliases.keys().collect::<Vec<_>>(), ["m"]);
+    }
+
+    #[test]
+    fn test_shadowing_needs_to_be_allowed() {
+        let mut refused = aliases(&[("new", "new scratch"), ("blank", "  ")]);
+        let problems = refused.validate(is_command);
+        assert_eq!(problems.len(), 2);
+        assert!(refused.aliases.is_empty());
+
+        let mut allowed = aliases(&[("new", "new scratch"), ("sessions", "models")]);
+        allowed.allow_shadowing = true;
+        assert!(allowed.validate(is_command).is_empty());
+        // Expanding into itself runs the built-in
+        assert_eq!(resolve(&allowed, "new"), expanded("new scratch"));
+        assert_eq!(resolve(&allowed, "sessions"), expanded("models"));
+    }
+
+    #[test]
+    fn test_parses_from_config_json() {
+        let aliases: CommandAliases =
+            serde_json::from_str(r#"{"aliases": {"m": "models"}}"#).unwrap();
+        assert_eq!(aliases.aliases["m"], "models");
+        assert!(!aliases.allow_shadowing);
+    }
+}
@@ -1,8 +1,10 @@
-use crate::command::parser::ParsedCommand;
+use crate::command::parser::{split_args, ParsedCommand};
 use crate::command::registry::{Command, CommandResult, Registry};
+use crate::persistence::TimeFormat;
 use crate::push_toast;
 use crate::session::manager::SessionManager;
-use chrono::{DateTime, Local, Utc};
+use chrono::{DateTime, Local, TimeZone};
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 
 pub fn handle_exit<'a>(
@@ -12,26 +14,95 @@ pub fn handle_exit<'a>(
     Box::pin(async { CommandResult::Success("Exiting...".to_string()) })
 }
 
+const CONFIRMED_FLAG: &str = "--yes";
+
 pub fn handle_sessions<'a>(
-    _parsed: &'a ParsedCommand<'a>,
+    parsed: &'a ParsedCommand<'a>,
     sm: &'a mut SessionManager,
 ) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let args = parsed.args.clone();
+    let time_format = parsed
+        .prefs_dao
+        .and_then(|dao| dao.get_time_format().ok())
+        .unwrap_or_default();
+
     Box::pin(async move {
+        let confirmed = args.iter().any(|a| a == CONFIRMED_FLAG);
+        let args: Vec<&str> = args
+            .iter()
+            .map(|a| a.as_str())
+            .filter(|a| *a != CONFIRMED_FLAG)
+            .collect();
+
+        match args.as_slice() {
+            [] => {}
+            ["clear"] => {
+                if !confirmed {
+                    return CommandResult::Confirm {
+                        title: "Delete all sessions".to_string(),
+                        message: format!("Delete all {} sessions?", sm.list_sessions().len()),
+                        command: format!("/sessions clear {}", CONFIRMED_FLAG),
+                    };
+                }
+                let deleted = sm.clear();
+                return CommandResult::Success(format_deleted_count(deleted));
+            }
+            ["prune", days] => {
+                let Ok(days) = days.parse::<u64>() else {
+                    return CommandResult::Error(format!("Invalid number of days: {}", days));
+                };
+                let Some(before) = days.checked_mul(86_400).and_then(|secs| {
+                    std::time::SystemTime::now().checked_sub(std::time::Duration::from_secs(secs))
+                }) else {
+                    return CommandResult::Error(format!(
+                        "Too many days: {}. Usage: /sessions prune <days>",
+                        days
+                    ));
+                };
+                if !confirmed {
+                    let count = sm
+                        .list_sessions()
+                        .iter()
+                        .filter(|s| s.updated_at < before)
+                        .count();
+                    return CommandResult::Confirm {
+                        title: "Prune sessions".to_string(),
+                        message: format!(
+                            "Delete {} sessions not updated in the last {} days?",
+                            count, days
+                        ),
+                        command: format!("/sessions prune {} {}", days, CONFIRMED_FLAG),
+                    };
+                }
+                let deleted = sm.prune(before);
+                return CommandResult::Success(format_deleted_count(deleted));
+            }
+            _ => {
+                return CommandResult::Error("Usage: /sessions [clear | prune <days>]".to_string());
+            }
+        }
+
         let mut sessions = sm.list_sessions();
         sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
 
         let items: Vec<crate::command::registry::DialogItem> = sessions
             .into_iter()
             .map(|session| {
-                let date_group = format_date_group(session.updated_at);
-                let time = format_time(session.updated_at);
+                let group = if session.pinned {
+                    "Pinned".to_string()
+                } else {
+                    format_date_group(session.updated_at)
+                };
+                let time = format_time(session.updated_at, time_format);
+                let tags = format_session_tags(&session.tags);
+                let tip = format_session_tip(&tags, session.message_count, time, session.updated);
 
                 crate::command::registry::DialogItem {
                     id: session.id.clone(),
                     name: session.title.clone(),
-                    group: date_group,
-                    description: String::new(),
-                    tip: Some(time),
+                    group,
+                    description: tags,
+                    tip: Some(tip),
                     provider_id: String::new(),
                 }
             })
@@ -44,24 +115,83 @@ pub fn handle_sessions<'a>(
     })
 }
 
-fn format_date_group(created_at: std::time::SystemTime) -> String {
-    let datetime: DateTime<Local> = created_at.into();
-    let now: DateTime<Local> = Utc::now().into();
-    let duration = now.signed_duration_since(datetime);
+/// Shown in the sessions dialog next to sessions changed in the background.
+pub const UPDATED_MARKER: &str = "●";
+
+/// The right-hand tip of a sessions dialog row: tags, the message count and
+/// the time, with [`UPDATED_MARKER`] in front for sessions changed since
+/// last opened.
+pub fn format_session_tip(tags: &str, messages: usize, time: String, updated: bool) -> String {
+    let count = match messages {
+        1 => "1 msg".to_string(),
+        n => format!("{} msgs", n),
+    };
+    let tip = if tags.is_empty() {
+        format!("{}  {}", count, time)
+    } else {
+        format!("{}  {}  {}", tags, count, time)
+    };
+    if updated {
+        format!("{} {}", UPDATED_MARKER, tip)
+    } else {
+        tip
+    }
+}
+
+/// Renders tags as `#tag` labels; also used as the searchable description in
+/// the sessions dialog.
+pub fn format_session_tags(tags: &[String]) -> String {
+    tags.iter()
+        .map(|t| format!("#{}", t))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn format_deleted_count(count: usize) -> String {
+    if count == 1 {
+        "Deleted 1 session".to_string()
+    } else {
+        format!("Deleted {} sessions", count)
+    }
+}
+
+/// "Today" when `at` falls on the current local calendar day, otherwise
+/// its date.
+pub fn format_date_group(at: std::time::SystemTime) -> String {
+    date_group(&at.into(), &Local::now())
+}
 
-    if duration.num_days() == 0 {
+/// Both times must be in the same timezone, so the day boundary is the
+/// user's midnight rather than UTC's.
+fn date_group<Tz: TimeZone>(at: &DateTime<Tz>, now: &DateTime<Tz>) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    if at.date_naive() == now.date_naive() {
         "Today".to_string()
     } else {
-        datetime.format("%a %b %d %Y").to_string()
+        at.format("%a %b %d %Y").to_string()
+    }
+}
+
+pub fn format_time(at: std::time::SystemTime, format: TimeFormat) -> String {
+    let datetime: DateTime<Local> = at.into();
+    match format {
+        TimeFormat::TwelveHour => clock_time(&datetime, false),
+        TimeFormat::TwentyFourHour => clock_time(&datetime, true),
+        TimeFormat::Relative => crate::utils::relative_time(at),
     }
 }
 
-fn format_time(created_at: std::time::SystemTime) -> String {
+fn clock_time<Tz: TimeZone>(at: &DateTime<Tz>, twenty_four_hour: bool) -> String {
     use chrono::Timelike;
-    let datetime: DateTime<Local> = created_at.into();
-    let hour = datetime.time().hour12();
-    let am_pm = if hour.0 { "PM" } else { "AM" };
-    format!("{}:{:02} {}", hour.1, datetime.time().minute(), am_pm)
+    if twenty_four_hour {
+        format!("{:02}:{:02}", at.hour(), at.minute())
+    } else {
+        let (pm, hour) = at.hour12();
+        let am_pm = if pm { "PM" } else { "AM" };
+        format!("{}:{:02} {}", hour, at.minute(), am_pm)
+    }
 }
 
 pub fn handle_new<'a>(
@@ -129,17 +259,16 @@ pub fn handle_connect<'a>(
                     } else {
                         "Other"
                     };
-                    let is_connected = connected_providers.contains_key(&id);
+                    let key = crate::persistence::auth::resolve_key(
+                        connected_providers.get(&id).map(|c| c.key().to_string()),
+                        &provider.env,
+                    );
                     crate::command::registry::DialogItem {
                         id: id.clone(),
                         name: provider.name.clone(),
                         group: group.to_string(),
                         description: id.clone(),
-                        tip: if is_connected {
-                            Some("🟢 Connected".to_string())
-                        } else {
-                            None
-                        },
+                        tip: key.map(|(_, source)| format!("🟢 Connected ({})", source.label())),
                         provider_id: id.clone(),
                     }
                 })
@@ -217,11 +346,20 @@ pub fn handle_models<'a>(
             Err(e) => return CommandResult::Error(format!("Failed to load auth config: {}", e)),
         };
 
-        let connected_providers = match auth_dao.load() {
-            Ok(providers) => providers,
+        let mut connected_providers: std::collections::HashSet<String> = match auth_dao.load() {
+            Ok(providers) => providers.into_keys().collect(),
             Err(e) => return CommandResult::Error(format!("Failed to load providers: {}", e)),
         };
 
+        // Providers whose key comes from `.env` or the environment
+        if let Ok(discovery) = Discovery::new() {
+            if let Ok(providers) = discovery.fetch_providers().await {
+                connected_providers.extend(providers.into_iter().filter_map(|(id, provider)| {
+                    crate::persistence::auth::resolve_key(None, &provider.env).map(|_| id)
+                }));
+            }
+        }
+
         if connected_providers.is_empty() {
             return CommandResult::Error(
                 "No models available. Please connect a provider first using /connect".to_string(),
@@ -239,7 +377,7 @@ pub fn handle_models<'a>(
                         std::collections::HashMap::new();
 
                     for model in &models {
-                        if connected_providers.contains_key(&model.provider_id)
+                        if connected_providers.contains(&model.provider_id)
                             && if let Some(filter) = &provider_filter {
                                 model.provider_id.contains(filter)
                                     || model.provider_name.to_lowercase().contains(filter)
@@ -357,7 +495,7 @@ pub fn handle_models<'a>(
                             continue;
                         }
 
-                        if connected_providers.contains_key(&model.provider_id)
+                        if connected_providers.contains(&model.provider_id)
                             && if let Some(filter) = &provider_filter {
                                 model.provider_id.contains(filter)
                                     || model.provider_name.to_lowercase().contains(filter)
@@ -425,216 +563,2207 @@ pub fn handle_models<'a>(
     })
 }
 
-pub fn handle_refreshmodels<'a>(
+pub fn handle_pin<'a>(
     _parsed: &'a ParsedCommand<'a>,
-    _sm: &'a mut SessionManager,
+    sm: &'a mut SessionManager,
 ) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
     Box::pin(async move {
-        let discovery = match crate::model::discovery::Discovery::new() {
-            Ok(d) => d,
-            Err(e) => {
-                push_toast(ratatui_toolkit::Toast::new(
-                    format!("Failed to initialize model discovery: {}", e),
-                    ratatui_toolkit::ToastLevel::Error,
-                    Some(std::time::Duration::from_secs(3)),
-                ));
-                return CommandResult::Success(String::new());
-            }
+        let Some(id) = sm.get_current_session_id().cloned() else {
+            return CommandResult::Error("No active session to pin".to_string());
         };
 
-        let providers = match discovery.refresh_cache().await {
-            Ok(p) => p,
-            Err(e) => {
-                push_toast(ratatui_toolkit::Toast::new(
-                    format!("Failed to refresh models cache: {}", e),
-                    ratatui_toolkit::ToastLevel::Error,
+        match sm.toggle_pin(&id) {
+            Ok(pinned) => {
+                push_toast(crate::ui::toast::new_toast(
+                    if pinned {
+                        "Session pinned"
+                    } else {
+                        "Session unpinned"
+                    },
+                    ratatui_toolkit::ToastLevel::Info,
                     Some(std::time::Duration::from_secs(3)),
                 ));
-                return CommandResult::Success(String::new());
+                CommandResult::Success(String::new())
             }
-        };
-
-        let provider_count = providers.len();
-        let model_count: usize = providers.values().map(|p| p.models.len()).sum();
-
-        push_toast(ratatui_toolkit::Toast::new(
-            format!(
-                "Models cache refreshed: {} providers, {} models",
-                provider_count, model_count
-            ),
-            ratatui_toolkit::ToastLevel::Info,
-            Some(std::time::Duration::from_secs(3)),
-        ));
-
-        CommandResult::Success(String::new())
+            Err(e) => CommandResult::Error(format!("Failed to pin session: {:?}", e)),
+        }
     })
 }
 
-pub fn register_all_commands(registry: &mut Registry) {
-    registry.register(Command {
-        name: "exit".to_string(),
-        description: "Quit crabcode".to_string(),
-        handler: handle_exit,
-    });
-
-    registry.register(Command {
-        name: "sessions".to_string(),
-        description: "List all sessions".to_string(),
-        handler: handle_sessions,
-    });
-
-    registry.register(Command {
-        name: "new".to_string(),
-        description: "Switch to home screen".to_string(),
-        handler: handle_new,
-    });
-
-    registry.register(Command {
-        name: "home".to_string(),
-        description: "Switch to home screen".to_string(),
-        handler: handle_new,
-    });
+pub fn handle_tag<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let args = parsed.args.clone();
 
-    registry.register(Command {
-        name: "connect".to_string(),
-        description: "Connect to a model provider".to_string(),
-        handler: handle_connect,
-    });
+    Box::pin(async move {
+        let Some(id) = sm.get_current_session_id().cloned() else {
+            return CommandResult::Error("No active session to tag".to_string());
+        };
 
-    registry.register(Command {
-        name: "models".to_string(),
-        description: "List available models".to_string(),
-        handler: handle_models,
-    });
+        let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+        // The toast for when the tags changed, and for when they didn't
+        let (result, changed, unchanged) = match args.as_slice() {
+            [] => {
+                let tags = sm
+                    .get_session(&id)
+                    .map(|s| format_session_tags(&s.tags))
+                    .unwrap_or_default();
+                let message = if tags.is_empty() {
+                    "No tags".to_string()
+                } else {
+                    format!("Tags: {}", tags)
+                };
+                (Ok(false), String::new(), message)
+            }
+            ["remove"] => {
+                return CommandResult::Error("Usage: /tag remove <label>".to_string());
+            }
+            ["remove", tag] => {
+                let tag = tag.trim_start_matches('#');
+                if tag.is_empty() {
+                    return CommandResult::Error("Tag cannot be empty".to_string());
+                }
+                (
+                    sm.remove_tag(&id, tag),
+                    format!("Removed tag #{}", tag),
+                    format!("No tag #{} to remove", tag),
+                )
+            }
+            [tag] => {
+                let tag = tag.trim_start_matches('#');
+                if tag.is_empty() {
+                    return CommandResult::Error("Tag cannot be empty".to_string());
+                }
+                (
+                    sm.add_tag(&id, tag),
+                    format!("Tagged #{}", tag),
+                    format!("Already tagged #{}", tag),
+                )
+            }
+            _ => {
+                return CommandResult::Error("Usage: /tag [<label> | remove <label>]".to_string());
+            }
+        };
 
-    registry.register(Command {
-        name: "refreshmodels".to_string(),
-        description: "Refresh the models.dev cache".to_string(),
-        handler: handle_refreshmodels,
-    });
+        match result {
+            Ok(updated) => {
+                push_toast(crate::ui::toast::new_toast(
+                    if updated { changed } else { unchanged },
+                    ratatui_toolkit::ToastLevel::Info,
+                    Some(std::time::Duration::from_secs(3)),
+                ));
+                CommandResult::Success(String::new())
+            }
+            Err(e) => CommandResult::Error(format!("Failed to update tags: {:?}", e)),
+        }
+    })
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::command::registry::Registry;
+/// Index of every message with `role` in the session, oldest first.
+fn message_indices(
+    sm: &SessionManager,
+    id: &str,
+    role: crate::session::types::MessageRole,
+) -> Vec<usize> {
+    let count = sm.message_count(id);
+    sm.load_messages_page(id, 0, count)
+        .iter()
+        .enumerate()
+        .filter(|(_, message)| message.role == role)
+        .map(|(i, _)| i)
+        .collect()
+}
 
-    fn create_registry() -> Registry {
-        let mut registry = Registry::new();
-        register_all_commands(&mut registry);
-        registry
-    }
+pub fn handle_branch<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let args = parsed.args.clone();
 
-    #[tokio::test]
-    async fn test_handle_exit() {
-        let parsed = ParsedCommand {
-            name: "exit".to_string(),
-            args: vec![],
-            raw: "/exit".to_string(),
-            prefs_dao: None,
-            active_model_id: None,
+    Box::pin(async move {
+        let nth = match args.as_slice() {
+            [] => 1,
+            [n] => match n.parse::<usize>() {
+                Ok(n) if n > 0 => n,
+                _ => return CommandResult::Error("Usage: /branch [n]".to_string()),
+            },
+            _ => return CommandResult::Error("Usage: /branch [n]".to_string()),
+        };
+        let Some(id) = sm.get_current_session_id().cloned() else {
+            return CommandResult::Error("No active session to branch".to_string());
         };
-        let mut session_manager = SessionManager::new();
-        let result = handle_exit(&parsed, &mut session_manager).await;
-        assert_eq!(result, CommandResult::Success("Exiting...".to_string()));
-    }
 
-    #[tokio::test]
-    async fn test_handle_sessions() {
-        let parsed = ParsedCommand {
-            name: "sessions".to_string(),
-            args: vec![],
-            raw: "/sessions".to_string(),
-            prefs_dao: None,
-            active_model_id: None,
+        let replies = message_indices(sm, &id, crate::session::types::MessageRole::Assistant);
+        let Some(&at) = replies.iter().rev().nth(nth - 1) else {
+            return CommandResult::Error(format!(
+                "This session has {} replies to branch from",
+                replies.len()
+            ));
         };
-        let mut session_manager = SessionManager::new();
-        let result = handle_sessions(&parsed, &mut session_manager).await;
-        match result {
-            CommandResult::ShowDialog { title, items } => {
-                assert_eq!(title, "Sessions");
-                assert!(items.is_empty());
+
+        match sm.branch(&id, at) {
+            Ok(branch) => {
+                sm.switch_session(&branch);
+                push_toast(crate::ui::toast::new_toast(
+                    "Branched into a new session; the original is unchanged",
+                    ratatui_toolkit::ToastLevel::Info,
+                    Some(std::time::Duration::from_secs(3)),
+                ));
+                CommandResult::OpenSession {
+                    id: branch,
+                    resend: None,
+                }
             }
-            _ => panic!("Expected ShowDialog"),
+            Err(e) => CommandResult::Error(format!("Failed to branch session: {}", e)),
         }
-    }
+    })
+}
 
-    #[tokio::test]
-    async fn test_handle_sessions_with_data() {
-        let mut session_manager = SessionManager::new();
-        session_manager.create_session(Some("session-1".to_string()));
-        session_manager.create_session(Some("session-2".to_string()));
+pub fn handle_redo<'a>(
+    _parsed: &'a ParsedCommand<'a>,
+    sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(id) = sm.get_current_session_id().cloned() else {
+            return CommandResult::Error("No active session to redo".to_string());
+        };
 
-        let parsed = ParsedCommand {
-            name: "sessions".to_string(),
-            args: vec![],
-            raw: "/sessions".to_string(),
-            prefs_dao: None,
-            active_model_id: None,
+        let prompts = message_indices(sm, &id, crate::session::types::MessageRole::User);
+        let Some(&at) = prompts.last() else {
+            return CommandResult::Error("Nothing to redo yet".to_string());
         };
-        let result = handle_sessions(&parsed, &mut session_manager).await;
-        match result {
-            CommandResult::ShowDialog { title, items } => {
-                assert_eq!(title, "Sessions");
-                assert_eq!(items.len(), 2);
-                assert!(
-                    items.iter().any(|item| item.name == "session-1"),
-                    "Items: {:?}",
-                    items.iter().map(|i| &i.name).collect::<Vec<_>>()
-                );
-                assert!(items.iter().any(|item| item.name == "session-2"));
+        let prompt = sm
+            .load_messages_page(&id, sm.message_count(&id) - at - 1, 1)
+            .pop()
+            .map(|message| message.content)
+            .unwrap_or_default();
+
+        // The branch keeps everything before the prompt, which is sent again
+        let branch = if at > 0 {
+            match sm.branch(&id, at - 1) {
+                Ok(branch) => branch,
+                Err(e) => return CommandResult::Error(format!("Failed to branch session: {}", e)),
             }
-            _ => panic!("Expected ShowDialog"),
+        } else {
+            let title = sm
+                .get_session(&id)
+                .map(|s| s.title.clone())
+                .unwrap_or_default();
+            let branch = sm.create_session(None);
+            let _ = sm.rename_session(&branch, format!("{} (branch)", title));
+            branch
+        };
+
+        sm.switch_session(&branch);
+        CommandResult::OpenSession {
+            id: branch,
+            resend: Some(prompt),
         }
-    }
+    })
+}
 
-    #[tokio::test]
-    async fn test_handle_new_no_args() {
-        let parsed = ParsedCommand {
-            name: "new".to_string(),
-            args: vec![],
-            raw: "/new".to_string(),
-            prefs_dao: None,
-            active_model_id: None,
+/// One line per bookmark: its number for `/bookmarks <n>`, who wrote the
+/// message and how it starts.
+pub fn format_bookmarks(bookmarks: &[(usize, crate::session::types::Message)]) -> String {
+    use crate::session::types::MessageRole;
+
+    let mut out = format!(
+        "Bookmarks ({}), /bookmarks <n> jumps to one:",
+        bookmarks.len()
+    );
+    for (i, (index, message)) in bookmarks.iter().enumerate() {
+        let author = match message.role {
+            MessageRole::User => "You",
+            MessageRole::Assistant => "Assistant",
+            MessageRole::System => "System",
+            MessageRole::Tool => "Tool",
         };
-        let mut session_manager = SessionManager::new();
-        let result = handle_new(&parsed, &mut session_manager).await;
-        match result {
-            CommandResult::Success(msg) => {
-                assert!(msg.is_empty());
-            }
-            _ => panic!("Expected Success"),
-        }
+        let first_line = message
+            .content
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or("");
+        out.push_str(&format!(
+            "\n{}. message {} · {}: {}",
+            i + 1,
+            index + 1,
+            author,
+            crate::utils::truncate_chars(first_line, 80)
+        ));
     }
+    out
+}
 
-    #[tokio::test]
-    async fn test_handle_new_with_name() {
-        let parsed = ParsedCommand {
-            name: "new".to_string(),
-            args: vec!["my-session".to_string()],
-            raw: "/new my-session".to_string(),
-            prefs_dao: None,
-            active_model_id: None,
+pub fn handle_bookmarks<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let args = parsed.args.clone();
+
+    Box::pin(async move {
+        let Some(id) = sm.get_current_session_id().cloned() else {
+            return CommandResult::Error("No active session".to_string());
         };
-        let mut session_manager = SessionManager::new();
-        let result = handle_new(&parsed, &mut session_manager).await;
-        match result {
-            CommandResult::Success(msg) => {
-                assert!(msg.is_empty());
+        let bookmarks = sm.bookmarks(&id);
+
+        match args.as_slice() {
+            [] if bookmarks.is_empty() => {
+                push_toast(crate::ui::toast::new_toast(
+                    "No bookmarks yet; Ctrl+X s bookmarks the message at the top",
+                    ratatui_toolkit::ToastLevel::Info,
+                    Some(std::time::Duration::from_secs(3)),
+                ));
+                CommandResult::Success(String::new())
             }
-            _ => panic!("Expected Success"),
+            [] => {
+                let count = sm.message_count(&id);
+                let messages = sm.load_messages_page(&id, 0, count);
+                let listed: Vec<_> = bookmarks
+                    .iter()
+                    .filter_map(|&index| Some((index, messages.get(index)?.clone())))
+                    .collect();
+                CommandResult::Success(format_bookmarks(&listed))
+            }
+            [n] => match n.parse::<usize>() {
+                Ok(n) if n > 0 && n <= bookmarks.len() => {
+                    CommandResult::ScrollToMessage(bookmarks[n - 1])
+                }
+                _ => CommandResult::Error(format!("Pick a bookmark from 1 to {}", bookmarks.len())),
+            },
+            _ => CommandResult::Error("Usage: /bookmarks [<n>]".to_string()),
         }
-    }
+    })
+}
 
-    #[tokio::test]
-    async fn test_handle_home() {
-        let parsed = ParsedCommand {
-            name: "home".to_string(),
-            args: vec![],
-            raw: "/home".to_string(),
+pub fn handle_stats<'a>(
+    _parsed: &'a ParsedCommand<'a>,
+    sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(id) = sm.get_current_session_id().cloned() else {
+            return CommandResult::Error("No active session".to_string());
+        };
+        let title = sm
+            .get_session(&id)
+            .map(|s| s.title.clone())
+            .unwrap_or_default();
+        let count = sm.message_count(&id);
+        let messages = sm.load_messages_page(&id, 0, count);
+
+        // Prices come from the models.dev cache; stats don't go online
+        let providers = crate::model::discovery::Discovery::new()
+            .ok()
+            .and_then(|discovery| discovery.cached_providers());
+        let price_of = |message: &crate::session::types::Message| {
+            providers
+                .as_ref()?
+                .get(message.provider.as_deref()?)?
+                .models
+                .get(message.model.as_deref()?)?
+                .cost
+                .clone()
+        };
+
+        let stats = crate::session::stats::SessionStats::from_messages(&messages, price_of);
+        CommandResult::Success(crate::session::stats::format_stats(&title, &stats))
+    })
+}
+
+pub fn handle_usage<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let args = parsed.args.clone();
+
+    Box::pin(async move {
+        let refresh = match args.as_slice() {
+            [] => false,
+            [arg] if arg == "refresh" => true,
+            _ => return CommandResult::Error("Usage: /usage [refresh]".to_string()),
+        };
+
+        let providers = crate::model::discovery::Discovery::new()
+            .ok()
+            .and_then(|discovery| discovery.cached_providers());
+        let price_of = |provider: &str, model: &str| {
+            providers
+                .as_ref()?
+                .get(provider)?
+                .models
+                .get(model)?
+                .cost
+                .clone()
+        };
+
+        match sm.usage(refresh, price_of) {
+            Ok(report) => CommandResult::Success(crate::session::usage::format_usage(report)),
+            Err(e) => CommandResult::Error(format!("Failed to read usage: {}", e)),
+        }
+    })
+}
+
+/// Most recent tool calls shown by `/tools-log` without an explicit count.
+const TOOLS_LOG_DEFAULT_LIMIT: usize = 20;
+
+pub fn format_tool_calls(records: &[crate::persistence::ToolCallRecord], total: usize) -> String {
+    let mut out = format!("Tool calls ({} of {}):", records.len(), total);
+    let first_index = total - records.len();
+    for (i, record) in records.iter().enumerate() {
+        let duration = record
+            .duration_ms
+            .map(|ms| format!(" · {}ms", ms))
+            .unwrap_or_default();
+        out.push_str(&format!(
+            "\n{}. {} · {}{}\n   args: {}",
+            first_index + i + 1,
+            record.name,
+            record.status,
+            duration,
+            crate::utils::truncate_chars(&record.args, 120)
+        ));
+        let output = record.output.lines().next().unwrap_or("").trim();
+        if !output.is_empty() {
+            out.push_str(&format!(
+                "\n   output: {}",
+                crate::utils::truncate_chars(output, 120)
+            ));
+        }
+    }
+    out
+}
+
+pub fn handle_tools_log<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let args = parsed.args.clone();
+
+    Box::pin(async move {
+        let limit = match args.first().map(|a| a.parse::<usize>()) {
+            None => TOOLS_LOG_DEFAULT_LIMIT,
+            Some(Ok(n)) if n > 0 => n,
+            Some(_) => return CommandResult::Error("Usage: /tools-log [<count>]".to_string()),
+        };
+        let Some(id) = sm.get_current_session_id().cloned() else {
+            return CommandResult::Error("No active session".to_string());
+        };
+
+        let records = sm.tool_calls(&id);
+        if records.is_empty() {
+            push_toast(crate::ui::toast::new_toast(
+                "No tool calls in this session",
+                ratatui_toolkit::ToastLevel::Info,
+                Some(std::time::Duration::from_secs(3)),
+            ));
+            return CommandResult::Success(String::new());
+        }
+
+        let recent = &records[records.len().saturating_sub(limit)..];
+        CommandResult::Success(format_tool_calls(recent, records.len()))
+    })
+}
+
+pub fn format_scroll_preferences(prefs: &crate::persistence::ScrollPreferences) -> String {
+    let on_off = |b: bool| if b { "on" } else { "off" };
+    format!(
+        "Scroll: {} lines, acceleration {}, smooth {}",
+        prefs.step,
+        on_off(prefs.accelerate),
+        on_off(prefs.smooth)
+    )
+}
+
+fn apply_scroll_command(dao: &crate::persistence::PrefsDAO, args: &[String]) -> CommandResult {
+    use crate::persistence::ScrollPreferences;
+
+    let usage = || {
+        CommandResult::Error(format!(
+            "Usage: /scroll [<1-{}> | smooth [on|off] | accel on|off]",
+            ScrollPreferences::MAX_STEP
+        ))
+    };
+    let parse_toggle = |value: &str| match value {
+        "on" => Some(true),
+        "off" => Some(false),
+        _ => None,
+    };
+
+    let mut prefs = dao.get_scroll_preferences().unwrap_or_default();
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+    match args.as_slice() {
+        [] => {}
+        ["smooth"] => prefs.smooth = !prefs.smooth,
+        ["smooth", value] => match parse_toggle(value) {
+            Some(enabled) => prefs.smooth = enabled,
+            None => return usage(),
+        },
+        ["accel", value] => match parse_toggle(value) {
+            Some(enabled) => prefs.accelerate = enabled,
+            None => return usage(),
+        },
+        [lines] => match lines.parse::<usize>() {
+            Ok(n) if (1..=ScrollPreferences::MAX_STEP).contains(&n) => prefs.step = n,
+            _ => return usage(),
+        },
+        _ => return usage(),
+    }
+
+    if !args.is_empty() {
+        if let Err(e) = dao.set_scroll_preferences(&prefs) {
+            return CommandResult::Error(format!("Failed to save scroll settings: {}", e));
+        }
+    }
+
+    push_toast(crate::ui::toast::new_toast(
+        format_scroll_preferences(&prefs),
+        ratatui_toolkit::ToastLevel::Info,
+        Some(std::time::Duration::from_secs(3)),
+    ));
+    CommandResult::Success(String::new())
+}
+
+pub fn handle_scroll<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    // PrefsDAO isn't Sync, so do the work before building the future
+    let result = match parsed.prefs_dao {
+        Some(dao) => apply_scroll_command(dao, &parsed.args),
+        None => CommandResult::Error("Preferences are unavailable".to_string()),
+    };
+
+    Box::pin(async move { result })
+}
+
+fn apply_recent_command(dao: &crate::persistence::PrefsDAO, args: &[String]) -> CommandResult {
+    use crate::persistence::prefs::ModelPreferences;
+
+    let usage = || {
+        CommandResult::Error(format!(
+            "Usage: /recent <1-{}>",
+            ModelPreferences::MAX_RECENT_LIMIT
+        ))
+    };
+
+    let saved = match args {
+        [] => dao
+            .get_model_preferences()
+            .map(|prefs| prefs.effective_recent_limit()),
+        [value] => match value.parse::<usize>() {
+            Ok(n) if (1..=ModelPreferences::MAX_RECENT_LIMIT).contains(&n) => {
+                dao.set_recent_limit(n)
+            }
+            _ => return usage(),
+        },
+        _ => return usage(),
+    };
+    let limit = match saved {
+        Ok(limit) => limit,
+        Err(e) => {
+            return CommandResult::Error(format!("Failed to save recent models limit: {}", e))
+        }
+    };
+
+    push_toast(crate::ui::toast::new_toast(
+        format!("Recent models: keeping {}", limit),
+        ratatui_toolkit::ToastLevel::Info,
+        Some(std::time::Duration::from_secs(3)),
+    ));
+    CommandResult::Success(String::new())
+}
+
+pub fn handle_recent<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let result = match parsed.prefs_dao {
+        Some(dao) => apply_recent_command(dao, &parsed.args),
+        None => CommandResult::Error("Preferences are unavailable".to_string()),
+    };
+
+    Box::pin(async move { result })
+}
+
+fn apply_tee_command(dao: &crate::persistence::PrefsDAO, args: &[String]) -> CommandResult {
+    let enabled = match args {
+        [] => match dao.get_tee_output() {
+            Ok(enabled) => !enabled,
+            Err(e) => return CommandResult::Error(format!("Failed to read tee setting: {}", e)),
+        },
+        [arg] if arg == "on" => true,
+        [arg] if arg == "off" => false,
+        _ => return CommandResult::Error("Usage: /tee [on|off]".to_string()),
+    };
+    if let Err(e) = dao.set_tee_output(enabled) {
+        return CommandResult::Error(format!("Failed to save tee setting: {}", e));
+    }
+
+    let message = if enabled {
+        "Tee on: turns are also written to the transcripts directory"
+    } else {
+        "Tee off"
+    };
+    push_toast(crate::ui::toast::new_toast(
+        message,
+        ratatui_toolkit::ToastLevel::Info,
+        Some(std::time::Duration::from_secs(3)),
+    ));
+    CommandResult::Success(String::new())
+}
+
+pub fn handle_tee<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let result = match parsed.prefs_dao {
+        Some(dao) => apply_tee_command(dao, &parsed.args),
+        None => CommandResult::Error("Preferences are unavailable".to_string()),
+    };
+
+    Box::pin(async move { result })
+}
+
+/// Shows or sets one sampling value of the current session for `/temp` and
+/// `/top-p`; `off` goes back to the provider's default.
+fn apply_sampling_command(
+    sm: &mut SessionManager,
+    args: &[String],
+    command: &str,
+    label: &str,
+    max: f32,
+    field: fn(&mut crate::session::types::SamplingParams) -> &mut Option<f32>,
+) -> CommandResult {
+    let usage = || CommandResult::Error(format!("Usage: /{} [<0-{}> | off]", command, max));
+    let Some(id) = sm.get_current_session_id().cloned() else {
+        return CommandResult::Error("No active session".to_string());
+    };
+
+    let mut sampling = sm.current_sampling();
+    let value = match args {
+        [] => *field(&mut sampling),
+        [arg] if arg == "off" => None,
+        [arg] => match arg.parse::<f32>() {
+            Ok(value) if (0.0..=max).contains(&value) => Some(value),
+            _ => return usage(),
+        },
+        _ => return usage(),
+    };
+    if !args.is_empty() {
+        *field(&mut sampling) = value;
+        if let Err(e) = sm.set_sampling(&id, sampling) {
+            return CommandResult::Error(format!("Failed to save {}: {}", command, e));
+        }
+    }
+
+    let shown = value.map_or_else(|| "provider default".to_string(), |v| v.to_string());
+    push_toast(crate::ui::toast::new_toast(
+        format!("{}: {} for this session", label, shown),
+        ratatui_toolkit::ToastLevel::Info,
+        Some(std::time::Duration::from_secs(3)),
+    ));
+    CommandResult::Success(String::new())
+}
+
+pub fn handle_temp<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    use crate::session::types::SamplingParams;
+
+    let result = apply_sampling_command(
+        sm,
+        &parsed.args,
+        "temp",
+        "Temperature",
+        SamplingParams::MAX_TEMPERATURE,
+        |s| &mut s.temperature,
+    );
+
+    Box::pin(async move { result })
+}
+
+pub fn handle_top_p<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    use crate::session::types::SamplingParams;
+
+    let result = apply_sampling_command(
+        sm,
+        &parsed.args,
+        "top-p",
+        "Top-p",
+        SamplingParams::MAX_TOP_P,
+        |s| &mut s.top_p,
+    );
+
+    Box::pin(async move { result })
+}
+
+fn apply_seed_command(sm: &mut SessionManager, args: &[String]) -> CommandResult {
+    let Some(id) = sm.get_current_session_id().cloned() else {
+        return CommandResult::Error("No active session".to_string());
+    };
+
+    let mut sampling = sm.current_sampling();
+    let seed = match args {
+        [] => sampling.seed,
+        [arg] if arg == "off" => None,
+        [arg] => match arg.parse::<u32>() {
+            Ok(seed) => Some(seed),
+            Err(_) => return CommandResult::Error("Usage: /seed [<number> | off]".to_string()),
+        },
+        _ => return CommandResult::Error("Usage: /seed [<number> | off]".to_string()),
+    };
+    if !args.is_empty() {
+        sampling.seed = seed;
+        if let Err(e) = sm.set_sampling(&id, sampling) {
+            return CommandResult::Error(format!("Failed to save seed: {}", e));
+        }
+    }
+
+    let message = match seed {
+        Some(seed) => format!("Seed: {} for this session", seed),
+        None => "Seed: off for this session".to_string(),
+    };
+    push_toast(crate::ui::toast::new_toast(
+        message,
+        ratatui_toolkit::ToastLevel::Info,
+        Some(std::time::Duration::from_secs(3)),
+    ));
+    CommandResult::Success(String::new())
+}
+
+pub fn handle_seed<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let result = apply_seed_command(sm, &parsed.args);
+
+    Box::pin(async move { result })
+}
+
+fn apply_effort_command(sm: &mut SessionManager, args: &[String]) -> CommandResult {
+    use crate::session::types::ReasoningEffort;
+
+    let Some(id) = sm.get_current_session_id().cloned() else {
+        return CommandResult::Error("No active session".to_string());
+    };
+
+    let mut sampling = sm.current_sampling();
+    let effort = match args {
+        [] => sampling.effort,
+        [arg] if arg == "off" => None,
+        [arg] => match ReasoningEffort::parse(arg) {
+            Some(effort) => Some(effort),
+            None => {
+                return CommandResult::Error(
+                    "Usage: /effort [low | medium | high | off]".to_string(),
+                )
+            }
+        },
+        _ => return CommandResult::Error("Usage: /effort [low | medium | high | off]".to_string()),
+    };
+    if !args.is_empty() {
+        sampling.effort = effort;
+        if let Err(e) = sm.set_sampling(&id, sampling) {
+            return CommandResult::Error(format!("Failed to save effort: {}", e));
+        }
+    }
+
+    let message = match effort {
+        Some(effort) => format!("Reasoning effort: {} for this session", effort.label()),
+        None => "Reasoning effort: model default for this session".to_string(),
+    };
+    push_toast(crate::ui::toast::new_toast(
+        message,
+        ratatui_toolkit::ToastLevel::Info,
+        Some(std::time::Duration::from_secs(3)),
+    ));
+    CommandResult::Success(String::new())
+}
+
+pub fn handle_effort<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let result = apply_effort_command(sm, &parsed.args);
+
+    Box::pin(async move { result })
+}
+
+/// `tail` is split here rather than taken from `args` so an unclosed quote
+/// is an error instead of becoming part of a stop sequence.
+fn apply_stop_command(sm: &mut SessionManager, tail: &str) -> CommandResult {
+    use crate::session::types::SamplingParams;
+
+    let Some(id) = sm.get_current_session_id().cloned() else {
+        return CommandResult::Error("No active session".to_string());
+    };
+    let args = match split_args(tail) {
+        Ok(args) => args,
+        Err(e) => return CommandResult::Error(e),
+    };
+
+    let mut sampling = sm.current_sampling();
+    let changed = match args.as_slice() {
+        [] => false,
+        [arg] if arg == "clear" || arg == "off" => {
+            sampling.stop.clear();
+            true
+        }
+        _ => {
+            if args.iter().any(|a| a.is_empty()) {
+                return CommandResult::Error("Stop sequences can't be empty".to_string());
+            }
+            let mut stops: Vec<String> = Vec::new();
+            for arg in args {
+                if !stops.contains(&arg) {
+                    stops.push(arg);
+                }
+            }
+            if stops.len() > SamplingParams::MAX_STOP_SEQUENCES {
+                return CommandResult::Error(format!(
+                    "At most {} stop sequences",
+                    SamplingParams::MAX_STOP_SEQUENCES
+                ));
+            }
+            sampling.stop = stops;
+            true
+        }
+    };
+
+    let message = if sampling.stop.is_empty() {
+        "No stop sequences for this session".to_string()
+    } else {
+        let quoted: Vec<String> = sampling.stop.iter().map(|s| format!("{:?}", s)).collect();
+        format!("Stop sequences: {}", quoted.join(", "))
+    };
+    if changed {
+        if let Err(e) = sm.set_sampling(&id, sampling) {
+            return CommandResult::Error(format!("Failed to save stop sequences: {}", e));
+        }
+    }
+
+    push_toast(crate::ui::toast::new_toast(
+        message,
+        ratatui_toolkit::ToastLevel::Info,
+        Some(std::time::Duration::from_secs(3)),
+    ));
+    CommandResult::Success(String::new())
+}
+
+pub fn handle_stop<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let result = apply_stop_command(sm, parsed.tail());
+
+    Box::pin(async move { result })
+}
+
+fn apply_notify_command(dao: &crate::persistence::PrefsDAO, args: &[String]) -> CommandResult {
+    use crate::utils::notify::DEFAULT_NOTIFY_AFTER_SECS;
+
+    let secs = match args {
+        [] => match dao.get_notify_after_secs() {
+            Ok(0) => DEFAULT_NOTIFY_AFTER_SECS,
+            Ok(_) => 0,
+            Err(e) => return CommandResult::Error(format!("Failed to read notify setting: {}", e)),
+        },
+        [arg] if arg == "off" => 0,
+        [arg] => match arg.parse::<u64>() {
+            Ok(secs) if secs > 0 => secs,
+            _ => return CommandResult::Error("Usage: /notify [<seconds>|off]".to_string()),
+        },
+        _ => return CommandResult::Error("Usage: /notify [<seconds>|off]".to_string()),
+    };
+    if let Err(e) = dao.set_notify_after_secs(secs) {
+        return CommandResult::Error(format!("Failed to save notify setting: {}", e));
+    }
+
+    let message = if secs == 0 {
+        "Notifications off".to_string()
+    } else {
+        format!("Notifying when a turn takes {}s or longer", secs)
+    };
+    push_toast(crate::ui::toast::new_toast(
+        message,
+        ratatui_toolkit::ToastLevel::Info,
+        Some(std::time::Duration::from_secs(3)),
+    ));
+    CommandResult::Success(String::new())
+}
+
+pub fn handle_notify<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let result = match parsed.prefs_dao {
+        Some(dao) => apply_notify_command(dao, &parsed.args),
+        None => CommandResult::Error("Preferences are unavailable".to_string()),
+    };
+
+    Box::pin(async move { result })
+}
+
+fn apply_draft_command(dao: &crate::persistence::PrefsDAO, args: &[String]) -> CommandResult {
+    let enabled = match args {
+        [] => match dao.get_draft_autosave() {
+            Ok(enabled) => !enabled,
+            Err(e) => return CommandResult::Error(format!("Failed to read draft setting: {}", e)),
+        },
+        [arg] if arg == "on" => true,
+        [arg] if arg == "off" => false,
+        _ => return CommandResult::Error("Usage: /draft [on|off]".to_string()),
+    };
+    if let Err(e) = dao.set_draft_autosave(enabled) {
+        return CommandResult::Error(format!("Failed to save draft setting: {}", e));
+    }
+
+    let message = if enabled {
+        "Drafts on: unsent input is kept per session"
+    } else {
+        "Drafts off"
+    };
+    push_toast(crate::ui::toast::new_toast(
+        message,
+        ratatui_toolkit::ToastLevel::Info,
+        Some(std::time::Duration::from_secs(3)),
+    ));
+    CommandResult::Success(String::new())
+}
+
+pub fn handle_draft<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let result = match parsed.prefs_dao {
+        Some(dao) => apply_draft_command(dao, &parsed.args),
+        None => CommandResult::Error("Preferences are unavailable".to_string()),
+    };
+
+    Box::pin(async move { result })
+}
+
+fn apply_quit_guard_command(dao: &crate::persistence::PrefsDAO, args: &[String]) -> CommandResult {
+    let enabled = match args {
+        [] => match dao.get_quit_guard() {
+            Ok(enabled) => !enabled,
+            Err(e) => {
+                return CommandResult::Error(format!("Failed to read quit guard setting: {}", e))
+            }
+        },
+        [arg] if arg == "on" => true,
+        [arg] if arg == "off" => false,
+        _ => return CommandResult::Error("Usage: /quit-guard [on|off]".to_string()),
+    };
+    if let Err(e) = dao.set_quit_guard(enabled) {
+        return CommandResult::Error(format!("Failed to save quit guard setting: {}", e));
+    }
+
+    let message = if enabled {
+        "Quit guard on: Ctrl+C twice stops a response before quitting"
+    } else {
+        "Quit guard off: Ctrl+C twice always quits"
+    };
+    push_toast(crate::ui::toast::new_toast(
+        message,
+        ratatui_toolkit::ToastLevel::Info,
+        Some(std::time::Duration::from_secs(3)),
+    ));
+    CommandResult::Success(String::new())
+}
+
+pub fn handle_quit_guard<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let result = match parsed.prefs_dao {
+        Some(dao) => apply_quit_guard_command(dao, &parsed.args),
+        None => CommandResult::Error("Preferences are unavailable".to_string()),
+    };
+
+    Box::pin(async move { result })
+}
+
+/// `typed_prefix` is the one this command was typed with, for the usage
+/// message.
+fn apply_command_prefix_command(
+    dao: &crate::persistence::PrefsDAO,
+    args: &[String],
+    typed_prefix: &str,
+) -> CommandResult {
+    use crate::command::parser::{is_valid_prefix, DEFAULT_PREFIX, MAX_PREFIX_CHARS};
+
+    let prefix = match args {
+        [] => match dao.get_command_prefix() {
+            Ok(prefix) => {
+                push_toast(crate::ui::toast::new_toast(
+                    format!("Commands start with {}", prefix),
+                    ratatui_toolkit::ToastLevel::Info,
+                    Some(std::time::Duration::from_secs(3)),
+                ));
+                return CommandResult::Success(String::new());
+            }
+            Err(e) => {
+                return CommandResult::Error(format!("Failed to read command prefix: {}", e))
+            }
+        },
+        [arg] if arg == "reset" => DEFAULT_PREFIX,
+        [arg] if is_valid_prefix(arg) => arg.as_str(),
+        [_] => {
+            return CommandResult::Error(format!(
+                "A prefix is 1 to {} characters, without letters, digits, spaces, quotes or backslashes",
+                MAX_PREFIX_CHARS
+            ))
+        }
+        _ => {
+            return CommandResult::Error(format!(
+                "Usage: {}command-prefix [<prefix>|reset]",
+                typed_prefix
+            ))
+        }
+    };
+    if let Err(e) = dao.set_command_prefix(prefix) {
+        return CommandResult::Error(format!("Failed to save command prefix: {}", e));
+    }
+
+    push_toast(crate::ui::toast::new_toast(
+        format!("Commands now start with {}", prefix),
+        ratatui_toolkit::ToastLevel::Info,
+        Some(std::time::Duration::from_secs(3)),
+    ));
+    CommandResult::Success(String::new())
+}
+
+pub fn handle_command_prefix<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let result = match parsed.prefs_dao {
+        Some(dao) => apply_command_prefix_command(dao, &parsed.args, parsed.prefix()),
+        None => CommandResult::Error("Preferences are unavailable".to_string()),
+    };
+
+    Box::pin(async move { result })
+}
+
+fn apply_paste_command(
+    dao: Option<&crate::persistence::PrefsDAO>,
+    args: &[String],
+) -> CommandResult {
+    use crate::command::registry::PasteAction;
+
+    let usage = || CommandResult::Error("Usage: /paste [remove [n] | <lines> | off]".to_string());
+
+    let lines = match args {
+        [] => return CommandResult::PastedContent(PasteAction::List),
+        [cmd] if cmd == "remove" => return CommandResult::PastedContent(PasteAction::Remove(None)),
+        [cmd, n] if cmd == "remove" => {
+            return match n.parse::<usize>() {
+                Ok(n) if n > 0 => CommandResult::PastedContent(PasteAction::Remove(Some(n - 1))),
+                _ => usage(),
+            }
+        }
+        [value] if value == "off" => 0,
+        [value] => match value.parse::<usize>() {
+            Ok(lines) if lines > 0 => lines,
+            _ => return usage(),
+        },
+        _ => return usage(),
+    };
+
+    let Some(dao) = dao else {
+        return CommandResult::Error("Preferences are unavailable".to_string());
+    };
+    if let Err(e) = dao.set_paste_attach_lines(lines) {
+        return CommandResult::Error(format!("Failed to save paste threshold: {}", e));
+    }
+
+    let message = if lines == 0 {
+        "Pastes are always inserted as text".to_string()
+    } else {
+        format!(
+            "Pastes of {}+ lines are attached instead of inserted",
+            lines
+        )
+    };
+    push_toast(crate::ui::toast::new_toast(
+        message,
+        ratatui_toolkit::ToastLevel::Info,
+        Some(std::time::Duration::from_secs(3)),
+    ));
+    CommandResult::Success(String::new())
+}
+
+pub fn handle_paste<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let result = apply_paste_command(parsed.prefs_dao, &parsed.args);
+
+    Box::pin(async move { result })
+}
+
+pub fn handle_reload<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let args = parsed.args.clone();
+
+    Box::pin(async move {
+        if args.is_empty() {
+            CommandResult::Reload
+        } else {
+            CommandResult::Error("Usage: /reload".to_string())
+        }
+    })
+}
+
+pub fn handle_notifications<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let args = parsed.args.clone();
+
+    Box::pin(async move {
+        if args.is_empty() {
+            CommandResult::ShowNotifications
+        } else {
+            CommandResult::Error("Usage: /notifications".to_string())
+        }
+    })
+}
+
+pub fn handle_debug<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let args = parsed.args.clone();
+
+    Box::pin(async move {
+        match args.as_slice() {
+            [arg] if arg == "last" => CommandResult::DebugLastTurn,
+            _ => CommandResult::Error("Usage: /debug last".to_string()),
+        }
+    })
+}
+
+fn apply_tone_command(dao: &crate::persistence::PrefsDAO, args: &[String]) -> CommandResult {
+    use crate::persistence::Tone;
+
+    let usage = || {
+        let names: Vec<&str> = Tone::ALL.iter().map(|tone| tone.label()).collect();
+        CommandResult::Error(format!("Usage: /tone [{}]", names.join(" | ")))
+    };
+
+    let tone = match args {
+        [] => dao.get_tone().unwrap_or_default(),
+        [value] => match Tone::parse(value) {
+            Some(tone) => tone,
+            None => return usage(),
+        },
+        _ => return usage(),
+    };
+
+    if !args.is_empty() {
+        if let Err(e) = dao.set_tone(tone) {
+            return CommandResult::Error(format!("Failed to save tone: {}", e));
+        }
+    }
+
+    push_toast(crate::ui::toast::new_toast(
+        format!("Tone: {}", tone.label()),
+        ratatui_toolkit::ToastLevel::Info,
+        Some(std::time::Duration::from_secs(3)),
+    ));
+    CommandResult::Success(String::new())
+}
+
+pub fn handle_tone<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let result = match parsed.prefs_dao {
+        Some(dao) => apply_tone_command(dao, &parsed.args),
+        None => CommandResult::Error("Preferences are unavailable".to_string()),
+    };
+
+    Box::pin(async move { result })
+}
+
+fn apply_time_format_command(dao: &crate::persistence::PrefsDAO, args: &[String]) -> CommandResult {
+    let usage = || CommandResult::Error("Usage: /time-format [12h | 24h | relative]".to_string());
+
+    let format = match args {
+        [] => dao.get_time_format().unwrap_or_default(),
+        [value] => match TimeFormat::parse(value) {
+            Some(format) => format,
+            None => return usage(),
+        },
+        _ => return usage(),
+    };
+
+    if !args.is_empty() {
+        if let Err(e) = dao.set_time_format(format) {
+            return CommandResult::Error(format!("Failed to save time format: {}", e));
+        }
+    }
+
+    push_toast(crate::ui::toast::new_toast(
+        format!("Time format: {}", format.label()),
+        ratatui_toolkit::ToastLevel::Info,
+        Some(std::time::Duration::from_secs(3)),
+    ));
+    CommandResult::Success(String::new())
+}
+
+pub fn handle_time_format<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let result = match parsed.prefs_dao {
+        Some(dao) => apply_time_format_command(dao, &parsed.args),
+        None => CommandResult::Error("Preferences are unavailable".to_string()),
+    };
+
+    Box::pin(async move { result })
+}
+
+fn apply_color_command(dao: &crate::persistence::PrefsDAO, args: &[String]) -> CommandResult {
+    use crate::persistence::ColorMode;
+
+    let usage = || CommandResult::Error("Usage: /color [on | off | high-contrast]".to_string());
+
+    let mode = match args {
+        [] => dao.get_color_mode().unwrap_or_default(),
+        [value] => match ColorMode::parse(value) {
+            Some(mode) => mode,
+            None => return usage(),
+        },
+        _ => return usage(),
+    };
+
+    if !args.is_empty() {
+        if let Err(e) = dao.set_color_mode(mode) {
+            return CommandResult::Error(format!("Failed to save color mode: {}", e));
+        }
+    }
+
+    push_toast(crate::ui::toast::new_toast(
+        format!("Color mode: {}", mode.label()),
+        ratatui_toolkit::ToastLevel::Info,
+        Some(std::time::Duration::from_secs(3)),
+    ));
+    CommandResult::Success(String::new())
+}
+
+pub fn handle_color<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let result = match parsed.prefs_dao {
+        Some(dao) => apply_color_command(dao, &parsed.args),
+        None => CommandResult::Error("Preferences are unavailable".to_string()),
+    };
+
+    Box::pin(async move { result })
+}
+
+fn apply_density_command(dao: &crate::persistence::PrefsDAO, args: &[String]) -> CommandResult {
+    use crate::persistence::Density;
+
+    let usage =
+        || CommandResult::Error("Usage: /density [comfortable | compact | toggle]".to_string());
+
+    let current = dao.get_density().unwrap_or_default();
+    let density = match args {
+        [] => current,
+        [value] if value == "toggle" => current.toggled(),
+        [value] => match Density::parse(value) {
+            Some(density) => density,
+            None => return usage(),
+        },
+        _ => return usage(),
+    };
+
+    if !args.is_empty() {
+        if let Err(e) = dao.set_density(density) {
+            return CommandResult::Error(format!("Failed to save density: {}", e));
+        }
+    }
+
+    push_toast(crate::ui::toast::new_toast(
+        format!("Density: {}", density.label()),
+        ratatui_toolkit::ToastLevel::Info,
+        Some(std::time::Duration::from_secs(3)),
+    ));
+    CommandResult::Success(String::new())
+}
+
+pub fn handle_density<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let result = match parsed.prefs_dao {
+        Some(dao) => apply_density_command(dao, &parsed.args),
+        None => CommandResult::Error("Preferences are unavailable".to_string()),
+    };
+
+    Box::pin(async move { result })
+}
+
+fn apply_input_history_command(
+    dao: &crate::persistence::PrefsDAO,
+    args: &[String],
+) -> CommandResult {
+    use crate::persistence::HistoryScope;
+
+    let usage =
+        || CommandResult::Error("Usage: /input-history [project | global | toggle]".to_string());
+
+    let current = dao.get_history_scope().unwrap_or_default();
+    let scope = match args {
+        [] => current,
+        [value] if value == "toggle" => current.toggled(),
+        [value] => match HistoryScope::parse(value) {
+            Some(scope) => scope,
+            None => return usage(),
+        },
+        _ => return usage(),
+    };
+
+    if !args.is_empty() {
+        if let Err(e) = dao.set_history_scope(scope) {
+            return CommandResult::Error(format!("Failed to save input history scope: {}", e));
+        }
+    }
+
+    let message = match scope {
+        HistoryScope::Project => "Input history: prompts sent from this directory",
+        HistoryScope::Global => "Input history: prompts sent from anywhere",
+    };
+    push_toast(crate::ui::toast::new_toast(
+        message,
+        ratatui_toolkit::ToastLevel::Info,
+        Some(std::time::Duration::from_secs(3)),
+    ));
+    CommandResult::Success(String::new())
+}
+
+pub fn handle_input_history<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let result = match parsed.prefs_dao {
+        Some(dao) => apply_input_history_command(dao, &parsed.args),
+        None => CommandResult::Error("Preferences are unavailable".to_string()),
+    };
+
+    Box::pin(async move { result })
+}
+
+fn apply_markdown_command(dao: &crate::persistence::PrefsDAO, args: &[String]) -> CommandResult {
+    use crate::persistence::MarkdownRendererKind;
+
+    let kind = match args {
+        [] => dao.get_markdown_renderer().unwrap_or_default(),
+        [value] => match MarkdownRendererKind::parse(value) {
+            Some(kind) => kind,
+            None => {
+                return CommandResult::Error(
+                    "Usage: /markdown [tui-markdown | builtin]".to_string(),
+                )
+            }
+        },
+        _ => return CommandResult::Error("Usage: /markdown [tui-markdown | builtin]".to_string()),
+    };
+
+    if !args.is_empty() {
+        if let Err(e) = dao.set_markdown_renderer(kind) {
+            return CommandResult::Error(format!("Failed to save markdown renderer: {}", e));
+        }
+    }
+
+    push_toast(crate::ui::toast::new_toast(
+        format!("Markdown renderer: {}", kind.label()),
+        ratatui_toolkit::ToastLevel::Info,
+        Some(std::time::Duration::from_secs(3)),
+    ));
+    CommandResult::Success(String::new())
+}
+
+pub fn handle_markdown<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let result = match parsed.prefs_dao {
+        Some(dao) => apply_markdown_command(dao, &parsed.args),
+        None => CommandResult::Error("Preferences are unavailable".to_string()),
+    };
+
+    Box::pin(async move { result })
+}
+
+/// Resolves `target` against the current working directory `cwd`, expanding
+/// a leading `~`, and checks that it is an existing directory.
+pub fn resolve_working_dir(cwd: &str, target: &str) -> Result<String, String> {
+    let expanded = match target.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            let home = dirs::home_dir().ok_or("Can't find the home directory")?;
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(target),
+    };
+    let path = Path::new(cwd).join(expanded);
+
+    let path = path
+        .canonicalize()
+        .map_err(|_| format!("No such directory: {}", target))?;
+    if !path.is_dir() {
+        return Err(format!("Not a directory: {}", target));
+    }
+    Ok(path.to_string_lossy().to_string())
+}
+
+pub fn handle_cd<'a>(
+    parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let target = parsed.args.join(" ");
+
+    Box::pin(async move {
+        if target.is_empty() {
+            return CommandResult::Error("Usage: /cd <path>".to_string());
+        }
+        CommandResult::ChangeDirectory(target)
+    })
+}
+
+/// Whether there's a desktop to open a file manager on: not over SSH, in
+/// tests, or on Linux without a display server.
+fn has_file_manager() -> bool {
+    if cfg!(test)
+        || std::env::var("CRABCODE_TEST_MODE").is_ok()
+        || std::env::var_os("SSH_CONNECTION").is_some()
+    {
+        return false;
+    }
+    if cfg!(any(target_os = "macos", target_os = "windows")) {
+        return true;
+    }
+    ["DISPLAY", "WAYLAND_DISPLAY"]
+        .iter()
+        .any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty()))
+}
+
+/// Opens `path` with the OS default handler, or just names it when that
+/// isn't possible. Returns the message to show.
+fn reveal_path(what: &str, path: &Path) -> String {
+    if has_file_manager() && open::that_detached(path).is_ok() {
+        format!("Opened {}: {}", what.to_lowercase(), path.display())
+    } else {
+        format!("{}: {}", what, path.display())
+    }
+}
+
+fn open_path_result(what: &str, path: &Path) -> CommandResult {
+    push_toast(crate::ui::toast::new_toast(
+        reveal_path(what, path),
+        ratatui_toolkit::ToastLevel::Info,
+        Some(std::time::Duration::from_secs(8)),
+    ));
+    CommandResult::Success(String::new())
+}
+
+pub fn handle_open_data<'a>(
+    _parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let result = match crate::persistence::ensure_data_dir() {
+        Ok(()) => open_path_result("Data directory", &crate::persistence::get_data_dir()),
+        Err(e) => CommandResult::Error(format!("Failed to create data directory: {}", e)),
+    };
+
+    Box::pin(async move { result })
+}
+
+pub fn handle_open_cache<'a>(
+    _parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let result = match crate::persistence::ensure_cache_dir() {
+        Ok(()) => open_path_result("Cache directory", &crate::persistence::get_cache_dir()),
+        Err(e) => CommandResult::Error(format!("Failed to create cache directory: {}", e)),
+    };
+
+    Box::pin(async move { result })
+}
+
+pub fn handle_open_log<'a>(
+    _parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    let path = crate::logging::log_path();
+    let result = if path.exists() {
+        open_path_result("Log file", &path)
+    } else {
+        CommandResult::Error(format!("No log file yet at {}", path.display()))
+    };
+
+    Box::pin(async move { result })
+}
+
+/// Where crabcode keeps things and how it reaches the network.
+pub fn format_doctor_report() -> String {
+    let http = crate::utils::http::settings();
+    let frame_rate = crate::utils::frame_rate::settings();
+    let toasts = crate::ui::toast::settings();
+    [
+        format!("crabcode {}", env!("CARGO_PKG_VERSION")),
+        format!("Proxy: {}", http.proxy_summary()),
+        format!(
+            "HTTP: {}s timeout, {}s to connect, {} retries",
+            http.timeout.as_secs(),
+            http.connect_timeout.as_secs(),
+            http.retries
+        ),
+        format!(
+            "Frame rate: {} fps while streaming, {}ms input poll when idle",
+            frame_rate.max_fps,
+            frame_rate.idle_poll.as_millis()
+        ),
+        format!(
+            "Toasts: {}ms success, {}ms info, {}ms warning, {}ms error",
+            toasts.success.as_millis(),
+            toasts.info.as_millis(),
+            toasts.warning.as_millis(),
+            toasts.error.as_millis()
+        ),
+        format!(
+            "Data directory: {}",
+            crate::persistence::get_data_dir().display()
+        ),
+        format!(
+            "Cache directory: {}",
+            crate::persistence::get_cache_dir().display()
+        ),
+        format!("Log file: {}", crate::logging::log_path().display()),
+    ]
+    .join("\n")
+}
+
+pub fn handle_doctor<'a>(
+    _parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    Box::pin(async move { CommandResult::Success(format_doctor_report()) })
+}
+
+pub fn handle_refreshmodels<'a>(
+    _parsed: &'a ParsedCommand<'a>,
+    _sm: &'a mut SessionManager,
+) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+    Box::pin(async move {
+        let discovery = match crate::model::discovery::Discovery::new() {
+            Ok(d) => d,
+            Err(e) => {
+                push_toast(crate::ui::toast::new_toast(
+                    format!("Failed to initialize model discovery: {}", e),
+                    ratatui_toolkit::ToastLevel::Error,
+                    Some(std::time::Duration::from_secs(3)),
+                ));
+                return CommandResult::Success(String::new());
+            }
+        };
+
+        let providers = match discovery.refresh_cache().await {
+            Ok(p) => p,
+            Err(e) => {
+                push_toast(crate::ui::toast::new_toast(
+                    format!("Failed to refresh models cache: {}", e),
+                    ratatui_toolkit::ToastLevel::Error,
+                    Some(std::time::Duration::from_secs(3)),
+                ));
+                return CommandResult::Success(String::new());
+            }
+        };
+
+        let provider_count = providers.len();
+        let model_count: usize = providers.values().map(|p| p.models.len()).sum();
+
+        push_toast(crate::ui::toast::new_toast(
+            format!(
+                "Models cache refreshed: {} providers, {} models",
+                provider_count, model_count
+            ),
+            ratatui_toolkit::ToastLevel::Info,
+            Some(std::time::Duration::from_secs(3)),
+        ));
+
+        CommandResult::Success(String::new())
+    })
+}
+
+pub fn register_all_commands(registry: &mut Registry) {
+    registry.register(Command {
+        name: "exit".to_string(),
+        description: "Quit crabcode".to_string(),
+        handler: handle_exit,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "sessions".to_string(),
+        description: "List sessions (clear, prune <days>)".to_string(),
+        handler: handle_sessions,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "new".to_string(),
+        description: "Switch to home screen".to_string(),
+        handler: handle_new,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "home".to_string(),
+        description: "Switch to home screen".to_string(),
+        handler: handle_new,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "connect".to_string(),
+        description: "Connect to a model provider".to_string(),
+        handler: handle_connect,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "models".to_string(),
+        description: "List available models".to_string(),
+        handler: handle_models,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "refreshmodels".to_string(),
+        description: "Refresh the models.dev cache".to_string(),
+        handler: handle_refreshmodels,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "pin".to_string(),
+        description: "Pin or unpin the current session".to_string(),
+        handler: handle_pin,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "scroll".to_string(),
+        description: "Set mouse scroll speed (<lines>, smooth, accel on|off)".to_string(),
+        handler: handle_scroll,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "recent".to_string(),
+        description: "Set how many recent models /models keeps".to_string(),
+        handler: handle_recent,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "tools-log".to_string(),
+        description: "Review tool calls made in this session".to_string(),
+        handler: handle_tools_log,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "color".to_string(),
+        description: "Set color mode (on, off, high-contrast)".to_string(),
+        handler: handle_color,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "density".to_string(),
+        description: "Set chat spacing (comfortable, compact, toggle)".to_string(),
+        handler: handle_density,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "markdown".to_string(),
+        description: "Set the markdown renderer (tui-markdown, builtin)".to_string(),
+        handler: handle_markdown,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "tone".to_string(),
+        description: "Set the reply tone (default, concise, verbose, teaching, terse)".to_string(),
+        handler: handle_tone,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "time-format".to_string(),
+        description: "Show times as 12h, 24h or relative (5m ago)".to_string(),
+        handler: handle_time_format,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "tag".to_string(),
+        description: "Tag the current session (remove <label> to untag)".to_string(),
+        handler: handle_tag,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "branch".to_string(),
+        description: "Continue from the nth-latest reply in a new session".to_string(),
+        handler: handle_branch,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "redo".to_string(),
+        description: "Send the last prompt again in a new session".to_string(),
+        handler: handle_redo,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "bookmarks".to_string(),
+        description: "List bookmarked messages, or jump to one".to_string(),
+        handler: handle_bookmarks,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "stats".to_string(),
+        description: "Show message, token, cost and timing stats for this session".to_string(),
+        handler: handle_stats,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "usage".to_string(),
+        description: "Show spend, tokens and models used across all sessions (refresh)".to_string(),
+        handler: handle_usage,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "debug".to_string(),
+        description: "Dump the last request and response (last)".to_string(),
+        handler: handle_debug,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "tee".to_string(),
+        description: "Also write each turn to a transcript file (on, off)".to_string(),
+        handler: handle_tee,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "temp".to_string(),
+        description: "Set the sampling temperature for this session (0-2, off)".to_string(),
+        handler: handle_temp,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "top-p".to_string(),
+        description: "Set nucleus sampling (top-p) for this session (0-1, off)".to_string(),
+        handler: handle_top_p,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "seed".to_string(),
+        description: "Set a sampling seed for reproducible replies in this session (off)"
+            .to_string(),
+        handler: handle_seed,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "effort".to_string(),
+        description: "Reasoning effort for this session (low, medium, high, off)".to_string(),
+        handler: handle_effort,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "stop".to_string(),
+        description: "Set stop sequences for this session (quote them; clear)".to_string(),
+        handler: handle_stop,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "notify".to_string(),
+        description: "Notify when a long turn finishes (seconds, off)".to_string(),
+        handler: handle_notify,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "draft".to_string(),
+        description: "Keep unsent input as a draft per session (on, off)".to_string(),
+        handler: handle_draft,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "input-history".to_string(),
+        description: "Recall prompts of this directory or all of them (project, global)"
+            .to_string(),
+        handler: handle_input_history,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "quit-guard".to_string(),
+        description: "Stop a running response before Ctrl+C quits (on, off)".to_string(),
+        handler: handle_quit_guard,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "command-prefix".to_string(),
+        description: "Set what commands start with (reset for /)".to_string(),
+        handler: handle_command_prefix,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "reload".to_string(),
+        description: "Reload preferences, themes and keys without restarting".to_string(),
+        handler: handle_reload,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "notifications".to_string(),
+        description: "Show recent notifications, including expired ones".to_string(),
+        handler: handle_notifications,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "paste".to_string(),
+        description: "List or remove pasted attachments, or set the line threshold".to_string(),
+        handler: handle_paste,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "cd".to_string(),
+        description: "Change the working directory used by tools".to_string(),
+        handler: handle_cd,
+        takes_rest: true,
+    });
+
+    registry.register(Command {
+        name: "open-data".to_string(),
+        description: "Open the data directory (sessions, settings)".to_string(),
+        handler: handle_open_data,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "open-cache".to_string(),
+        description: "Open the cache directory (models, providers)".to_string(),
+        handler: handle_open_cache,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "open-log".to_string(),
+        description: "Open the log file".to_string(),
+        handler: handle_open_log,
+        takes_rest: false,
+    });
+
+    registry.register(Command {
+        name: "doctor".to_string(),
+        description: "Show version, proxy, HTTP and storage settings".to_string(),
+        handler: handle_doctor,
+        takes_rest: false,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::registry::Registry;
+
+    fn create_registry() -> Registry {
+        let mut registry = Registry::new();
+        register_all_commands(&mut registry);
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_handle_exit() {
+        let parsed = session_command("exit", &[]);
+        let mut session_manager = SessionManager::new();
+        let result = handle_exit(&parsed, &mut session_manager).await;
+        assert_eq!(result, CommandResult::Success("Exiting...".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_handle_sessions() {
+        let parsed = session_command("sessions", &[]);
+        let mut session_manager = SessionManager::new();
+        let result = handle_sessions(&parsed, &mut session_manager).await;
+        match result {
+            CommandResult::ShowDialog { title, items } => {
+                assert_eq!(title, "Sessions");
+                assert!(items.is_empty());
+            }
+            _ => panic!("Expected ShowDialog"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_sessions_with_data() {
+        let mut session_manager = SessionManager::new();
+        session_manager.create_session(Some("session-1".to_string()));
+        session_manager.create_session(Some("session-2".to_string()));
+
+        let parsed = session_command("sessions", &[]);
+        let result = handle_sessions(&parsed, &mut session_manager).await;
+        match result {
+            CommandResult::ShowDialog { title, items } => {
+                assert_eq!(title, "Sessions");
+                assert_eq!(items.len(), 2);
+                assert!(
+                    items.iter().any(|item| item.name == "session-1"),
+                    "Items: {:?}",
+                    items.iter().map(|i| &i.name).collect::<Vec<_>>()
+                );
+                assert!(items.iter().any(|item| item.name == "session-2"));
+            }
+            _ => panic!("Expected ShowDialog"),
+        }
+    }
+
+    #[test]
+    fn test_format_session_tip() {
+        assert_eq!(
+            format_session_tip("", 1, "1:30 PM".into(), false),
+            "1 msg  1:30 PM"
+        );
+        assert_eq!(
+            format_session_tip("#bug", 12, "1:30 PM".into(), true),
+            "● #bug  12 msgs  1:30 PM"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handle_sessions_clear_asks_for_confirmation() {
+        let mut session_manager = SessionManager::new();
+        session_manager.create_session(Some("session-1".to_string()));
+
+        let parsed = session_command("sessions", &["clear"]);
+        let result = handle_sessions(&parsed, &mut session_manager).await;
+        match result {
+            CommandResult::Confirm { command, .. } => {
+                assert_eq!(command, "/sessions clear --yes");
+            }
+            _ => panic!("Expected Confirm"),
+        }
+        assert_eq!(session_manager.list_sessions().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_handle_sessions_clear_confirmed() {
+        let mut session_manager = SessionManager::new();
+        session_manager.create_session(Some("session-1".to_string()));
+        session_manager.create_session(Some("session-2".to_string()));
+
+        let parsed = session_command("sessions", &["clear", "--yes"]);
+        let result = handle_sessions(&parsed, &mut session_manager).await;
+        assert_eq!(
+            result,
+            CommandResult::Success("Deleted 2 sessions".to_string())
+        );
+        assert!(session_manager.list_sessions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_handle_sessions_prune_confirmed() {
+        let mut session_manager = SessionManager::new();
+        let old = session_manager.create_session(Some("old".to_string()));
+        let new = session_manager.create_session(Some("new".to_string()));
+        session_manager.get_session(&old).unwrap().updated_at =
+            std::time::SystemTime::now() - std::time::Duration::from_secs(10 * 24 * 60 * 60);
+
+        let parsed = session_command("sessions", &["prune", "7", "--yes"]);
+        let result = handle_sessions(&parsed, &mut session_manager).await;
+        assert_eq!(
+            result,
+            CommandResult::Success("Deleted 1 session".to_string())
+        );
+        assert!(session_manager.get_session(&old).is_none());
+        assert!(session_manager.get_session(&new).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_handle_sessions_prune_invalid_days() {
+        // u64::MAX days overflows when converted to seconds
+        for days in ["soon", "18446744073709551615"] {
+            let parsed = session_command("sessions", &["prune", days]);
+            let mut session_manager = SessionManager::new();
+            let result = handle_sessions(&parsed, &mut session_manager).await;
+            assert!(matches!(result, CommandResult::Error(_)), "{}", days);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_sessions_pinned_group() {
+        let mut session_manager = SessionManager::new();
+        let first = session_manager.create_session(Some("session-1".to_string()));
+        let second = session_manager.create_session(Some("session-2".to_string()));
+        session_manager.toggle_pin(&first).unwrap();
+        session_manager.add_tag(&second, "work").unwrap();
+
+        let parsed = session_command("sessions", &[]);
+        let result = handle_sessions(&parsed, &mut session_manager).await;
+        match result {
+            CommandResult::ShowDialog { items, .. } => {
+                let pinned = items.iter().find(|i| i.id == first).unwrap();
+                assert_eq!(pinned.group, "Pinned");
+                let tagged = items.iter().find(|i| i.id == second).unwrap();
+                assert_ne!(tagged.group, "Pinned");
+                assert_eq!(tagged.description, "#work");
+            }
+            _ => panic!("Expected ShowDialog"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_pin_no_session() {
+        let parsed = session_command("pin", &[]);
+        let mut session_manager = SessionManager::new();
+        let result = handle_pin(&parsed, &mut session_manager).await;
+        assert!(matches!(result, CommandResult::Error(_)));
+    }
+
+    #[tokio::test]
+    async fn test_handle_pin_toggles_current_session() {
+        let mut session_manager = SessionManager::new();
+        let id = session_manager.create_session(Some("session-1".to_string()));
+
+        let parsed = session_command("pin", &[]);
+        handle_pin(&parsed, &mut session_manager).await;
+        assert!(session_manager.get_session(&id).unwrap().pinned);
+        handle_pin(&parsed, &mut session_manager).await;
+        assert!(!session_manager.get_session(&id).unwrap().pinned);
+    }
+
+    fn session_with_two_turns() -> (SessionManager, String) {
+        use crate::session::types::Message;
+
+        let mut session_manager = SessionManager::new().with_in_memory_history();
+        let id = session_manager.create_session(Some("session-1".to_string()));
+        for message in [
+            Message::user("first"),
+            Message::assistant("reply one"),
+            Message::user("second"),
+            Message::assistant("reply two"),
+        ] {
+            session_manager
+                .add_message_to_current_session(&message)
+                .unwrap();
+        }
+        (session_manager, id)
+    }
+
+    fn session_command(name: &str, args: &[&str]) -> ParsedCommand<'static> {
+        ParsedCommand {
+            name: name.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            raw: format!("/{} {}", name, args.join(" ")),
             prefs_dao: None,
             active_model_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_branch() {
+        let (mut session_manager, id) = session_with_two_turns();
+
+        let result = handle_branch(&session_command("branch", &["2"]), &mut session_manager).await;
+        let CommandResult::OpenSession { id: branch, resend } = result else {
+            panic!("expected a branch, got {:?}", result);
+        };
+        assert_eq!(resend, None);
+        assert_eq!(session_manager.get_current_session_id(), Some(&branch));
+        assert_eq!(session_manager.message_count(&branch), 2);
+        assert_eq!(session_manager.message_count(&id), 4);
+
+        let result = handle_branch(&session_command("branch", &["3"]), &mut session_manager).await;
+        assert!(matches!(result, CommandResult::Error(_)));
+    }
+
+    #[tokio::test]
+    async fn test_handle_redo() {
+        let (mut session_manager, id) = session_with_two_turns();
+
+        let result = handle_redo(&session_command("redo", &[]), &mut session_manager).await;
+        let CommandResult::OpenSession { id: branch, resend } = result else {
+            panic!("expected a branch, got {:?}", result);
+        };
+        assert_eq!(resend.as_deref(), Some("second"));
+        assert_eq!(session_manager.message_count(&branch), 2);
+        assert_eq!(session_manager.message_count(&id), 4);
+
+        let mut empty = SessionManager::new();
+        empty.create_session(None);
+        let result = handle_redo(&session_command("redo", &[]), &mut empty).await;
+        assert!(matches!(result, CommandResult::Error(_)));
+    }
+
+    #[tokio::test]
+    async fn test_handle_bookmarks() {
+        let (mut session_manager, id) = session_with_two_turns();
+
+        let result =
+            handle_bookmarks(&session_command("bookmarks", &[]), &mut session_manager).await;
+        assert_eq!(result, CommandResult::Success(String::new()));
+
+        session_manager.toggle_bookmark(&id, 3).unwrap();
+        session_manager.toggle_bookmark(&id, 0).unwrap();
+        let result =
+            handle_bookmarks(&session_command("bookmarks", &[]), &mut session_manager).await;
+        assert_eq!(
+            result,
+            CommandResult::Success(
+                "Bookmarks (2), /bookmarks <n> jumps to one:\n1. message 1 · You: first\n2. message 4 · Assistant: reply two"
+                    .to_string()
+            )
+        );
+
+        let result =
+            handle_bookmarks(&session_command("bookmarks", &["2"]), &mut session_manager).await;
+        assert_eq!(result, CommandResult::ScrollToMessage(3));
+        let result =
+            handle_bookmarks(&session_command("bookmarks", &["3"]), &mut session_manager).await;
+        assert!(matches!(result, CommandResult::Error(_)));
+    }
+
+    #[tokio::test]
+    async fn test_handle_stats() {
+        let result = handle_stats(&session_command("stats", &[]), &mut SessionManager::new()).await;
+        assert!(matches!(result, CommandResult::Error(_)));
+
+        let (mut session_manager, _) = session_with_two_turns();
+        let result = handle_stats(&session_command("stats", &[]), &mut session_manager).await;
+        let CommandResult::Success(table) = result else {
+            panic!("expected stats, got {:?}", result);
+        };
+        assert!(table.starts_with("Stats for session-1"));
+        assert!(table.contains("4 (2 you · 2 assistant)"));
+    }
+
+    #[tokio::test]
+    async fn test_handle_usage() {
+        let (mut session_manager, _) = session_with_two_turns();
+
+        let result = handle_usage(&session_command("usage", &[]), &mut session_manager).await;
+        let CommandResult::Success(text) = result else {
+            panic!("expected usage, got {:?}", result);
         };
+        assert!(text.starts_with("Usage across 1 sessions"));
+
+        let result = handle_usage(
+            &session_command("usage", &["refresh"]),
+            &mut session_manager,
+        )
+        .await;
+        assert!(matches!(result, CommandResult::Success(_)));
+        let result = handle_usage(&session_command("usage", &["all"]), &mut session_manager).await;
+        assert!(matches!(result, CommandResult::Error(_)));
+    }
+
+    #[tokio::test]
+    async fn test_handle_tag_add_and_remove() {
+        let mut session_manager = SessionManager::new();
+        let id = session_manager.create_session(Some("session-1".to_string()));
+
+        let add = session_command("tag", &["#work"]);
+        handle_tag(&add, &mut session_manager).await;
+        assert_eq!(
+            session_manager.get_session(&id).unwrap().tags,
+            vec!["work".to_string()]
+        );
+
+        let remove = session_command("tag", &["remove", "work"]);
+        handle_tag(&remove, &mut session_manager).await;
+        assert!(session_manager.get_session(&id).unwrap().tags.is_empty());
+
+        // Removing it again says so rather than claiming it was removed
+        handle_tag(&remove, &mut session_manager).await;
+        assert!(crate::toast_history()
+            .iter()
+            .any(|toast| toast.message == "No tag #work to remove"));
+    }
+
+    #[tokio::test]
+    async fn test_handle_tag_remove_without_label() {
+        let mut session_manager = SessionManager::new();
+        let id = session_manager.create_session(Some("session-1".to_string()));
+
+        let parsed = session_command("tag", &["remove"]);
+        let result = handle_tag(&parsed, &mut session_manager).await;
+        assert!(matches!(result, CommandResult::Error(ref e) if e.starts_with("Usage:")));
+        assert!(session_manager.get_session(&id).unwrap().tags.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_handle_new_no_args() {
+        let parsed = session_command("new", &[]);
+        let mut session_manager = SessionManager::new();
+        let result = handle_new(&parsed, &mut session_manager).await;
+        match result {
+            CommandResult::Success(msg) => {
+                assert!(msg.is_empty());
+            }
+            _ => panic!("Expected Success"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_new_with_name() {
+        let parsed = session_command("new", &["my-session"]);
+        let mut session_manager = SessionManager::new();
+        let result = handle_new(&parsed, &mut session_manager).await;
+        match result {
+            CommandResult::Success(msg) => {
+                assert!(msg.is_empty());
+            }
+            _ => panic!("Expected Success"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_home() {
+        let parsed = session_command("home", &[]);
         let mut session_manager = SessionManager::new();
         let result = handle_new(&parsed, &mut session_manager).await;
         match result {
@@ -650,13 +2779,7 @@ mod tests {
         let _ = crate::config::ApiKeyConfig::cleanup_test();
         let _ = crate::model::discovery::Discovery::cleanup_test();
 
-        let parsed = ParsedCommand {
-            name: "connect".to_string(),
-            args: vec![],
-            raw: "/connect".to_string(),
-            prefs_dao: None,
-            active_model_id: None,
-        };
+        let parsed = session_command("connect", &[]);
         let mut session_manager = SessionManager::new();
         let result = handle_connect(&parsed, &mut session_manager).await;
         match result {
@@ -681,13 +2804,7 @@ mod tests {
     async fn test_handle_connect_provider_only() {
         let _ = crate::config::ApiKeyConfig::cleanup_test();
 
-        let parsed = ParsedCommand {
-            name: "connect".to_string(),
-            args: vec!["nano-gpt".to_string()],
-            raw: "/connect nano-gpt".to_string(),
-            prefs_dao: None,
-            active_model_id: None,
-        };
+        let parsed = session_command("connect", &["nano-gpt"]);
         let mut session_manager = SessionManager::new();
         let result = handle_connect(&parsed, &mut session_manager).await;
         match result {
@@ -704,13 +2821,7 @@ mod tests {
     async fn test_handle_connect_with_api_key() {
         let _ = crate::config::ApiKeyConfig::cleanup_test();
 
-        let parsed = ParsedCommand {
-            name: "connect".to_string(),
-            args: vec!["nano-gpt".to_string(), "sk-test-key".to_string()],
-            raw: "/connect nano-gpt sk-test-key".to_string(),
-            prefs_dao: None,
-            active_model_id: None,
-        };
+        let parsed = session_command("connect", &["nano-gpt", "sk-test-key"]);
         let mut session_manager = SessionManager::new();
         let result = handle_connect(&parsed, &mut session_manager).await;
         match result {
@@ -729,13 +2840,7 @@ mod tests {
 
         let mut session_manager = SessionManager::new();
 
-        let parsed1 = ParsedCommand {
-            name: "connect".to_string(),
-            args: vec!["nano-gpt".to_string(), "sk-test-key".to_string()],
-            raw: "/connect nano-gpt sk-test-key".to_string(),
-            prefs_dao: None,
-            active_model_id: None,
-        };
+        let parsed1 = session_command("connect", &["nano-gpt", "sk-test-key"]);
         let result1 = handle_connect(&parsed1, &mut session_manager).await;
         match result1 {
             CommandResult::Success(msg) => {
@@ -755,13 +2860,7 @@ mod tests {
     #[tokio::test]
     async fn test_handle_models() {
         let _ = crate::model::discovery::Discovery::cleanup_test();
-        let parsed = ParsedCommand {
-            name: "models".to_string(),
-            args: vec![],
-            raw: "/models".to_string(),
-            prefs_dao: None,
-            active_model_id: None,
-        };
+        let parsed = session_command("models", &[]);
         let mut session_manager = SessionManager::new();
         let result = handle_models(&parsed, &mut session_manager).await;
         match result {
@@ -778,13 +2877,7 @@ mod tests {
     #[tokio::test]
     async fn test_handle_models_with_filter() {
         let _ = crate::model::discovery::Discovery::cleanup_test();
-        let parsed = ParsedCommand {
-            name: "models".to_string(),
-            args: vec!["open".to_string()],
-            raw: "/models open".to_string(),
-            prefs_dao: None,
-            active_model_id: None,
-        };
+        let parsed = session_command("models", &["open"]);
         let mut session_manager = SessionManager::new();
         let result = handle_models(&parsed, &mut session_manager).await;
         match result {
@@ -802,13 +2895,7 @@ mod tests {
     async fn test_handle_models_cleanup() {
         let _ = crate::config::ApiKeyConfig::cleanup_test();
         let _ = crate::model::discovery::Discovery::cleanup_test();
-        let parsed = ParsedCommand {
-            name: "models".to_string(),
-            args: vec![],
-            raw: "/models".to_string(),
-            prefs_dao: None,
-            active_model_id: None,
-        };
+        let parsed = session_command("models", &[]);
         let mut session_manager = SessionManager::new();
         let result = handle_models(&parsed, &mut session_manager).await;
         match result {
@@ -826,13 +2913,7 @@ mod tests {
     #[tokio::test]
     async fn test_handle_refreshmodels() {
         let _ = crate::model::discovery::Discovery::cleanup_test();
-        let parsed = ParsedCommand {
-            name: "refreshmodels".to_string(),
-            args: vec![],
-            raw: "/refreshmodels".to_string(),
-            prefs_dao: None,
-            active_model_id: None,
-        };
+        let parsed = session_command("refreshmodels", &[]);
         let mut session_manager = SessionManager::new();
         let result = handle_refreshmodels(&parsed, &mut session_manager).await;
         assert_eq!(result, CommandResult::Success(String::new()));
@@ -843,7 +2924,7 @@ mod tests {
     async fn test_registry_has_all_commands() {
         let registry = create_registry();
         let names = registry.get_command_names();
-        assert_eq!(names.len(), 7);
+        assert_eq!(names.len(), 42);
         assert!(names.contains(&"exit".to_string()));
         assert!(names.contains(&"sessions".to_string()));
         assert!(names.contains(&"new".to_string()));
@@ -851,18 +2932,633 @@ mod tests {
         assert!(names.contains(&"models".to_string()));
         assert!(names.contains(&"home".to_string()));
         assert!(names.contains(&"refreshmodels".to_string()));
+        assert!(names.contains(&"pin".to_string()));
+        assert!(names.contains(&"tag".to_string()));
+        assert!(names.contains(&"scroll".to_string()));
+        assert!(names.contains(&"color".to_string()));
+        assert!(names.contains(&"tools-log".to_string()));
+        assert!(names.contains(&"cd".to_string()));
+    }
+
+    async fn scroll_command(args: &[&str], dao: &crate::persistence::PrefsDAO) -> CommandResult {
+        let parsed = ParsedCommand {
+            prefs_dao: Some(dao),
+            ..session_command("scroll", args)
+        };
+        let mut session_manager = SessionManager::new();
+        handle_scroll(&parsed, &mut session_manager).await
+    }
+
+    #[tokio::test]
+    async fn test_handle_scroll_sets_preferences() {
+        let dao = crate::persistence::PrefsDAO::new_in_memory().unwrap();
+
+        assert_eq!(
+            scroll_command(&["5"], &dao).await,
+            CommandResult::Success(String::new())
+        );
+        assert_eq!(dao.get_scroll_preferences().unwrap().step, 5);
+
+        scroll_command(&["accel", "on"], &dao).await;
+        assert!(dao.get_scroll_preferences().unwrap().accelerate);
+
+        scroll_command(&["smooth"], &dao).await;
+        assert!(dao.get_scroll_preferences().unwrap().smooth);
+        scroll_command(&["smooth", "off"], &dao).await;
+        assert!(!dao.get_scroll_preferences().unwrap().smooth);
+    }
+
+    #[tokio::test]
+    async fn test_handle_scroll_rejects_invalid_args() {
+        let dao = crate::persistence::PrefsDAO::new_in_memory().unwrap();
+        for args in [&["0"][..], &["999"], &["fast"], &["accel", "maybe"]] {
+            assert!(matches!(
+                scroll_command(args, &dao).await,
+                CommandResult::Error(_)
+            ));
+        }
+        assert_eq!(
+            dao.get_scroll_preferences().unwrap(),
+            crate::persistence::ScrollPreferences::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handle_scroll_without_prefs() {
+        let parsed = session_command("scroll", &["5"]);
+        let mut session_manager = SessionManager::new();
+        let result = handle_scroll(&parsed, &mut session_manager).await;
+        assert!(matches!(result, CommandResult::Error(_)));
+    }
+
+    #[test]
+    fn test_format_tool_calls() {
+        let record = |name: &str, duration_ms| crate::persistence::ToolCallRecord {
+            session_id: 1,
+            call_id: "call".to_string(),
+            name: name.to_string(),
+            args: r#"{"path":"src/main.rs"}"#.to_string(),
+            status: "ok".to_string(),
+            duration_ms,
+            output: "fn main() {}\nmore".to_string(),
+            timestamp: 0,
+        };
+
+        let text = format_tool_calls(&[record("read", Some(12)), record("glob", None)], 5);
+        assert_eq!(
+            text,
+            "Tool calls (2 of 5):\n\
+             4. read · ok · 12ms\n   args: {\"path\":\"src/main.rs\"}\n   output: fn main() {}\n\
+             5. glob · ok\n   args: {\"path\":\"src/main.rs\"}\n   output: fn main() {}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handle_tools_log_without_session() {
+        let parsed = session_command("tools-log", &[]);
+        let mut session_manager = SessionManager::new();
+        let result = handle_tools_log(&parsed, &mut session_manager).await;
+        assert!(matches!(result, CommandResult::Error(_)));
+    }
+
+    #[tokio::test]
+    async fn test_handle_color_sets_mode() {
+        use crate::persistence::ColorMode;
+
+        let dao = crate::persistence::PrefsDAO::new_in_memory().unwrap();
+        let color_command = |arg: &str| ParsedCommand {
+            prefs_dao: Some(&dao),
+            ..session_command("color", &[arg])
+        };
+        let mut session_manager = SessionManager::new();
+
+        let result = handle_color(&color_command("off"), &mut session_manager).await;
+        assert_eq!(result, CommandResult::Success(String::new()));
+        assert_eq!(dao.get_color_mode().unwrap(), ColorMode::Monochrome);
+
+        let result = handle_color(&color_command("sepia"), &mut session_manager).await;
+        assert!(matches!(result, CommandResult::Error(_)));
+        assert_eq!(dao.get_color_mode().unwrap(), ColorMode::Monochrome);
+
+        handle_color(&color_command("high-contrast"), &mut session_manager).await;
+        assert_eq!(dao.get_color_mode().unwrap(), ColorMode::HighContrast);
+    }
+
+    #[tokio::test]
+    async fn test_handle_density() {
+        use crate::persistence::Density;
+
+        let dao = crate::persistence::PrefsDAO::new_in_memory().unwrap();
+        let density_command = |arg: &str| ParsedCommand {
+            prefs_dao: Some(&dao),
+            ..session_command("density", &[arg])
+        };
+        let mut session_manager = SessionManager::new();
+
+        let result = handle_density(&density_command("compact"), &mut session_manager).await;
+        assert_eq!(result, CommandResult::Success(String::new()));
+        assert_eq!(dao.get_density().unwrap(), Density::Compact);
+
+        handle_density(&density_command("toggle"), &mut session_manager).await;
+        assert_eq!(dao.get_density().unwrap(), Density::Comfortable);
+
+        let result = handle_density(&density_command("tight"), &mut session_manager).await;
+        assert!(matches!(result, CommandResult::Error(_)));
+        assert_eq!(dao.get_density().unwrap(), Density::Comfortable);
+    }
+
+    #[tokio::test]
+    async fn test_handle_markdown() {
+        use crate::persistence::MarkdownRendererKind;
+
+        let dao = crate::persistence::PrefsDAO::new_in_memory().unwrap();
+        let markdown_command = |arg: &str| ParsedCommand {
+            prefs_dao: Some(&dao),
+            ..session_command("markdown", &[arg])
+        };
+        let mut session_manager = SessionManager::new();
+
+        let result = handle_markdown(&markdown_command("builtin"), &mut session_manager).await;
+        assert_eq!(result, CommandResult::Success(String::new()));
+        assert_eq!(
+            dao.get_markdown_renderer().unwrap(),
+            MarkdownRendererKind::Builtin
+        );
+
+        let result = handle_markdown(&markdown_command("html"), &mut session_manager).await;
+        assert!(matches!(result, CommandResult::Error(_)));
+        assert_eq!(
+            dao.get_markdown_renderer().unwrap(),
+            MarkdownRendererKind::Builtin
+        );
+    }
+
+    #[test]
+    fn test_date_group_uses_local_midnight() {
+        use chrono::FixedOffset;
+
+        let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+        let at = |h, m| tokyo.with_ymd_and_hms(2026, 3, 10, h, m, 0).unwrap();
+        let now = at(0, 5);
+
+        // Ten minutes ago, but yesterday
+        let before_midnight = tokyo.with_ymd_and_hms(2026, 3, 9, 23, 55, 0).unwrap();
+        assert_eq!(date_group(&before_midnight, &now), "Mon Mar 09 2026");
+        assert_eq!(date_group(&at(0, 1), &now), "Today");
+        // Same day, most of it ago
+        assert_eq!(date_group(&at(0, 1), &at(23, 59)), "Today");
+        // Still the 9th in UTC, but the 10th locally
+        let stored = chrono::Utc.with_ymd_and_hms(2026, 3, 9, 23, 30, 0).unwrap();
+        assert_eq!(date_group(&stored.with_timezone(&tokyo), &now), "Today");
+    }
+
+    #[test]
+    fn test_clock_time_formats() {
+        let utc = chrono::Utc;
+        let midnight = utc.with_ymd_and_hms(2026, 3, 10, 0, 5, 0).unwrap();
+        let afternoon = utc.with_ymd_and_hms(2026, 3, 10, 13, 30, 0).unwrap();
+
+        assert_eq!(clock_time(&midnight, false), "12:05 AM");
+        assert_eq!(clock_time(&afternoon, false), "1:30 PM");
+        assert_eq!(clock_time(&midnight, true), "00:05");
+        assert_eq!(clock_time(&afternoon, true), "13:30");
+    }
+
+    #[tokio::test]
+    async fn test_handle_time_format() {
+        let dao = crate::persistence::PrefsDAO::new_in_memory().unwrap();
+        let time_format_command = |arg: &str| ParsedCommand {
+            prefs_dao: Some(&dao),
+            ..session_command("time-format", &[arg])
+        };
+        let mut session_manager = SessionManager::new();
+        assert_eq!(dao.get_time_format().unwrap(), TimeFormat::TwelveHour);
+
+        let result = handle_time_format(&time_format_command("24h"), &mut session_manager).await;
+        assert_eq!(result, CommandResult::Success(String::new()));
+        assert_eq!(dao.get_time_format().unwrap(), TimeFormat::TwentyFourHour);
+
+        handle_time_format(&time_format_command("relative"), &mut session_manager).await;
+        assert_eq!(dao.get_time_format().unwrap(), TimeFormat::Relative);
+
+        let result = handle_time_format(&time_format_command("36h"), &mut session_manager).await;
+        assert!(matches!(result, CommandResult::Error(_)));
+        assert_eq!(dao.get_time_format().unwrap(), TimeFormat::Relative);
+    }
+
+    #[tokio::test]
+    async fn test_handle_tone_sets_preset() {
+        use crate::persistence::Tone;
+
+        let dao = crate::persistence::PrefsDAO::new_in_memory().unwrap();
+        let tone_command = |arg: &str| ParsedCommand {
+            prefs_dao: Some(&dao),
+            ..session_command("tone", &[arg])
+        };
+        let mut session_manager = SessionManager::new();
+        assert_eq!(dao.get_tone().unwrap(), Tone::Default);
+
+        let result = handle_tone(&tone_command("teaching"), &mut session_manager).await;
+        assert_eq!(result, CommandResult::Success(String::new()));
+        assert_eq!(dao.get_tone().unwrap(), Tone::Teaching);
+
+        let result = handle_tone(&tone_command("chatty"), &mut session_manager).await;
+        assert!(matches!(result, CommandResult::Error(_)));
+        assert_eq!(dao.get_tone().unwrap(), Tone::Teaching);
+    }
+
+    #[tokio::test]
+    async fn test_handle_recent_sets_limit() {
+        let dao = crate::persistence::PrefsDAO::new_in_memory().unwrap();
+        let recent_command = |arg: &str| ParsedCommand {
+            prefs_dao: Some(&dao),
+            ..session_command("recent", &[arg])
+        };
+        let mut session_manager = SessionManager::new();
+
+        let result = handle_recent(&recent_command("3"), &mut session_manager).await;
+        assert_eq!(result, CommandResult::Success(String::new()));
+        assert_eq!(dao.get_model_preferences().unwrap().recent_limit, 3);
+
+        for arg in ["0", "500", "many"] {
+            let result = handle_recent(&recent_command(arg), &mut session_manager).await;
+            assert!(matches!(result, CommandResult::Error(_)));
+        }
+        assert_eq!(dao.get_model_preferences().unwrap().recent_limit, 3);
+    }
+
+    #[tokio::test]
+    async fn test_handle_temp_and_top_p() {
+        let mut session_manager = SessionManager::new();
+        assert!(matches!(
+            handle_temp(&session_command("temp", &["0.5"]), &mut session_manager).await,
+            CommandResult::Error(msg) if msg == "No active session"
+        ));
+
+        session_manager.create_session(Some("session-1".to_string()));
+        assert!(matches!(
+            handle_temp(&session_command("temp", &["0.5"]), &mut session_manager).await,
+            CommandResult::Success(_)
+        ));
+        assert!(matches!(
+            handle_top_p(&session_command("top-p", &["0.9"]), &mut session_manager).await,
+            CommandResult::Success(_)
+        ));
+        let sampling = session_manager.current_sampling();
+        assert_eq!(sampling.temperature, Some(0.5));
+        assert_eq!(sampling.top_p, Some(0.9));
+
+        for bad in ["2.5", "-1", "warm", "NaN"] {
+            assert!(matches!(
+                handle_temp(&session_command("temp", &[bad]), &mut session_manager).await,
+                CommandResult::Error(msg) if msg.starts_with("Usage: /temp")
+            ));
+        }
+        assert!(matches!(
+            handle_top_p(&session_command("top-p", &["1.1"]), &mut session_manager).await,
+            CommandResult::Error(_)
+        ));
+
+        handle_temp(&session_command("temp", &["off"]), &mut session_manager).await;
+        let sampling = session_manager.current_sampling();
+        assert_eq!(sampling.temperature, None);
+        assert_eq!(sampling.top_p, Some(0.9));
+    }
+
+    #[tokio::test]
+    async fn test_handle_seed() {
+        let seed_command = |args: &[&str]| session_command("seed", args);
+        let mut session_manager = SessionManager::new();
+        session_manager.create_session(Some("session-1".to_string()));
+
+        assert!(matches!(
+            handle_seed(&seed_command(&["42"]), &mut session_manager).await,
+            CommandResult::Success(_)
+        ));
+        assert_eq!(session_manager.current_sampling().seed, Some(42));
+
+        for bad in ["-1", "1.5", "lucky"] {
+            assert!(matches!(
+                handle_seed(&seed_command(&[bad]), &mut session_manager).await,
+                CommandResult::Error(_)
+            ));
+        }
+        assert_eq!(session_manager.current_sampling().seed, Some(42));
+
+        handle_seed(&seed_command(&["off"]), &mut session_manager).await;
+        assert!(session_manager.current_sampling().is_default());
+    }
+
+    #[tokio::test]
+    async fn test_handle_effort() {
+        use crate::session::types::ReasoningEffort;
+
+        let effort_command = |args: &[&str]| session_command("effort", args);
+        let mut session_manager = SessionManager::new();
+        session_manager.create_session(Some("session-1".to_string()));
+
+        assert!(matches!(
+            handle_effort(&effort_command(&["High"]), &mut session_manager).await,
+            CommandResult::Success(_)
+        ));
+        assert_eq!(
+            session_manager.current_sampling().effort,
+            Some(ReasoningEffort::High)
+        );
+
+        for bad in [&["max"][..], &["low", "high"]] {
+            assert!(matches!(
+                handle_effort(&effort_command(bad), &mut session_manager).await,
+                CommandResult::Error(_)
+            ));
+        }
+        assert_eq!(
+            session_manager.current_sampling().effort,
+            Some(ReasoningEffort::High)
+        );
+
+        handle_effort(&effort_command(&["off"]), &mut session_manager).await;
+        assert!(session_manager.current_sampling().is_default());
+    }
+
+    #[tokio::test]
+    async fn test_handle_stop() {
+        // `handle_stop` reads the raw line, so quotes reach it as typed
+        let stop_command = |raw: &str| ParsedCommand {
+            raw: raw.to_string(),
+            ..session_command("stop", &[])
+        };
+        let mut session_manager = SessionManager::new();
+        session_manager.create_session(Some("session-1".to_string()));
+
+        let result = handle_stop(
+            &stop_command(r#"/stop "```" "```" "\nUser:""#),
+            &mut session_manager,
+        )
+        .await;
+        assert!(matches!(result, CommandResult::Success(_)));
+        assert_eq!(session_manager.current_sampling().stop, ["```", "\nUser:"]);
+
+        // Listing leaves them alone
+        handle_stop(&stop_command("/stop"), &mut session_manager).await;
+        assert_eq!(session_manager.current_sampling().stop.len(), 2);
+
+        for bad in [r#"/stop """#, "/stop a b c d e", r#"/stop "open"#] {
+            assert!(matches!(
+                handle_stop(&stop_command(bad), &mut session_manager).await,
+                CommandResult::Error(_)
+            ));
+        }
+        assert_eq!(session_manager.current_sampling().stop.len(), 2);
+
+        handle_stop(&stop_command("/stop clear"), &mut session_manager).await;
+        assert!(session_manager.current_sampling().is_default());
+    }
+
+    #[tokio::test]
+    async fn test_handle_doctor() {
+        let parsed = session_command("doctor", &[]);
+        let mut session_manager = SessionManager::new();
+        let CommandResult::Success(report) = handle_doctor(&parsed, &mut session_manager).await
+        else {
+            panic!("expected a report");
+        };
+        assert!(report.starts_with(&format!("crabcode {}", env!("CARGO_PKG_VERSION"))));
+        assert!(report.contains("\nProxy: "));
+        assert!(report.contains("\nHTTP: "));
+        assert!(report.contains("\nToasts: "));
+    }
+
+    #[test]
+    fn test_reveal_path_names_path_when_headless() {
+        let path = Path::new("/tmp/crabcode-data");
+        assert!(!has_file_manager());
+        assert_eq!(
+            reveal_path("Data directory", path),
+            "Data directory: /tmp/crabcode-data"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handle_tee_toggles() {
+        let dao = crate::persistence::PrefsDAO::new_in_memory().unwrap();
+        let tee_command = |args: &[&str]| ParsedCommand {
+            prefs_dao: Some(&dao),
+            ..session_command("tee", args)
+        };
+        let mut session_manager = SessionManager::new();
+        assert!(!dao.get_tee_output().unwrap());
+
+        let result = handle_tee(&tee_command(&["on"]), &mut session_manager).await;
+        assert_eq!(result, CommandResult::Success(String::new()));
+        assert!(dao.get_tee_output().unwrap());
+
+        handle_tee(&tee_command(&[]), &mut session_manager).await;
+        assert!(!dao.get_tee_output().unwrap());
+
+        let result = handle_tee(&tee_command(&["maybe"]), &mut session_manager).await;
+        assert!(matches!(result, CommandResult::Error(_)));
+        assert!(!dao.get_tee_output().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_handle_notify() {
+        let dao = crate::persistence::PrefsDAO::new_in_memory().unwrap();
+        let notify_command = |args: &[&str]| ParsedCommand {
+            prefs_dao: Some(&dao),
+            ..session_command("notify", args)
+        };
+        let mut session_manager = SessionManager::new();
+        assert_eq!(dao.get_notify_after_secs().unwrap(), 0);
+
+        handle_notify(&notify_command(&[]), &mut session_manager).await;
+        assert_eq!(
+            dao.get_notify_after_secs().unwrap(),
+            crate::utils::notify::DEFAULT_NOTIFY_AFTER_SECS
+        );
+        handle_notify(&notify_command(&[]), &mut session_manager).await;
+        assert_eq!(dao.get_notify_after_secs().unwrap(), 0);
+        handle_notify(&notify_command(&["90"]), &mut session_manager).await;
+        assert_eq!(dao.get_notify_after_secs().unwrap(), 90);
+        handle_notify(&notify_command(&["off"]), &mut session_manager).await;
+        assert_eq!(dao.get_notify_after_secs().unwrap(), 0);
+
+        for args in [&["0"][..], &["soon"], &["1", "2"]] {
+            let result = handle_notify(&notify_command(args), &mut session_manager).await;
+            assert!(matches!(result, CommandResult::Error(_)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_draft_toggles() {
+        let dao = crate::persistence::PrefsDAO::new_in_memory().unwrap();
+        let draft_command = |args: &[&str]| ParsedCommand {
+            prefs_dao: Some(&dao),
+            ..session_command("draft", args)
+        };
+        let mut session_manager = SessionManager::new();
+        assert!(dao.get_draft_autosave().unwrap());
+
+        handle_draft(&draft_command(&[]), &mut session_manager).await;
+        assert!(!dao.get_draft_autosave().unwrap());
+        handle_draft(&draft_command(&["on"]), &mut session_manager).await;
+        assert!(dao.get_draft_autosave().unwrap());
+
+        let result = handle_draft(&draft_command(&["later"]), &mut session_manager).await;
+        assert!(matches!(result, CommandResult::Error(_)));
+    }
+
+    #[tokio::test]
+    async fn test_handle_input_history() {
+        use crate::persistence::HistoryScope;
+
+        let dao = crate::persistence::PrefsDAO::new_in_memory().unwrap();
+        let input_history_command = |args: &[&str]| ParsedCommand {
+            prefs_dao: Some(&dao),
+            ..session_command("input-history", args)
+        };
+        let mut session_manager = SessionManager::new();
+
+        handle_input_history(&input_history_command(&["global"]), &mut session_manager).await;
+        assert_eq!(dao.get_history_scope().unwrap(), HistoryScope::Global);
+        handle_input_history(&input_history_command(&["toggle"]), &mut session_manager).await;
+        assert_eq!(dao.get_history_scope().unwrap(), HistoryScope::Project);
+
+        let result =
+            handle_input_history(&input_history_command(&["session"]), &mut session_manager).await;
+        assert!(matches!(result, CommandResult::Error(_)));
+    }
+
+    #[tokio::test]
+    async fn test_handle_quit_guard_toggles() {
+        let dao = crate::persistence::PrefsDAO::new_in_memory().unwrap();
+        let quit_guard_command = |args: &[&str]| ParsedCommand {
+            prefs_dao: Some(&dao),
+            ..session_command("quit-guard", args)
+        };
+        let mut session_manager = SessionManager::new();
+        assert!(dao.get_quit_guard().unwrap());
+
+        handle_quit_guard(&quit_guard_command(&[]), &mut session_manager).await;
+        assert!(!dao.get_quit_guard().unwrap());
+        handle_quit_guard(&quit_guard_command(&["on"]), &mut session_manager).await;
+        assert!(dao.get_quit_guard().unwrap());
+
+        let result = handle_quit_guard(&quit_guard_command(&["maybe"]), &mut session_manager).await;
+        assert!(matches!(result, CommandResult::Error(_)));
+    }
+
+    #[tokio::test]
+    async fn test_handle_command_prefix() {
+        let dao = crate::persistence::PrefsDAO::new_in_memory().unwrap();
+        let prefix_command = |args: &[&str]| ParsedCommand {
+            prefs_dao: Some(&dao),
+            ..session_command("command-prefix", args)
+        };
+        let mut session_manager = SessionManager::new();
+
+        handle_command_prefix(&prefix_command(&[":"]), &mut session_manager).await;
+        assert_eq!(dao.get_command_prefix().unwrap(), ":");
+        let typed = ParsedCommand {
+            raw: ":command-prefix ; ;".to_string(),
+            ..prefix_command(&[";", ";"])
+        };
+        assert_eq!(
+            handle_command_prefix(&typed, &mut session_manager).await,
+            CommandResult::Error("Usage: :command-prefix [<prefix>|reset]".to_string())
+        );
+        for bad in [&["x"][..], &[" "], &[":", ";"]] {
+            let result = handle_command_prefix(&prefix_command(bad), &mut session_manager).await;
+            assert!(matches!(result, CommandResult::Error(_)));
+        }
+        assert_eq!(dao.get_command_prefix().unwrap(), ":");
+
+        handle_command_prefix(&prefix_command(&["reset"]), &mut session_manager).await;
+        assert_eq!(dao.get_command_prefix().unwrap(), "/");
+    }
+
+    #[tokio::test]
+    async fn test_handle_paste() {
+        use crate::command::registry::PasteAction;
+
+        let dao = crate::persistence::PrefsDAO::new_in_memory().unwrap();
+        let paste_command = |args: &[&str]| ParsedCommand {
+            prefs_dao: Some(&dao),
+            ..session_command("paste", args)
+        };
+        let mut session_manager = SessionManager::new();
+
+        let result = handle_paste(&paste_command(&[]), &mut session_manager).await;
+        assert_eq!(result, CommandResult::PastedContent(PasteAction::List));
+        let result = handle_paste(&paste_command(&["remove", "2"]), &mut session_manager).await;
+        assert_eq!(
+            result,
+            CommandResult::PastedContent(PasteAction::Remove(Some(1)))
+        );
+
+        assert_eq!(
+            dao.get_paste_attach_lines().unwrap(),
+            crate::session::paste::DEFAULT_ATTACH_LINES
+        );
+        handle_paste(&paste_command(&["50"]), &mut session_manager).await;
+        assert_eq!(dao.get_paste_attach_lines().unwrap(), 50);
+        handle_paste(&paste_command(&["off"]), &mut session_manager).await;
+        assert_eq!(dao.get_paste_attach_lines().unwrap(), 0);
+
+        for args in [&["0"][..], &["remove", "0"], &["lots"]] {
+            let result = handle_paste(&paste_command(args), &mut session_manager).await;
+            assert!(matches!(result, CommandResult::Error(_)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_debug() {
+        let debug_command = |args: &[&str]| session_command("debug", args);
+        let mut session_manager = SessionManager::new();
+
+        let result = handle_debug(&debug_command(&["last"]), &mut session_manager).await;
+        assert_eq!(result, CommandResult::DebugLastTurn);
+
+        for args in [&[][..], &["first"], &["last", "2"]] {
+            let result = handle_debug(&debug_command(args), &mut session_manager).await;
+            assert!(matches!(result, CommandResult::Error(_)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_reload() {
+        let mut session_manager = SessionManager::new();
+
+        let result = handle_reload(&session_command("reload", &[]), &mut session_manager).await;
+        assert_eq!(result, CommandResult::Reload);
+        let result = handle_reload(
+            &session_command("reload", &["themes"]),
+            &mut session_manager,
+        )
+        .await;
+        assert!(matches!(result, CommandResult::Error(_)));
+    }
+
+    #[tokio::test]
+    async fn test_handle_notifications() {
+        let mut session_manager = SessionManager::new();
+
+        let result =
+            handle_notifications(&session_command("notifications", &[]), &mut session_manager)
+                .await;
+        assert_eq!(result, CommandResult::ShowNotifications);
+        let result = handle_notifications(
+            &session_command("notifications", &["clear"]),
+            &mut session_manager,
+        )
+        .await;
+        assert!(matches!(result, CommandResult::Error(_)));
     }
 
     #[tokio::test]
     async fn test_execute_exit_command() {
         let registry = create_registry();
-        let parsed = ParsedCommand {
-            name: "exit".to_string(),
-            args: vec![],
-            raw: "/exit".to_string(),
-            prefs_dao: None,
-            active_model_id: None,
-        };
+        let parsed = session_command("exit", &[]);
         let mut session_manager = SessionManager::new();
         let result = registry.execute(&parsed, &mut session_manager).await;
         assert_eq!(result, CommandResult::Success("Exiting...".to_string()));
@@ -871,13 +3567,7 @@ mod tests {
     #[tokio::test]
     async fn test_execute_unknown_command() {
         let registry = create_registry();
-        let parsed = ParsedCommand {
-            name: "unknown".to_string(),
-            args: vec![],
-            raw: "/unknown".to_string(),
-            prefs_dao: None,
-            active_model_id: None,
-        };
+        let parsed = session_command("unknown", &[]);
         let mut session_manager = SessionManager::new();
         let result = registry.execute(&parsed, &mut session_manager).await;
         match result {
@@ -887,4 +3577,38 @@ mod tests {
             _ => panic!("Expected Error"),
         }
     }
+
+    #[tokio::test]
+    async fn test_handle_cd() {
+        let mut session_manager = SessionManager::new();
+        let parsed = session_command("cd", &["my", "dir"]);
+        let result = handle_cd(&parsed, &mut session_manager).await;
+        assert_eq!(result, CommandResult::ChangeDirectory("my dir".to_string()));
+
+        let parsed = session_command("cd", &[]);
+        let result = handle_cd(&parsed, &mut session_manager).await;
+        assert!(matches!(result, CommandResult::Error(_)));
+    }
+
+    #[test]
+    fn test_resolve_working_dir() {
+        let root = std::env::temp_dir().join("crabcode_test_cd");
+        std::fs::create_dir_all(root.join("sub/inner")).unwrap();
+        let root = root.canonicalize().unwrap();
+        std::fs::write(root.join("file.txt"), "").unwrap();
+        let root_str = root.to_string_lossy().to_string();
+        let sub = root.join("sub").to_string_lossy().to_string();
+
+        // Relative paths resolve against the current directory, not the process's
+        assert_eq!(resolve_working_dir(&root_str, "sub"), Ok(sub.clone()));
+        assert_eq!(
+            resolve_working_dir(&sub, "inner"),
+            Ok(root.join("sub/inner").to_string_lossy().to_string())
+        );
+        assert_eq!(resolve_working_dir(&sub, ".."), Ok(root_str.clone()));
+        assert_eq!(resolve_working_dir("/", &sub), Ok(sub.clone()));
+
+        assert!(resolve_working_dir(&root_str, "missing").is_err());
+        assert!(resolve_working_dir(&root_str, "file.txt").is_err());
+    }
 }
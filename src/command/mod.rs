@@ -1,3 +1,4 @@
+pub mod aliases;
 pub mod handlers;
 pub mod parser;
 pub mod registry;
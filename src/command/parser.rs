@@ -1,3 +1,13 @@
+/// Ends argument splitting; what follows is passed on as one argument.
+pub const REST_MARKER: &str = "--";
+
+/// What starts a command unless the user picked another prefix. Command
+/// lines built inside crabcode (shortcuts, confirmations) always use it.
+pub const DEFAULT_PREFIX: &str = "/";
+
+/// Longest command prefix that can be set.
+pub const MAX_PREFIX_CHARS: usize = 3;
+
 #[derive(Debug, Clone)]
 pub struct ParsedCommand<'a> {
     pub name: String,
@@ -7,6 +17,36 @@ pub struct ParsedCommand<'a> {
     pub active_model_id: Option<String>,
 }
 
+impl ParsedCommand<'_> {
+    /// The prefix the command was typed with, for messages that name
+    /// commands the way the user writes them.
+    pub fn prefix(&self) -> &str {
+        match self.raw.find(&self.name) {
+            Some(start) => self.raw[..start].trim(),
+            None => DEFAULT_PREFIX,
+        }
+    }
+
+    /// Everything after the command name as typed, quotes and all, for
+    /// handlers that read the arguments themselves.
+    pub fn tail(&self) -> &str {
+        // The prefix is never alphanumeric, so the name is its first match
+        match self.raw.find(&self.name) {
+            Some(start) => self.raw[start + self.name.len()..].trim(),
+            None => "",
+        }
+    }
+
+    /// This command with the rest of the line as its only argument, for
+    /// commands that take free text.
+    pub fn with_rest_arg(&self) -> Self {
+        Self {
+            args: rest_arg(self.tail()).into_iter().collect(),
+            ..self.clone()
+        }
+    }
+}
+
 impl<'a> PartialEq for ParsedCommand<'a> {
     fn eq(&self, other: &Self) -> bool {
         self.name == other.name && self.args == other.args
@@ -20,27 +60,61 @@ pub enum InputType<'a> {
 }
 
 pub fn parse_input(input: &str) -> InputType {
+    parse_input_with_prefix(input, DEFAULT_PREFIX)
+}
+
+/// Like `parse_input`, for input where commands start with `prefix`.
+///
+/// Doubling the prefix (`//etc/hosts`) or escaping it with a backslash
+/// (`\/etc/hosts`) sends the text as a message starting with one prefix.
+pub fn parse_input_with_prefix<'a>(input: &str, prefix: &str) -> InputType<'a> {
     let trimmed = input.trim();
 
-    if trimmed.starts_with('/') {
-        if let Some(parsed) = parse_command(trimmed) {
-            return InputType::Command(parsed);
-        }
+    let escaped = trimmed
+        .strip_prefix(prefix)
+        .filter(|rest| rest.starts_with(prefix))
+        .or_else(|| {
+            trimmed
+                .strip_prefix('\\')
+                .filter(|rest| rest.starts_with(prefix))
+        });
+    if let Some(message) = escaped {
+        return InputType::Message(message.to_string());
+    }
+
+    if let Some(parsed) = parse_command(trimmed, prefix) {
+        return InputType::Command(parsed);
     }
 
     InputType::Message(trimmed.to_string())
 }
 
-fn parse_command(input: &str) -> Option<ParsedCommand> {
-    let without_slash = input.strip_prefix('/')?;
-    let parts: Vec<&str> = without_slash.split_whitespace().collect();
+/// Whether `prefix` can start commands: a few characters that aren't
+/// letters, digits, spaces, quotes or backslashes, so it can't be mistaken
+/// for part of a name or an argument.
+pub fn is_valid_prefix(prefix: &str) -> bool {
+    !prefix.is_empty()
+        && prefix.chars().count() <= MAX_PREFIX_CHARS
+        && prefix
+            .chars()
+            .all(|c| !c.is_alphanumeric() && !c.is_whitespace() && !matches!(c, '"' | '\'' | '\\'))
+}
+
+fn parse_command<'a>(input: &str, prefix: &str) -> Option<ParsedCommand<'a>> {
+    let without_prefix = input.strip_prefix(prefix)?.trim_start();
+    let (name, tail) = without_prefix
+        .split_once(char::is_whitespace)
+        .unwrap_or((without_prefix, ""));
 
-    if parts.is_empty() {
+    if name.is_empty() {
         return None;
     }
 
-    let name = parts[0].to_string();
-    let args: Vec<String> = parts[1..].iter().map(|s| s.to_string()).collect();
+    let name = name.to_string();
+    // An unclosed quote keeps the plain words rather than failing here;
+    // handlers that care split `tail()` themselves
+    let args =
+        split_args(tail).unwrap_or_else(|_| tail.split_whitespace().map(str::to_string).collect());
 
     Some(ParsedCommand {
         name,
@@ -51,6 +125,93 @@ fn parse_command(input: &str) -> Option<ParsedCommand> {
     })
 }
 
+/// Splits arguments on whitespace like a shell: single or double quotes
+/// keep spaces in an argument (`"My Long Title"`) and can start mid-word
+/// (`key="a b"`). Inside quotes `\n`, `\t`, `\\` and an escaped quote are
+/// unescaped; outside them a backslash escapes a space, quote or backslash.
+/// Other backslashes are kept, so Windows paths survive.
+///
+/// A bare `--` ends the splitting: everything after it is one last
+/// argument, exactly as typed.
+pub fn split_args(input: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut arg: Option<String> = None;
+    // Whether the current argument had quotes or escapes, so `"--"` is kept
+    let mut literal = false;
+    let mut chars = input.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if let Some(arg) = arg.take() {
+                    if arg == REST_MARKER && !literal {
+                        let rest = input[i..].trim();
+                        if !rest.is_empty() {
+                            args.push(rest.to_string());
+                        }
+                        return Ok(args);
+                    }
+                    args.push(arg);
+                }
+                literal = false;
+            }
+            '"' | '\'' => {
+                literal = true;
+                let arg = arg.get_or_insert_with(String::new);
+                loop {
+                    match chars.next().map(|(_, c)| c) {
+                        None => return Err(format!("Missing closing {}", c)),
+                        Some(q) if q == c => break,
+                        Some('\\') => match chars.next().map(|(_, c)| c) {
+                            Some('n') => arg.push('\n'),
+                            Some('t') => arg.push('\t'),
+                            Some(e @ ('"' | '\'' | '\\')) => arg.push(e),
+                            Some(other) => {
+                                arg.push('\\');
+                                arg.push(other);
+                            }
+                            None => return Err(format!("Missing closing {}", c)),
+                        },
+                        Some(other) => arg.push(other),
+                    }
+                }
+            }
+            '\\' => {
+                let arg = arg.get_or_insert_with(String::new);
+                match chars.peek() {
+                    Some(&(_, e)) if e.is_whitespace() || matches!(e, '"' | '\'' | '\\') => {
+                        literal = true;
+                        arg.push(e);
+                        chars.next();
+                    }
+                    _ => arg.push('\\'),
+                }
+            }
+            other => arg.get_or_insert_with(String::new).push(other),
+        }
+    }
+    args.extend(arg.filter(|arg| arg != REST_MARKER || literal));
+    Ok(args)
+}
+
+/// The whole of `tail` as the single argument of a command that takes the
+/// rest of the line, `None` when there is nothing. A leading `--` is
+/// dropped, and a rest that is one quoted argument is unquoted, so
+/// `/cd "My Dir"` and `/cd My Dir` mean the same.
+pub fn rest_arg(tail: &str) -> Option<String> {
+    let tail = tail.trim();
+    let rest = match tail.strip_prefix(REST_MARKER) {
+        Some(rest) if rest.is_empty() || rest.starts_with(char::is_whitespace) => rest.trim(),
+        _ => tail,
+    };
+    if rest.is_empty() {
+        return None;
+    }
+    match split_args(rest).as_deref() {
+        Ok([only]) if rest.starts_with(['"', '\'']) => Some(only.clone()),
+        _ => Some(rest.to_string()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -58,7 +219,7 @@ mod tests {
     #[test]
     fn test_parse_command_simple() {
         let input = "/exit";
-        let result = parse_command(input);
+        let result = parse_command(input, DEFAULT_PREFIX);
         assert_eq!(
             result,
             Some(ParsedCommand {
@@ -74,7 +235,7 @@ mod tests {
     #[test]
     fn test_parse_command_with_args() {
         let input = "/new my-session";
-        let result = parse_command(input);
+        let result = parse_command(input, DEFAULT_PREFIX);
         assert_eq!(
             result,
             Some(ParsedCommand {
@@ -90,7 +251,7 @@ mod tests {
     #[test]
     fn test_parse_command_with_multiple_args() {
         let input = "/connect nano-gpt gpt-4";
-        let result = parse_command(input);
+        let result = parse_command(input, DEFAULT_PREFIX);
         assert_eq!(
             result,
             Some(ParsedCommand {
@@ -106,14 +267,14 @@ mod tests {
     #[test]
     fn test_parse_command_empty() {
         let input = "/";
-        let result = parse_command(input);
+        let result = parse_command(input, DEFAULT_PREFIX);
         assert_eq!(result, None);
     }
 
     #[test]
     fn test_parse_command_only_slash_and_spaces() {
         let input = "/    ";
-        let result = parse_command(input);
+        let result = parse_command(input, DEFAULT_PREFIX);
         assert_eq!(result, None);
     }
 
@@ -176,4 +337,148 @@ mod tests {
         let result = parse_input(input);
         assert_eq!(result, InputType::Message("".to_string()));
     }
+
+    fn args(input: &str) -> Vec<String> {
+        split_args(input).unwrap()
+    }
+
+    #[test]
+    fn test_split_args_quotes() {
+        assert_eq!(
+            args(r#"rename "My Long Title""#),
+            ["rename", "My Long Title"]
+        );
+        assert_eq!(args("x 'key with spaces'"), ["x", "key with spaces"]);
+        assert_eq!(
+            args(r#" "```" 'END' "\n\nUser:" plain "#),
+            ["```", "END", "\n\nUser:", "plain"]
+        );
+        // Quotes in the middle of a word, and empty ones
+        assert_eq!(args(r#"key="a b" '' """#), ["key=a b", "", ""]);
+        assert!(args("").is_empty());
+        assert!(split_args(r#""open"#).is_err());
+        assert!(split_args("'open").is_err());
+    }
+
+    #[test]
+    fn test_split_args_escapes() {
+        assert_eq!(args(r#"'say "hi"' "a\"b""#), ["say \"hi\"", "a\"b"]);
+        assert_eq!(args(r#"'it\'s' "back\\slash""#), ["it's", "back\\slash"]);
+        assert_eq!(args(r#"My\ Dir \"quoted\""#), ["My Dir", "\"quoted\""]);
+        assert_eq!(args(r"C:\Users\me"), [r"C:\Users\me"]);
+        assert_eq!(args(r#""C:\Users""#), [r"C:\Users"]);
+    }
+
+    #[test]
+    fn test_parse_command_with_quoted_args() {
+        let parsed =
+            parse_command(r#"/connect nano-gpt "key with spaces""#, DEFAULT_PREFIX).unwrap();
+        assert_eq!(parsed.name, "connect");
+        assert_eq!(parsed.args, ["nano-gpt", "key with spaces"]);
+        assert_eq!(parsed.tail(), r#"nano-gpt "key with spaces""#);
+
+        // An unclosed quote falls back to the plain words
+        let parsed = parse_command(r#"/stop "END"#, DEFAULT_PREFIX).unwrap();
+        assert_eq!(parsed.args, ["\"END"]);
+        assert_eq!(parse_command("/exit", DEFAULT_PREFIX).unwrap().tail(), "");
+    }
+
+    #[test]
+    fn test_prefix_is_the_one_typed() {
+        assert_eq!(
+            parse_command("/exit", DEFAULT_PREFIX).unwrap().prefix(),
+            "/"
+        );
+        assert_eq!(parse_command(";;new x", ";;").unwrap().prefix(), ";;");
+    }
+
+    #[test]
+    fn test_split_args_rest_marker() {
+        assert_eq!(
+            args(r#"remove -- my  "long" label "#),
+            ["remove", r#"my  "long" label"#]
+        );
+        assert_eq!(args("a -- "), ["a"]);
+        assert_eq!(args("a --"), ["a"]);
+        // Only a bare `--` ends splitting
+        assert_eq!(args(r#"--flag "--" '--' b"#), ["--flag", "--", "--", "b"]);
+    }
+
+    #[test]
+    fn test_rest_arg() {
+        assert_eq!(
+            rest_arg("  My  Long Title "),
+            Some("My  Long Title".to_string())
+        );
+        assert_eq!(rest_arg("-- it's here"), Some("it's here".to_string()));
+        assert_eq!(rest_arg(r#""My Dir""#), Some("My Dir".to_string()));
+        assert_eq!(rest_arg(r#""a" b"#), Some(r#""a" b"#.to_string()));
+        assert_eq!(
+            rest_arg(r"C:\Program Files"),
+            Some(r"C:\Program Files".to_string())
+        );
+        assert_eq!(rest_arg("--verbose"), Some("--verbose".to_string()));
+        assert_eq!(rest_arg(" -- "), None);
+        assert_eq!(rest_arg(""), None);
+
+        let parsed = parse_command("/cd my 'odd dir", DEFAULT_PREFIX)
+            .unwrap()
+            .with_rest_arg();
+        assert_eq!(parsed.args, ["my 'odd dir"]);
+    }
+
+    #[test]
+    fn test_parse_input_with_another_prefix() {
+        let InputType::Command(parsed) = parse_input_with_prefix(":cd -- My Dir", ":") else {
+            panic!("not a command");
+        };
+        assert_eq!(parsed.name, "cd");
+        assert_eq!(parsed.tail(), "-- My Dir");
+
+        // The usual slash is then just text
+        assert_eq!(
+            parse_input_with_prefix("/usr/bin is on PATH", ":"),
+            InputType::Message("/usr/bin is on PATH".to_string())
+        );
+        assert!(matches!(
+            parse_input_with_prefix(";;models", ";;"),
+            InputType::Command(parsed) if parsed.name == "models"
+        ));
+    }
+
+    #[test]
+    fn test_valid_prefixes() {
+        for prefix in ["/", ":", ";;", "!"] {
+            assert!(is_valid_prefix(prefix), "{}", prefix);
+        }
+        for prefix in ["", "c", " /", "\"", "\\", "::::"] {
+            assert!(!is_valid_prefix(prefix), "{:?}", prefix);
+        }
+    }
+
+    #[test]
+    fn test_escaped_prefix_is_a_message() {
+        assert_eq!(
+            parse_input("//not-a-command"),
+            InputType::Message("/not-a-command".to_string())
+        );
+        assert_eq!(
+            parse_input(r"\/etc/hosts has the wrong entry"),
+            InputType::Message("/etc/hosts has the wrong entry".to_string())
+        );
+        assert_eq!(
+            parse_input_with_prefix("::q", ":"),
+            InputType::Message(":q".to_string())
+        );
+        assert_eq!(
+            parse_input_with_prefix(r"\;;models", ";;"),
+            InputType::Message(";;models".to_string())
+        );
+        // Only the prefix is escaped
+        assert_eq!(
+            parse_input(r"\n is a newline"),
+            InputType::Message(r"\n is a newline".to_string())
+        );
+        assert!(matches!(parse_input("/models"), InputType::Command(_)));
+    }
 }
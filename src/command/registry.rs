@@ -1,8 +1,20 @@
-use crate::command::parser::ParsedCommand;
+use crate::command::aliases::CommandAliases;
+use crate::command::parser::{parse_input_with_prefix, InputType, ParsedCommand};
 use crate::session::manager::SessionManager;
+use nucleo_matcher::{
+    pattern::{Atom, AtomKind, CaseMatching, Normalization},
+    Config, Matcher, Utf32Str,
+};
 use std::collections::HashMap;
 use std::pin::Pin;
 
+/// Letters a mistyped command can be missing or have extra and still be
+/// suggested.
+const MAX_TYPO_CHARS: usize = 2;
+
+/// Suggestions listed for a command that could be a typo of several.
+const MAX_SUGGESTIONS: usize = 3;
+
 pub type CommandHandler =
     for<'a> fn(
         &'a ParsedCommand<'a>,
@@ -14,6 +26,9 @@ pub struct Command {
     pub name: String,
     pub description: String,
     pub handler: CommandHandler,
+    /// Gets everything after its name as one argument, spaces and quotes
+    /// included, instead of split words
+    pub takes_rest: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -24,6 +39,39 @@ pub enum CommandResult {
         title: String,
         items: Vec<DialogItem>,
     },
+    /// Ask the user to confirm before running `command`.
+    Confirm {
+        title: String,
+        message: String,
+        command: String,
+    },
+    /// Switch the working directory used by tools and the system prompt.
+    /// `path` is as typed and still has to be resolved by the caller.
+    ChangeDirectory(String),
+    /// Write out what was sent and received in the last turn.
+    DebugLastTurn,
+    /// Show or drop pastes attached to the input.
+    PastedContent(PasteAction),
+    /// Show a session the manager has already switched to, sending
+    /// `resend` as the first new message.
+    OpenSession {
+        id: String,
+        resend: Option<String>,
+    },
+    /// Scroll the chat to the message at this index of the current session,
+    /// loading older messages first if needed.
+    ScrollToMessage(usize),
+    /// Read preferences, themes, `.env` and connected providers again.
+    Reload,
+    /// List recent toasts in an overlay.
+    ShowNotifications,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PasteAction {
+    List,
+    /// Drop the attachment at this index, or the last one
+    Remove(Option<usize>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -38,12 +86,45 @@ pub struct DialogItem {
 
 pub struct Registry {
     commands: HashMap<String, Command>,
+    aliases: CommandAliases,
 }
 
 impl Registry {
     pub fn new() -> Self {
         Self {
             commands: HashMap::new(),
+            aliases: CommandAliases::default(),
+        }
+    }
+
+    /// Replaces the user's aliases, leaving out the ones that can't be
+    /// used. Returns why each of those was left out.
+    pub fn set_aliases(&mut self, mut aliases: CommandAliases) -> Vec<String> {
+        let problems = aliases.validate(|name| self.commands.contains_key(name));
+        self.aliases = aliases;
+        problems
+    }
+
+    /// Rewrites an aliased command into the one it stands for, so callers
+    /// see the real name before dispatching it.
+    pub fn resolve_alias(&self, parsed: &mut ParsedCommand<'_>) {
+        let Some(line) = self
+            .aliases
+            .resolve(&format!("{} {}", parsed.name, parsed.tail()), |name| {
+                self.commands.contains_key(name)
+            })
+        else {
+            return;
+        };
+        // Parsed again so quotes in the alias and the typed arguments both
+        // count, and `raw` reads as if the expansion had been typed
+        let prefix = parsed.prefix().to_string();
+        if let InputType::Command(expanded) =
+            parse_input_with_prefix(&format!("{}{}", prefix, line), &prefix)
+        {
+            parsed.name = expanded.name;
+            parsed.args = expanded.args;
+            parsed.raw = expanded.raw;
         }
     }
 
@@ -61,12 +142,68 @@ impl Registry {
         session_manager: &'a mut SessionManager,
     ) -> CommandResult {
         if let Some(command) = self.get(&parsed.name) {
-            (command.handler)(parsed, session_manager).await
-        } else {
-            CommandResult::Error(format!("Unknown command: {}", parsed.name))
+            if command.takes_rest {
+                let parsed = parsed.with_rest_arg();
+                return (command.handler)(&parsed, session_manager).await;
+            }
+            return (command.handler)(parsed, session_manager).await;
+        }
+
+        let suggestions: Vec<String> = self
+            .suggestions(&parsed.name)
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|name| format!("{}{}", parsed.prefix(), name))
+            .collect();
+        match suggestions.as_slice() {
+            [] => CommandResult::Error(format!("Unknown command: {}", parsed.name)),
+            [only] => CommandResult::Error(format!(
+                "Unknown command: {}. Did you mean {}?",
+                parsed.name, only
+            )),
+            [rest @ .., last] => CommandResult::Error(format!(
+                "Unknown command: {}. Did you mean {} or {}?",
+                parsed.name,
+                rest.join(", "),
+                last
+            )),
         }
     }
 
+    /// Commands `name` could be a typo of, closest first: ones it fuzzy
+    /// matches with up to `MAX_TYPO_CHARS` letters left out, or that match
+    /// it with as many extra.
+    pub fn suggestions(&self, name: &str) -> Vec<&str> {
+        let mut matcher = Matcher::new(Config::DEFAULT);
+        let mut buf = Vec::new();
+        let mut score = |needle: &str, haystack: &str| {
+            Atom::new(
+                needle,
+                CaseMatching::Ignore,
+                Normalization::Smart,
+                AtomKind::Fuzzy,
+                false,
+            )
+            .score(Utf32Str::new(haystack, &mut buf), &mut matcher)
+        };
+
+        let typed = name.chars().count();
+        if typed == 0 {
+            return Vec::new();
+        }
+        let mut scored: Vec<(&str, u16)> = self
+            .commands
+            .keys()
+            .filter(|command| command.chars().count().abs_diff(typed) <= MAX_TYPO_CHARS)
+            .filter_map(|command| {
+                let score = score(name, command).or_else(|| score(command, name))?;
+                Some((command.as_str(), score))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        scored.into_iter().map(|(command, _)| command).collect()
+    }
+
     pub fn list_commands(&self) -> Vec<&Command> {
         self.commands.values().collect()
     }
@@ -87,6 +224,14 @@ impl Default for Registry {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::command::parser::parse_input;
+
+    fn parsed_command(input: &str) -> ParsedCommand<'_> {
+        match parse_input(input) {
+            InputType::Command(parsed) => parsed,
+            InputType::Message(text) => panic!("not a command: {}", text),
+        }
+    }
 
     fn dummy_handler<'a>(
         _parsed: &'a ParsedCommand<'a>,
@@ -132,6 +277,7 @@ mod tests {
             name: "test".to_string(),
             description: "Test command".to_string(),
             handler: dummy_handler,
+            takes_rest: false,
         };
         registry.register(command);
         assert_eq!(registry.commands.len(), 1);
@@ -144,6 +290,7 @@ mod tests {
             name: "test".to_string(),
             description: "Test command".to_string(),
             handler: dummy_handler,
+            takes_rest: false,
         };
         registry.register(command.clone());
 
@@ -166,16 +313,11 @@ mod tests {
             name: "test".to_string(),
             description: "Test command".to_string(),
             handler: dummy_handler,
+            takes_rest: false,
         };
         registry.register(command);
 
-        let parsed = ParsedCommand {
-            name: "test".to_string(),
-            args: vec![],
-            raw: "/test".to_string(),
-            prefs_dao: None,
-            active_model_id: None,
-        };
+        let parsed = parsed_command("/test");
         let mut session_manager = SessionManager::new();
         let result = registry.execute(&parsed, &mut session_manager).await;
         assert_eq!(result, CommandResult::Success("ok".to_string()));
@@ -185,13 +327,7 @@ mod tests {
     async fn test_execute_unknown_command() {
         let registry = Registry::new();
 
-        let parsed = ParsedCommand {
-            name: "unknown".to_string(),
-            args: vec![],
-            raw: "/unknown".to_string(),
-            prefs_dao: None,
-            active_model_id: None,
-        };
+        let parsed = parsed_command("/unknown");
         let mut session_manager = SessionManager::new();
         let result = registry.execute(&parsed, &mut session_manager).await;
         assert_eq!(
@@ -200,6 +336,136 @@ mod tests {
         );
     }
 
+    fn registry_with(names: &[&str]) -> Registry {
+        let mut registry = Registry::new();
+        for name in names {
+            registry.register(Command {
+                name: name.to_string(),
+                description: String::new(),
+                handler: dummy_handler,
+                takes_rest: false,
+            });
+        }
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_mistyped_command_suggests_the_closest() {
+        let registry = registry_with(&[
+            "models",
+            "refreshmodels",
+            "sessions",
+            "new",
+            "tee",
+            "temp",
+            "stats",
+        ]);
+        assert_eq!(registry.suggestions("modls"), ["models"]);
+        assert_eq!(registry.suggestions("MODELSS"), ["models"]);
+        assert_eq!(registry.suggestions("sesions"), ["sessions"]);
+        assert_eq!(registry.suggestions("refreshmodls"), ["refreshmodels"]);
+        assert!(registry.suggestions("deploy").is_empty());
+
+        let parsed = parsed_command("/modls");
+        let mut session_manager = SessionManager::new();
+        let result = registry.execute(&parsed, &mut session_manager).await;
+        assert_eq!(
+            result,
+            CommandResult::Error("Unknown command: modls. Did you mean /models?".to_string())
+        );
+
+        // Suggestions use the prefix the command was typed with
+        let InputType::Command(parsed) = parse_input_with_prefix(";modls", ";") else {
+            panic!("expected a command");
+        };
+        let result = registry.execute(&parsed, &mut session_manager).await;
+        assert_eq!(
+            result,
+            CommandResult::Error("Unknown command: modls. Did you mean ;models?".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_alias_rewrites_the_command() {
+        let mut registry = registry_with(&["models", "new"]);
+        let problems = registry.set_aliases(CommandAliases {
+            aliases: [("m", "models gpt-4o"), ("new", "models")]
+                .into_iter()
+                .map(|(name, target)| (name.to_string(), target.to_string()))
+                .collect(),
+            allow_shadowing: false,
+        });
+        assert_eq!(problems.len(), 1);
+
+        let mut parsed = parsed_command(r#"/m extra "two words""#);
+        registry.resolve_alias(&mut parsed);
+        assert_eq!(parsed.name, "models");
+        assert_eq!(parsed.args, ["gpt-4o", "extra", "two words"]);
+        assert_eq!(parsed.tail(), r#"gpt-4o extra "two words""#);
+
+        // The refused alias leaves the built-in alone
+        let mut parsed = parsed_command("/new");
+        registry.resolve_alias(&mut parsed);
+        assert_eq!(parsed.name, "new");
+    }
+
+    fn args_handler<'a>(
+        parsed: &'a ParsedCommand<'a>,
+        _sm: &'a mut SessionManager,
+    ) -> Pin<Box<dyn std::future::Future<Output = CommandResult> + Send + 'a>> {
+        let args = parsed.args.join("|");
+        Box::pin(async move { CommandResult::Success(args) })
+    }
+
+    #[tokio::test]
+    async fn test_rest_taking_commands_get_the_line_as_typed() {
+        let mut registry = Registry::new();
+        for (name, takes_rest) in [("cd", true), ("tag", false)] {
+            registry.register(Command {
+                name: name.to_string(),
+                description: String::new(),
+                handler: args_handler,
+                takes_rest,
+            });
+        }
+        let mut sm = SessionManager::new();
+        let mut run = async |input: &str| {
+            let InputType::Command(parsed) = parse_input(input) else {
+                panic!("not a command: {}", input);
+            };
+            registry.execute(&parsed, &mut sm).await
+        };
+
+        let ok = |args: &str| CommandResult::Success(args.to_string());
+        assert_eq!(run("/cd My  Projects/it's").await, ok("My  Projects/it's"));
+        assert_eq!(run("/cd -- -weird dir").await, ok("-weird dir"));
+        assert_eq!(run("/cd").await, ok(""));
+        assert_eq!(run("/tag my label").await, ok("my|label"));
+        assert_eq!(run("/tag remove -- my label").await, ok("remove|my label"));
+    }
+
+    #[tokio::test]
+    async fn test_ambiguous_typo_lists_suggestions() {
+        let registry = registry_with(&["tee", "temp", "tag", "new"]);
+        let mut suggestions = registry.suggestions("te");
+        suggestions.sort();
+        assert_eq!(suggestions, ["tee", "temp"]);
+
+        // An exact name runs its command even when others are close to it
+        let parsed = parsed_command("/tee");
+        let mut session_manager = SessionManager::new();
+        let result = registry.execute(&parsed, &mut session_manager).await;
+        assert_eq!(result, CommandResult::Success("ok".to_string()));
+
+        let parsed = parsed_command("/te");
+        let CommandResult::Error(msg) = registry.execute(&parsed, &mut session_manager).await
+        else {
+            panic!("expected an error");
+        };
+        assert!(msg.starts_with("Unknown command: te. Did you mean /te"));
+        assert!(msg.contains(" or /"));
+    }
+
     #[test]
     fn test_list_commands() {
         let mut registry = Registry::new();
@@ -208,11 +474,13 @@ mod tests {
             name: "test1".to_string(),
             description: "Test command 1".to_string(),
             handler: dummy_handler,
+            takes_rest: false,
         };
         let command2 = Command {
             name: "test2".to_string(),
             description: "Test command 2".to_string(),
             handler: dummy_handler,
+            takes_rest: false,
         };
 
         registry.register(command1);
@@ -230,11 +498,13 @@ mod tests {
             name: "zebra".to_string(),
             description: "Test command 1".to_string(),
             handler: dummy_handler,
+            takes_rest: false,
         };
         let command2 = Command {
             name: "apple".to_string(),
             description: "Test command 2".to_string(),
             handler: dummy_handler,
+            takes_rest: false,
         };
 
         registry.register(command1);
@@ -266,16 +536,11 @@ mod tests {
             name: "test".to_string(),
             description: "Test command".to_string(),
             handler: handler_with_args,
+            takes_rest: false,
         };
         registry.register(command);
 
-        let parsed = ParsedCommand {
-            name: "test".to_string(),
-            args: vec!["arg1".to_string(), "arg2".to_string()],
-            raw: "/test arg1 arg2".to_string(),
-            prefs_dao: None,
-            active_model_id: None,
-        };
+        let parsed = parsed_command("/test arg1 arg2");
         let mut session_manager = SessionManager::new();
         let result = registry.execute(&parsed, &mut session_manager).await;
         assert_eq!(
@@ -0,0 +1,95 @@
+use async_trait::async_trait;
+use tokio_util::sync::CancellationToken;
+
+use super::ChunkSender;
+use crate::session::types::{Message, SamplingParams};
+
+/// Everything needed to stream one turn.
+pub struct StreamRequest {
+    pub provider_name: String,
+    pub model: String,
+    pub messages: Vec<Message>,
+    pub cwd: String,
+    pub sampling: SamplingParams,
+    /// The agent the turn runs as; tools that change files only preview in Plan
+    pub agent: String,
+}
+
+/// Produces the response to a turn. Implementations send chunks on `sender`
+/// as they arrive and `Cancelled` once `cancel` fires; the caller follows up
+/// with `End` or `Failed` depending on the result.
+#[async_trait]
+pub trait ChatStream: Send + Sync {
+    async fn stream(
+        &self,
+        request: StreamRequest,
+        cancel: CancellationToken,
+        sender: ChunkSender,
+    ) -> Result<(), String>;
+}
+
+/// Streams from the active provider through aisdk.
+pub struct AisdkChatStream;
+
+#[async_trait]
+impl ChatStream for AisdkChatStream {
+    async fn stream(
+        &self,
+        request: StreamRequest,
+        cancel: CancellationToken,
+        sender: ChunkSender,
+    ) -> Result<(), String> {
+        super::client::stream_llm_with_cancellation(cancel, request, sender)
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+pub enum MockOutcome {
+    Complete,
+    Fail(String),
+    /// Keep the turn open until it's cancelled
+    WaitForCancel,
+}
+
+/// Replies with canned text, for testing streaming without a provider.
+#[cfg(test)]
+pub struct MockChatStream {
+    pub chunks: Vec<String>,
+    pub outcome: MockOutcome,
+}
+
+#[cfg(test)]
+impl MockChatStream {
+    pub fn new(chunks: &[&str], outcome: MockOutcome) -> Self {
+        Self {
+            chunks: chunks.iter().map(|c| c.to_string()).collect(),
+            outcome,
+        }
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl ChatStream for MockChatStream {
+    async fn stream(
+        &self,
+        _request: StreamRequest,
+        cancel: CancellationToken,
+        sender: ChunkSender,
+    ) -> Result<(), String> {
+        for chunk in &self.chunks {
+            let _ = sender.send(super::ChunkMessage::Text(chunk.clone()));
+        }
+        match &self.outcome {
+            MockOutcome::Complete => Ok(()),
+            MockOutcome::Fail(error) => Err(error.clone()),
+            MockOutcome::WaitForCancel => {
+                cancel.cancelled().await;
+                let _ = sender.send(super::ChunkMessage::Cancelled);
+                Err("Streaming cancelled by user".to_string())
+            }
+        }
+    }
+}
@@ -48,7 +48,7 @@ impl LLMClient {
         let aisdk_messages = self.convert_messages(messages);
 
         let tool_registry = crate::tools::initialize_tool_registry().await;
-        let aisdk_tools = convert_to_aisdk_tools(&tool_registry, None).await;
+        let aisdk_tools = convert_to_aisdk_tools(&tool_registry, None, None, "aisdk").await;
 
         let provider_kind = self.provider_kind();
         let base_url = provider_kind.normalize_base_url(&self.base_url);
@@ -181,24 +181,22 @@ impl LLMClient {
 
 pub async fn stream_llm_with_cancellation(
     cancel_token: CancellationToken,
-    provider_name: String,
-    model: String,
-    messages: Vec<crate::session::types::Message>,
+    request: crate::llm::chat_stream::StreamRequest,
     sender: crate::llm::ChunkSender,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let crate::llm::chat_stream::StreamRequest {
+        provider_name,
+        model,
+        messages,
+        cwd,
+        sampling,
+        agent,
+    } = request;
     log("GOING TO STREAM");
     use std::time::Instant;
 
     let auth_dao = crate::persistence::AuthDAO::new()?;
 
-    let api_key = auth_dao.get_api_key(&provider_name)?;
-    if api_key.is_none() {
-        let _ = sender.send(crate::llm::ChunkMessage::Warning(format!(
-            "No API key configured for '{}'. Trying anyway.",
-            provider_name
-        )));
-    }
-
     let discovery = crate::model::discovery::Discovery::new()?;
 
     let providers = discovery.fetch_providers().await?;
@@ -207,8 +205,52 @@ pub async fn stream_llm_with_cancellation(
         .get(&provider_name)
         .ok_or_else(|| anyhow::anyhow!("Provider not found: {}", provider_name))?;
 
+    let api_key = match auth_dao.resolve_api_key(&provider_name, &provider.env)? {
+        Some((key, source)) => {
+            let _ = log(&format!(
+                "API key for {} from {}",
+                provider_name,
+                source.label()
+            ));
+            Some(key)
+        }
+        None => {
+            let _ = sender.send(crate::llm::ChunkMessage::Warning(format!(
+                "No API key configured for '{}'. Trying anyway.",
+                provider_name
+            )));
+            None
+        }
+    };
+
     let npm_package = &provider.npm;
     let provider_kind = ProviderKind::from_provider(&provider_name, npm_package);
+
+    let mut sampling = sampling;
+    let supports_temperature = provider.models.get(&model).is_some_and(|m| m.temperature);
+    if !supports_temperature && (sampling.temperature.is_some() || sampling.top_p.is_some()) {
+        let _ = sender.send(crate::llm::ChunkMessage::Warning(format!(
+            "{} doesn't support temperature or top-p; ignoring /temp and /top-p",
+            model
+        )));
+        sampling.temperature = None;
+        sampling.top_p = None;
+    }
+    if sampling.seed.is_some() && !provider_kind.supports_seed() {
+        let _ = sender.send(crate::llm::ChunkMessage::Warning(format!(
+            "{} doesn't take a seed; ignoring /seed",
+            provider.name
+        )));
+        sampling.seed = None;
+    }
+    let supports_reasoning = provider.models.get(&model).is_some_and(|m| m.reasoning);
+    if sampling.effort.is_some() && !supports_reasoning {
+        let _ = sender.send(crate::llm::ChunkMessage::Warning(format!(
+            "{} isn't a reasoning model; ignoring /effort",
+            model
+        )));
+        sampling.effort = None;
+    }
     let base_url = provider_kind.normalize_base_url(&provider.api);
 
     let _ = log(&format!(
@@ -220,7 +262,13 @@ pub async fn stream_llm_with_cancellation(
     let aisdk_messages = convert_messages(&messages);
 
     let tool_registry = crate::tools::initialize_tool_registry().await;
-    let aisdk_tools = convert_to_aisdk_tools(&tool_registry, Some(sender.clone())).await;
+    let aisdk_tools = convert_to_aisdk_tools(
+        &tool_registry,
+        Some(sender.clone()),
+        Some(cwd.into()),
+        &agent,
+    )
+    .await;
 
     let response = match provider_kind {
         ProviderKind::OpenAICompatible => {
@@ -242,6 +290,8 @@ pub async fn stream_llm_with_cancellation(
                 .messages(aisdk_messages)
                 .stop_when(step_count_is(15));
 
+            builder = apply_sampling(builder, &sampling);
+
             for tool in aisdk_tools {
                 builder = builder.with_tool(tool);
             }
@@ -267,6 +317,8 @@ pub async fn stream_llm_with_cancellation(
                 .messages(aisdk_messages)
                 .stop_when(step_count_is(15));
 
+            builder = apply_sampling(builder, &sampling);
+
             for tool in aisdk_tools {
                 builder = builder.with_tool(tool);
             }
@@ -292,6 +344,8 @@ pub async fn stream_llm_with_cancellation(
                 .messages(aisdk_messages)
                 .stop_when(step_count_is(15));
 
+            builder = apply_sampling(builder, &sampling);
+
             for tool in aisdk_tools {
                 builder = builder.with_tool(tool);
             }
@@ -348,12 +402,21 @@ pub async fn stream_llm_with_cancellation(
     Ok(())
 }
 
+/// Messages as the provider sees them; tool rows are only shown in the chat.
+pub fn provider_messages(
+    messages: &[crate::session::types::Message],
+) -> impl Iterator<Item = &crate::session::types::Message> {
+    messages
+        .iter()
+        .filter(|m| m.role != crate::session::types::MessageRole::Tool)
+}
+
 fn convert_messages(messages: &[crate::session::types::Message]) -> Vec<AisdkMessage> {
     use aisdk::core::Message::{Assistant, System, User};
 
     let mut aisdk_messages = Vec::new();
 
-    for msg in messages {
+    for msg in provider_messages(messages) {
         match msg.role {
             crate::session::types::MessageRole::System => {
                 aisdk_messages.push(System(msg.content.clone().into()));
@@ -381,6 +444,11 @@ enum ProviderKind {
 }
 
 impl ProviderKind {
+    /// OpenAI and OpenAI-compatible APIs accept a `seed`; Anthropic's doesn't.
+    fn supports_seed(self) -> bool {
+        !matches!(self, Self::Anthropic)
+    }
+
     fn from_provider(provider_name: &str, npm_package: &str) -> Self {
         // Dirty: But add any workaround/overrides here in case npm_package can be treated differently.
         // if provider_name == "kimi-for-coding" {
@@ -402,6 +470,73 @@ impl ProviderKind {
     }
 }
 
+/// aisdk takes temperature and top-p as whole percentages.
+fn as_percent(value: f32) -> u32 {
+    (value * 100.0).round().max(0.0) as u32
+}
+
+/// The request builder calls `SamplingParams` turn into, shared by every
+/// provider branch.
+trait SamplingOptions: Sized {
+    fn temperature(self, percent: u32) -> Self;
+    fn top_p(self, percent: u32) -> Self;
+    fn seed(self, seed: u32) -> Self;
+    fn stop_sequences(self, stops: Vec<String>) -> Self;
+    fn reasoning_effort(self, effort: crate::session::types::ReasoningEffort) -> Self;
+}
+
+impl SamplingOptions for aisdk::core::LanguageModelRequestBuilder {
+    fn temperature(self, percent: u32) -> Self {
+        aisdk::core::LanguageModelRequestBuilder::temperature(self, percent)
+    }
+
+    fn top_p(self, percent: u32) -> Self {
+        aisdk::core::LanguageModelRequestBuilder::top_p(self, percent)
+    }
+
+    fn seed(self, seed: u32) -> Self {
+        aisdk::core::LanguageModelRequestBuilder::seed(self, seed)
+    }
+
+    fn stop_sequences(self, stops: Vec<String>) -> Self {
+        aisdk::core::LanguageModelRequestBuilder::stop_sequences(self, stops)
+    }
+
+    fn reasoning_effort(self, effort: crate::session::types::ReasoningEffort) -> Self {
+        use crate::session::types::ReasoningEffort;
+        let effort = match effort {
+            ReasoningEffort::Low => aisdk::core::ReasoningEffort::Low,
+            ReasoningEffort::Medium => aisdk::core::ReasoningEffort::Medium,
+            ReasoningEffort::High => aisdk::core::ReasoningEffort::High,
+        };
+        aisdk::core::LanguageModelRequestBuilder::reasoning_effort(self, effort)
+    }
+}
+
+/// Sets whatever the session overrides; anything unset (including an empty
+/// stop list) is left to the provider.
+fn apply_sampling<B: SamplingOptions>(
+    mut builder: B,
+    sampling: &crate::session::types::SamplingParams,
+) -> B {
+    if let Some(temperature) = sampling.temperature {
+        builder = builder.temperature(as_percent(temperature));
+    }
+    if let Some(top_p) = sampling.top_p {
+        builder = builder.top_p(as_percent(top_p));
+    }
+    if let Some(seed) = sampling.seed {
+        builder = builder.seed(seed);
+    }
+    if !sampling.stop.is_empty() {
+        builder = builder.stop_sequences(sampling.stop.clone());
+    }
+    if let Some(effort) = sampling.effort {
+        builder = builder.reasoning_effort(effort);
+    }
+    builder
+}
+
 fn normalize_anthropic_base_url(base_url: &str) -> String {
     let trimmed = base_url.trim_end_matches('/');
     if trimmed.ends_with("/v1") {
@@ -410,3 +545,67 @@ fn normalize_anthropic_base_url(base_url: &str) -> String {
         trimmed.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::types::{ReasoningEffort, SamplingParams};
+
+    /// Records the calls `apply_sampling` makes.
+    #[derive(Default)]
+    struct RecordingBuilder(Vec<String>);
+
+    impl SamplingOptions for RecordingBuilder {
+        fn temperature(mut self, percent: u32) -> Self {
+            self.0.push(format!("temperature {}", percent));
+            self
+        }
+
+        fn top_p(mut self, percent: u32) -> Self {
+            self.0.push(format!("top_p {}", percent));
+            self
+        }
+
+        fn seed(mut self, seed: u32) -> Self {
+            self.0.push(format!("seed {}", seed));
+            self
+        }
+
+        fn stop_sequences(mut self, stops: Vec<String>) -> Self {
+            self.0.push(format!("stop {:?}", stops));
+            self
+        }
+
+        fn reasoning_effort(mut self, effort: ReasoningEffort) -> Self {
+            self.0.push(format!("effort {}", effort.label()));
+            self
+        }
+    }
+
+    #[test]
+    fn test_stop_sequences_reach_builder() {
+        let sampling = SamplingParams {
+            temperature: Some(0.7),
+            stop: vec!["```".to_string(), "\nUser:".to_string()],
+            ..SamplingParams::default()
+        };
+        let builder = apply_sampling(RecordingBuilder::default(), &sampling);
+        assert_eq!(builder.0, ["temperature 70", r#"stop ["```", "\nUser:"]"#]);
+    }
+
+    #[test]
+    fn test_effort_reaches_builder() {
+        let sampling = SamplingParams {
+            effort: Some(ReasoningEffort::High),
+            ..SamplingParams::default()
+        };
+        let builder = apply_sampling(RecordingBuilder::default(), &sampling);
+        assert_eq!(builder.0, ["effort high"]);
+    }
+
+    #[test]
+    fn test_default_sampling_sets_nothing() {
+        let builder = apply_sampling(RecordingBuilder::default(), &SamplingParams::default());
+        assert!(builder.0.is_empty());
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use super::ChunkMessage;
+use crate::session::types::{Message, MessageRole};
+
+/// Chunks kept per turn; older ones are dropped first.
+pub const MAX_LOGGED_CHUNKS: usize = 2000;
+
+const REDACTED: &str = "[redacted]";
+
+/// What was sent to the provider and received back for one turn, for
+/// `/debug last`.
+#[derive(Debug, Clone)]
+pub struct TurnLog {
+    pub provider: String,
+    pub model: String,
+    request: Vec<(String, String)>,
+    chunks: VecDeque<String>,
+    dropped_chunks: usize,
+}
+
+impl TurnLog {
+    /// Records the request as the provider receives it, i.e. after the
+    /// system prompt was added and without UI-only tool rows.
+    pub fn new(provider: &str, model: &str, messages: &[Message]) -> Self {
+        Self {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            request: super::client::provider_messages(messages)
+                .map(|m| (role_name(&m.role).to_string(), m.content.clone()))
+                .collect(),
+            chunks: VecDeque::new(),
+            dropped_chunks: 0,
+        }
+    }
+
+    pub fn record(&mut self, chunk: &ChunkMessage) {
+        if self.chunks.len() >= MAX_LOGGED_CHUNKS {
+            self.chunks.pop_front();
+            self.dropped_chunks += 1;
+        }
+        self.chunks.push_back(describe_chunk(chunk));
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len() + self.dropped_chunks
+    }
+
+    /// The whole turn as text, with anything that looks like a credential
+    /// (or is one of `known_secrets`) masked.
+    pub fn render(&self, known_secrets: &[String]) -> String {
+        let mut out = format!("Provider: {}\nModel: {}\n\n", self.provider, self.model);
+
+        out.push_str(&format!(
+            "=== Request ({} messages) ===\n",
+            self.request.len()
+        ));
+        for (i, (role, content)) in self.request.iter().enumerate() {
+            out.push_str(&format!("\n[{}] {}\n{}\n", i, role, content));
+        }
+
+        out.push_str(&format!("\n=== Response ({} chunks", self.chunk_count()));
+        if self.dropped_chunks > 0 {
+            out.push_str(&format!(", first {} not kept", self.dropped_chunks));
+        }
+        out.push_str(") ===\n");
+        for chunk in &self.chunks {
+            out.push_str(chunk);
+            out.push('\n');
+        }
+
+        mask_secrets(&out, known_secrets)
+    }
+}
+
+fn role_name(role: &MessageRole) -> &'static str {
+    match role {
+        MessageRole::System => "system",
+        MessageRole::User => "user",
+        MessageRole::Assistant => "assistant",
+        MessageRole::Tool => "tool",
+    }
+}
+
+fn describe_chunk(chunk: &ChunkMessage) -> String {
+    let json = |value: Result<String, serde_json::Error>| value.unwrap_or_default();
+    match chunk {
+        ChunkMessage::Text(text) => format!("text {:?}", text),
+        ChunkMessage::Reasoning(text) => format!("reasoning {:?}", text),
+        ChunkMessage::Warning(text) => format!("warning {:?}", text),
+        ChunkMessage::ToolCalls(calls) => {
+            format!("tool_calls {}", json(serde_json::to_string(calls)))
+        }
+        ChunkMessage::ToolResult(result) => {
+            format!("tool_result {}", json(serde_json::to_string(result)))
+        }
+        ChunkMessage::ToolOutput { tool_call_id, text } => {
+            format!("tool_output {} {:?}", tool_call_id, text)
+        }
+        ChunkMessage::End => "end".to_string(),
+        ChunkMessage::Failed(error) => format!("failed {:?}", error),
+        ChunkMessage::Cancelled => "cancelled".to_string(),
+        ChunkMessage::Metrics {
+            token_count,
+            duration_ms,
+        } => format!("metrics tokens={} duration_ms={}", token_count, duration_ms),
+    }
+}
+
+fn secret_patterns() -> &'static [(Regex, &'static str)] {
+    static PATTERNS: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            // Provider key formats: OpenAI/Anthropic, Google, GitHub
+            (r"sk-[A-Za-z0-9_\-]{16,}", ""),
+            (r"AIza[0-9A-Za-z_\-]{20,}", ""),
+            (r"gh[pousr]_[A-Za-z0-9]{20,}", ""),
+            // Values after an auth header or a key-like name
+            (r"(?i)(bearer\s+)[A-Za-z0-9._\-]{8,}", "$1"),
+            (
+                r#"(?i)((?:api[_-]?key|secret|token|password)["']?\s*[:=]\s*["']?)[^\s"',}]{8,}"#,
+                "$1",
+            ),
+        ]
+        .into_iter()
+        .map(|(pattern, keep)| (Regex::new(pattern).unwrap(), keep))
+        .collect()
+    })
+}
+
+/// Replaces credentials in `text`: every value in `known` (keys from
+/// auth.json and the environment) plus anything matching common key
+/// formats.
+pub fn mask_secrets(text: &str, known: &[String]) -> String {
+    let mut masked = text.to_string();
+    for secret in known.iter().filter(|s| s.len() >= 8) {
+        masked = masked.replace(secret.as_str(), REDACTED);
+    }
+    for (pattern, keep) in secret_patterns() {
+        masked = pattern
+            .replace_all(&masked, format!("{}{}", keep, REDACTED).as_str())
+            .into_owned();
+    }
+    masked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_request_and_chunks() {
+        let messages = vec![
+            Message::system("You are helpful"),
+            Message::user("hi"),
+            Message::tool(r#"{"name":"read"}"#),
+        ];
+        let mut log = TurnLog::new("openai", "gpt-a", &messages);
+        log.record(&ChunkMessage::Text("Hel".into()));
+        log.record(&ChunkMessage::Text("lo".into()));
+        log.record(&ChunkMessage::End);
+
+        let text = log.render(&[]);
+        assert!(text.contains("Model: gpt-a"));
+        // Tool rows never reach the provider
+        assert!(text.contains("=== Request (2 messages) ==="));
+        assert!(text.contains("[0] system\nYou are helpful"));
+        assert!(text.contains("=== Response (3 chunks) ==="));
+        assert!(text.contains("text \"Hel\"\ntext \"lo\"\nend"));
+    }
+
+    #[test]
+    fn test_chunks_are_a_ring_buffer() {
+        let mut log = TurnLog::new("p", "m", &[]);
+        for i in 0..MAX_LOGGED_CHUNKS + 3 {
+            log.record(&ChunkMessage::Text(i.to_string()));
+        }
+
+        assert_eq!(log.chunk_count(), MAX_LOGGED_CHUNKS + 3);
+        let text = log.render(&[]);
+        assert!(text.contains("first 3 not kept"));
+        assert!(!text.contains("text \"2\"\n"));
+        assert!(text.contains("text \"3\"\n"));
+    }
+
+    #[test]
+    fn test_mask_secrets() {
+        let text = concat!(
+            "key sk-ant-REDACTED\n",
+            "Authorization: Bearer abc.def.ghi123\n",
+            "{\"api_key\": \"hunter2hunter2\"}\n",
+            "custom my-own-secret-value\n",
+            "nothing to see here"
+        );
+        let masked = mask_secrets(text, &["my-own-secret-value".to_string()]);
+
+        assert!(!masked.contains("sk-ant-REDACTED"));
+        assert!(masked.contains("Bearer [redacted]"));
+        assert!(masked.contains("\"api_key\": \"[redacted]\""));
+        assert!(masked.contains("custom [redacted]"));
+        assert!(masked.contains("nothing to see here"));
+    }
+}
@@ -0,0 +1,119 @@
+/// Overrides the hard cap on a single message, in estimated tokens.
+/// `0` disables the cap.
+pub const MAX_INPUT_TOKENS_ENV: &str = "CRABCODE_MAX_INPUT_TOKENS";
+
+/// Rough token estimate (~4 characters per token), matching the estimate
+/// used for streaming metrics.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RequestSize {
+    Ok,
+    /// The whole request (history + message) likely exceeds the context
+    /// window. Still sent since the estimate is approximate.
+    OverContext {
+        estimated: usize,
+        limit: usize,
+    },
+    /// The message alone exceeds the hard cap and is refused.
+    OverHardCap {
+        estimated: usize,
+        cap: usize,
+    },
+}
+
+/// Hard cap for a single message: the env override when set, otherwise the
+/// model's context limit since a message larger than that can never fit.
+pub fn hard_cap(context_limit: Option<u32>) -> Option<usize> {
+    match std::env::var(MAX_INPUT_TOKENS_ENV)
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+    {
+        Some(0) => None,
+        Some(cap) => Some(cap),
+        None => context_limit.filter(|&l| l > 0).map(|l| l as usize),
+    }
+}
+
+pub fn check_request_size(
+    message_tokens: usize,
+    request_tokens: usize,
+    context_limit: Option<u32>,
+    hard_cap: Option<usize>,
+) -> RequestSize {
+    if let Some(cap) = hard_cap {
+        if message_tokens > cap {
+            return RequestSize::OverHardCap {
+                estimated: message_tokens,
+                cap,
+            };
+        }
+    }
+
+    match context_limit.filter(|&l| l > 0) {
+        Some(limit) if request_tokens > limit as usize => RequestSize::OverContext {
+            estimated: request_tokens,
+            limit: limit as usize,
+        },
+        _ => RequestSize::Ok,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abc"), 1);
+        assert_eq!(estimate_tokens("abcdefgh"), 2);
+        assert_eq!(estimate_tokens("🦀🦀🦀🦀🦀"), 2);
+    }
+
+    #[test]
+    fn test_check_request_size_ok() {
+        assert_eq!(
+            check_request_size(10, 100, Some(1000), Some(1000)),
+            RequestSize::Ok
+        );
+        assert_eq!(check_request_size(10, 100, None, None), RequestSize::Ok);
+    }
+
+    #[test]
+    fn test_check_request_size_over_context() {
+        assert_eq!(
+            check_request_size(10, 1500, Some(1000), Some(1000)),
+            RequestSize::OverContext {
+                estimated: 1500,
+                limit: 1000
+            }
+        );
+    }
+
+    #[test]
+    fn test_check_request_size_over_hard_cap() {
+        assert_eq!(
+            check_request_size(2000, 2500, Some(1000), Some(1000)),
+            RequestSize::OverHardCap {
+                estimated: 2000,
+                cap: 1000
+            }
+        );
+        // Without a cap an oversized message is only warned about
+        assert_eq!(
+            check_request_size(2000, 2500, Some(1000), None),
+            RequestSize::OverContext {
+                estimated: 2500,
+                limit: 1000
+            }
+        );
+    }
+
+    #[test]
+    fn test_zero_context_limit_is_unknown() {
+        assert_eq!(check_request_size(10, 5000, Some(0), None), RequestSize::Ok);
+    }
+}
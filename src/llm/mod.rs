@@ -1,5 +1,12 @@
+pub mod chat_stream;
 pub mod client;
+pub mod debug_log;
+pub mod limits;
+pub mod plain;
 pub mod provider;
+pub mod provider_error;
+pub mod stream_state;
+pub mod tee;
 pub mod tool_calls;
 
 pub use client::LLMClient;
@@ -13,6 +20,11 @@ pub enum ChunkMessage {
     Warning(String),
     ToolCalls(Vec<ToolCall>),
     ToolResult(ToolCallResult),
+    /// Output a tool printed so far, sent while it's still running
+    ToolOutput {
+        tool_call_id: String,
+        text: String,
+    },
     End,
     Failed(String),
     Cancelled,
@@ -24,3 +36,88 @@ pub enum ChunkMessage {
 
 pub type ChunkSender = mpsc::UnboundedSender<ChunkMessage>;
 pub type ChunkReceiver = mpsc::UnboundedReceiver<ChunkMessage>;
+
+/// Merges consecutive `Text` (and `Reasoning`, and a call's `ToolOutput`)
+/// chunks drained in one tick so the chat appends and re-lays out once per
+/// run instead of once per chunk.
+pub fn coalesce_chunks(chunks: Vec<ChunkMessage>) -> Vec<ChunkMessage> {
+    let mut out: Vec<ChunkMessage> = Vec::with_capacity(chunks.len());
+    for chunk in chunks {
+        match (out.last_mut(), chunk) {
+            (Some(ChunkMessage::Text(prev)), ChunkMessage::Text(text)) => prev.push_str(&text),
+            (Some(ChunkMessage::Reasoning(prev)), ChunkMessage::Reasoning(text)) => {
+                prev.push_str(&text)
+            }
+            (
+                Some(ChunkMessage::ToolOutput {
+                    tool_call_id: prev_id,
+                    text: prev,
+                }),
+                ChunkMessage::ToolOutput { tool_call_id, text },
+            ) if *prev_id == tool_call_id => prev.push_str(&text),
+            (_, chunk) => out.push(chunk),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coalesce_consecutive_text() {
+        let chunks = vec![
+            ChunkMessage::Text("Hel".to_string()),
+            ChunkMessage::Text("lo ".to_string()),
+            ChunkMessage::Text("🦀".to_string()),
+        ];
+        let out = coalesce_chunks(chunks);
+        assert_eq!(out.len(), 1);
+        assert!(matches!(&out[0], ChunkMessage::Text(t) if t == "Hello 🦀"));
+    }
+
+    #[test]
+    fn test_coalesce_keeps_order_across_other_chunks() {
+        let chunks = vec![
+            ChunkMessage::Reasoning("think".to_string()),
+            ChunkMessage::Reasoning("ing".to_string()),
+            ChunkMessage::Text("a".to_string()),
+            ChunkMessage::Text("b".to_string()),
+            ChunkMessage::ToolCalls(Vec::new()),
+            ChunkMessage::Text("c".to_string()),
+            ChunkMessage::End,
+        ];
+        let out = coalesce_chunks(chunks);
+        assert_eq!(out.len(), 5);
+        assert!(matches!(&out[0], ChunkMessage::Reasoning(t) if t == "thinking"));
+        assert!(matches!(&out[1], ChunkMessage::Text(t) if t == "ab"));
+        assert!(matches!(&out[2], ChunkMessage::ToolCalls(_)));
+        assert!(matches!(&out[3], ChunkMessage::Text(t) if t == "c"));
+        assert!(matches!(&out[4], ChunkMessage::End));
+    }
+
+    #[test]
+    fn test_coalesce_tool_output_per_call() {
+        let output = |id: &str, text: &str| ChunkMessage::ToolOutput {
+            tool_call_id: id.to_string(),
+            text: text.to_string(),
+        };
+        let chunks = vec![
+            output("call_1", "Compiling a\n"),
+            output("call_1", "Compiling b\n"),
+            output("call_2", "ok\n"),
+            output("call_1", "Finished\n"),
+        ];
+        let out = coalesce_chunks(chunks);
+        assert_eq!(out.len(), 3);
+        assert!(
+            matches!(&out[0], ChunkMessage::ToolOutput { tool_call_id, text }
+            if tool_call_id == "call_1" && text == "Compiling a\nCompiling b\n")
+        );
+        assert!(
+            matches!(&out[1], ChunkMessage::ToolOutput { tool_call_id, .. }
+            if tool_call_id == "call_2")
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use std::io::{self, Write};
+
+use super::ChunkMessage;
+
+/// Prints a turn as plain text when there's no terminal to draw on: the
+/// reply goes to `out` as it streams, tool activity and warnings to `err`,
+/// so `crabcode -p ... > file` only captures the reply.
+pub struct PlainOutput<O: Write, E: Write> {
+    out: O,
+    err: E,
+    at_line_start: bool,
+    /// Why the turn failed, once it has
+    pub failure: Option<String>,
+}
+
+impl<O: Write, E: Write> PlainOutput<O, E> {
+    pub fn new(out: O, err: E) -> Self {
+        Self {
+            out,
+            err,
+            at_line_start: true,
+            failure: None,
+        }
+    }
+
+    /// Writes `chunk` and flushes, so readers of a pipe see text as it
+    /// arrives.
+    pub fn write_chunk(&mut self, chunk: &ChunkMessage) -> io::Result<()> {
+        match chunk {
+            ChunkMessage::Text(text) if !text.is_empty() => {
+                self.out.write_all(text.as_bytes())?;
+                self.at_line_start = text.ends_with('\n');
+                self.out.flush()?;
+            }
+            ChunkMessage::ToolCalls(calls) => {
+                for call in calls {
+                    writeln!(
+                        self.err,
+                        "> tool {} {}",
+                        call.function.name,
+                        call.function.args_value()
+                    )?;
+                }
+            }
+            ChunkMessage::ToolResult(result) => {
+                let status = serde_json::from_str::<serde_json::Value>(&result.content)
+                    .ok()
+                    .and_then(|v| v.get("status")?.as_str().map(str::to_string))
+                    .unwrap_or_else(|| "ok".to_string());
+                writeln!(self.err, "> {} {}", result.name, status)?;
+            }
+            ChunkMessage::Warning(warning) => writeln!(self.err, "warning: {}", warning)?,
+            ChunkMessage::Failed(error) => self.failure = Some(error.clone()),
+            ChunkMessage::Cancelled => self.failure = Some("Cancelled".to_string()),
+            // End the reply with a newline so the shell prompt starts on its own line
+            ChunkMessage::End if !self.at_line_start => {
+                self.out.write_all(b"\n")?;
+                self.at_line_start = true;
+                self.out.flush()?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// The prompt for a headless run: `--prompt`, text piped on stdin, or both
+/// with the piped text after the prompt (`git diff | crabcode -p "review"`).
+/// `None` when neither has anything in it.
+pub fn headless_prompt(arg: Option<String>, piped: Option<String>) -> Option<String> {
+    let arg = arg.filter(|a| !a.trim().is_empty());
+    let piped = piped
+        .map(|p| p.trim_end().to_string())
+        .filter(|p| !p.trim().is_empty());
+    match (arg, piped) {
+        (Some(arg), Some(piped)) => Some(format!("{}\n\n{}", arg, piped)),
+        (arg, piped) => arg.or(piped),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{FunctionCall, ToolCall, ToolCallResult};
+
+    #[test]
+    fn test_reply_to_out_and_tools_to_err() {
+        let mut plain = PlainOutput::new(Vec::new(), Vec::new());
+        plain
+            .write_chunk(&ChunkMessage::Warning("No API key".into()))
+            .unwrap();
+        plain
+            .write_chunk(&ChunkMessage::Text("Look".into()))
+            .unwrap();
+        plain
+            .write_chunk(&ChunkMessage::ToolCalls(vec![ToolCall {
+                id: "call_1".to_string(),
+                call_type: "function".to_string(),
+                function: FunctionCall {
+                    name: "read".to_string(),
+                    arguments: r#"{"file_path":"a.rs"}"#.to_string(),
+                },
+            }]))
+            .unwrap();
+        plain
+            .write_chunk(&ChunkMessage::ToolResult(ToolCallResult {
+                tool_call_id: "call_1".to_string(),
+                role: "tool".to_string(),
+                name: "read".to_string(),
+                content: r#"{"status":"success"}"#.to_string(),
+            }))
+            .unwrap();
+        plain
+            .write_chunk(&ChunkMessage::Text("ing".into()))
+            .unwrap();
+        plain.write_chunk(&ChunkMessage::End).unwrap();
+
+        assert_eq!(String::from_utf8(plain.out).unwrap(), "Looking\n");
+        assert_eq!(
+            String::from_utf8(plain.err).unwrap(),
+            "warning: No API key\n> tool read {\"file_path\":\"a.rs\"}\n> read success\n"
+        );
+        assert!(plain.failure.is_none());
+    }
+
+    #[test]
+    fn test_failure_is_kept() {
+        let mut plain = PlainOutput::new(Vec::new(), Vec::new());
+        plain
+            .write_chunk(&ChunkMessage::Text("done\n".into()))
+            .unwrap();
+        plain.write_chunk(&ChunkMessage::End).unwrap();
+        // No second newline after one the reply already ends with
+        assert_eq!(plain.out, b"done\n");
+
+        plain
+            .write_chunk(&ChunkMessage::Failed("rate limited".into()))
+            .unwrap();
+        assert_eq!(plain.failure.as_deref(), Some("rate limited"));
+    }
+
+    #[test]
+    fn test_headless_prompt() {
+        let s = |text: &str| Some(text.to_string());
+        assert_eq!(headless_prompt(s("hi"), None), s("hi"));
+        assert_eq!(headless_prompt(None, s("from stdin\n")), s("from stdin"));
+        assert_eq!(
+            headless_prompt(s("review"), s("diff --git\n")),
+            s("review\n\ndiff --git")
+        );
+        assert_eq!(headless_prompt(s("  "), s("\n")), None);
+        assert_eq!(headless_prompt(None, None), None);
+    }
+}
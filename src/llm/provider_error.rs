@@ -0,0 +1,231 @@
+/// What went wrong with a request, read from the provider's error text so
+/// the failure can be explained with something to do about it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProviderError {
+    RateLimited,
+    InvalidKey,
+    ContextTooLong,
+    ModelNotFound,
+    /// Out of credits or over the plan's spending limit
+    InsufficientQuota,
+    /// The provider is up but too busy to take the request
+    Overloaded,
+    Unknown,
+}
+
+impl ProviderError {
+    /// Providers word the same failure differently, so this looks for the
+    /// error codes and phrases the common APIs use. Checked in order:
+    /// OpenAI reports an empty balance as a 429, for one.
+    pub fn classify(error: &str) -> Self {
+        let error = error.to_lowercase();
+        let has = |phrases: &[&str]| phrases.iter().any(|p| error.contains(p));
+
+        if has(&[
+            "insufficient_quota",
+            "insufficient quota",
+            "exceeded your current quota",
+            "credit balance is too low",
+            "insufficient credits",
+            "insufficient balance",
+            "insufficient funds",
+            "payment required",
+        ]) || has_status(&error, "402")
+        {
+            Self::InsufficientQuota
+        } else if has(&[
+            "invalid_api_key",
+            "invalid api key",
+            "incorrect api key",
+            "invalid x-api-key",
+            "api key not valid",
+            "authentication_error",
+            "unauthorized",
+            "no auth credentials",
+        ]) || has_status(&error, "401")
+        {
+            Self::InvalidKey
+        } else if has(&[
+            "context_length_exceeded",
+            "context length",
+            "maximum context",
+            "context window",
+            "prompt is too long",
+            "input is too long",
+            "too many tokens",
+            "request too large",
+        ]) || has_status(&error, "413")
+        {
+            Self::ContextTooLong
+        } else if error.contains("model_not_found")
+            || (error.contains("model")
+                && (has(&[
+                    "not found",
+                    "does not exist",
+                    "no such model",
+                    "unknown model",
+                ]) || has_status(&error, "404")))
+        {
+            Self::ModelNotFound
+        } else if has(&["rate limit", "rate_limit", "ratelimit", "too many requests"])
+            || has_status(&error, "429")
+        {
+            Self::RateLimited
+        } else if has(&["overloaded", "service unavailable", "over capacity"])
+            || has_status(&error, "503")
+            || has_status(&error, "529")
+        {
+            Self::Overloaded
+        } else {
+            Self::Unknown
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::RateLimited => "Rate limited by the provider",
+            Self::InvalidKey => "The provider rejected the API key",
+            Self::ContextTooLong => "The conversation is too long for this model",
+            Self::ModelNotFound => "The provider doesn't know this model",
+            Self::InsufficientQuota => "Out of credits with this provider",
+            Self::Overloaded => "The provider is overloaded",
+            Self::Unknown => "LLM error",
+        }
+    }
+
+    /// What to do about it, naming commands with the configured `prefix`.
+    pub fn suggestion(&self, prefix: &str) -> Option<String> {
+        let p = prefix;
+        match self {
+            Self::RateLimited => Some("wait a moment and send again".to_string()),
+            Self::InvalidKey => Some(format!("run {}connect to enter a new key", p)),
+            Self::ContextTooLong => Some(format!(
+                "start a {}new session, or pick a model with a larger context in {}models",
+                p, p
+            )),
+            Self::ModelNotFound => Some(format!("pick another one with {}models", p)),
+            Self::InsufficientQuota => {
+                Some(format!("add credits, or switch provider with {}models", p))
+            }
+            Self::Overloaded => Some(format!(
+                "try again shortly, or switch model with {}models",
+                p
+            )),
+            Self::Unknown => None,
+        }
+    }
+}
+
+/// What to show for a failed request: the explanation and what to do, or
+/// the provider's own text when it isn't one crabcode recognizes. Commands
+/// are named with `prefix`, the command prefix in use.
+pub fn describe(error: &str, prefix: &str) -> String {
+    let kind = ProviderError::classify(error);
+    match kind.suggestion(prefix) {
+        Some(suggestion) => format!("{}: {}", kind.message(), suggestion),
+        None => format!("{}: {}", kind.message(), error),
+    }
+}
+
+/// Whether `code` appears in `error` as a number of its own, so "401" is
+/// found in "status 401" but not in "took 4012ms".
+fn has_status(error: &str, code: &str) -> bool {
+    error.match_indices(code).any(|(i, _)| {
+        let before = error[..i].chars().next_back();
+        let after = error[i + code.len()..].chars().next();
+        !before.is_some_and(|c| c.is_ascii_alphanumeric())
+            && !after.is_some_and(|c| c.is_ascii_alphanumeric())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classifies_common_provider_errors() {
+        let cases = [
+            (
+                "API error: status 429 Too Many Requests: {\"error\":{\"type\":\"rate_limit_error\"}}",
+                ProviderError::RateLimited,
+            ),
+            (
+                "Rate limit reached for gpt-4o in organization org-x on tokens per min",
+                ProviderError::RateLimited,
+            ),
+            (
+                "Incorrect API key provided: sk-abc***. You can find your API key at https://platform.openai.com",
+                ProviderError::InvalidKey,
+            ),
+            (
+                "{\"type\":\"error\",\"error\":{\"type\":\"authentication_error\",\"message\":\"invalid x-api-key\"}}",
+                ProviderError::InvalidKey,
+            ),
+            (
+                "This model's maximum context length is 128000 tokens. However, your messages resulted in 130512 tokens (context_length_exceeded)",
+                ProviderError::ContextTooLong,
+            ),
+            (
+                "prompt is too long: 210432 tokens > 200000 maximum",
+                ProviderError::ContextTooLong,
+            ),
+            (
+                "The model `gpt-5-mega` does not exist or you do not have access to it.",
+                ProviderError::ModelNotFound,
+            ),
+            (
+                "HTTP 404: {\"error\":{\"code\":\"model_not_found\"}}",
+                ProviderError::ModelNotFound,
+            ),
+            (
+                "status 429: You exceeded your current quota, please check your plan and billing details (insufficient_quota)",
+                ProviderError::InsufficientQuota,
+            ),
+            (
+                "Your credit balance is too low to access the Anthropic API.",
+                ProviderError::InsufficientQuota,
+            ),
+            (
+                "{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"} (529)",
+                ProviderError::Overloaded,
+            ),
+            ("error sending request: connection refused", ProviderError::Unknown),
+        ];
+        for (error, expected) in cases {
+            assert_eq!(ProviderError::classify(error), expected, "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_status_codes_must_stand_alone() {
+        assert!(has_status("http status 401", "401"));
+        assert!(has_status("(401)", "401"));
+        assert!(!has_status("request took 4012ms", "401"));
+        assert_eq!(
+            ProviderError::classify("stream ended after 4290 tokens"),
+            ProviderError::Unknown
+        );
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(
+            describe("status 401 Unauthorized", "/"),
+            "The provider rejected the API key: run /connect to enter a new key"
+        );
+        // Errors it doesn't recognize are shown as they came
+        assert_eq!(describe("broken pipe", "/"), "LLM error: broken pipe");
+    }
+
+    #[test]
+    fn test_describe_uses_the_command_prefix() {
+        assert_eq!(
+            describe("status 401 Unauthorized", ":"),
+            "The provider rejected the API key: run :connect to enter a new key"
+        );
+        assert_eq!(
+            describe("prompt is too long: 210432 tokens > 200000 maximum", "!!"),
+            "The conversation is too long for this model: start a !!new session, or pick a model with a larger context in !!models"
+        );
+    }
+}
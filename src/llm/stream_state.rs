@@ -0,0 +1,239 @@
+use tokio::sync::mpsc::{self, error::TryRecvError};
+use tokio_util::sync::CancellationToken;
+
+use super::{ChunkMessage, ChunkReceiver, ChunkSender};
+
+/// A turn whose chunks are still being received.
+pub struct ActiveStream {
+    receiver: ChunkReceiver,
+    cancel_token: CancellationToken,
+    pub model: String,
+    pub provider: String,
+    pub started: std::time::Instant,
+    /// Save the partial reply when the cancellation arrives, as when Ctrl+C
+    /// stops the turn
+    pub keep_reply: bool,
+}
+
+/// Where the current LLM turn is. Everything a running turn needs lives in
+/// `ActiveStream`, so there's no receiver without a token or model.
+#[derive(Default)]
+pub enum StreamingState {
+    #[default]
+    Idle,
+    Active(ActiveStream),
+    /// Cancel was requested; chunks are drained until the task reports back
+    Cancelling(ActiveStream),
+}
+
+impl StreamingState {
+    /// Begins a turn for `model`, cancelling one that's still running so it
+    /// stops calling tools. Its sends fail from here on, since its receiver
+    /// is dropped. Returns the sender and token for the streaming task.
+    pub fn start(
+        &mut self,
+        model: impl Into<String>,
+        provider: impl Into<String>,
+    ) -> (ChunkSender, CancellationToken) {
+        if let Some(stream) = self.stream() {
+            stream.cancel_token.cancel();
+        }
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let cancel_token = CancellationToken::new();
+        *self = StreamingState::Active(ActiveStream {
+            receiver,
+            cancel_token: cancel_token.clone(),
+            model: model.into(),
+            provider: provider.into(),
+            started: std::time::Instant::now(),
+            keep_reply: false,
+        });
+        (sender, cancel_token)
+    }
+
+    /// Asks the running turn to stop. Returns false when there was nothing
+    /// to cancel or a cancel is already pending.
+    pub fn cancel(&mut self) -> bool {
+        match std::mem::take(self) {
+            StreamingState::Active(stream) => {
+                stream.cancel_token.cancel();
+                *self = StreamingState::Cancelling(stream);
+                true
+            }
+            other => {
+                *self = other;
+                false
+            }
+        }
+    }
+
+    /// Like `cancel`, but marks the turn so its partial reply is saved.
+    pub fn cancel_keeping_reply(&mut self) -> bool {
+        if let Some(stream) = self.stream_mut() {
+            stream.keep_reply = true;
+        }
+        self.cancel()
+    }
+
+    /// Takes the chunks received so far, up to and including the one that
+    /// ends the turn. A task that went away without saying how it ended is
+    /// reported as failed.
+    pub fn drain(&mut self) -> Vec<ChunkMessage> {
+        let mut chunks = Vec::new();
+        let Some(stream) = self.stream_mut() else {
+            return chunks;
+        };
+
+        loop {
+            match stream.receiver.try_recv() {
+                Ok(chunk) => {
+                    let ends_turn = is_terminal(&chunk);
+                    chunks.push(chunk);
+                    if ends_turn {
+                        break;
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    chunks.push(ChunkMessage::Failed(
+                        "Stream ended unexpectedly".to_string(),
+                    ));
+                    break;
+                }
+            }
+        }
+        chunks
+    }
+
+    /// Ends the turn, returning it so its model and provider can be recorded.
+    pub fn finish(&mut self) -> Option<ActiveStream> {
+        match std::mem::take(self) {
+            StreamingState::Active(stream) | StreamingState::Cancelling(stream) => Some(stream),
+            StreamingState::Idle => None,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !matches!(self, StreamingState::Idle)
+    }
+
+    pub fn is_cancelling(&self) -> bool {
+        matches!(self, StreamingState::Cancelling(_))
+    }
+
+    fn stream(&self) -> Option<&ActiveStream> {
+        match self {
+            StreamingState::Active(stream) | StreamingState::Cancelling(stream) => Some(stream),
+            StreamingState::Idle => None,
+        }
+    }
+
+    fn stream_mut(&mut self) -> Option<&mut ActiveStream> {
+        match self {
+            StreamingState::Active(stream) | StreamingState::Cancelling(stream) => Some(stream),
+            StreamingState::Idle => None,
+        }
+    }
+}
+
+pub fn is_terminal(chunk: &ChunkMessage) -> bool {
+    matches!(
+        chunk,
+        ChunkMessage::End | ChunkMessage::Failed(_) | ChunkMessage::Cancelled
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(chunk: &ChunkMessage) -> Option<&str> {
+        match chunk {
+            ChunkMessage::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_start_cancel_finish() {
+        let mut state = StreamingState::default();
+        assert!(!state.is_active());
+        assert!(!state.cancel());
+
+        let (_sender, token) = state.start("model", "provider");
+        assert!(state.is_active());
+        assert!(!state.is_cancelling());
+
+        assert!(state.cancel());
+        assert!(token.is_cancelled());
+        assert!(state.is_cancelling());
+        assert!(!state.cancel());
+
+        let stream = state.finish().unwrap();
+        assert_eq!(stream.model, "model");
+        assert_eq!(stream.provider, "provider");
+        assert!(!stream.keep_reply);
+        assert!(!state.is_active());
+        assert!(state.finish().is_none());
+    }
+
+    #[test]
+    fn test_keep_reply_belongs_to_the_cancelled_turn() {
+        let mut state = StreamingState::default();
+        assert!(!state.cancel_keeping_reply());
+
+        state.start("a", "p");
+        assert!(state.cancel_keeping_reply());
+        assert!(state.finish().unwrap().keep_reply);
+
+        // The next turn starts without it, however the last one ended
+        state.start("b", "p");
+        state.cancel();
+        assert!(!state.finish().unwrap().keep_reply);
+    }
+
+    #[test]
+    fn test_restart_cancels_previous_turn() {
+        let mut state = StreamingState::default();
+        let (old_sender, old_token) = state.start("a", "p");
+        let (sender, token) = state.start("b", "p");
+
+        assert!(old_token.is_cancelled());
+        assert!(!token.is_cancelled());
+        // The old task can no longer reach the new turn's channel
+        assert!(old_sender.send(ChunkMessage::Text("stale".into())).is_err());
+
+        sender.send(ChunkMessage::Text("fresh".into())).unwrap();
+        let chunks = state.drain();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(text(&chunks[0]), Some("fresh"));
+    }
+
+    #[test]
+    fn test_drain_stops_at_end_of_turn() {
+        let mut state = StreamingState::default();
+        let (sender, _token) = state.start("model", "provider");
+        sender.send(ChunkMessage::Text("hi".into())).unwrap();
+        sender.send(ChunkMessage::End).unwrap();
+        sender.send(ChunkMessage::Text("late".into())).unwrap();
+
+        let chunks = state.drain();
+        assert_eq!(chunks.len(), 2);
+        assert!(matches!(chunks[1], ChunkMessage::End));
+    }
+
+    #[test]
+    fn test_dropped_sender_fails_the_turn() {
+        let mut state = StreamingState::default();
+        let (sender, _token) = state.start("model", "provider");
+        sender.send(ChunkMessage::Text("partial".into())).unwrap();
+        drop(sender);
+
+        let chunks = state.drain();
+        assert_eq!(chunks.len(), 2);
+        assert!(matches!(chunks[1], ChunkMessage::Failed(_)));
+
+        assert!(StreamingState::Idle.drain().is_empty());
+    }
+}
@@ -0,0 +1,122 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use super::ChunkMessage;
+
+/// Copies a streaming turn to a file as it arrives: the assistant's text
+/// plus one line per tool call and result.
+pub struct StreamTee {
+    file: File,
+    pub path: PathBuf,
+}
+
+impl StreamTee {
+    /// Creates `<dir>/<session>-<timestamp>.md`.
+    pub fn open(dir: &Path, session: &str, timestamp: &str) -> io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!("{}-{}.md", file_stem(session), timestamp));
+        let file = File::create(&path)?;
+        Ok(Self { file, path })
+    }
+
+    /// Writes `chunk` straight through so the file is current even if the
+    /// app dies mid-turn.
+    pub fn write_chunk(&mut self, chunk: &ChunkMessage) -> io::Result<()> {
+        match chunk {
+            ChunkMessage::Text(text) => self.file.write_all(text.as_bytes()),
+            ChunkMessage::ToolCalls(calls) => {
+                for call in calls {
+                    writeln!(
+                        self.file,
+                        "\n> tool {} {}",
+                        call.function.name,
+                        call.function.args_value()
+                    )?;
+                }
+                Ok(())
+            }
+            ChunkMessage::ToolResult(result) => {
+                let status = serde_json::from_str::<serde_json::Value>(&result.content)
+                    .ok()
+                    .and_then(|v| v.get("status")?.as_str().map(str::to_string))
+                    .unwrap_or_else(|| "ok".to_string());
+                writeln!(self.file, "> {} {}\n", result.name, status)
+            }
+            ChunkMessage::Failed(error) => writeln!(self.file, "\n\n[failed: {}]", error),
+            ChunkMessage::Cancelled => writeln!(self.file, "\n\n[cancelled]"),
+            ChunkMessage::End => writeln!(self.file),
+            ChunkMessage::Reasoning(_)
+            | ChunkMessage::Warning(_)
+            | ChunkMessage::ToolOutput { .. }
+            | ChunkMessage::Metrics { .. } => Ok(()),
+        }
+    }
+}
+
+/// Keeps session titles usable as file names.
+fn file_stem(session: &str) -> String {
+    let stem: String = session
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    let stem = stem.trim_matches('-');
+    let stem: String = stem.chars().take(40).collect();
+    if stem.is_empty() {
+        "session".to_string()
+    } else {
+        stem
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{FunctionCall, ToolCall, ToolCallResult};
+
+    #[test]
+    fn test_tee_writes_text_and_tool_rows() {
+        let dir = std::env::temp_dir().join(format!("crabcode-tee-{}", std::process::id()));
+        let mut tee = StreamTee::open(&dir, "Fix the bug?", "20260101-120000").unwrap();
+        assert_eq!(
+            tee.path.file_name().unwrap(),
+            "Fix-the-bug-20260101-120000.md"
+        );
+
+        tee.write_chunk(&ChunkMessage::Text("Looking".into()))
+            .unwrap();
+        tee.write_chunk(&ChunkMessage::ToolCalls(vec![ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "read".to_string(),
+                arguments: r#"{"file_path":"a.rs"}"#.to_string(),
+            },
+        }]))
+        .unwrap();
+        tee.write_chunk(&ChunkMessage::ToolResult(ToolCallResult {
+            tool_call_id: "call_1".to_string(),
+            role: "tool".to_string(),
+            name: "read".to_string(),
+            content: r#"{"status":"error"}"#.to_string(),
+        }))
+        .unwrap();
+        tee.write_chunk(&ChunkMessage::Text("Done.".into()))
+            .unwrap();
+        tee.write_chunk(&ChunkMessage::Cancelled).unwrap();
+
+        let text = std::fs::read_to_string(&tee.path).unwrap();
+        assert_eq!(
+            text,
+            "Looking\n> tool read {\"file_path\":\"a.rs\"}\n> read error\n\nDone.\n\n[cancelled]\n"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_file_stem() {
+        assert_eq!(file_stem("session-3"), "session-3");
+        assert_eq!(file_stem("../../etc"), "etc");
+        assert_eq!(file_stem("???"), "session");
+    }
+}
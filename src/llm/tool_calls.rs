@@ -11,9 +11,174 @@ pub struct ToolCall {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FunctionCall {
     pub name: String,
+    /// Always a JSON string once parsed; providers that send an object are
+    /// normalized by `arguments_from_any`
+    #[serde(deserialize_with = "arguments_from_any")]
     pub arguments: String,
 }
 
+/// Accepts arguments as a JSON-encoded string (OpenAI style) or as the
+/// object itself (Anthropic/Gemini style). Missing arguments become `{}`.
+fn arguments_from_any<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(s) => s,
+        serde_json::Value::Null => "{}".to_string(),
+        other => other.to_string(),
+    })
+}
+
+impl FunctionCall {
+    pub fn parse_args(&self) -> Result<serde_json::Value, serde_json::Error> {
+        parse_arguments(&self.arguments)
+    }
+
+    /// Like `parse_args`, but text that can't be parsed even after repair is
+    /// kept as a plain string so it still shows up.
+    pub fn args_value(&self) -> serde_json::Value {
+        self.parse_args()
+            .unwrap_or_else(|_| serde_json::Value::String(self.arguments.clone()))
+    }
+}
+
+/// Parses tool arguments for display, running them through `repair_json`
+/// when they aren't valid as-is. Double-encoded objects are unwrapped and
+/// empty arguments mean `{}`. Never run a tool with the result: use
+/// `parse_arguments_to_run` for that.
+pub fn parse_arguments(raw: &str) -> Result<serde_json::Value, serde_json::Error> {
+    parse_arguments_with(raw, repair_json)
+}
+
+/// Like `parse_arguments`, but only with the repairs of
+/// `repair_json_losslessly`, so arguments cut off inside a value are an
+/// error rather than a shorter command or path.
+pub fn parse_arguments_to_run(raw: &str) -> Result<serde_json::Value, serde_json::Error> {
+    parse_arguments_with(raw, repair_json_losslessly)
+}
+
+fn parse_arguments_with(
+    raw: &str,
+    repair: fn(&str) -> Option<String>,
+) -> Result<serde_json::Value, serde_json::Error> {
+    if raw.trim().is_empty() {
+        return Ok(serde_json::json!({}));
+    }
+
+    let value = match serde_json::from_str(raw) {
+        Ok(value) => value,
+        Err(e) => match repair(raw) {
+            Some(repaired) => serde_json::from_str(&repaired).map_err(|_| e)?,
+            None => return Err(e),
+        },
+    };
+
+    Ok(match value {
+        serde_json::Value::String(inner) => parse_arguments_with(&inner, repair)
+            .ok()
+            .filter(serde_json::Value::is_object)
+            .unwrap_or(serde_json::Value::String(inner)),
+        value => value,
+    })
+}
+
+/// Fixes what providers get wrong in tool arguments: trailing commas, and
+/// JSON cut off mid-stream (an unclosed string, a key without a value, or
+/// unclosed objects and arrays). Returns `None` for text that doesn't start
+/// like JSON or has mismatched brackets. The result may still not parse,
+/// e.g. when it was cut off inside a key or a literal.
+pub fn repair_json(raw: &str) -> Option<String> {
+    repair_json_with(raw, false)
+}
+
+/// Only the repairs of `repair_json` that can't change a value: trailing
+/// commas, and closing the objects and arrays of JSON cut off right after a
+/// complete value. Returns `None` for JSON cut off inside a string, number
+/// or literal, or after a key.
+pub fn repair_json_losslessly(raw: &str) -> Option<String> {
+    repair_json_with(raw, true)
+}
+
+fn repair_json_with(raw: &str, lossless: bool) -> Option<String> {
+    let trimmed = raw.trim();
+    if !trimmed.starts_with('{') && !trimmed.starts_with('[') {
+        return None;
+    }
+
+    let mut out = String::with_capacity(trimmed.len() + 8);
+    let mut closers: Vec<char> = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for c in trimmed.chars() {
+        if in_string {
+            out.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '{' => closers.push('}'),
+            '[' => closers.push(']'),
+            '}' | ']' => {
+                if closers.pop() != Some(c) {
+                    return None;
+                }
+                strip_trailing_comma(&mut out);
+            }
+            _ => {}
+        }
+        out.push(c);
+    }
+
+    if lossless && (in_string || !(closers.is_empty() || ends_with_complete_value(&out))) {
+        return None;
+    }
+    if in_string {
+        if escaped {
+            out.pop();
+        }
+        out.push('"');
+    }
+    if out.trim_end().ends_with(':') {
+        out.push_str(" null");
+    }
+    strip_trailing_comma(&mut out);
+    while let Some(closer) = closers.pop() {
+        out.push(closer);
+    }
+    Some(out)
+}
+
+/// Whether `out` stops where nothing of a value can be missing: after a
+/// string, an object or array, a separator, or a literal that can't go on.
+fn ends_with_complete_value(out: &str) -> bool {
+    let out = out.trim_end();
+    out.ends_with(['"', '}', ']', ',', '{', '['])
+        || ["true", "false", "null"]
+            .iter()
+            .any(|literal| out.ends_with(literal))
+}
+
+fn strip_trailing_comma(out: &mut String) {
+    let len = out.trim_end().len();
+    if out[..len].ends_with(',') {
+        out.truncate(len - 1);
+    }
+}
+
+/// Output kept on the row of a running call; the chat only shows its last
+/// few lines.
+pub const LIVE_OUTPUT_LIMIT: usize = 4096;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolCallResult {
     pub tool_call_id: String,
@@ -23,6 +188,46 @@ pub struct ToolCallResult {
 }
 
 impl ToolCall {
+    /// The chat's tool row for a call that just started. Results from the
+    /// bridge are merged into it as they arrive. Arguments that can't be
+    /// repaired show as an error instead of a row with no arguments.
+    pub fn running_row(&self) -> serde_json::Value {
+        match self.function.parse_args() {
+            Ok(args) => serde_json::json!({
+                "id": self.id,
+                "name": self.function.name,
+                "status": "running",
+                "args": args,
+            }),
+            Err(e) => serde_json::json!({
+                "id": self.id,
+                "name": self.function.name,
+                "status": "error",
+                "args": self.function.arguments,
+                "output_preview": format!("Malformed tool arguments: {}", e),
+            }),
+        }
+    }
+
+    /// Adds output a running call printed to its row, keeping only the newest
+    /// `LIVE_OUTPUT_LIMIT` bytes.
+    pub fn append_live_output(row: &mut serde_json::Value, text: &str) {
+        let mut live = row
+            .get("live_output")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        live.push_str(text);
+        row["live_output"] = crate::utils::tail_bytes(&live, LIVE_OUTPUT_LIMIT).into();
+    }
+
+    /// Drops the output shown while the call ran, once it finished.
+    pub fn clear_live_output(row: &mut serde_json::Value) {
+        if let Some(row) = row.as_object_mut() {
+            row.remove("live_output");
+        }
+    }
+
     pub fn parse_from_json(json_str: &str) -> Result<Vec<Self>, serde_json::Error> {
         serde_json::from_str(json_str)
     }
@@ -95,10 +300,56 @@ impl ToolCall {
     }
 }
 
+/// The follow-up sent by the "explain this error" shortcut for a chat tool
+/// row. `None` unless the row failed and captured some output to go on.
+pub fn explain_error_prompt(row: &serde_json::Value) -> Option<String> {
+    if row.get("status").and_then(|v| v.as_str()) != Some("error") {
+        return None;
+    }
+    let output = row
+        .get("output_preview")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())?;
+    let name = row.get("name").and_then(|v| v.as_str()).unwrap_or("tool");
+
+    let mut prompt = format!(
+        "The `{}` tool call failed. Explain what went wrong, then fix it.\n",
+        name
+    );
+    if let Some(args) = row.get("args").filter(|args| !args.is_null()) {
+        prompt.push_str(&format!("\nArguments: {}\n", args));
+    }
+    prompt.push_str(&format!("\nOutput:\n```\n{}\n```", output));
+    Some(prompt)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_explain_error_prompt() {
+        let row = serde_json::json!({
+            "name": "bash",
+            "status": "error",
+            "args": {"command": "cargo tset"},
+            "output_preview": "error: no such command: `tset`\n",
+        });
+        assert_eq!(
+            explain_error_prompt(&row).unwrap(),
+            "The `bash` tool call failed. Explain what went wrong, then fix it.\n\n\
+             Arguments: {\"command\":\"cargo tset\"}\n\n\
+             Output:\n```\nerror: no such command: `tset`\n```"
+        );
+
+        // Only failed rows with something to explain
+        let ok = serde_json::json!({"name": "bash", "status": "ok", "output_preview": "fine"});
+        assert!(explain_error_prompt(&ok).is_none());
+        let silent = serde_json::json!({"name": "bash", "status": "error", "output_preview": " "});
+        assert!(explain_error_prompt(&silent).is_none());
+    }
+
     #[test]
     fn test_parse_tool_calls() {
         let json = r#"[{"id":"call_1","type":"function","function":{"name":"read","arguments":"{\"file_path\":\"/tmp/test.txt\"}"}}]"#;
@@ -106,4 +357,196 @@ mod tests {
         assert_eq!(calls.len(), 1);
         assert_eq!(calls[0].function.name, "read");
     }
+
+    fn call_with_args(arguments: &str) -> ToolCall {
+        ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "read".to_string(),
+                arguments: arguments.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_tool_call_round_trip() {
+        let call = call_with_args(r#"{"file_path":"/tmp/a.txt"}"#);
+        let json = serde_json::to_string(&call).unwrap();
+        assert!(json.contains(r#""type":"function""#));
+
+        let back: ToolCall = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.id, call.id);
+        assert_eq!(back.call_type, "function");
+        assert_eq!(back.function.arguments, call.function.arguments);
+
+        let result = ToolCallResult {
+            tool_call_id: "call_1".to_string(),
+            role: "tool".to_string(),
+            name: "read".to_string(),
+            content: r#"{"status":"ok"}"#.to_string(),
+        };
+        let back: ToolCallResult =
+            serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap();
+        assert_eq!(back.tool_call_id, "call_1");
+        assert_eq!(back.content, result.content);
+    }
+
+    #[test]
+    fn test_string_and_object_arguments_agree() {
+        let as_string = r#"[{"id":"a","type":"function","function":{"name":"read","arguments":"{\"file_path\":\"/tmp/a.txt\"}"}}]"#;
+        let as_object = r#"[{"id":"a","type":"function","function":{"name":"read","arguments":{"file_path":"/tmp/a.txt"}}}]"#;
+        let missing =
+            r#"[{"id":"a","type":"function","function":{"name":"read","arguments":null}}]"#;
+
+        let from_string = ToolCall::parse_from_json(as_string).unwrap();
+        let from_object = ToolCall::parse_from_json(as_object).unwrap();
+        assert_eq!(
+            from_string[0].function.args_value(),
+            from_object[0].function.args_value()
+        );
+        assert_eq!(
+            from_object[0].function.args_value()["file_path"],
+            "/tmp/a.txt"
+        );
+
+        let missing = ToolCall::parse_from_json(missing).unwrap();
+        assert_eq!(missing[0].function.args_value(), serde_json::json!({}));
+    }
+
+    #[test]
+    fn test_args_value_edge_cases() {
+        // Double-encoded object
+        let call = call_with_args(r#""{\"file_path\":\"/tmp/a.txt\"}""#);
+        assert_eq!(call.function.args_value()["file_path"], "/tmp/a.txt");
+
+        // Not JSON: kept as text instead of being dropped
+        let call = call_with_args("file_path=/tmp/a.txt");
+        assert_eq!(
+            call.function.args_value(),
+            serde_json::Value::String("file_path=/tmp/a.txt".to_string())
+        );
+
+        assert_eq!(
+            call_with_args("  ").function.args_value(),
+            serde_json::json!({})
+        );
+    }
+
+    #[test]
+    fn test_repair_recoverable_arguments() {
+        let cases = [
+            (
+                r#"{"path":"/tmp","limit":5,}"#,
+                serde_json::json!({"path": "/tmp", "limit": 5}),
+            ),
+            (
+                r#"{"paths":["a","b",],}"#,
+                serde_json::json!({"paths": ["a", "b"]}),
+            ),
+            (
+                r#"{"command":"echo hi"#,
+                serde_json::json!({"command": "echo hi"}),
+            ),
+            (
+                r#"{"command":"say \"#,
+                serde_json::json!({"command": "say "}),
+            ),
+            (r#"{"a":{"b":[1,2"#, serde_json::json!({"a": {"b": [1, 2]}})),
+            (
+                r#"{"path":"/tmp","limit":"#,
+                serde_json::json!({"path": "/tmp", "limit": null}),
+            ),
+            (
+                r#"{"text":"a, b}","n":1,"#,
+                serde_json::json!({"text": "a, b}", "n": 1}),
+            ),
+        ];
+
+        for (raw, expected) in cases {
+            assert_eq!(parse_arguments(raw).unwrap(), expected, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn test_cut_off_arguments_are_not_repaired_for_running() {
+        for raw in [
+            r#"{"command":"rm -rf /tmp/build/cache"#,
+            r#"{"path":"/tmp","limit":"#,
+            r#"{"path":"/tmp","limit":5"#,
+            r#"{"paths":["/tmp/a","/tm"#,
+        ] {
+            assert!(parse_arguments(raw).is_ok(), "{}", raw);
+            assert!(parse_arguments_to_run(raw).is_err(), "{}", raw);
+        }
+        assert_eq!(
+            parse_arguments_to_run(r#""{\"path\":\"/tmp\"}""#).unwrap(),
+            serde_json::json!({"path": "/tmp"})
+        );
+        assert_eq!(parse_arguments_to_run("").unwrap(), serde_json::json!({}));
+    }
+
+    #[test]
+    fn test_lossless_repairs_are_run() {
+        let cases = [
+            (
+                r#"{"path":"/tmp","limit":5,}"#,
+                serde_json::json!({"path": "/tmp", "limit": 5}),
+            ),
+            (
+                r#"{"paths":["a, b}",],"#,
+                serde_json::json!({"paths": ["a, b}"]}),
+            ),
+            (
+                r#"{"path":"/tmp","recursive":true"#,
+                serde_json::json!({"path": "/tmp", "recursive": true}),
+            ),
+            (r#"{"path":"/tmp""#, serde_json::json!({"path": "/tmp"})),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(parse_arguments_to_run(raw).unwrap(), expected, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn test_repair_gives_up_on_unrecoverable_arguments() {
+        assert_eq!(repair_json("not json"), None);
+        assert_eq!(repair_json(r#"{"a":[1}"#), None);
+        assert!(parse_arguments(r#"{"a":1,"b"#).is_err());
+        assert!(parse_arguments(r#"{"a":tru"#).is_err());
+
+        let row = call_with_args(r#"{"a":tru"#).running_row();
+        assert_eq!(row["status"], "error");
+        assert_eq!(row["args"], r#"{"a":tru"#);
+        assert!(row["output_preview"]
+            .as_str()
+            .unwrap()
+            .starts_with("Malformed tool arguments"));
+    }
+
+    #[test]
+    fn test_running_row_shape() {
+        let row = call_with_args(r#"{"file_path":"/tmp/a.txt"}"#).running_row();
+        assert_eq!(row["id"], "call_1");
+        assert_eq!(row["name"], "read");
+        assert_eq!(row["status"], "running");
+        assert_eq!(row["args"]["file_path"], "/tmp/a.txt");
+    }
+
+    #[test]
+    fn test_live_output_keeps_the_newest() {
+        let mut row = call_with_args("{}").running_row();
+        ToolCall::append_live_output(&mut row, "Compiling a\n");
+        ToolCall::append_live_output(&mut row, "Compiling b\n");
+        assert_eq!(row["live_output"], "Compiling a\nCompiling b\n");
+
+        ToolCall::append_live_output(&mut row, &"x".repeat(LIVE_OUTPUT_LIMIT));
+        let live = row["live_output"].as_str().unwrap();
+        assert_eq!(live.len(), LIVE_OUTPUT_LIMIT);
+        assert!(!live.contains("Compiling"));
+
+        ToolCall::clear_live_output(&mut row);
+        assert!(row.get("live_output").is_none());
+        assert_eq!(row["status"], "running");
+    }
 }
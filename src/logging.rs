@@ -2,6 +2,14 @@ use anyhow::Result;
 use chrono::Local;
 use std::fs::OpenOptions;
 use std::io::Write;
+use std::path::PathBuf;
+
+/// Log file, relative to the directory crabcode was started in
+pub const LOG_FILE: &str = "app.log";
+
+pub fn log_path() -> PathBuf {
+    std::env::current_dir().unwrap_or_default().join(LOG_FILE)
+}
 
 #[allow(unused_must_use)]
 pub fn log(message: &str) -> Result<()> {
@@ -11,7 +19,7 @@ pub fn log(message: &str) -> Result<()> {
     let mut file = OpenOptions::new()
         .create(true)
         .append(true)
-        .open("app.log")?;
+        .open(LOG_FILE)?;
 
     file.write_all(log_line.as_bytes())?;
     Ok(())
@@ -33,35 +33,95 @@ use ratatui::crossterm::{
     },
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
-use ratatui_toolkit::{render_toasts, Toast, ToastManager};
-use std::io;
-use std::sync::Mutex;
+use ratatui_toolkit::{Toast, ToastManager};
+use std::io::{self, IsTerminal, Read};
+use std::sync::{Mutex, MutexGuard};
 use std::time::Duration;
+use ui::toast::{ToastHistory, ToastRecord};
 
 lazy_static::lazy_static! {
     static ref TOAST_MANAGER: Mutex<ToastManager> = Mutex::new(ToastManager::new());
+    static ref TOAST_HISTORY: Mutex<ToastHistory> = Mutex::new(ToastHistory::default());
 }
 
+/// Shows a toast. Build it with `ui::toast::new_toast` so one without a
+/// duration stays up as long as its level's default.
 pub fn push_toast(toast: Toast) {
-    TOAST_MANAGER.lock().unwrap().add(toast);
+    TOAST_HISTORY
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .record(&toast);
+    get_toast_manager().add(toast);
+}
+
+/// Every recent toast, expired or not, newest first.
+pub fn toast_history() -> Vec<ToastRecord> {
+    TOAST_HISTORY
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .newest_first()
 }
 
 pub fn remove_expired_toasts() {
-    TOAST_MANAGER.lock().unwrap().remove_expired();
+    get_toast_manager().remove_expired();
 }
 
-pub fn get_toast_manager() -> &'static Mutex<ToastManager> {
-    &TOAST_MANAGER
+/// Changes when a toast is added or expires
+fn toast_state() -> (usize, Option<std::time::Instant>) {
+    let manager = get_toast_manager();
+    let toasts = manager.get_active();
+    (toasts.len(), toasts.last().map(|toast| toast.created_at))
+}
+
+/// Locks the toasts. Toasts are pushed from error paths all over, so a
+/// panic while they're held mustn't make every later toast panic too; the
+/// list is still usable and the lock is taken back.
+pub fn get_toast_manager() -> MutexGuard<'static, ToastManager> {
+    TOAST_MANAGER.lock().unwrap_or_else(|e| e.into_inner())
 }
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
-struct Args {}
+struct Args {
+    /// Disable colors; emphasis is shown with symbols instead
+    #[arg(long)]
+    no_color: bool,
+
+    /// Send this prompt, print the reply and exit. Text piped on stdin is
+    /// appended to it, or used as the prompt on its own
+    #[arg(short, long)]
+    prompt: Option<String>,
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let _args = Args::parse();
+    let args = Args::parse();
+    if let Ok(cwd) = std::env::current_dir() {
+        persistence::auth::load_dotenv(&cwd);
+    }
     let mut app = App::new();
+    app.repair_active_model().await;
+    if args.no_color {
+        app.disable_color();
+    }
+
+    // Drawing the TUI into a pipe would corrupt the output, so anything
+    // not attached to a terminal gets the plain-text path
+    if args.prompt.is_some() || !io::stdout().is_terminal() || !io::stdin().is_terminal() {
+        let piped = if io::stdin().is_terminal() {
+            None
+        } else {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            Some(text)
+        };
+        let Some(prompt) = llm::plain::headless_prompt(args.prompt, piped) else {
+            anyhow::bail!("No prompt: pass --prompt or pipe one on stdin");
+        };
+        let result = run_plain(&mut app, prompt).await;
+        app.shutdown().await;
+        return result;
+    }
 
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -87,6 +147,7 @@ async fn main() -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     let result = run_event_loop(&mut terminal, &mut app).await;
+    app.shutdown().await;
 
     disable_raw_mode()?;
     if supports_keyboard_enhancement().unwrap_or(false) {
@@ -110,34 +171,88 @@ async fn main() -> Result<()> {
     result
 }
 
+/// Sends one prompt and prints the reply to stdout as it streams.
+async fn run_plain(app: &mut App, prompt: String) -> Result<()> {
+    app.enable_plain_output();
+    if !app.submit_prompt(prompt) {
+        anyhow::bail!(
+            "Prompt not sent: it's over the input token limit (set {} to override)",
+            llm::limits::MAX_INPUT_TOKENS_ENV
+        );
+    }
+
+    while app.is_streaming() {
+        app.process_streaming_chunks();
+        tokio::time::sleep(Duration::from_millis(16)).await;
+    }
+    match app.plain_output_failure() {
+        Some(error) => Err(anyhow::anyhow!(error)),
+        None => Ok(()),
+    }
+}
+
 async fn run_event_loop(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     app: &mut App,
 ) -> Result<()> {
-    // Use a shorter poll duration for smoother animations (16ms = ~60fps max)
-    const POLL_DURATION: Duration = Duration::from_millis(16);
+    // A short poll while animating for smooth updates (16ms = ~60fps by
+    // default), a long one while idle to save power
+    let frame_rate = utils::frame_rate::settings();
+    // Redraw at least this often, for anything that changes without telling
+    // (relative times, status bar)
+    const MAX_REDRAW_INTERVAL: Duration = Duration::from_secs(1);
+    let mut mouse_captured = true;
+    let mut redraw = true;
+    let mut last_draw = std::time::Instant::now();
+    let mut toasts = toast_state();
 
     while app.running {
         let loop_start = std::time::Instant::now();
 
         app.process_streaming_chunks();
+        app.finish_session_load();
+        app.load_older_messages();
+        app.reload_changed_themes();
         app.update_animations();
+        app.autosave_draft();
         remove_expired_toasts();
-        terminal.draw(|f| app.render(f))?;
+        let current_toasts = toast_state();
+        if current_toasts != toasts {
+            toasts = current_toasts;
+            redraw = true;
+        }
+        // Copy mode hands the mouse back to the terminal for selecting text
+        if app.wants_mouse_capture() != mouse_captured {
+            mouse_captured = !mouse_captured;
+            if mouse_captured {
+                execute!(terminal.backend_mut(), EnableMouseCapture)?;
+            } else {
+                execute!(terminal.backend_mut(), DisableMouseCapture)?;
+            }
+        }
+        // Only draw when something changed; idle frames cost nothing
+        let changed = app.take_needs_redraw();
+        if changed || redraw || last_draw.elapsed() >= MAX_REDRAW_INTERVAL {
+            terminal.draw(|f| app.render(f))?;
+            last_draw = std::time::Instant::now();
+            redraw = false;
+        }
 
         // Calculate how long the loop iteration took
         let elapsed = loop_start.elapsed();
 
         // Poll for events, but with a dynamic timeout to maintain consistent frame timing
-        // If we spent less than POLL_DURATION processing, wait for the remainder
-        let poll_timeout = if elapsed < POLL_DURATION {
-            POLL_DURATION - elapsed
+        // If we spent less than the frame duration processing, wait for the remainder
+        let frame_duration = if app.is_animating() {
+            frame_rate.frame()
         } else {
-            Duration::from_millis(0)
+            frame_rate.idle_poll
         };
+        let poll_timeout = frame_duration.saturating_sub(elapsed);
 
         if event::poll(poll_timeout)? {
             let event = event::read()?;
+            redraw = true;
 
             // DO NOT REMOVE THIS LOG THAT I UNCOMMENT SOMETIMES. I USE IT FOR DEBUGGING
             // push_toast(Toast::new(
@@ -162,3 +277,49 @@ async fn run_event_loop(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui_toolkit::ToastLevel;
+
+    #[test]
+    fn test_toasts_survive_a_panic_while_locked() {
+        let _ = std::thread::spawn(|| {
+            let _toasts = get_toast_manager();
+            panic!("toast operation failed");
+        })
+        .join();
+
+        push_toast(Toast::new("still works", ToastLevel::Info, None));
+        remove_expired_toasts();
+        assert!(toast_state().0 > 0);
+    }
+
+    #[test]
+    fn test_concurrent_toasts() {
+        let threads: Vec<_> = (0..8)
+            .map(|thread| {
+                std::thread::spawn(move || {
+                    for i in 0..200 {
+                        push_toast(Toast::new(
+                            format!("toast {} from {}", i, thread),
+                            ToastLevel::Info,
+                            Some(Duration::from_millis(1)),
+                        ));
+                        if i % 10 == 0 {
+                            remove_expired_toasts();
+                            toast_state();
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        // The manager keeps only the newest few
+        assert!(toast_state().0 <= 5);
+    }
+}
@@ -5,7 +5,7 @@ use std::collections::HashMap;
 use std::env;
 use std::fs;
 use std::path::PathBuf;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 const MODELS_DEV_API_URL: &str = "https://models.dev/api.json";
 const CACHE_TTL_SECONDS: u64 = 24 * 60 * 60;
@@ -106,10 +106,7 @@ impl Discovery {
             let cache_path = cache_dir.join("models_dev_cache.json");
 
             Ok(Self {
-                client: Client::builder()
-                    .timeout(Duration::from_secs(30))
-                    .build()
-                    .context("Failed to create HTTP client")?,
+                client: crate::utils::http::client().clone(),
                 cache_path,
             })
         } else {
@@ -119,10 +116,7 @@ impl Discovery {
             let cache_path = cache_dir.join("models_dev_cache.json");
 
             Ok(Self {
-                client: Client::builder()
-                    .timeout(Duration::from_secs(30))
-                    .build()
-                    .context("Failed to create HTTP client")?,
+                client: crate::utils::http::client().clone(),
                 cache_path,
             })
         }
@@ -137,12 +131,12 @@ impl Discovery {
     }
 
     async fn fetch_from_api(&self) -> Result<HashMap<String, Provider>> {
-        let response = self
-            .client
-            .get(MODELS_DEV_API_URL)
-            .send()
-            .await
-            .context("Failed to fetch from models.dev API")?;
+        let timeout = crate::utils::http::settings().timeout;
+        let response = crate::utils::http::send_with_retry(|| {
+            self.client.get(MODELS_DEV_API_URL).timeout(timeout)
+        })
+        .await
+        .context("Failed to fetch from models.dev API")?;
 
         if !response.status().is_success() {
             return Err(anyhow::anyhow!(
@@ -204,6 +198,12 @@ impl Discovery {
         Ok(())
     }
 
+    /// The cached providers, without going online; `None` when there's no
+    /// fresh cache.
+    pub fn cached_providers(&self) -> Option<HashMap<String, Provider>> {
+        self.load_from_cache().ok().flatten()
+    }
+
     pub async fn fetch_providers(&self) -> Result<HashMap<String, Provider>> {
         if let Some(cached) = self.load_from_cache()? {
             return Ok(cached);
@@ -0,0 +1,181 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::model::discovery::Provider;
+use crate::persistence::prefs::ModelRef;
+
+/// Used when nothing has been picked yet; it works without an API key.
+pub const DEFAULT_PROVIDER: &str = "opencode";
+pub const DEFAULT_MODEL: &str = "big-pickle";
+
+fn model_exists(providers: &HashMap<String, Provider>, provider_id: &str, model_id: &str) -> bool {
+    providers
+        .get(provider_id)
+        .is_some_and(|p| p.models.contains_key(model_id))
+}
+
+fn is_usable(
+    providers: &HashMap<String, Provider>,
+    connected: &HashSet<String>,
+    provider_id: &str,
+    model_id: &str,
+) -> bool {
+    let keyless_default = provider_id == DEFAULT_PROVIDER && model_id == DEFAULT_MODEL;
+    model_exists(providers, provider_id, model_id)
+        && (keyless_default || connected.contains(provider_id))
+}
+
+/// The newest model a provider offers, standing in for its default.
+fn newest_model(provider: &Provider) -> Option<&str> {
+    provider
+        .models
+        .values()
+        .max_by(|a, b| {
+            a.release_date
+                .cmp(&b.release_date)
+                .then_with(|| b.id.cmp(&a.id))
+        })
+        .map(|m| m.id.as_str())
+}
+
+/// Checks the active model against what models.dev lists and which
+/// providers have a key. Returns the `(provider, model)` to switch to when
+/// it's no longer usable, or `None` when it's fine or nothing better exists.
+///
+/// The replacement is the most recent usable model, then the newest model
+/// of a connected provider, then the built-in default.
+pub fn repair_active_model(
+    providers: &HashMap<String, Provider>,
+    connected: &HashSet<String>,
+    recent: &[ModelRef],
+    provider_id: &str,
+    model_id: &str,
+) -> Option<(String, String)> {
+    if is_usable(providers, connected, provider_id, model_id) {
+        return None;
+    }
+
+    if let Some(m) = recent
+        .iter()
+        .find(|m| is_usable(providers, connected, &m.provider_id, &m.model_id))
+    {
+        return Some((m.provider_id.clone(), m.model_id.clone()));
+    }
+
+    let mut connected: Vec<&String> = connected.iter().collect();
+    connected.sort();
+    for id in connected {
+        if let Some(model) = providers.get(id).and_then(newest_model) {
+            return Some((id.clone(), model.to_string()));
+        }
+    }
+
+    let is_default = provider_id == DEFAULT_PROVIDER && model_id == DEFAULT_MODEL;
+    if !is_default && model_exists(providers, DEFAULT_PROVIDER, DEFAULT_MODEL) {
+        return Some((DEFAULT_PROVIDER.to_string(), DEFAULT_MODEL.to_string()));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn providers() -> HashMap<String, Provider> {
+        serde_json::from_value(serde_json::json!({
+            "opencode": {
+                "id": "opencode",
+                "name": "OpenCode",
+                "models": {
+                    "big-pickle": {"id": "big-pickle", "name": "Big Pickle"}
+                }
+            },
+            "openai": {
+                "id": "openai",
+                "name": "OpenAI",
+                "models": {
+                    "gpt-old": {"id": "gpt-old", "name": "GPT Old", "release_date": "2023-01-01"},
+                    "gpt-new": {"id": "gpt-new", "name": "GPT New", "release_date": "2025-01-01"}
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    fn connected(ids: &[&str]) -> HashSet<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    fn model_ref(provider_id: &str, model_id: &str) -> ModelRef {
+        ModelRef {
+            provider_id: provider_id.to_string(),
+            model_id: model_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_usable_model_is_kept() {
+        let providers = providers();
+        let openai = connected(&["openai"]);
+
+        assert_eq!(
+            repair_active_model(&providers, &openai, &[], "openai", "gpt-old"),
+            None
+        );
+        // The default needs no key
+        assert_eq!(
+            repair_active_model(&providers, &connected(&[]), &[], "opencode", "big-pickle"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_removed_model_falls_back_to_recent() {
+        let providers = providers();
+        let recent = [
+            model_ref("openai", "gpt-gone"),
+            model_ref("openai", "gpt-old"),
+        ];
+
+        assert_eq!(
+            repair_active_model(
+                &providers,
+                &connected(&["openai"]),
+                &recent,
+                "openai",
+                "gpt-gone"
+            ),
+            Some(("openai".to_string(), "gpt-old".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_falls_back_to_newest_connected_model() {
+        let providers = providers();
+
+        assert_eq!(
+            repair_active_model(
+                &providers,
+                &connected(&["openai"]),
+                &[],
+                "anthropic",
+                "claude"
+            ),
+            Some(("openai".to_string(), "gpt-new".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_disconnected_provider_falls_back_to_default() {
+        let providers = providers();
+
+        assert_eq!(
+            repair_active_model(&providers, &connected(&[]), &[], "openai", "gpt-new"),
+            Some((DEFAULT_PROVIDER.to_string(), DEFAULT_MODEL.to_string()))
+        );
+        // Nothing to switch to
+        assert_eq!(
+            repair_active_model(&HashMap::new(), &connected(&[]), &[], "openai", "gpt-new"),
+            None
+        );
+    }
+}
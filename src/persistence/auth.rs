@@ -1,7 +1,8 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 
 use super::{ensure_data_dir, get_data_dir};
 
@@ -18,6 +19,154 @@ pub enum AuthConfig {
     },
 }
 
+impl AuthConfig {
+    pub fn key(&self) -> &str {
+        match self {
+            AuthConfig::Api { key } => key,
+            AuthConfig::OAuth { access, .. } => access,
+        }
+    }
+}
+
+/// Where a provider's API key was found, highest precedence first.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeySource {
+    /// Saved in auth.json with `/connect`
+    Stored,
+    /// A `.env` file in the directory crabcode was started in
+    DotEnv(String),
+    /// The process environment
+    Env(String),
+}
+
+impl KeySource {
+    pub fn label(&self) -> String {
+        match self {
+            KeySource::Stored => "auth.json".to_string(),
+            KeySource::DotEnv(var) => format!(".env {}", var),
+            KeySource::Env(var) => format!("${}", var),
+        }
+    }
+}
+
+/// The `.env` file read at startup and the variables in it
+static DOTENV: RwLock<Option<(PathBuf, HashMap<String, String>)>> = RwLock::new(None);
+
+/// Reads provider keys from `dir/.env`. The values are only kept in memory,
+/// they're never copied into auth.json or the process environment.
+pub fn load_dotenv(dir: &Path) {
+    let path = dir.join(".env");
+    let vars = read_dotenv(&path);
+    if let Ok(mut dotenv) = DOTENV.write() {
+        *dotenv = Some((path, vars));
+    }
+}
+
+/// Reads the `.env` file from startup again, for `/reload`. Returns how
+/// many variables it has.
+pub fn reload_dotenv() -> usize {
+    let Ok(mut dotenv) = DOTENV.write() else {
+        return 0;
+    };
+    match dotenv.as_mut() {
+        Some((path, vars)) => {
+            *vars = read_dotenv(path);
+            vars.len()
+        }
+        None => 0,
+    }
+}
+
+fn read_dotenv(path: &Path) -> HashMap<String, String> {
+    match dotenvy::from_path_iter(path) {
+        Ok(iter) => iter.filter_map(|item| item.ok()).collect(),
+        Err(_) => HashMap::new(),
+    }
+}
+
+fn dotenv_vars() -> HashMap<String, String> {
+    DOTENV
+        .read()
+        .ok()
+        .and_then(|dotenv| dotenv.as_ref().map(|(_, vars)| vars.clone()))
+        .unwrap_or_default()
+}
+
+/// Whether any provider key is available, from the same places keys are
+/// looked up in: stored via `/connect`, or an `*_API_KEY` variable in
+/// `.env` or the environment.
+pub fn has_any_key() -> bool {
+    let stored = AuthDAO::new()
+        .and_then(|dao| dao.load())
+        .is_ok_and(|providers| !providers.is_empty());
+    stored || has_key_var(dotenv_vars()) || has_key_var(std::env::vars())
+}
+
+/// Empty values are skipped, as when resolving a key.
+fn has_key_var(vars: impl IntoIterator<Item = (String, String)>) -> bool {
+    vars.into_iter()
+        .any(|(name, value)| name.ends_with("_API_KEY") && !value.is_empty())
+}
+
+/// Every key crabcode might send: stored ones plus credential-looking
+/// variables from `.env` and the environment. Used to mask them in debug
+/// output.
+pub fn known_secrets() -> Vec<String> {
+    let looks_secret = |name: &str| {
+        let name = name.to_ascii_uppercase();
+        ["KEY", "TOKEN", "SECRET", "PASSWORD"]
+            .iter()
+            .any(|word| name.contains(word))
+    };
+
+    let mut secrets: Vec<String> = AuthDAO::new()
+        .and_then(|dao| dao.load())
+        .map(|providers| providers.values().map(|c| c.key().to_string()).collect())
+        .unwrap_or_default();
+    secrets.extend(
+        dotenv_vars()
+            .into_iter()
+            .filter(|(name, _)| looks_secret(name))
+            .map(|(_, value)| value),
+    );
+    secrets.extend(
+        std::env::vars()
+            .filter(|(name, _)| looks_secret(name))
+            .map(|(_, value)| value),
+    );
+    secrets
+}
+
+/// Picks the key to use given the `stored` one and the provider's env var
+/// names: stored > `.env` > process environment.
+pub fn resolve_key(stored: Option<String>, env_names: &[String]) -> Option<(String, KeySource)> {
+    resolve_key_from(stored, env_names, &dotenv_vars(), |var| {
+        std::env::var(var).ok()
+    })
+}
+
+fn resolve_key_from(
+    stored: Option<String>,
+    env_names: &[String],
+    dotenv: &HashMap<String, String>,
+    env: impl Fn(&str) -> Option<String>,
+) -> Option<(String, KeySource)> {
+    if let Some(key) = stored.filter(|k| !k.is_empty()) {
+        return Some((key, KeySource::Stored));
+    }
+    for var in env_names {
+        if let Some(key) = dotenv.get(var).filter(|k| !k.is_empty()) {
+            return Some((key.clone(), KeySource::DotEnv(var.clone())));
+        }
+    }
+    for var in env_names {
+        if let Some(key) = env(var).filter(|k| !k.is_empty()) {
+            return Some((key, KeySource::Env(var.clone())));
+        }
+    }
+    None
+}
+
 pub struct AuthDAO {
     auth_path: PathBuf,
 }
@@ -59,9 +208,120 @@ impl AuthDAO {
 
     pub fn get_api_key(&self, name: &str) -> Result<Option<String>> {
         let providers = self.load()?;
-        Ok(providers.get(name).and_then(|c| match c {
-            AuthConfig::Api { key } => Some(key.clone()),
-            AuthConfig::OAuth { access, .. } => Some(access.clone()),
-        }))
+        Ok(providers.get(name).map(|c| c.key().to_string()))
+    }
+
+    /// Like `get_api_key`, falling back to the provider's `env_names` in
+    /// `.env` and then the process environment.
+    pub fn resolve_api_key(
+        &self,
+        name: &str,
+        env_names: &[String],
+    ) -> Result<Option<(String, KeySource)>> {
+        Ok(resolve_key(self.get_api_key(name)?, env_names))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_key_precedence() {
+        let names = vec!["OPENAI_API_KEY".to_string()];
+        let dotenv = vars(&[("OPENAI_API_KEY", "from-dotenv")]);
+        let env = |_: &str| Some("from-env".to_string());
+
+        assert_eq!(
+            resolve_key_from(Some("stored".to_string()), &names, &dotenv, env),
+            Some(("stored".to_string(), KeySource::Stored))
+        );
+        assert_eq!(
+            resolve_key_from(None, &names, &dotenv, env),
+            Some((
+                "from-dotenv".to_string(),
+                KeySource::DotEnv("OPENAI_API_KEY".to_string())
+            ))
+        );
+        assert_eq!(
+            resolve_key_from(None, &names, &HashMap::new(), env),
+            Some((
+                "from-env".to_string(),
+                KeySource::Env("OPENAI_API_KEY".to_string())
+            ))
+        );
+        assert_eq!(
+            resolve_key_from(None, &names, &HashMap::new(), |_| None),
+            None
+        );
+    }
+
+    #[test]
+    fn test_resolve_key_skips_empty_values() {
+        let names = vec!["A_KEY".to_string(), "B_KEY".to_string()];
+        let dotenv = vars(&[("A_KEY", ""), ("B_KEY", "b")]);
+
+        assert_eq!(
+            resolve_key_from(Some(String::new()), &names, &dotenv, |_| None),
+            Some(("b".to_string(), KeySource::DotEnv("B_KEY".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_has_key_var() {
+        assert!(has_key_var(vars(&[
+            ("PATH", "/bin"),
+            ("GROQ_API_KEY", "gsk")
+        ])));
+        assert!(!has_key_var(vars(&[("OPENAI_API_KEY", "")])));
+        assert!(!has_key_var(vars(&[("PATH", "/bin")])));
+    }
+
+    #[test]
+    fn test_load_dotenv_file() {
+        let dir = std::env::temp_dir().join("crabcode_test_dotenv");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join(".env"),
+            "# provider keys\nANTHROPIC_API_KEY=\"sk-test\"\nexport OTHER=1\n",
+        )
+        .unwrap();
+
+        let parsed = read_dotenv(&dir.join(".env"));
+        assert_eq!(
+            parsed.get("ANTHROPIC_API_KEY").map(String::as_str),
+            Some("sk-test")
+        );
+        assert_eq!(parsed.get("OTHER").map(String::as_str), Some("1"));
+        assert!(read_dotenv(&dir.join("missing.env")).is_empty());
+    }
+
+    #[test]
+    fn test_reload_dotenv_rereads_the_file() {
+        let dir = std::env::temp_dir().join("crabcode_test_reload_dotenv");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(".env"), "CRABCODE_TEST_FIRST=1\n").unwrap();
+        load_dotenv(&dir);
+        assert_eq!(dotenv_vars().len(), 1);
+
+        std::fs::write(
+            dir.join(".env"),
+            "CRABCODE_TEST_FIRST=1\nCRABCODE_TEST_SECOND=2\n",
+        )
+        .unwrap();
+        assert_eq!(reload_dotenv(), 2);
+        assert_eq!(
+            dotenv_vars()
+                .get("CRABCODE_TEST_SECOND")
+                .map(String::as_str),
+            Some("2")
+        );
     }
 }
@@ -1,4 +1,4 @@
-use crate::persistence::{Message, MessagePart, Session as PersistenceSession};
+use crate::persistence::{Message, MessagePart, ToolCallRecord};
 use crate::session::types::{Message as SessionMessage, MessageRole, Session};
 
 impl From<SessionMessage> for Message {
@@ -115,18 +115,62 @@ impl TryFrom<Message> for SessionMessage {
     }
 }
 
+/// Builds an audit record from the JSON the chat keeps for a tool row
+/// (`id`, `name`, `status`, `args`, `duration_ms`, `output_preview`).
+pub fn tool_call_from_message(content: &str) -> Option<ToolCallRecord> {
+    let v: serde_json::Value = serde_json::from_str(content).ok()?;
+    let text = |key: &str| v.get(key).and_then(|s| s.as_str()).map(str::to_string);
+
+    Some(ToolCallRecord {
+        session_id: 0,
+        call_id: text("id").unwrap_or_default(),
+        name: text("name")?,
+        args: v
+            .get("args")
+            .map(|a| a.to_string())
+            .unwrap_or_else(|| "{}".to_string()),
+        status: text("status").unwrap_or_else(|| "ok".to_string()),
+        duration_ms: v.get("duration_ms").and_then(|d| d.as_i64()),
+        output: text("output_preview").unwrap_or_default(),
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64,
+    })
+}
+
 pub fn session_to_persistence(name: String, session: &Session) -> (String, Vec<Message>) {
     let messages: Vec<Message> = session.messages.iter().map(|m| m.clone().into()).collect();
     (name, messages)
 }
 
-pub fn persistence_to_session(
-    _persistence_session: PersistenceSession,
-    messages: Vec<Message>,
-) -> Result<Session, anyhow::Error> {
-    let mut session = Session::new();
-    for msg in messages {
-        session.add_message(msg.try_into()?);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_call_from_message() {
+        let content = serde_json::json!({
+            "id": "call_1",
+            "name": "read",
+            "status": "ok",
+            "args": { "path": "src/main.rs" },
+            "duration_ms": 42,
+            "output_preview": "fn main() {}",
+        })
+        .to_string();
+
+        let record = tool_call_from_message(&content).unwrap();
+        assert_eq!(record.call_id, "call_1");
+        assert_eq!(record.name, "read");
+        assert_eq!(record.args, r#"{"path":"src/main.rs"}"#);
+        assert_eq!(record.duration_ms, Some(42));
+        assert_eq!(record.output, "fn main() {}");
+    }
+
+    #[test]
+    fn test_tool_call_from_message_requires_name() {
+        assert!(tool_call_from_message("not json").is_none());
+        assert!(tool_call_from_message(r#"{"id": "call_1"}"#).is_none());
     }
-    Ok(session)
 }
@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
 use anyhow::Result;
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
@@ -14,6 +17,11 @@ pub struct Session {
     pub total_cost: f64,
     pub total_time_sec: f64,
     pub avg_tokens_per_sec: f64,
+    pub pinned: bool,
+    pub tags: Vec<String>,
+    pub sampling: crate::session::types::SamplingParams,
+    /// Indices of the bookmarked messages
+    pub bookmarks: Vec<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,20 +50,129 @@ pub struct Message {
     pub output_tokens: Option<i64>,
 }
 
+/// Replies of one provider and model across all sessions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelUsage {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub replies: usize,
+    pub output_tokens: i64,
+    /// Stored size of the history before each reply, which was sent with it
+    pub input_chars: i64,
+}
+
+/// Tool output kept in the audit log is cut to this many characters.
+pub const MAX_TOOL_OUTPUT_CHARS: usize = 2000;
+
+/// One tool invocation, kept so a reopened session shows what the agent did.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCallRecord {
+    pub session_id: i64,
+    pub call_id: String,
+    pub name: String,
+    /// Arguments as JSON
+    pub args: String,
+    pub status: String,
+    pub duration_ms: Option<i64>,
+    pub output: String,
+    pub timestamp: i64,
+}
+
+fn session_from_row(row: &rusqlite::Row) -> rusqlite::Result<Session> {
+    let tags_json: String = row.get(9)?;
+    let sampling_json: String = row.get(10)?;
+    let bookmarks_json: String = row.get(11)?;
+    Ok(Session {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        created_at: row.get(2)?,
+        updated_at: row.get(3)?,
+        total_tokens: row.get(4)?,
+        total_cost: row.get(5)?,
+        total_time_sec: row.get(6)?,
+        avg_tokens_per_sec: row.get(7)?,
+        pinned: row.get(8)?,
+        tags: serde_json::from_str(&tags_json).unwrap_or_default(),
+        sampling: serde_json::from_str(&sampling_json).unwrap_or_default(),
+        bookmarks: serde_json::from_str(&bookmarks_json).unwrap_or_default(),
+    })
+}
+
+fn message_from_row(row: &rusqlite::Row) -> rusqlite::Result<Message> {
+    let parts_json: String = row.get(3)?;
+    let parts: Vec<MessagePart> = serde_json::from_str(&parts_json)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    Ok(Message {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        role: row.get(2)?,
+        parts,
+        timestamp: row.get(4)?,
+        tokens_used: row.get(5)?,
+        model: row.get(6)?,
+        provider: row.get(7)?,
+        agent_mode: row.get(8)?,
+        duration_ms: row.get(9)?,
+        t0_ms: row.get(10)?,
+        t1_ms: row.get(11)?,
+        tn_ms: row.get(12)?,
+        output_tokens: row.get(13)?,
+    })
+}
+
+fn tool_call_from_row(row: &rusqlite::Row) -> rusqlite::Result<ToolCallRecord> {
+    Ok(ToolCallRecord {
+        session_id: row.get(0)?,
+        call_id: row.get(1)?,
+        name: row.get(2)?,
+        args: row.get(3)?,
+        status: row.get(4)?,
+        duration_ms: row.get(5)?,
+        output: row.get(6)?,
+        timestamp: row.get(7)?,
+    })
+}
+
 pub struct HistoryDAO {
     conn: Connection,
+    /// Database file, or `None` for an in-memory database
+    path: Option<PathBuf>,
 }
 
 impl HistoryDAO {
     pub fn new() -> Result<Self> {
-        let data_dir = get_data_dir();
         ensure_data_dir()?;
-        let db_path = data_dir.join("data.db");
+        Self::open(&get_data_dir().join("data.db"))
+    }
 
-        let mut conn = Connection::open(&db_path)?;
+    /// Opens the database at `path`. Every connection to the same file
+    /// sees the same history, e.g. one opened on a background thread.
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut conn = Connection::open(path)?;
         run_migrations(&mut conn)?;
 
-        Ok(Self { conn })
+        Ok(Self {
+            conn,
+            path: Some(path.to_path_buf()),
+        })
+    }
+
+    #[cfg(test)]
+    pub fn new_in_memory() -> Result<Self> {
+        let mut conn = Connection::open_in_memory()?;
+        run_migrations(&mut conn)?;
+
+        Ok(Self { conn, path: None })
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Writes out any dirty pages SQLite is still holding in memory.
+    pub fn flush(&self) -> Result<()> {
+        self.conn.cache_flush()?;
+        Ok(())
     }
 
     pub fn create_session(&self, name: String) -> Result<i64> {
@@ -66,22 +183,11 @@ impl HistoryDAO {
 
     pub fn list_sessions(&self) -> Result<Vec<Session>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, name, created_at, updated_at, total_tokens, total_cost, total_time_sec, avg_tokens_per_sec
+            "SELECT id, name, created_at, updated_at, total_tokens, total_cost, total_time_sec, avg_tokens_per_sec, pinned, tags, sampling, bookmarks
              FROM sessions ORDER BY updated_at DESC"
         )?;
 
-        let session_iter = stmt.query_map([], |row| {
-            Ok(Session {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                created_at: row.get(2)?,
-                updated_at: row.get(3)?,
-                total_tokens: row.get(4)?,
-                total_cost: row.get(5)?,
-                total_time_sec: row.get(6)?,
-                avg_tokens_per_sec: row.get(7)?,
-            })
-        })?;
+        let session_iter = stmt.query_map([], session_from_row)?;
 
         let result: Result<Vec<_>, _> = session_iter.collect();
         result.map_err(Into::into)
@@ -89,22 +195,13 @@ impl HistoryDAO {
 
     pub fn get_session(&self, id: i64) -> Result<Option<Session>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, name, created_at, updated_at, total_tokens, total_cost, total_time_sec, avg_tokens_per_sec
+            "SELECT id, name, created_at, updated_at, total_tokens, total_cost, total_time_sec, avg_tokens_per_sec, pinned, tags, sampling, bookmarks
              FROM sessions WHERE id = ?1"
         )?;
 
         let mut rows = stmt.query(params![id])?;
         if let Some(row) = rows.next()? {
-            Ok(Some(Session {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                created_at: row.get(2)?,
-                updated_at: row.get(3)?,
-                total_tokens: row.get(4)?,
-                total_cost: row.get(5)?,
-                total_time_sec: row.get(6)?,
-                avg_tokens_per_sec: row.get(7)?,
-            }))
+            Ok(Some(session_from_row(row)?))
         } else {
             Ok(None)
         }
@@ -147,29 +244,138 @@ impl HistoryDAO {
              FROM messages WHERE session_id = ?1 ORDER BY timestamp ASC",
         )?;
 
-        let message_iter = stmt.query_map(params![session_id], |row| {
-            let parts_json: String = row.get(3)?;
-            let parts: Vec<MessagePart> = serde_json::from_str(&parts_json)
-                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
-            Ok(Message {
-                id: row.get(0)?,
-                session_id: row.get(1)?,
-                role: row.get(2)?,
-                parts,
-                timestamp: row.get(4)?,
-                tokens_used: row.get(5)?,
-                model: row.get(6)?,
-                provider: row.get(7)?,
-                agent_mode: row.get(8)?,
-                duration_ms: row.get(9)?,
-                t0_ms: row.get(10)?,
-                t1_ms: row.get(11)?,
-                tn_ms: row.get(12)?,
-                output_tokens: row.get(13)?,
+        let message_iter = stmt.query_map(params![session_id], message_from_row)?;
+
+        let result: Result<Vec<_>, _> = message_iter.collect();
+        result.map_err(Into::into)
+    }
+
+    pub fn count_sessions(&self) -> Result<usize> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    /// Replies across all sessions, grouped by provider and model, most used
+    /// first. Replies without a token count are estimated from their size.
+    pub fn usage_by_model(&self) -> Result<Vec<ModelUsage>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT m.provider, m.model, COUNT(*),
+                    SUM(COALESCE(m.output_tokens, LENGTH(m.parts) / 4)),
+                    SUM((SELECT COALESCE(SUM(LENGTH(p.parts)), 0) FROM messages p
+                         WHERE p.session_id = m.session_id AND p.rowid < m.rowid))
+             FROM messages m WHERE m.role = 'assistant'
+             GROUP BY m.provider, m.model
+             ORDER BY COUNT(*) DESC",
+        )?;
+
+        let usage_iter = stmt.query_map([], |row| {
+            Ok(ModelUsage {
+                provider: row.get(0)?,
+                model: row.get(1)?,
+                replies: row.get::<_, i64>(2)? as usize,
+                output_tokens: row.get(3)?,
+                input_chars: row.get(4)?,
             })
         })?;
 
-        let result: Result<Vec<_>, _> = message_iter.collect();
+        let result: Result<Vec<_>, _> = usage_iter.collect();
+        result.map_err(Into::into)
+    }
+
+    /// Output tokens per local day over the last `days` days, oldest first.
+    /// Days without replies are left out.
+    pub fn output_tokens_by_day(&self, days: u32) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT date(timestamp, 'unixepoch', 'localtime') AS day,
+                    SUM(COALESCE(output_tokens, LENGTH(parts) / 4))
+             FROM messages
+             WHERE role = 'assistant' AND timestamp >= strftime('%s', 'now', ?1)
+             GROUP BY day ORDER BY day",
+        )?;
+
+        let day_iter = stmt.query_map(params![format!("-{} days", days)], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+
+        let result: Result<Vec<_>, _> = day_iter.collect();
+        result.map_err(Into::into)
+    }
+
+    pub fn count_messages(&self, session_id: i64) -> Result<usize> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM messages WHERE session_id = ?1",
+            params![session_id],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    /// Stored message counts by session id, for every session with messages.
+    pub fn count_messages_by_session(&self) -> Result<HashMap<i64, usize>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT session_id, COUNT(*) FROM messages GROUP BY session_id")?;
+        let counts = stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)? as usize))
+        })?;
+        counts.collect::<Result<_, _>>().map_err(Into::into)
+    }
+
+    /// Returns up to `limit` messages older than the newest `skip_newest`
+    /// ones, oldest first.
+    pub fn get_messages_page(
+        &self,
+        session_id: i64,
+        skip_newest: usize,
+        limit: usize,
+    ) -> Result<Vec<Message>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, session_id, role, parts, timestamp, tokens_used, model, provider, agent_mode, duration_ms,
+                    t0_ms, t1_ms, tn_ms, output_tokens
+             FROM messages WHERE session_id = ?1 ORDER BY timestamp DESC, rowid DESC
+             LIMIT ?2 OFFSET ?3",
+        )?;
+
+        let message_iter = stmt.query_map(
+            params![session_id, limit as i64, skip_newest as i64],
+            message_from_row,
+        )?;
+
+        let mut messages: Vec<Message> = message_iter.collect::<Result<_, _>>()?;
+        messages.reverse();
+        Ok(messages)
+    }
+
+    pub fn add_tool_call(&self, record: &ToolCallRecord) -> Result<()> {
+        let output = crate::utils::truncate_chars(&record.output, MAX_TOOL_OUTPUT_CHARS);
+        self.conn.execute(
+            "INSERT INTO tool_calls (session_id, call_id, name, args, status, duration_ms, output, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                record.session_id,
+                &record.call_id,
+                &record.name,
+                &record.args,
+                &record.status,
+                record.duration_ms,
+                output,
+                record.timestamp,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_tool_calls(&self, session_id: i64) -> Result<Vec<ToolCallRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT session_id, call_id, name, args, status, duration_ms, output, timestamp
+             FROM tool_calls WHERE session_id = ?1 ORDER BY timestamp ASC, id ASC",
+        )?;
+
+        let record_iter = stmt.query_map(params![session_id], tool_call_from_row)?;
+
+        let result: Result<Vec<_>, _> = record_iter.collect();
         result.map_err(Into::into)
     }
 
@@ -216,6 +422,8 @@ impl HistoryDAO {
     }
 
     pub fn delete_session(&self, id: i64) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM tool_calls WHERE session_id = ?1", params![id])?;
         self.conn
             .execute("DELETE FROM sessions WHERE id = ?1", params![id])?;
         Ok(())
@@ -229,6 +437,45 @@ impl HistoryDAO {
         Ok(())
     }
 
+    pub fn set_session_pinned(&self, id: i64, pinned: bool) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sessions SET pinned = ?1 WHERE id = ?2",
+            params![pinned, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_session_tags(&self, id: i64, tags: &[String]) -> Result<()> {
+        let tags_json = serde_json::to_string(tags)?;
+        self.conn.execute(
+            "UPDATE sessions SET tags = ?1 WHERE id = ?2",
+            params![tags_json, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_session_sampling(
+        &self,
+        id: i64,
+        sampling: &crate::session::types::SamplingParams,
+    ) -> Result<()> {
+        let sampling_json = serde_json::to_string(sampling)?;
+        self.conn.execute(
+            "UPDATE sessions SET sampling = ?1 WHERE id = ?2",
+            params![sampling_json, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_session_bookmarks(&self, id: i64, bookmarks: &[usize]) -> Result<()> {
+        let bookmarks_json = serde_json::to_string(bookmarks)?;
+        self.conn.execute(
+            "UPDATE sessions SET bookmarks = ?1 WHERE id = ?2",
+            params![bookmarks_json, id],
+        )?;
+        Ok(())
+    }
+
     pub fn get_full_session(&self, id: i64) -> Result<Option<(Session, Vec<Message>)>> {
         let session = self.get_session(id)?;
         if let Some(session) = session {
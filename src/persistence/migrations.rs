@@ -1,13 +1,65 @@
 use anyhow::Result;
 use rusqlite::{params, Connection};
 
+/// Newest schema this build knows how to read and write.
+pub const SCHEMA_VERSION: i32 = 6;
+
+/// The database was migrated by a newer crabcode than this one.
+#[derive(Debug)]
+pub struct SchemaTooNew {
+    pub found: i32,
+}
+
+impl std::fmt::Display for SchemaTooNew {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "data.db uses schema v{} but this crabcode only supports up to v{}; \
+             update crabcode to use your saved history",
+            self.found, SCHEMA_VERSION
+        )
+    }
+}
+
+impl std::error::Error for SchemaTooNew {}
+
+/// Returns the database's schema version, failing without touching it when
+/// a newer crabcode has already migrated it past what this build supports.
+pub fn check_schema_version(db: &Connection) -> Result<i32> {
+    let version = get_current_version(db)?;
+    if version > SCHEMA_VERSION {
+        return Err(SchemaTooNew { found: version }.into());
+    }
+    Ok(version)
+}
+
 pub fn run_migrations(db: &mut Connection) -> Result<()> {
-    let current_version: i32 = get_current_version(db)?;
+    let current_version: i32 = check_schema_version(db)?;
 
     if current_version < 1 {
         migrate_to_v1(db)?;
     }
 
+    if current_version < 2 {
+        migrate_to_v2(db)?;
+    }
+
+    if current_version < 3 {
+        migrate_to_v3(db)?;
+    }
+
+    if current_version < 4 {
+        migrate_to_v4(db)?;
+    }
+
+    if current_version < 5 {
+        migrate_to_v5(db)?;
+    }
+
+    if current_version < 6 {
+        migrate_to_v6(db)?;
+    }
+
     Ok(())
 }
 
@@ -96,3 +148,141 @@ fn migrate_to_v1(db: &mut Connection) -> Result<()> {
     tx.commit()?;
     Ok(())
 }
+
+fn migrate_to_v2(db: &mut Connection) -> Result<()> {
+    let tx = db.transaction()?;
+
+    tx.execute_batch(
+        r#"
+        ALTER TABLE sessions ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE sessions ADD COLUMN tags TEXT NOT NULL DEFAULT '[]';
+        "#,
+    )?;
+
+    tx.execute(
+        "INSERT INTO migrations (version, applied_at) VALUES (2, strftime('%s', 'now'))",
+        params![],
+    )?;
+
+    tx.commit()?;
+    Ok(())
+}
+
+fn migrate_to_v3(db: &mut Connection) -> Result<()> {
+    let tx = db.transaction()?;
+
+    tx.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS tool_calls (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id INTEGER NOT NULL,
+            call_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            args TEXT NOT NULL DEFAULT '{}',
+            status TEXT NOT NULL,
+            duration_ms INTEGER,
+            output TEXT NOT NULL DEFAULT '',
+            timestamp INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+            FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_tool_calls_session ON tool_calls(session_id, timestamp);
+        "#,
+    )?;
+
+    tx.execute(
+        "INSERT INTO migrations (version, applied_at) VALUES (3, strftime('%s', 'now'))",
+        params![],
+    )?;
+
+    tx.commit()?;
+    Ok(())
+}
+
+fn migrate_to_v4(db: &mut Connection) -> Result<()> {
+    let tx = db.transaction()?;
+
+    tx.execute_batch(
+        r#"
+        ALTER TABLE sessions ADD COLUMN sampling TEXT NOT NULL DEFAULT '{}';
+        "#,
+    )?;
+
+    tx.execute(
+        "INSERT INTO migrations (version, applied_at) VALUES (4, strftime('%s', 'now'))",
+        params![],
+    )?;
+
+    tx.commit()?;
+    Ok(())
+}
+
+fn migrate_to_v5(db: &mut Connection) -> Result<()> {
+    let tx = db.transaction()?;
+
+    tx.execute_batch(
+        r#"
+        ALTER TABLE sessions ADD COLUMN bookmarks TEXT NOT NULL DEFAULT '[]';
+        "#,
+    )?;
+
+    tx.execute(
+        "INSERT INTO migrations (version, applied_at) VALUES (5, strftime('%s', 'now'))",
+        params![],
+    )?;
+
+    tx.commit()?;
+    Ok(())
+}
+
+fn migrate_to_v6(db: &mut Connection) -> Result<()> {
+    let tx = db.transaction()?;
+
+    // Prompts sent before this have no directory and only show up in the
+    // global history
+    tx.execute_batch(
+        r#"
+        ALTER TABLE prompt_history ADD COLUMN cwd TEXT;
+        CREATE INDEX IF NOT EXISTS idx_prompt_history_cwd ON prompt_history(cwd, timestamp DESC);
+        "#,
+    )?;
+
+    tx.execute(
+        "INSERT INTO migrations (version, applied_at) VALUES (6, strftime('%s', 'now'))",
+        params![],
+    )?;
+
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_reach_schema_version() {
+        let mut db = Connection::open_in_memory().unwrap();
+        run_migrations(&mut db).unwrap();
+        assert_eq!(check_schema_version(&db).unwrap(), SCHEMA_VERSION);
+
+        // Running again on an up-to-date database is a no-op
+        run_migrations(&mut db).unwrap();
+    }
+
+    #[test]
+    fn test_newer_schema_is_refused() {
+        let mut db = Connection::open_in_memory().unwrap();
+        run_migrations(&mut db).unwrap();
+        db.execute(
+            "INSERT INTO migrations (version) VALUES (?1)",
+            params![SCHEMA_VERSION + 1],
+        )
+        .unwrap();
+
+        let err = run_migrations(&mut db).unwrap_err();
+        let too_new = err.downcast_ref::<SchemaTooNew>().unwrap();
+        assert_eq!(too_new.found, SCHEMA_VERSION + 1);
+        assert!(err.to_string().contains("update crabcode"));
+    }
+}
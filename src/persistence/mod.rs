@@ -11,22 +11,40 @@ pub mod prompt_history;
 pub mod providers;
 
 pub use auth::{AuthConfig, AuthDAO};
-pub use conversions::persistence_to_session;
 pub use db::{get_db_conn, DbConn};
-pub use history::{HistoryDAO, Message, MessagePart, Session};
-pub use prefs::PrefsDAO;
+pub use history::{HistoryDAO, Message, MessagePart, ModelUsage, ToolCallRecord};
+pub use prefs::{
+    ColorMode, Density, HistoryScope, MarkdownRendererKind, PrefsDAO, ScrollPreferences,
+    TimeFormat, Tone,
+};
 pub use prompt_history::PromptHistoryCache;
 
+pub const DATA_DIR_VAR: &str = "CRABCODE_DATA_DIR";
+pub const CACHE_DIR_VAR: &str = "CRABCODE_CACHE_DIR";
+
+/// Where auth, prefs and history live. `CRABCODE_DATA_DIR` replaces the
+/// whole path; otherwise it's `crabcode` under the platform data dir, which
+/// already follows `XDG_DATA_HOME` on Linux.
 pub fn get_data_dir() -> PathBuf {
-    dirs::data_local_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("crabcode")
+    dir_from_env(DATA_DIR_VAR, dirs::data_local_dir())
 }
 
+/// Like `get_data_dir`, but for the provider and models caches, overridden
+/// by `CRABCODE_CACHE_DIR` (or `XDG_CACHE_HOME` on Linux).
 pub fn get_cache_dir() -> PathBuf {
-    dirs::cache_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("crabcode")
+    dir_from_env(CACHE_DIR_VAR, dirs::cache_dir())
+}
+
+fn dir_from_env(var: &str, base: Option<PathBuf>) -> PathBuf {
+    dir_from(std::env::var_os(var), base)
+}
+
+/// `value` is the override variable's value, if it's set.
+fn dir_from(value: Option<std::ffi::OsString>, base: Option<PathBuf>) -> PathBuf {
+    match value {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => base.unwrap_or_else(|| PathBuf::from(".")).join("crabcode"),
+    }
 }
 
 pub fn ensure_data_dir() -> Result<()> {
@@ -40,3 +58,24 @@ pub fn ensure_cache_dir() -> Result<()> {
     std::fs::create_dir_all(&dir)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dir_override() {
+        let dir = std::env::temp_dir().join("crabcode-test-data-dir");
+        let base = || Some(PathBuf::from("/base"));
+
+        assert_eq!(dir_from(Some(dir.clone().into_os_string()), base()), dir);
+
+        // An empty value falls back to the platform dir
+        assert_eq!(
+            dir_from(Some(Default::default()), base()),
+            PathBuf::from("/base/crabcode")
+        );
+        assert_eq!(dir_from(None, base()), PathBuf::from("/base/crabcode"));
+        assert_eq!(dir_from(None, None), PathBuf::from("./crabcode"));
+    }
+}
@@ -1,11 +1,27 @@
 use anyhow::Result;
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use super::{ensure_data_dir, get_data_dir};
 
 const MODEL_PREFS_KEY: &str = "model_preferences";
+const SCROLL_PREFS_KEY: &str = "scroll_preferences";
+const COLOR_MODE_KEY: &str = "color_mode";
+const TEE_OUTPUT_KEY: &str = "tee_output";
+const TONE_KEY: &str = "tone";
+const TIME_FORMAT_KEY: &str = "time_format";
+const DENSITY_KEY: &str = "density";
+const MARKDOWN_RENDERER_KEY: &str = "markdown_renderer";
+const HISTORY_SCOPE_KEY: &str = "history_scope";
+const PASTE_ATTACH_LINES_KEY: &str = "paste_attach_lines";
+const NOTIFY_AFTER_KEY: &str = "notify_after_secs";
+const DRAFT_AUTOSAVE_KEY: &str = "draft_autosave";
+const QUIT_GUARD_KEY: &str = "quit_guard";
+const COMMAND_PREFIX_KEY: &str = "command_prefix";
+/// Prefix of the per-session input drafts
+const DRAFT_KEY_PREFIX: &str = "draft:";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelRef {
@@ -26,6 +42,17 @@ pub struct ModelPreferences {
     pub recent: Vec<ModelRef>,
     pub favorite: Vec<ModelRef>,
     pub variant: serde_json::Value,
+    /// Last model picked for each provider, so switching back to a provider
+    /// restores it
+    #[serde(default, rename = "lastModel")]
+    pub last_model: HashMap<String, String>,
+    /// How many models the Recent group keeps
+    #[serde(default = "default_recent_limit", rename = "recentLimit")]
+    pub recent_limit: usize,
+}
+
+fn default_recent_limit() -> usize {
+    ModelPreferences::DEFAULT_RECENT_LIMIT
 }
 
 impl Default for ModelPreferences {
@@ -34,16 +61,24 @@ impl Default for ModelPreferences {
             recent: Vec::new(),
             favorite: Vec::new(),
             variant: serde_json::json!({}),
+            last_model: HashMap::new(),
+            recent_limit: Self::DEFAULT_RECENT_LIMIT,
         }
     }
 }
 
 impl ModelPreferences {
+    pub const DEFAULT_RECENT_LIMIT: usize = 10;
+    pub const MAX_RECENT_LIMIT: usize = 50;
+
     pub fn get_active_model(&self) -> Option<&ModelRef> {
         self.recent.first()
     }
 
     pub fn add_recent(&mut self, provider_id: String, model_id: String) -> bool {
+        self.last_model
+            .insert(provider_id.clone(), model_id.clone());
+
         let new_ref = ModelRef {
             provider_id,
             model_id,
@@ -52,14 +87,37 @@ impl ModelPreferences {
         self.recent.retain(|m| m != &new_ref);
 
         self.recent.insert(0, new_ref);
-
-        if self.recent.len() > 10 {
-            self.recent.truncate(10);
-        }
+        self.recent.truncate(self.effective_recent_limit());
 
         true
     }
 
+    /// Changes how many recent models are kept, dropping the oldest ones
+    /// that no longer fit. At least one is kept since the newest is the
+    /// active model.
+    pub fn set_recent_limit(&mut self, limit: usize) {
+        self.recent_limit = limit;
+        self.recent.truncate(self.effective_recent_limit());
+    }
+
+    pub fn effective_recent_limit(&self) -> usize {
+        self.recent_limit.clamp(1, Self::MAX_RECENT_LIMIT)
+    }
+
+    /// The model last used with `provider_id`. Preferences saved before
+    /// models were remembered per provider fall back to the recent list.
+    pub fn model_for_provider(&self, provider_id: &str) -> Option<&str> {
+        self.last_model
+            .get(provider_id)
+            .map(String::as_str)
+            .or_else(|| {
+                self.recent
+                    .iter()
+                    .find(|m| m.provider_id == provider_id)
+                    .map(|m| m.model_id.as_str())
+            })
+    }
+
     pub fn toggle_favorite(&mut self, provider_id: String, model_id: String) {
         let new_ref = ModelRef {
             provider_id,
@@ -73,6 +131,31 @@ impl ModelPreferences {
         }
     }
 
+    /// Swaps a favorite with its neighbor above (`up`) or below. Returns
+    /// false when it isn't a favorite or is already at that end.
+    pub fn move_favorite(&mut self, provider_id: &str, model_id: &str, up: bool) -> bool {
+        let Some(pos) = self
+            .favorite
+            .iter()
+            .position(|m| m.provider_id == provider_id && m.model_id == model_id)
+        else {
+            return false;
+        };
+
+        let target = if up {
+            pos.checked_sub(1)
+        } else {
+            Some(pos + 1).filter(|&i| i < self.favorite.len())
+        };
+        match target {
+            Some(target) => {
+                self.favorite.swap(pos, target);
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn is_favorite(&self, provider_id: &str, model_id: &str) -> bool {
         self.favorite
             .iter()
@@ -80,6 +163,239 @@ impl ModelPreferences {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScrollPreferences {
+    /// Lines moved per mouse wheel event
+    pub step: usize,
+    /// Grow the step while wheel events arrive in quick succession
+    pub accelerate: bool,
+    /// Always scroll one line at a time
+    pub smooth: bool,
+}
+
+impl Default for ScrollPreferences {
+    fn default() -> Self {
+        Self {
+            step: 3,
+            accelerate: false,
+            smooth: false,
+        }
+    }
+}
+
+impl ScrollPreferences {
+    pub const MAX_STEP: usize = 50;
+
+    pub fn base_step(&self) -> usize {
+        if self.smooth {
+            1
+        } else {
+            self.step.clamp(1, Self::MAX_STEP)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorMode {
+    /// Colors from the active theme
+    #[default]
+    Theme,
+    /// The built-in high-contrast theme
+    HighContrast,
+    /// No colors at all; emphasis is shown with symbols instead
+    Monochrome,
+}
+
+impl ColorMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "on" | "theme" => Some(Self::Theme),
+            "high-contrast" | "contrast" => Some(Self::HighContrast),
+            "off" | "mono" | "monochrome" => Some(Self::Monochrome),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Theme => "theme",
+            Self::HighContrast => "high-contrast",
+            Self::Monochrome => "monochrome",
+        }
+    }
+}
+
+/// How the assistant is asked to write, on top of the model's base prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Tone {
+    /// Just the base prompt
+    #[default]
+    Default,
+    Concise,
+    Verbose,
+    Teaching,
+    Terse,
+}
+
+impl Tone {
+    pub const ALL: [Tone; 5] = [
+        Tone::Default,
+        Tone::Concise,
+        Tone::Verbose,
+        Tone::Teaching,
+        Tone::Terse,
+    ];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|tone| tone.label().eq_ignore_ascii_case(value))
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Concise => "concise",
+            Self::Verbose => "verbose",
+            Self::Teaching => "teaching",
+            Self::Terse => "terse",
+        }
+    }
+}
+
+/// How timestamps are shown, e.g. in the sessions dialog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TimeFormat {
+    #[default]
+    #[serde(rename = "12h")]
+    TwelveHour,
+    #[serde(rename = "24h")]
+    TwentyFourHour,
+    /// "5m ago", "yesterday", ...
+    #[serde(rename = "relative")]
+    Relative,
+}
+
+impl TimeFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "12" | "12h" => Some(Self::TwelveHour),
+            "24" | "24h" => Some(Self::TwentyFourHour),
+            "relative" | "ago" => Some(Self::Relative),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::TwelveHour => "12h",
+            Self::TwentyFourHour => "24h",
+            Self::Relative => "relative",
+        }
+    }
+}
+
+/// How much vertical space the chat spends between messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Density {
+    /// Blank lines between messages and around the metadata footers
+    #[default]
+    Comfortable,
+    /// No blank lines; footers stay on one line under their reply
+    Compact,
+}
+
+impl Density {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "comfortable" | "normal" => Some(Self::Comfortable),
+            "compact" => Some(Self::Compact),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Comfortable => "comfortable",
+            Self::Compact => "compact",
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::Comfortable => Self::Compact,
+            Self::Compact => Self::Comfortable,
+        }
+    }
+}
+
+/// Which markdown renderer formats replies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MarkdownRendererKind {
+    /// The tui-markdown crate
+    #[default]
+    TuiMarkdown,
+    /// crabcode's own line-based renderer
+    Builtin,
+}
+
+impl MarkdownRendererKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "tui-markdown" | "default" => Some(Self::TuiMarkdown),
+            "builtin" => Some(Self::Builtin),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::TuiMarkdown => "tui-markdown",
+            Self::Builtin => "builtin",
+        }
+    }
+}
+
+/// Which prompts Up recalls in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HistoryScope {
+    /// Prompts sent from the current working directory
+    #[default]
+    Project,
+    /// Prompts sent from anywhere
+    Global,
+}
+
+impl HistoryScope {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "project" | "cwd" => Some(Self::Project),
+            "global" | "all" => Some(Self::Global),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Project => "project",
+            Self::Global => "global",
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::Project => Self::Global,
+            Self::Global => Self::Project,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct PrefsDAO {
     conn: Connection,
@@ -98,6 +414,14 @@ impl PrefsDAO {
         Ok(Self { conn })
     }
 
+    #[cfg(test)]
+    pub fn new_in_memory() -> Result<Self> {
+        let mut conn = Connection::open_in_memory()?;
+        super::migrations::run_migrations(&mut conn)?;
+
+        Ok(Self { conn })
+    }
+
     fn get_pref(&self, key: &str) -> Result<Option<String>> {
         let mut stmt = self
             .conn
@@ -119,6 +443,12 @@ impl PrefsDAO {
         Ok(())
     }
 
+    fn delete_pref(&self, key: &str) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM prefs WHERE key = ?1", params![key])?;
+        Ok(())
+    }
+
     pub fn get_model_preferences(&self) -> Result<ModelPreferences> {
         match self.get_pref(MODEL_PREFS_KEY)? {
             Some(json_str) => {
@@ -134,6 +464,177 @@ impl PrefsDAO {
         self.set_pref(MODEL_PREFS_KEY, &json_str)
     }
 
+    pub fn get_scroll_preferences(&self) -> Result<ScrollPreferences> {
+        match self.get_pref(SCROLL_PREFS_KEY)? {
+            Some(json_str) => Ok(serde_json::from_str(&json_str)?),
+            None => Ok(ScrollPreferences::default()),
+        }
+    }
+
+    pub fn set_scroll_preferences(&self, prefs: &ScrollPreferences) -> Result<()> {
+        let json_str = serde_json::to_string(prefs)?;
+        self.set_pref(SCROLL_PREFS_KEY, &json_str)
+    }
+
+    pub fn get_color_mode(&self) -> Result<ColorMode> {
+        match self.get_pref(COLOR_MODE_KEY)? {
+            Some(json_str) => Ok(serde_json::from_str(&json_str)?),
+            None => Ok(ColorMode::default()),
+        }
+    }
+
+    pub fn set_color_mode(&self, mode: ColorMode) -> Result<()> {
+        let json_str = serde_json::to_string(&mode)?;
+        self.set_pref(COLOR_MODE_KEY, &json_str)
+    }
+
+    /// Whether each turn is also written to a transcript file as it streams.
+    pub fn get_tee_output(&self) -> Result<bool> {
+        match self.get_pref(TEE_OUTPUT_KEY)? {
+            Some(json_str) => Ok(serde_json::from_str(&json_str)?),
+            None => Ok(false),
+        }
+    }
+
+    pub fn set_tee_output(&self, enabled: bool) -> Result<()> {
+        self.set_pref(TEE_OUTPUT_KEY, &enabled.to_string())
+    }
+
+    pub fn get_tone(&self) -> Result<Tone> {
+        match self.get_pref(TONE_KEY)? {
+            Some(json_str) => Ok(serde_json::from_str(&json_str)?),
+            None => Ok(Tone::default()),
+        }
+    }
+
+    pub fn set_tone(&self, tone: Tone) -> Result<()> {
+        let json_str = serde_json::to_string(&tone)?;
+        self.set_pref(TONE_KEY, &json_str)
+    }
+
+    /// Line count from which a paste becomes an attachment; 0 means never.
+    pub fn get_paste_attach_lines(&self) -> Result<usize> {
+        match self.get_pref(PASTE_ATTACH_LINES_KEY)? {
+            Some(json_str) => Ok(serde_json::from_str(&json_str)?),
+            None => Ok(crate::session::paste::DEFAULT_ATTACH_LINES),
+        }
+    }
+
+    pub fn set_paste_attach_lines(&self, lines: usize) -> Result<()> {
+        self.set_pref(PASTE_ATTACH_LINES_KEY, &lines.to_string())
+    }
+
+    /// How long a turn must take before its end is notified; 0 means never.
+    pub fn get_notify_after_secs(&self) -> Result<u64> {
+        match self.get_pref(NOTIFY_AFTER_KEY)? {
+            Some(json_str) => Ok(serde_json::from_str(&json_str)?),
+            None => Ok(0),
+        }
+    }
+
+    pub fn set_notify_after_secs(&self, secs: u64) -> Result<()> {
+        self.set_pref(NOTIFY_AFTER_KEY, &secs.to_string())
+    }
+
+    /// Whether unsent input is saved as a draft per session.
+    pub fn get_draft_autosave(&self) -> Result<bool> {
+        match self.get_pref(DRAFT_AUTOSAVE_KEY)? {
+            Some(json_str) => Ok(serde_json::from_str(&json_str)?),
+            None => Ok(true),
+        }
+    }
+
+    pub fn set_draft_autosave(&self, enabled: bool) -> Result<()> {
+        self.set_pref(DRAFT_AUTOSAVE_KEY, &enabled.to_string())
+    }
+
+    /// Whether Ctrl+C twice stops a running response instead of quitting.
+    pub fn get_quit_guard(&self) -> Result<bool> {
+        match self.get_pref(QUIT_GUARD_KEY)? {
+            Some(json_str) => Ok(serde_json::from_str(&json_str)?),
+            None => Ok(true),
+        }
+    }
+
+    pub fn set_quit_guard(&self, enabled: bool) -> Result<()> {
+        self.set_pref(QUIT_GUARD_KEY, &enabled.to_string())
+    }
+
+    /// What typed commands start with. A stored prefix that isn't valid
+    /// any more falls back to the default.
+    pub fn get_command_prefix(&self) -> Result<String> {
+        use crate::command::parser::{is_valid_prefix, DEFAULT_PREFIX};
+
+        Ok(self
+            .get_pref(COMMAND_PREFIX_KEY)?
+            .filter(|prefix| is_valid_prefix(prefix))
+            .unwrap_or_else(|| DEFAULT_PREFIX.to_string()))
+    }
+
+    pub fn set_command_prefix(&self, prefix: &str) -> Result<()> {
+        self.set_pref(COMMAND_PREFIX_KEY, prefix)
+    }
+
+    pub fn get_draft(&self, key: &str) -> Result<Option<String>> {
+        self.get_pref(&format!("{}{}", DRAFT_KEY_PREFIX, key))
+    }
+
+    pub fn set_draft(&self, key: &str, text: &str) -> Result<()> {
+        self.set_pref(&format!("{}{}", DRAFT_KEY_PREFIX, key), text)
+    }
+
+    pub fn clear_draft(&self, key: &str) -> Result<()> {
+        self.delete_pref(&format!("{}{}", DRAFT_KEY_PREFIX, key))
+    }
+
+    pub fn get_time_format(&self) -> Result<TimeFormat> {
+        match self.get_pref(TIME_FORMAT_KEY)? {
+            Some(json_str) => Ok(serde_json::from_str(&json_str)?),
+            None => Ok(TimeFormat::default()),
+        }
+    }
+
+    pub fn set_time_format(&self, format: TimeFormat) -> Result<()> {
+        let json_str = serde_json::to_string(&format)?;
+        self.set_pref(TIME_FORMAT_KEY, &json_str)
+    }
+
+    pub fn get_density(&self) -> Result<Density> {
+        match self.get_pref(DENSITY_KEY)? {
+            Some(json_str) => Ok(serde_json::from_str(&json_str)?),
+            None => Ok(Density::default()),
+        }
+    }
+
+    pub fn set_density(&self, density: Density) -> Result<()> {
+        let json_str = serde_json::to_string(&density)?;
+        self.set_pref(DENSITY_KEY, &json_str)
+    }
+
+    pub fn get_markdown_renderer(&self) -> Result<MarkdownRendererKind> {
+        match self.get_pref(MARKDOWN_RENDERER_KEY)? {
+            Some(json_str) => Ok(serde_json::from_str(&json_str)?),
+            None => Ok(MarkdownRendererKind::default()),
+        }
+    }
+
+    pub fn set_markdown_renderer(&self, kind: MarkdownRendererKind) -> Result<()> {
+        let json_str = serde_json::to_string(&kind)?;
+        self.set_pref(MARKDOWN_RENDERER_KEY, &json_str)
+    }
+
+    pub fn get_history_scope(&self) -> Result<HistoryScope> {
+        match self.get_pref(HISTORY_SCOPE_KEY)? {
+            Some(json_str) => Ok(serde_json::from_str(&json_str)?),
+            None => Ok(HistoryScope::default()),
+        }
+    }
+
+    pub fn set_history_scope(&self, scope: HistoryScope) -> Result<()> {
+        let json_str = serde_json::to_string(&scope)?;
+        self.set_pref(HISTORY_SCOPE_KEY, &json_str)
+    }
+
     pub fn get_active_model(&self) -> Result<Option<(String, String)>> {
         let prefs = self.get_model_preferences()?;
         if let Some(model_ref) = prefs.get_active_model() {
@@ -152,6 +653,18 @@ impl PrefsDAO {
         self.set_model_preferences(&prefs)
     }
 
+    pub fn set_recent_limit(&self, limit: usize) -> Result<usize> {
+        let mut prefs = self.get_model_preferences()?;
+        prefs.set_recent_limit(limit);
+        self.set_model_preferences(&prefs)?;
+        Ok(prefs.effective_recent_limit())
+    }
+
+    pub fn get_provider_model(&self, provider_id: &str) -> Result<Option<String>> {
+        let prefs = self.get_model_preferences()?;
+        Ok(prefs.model_for_provider(provider_id).map(str::to_string))
+    }
+
     pub fn toggle_favorite(&self, provider_id: String, model_id: String) -> Result<bool> {
         let mut prefs = self.get_model_preferences()?;
         let was_favorite = prefs.is_favorite(&provider_id, &model_id);
@@ -160,6 +673,15 @@ impl PrefsDAO {
         Ok(!was_favorite)
     }
 
+    pub fn move_favorite(&self, provider_id: &str, model_id: &str, up: bool) -> Result<bool> {
+        let mut prefs = self.get_model_preferences()?;
+        if !prefs.move_favorite(provider_id, model_id, up) {
+            return Ok(false);
+        }
+        self.set_model_preferences(&prefs)?;
+        Ok(true)
+    }
+
     pub fn is_favorite(&self, provider_id: &str, model_id: &str) -> Result<bool> {
         let prefs = self.get_model_preferences()?;
         Ok(prefs.is_favorite(provider_id, model_id))
@@ -221,6 +743,137 @@ mod tests {
         assert_eq!(prefs.recent.len(), 10);
     }
 
+    #[test]
+    fn test_model_preferences_remembers_model_per_provider() {
+        let mut prefs = ModelPreferences::default();
+        prefs.add_recent("anthropic".to_string(), "claude-a".to_string());
+        prefs.add_recent("openai".to_string(), "gpt-a".to_string());
+        prefs.add_recent("anthropic".to_string(), "claude-b".to_string());
+
+        assert_eq!(prefs.model_for_provider("anthropic"), Some("claude-b"));
+        assert_eq!(prefs.model_for_provider("openai"), Some("gpt-a"));
+        assert_eq!(prefs.model_for_provider("google"), None);
+    }
+
+    #[test]
+    fn test_model_preferences_without_last_model_use_recent() {
+        let prefs: ModelPreferences = serde_json::from_str(
+            r#"{"recent":[{"providerID":"openai","modelID":"gpt-a"}],"favorite":[],"variant":{}}"#,
+        )
+        .unwrap();
+
+        assert!(prefs.last_model.is_empty());
+        assert_eq!(prefs.model_for_provider("openai"), Some("gpt-a"));
+    }
+
+    #[test]
+    fn test_provider_model_roundtrip() {
+        let dao = setup_test_dao();
+        assert_eq!(dao.get_provider_model("openai").unwrap(), None);
+
+        dao.set_active_model("openai".to_string(), "gpt-a".to_string())
+            .unwrap();
+        dao.set_active_model("anthropic".to_string(), "claude-a".to_string())
+            .unwrap();
+
+        assert_eq!(
+            dao.get_provider_model("openai").unwrap().as_deref(),
+            Some("gpt-a")
+        );
+        assert_eq!(
+            dao.get_active_model().unwrap(),
+            Some(("anthropic".to_string(), "claude-a".to_string()))
+        );
+    }
+
+    fn favorite_ids(dao: &PrefsDAO) -> Vec<String> {
+        dao.get_model_preferences()
+            .unwrap()
+            .favorite
+            .into_iter()
+            .map(|m| m.model_id)
+            .collect()
+    }
+
+    #[test]
+    fn test_move_favorite_persists_order() {
+        let dao = setup_test_dao();
+        for id in ["a", "b", "c"] {
+            dao.toggle_favorite("provider".to_string(), id.to_string())
+                .unwrap();
+        }
+
+        assert!(dao.move_favorite("provider", "c", true).unwrap());
+        assert_eq!(favorite_ids(&dao), vec!["a", "c", "b"]);
+        assert!(dao.move_favorite("provider", "a", false).unwrap());
+        assert_eq!(favorite_ids(&dao), vec!["c", "a", "b"]);
+
+        // Already at the top, or not a favorite at all
+        assert!(!dao.move_favorite("provider", "c", true).unwrap());
+        assert!(!dao.move_favorite("provider", "z", false).unwrap());
+
+        // Removing one keeps the others' relative order
+        dao.toggle_favorite("provider".to_string(), "a".to_string())
+            .unwrap();
+        assert_eq!(favorite_ids(&dao), vec!["c", "b"]);
+    }
+
+    #[test]
+    fn test_model_preferences_recent_limit_evicts_oldest() {
+        let mut prefs = ModelPreferences::default();
+        prefs.set_recent_limit(3);
+        for i in 0..5 {
+            prefs.add_recent("provider".to_string(), format!("model{}", i));
+        }
+
+        let ids: Vec<_> = prefs.recent.iter().map(|m| m.model_id.as_str()).collect();
+        assert_eq!(ids, vec!["model4", "model3", "model2"]);
+
+        // Re-adding moves to the front instead of duplicating
+        prefs.add_recent("provider".to_string(), "model2".to_string());
+        let ids: Vec<_> = prefs.recent.iter().map(|m| m.model_id.as_str()).collect();
+        assert_eq!(ids, vec!["model2", "model4", "model3"]);
+
+        prefs.set_recent_limit(1);
+        assert_eq!(prefs.recent.len(), 1);
+        assert_eq!(prefs.recent[0].model_id, "model2");
+    }
+
+    #[test]
+    fn test_model_preferences_recent_limit_is_clamped() {
+        let mut prefs = ModelPreferences::default();
+        prefs.add_recent("provider".to_string(), "model".to_string());
+
+        prefs.set_recent_limit(0);
+        assert_eq!(prefs.effective_recent_limit(), 1);
+        assert_eq!(prefs.recent.len(), 1);
+
+        prefs.set_recent_limit(1000);
+        assert_eq!(
+            prefs.effective_recent_limit(),
+            ModelPreferences::MAX_RECENT_LIMIT
+        );
+    }
+
+    #[test]
+    fn test_recent_limit_roundtrip() {
+        let dao = setup_test_dao();
+        assert_eq!(
+            dao.get_model_preferences().unwrap().recent_limit,
+            ModelPreferences::DEFAULT_RECENT_LIMIT
+        );
+
+        assert_eq!(dao.set_recent_limit(4).unwrap(), 4);
+        for i in 0..6 {
+            dao.set_active_model("provider".to_string(), format!("model{}", i))
+                .unwrap();
+        }
+
+        let prefs = dao.get_model_preferences().unwrap();
+        assert_eq!(prefs.recent_limit, 4);
+        assert_eq!(prefs.recent.len(), 4);
+    }
+
     #[test]
     fn test_model_preferences_toggle_favorite() {
         let mut prefs = ModelPreferences::default();
@@ -234,6 +887,123 @@ mod tests {
         assert!(!prefs.is_favorite("provider1", "model1"));
     }
 
+    #[test]
+    fn test_scroll_preferences_roundtrip() {
+        let dao = setup_test_dao();
+        assert_eq!(
+            dao.get_scroll_preferences().unwrap(),
+            ScrollPreferences::default()
+        );
+
+        let prefs = ScrollPreferences {
+            step: 5,
+            accelerate: true,
+            smooth: false,
+        };
+        dao.set_scroll_preferences(&prefs).unwrap();
+        assert_eq!(dao.get_scroll_preferences().unwrap(), prefs);
+    }
+
+    #[test]
+    fn test_scroll_preferences_partial_json_uses_defaults() {
+        let prefs: ScrollPreferences = serde_json::from_str(r#"{"step": 7}"#).unwrap();
+        assert_eq!(prefs.step, 7);
+        assert!(!prefs.accelerate);
+        assert!(!prefs.smooth);
+    }
+
+    #[test]
+    fn test_scroll_preferences_base_step() {
+        let mut prefs = ScrollPreferences::default();
+        assert_eq!(prefs.base_step(), 3);
+        prefs.step = 0;
+        assert_eq!(prefs.base_step(), 1);
+        prefs.step = 500;
+        assert_eq!(prefs.base_step(), ScrollPreferences::MAX_STEP);
+        prefs.smooth = true;
+        assert_eq!(prefs.base_step(), 1);
+    }
+
+    #[test]
+    fn test_color_mode_roundtrip() {
+        let dao = setup_test_dao();
+        assert_eq!(dao.get_color_mode().unwrap(), ColorMode::Theme);
+
+        dao.set_color_mode(ColorMode::HighContrast).unwrap();
+        assert_eq!(dao.get_color_mode().unwrap(), ColorMode::HighContrast);
+        assert_eq!(
+            serde_json::to_string(&ColorMode::HighContrast).unwrap(),
+            r#""high-contrast""#
+        );
+    }
+
+    #[test]
+    fn test_color_mode_parse() {
+        assert_eq!(ColorMode::parse("off"), Some(ColorMode::Monochrome));
+        assert_eq!(ColorMode::parse("on"), Some(ColorMode::Theme));
+        assert_eq!(
+            ColorMode::parse("high-contrast"),
+            Some(ColorMode::HighContrast)
+        );
+        assert_eq!(ColorMode::parse("rainbow"), None);
+    }
+
+    #[test]
+    fn test_density_roundtrip() {
+        let dao = setup_test_dao();
+        assert_eq!(dao.get_density().unwrap(), Density::Comfortable);
+
+        dao.set_density(Density::Compact).unwrap();
+        assert_eq!(dao.get_density().unwrap(), Density::Compact);
+        assert_eq!(Density::parse("compact"), Some(Density::Compact));
+        assert_eq!(Density::Compact.toggled(), Density::Comfortable);
+        assert_eq!(Density::parse("dense"), None);
+    }
+
+    #[test]
+    fn test_history_scope_roundtrip() {
+        let dao = setup_test_dao();
+        assert_eq!(dao.get_history_scope().unwrap(), HistoryScope::Project);
+
+        dao.set_history_scope(HistoryScope::Global).unwrap();
+        assert_eq!(dao.get_history_scope().unwrap(), HistoryScope::Global);
+        assert_eq!(HistoryScope::parse("project"), Some(HistoryScope::Project));
+        assert_eq!(HistoryScope::Global.toggled(), HistoryScope::Project);
+        assert_eq!(HistoryScope::parse("session"), None);
+    }
+
+    #[test]
+    fn test_command_prefix_roundtrip() {
+        let dao = setup_test_dao();
+        assert_eq!(dao.get_command_prefix().unwrap(), "/");
+
+        dao.set_command_prefix(":").unwrap();
+        assert_eq!(dao.get_command_prefix().unwrap(), ":");
+        dao.set_command_prefix("go").unwrap();
+        assert_eq!(dao.get_command_prefix().unwrap(), "/");
+    }
+
+    #[test]
+    fn test_markdown_renderer_roundtrip() {
+        let dao = setup_test_dao();
+        assert_eq!(
+            dao.get_markdown_renderer().unwrap(),
+            MarkdownRendererKind::TuiMarkdown
+        );
+
+        dao.set_markdown_renderer(MarkdownRendererKind::Builtin)
+            .unwrap();
+        assert_eq!(
+            dao.get_markdown_renderer().unwrap(),
+            MarkdownRendererKind::Builtin
+        );
+        assert_eq!(
+            MarkdownRendererKind::parse("tui-markdown"),
+            Some(MarkdownRendererKind::TuiMarkdown)
+        );
+        assert_eq!(MarkdownRendererKind::parse("html"), None);
+    }
+
     #[test]
     fn test_model_ref_equality() {
         let ref1 = ModelRef {
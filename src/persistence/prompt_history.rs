@@ -3,8 +3,9 @@ use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
-use super::{ensure_data_dir, get_data_dir, migrations::run_migrations};
+use super::{ensure_data_dir, get_data_dir, migrations::run_migrations, HistoryScope};
 
+/// Prompts kept per working directory, and recalled at most.
 const MAX_HISTORY_SIZE: usize = 100;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,44 +31,52 @@ impl PromptHistoryDAO {
         Ok(Self { conn })
     }
 
-    pub fn add_prompt(&self, prompt: &str) -> Result<()> {
+    #[cfg(test)]
+    pub fn new_in_memory() -> Result<Self> {
+        let mut conn = Connection::open_in_memory()?;
+        run_migrations(&mut conn)?;
+
+        Ok(Self { conn })
+    }
+
+    /// Records `prompt` as sent from `cwd`.
+    pub fn add_prompt(&self, prompt: &str, cwd: Option<&str>) -> Result<()> {
         if prompt.trim().is_empty() {
             return Ok(());
         }
 
         self.conn.execute(
-            "INSERT INTO prompt_history (prompt, timestamp) VALUES (?1, strftime('%s', 'now'))",
-            params![prompt],
+            "INSERT INTO prompt_history (prompt, cwd, timestamp) VALUES (?1, ?2, strftime('%s', 'now'))",
+            params![prompt, cwd],
         )?;
 
-        self.cleanup_old_entries()?;
+        self.cleanup_old_entries(cwd)?;
         Ok(())
     }
 
-    fn cleanup_old_entries(&self) -> Result<()> {
-        let count: i64 = self
-            .conn
-            .query_row("SELECT COUNT(*) FROM prompt_history", [], |row| row.get(0))?;
-
-        if count > MAX_HISTORY_SIZE as i64 {
-            let to_delete = count - MAX_HISTORY_SIZE as i64;
-            self.conn.execute(
-                "DELETE FROM prompt_history WHERE id IN (
-                    SELECT id FROM prompt_history ORDER BY timestamp ASC LIMIT ?1
-                )",
-                params![to_delete],
-            )?;
-        }
+    /// Keeps the newest `MAX_HISTORY_SIZE` prompts of `cwd`, so a busy
+    /// project doesn't push out the history of the others.
+    fn cleanup_old_entries(&self, cwd: Option<&str>) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM prompt_history WHERE cwd IS ?1 AND id NOT IN (
+                SELECT id FROM prompt_history WHERE cwd IS ?1
+                ORDER BY timestamp DESC, id DESC LIMIT ?2
+            )",
+            params![cwd, MAX_HISTORY_SIZE as i64],
+        )?;
 
         Ok(())
     }
 
-    pub fn get_recent_prompts(&self, limit: usize) -> Result<Vec<PromptEntry>> {
+    /// The newest prompts sent from `cwd`, or from anywhere when it's `None`.
+    pub fn get_recent_prompts(&self, limit: usize, cwd: Option<&str>) -> Result<Vec<PromptEntry>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, prompt, timestamp FROM prompt_history ORDER BY timestamp DESC LIMIT ?1",
+            "SELECT id, prompt, timestamp FROM prompt_history
+             WHERE ?2 IS NULL OR cwd = ?2
+             ORDER BY timestamp DESC, id DESC LIMIT ?1",
         )?;
 
-        let prompt_iter = stmt.query_map(params![limit as i64], |row| {
+        let prompt_iter = stmt.query_map(params![limit as i64, cwd], |row| {
             Ok(PromptEntry {
                 id: row.get(0)?,
                 prompt: row.get(1)?,
@@ -89,22 +98,52 @@ pub struct PromptHistoryCache {
     prompts: VecDeque<String>,
     current_index: Option<usize>,
     dao: PromptHistoryDAO,
+    /// Directory new prompts are recorded under
+    cwd: Option<String>,
+    scope: HistoryScope,
 }
 
 impl PromptHistoryCache {
     pub fn new() -> Result<Self> {
-        let dao = PromptHistoryDAO::new()?;
-        let prompts = dao
-            .get_recent_prompts(MAX_HISTORY_SIZE)?
-            .into_iter()
-            .map(|entry| entry.prompt)
-            .collect();
+        Self::with_dao(PromptHistoryDAO::new()?)
+    }
 
-        Ok(Self {
-            prompts,
+    /// Starts out with the global history until `set_scope` picks one.
+    fn with_dao(dao: PromptHistoryDAO) -> Result<Self> {
+        let mut cache = Self {
+            prompts: VecDeque::new(),
             current_index: None,
             dao,
-        })
+            cwd: None,
+            scope: HistoryScope::Global,
+        };
+        cache.reload()?;
+        Ok(cache)
+    }
+
+    /// Records new prompts under `cwd` and recalls the ones `scope` covers.
+    pub fn set_scope(&mut self, cwd: Option<String>, scope: HistoryScope) -> Result<()> {
+        if self.cwd == cwd && self.scope == scope {
+            return Ok(());
+        }
+        self.cwd = cwd;
+        self.scope = scope;
+        self.reload()
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        let cwd = match self.scope {
+            HistoryScope::Project => self.cwd.as_deref(),
+            HistoryScope::Global => None,
+        };
+        self.prompts = self
+            .dao
+            .get_recent_prompts(MAX_HISTORY_SIZE, cwd)?
+            .into_iter()
+            .map(|entry| entry.prompt)
+            .collect();
+        self.current_index = None;
+        Ok(())
     }
 
     pub fn add_prompt(&mut self, prompt: &str) -> Result<()> {
@@ -112,7 +151,7 @@ impl PromptHistoryCache {
             return Ok(());
         }
 
-        self.dao.add_prompt(prompt)?;
+        self.dao.add_prompt(prompt, self.cwd.as_deref())?;
 
         if let Some(pos) = self.prompts.iter().position(|p| p == prompt) {
             self.prompts.remove(pos);
@@ -174,3 +213,68 @@ impl PromptHistoryCache {
         self.current_index.is_some()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache_in(cwd: &str, scope: HistoryScope) -> PromptHistoryCache {
+        let mut cache =
+            PromptHistoryCache::with_dao(PromptHistoryDAO::new_in_memory().unwrap()).unwrap();
+        cache.set_scope(Some(cwd.to_string()), scope).unwrap();
+        cache
+    }
+
+    #[test]
+    fn test_project_history_recalls_only_its_directory() {
+        let mut cache = cache_in("/work/api", HistoryScope::Project);
+        cache.add_prompt("run the api tests").unwrap();
+        cache
+            .set_scope(Some("/work/web".to_string()), HistoryScope::Project)
+            .unwrap();
+        assert_eq!(cache.len(), 0);
+        cache.add_prompt("fix the navbar").unwrap();
+        cache.add_prompt("bump react").unwrap();
+
+        assert_eq!(cache.navigate_up("").as_deref(), Some("bump react"));
+        assert_eq!(cache.navigate_up("").as_deref(), Some("fix the navbar"));
+        assert_eq!(cache.navigate_up(""), None);
+
+        cache
+            .set_scope(Some("/work/api".to_string()), HistoryScope::Project)
+            .unwrap();
+        assert_eq!(cache.navigate_up("").as_deref(), Some("run the api tests"));
+
+        cache
+            .set_scope(Some("/work/api".to_string()), HistoryScope::Global)
+            .unwrap();
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn test_history_is_capped_per_directory() {
+        let mut cache = cache_in("/work/api", HistoryScope::Project);
+        cache.add_prompt("the only api prompt").unwrap();
+        cache
+            .set_scope(Some("/work/web".to_string()), HistoryScope::Project)
+            .unwrap();
+        for i in 0..MAX_HISTORY_SIZE + 5 {
+            cache.add_prompt(&format!("web prompt {}", i)).unwrap();
+        }
+
+        let web = cache
+            .dao
+            .get_recent_prompts(1000, Some("/work/web"))
+            .unwrap();
+        assert_eq!(web.len(), MAX_HISTORY_SIZE);
+        assert_eq!(
+            web[0].prompt,
+            format!("web prompt {}", MAX_HISTORY_SIZE + 4)
+        );
+        let api = cache
+            .dao
+            .get_recent_prompts(1000, Some("/work/api"))
+            .unwrap();
+        assert_eq!(api.len(), 1);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::persistence::Tone;
 use crate::tools::ToolRegistry;
 
 mod rules;
@@ -35,6 +36,7 @@ pub struct SystemPromptComposer {
     is_git_repo: bool,
     platform: String,
     tool_registry: Option<ToolRegistry>,
+    tone: Tone,
 }
 
 impl SystemPromptComposer {
@@ -50,6 +52,7 @@ impl SystemPromptComposer {
             is_git_repo,
             platform: platform.into(),
             tool_registry: None,
+            tone: Tone::default(),
         }
     }
 
@@ -58,13 +61,19 @@ impl SystemPromptComposer {
         self
     }
 
+    pub fn with_tone(mut self, tone: Tone) -> Self {
+        self.tone = tone;
+        self
+    }
+
     pub async fn compose(&self,
     ) -> String {
-        let mut parts = Vec::new();
-
-        parts.push(self.get_header());
-        parts.push(self.get_core_prompt());
-        parts.push(self.get_environment_context());
+        let mut parts = vec![
+            self.get_header(),
+            self.get_core_prompt(),
+            self.get_tone_prompt(),
+            self.get_environment_context(),
+        ];
         
         if let Some(ref registry) = self.tool_registry {
             parts.push(self.get_tools_context(registry).await);
@@ -222,6 +231,31 @@ File Handling:
 Your output will be displayed on a command line interface. Your responses should be short and concise (typically < 4 lines, excluding tool calls)."#.to_string()
     }
 
+    /// Appended after the core prompt, so it wins over that prompt's own
+    /// length guidance.
+    fn get_tone_prompt(&self) -> String {
+        let directives = match self.tone {
+            Tone::Default => return String::new(),
+            Tone::Concise => {
+                "- Keep answers short and to the point\n- Skip background the user didn't ask for\n- One short sentence before tool calls at most"
+            }
+            Tone::Verbose => {
+                "- Explain what you are doing and why\n- Describe the changes you made and their trade-offs\n- Mention alternatives you considered when relevant"
+            }
+            Tone::Teaching => {
+                "- Explain the concepts behind your answer as if to a learner\n- Walk through changes step by step, pointing out what to take away\n- Suggest what to read or try next"
+            }
+            Tone::Terse => {
+                "- Answer in as few words as possible\n- No preamble, summaries or explanations unless asked\n- Prefer code and file references over prose"
+            }
+        };
+        format!(
+            "Tone ({}), overriding the length guidance above:\n{}",
+            self.tone.label(),
+            directives
+        )
+    }
+
     fn get_environment_context(&self) -> String {
         let git_status = if self.is_git_repo { "yes" } else { "no" };
         let date = chrono::Local::now().format("%a %b %d %Y").to_string();
@@ -280,4 +314,15 @@ mod tests {
         assert_eq!(ProviderType::from_model_id("gemini-pro"), ProviderType::Gemini);
         assert_eq!(ProviderType::from_model_id("unknown"), ProviderType::Generic);
     }
+
+    #[test]
+    fn test_tone_prompt() {
+        let composer = SystemPromptComposer::new("claude-3", "/tmp", false, "linux");
+        assert_eq!(composer.get_tone_prompt(), "");
+
+        let composer = composer.with_tone(Tone::Terse);
+        let prompt = composer.get_tone_prompt();
+        assert!(prompt.starts_with("Tone (terse)"));
+        assert!(prompt.contains("as few words as possible"));
+    }
 }
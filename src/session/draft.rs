@@ -0,0 +1,143 @@
+use std::time::{Duration, Instant};
+
+use crate::command::parser::DEFAULT_PREFIX;
+
+/// How long typing has to pause before the draft is saved
+pub const DRAFT_SAVE_DELAY: Duration = Duration::from_secs(2);
+/// Drafts shorter than this aren't worth restoring
+pub const MIN_DRAFT_CHARS: usize = 12;
+
+/// What to do with the stored draft.
+#[derive(Debug, PartialEq)]
+pub enum DraftChange {
+    Save(String),
+    Clear,
+}
+
+/// Decides when the input should be saved as a draft: once it has stopped
+/// changing for `DRAFT_SAVE_DELAY`. Commands are never saved, and a draft
+/// cut down to almost nothing is cleared.
+#[derive(Debug)]
+pub struct DraftAutosave {
+    /// The input as it was last saved or restored
+    saved: String,
+    /// Input that differs from `saved`, and when it last changed
+    pending: Option<(String, Instant)>,
+    /// What commands start with
+    command_prefix: String,
+}
+
+impl Default for DraftAutosave {
+    fn default() -> Self {
+        Self {
+            saved: String::new(),
+            pending: None,
+            command_prefix: DEFAULT_PREFIX.to_string(),
+        }
+    }
+}
+
+impl DraftAutosave {
+    pub fn set_command_prefix(&mut self, prefix: &str) {
+        self.command_prefix = prefix.to_string();
+    }
+
+    /// A doubled prefix escapes it, so that's a message worth keeping.
+    fn is_command(&self, text: &str) -> bool {
+        text.strip_prefix(self.command_prefix.as_str())
+            .is_some_and(|rest| !rest.starts_with(self.command_prefix.as_str()))
+    }
+
+    /// Starts over from `text`, the draft that's in the input now.
+    pub fn reset(&mut self, text: &str) {
+        self.saved = text.to_string();
+        self.pending = None;
+    }
+
+    /// Called with the input every frame. Returns the change to store once
+    /// the input has sat still long enough.
+    pub fn poll(&mut self, text: &str, now: Instant) -> Option<DraftChange> {
+        if text == self.saved || self.is_command(text) {
+            self.pending = None;
+            return None;
+        }
+        match &self.pending {
+            Some((pending, since)) if pending == text => {
+                if now.duration_since(*since) < DRAFT_SAVE_DELAY {
+                    return None;
+                }
+            }
+            _ => {
+                self.pending = Some((text.to_string(), now));
+                return None;
+            }
+        }
+        self.flush(text)
+    }
+
+    /// The change to store for `text` right away, before quitting or
+    /// leaving the session.
+    pub fn flush(&mut self, text: &str) -> Option<DraftChange> {
+        self.pending = None;
+        if text == self.saved || self.is_command(text) {
+            return None;
+        }
+        self.saved = text.to_string();
+        if text.trim().chars().count() < MIN_DRAFT_CHARS {
+            Some(DraftChange::Clear)
+        } else {
+            Some(DraftChange::Save(text.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saves_after_typing_pauses() {
+        let mut autosave = DraftAutosave::default();
+        let start = Instant::now();
+        let draft = "Refactor the parser so";
+
+        assert_eq!(autosave.poll("Refactor", start), None);
+        // Still typing: the delay starts over
+        assert_eq!(autosave.poll(draft, start + DRAFT_SAVE_DELAY), None);
+        assert_eq!(
+            autosave.poll(draft, start + DRAFT_SAVE_DELAY + Duration::from_secs(1)),
+            None
+        );
+        assert_eq!(
+            autosave.poll(draft, start + DRAFT_SAVE_DELAY * 2),
+            Some(DraftChange::Save(draft.to_string()))
+        );
+        assert_eq!(autosave.poll(draft, start + DRAFT_SAVE_DELAY * 3), None);
+    }
+
+    #[test]
+    fn test_commands_and_short_drafts() {
+        let mut autosave = DraftAutosave::default();
+        autosave.reset("Refactor the parser so");
+
+        assert_eq!(autosave.flush("/sessions"), None);
+        assert_eq!(autosave.flush("ok"), Some(DraftChange::Clear));
+        assert_eq!(autosave.flush("ok"), None);
+
+        let start = Instant::now();
+        assert_eq!(autosave.poll("/new", start), None);
+        assert_eq!(autosave.poll("/new", start + DRAFT_SAVE_DELAY), None);
+
+        assert_eq!(
+            autosave.flush("//etc/hosts looks wrong"),
+            Some(DraftChange::Save("//etc/hosts looks wrong".to_string()))
+        );
+
+        autosave.set_command_prefix(":");
+        assert_eq!(autosave.flush(":sessions"), None);
+        assert_eq!(
+            autosave.flush("/usr/local is missing"),
+            Some(DraftChange::Save("/usr/local is missing".to_string()))
+        );
+    }
+}
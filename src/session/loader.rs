@@ -0,0 +1,93 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use anyhow::Result;
+
+use crate::persistence::HistoryDAO;
+use crate::session::types::Message;
+
+/// The newest messages of a session and how many it has in total.
+#[derive(Debug, Default)]
+pub struct SessionPage {
+    pub messages: Vec<Message>,
+    pub total: usize,
+}
+
+/// A session page read on a background thread, so switching into a long
+/// session doesn't stall the event loop.
+pub struct SessionLoad {
+    pub id: String,
+    receiver: Receiver<Result<SessionPage>>,
+}
+
+impl SessionLoad {
+    /// Reads the newest `limit` messages of stored session `db_id` through
+    /// its own connection to the database at `path`.
+    pub fn spawn(id: String, path: PathBuf, db_id: i64, limit: usize) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(read_page(&path, db_id, limit));
+        });
+        Self { id, receiver }
+    }
+
+    /// The page once it has been read, without blocking.
+    pub fn poll(&self) -> Option<Result<SessionPage>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(anyhow::anyhow!("session loader stopped"))),
+        }
+    }
+
+    /// Blocks until the page has been read.
+    pub fn wait(self) -> Result<SessionPage> {
+        self.receiver.recv()?
+    }
+}
+
+fn read_page(path: &Path, db_id: i64, limit: usize) -> Result<SessionPage> {
+    let dao = HistoryDAO::open(path)?;
+    let total = dao.count_messages(db_id)?;
+    let messages = dao
+        .get_messages_page(db_id, 0, limit)?
+        .into_iter()
+        .filter_map(|m| m.try_into().ok())
+        .collect();
+    Ok(SessionPage { messages, total })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loads_newest_page_in_background() {
+        let dir = std::env::temp_dir().join(format!("crabcode-loader-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("data.db");
+        let dao = HistoryDAO::open(&path).unwrap();
+        let db_id = dao.create_session("long".to_string()).unwrap();
+        for i in 0..5 {
+            let mut message: crate::persistence::Message =
+                Message::user(format!("message {}", i)).into();
+            message.session_id = db_id;
+            message.timestamp = i;
+            dao.add_message(&message).unwrap();
+        }
+
+        let load = SessionLoad::spawn("session-1".to_string(), path, db_id, 2);
+        let page = load.wait().unwrap();
+        assert_eq!(page.total, 5);
+        let contents: Vec<&str> = page.messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["message 3", "message 4"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_missing_database_is_an_error() {
+        let path = std::env::temp_dir().join("crabcode-loader-missing/nested/data.db");
+        let load = SessionLoad::spawn("session-1".to_string(), path, 1, 10);
+        assert!(load.wait().is_err());
+    }
+}
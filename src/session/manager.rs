@@ -1,12 +1,30 @@
 use crate::persistence::HistoryDAO;
-use crate::session::types::Session;
-use std::collections::HashMap;
-use std::time::SystemTime;
+use crate::session::types::{SamplingParams, Session};
+use crate::session::usage::{UsageReport, USAGE_DAYS};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How long a deleted session can still be brought back with undo.
+pub const UNDO_DELETE_WINDOW: Duration = Duration::from_secs(5);
 
 #[derive(Debug)]
 pub enum SessionError {
     NotFound(String),
     PersistenceError(String),
+    /// A message index past the end of the session
+    MessageOutOfRange(usize),
+}
+
+impl std::fmt::Display for SessionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionError::NotFound(id) => write!(f, "session {} not found", id),
+            SessionError::PersistenceError(e) => write!(f, "{}", e),
+            SessionError::MessageOutOfRange(index) => {
+                write!(f, "the session has no message {}", index + 1)
+            }
+        }
+    }
 }
 
 impl From<anyhow::Error> for SessionError {
@@ -22,6 +40,17 @@ pub struct SessionInfo {
     pub created_at: SystemTime,
     pub updated_at: SystemTime,
     pub message_count: usize,
+    pub pinned: bool,
+    pub tags: Vec<String>,
+    /// Changed in the background since the user last opened it
+    pub updated: bool,
+}
+
+struct DeletedSession {
+    session: Session,
+    tool_calls: Vec<crate::persistence::ToolCallRecord>,
+    was_current: bool,
+    deleted_at: Instant,
 }
 
 pub struct SessionManager {
@@ -31,6 +60,11 @@ pub struct SessionManager {
     history_dao: Option<HistoryDAO>,
     id_mapping: HashMap<String, i64>,
     db_id_to_id: HashMap<i64, String>,
+    recently_deleted: Option<DeletedSession>,
+    /// Sessions changed while not open, cleared when they're opened
+    updated_in_background: HashSet<String>,
+    /// Usage across all sessions from the last `/usage`
+    usage: Option<UsageReport>,
 }
 
 impl SessionManager {
@@ -42,35 +76,52 @@ impl SessionManager {
             history_dao: None,
             id_mapping: HashMap::new(),
             db_id_to_id: HashMap::new(),
+            recently_deleted: None,
+            updated_in_background: HashSet::new(),
+            usage: None,
         }
     }
 
-    pub fn with_history(mut self) -> Result<Self, SessionError> {
+    pub fn with_history(self) -> Result<Self, SessionError> {
         let history_dao =
             HistoryDAO::new().map_err(|e| SessionError::PersistenceError(e.to_string()))?;
+        self.with_history_dao(history_dao)
+    }
+
+    pub fn with_history_dao(mut self, history_dao: HistoryDAO) -> Result<Self, SessionError> {
         self.load_sessions_from_db(&history_dao)?;
         self.history_dao = Some(history_dao);
         Ok(self)
     }
 
+    #[cfg(test)]
+    pub fn with_in_memory_history(mut self) -> Self {
+        self.history_dao = Some(HistoryDAO::new_in_memory().unwrap());
+        self
+    }
+
+    /// Makes sure everything added to history has been written to disk.
+    pub fn flush(&self) -> Result<(), SessionError> {
+        if let Some(ref dao) = self.history_dao {
+            dao.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Lists the stored sessions without their messages, which are read on
+    /// first use by `ensure_loaded`.
     fn load_sessions_from_db(&mut self, dao: &HistoryDAO) -> Result<(), SessionError> {
         let db_sessions = dao
             .list_sessions()
             .map_err(|e| SessionError::PersistenceError(e.to_string()))?;
 
         for db_session in db_sessions {
-            let messages = dao
-                .get_messages(db_session.id)
-                .map_err(|e| SessionError::PersistenceError(e.to_string()))?;
-
-            let mut session = if messages.is_empty() {
-                Session::with_title(db_session.name.clone())
-            } else {
-                crate::persistence::persistence_to_session(db_session.clone(), messages)
-                    .map_err(|e| SessionError::PersistenceError(e.to_string()))?
-            };
-
-            session.id = cuid2::create_id();
+            let mut session = Session::with_title(db_session.name.clone());
+            session.loaded = false;
+            session.pinned = db_session.pinned;
+            session.tags = db_session.tags.clone();
+            session.sampling = db_session.sampling;
+            session.bookmarks = db_session.bookmarks;
             session.title = db_session.name;
             session.created_at = std::time::UNIX_EPOCH
                 + std::time::Duration::from_secs(db_session.created_at as u64);
@@ -88,62 +139,111 @@ impl SessionManager {
         Ok(())
     }
 
+    /// Starts a session and makes it current. Like the ones loaded from
+    /// history it's keyed by a fresh cuid2; `name` is only its title,
+    /// `session-N` by default.
     pub fn create_session(&mut self, name: Option<String>) -> String {
         self.session_counter += 1;
-        let title = name
-            .clone()
-            .unwrap_or_else(|| format!("session-{}", self.session_counter));
+        let title = name.unwrap_or_else(|| format!("session-{}", self.session_counter));
 
-        let session_id = if let Some(ref session_name) = name {
-            session_name.clone()
-        } else {
-            format!("session-{}", self.session_counter)
-        };
-
-        let mut session = Session::with_title(title.clone());
-        session.id = session_id.clone();
+        let session = Session::with_title(title.clone());
+        let session_id = session.id.clone();
 
         self.sessions.insert(session_id.clone(), session);
         self.current_session_id = Some(session_id.clone());
 
+        // Without a row there's nothing to map, so the session just isn't
+        // saved rather than pointing at someone else's row
         if let Some(ref dao) = self.history_dao {
-            let db_id = dao
-                .create_session(title.clone())
-                .unwrap_or_else(|_| self.session_counter as i64);
-            self.id_mapping.insert(session_id.clone(), db_id);
-            self.db_id_to_id.insert(db_id, session_id.clone());
+            if let Ok(db_id) = dao.create_session(title) {
+                self.id_mapping.insert(session_id.clone(), db_id);
+                self.db_id_to_id.insert(db_id, session_id.clone());
+            }
         }
 
         session_id
     }
 
+    /// Every session, with message counts from history where there is one
+    /// since messages added this run only go there.
     pub fn list_sessions(&self) -> Vec<SessionInfo> {
+        let stored = self
+            .history_dao
+            .as_ref()
+            .and_then(|dao| dao.count_messages_by_session().ok());
+
         self.sessions
             .iter()
-            .map(|(id, session)| SessionInfo {
-                id: id.clone(),
-                title: session.title.clone(),
-                created_at: session.created_at,
-                updated_at: session.updated_at,
-                message_count: session.messages.len(),
+            .map(|(id, session)| {
+                let message_count = match (&stored, self.id_mapping.get(id)) {
+                    (Some(stored), Some(db_id)) => stored.get(db_id).copied().unwrap_or(0),
+                    _ => session.messages.len(),
+                };
+                self.session_info(id, session, message_count)
             })
             .collect()
     }
 
-    pub fn get_current_session(&mut self) -> Option<&mut Session> {
-        if let Some(id) = &self.current_session_id {
-            self.sessions.get_mut(id)
-        } else {
-            None
+    /// Like one entry of `list_sessions`.
+    pub fn get_session_info(&self, id: &str) -> Option<SessionInfo> {
+        let session = self.sessions.get(id)?;
+        Some(self.session_info(id, session, self.message_count(id)))
+    }
+
+    fn session_info(&self, id: &str, session: &Session, message_count: usize) -> SessionInfo {
+        SessionInfo {
+            id: id.to_string(),
+            title: session.title.clone(),
+            created_at: session.created_at,
+            updated_at: session.updated_at,
+            message_count,
+            pinned: session.pinned,
+            tags: session.tags.clone(),
+            updated: self.updated_in_background.contains(id),
         }
     }
 
+    pub fn get_current_session(&mut self) -> Option<&mut Session> {
+        let id = self.current_session_id.clone()?;
+        self.get_session(&id)
+    }
+
     pub fn get_session(&mut self, id: &str) -> Option<&mut Session> {
+        self.ensure_loaded(id);
         self.sessions.get_mut(id)
     }
 
+    /// All messages stored for a session, or `None` without history.
+    fn stored_messages(&self, id: &str) -> Option<Vec<crate::session::types::Message>> {
+        let (Some(dao), Some(db_id)) = (&self.history_dao, self.id_mapping.get(id)) else {
+            return None;
+        };
+        let messages = dao.get_messages(*db_id).ok()?;
+        Some(
+            messages
+                .into_iter()
+                .filter_map(|m| m.try_into().ok())
+                .collect(),
+        )
+    }
+
+    /// Reads a session's messages from history the first time they're
+    /// needed.
+    fn ensure_loaded(&mut self, id: &str) {
+        if self.sessions.get(id).is_none_or(|s| s.loaded) {
+            return;
+        }
+        let messages = self.stored_messages(id);
+        if let Some(session) = self.sessions.get_mut(id) {
+            session.messages = messages.unwrap_or_default();
+            session.loaded = true;
+        }
+    }
+
     pub fn switch_session(&mut self, id: &str) -> bool {
         if self.sessions.contains_key(id) {
+            self.ensure_loaded(id);
+            self.updated_in_background.remove(id);
             self.current_session_id = Some(id.to_string());
             true
         } else {
@@ -155,6 +255,20 @@ impl SessionManager {
         self.current_session_id.as_ref()
     }
 
+    /// Records activity on a session: bumps its `updated_at`, and flags it
+    /// for the sessions dialog when the change happened in the background
+    /// (auto-titling, a queued turn). The open session is never flagged
+    /// since its changes are already on screen.
+    pub fn mark_updated(&mut self, id: &str) {
+        let Some(session) = self.sessions.get_mut(id) else {
+            return;
+        };
+        session.updated_at = SystemTime::now();
+        if self.current_session_id.as_deref() != Some(id) {
+            self.updated_in_background.insert(id.to_string());
+        }
+    }
+
     pub fn clear_current_session(&mut self) {
         self.current_session_id = None;
     }
@@ -176,12 +290,36 @@ impl SessionManager {
                     .map_err(|e| SessionError::PersistenceError(e.to_string()));
             }
         }
+        if let Some(session_id) = self.current_session_id.clone() {
+            self.mark_updated(&session_id);
+        }
         Ok(())
     }
 
+    /// Adds a tool invocation to the current session's audit log.
+    pub fn record_tool_call(&self, record: &crate::persistence::ToolCallRecord) {
+        if let (Some(session_id), Some(ref dao)) = (&self.current_session_id, &self.history_dao) {
+            if let Some(db_id) = self.id_mapping.get(session_id) {
+                let _ = dao.add_tool_call(&crate::persistence::ToolCallRecord {
+                    session_id: *db_id,
+                    ..record.clone()
+                });
+            }
+        }
+    }
+
+    /// Tool invocations recorded for a session, oldest first.
+    pub fn tool_calls(&self, id: &str) -> Vec<crate::persistence::ToolCallRecord> {
+        match (&self.history_dao, self.id_mapping.get(id)) {
+            (Some(dao), Some(db_id)) => dao.get_tool_calls(*db_id).unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+
     pub fn rename_session(&mut self, id: &str, new_title: String) -> Result<(), SessionError> {
         if let Some(session) = self.sessions.get_mut(id) {
             session.title = new_title.clone();
+            // Not `mark_updated`: the user renamed it, so it isn't news to them
             session.updated_at = SystemTime::now();
 
             if let Some(ref dao) = self.history_dao {
@@ -196,6 +334,223 @@ impl SessionManager {
         }
     }
 
+    /// Number of messages stored for a session, preferring history over the
+    /// in-memory copy since only history sees messages added this run.
+    pub fn message_count(&self, id: &str) -> usize {
+        if let (Some(ref dao), Some(db_id)) = (&self.history_dao, self.id_mapping.get(id)) {
+            if let Ok(count) = dao.count_messages(*db_id) {
+                return count;
+            }
+        }
+        self.sessions.get(id).map_or(0, |s| s.messages.len())
+    }
+
+    /// The database file and row id of a stored session, so its messages
+    /// can be read without going through this manager.
+    pub fn history_location(&self, id: &str) -> Option<(std::path::PathBuf, i64)> {
+        let path = self.history_dao.as_ref()?.path()?;
+        let db_id = self.id_mapping.get(id)?;
+        Some((path.to_path_buf(), *db_id))
+    }
+
+    /// Loads up to `limit` messages older than the newest `skip_newest`,
+    /// oldest first. Used to page long sessions into the chat lazily.
+    pub fn load_messages_page(
+        &self,
+        id: &str,
+        skip_newest: usize,
+        limit: usize,
+    ) -> Vec<crate::session::types::Message> {
+        if let (Some(ref dao), Some(db_id)) = (&self.history_dao, self.id_mapping.get(id)) {
+            if let Ok(messages) = dao.get_messages_page(*db_id, skip_newest, limit) {
+                return messages
+                    .into_iter()
+                    .filter_map(|m| m.try_into().ok())
+                    .collect();
+            }
+        }
+
+        let Some(session) = self.sessions.get(id) else {
+            return Vec::new();
+        };
+        let end = session.messages.len().saturating_sub(skip_newest);
+        let start = end.saturating_sub(limit);
+        session.messages[start..end].to_vec()
+    }
+
+    /// Starts a new session with the messages of `from` up to and including
+    /// `at_message_index`, so the conversation can go another way without
+    /// losing the original. Returns the new session's id; the current
+    /// session stays as it is.
+    pub fn branch(&mut self, from: &str, at_message_index: usize) -> Result<String, SessionError> {
+        let source = self
+            .sessions
+            .get(from)
+            .ok_or_else(|| SessionError::NotFound(from.to_string()))?;
+        let title = format!("{} (branch)", source.title);
+        let sampling = source.sampling.clone();
+        let bookmarks: Vec<usize> = source
+            .bookmarks
+            .iter()
+            .copied()
+            .filter(|&index| index <= at_message_index)
+            .collect();
+        let count = self.message_count(from);
+        if at_message_index >= count {
+            return Err(SessionError::MessageOutOfRange(at_message_index));
+        }
+        let messages =
+            self.load_messages_page(from, count - at_message_index - 1, at_message_index + 1);
+
+        let mut session = Session::with_title(title.clone());
+        session.id = cuid2::create_id();
+        session.sampling = sampling;
+        session.bookmarks = bookmarks;
+        session.messages = messages;
+        let session_id = session.id.clone();
+
+        if let Some(ref dao) = self.history_dao {
+            let db_id = dao.create_session(title)?;
+            dao.set_session_sampling(db_id, &session.sampling)?;
+            dao.set_session_bookmarks(db_id, &session.bookmarks)?;
+            for message in &session.messages {
+                let mut db_message: crate::persistence::Message = message.clone().into();
+                db_message.session_id = db_id;
+                dao.add_message(&db_message)?;
+            }
+            self.id_mapping.insert(session_id.clone(), db_id);
+            self.db_id_to_id.insert(db_id, session_id.clone());
+        }
+
+        self.session_counter += 1;
+        self.sessions.insert(session_id.clone(), session);
+        Ok(session_id)
+    }
+
+    /// Usage across every stored session. It reads every reply, so it's
+    /// worked out on first use and kept until `refresh`.
+    pub fn usage(
+        &mut self,
+        refresh: bool,
+        price_of: impl Fn(&str, &str) -> Option<crate::model::discovery::Cost>,
+    ) -> Result<&UsageReport, SessionError> {
+        let report = match self.usage.take() {
+            Some(report) if !refresh => report,
+            _ => {
+                let dao = self.history_dao.as_ref().ok_or_else(|| {
+                    SessionError::PersistenceError("history is unavailable".to_string())
+                })?;
+                UsageReport::build(
+                    dao.count_sessions()?,
+                    dao.usage_by_model()?,
+                    &dao.output_tokens_by_day(USAGE_DAYS)?,
+                    chrono::Local::now().date_naive(),
+                    price_of,
+                )
+            }
+        };
+        Ok(self.usage.insert(report))
+    }
+
+    /// Flips the pinned flag and returns the new state.
+    pub fn toggle_pin(&mut self, id: &str) -> Result<bool, SessionError> {
+        let session = self
+            .sessions
+            .get_mut(id)
+            .ok_or_else(|| SessionError::NotFound(id.to_string()))?;
+        session.pinned = !session.pinned;
+        let pinned = session.pinned;
+
+        if let (Some(ref dao), Some(db_id)) = (&self.history_dao, self.id_mapping.get(id)) {
+            dao.set_session_pinned(*db_id, pinned)?;
+        }
+
+        Ok(pinned)
+    }
+
+    /// Bookmarks the message at `index` of a session, or removes its
+    /// bookmark. Returns whether it's bookmarked now.
+    pub fn toggle_bookmark(&mut self, id: &str, index: usize) -> Result<bool, SessionError> {
+        if index >= self.message_count(id) {
+            return Err(SessionError::MessageOutOfRange(index));
+        }
+        let session = self
+            .sessions
+            .get_mut(id)
+            .ok_or_else(|| SessionError::NotFound(id.to_string()))?;
+        let bookmarked = session.toggle_bookmark(index);
+
+        if let (Some(ref dao), Some(db_id)) = (&self.history_dao, self.id_mapping.get(id)) {
+            dao.set_session_bookmarks(*db_id, &session.bookmarks)?;
+        }
+
+        Ok(bookmarked)
+    }
+
+    /// Indices of the bookmarked messages of a session, in order.
+    pub fn bookmarks(&self, id: &str) -> Vec<usize> {
+        self.sessions
+            .get(id)
+            .map(|s| s.bookmarks.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn add_tag(&mut self, id: &str, tag: &str) -> Result<bool, SessionError> {
+        let session = self
+            .sessions
+            .get_mut(id)
+            .ok_or_else(|| SessionError::NotFound(id.to_string()))?;
+        if !session.add_tag(tag) {
+            return Ok(false);
+        }
+        self.persist_tags(id)?;
+        Ok(true)
+    }
+
+    pub fn remove_tag(&mut self, id: &str, tag: &str) -> Result<bool, SessionError> {
+        let session = self
+            .sessions
+            .get_mut(id)
+            .ok_or_else(|| SessionError::NotFound(id.to_string()))?;
+        if !session.remove_tag(tag) {
+            return Ok(false);
+        }
+        self.persist_tags(id)?;
+        Ok(true)
+    }
+
+    fn persist_tags(&self, id: &str) -> Result<(), SessionError> {
+        if let (Some(ref dao), Some(db_id), Some(session)) = (
+            &self.history_dao,
+            self.id_mapping.get(id),
+            self.sessions.get(id),
+        ) {
+            dao.set_session_tags(*db_id, &session.tags)?;
+        }
+        Ok(())
+    }
+
+    /// Sampling overrides of the current session; none without one.
+    pub fn current_sampling(&self) -> SamplingParams {
+        self.current_session_id
+            .as_ref()
+            .and_then(|id| self.sessions.get(id))
+            .map(|s| s.sampling.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn set_sampling(&mut self, id: &str, sampling: SamplingParams) -> Result<(), SessionError> {
+        let session = self
+            .sessions
+            .get_mut(id)
+            .ok_or_else(|| SessionError::NotFound(id.to_string()))?;
+        if let (Some(ref dao), Some(db_id)) = (&self.history_dao, self.id_mapping.get(id)) {
+            dao.set_session_sampling(*db_id, &sampling)?;
+        }
+        session.sampling = sampling;
+        Ok(())
+    }
+
     pub fn delete_session(&mut self, id: &str) -> bool {
         if let Some(db_id) = self.id_mapping.get(id) {
             if let Some(ref dao) = self.history_dao {
@@ -204,6 +559,7 @@ impl SessionManager {
         }
 
         if self.sessions.remove(id).is_some() {
+            self.updated_in_background.remove(id);
             if let Some(db_id) = self.id_mapping.remove(id) {
                 self.db_id_to_id.remove(&db_id);
             }
@@ -215,6 +571,95 @@ impl SessionManager {
             false
         }
     }
+
+    /// Deletes every session last updated before `before`, in memory and in
+    /// history. Returns how many were removed.
+    pub fn prune(&mut self, before: SystemTime) -> usize {
+        let stale: Vec<String> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| session.updated_at < before)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        stale.iter().filter(|id| self.delete_session(id)).count()
+    }
+
+    pub fn clear(&mut self) -> usize {
+        let ids: Vec<String> = self.sessions.keys().cloned().collect();
+        ids.iter().filter(|id| self.delete_session(id)).count()
+    }
+
+    /// Deletes a session but keeps it in memory so `undo_delete` can bring it
+    /// back within `UNDO_DELETE_WINDOW`. Only the latest deletion is kept.
+    pub fn soft_delete_session(&mut self, id: &str) -> bool {
+        let Some(mut session) = self.sessions.get(id).cloned() else {
+            return false;
+        };
+        // Messages sent this run are only in history, and sessions never
+        // opened have none in memory, so undo restores from a snapshot of it
+        session.messages = self.load_messages_page(id, 0, self.message_count(id));
+        session.loaded = true;
+        let was_current = self.current_session_id.as_deref() == Some(id);
+        let tool_calls = self.tool_calls(id);
+
+        if !self.delete_session(id) {
+            return false;
+        }
+
+        self.recently_deleted = Some(DeletedSession {
+            session,
+            tool_calls,
+            was_current,
+            deleted_at: Instant::now(),
+        });
+        true
+    }
+
+    /// Restores the most recently soft-deleted session, re-inserting it (and
+    /// its messages) into history. Returns `None` once the undo window passed.
+    pub fn undo_delete(&mut self) -> Option<String> {
+        self.undo_delete_within(UNDO_DELETE_WINDOW)
+    }
+
+    fn undo_delete_within(&mut self, window: Duration) -> Option<String> {
+        let deleted = self.recently_deleted.take()?;
+        if deleted.deleted_at.elapsed() > window {
+            return None;
+        }
+
+        let session = deleted.session;
+        let session_id = session.id.clone();
+
+        if let Some(ref dao) = self.history_dao {
+            if let Ok(db_id) = dao.create_session(session.title.clone()) {
+                let _ = dao.set_session_pinned(db_id, session.pinned);
+                let _ = dao.set_session_tags(db_id, &session.tags);
+                let _ = dao.set_session_sampling(db_id, &session.sampling);
+                let _ = dao.set_session_bookmarks(db_id, &session.bookmarks);
+                for message in &session.messages {
+                    let mut db_message: crate::persistence::Message = message.clone().into();
+                    db_message.session_id = db_id;
+                    let _ = dao.add_message(&db_message);
+                }
+                for record in &deleted.tool_calls {
+                    let _ = dao.add_tool_call(&crate::persistence::ToolCallRecord {
+                        session_id: db_id,
+                        ..record.clone()
+                    });
+                }
+                self.id_mapping.insert(session_id.clone(), db_id);
+                self.db_id_to_id.insert(db_id, session_id.clone());
+            }
+        }
+
+        self.sessions.insert(session_id.clone(), session);
+        if deleted.was_current {
+            self.current_session_id = Some(session_id.clone());
+        }
+
+        Some(session_id)
+    }
 }
 
 impl Default for SessionManager {
@@ -239,8 +684,7 @@ mod tests {
     fn test_create_session_default_name() {
         let mut manager = SessionManager::new();
         let id = manager.create_session(None);
-        assert_eq!(id, "session-1");
-        assert!(manager.sessions.contains_key(&id));
+        assert_eq!(manager.get_session(&id).unwrap().title, "session-1");
         assert_eq!(manager.current_session_id, Some(id));
     }
 
@@ -248,8 +692,7 @@ mod tests {
     fn test_create_session_custom_name() {
         let mut manager = SessionManager::new();
         let id = manager.create_session(Some("my-session".to_string()));
-        assert_eq!(id, "my-session");
-        assert!(manager.sessions.contains_key(&id));
+        assert_eq!(manager.get_session(&id).unwrap().title, "my-session");
         assert_eq!(manager.current_session_id, Some(id));
     }
 
@@ -260,9 +703,9 @@ mod tests {
         let id2 = manager.create_session(None);
         let id3 = manager.create_session(None);
 
-        assert_eq!(id1, "session-1");
-        assert_eq!(id2, "session-2");
-        assert_eq!(id3, "session-3");
+        assert_eq!(manager.get_session(&id1).unwrap().title, "session-1");
+        assert_eq!(manager.get_session(&id2).unwrap().title, "session-2");
+        assert_eq!(manager.get_session(&id3).unwrap().title, "session-3");
         assert_eq!(manager.sessions.len(), 3);
     }
 
@@ -299,22 +742,23 @@ mod tests {
     #[test]
     fn test_get_session() {
         let mut manager = SessionManager::new();
-        manager.create_session(Some("test".to_string()));
-        assert!(manager.get_session("test").is_some());
+        let id = manager.create_session(Some("test".to_string()));
+        assert!(manager.get_session(&id).is_some());
+        assert!(manager.get_session("test").is_none());
         assert!(manager.get_session("nonexistent").is_none());
     }
 
     #[test]
     fn test_switch_session() {
         let mut manager = SessionManager::new();
-        manager.create_session(Some("session-1".to_string()));
-        manager.create_session(Some("session-2".to_string()));
+        let first = manager.create_session(Some("session-1".to_string()));
+        let second = manager.create_session(Some("session-2".to_string()));
 
-        assert!(manager.switch_session("session-1"));
-        assert_eq!(manager.current_session_id, Some("session-1".to_string()));
+        assert!(manager.switch_session(&first));
+        assert_eq!(manager.current_session_id, Some(first));
 
-        assert!(manager.switch_session("session-2"));
-        assert_eq!(manager.current_session_id, Some("session-2".to_string()));
+        assert!(manager.switch_session(&second));
+        assert_eq!(manager.current_session_id, Some(second));
 
         assert!(!manager.switch_session("nonexistent"));
     }
@@ -322,22 +766,793 @@ mod tests {
     #[test]
     fn test_delete_session() {
         let mut manager = SessionManager::new();
-        manager.create_session(Some("session-1".to_string()));
-        manager.create_session(Some("session-2".to_string()));
+        let first = manager.create_session(Some("session-1".to_string()));
+        let second = manager.create_session(Some("session-2".to_string()));
 
-        assert!(manager.delete_session("session-1"));
-        assert!(!manager.sessions.contains_key("session-1"));
-        assert!(manager.sessions.contains_key("session-2"));
+        assert!(manager.delete_session(&first));
+        assert!(!manager.sessions.contains_key(&first));
+        assert!(manager.sessions.contains_key(&second));
     }
 
     #[test]
     fn test_delete_current_session() {
+        let mut manager = SessionManager::new();
+        let first = manager.create_session(Some("session-1".to_string()));
+        manager.create_session(Some("session-2".to_string()));
+
+        manager.switch_session(&first);
+        assert!(manager.delete_session(&first));
+        assert!(manager.current_session_id.is_none());
+    }
+
+    fn manager_with_messages(count: usize) -> (SessionManager, String) {
+        let mut manager = SessionManager::new();
+        let id = manager.create_session(Some("long".to_string()));
+        let session = manager.get_session(&id).unwrap();
+        for i in 0..count {
+            session.add_message(crate::session::types::Message::user(format!("msg {}", i)));
+        }
+        (manager, id)
+    }
+
+    #[test]
+    fn test_load_messages_page_in_memory() {
+        let (manager, id) = manager_with_messages(10);
+
+        let newest = manager.load_messages_page(&id, 0, 3);
+        let contents: Vec<&str> = newest.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["msg 7", "msg 8", "msg 9"]);
+
+        let older = manager.load_messages_page(&id, 3, 3);
+        let contents: Vec<&str> = older.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["msg 4", "msg 5", "msg 6"]);
+
+        let rest = manager.load_messages_page(&id, 9, 5);
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].content, "msg 0");
+
+        assert!(manager.load_messages_page(&id, 10, 5).is_empty());
+        assert_eq!(manager.message_count(&id), 10);
+    }
+
+    #[test]
+    fn test_load_messages_page_from_history() {
+        let mut manager = SessionManager::new().with_in_memory_history();
+        let id = manager.create_session(Some("long".to_string()));
+        for i in 0..5 {
+            manager
+                .add_message_to_current_session(&crate::session::types::Message::user(format!(
+                    "msg {}",
+                    i
+                )))
+                .unwrap();
+        }
+
+        assert_eq!(manager.message_count(&id), 5);
+        let page = manager.load_messages_page(&id, 2, 2);
+        let contents: Vec<&str> = page.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["msg 1", "msg 2"]);
+    }
+
+    #[test]
+    fn test_toggle_pin_persists() {
+        let mut manager = SessionManager::new().with_in_memory_history();
+        let id = manager.create_session(Some("session-1".to_string()));
+
+        assert!(manager.toggle_pin(&id).unwrap());
+        let db_id = manager.get_db_id(&id).unwrap();
+        let dao = manager.history_dao.as_ref().unwrap();
+        assert!(dao.get_session(db_id).unwrap().unwrap().pinned);
+
+        assert!(!manager.toggle_pin(&id).unwrap());
+        let dao = manager.history_dao.as_ref().unwrap();
+        assert!(!dao.get_session(db_id).unwrap().unwrap().pinned);
+    }
+
+    #[test]
+    fn test_toggle_pin_missing_session() {
+        let mut manager = SessionManager::new();
+        assert!(matches!(
+            manager.toggle_pin("missing"),
+            Err(SessionError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_tags_persist() {
+        let mut manager = SessionManager::new().with_in_memory_history();
+        let id = manager.create_session(Some("session-1".to_string()));
+
+        assert!(manager.add_tag(&id, "work").unwrap());
+        assert!(!manager.add_tag(&id, "Work").unwrap());
+        assert!(manager.add_tag(&id, "bug").unwrap());
+
+        let db_id = manager.get_db_id(&id).unwrap();
+        let stored = manager
+            .history_dao
+            .as_ref()
+            .unwrap()
+            .get_session(db_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.tags, vec!["work".to_string(), "bug".to_string()]);
+
+        assert!(manager.remove_tag(&id, "WORK").unwrap());
+        let info = manager.list_sessions();
+        assert_eq!(info[0].tags, vec!["bug".to_string()]);
+    }
+
+    #[test]
+    fn test_bookmarks_persist() {
+        use crate::session::types::Message;
+
+        let mut manager = SessionManager::new().with_in_memory_history();
+        let id = manager.create_session(None);
+        for content in ["first", "reply", "second"] {
+            manager
+                .add_message_to_current_session(&Message::user(content))
+                .unwrap();
+        }
+
+        assert!(manager.toggle_bookmark(&id, 2).unwrap());
+        assert!(manager.toggle_bookmark(&id, 0).unwrap());
+        assert!(matches!(
+            manager.toggle_bookmark(&id, 3),
+            Err(SessionError::MessageOutOfRange(3))
+        ));
+        assert_eq!(manager.bookmarks(&id), vec![0, 2]);
+
+        let db_id = manager.get_db_id(&id).unwrap();
+        let stored = manager
+            .history_dao
+            .as_ref()
+            .unwrap()
+            .get_session(db_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.bookmarks, vec![0, 2]);
+
+        // A branch keeps the bookmarks of the messages it copies
+        let branch = manager.branch(&id, 1).unwrap();
+        assert_eq!(manager.bookmarks(&branch), vec![0]);
+
+        assert!(!manager.toggle_bookmark(&id, 2).unwrap());
+        assert_eq!(manager.bookmarks(&id), vec![0]);
+    }
+
+    #[test]
+    fn test_usage_is_cached_until_refresh() {
+        use crate::session::types::Message;
+
+        let mut manager = SessionManager::new().with_in_memory_history();
+        manager.create_session(None);
+        let reply = |content: &str, model: &str, tokens: usize| {
+            let mut message = Message::assistant(content);
+            message.provider = Some("openai".to_string());
+            message.model = Some(model.to_string());
+            message.output_tokens = Some(tokens);
+            message
+        };
+        for message in [
+            Message::user("12345678"),
+            reply("one", "gpt-a", 40),
+            Message::user("again"),
+            reply("two", "gpt-a", 60),
+            reply("three", "gpt-b", 5),
+        ] {
+            manager.add_message_to_current_session(&message).unwrap();
+        }
+
+        let report = manager.usage(false, |_, _| None).unwrap();
+        assert_eq!(report.sessions, 1);
+        assert_eq!(report.models.len(), 2);
+        let top = &report.models[0].usage;
+        assert_eq!(top.model.as_deref(), Some("gpt-a"));
+        assert_eq!((top.replies, top.output_tokens), (2, 100));
+        assert!(top.input_chars > 0);
+        assert_eq!(report.daily_tokens.last().unwrap().1, 105);
+
+        manager.create_session(None);
+        assert_eq!(manager.usage(false, |_, _| None).unwrap().sessions, 1);
+        assert_eq!(manager.usage(true, |_, _| None).unwrap().sessions, 2);
+
+        let mut without_history = SessionManager::new();
+        assert!(without_history.usage(false, |_, _| None).is_err());
+    }
+
+    #[test]
+    fn test_sampling_persists() {
+        use crate::session::types::ReasoningEffort;
+
+        let mut manager = SessionManager::new().with_in_memory_history();
+        assert!(manager.current_sampling().is_default());
+        let id = manager.create_session(Some("session-1".to_string()));
+
+        let sampling = SamplingParams {
+            temperature: Some(0.2),
+            top_p: None,
+            seed: Some(7),
+            stop: vec!["```".to_string()],
+            effort: Some(ReasoningEffort::Medium),
+        };
+        manager.set_sampling(&id, sampling.clone()).unwrap();
+        assert_eq!(manager.current_sampling(), sampling);
+
+        let db_id = manager.get_db_id(&id).unwrap();
+        let stored = manager
+            .history_dao
+            .as_ref()
+            .unwrap()
+            .get_session(db_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.sampling, sampling);
+        assert!(manager.set_sampling("missing", sampling).is_err());
+        assert_eq!(manager.current_sampling().stop, vec!["```".to_string()]);
+    }
+
+    #[test]
+    fn test_prune_filters_by_updated_at() {
+        let mut manager = SessionManager::new().with_in_memory_history();
+        let now = SystemTime::now();
+        let day = Duration::from_secs(24 * 60 * 60);
+
+        let ids: Vec<String> = [("fresh", 1), ("edge", 30), ("old", 31), ("ancient", 400)]
+            .into_iter()
+            .map(|(title, age_days)| {
+                let id = manager.create_session(Some(title.to_string()));
+                manager.get_session(&id).unwrap().updated_at = now - day * age_days;
+                id
+            })
+            .collect();
+
+        let deleted = manager.prune(now - day * 30);
+
+        assert_eq!(deleted, 2);
+        assert!(manager.get_session(&ids[0]).is_some());
+        assert!(manager.get_session(&ids[1]).is_some());
+        assert!(manager.get_session(&ids[2]).is_none());
+        assert!(manager.get_session(&ids[3]).is_none());
+
+        let remaining = manager
+            .history_dao
+            .as_ref()
+            .unwrap()
+            .list_sessions()
+            .unwrap();
+        assert_eq!(remaining.len(), 2);
+    }
+
+    #[test]
+    fn test_prune_nothing_older() {
+        let mut manager = SessionManager::new();
+        manager.create_session(Some("session-1".to_string()));
+
+        let deleted = manager.prune(SystemTime::now() - Duration::from_secs(60 * 60));
+
+        assert_eq!(deleted, 0);
+        assert_eq!(manager.sessions.len(), 1);
+    }
+
+    #[test]
+    fn test_clear_removes_all_sessions() {
         let mut manager = SessionManager::new();
         manager.create_session(Some("session-1".to_string()));
         manager.create_session(Some("session-2".to_string()));
 
-        manager.switch_session("session-1");
-        assert!(manager.delete_session("session-1"));
+        assert_eq!(manager.clear(), 2);
+        assert!(manager.sessions.is_empty());
+        assert!(manager.current_session_id.is_none());
+    }
+
+    #[test]
+    fn test_soft_delete_and_undo() {
+        let mut manager = SessionManager::new().with_in_memory_history();
+        let id = manager.create_session(Some("session-1".to_string()));
+        manager
+            .add_message_to_current_session(&crate::session::types::Message::user("hello"))
+            .unwrap();
+
+        assert!(manager.soft_delete_session(&id));
+        assert!(manager.get_session(&id).is_none());
         assert!(manager.current_session_id.is_none());
+
+        assert_eq!(manager.undo_delete(), Some(id.clone()));
+        assert_eq!(manager.get_session(&id).unwrap().messages.len(), 1);
+        assert_eq!(manager.current_session_id, Some(id.clone()));
+
+        let dao = manager.history_dao.as_ref().unwrap();
+        let db_id = manager.get_db_id(&id).unwrap();
+        assert_eq!(dao.get_messages(db_id).unwrap().len(), 1);
+    }
+
+    fn tool_record(name: &str, output: &str) -> crate::persistence::ToolCallRecord {
+        crate::persistence::ToolCallRecord {
+            session_id: 0,
+            call_id: format!("call-{}", name),
+            name: name.to_string(),
+            args: r#"{"path":"src/main.rs"}"#.to_string(),
+            status: "ok".to_string(),
+            duration_ms: Some(12),
+            output: output.to_string(),
+            timestamp: 1,
+        }
+    }
+
+    #[test]
+    fn test_record_tool_calls() {
+        let mut manager = SessionManager::new().with_in_memory_history();
+        let id = manager.create_session(Some("audited".to_string()));
+
+        manager.record_tool_call(&tool_record("read", "fn main() {}"));
+        manager.record_tool_call(&tool_record("bash", &"x".repeat(5000)));
+
+        let calls = manager.tool_calls(&id);
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].name, "read");
+        assert_eq!(calls[0].duration_ms, Some(12));
+        assert_eq!(
+            calls[1].output.chars().count(),
+            crate::persistence::history::MAX_TOOL_OUTPUT_CHARS
+        );
+        assert_eq!(calls[0].session_id, manager.get_db_id(&id).unwrap());
+    }
+
+    #[test]
+    fn test_undo_delete_restores_tool_calls() {
+        let mut manager = SessionManager::new().with_in_memory_history();
+        let id = manager.create_session(Some("audited".to_string()));
+        manager.record_tool_call(&tool_record("read", "ok"));
+
+        assert!(manager.soft_delete_session(&id));
+        assert!(manager.tool_calls(&id).is_empty());
+
+        manager.undo_delete().unwrap();
+        let calls = manager.tool_calls(&id);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].name, "read");
+    }
+
+    #[test]
+    fn test_undo_delete_only_once() {
+        let mut manager = SessionManager::new();
+        let id = manager.create_session(Some("session-1".to_string()));
+
+        assert!(manager.soft_delete_session(&id));
+        assert!(manager.undo_delete().is_some());
+        assert!(manager.undo_delete().is_none());
+    }
+
+    #[test]
+    fn test_undo_delete_after_window_does_nothing() {
+        let mut manager = SessionManager::new();
+        let id = manager.create_session(Some("session-1".to_string()));
+
+        assert!(manager.soft_delete_session(&id));
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(manager.undo_delete_within(Duration::ZERO).is_none());
+        assert!(manager.get_session(&id).is_none());
+        assert!(manager.undo_delete().is_none());
+    }
+
+    #[test]
+    fn test_soft_delete_nonexistent() {
+        let mut manager = SessionManager::new();
+        assert!(!manager.soft_delete_session("missing"));
+        assert!(manager.undo_delete().is_none());
+    }
+
+    #[test]
+    fn test_delete_session_twice_only_removes_once() {
+        let mut manager = SessionManager::new();
+        let first = manager.create_session(Some("session-1".to_string()));
+        let second = manager.create_session(Some("session-2".to_string()));
+
+        assert!(manager.delete_session(&first));
+        assert!(!manager.delete_session(&first));
+        assert_eq!(manager.sessions.len(), 1);
+        assert!(manager.sessions.contains_key(&second));
+    }
+
+    #[test]
+    fn test_delete_session_removes_exactly_one_from_history() {
+        let mut manager = SessionManager::new().with_in_memory_history();
+        let first = manager.create_session(Some("session-1".to_string()));
+        let second = manager.create_session(Some("session-2".to_string()));
+        let third = manager.create_session(Some("session-3".to_string()));
+
+        assert!(manager.delete_session(&second));
+
+        let remaining: Vec<String> = manager
+            .history_dao
+            .as_ref()
+            .unwrap()
+            .list_sessions()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(remaining.len(), 2);
+        assert!(!remaining.contains(&"session-2".to_string()));
+        assert!(manager.get_db_id(&second).is_none());
+        assert!(manager.get_db_id(&first).is_some());
+        assert!(manager.get_db_id(&third).is_some());
+    }
+
+    #[test]
+    fn test_updated_flag_clears_when_opened() {
+        let mut manager = SessionManager::new();
+        let first = manager.create_session(None);
+        let second = manager.create_session(None);
+        let updated = |manager: &SessionManager, id: &str| {
+            manager
+                .list_sessions()
+                .into_iter()
+                .find(|s| s.id == id)
+                .unwrap()
+                .updated
+        };
+
+        // The open session is never flagged
+        manager.mark_updated(&second);
+        assert!(!updated(&manager, &second));
+
+        manager.mark_updated(&first);
+        manager.mark_updated("missing");
+        assert!(updated(&manager, &first));
+
+        manager.switch_session(&first);
+        assert!(!updated(&manager, &first));
+    }
+
+    #[test]
+    fn test_adding_a_message_bumps_updated_at() {
+        let mut manager = SessionManager::new().with_in_memory_history();
+        let id = manager.create_session(None);
+        manager.get_session(&id).unwrap().updated_at = std::time::UNIX_EPOCH;
+
+        manager
+            .add_message_to_current_session(&crate::session::types::Message::user("hi"))
+            .unwrap();
+        let session = manager.get_session(&id).unwrap();
+        assert!(session.updated_at > std::time::UNIX_EPOCH);
+        // The open session isn't flagged as changed in the background
+        assert!(!manager.list_sessions()[0].updated);
+    }
+
+    #[test]
+    fn test_branch_copies_history_up_to_message() {
+        use crate::session::types::{Message, ReasoningEffort};
+
+        let mut manager = SessionManager::new().with_in_memory_history();
+        let id = manager.create_session(None);
+        for message in [
+            Message::user("first"),
+            Message::assistant("reply one"),
+            Message::user("second"),
+            Message::assistant("reply two"),
+        ] {
+            manager.add_message_to_current_session(&message).unwrap();
+        }
+        let sampling = SamplingParams {
+            effort: Some(ReasoningEffort::High),
+            ..SamplingParams::default()
+        };
+        manager.set_sampling(&id, sampling.clone()).unwrap();
+
+        let branch = manager.branch(&id, 1).unwrap();
+        assert_eq!(manager.get_current_session_id(), Some(&id));
+        assert_eq!(manager.message_count(&branch), 2);
+        assert_eq!(manager.message_count(&id), 4);
+        let contents: Vec<String> = manager
+            .load_messages_page(&branch, 0, 10)
+            .into_iter()
+            .map(|m| m.content)
+            .collect();
+        assert_eq!(contents, ["first", "reply one"]);
+        let session = manager.get_session(&branch).unwrap();
+        assert_eq!(session.title, "session-1 (branch)");
+        assert_eq!(session.sampling, sampling);
+
+        assert!(matches!(
+            manager.branch(&id, 4),
+            Err(SessionError::MessageOutOfRange(4))
+        ));
+        assert!(matches!(
+            manager.branch("missing", 0),
+            Err(SessionError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_flush() {
+        assert!(SessionManager::new().flush().is_ok());
+
+        let mut manager = SessionManager::new().with_in_memory_history();
+        let id = manager.create_session(None);
+        manager
+            .add_message_to_current_session(&crate::session::types::Message::user("hello"))
+            .unwrap();
+        assert!(manager.flush().is_ok());
+        assert_eq!(manager.message_count(&id), 1);
+    }
+
+    /// What the next run sees: a new manager loading the same history.
+    fn reopen(mut manager: SessionManager) -> SessionManager {
+        let dao = manager.history_dao.take().unwrap();
+        SessionManager::new().with_history_dao(dao).unwrap()
+    }
+
+    fn id_of(manager: &SessionManager, title: &str) -> String {
+        manager
+            .list_sessions()
+            .into_iter()
+            .find(|info| info.title == title)
+            .map(|info| info.id)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_history_round_trips_sessions_and_messages() {
+        use crate::session::types::{Message, MessageRole};
+
+        let mut manager = SessionManager::new().with_in_memory_history();
+        manager.create_session(Some("first".to_string()));
+        let mut reply = Message::assistant("hi there");
+        reply.reasoning = Some("greet back".to_string());
+        reply.agent_mode = Some("Build".to_string());
+        reply.model = Some("gpt-a".to_string());
+        reply.provider = Some("openai".to_string());
+        reply.output_tokens = Some(3);
+        for message in [Message::user("hello"), reply] {
+            manager.add_message_to_current_session(&message).unwrap();
+        }
+        manager.create_session(Some("second".to_string()));
+        manager
+            .add_message_to_current_session(&Message::user("bye"))
+            .unwrap();
+
+        let mut manager = reopen(manager);
+        let mut titles: Vec<String> = manager
+            .list_sessions()
+            .into_iter()
+            .map(|s| s.title)
+            .collect();
+        titles.sort();
+        assert_eq!(titles, ["first", "second"]);
+        assert!(manager.get_current_session_id().is_none());
+
+        let first = id_of(&manager, "first");
+        let messages = &manager.get_session(&first).unwrap().messages;
+        let turns: Vec<(MessageRole, &str)> = messages
+            .iter()
+            .map(|m| (m.role.clone(), m.content.as_str()))
+            .collect();
+        assert_eq!(
+            turns,
+            [
+                (MessageRole::User, "hello"),
+                (MessageRole::Assistant, "hi there")
+            ]
+        );
+        let reply = &messages[1];
+        assert_eq!(reply.reasoning.as_deref(), Some("greet back"));
+        assert_eq!(reply.agent_mode.as_deref(), Some("Build"));
+        assert_eq!(reply.model.as_deref(), Some("gpt-a"));
+        assert_eq!(reply.provider.as_deref(), Some("openai"));
+        assert_eq!(reply.output_tokens, Some(3));
+
+        // Messages added after reopening land in the same stored session
+        assert!(manager.switch_session(&first));
+        manager
+            .add_message_to_current_session(&Message::user("again"))
+            .unwrap();
+        let mut manager = reopen(manager);
+        let first = id_of(&manager, "first");
+        assert_eq!(manager.message_count(&first), 3);
+        let second = id_of(&manager, "second");
+        assert_eq!(manager.get_session(&second).unwrap().messages.len(), 1);
+    }
+
+    #[test]
+    fn test_history_round_trips_renames_and_deletes() {
+        use crate::session::types::Message;
+
+        let mut manager = SessionManager::new().with_in_memory_history();
+        let keep = manager.create_session(Some("keep".to_string()));
+        for text in ["one", "two"] {
+            manager
+                .add_message_to_current_session(&Message::user(text))
+                .unwrap();
+        }
+        manager.rename_session(&keep, "kept".to_string()).unwrap();
+        manager.toggle_pin(&keep).unwrap();
+        manager.add_tag(&keep, "work").unwrap();
+        manager.toggle_bookmark(&keep, 1).unwrap();
+        let gone = manager.create_session(Some("gone".to_string()));
+        assert!(manager.delete_session(&gone));
+
+        let manager = reopen(manager);
+        let sessions = manager.list_sessions();
+        assert_eq!(sessions.len(), 1);
+        let kept = &sessions[0];
+        assert_eq!(kept.title, "kept");
+        assert_eq!(kept.message_count, 2);
+        assert!(kept.pinned);
+        assert_eq!(kept.tags, ["work"]);
+        assert_eq!(manager.bookmarks(&kept.id), [1]);
+    }
+
+    #[test]
+    fn test_loaded_sessions_resolve_their_own_rows() {
+        let mut manager = SessionManager::new().with_in_memory_history();
+        let first = manager.create_session(Some("same".to_string()));
+        let second = manager.create_session(Some("same".to_string()));
+        manager.create_session(Some("other".to_string()));
+        // Same titles no longer share an id
+        assert_ne!(first, second);
+        assert_eq!(manager.list_sessions().len(), 3);
+        let first_row = manager.get_db_id(&first).unwrap();
+        let second_row = manager.get_db_id(&second).unwrap();
+
+        let mut manager = reopen(manager);
+        let row_of = |manager: &SessionManager, id: &str| manager.get_db_id(id).unwrap();
+        let same: Vec<String> = manager
+            .list_sessions()
+            .into_iter()
+            .filter(|info| info.title == "same")
+            .map(|info| info.id)
+            .collect();
+        assert_eq!(same.len(), 2);
+        let first = same
+            .iter()
+            .find(|id| row_of(&manager, id) == first_row)
+            .unwrap()
+            .clone();
+        let second = same
+            .iter()
+            .find(|id| row_of(&manager, id) == second_row)
+            .unwrap()
+            .clone();
+
+        manager
+            .rename_session(&first, "renamed".to_string())
+            .unwrap();
+        assert!(manager.delete_session(&second));
+
+        let dao = manager.history_dao.as_ref().unwrap();
+        assert_eq!(dao.get_session(first_row).unwrap().unwrap().name, "renamed");
+        assert!(dao.get_session(second_row).unwrap().is_none());
+
+        let manager = reopen(manager);
+        let mut titles: Vec<String> = manager
+            .list_sessions()
+            .into_iter()
+            .map(|info| info.title)
+            .collect();
+        titles.sort();
+        assert_eq!(titles, ["other", "renamed"]);
+    }
+
+    #[test]
+    fn test_session_info_counts_stored_messages() {
+        use crate::session::types::Message;
+
+        let mut manager = SessionManager::new().with_in_memory_history();
+        let id = manager.create_session(Some("counted".to_string()));
+        let empty = manager.create_session(Some("empty".to_string()));
+        manager.switch_session(&id);
+        for text in ["one", "two", "three"] {
+            manager
+                .add_message_to_current_session(&Message::user(text))
+                .unwrap();
+        }
+
+        // Only history has the messages, the session itself has none loaded
+        assert!(manager.get_session(&id).unwrap().messages.is_empty());
+        let info = manager.get_session_info(&id).unwrap();
+        assert_eq!(info.title, "counted");
+        assert_eq!(info.message_count, 3);
+        let listed = manager.list_sessions();
+        let count_of = |id: &str| {
+            listed
+                .iter()
+                .find(|info| info.id == id)
+                .map(|info| info.message_count)
+        };
+        assert_eq!(count_of(&id), Some(3));
+        assert_eq!(count_of(&empty), Some(0));
+        assert!(manager.get_session_info("missing").is_none());
+
+        // Loaded from history, the messages and the counts agree
+        let mut manager = reopen(manager);
+        let id = id_of(&manager, "counted");
+        assert_eq!(manager.get_session_info(&id).unwrap().message_count, 3);
+        assert_eq!(manager.get_session(&id).unwrap().messages.len(), 3);
+
+        // Without history the loaded messages are all there is
+        let (manager, id) = manager_with_messages(4);
+        assert_eq!(manager.get_session_info(&id).unwrap().message_count, 4);
+        assert_eq!(manager.list_sessions()[0].message_count, 4);
+    }
+
+    #[test]
+    fn test_messages_load_on_switch() {
+        use crate::session::types::Message;
+
+        let mut manager = SessionManager::new().with_in_memory_history();
+        manager.create_session(Some("stored".to_string()));
+        for text in ["one", "two"] {
+            manager
+                .add_message_to_current_session(&Message::user(text))
+                .unwrap();
+        }
+
+        // Startup only lists the sessions
+        let mut manager = reopen(manager);
+        let id = id_of(&manager, "stored");
+        let session = &manager.sessions[&id];
+        assert!(!session.loaded);
+        assert!(session.messages.is_empty());
+        assert_eq!(manager.list_sessions()[0].message_count, 2);
+
+        assert!(manager.switch_session(&id));
+        let session = &manager.sessions[&id];
+        assert!(session.loaded);
+        let contents: Vec<&str> = session
+            .messages
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(contents, ["one", "two"]);
+
+        // Still stored under the same row once loaded
+        manager
+            .add_message_to_current_session(&Message::user("three"))
+            .unwrap();
+        assert_eq!(manager.message_count(&id), 3);
+        let mut manager = reopen(manager);
+        let id = id_of(&manager, "stored");
+        assert_eq!(manager.get_session(&id).unwrap().messages.len(), 3);
+    }
+
+    #[test]
+    fn test_undo_delete_restores_messages_sent_this_run() {
+        use crate::session::types::Message;
+
+        let mut manager = SessionManager::new().with_in_memory_history();
+        let id = manager.create_session(Some("active".to_string()));
+        for text in ["one", "two", "three"] {
+            manager
+                .add_message_to_current_session(&Message::user(text))
+                .unwrap();
+        }
+
+        assert!(manager.soft_delete_session(&id));
+        let id = manager.undo_delete().unwrap();
+        assert_eq!(manager.message_count(&id), 3);
+        let restored = manager.load_messages_page(&id, 0, 3);
+        assert_eq!(restored[2].content, "three");
+    }
+
+    #[test]
+    fn test_undo_delete_keeps_unopened_messages() {
+        use crate::session::types::Message;
+
+        let mut manager = SessionManager::new().with_in_memory_history();
+        manager.create_session(Some("stored".to_string()));
+        manager
+            .add_message_to_current_session(&Message::user("keep me"))
+            .unwrap();
+        let mut manager = reopen(manager);
+        let id = id_of(&manager, "stored");
+
+        assert!(manager.soft_delete_session(&id));
+        let id = manager.undo_delete().unwrap();
+        assert_eq!(manager.message_count(&id), 1);
+        assert_eq!(
+            manager.get_session(&id).unwrap().messages[0].content,
+            "keep me"
+        );
     }
 }
@@ -1,2 +1,8 @@
+pub mod draft;
+pub mod loader;
 pub mod manager;
+pub mod paste;
+pub mod queue;
+pub mod stats;
 pub mod types;
+pub mod usage;
@@ -0,0 +1,122 @@
+/// Pastes with at least this many lines become attachments by default.
+pub const DEFAULT_ATTACH_LINES: usize = 200;
+
+/// A large paste kept out of the input. It's sent with the next message
+/// but only shown as a one-line label until then.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PastedText {
+    pub content: String,
+    lines: usize,
+}
+
+impl PastedText {
+    pub fn new(content: String) -> Self {
+        let lines = content.lines().count();
+        Self { content, lines }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.lines
+    }
+
+    pub fn label(&self) -> String {
+        if self.lines == 1 {
+            "pasted content (1 line)".to_string()
+        } else {
+            format!("pasted content ({} lines)", self.lines)
+        }
+    }
+
+    /// The first non-blank line, to tell attachments apart.
+    pub fn preview(&self, max_chars: usize) -> String {
+        let first = self
+            .content
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("");
+        crate::utils::truncate_chars(first.trim(), max_chars)
+    }
+}
+
+/// Turns `\r\n` and lone `\r` into `\n`. Many terminals send pasted line
+/// breaks as `\r`, which the input would otherwise keep inside one line.
+pub fn normalize_newlines(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Whether a paste is big enough to attach instead of inserting it. A
+/// threshold of 0 turns attaching off.
+pub fn should_attach(text: &str, threshold: usize) -> bool {
+    threshold > 0 && text.lines().count() >= threshold
+}
+
+/// The message as sent: what was typed, then each attachment in a fenced
+/// block.
+pub fn with_attachments(msg: &str, attachments: &[PastedText]) -> String {
+    let mut out = msg.to_string();
+    for attachment in attachments {
+        if !out.is_empty() {
+            out.push_str("\n\n");
+        }
+        let fence = if attachment.content.contains("```") {
+            "````"
+        } else {
+            "```"
+        };
+        out.push_str(&format!(
+            "Pasted content ({} lines):\n{}\n{}\n{}",
+            attachment.line_count(),
+            fence,
+            attachment.content.trim_end_matches('\n'),
+            fence
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_newlines() {
+        assert_eq!(normalize_newlines("a\rb\r\nc\nd"), "a\nb\nc\nd");
+        assert_eq!(normalize_newlines("\r\r"), "\n\n");
+        assert_eq!(normalize_newlines("plain"), "plain");
+    }
+
+    #[test]
+    fn test_should_attach() {
+        let log = "line\n".repeat(5);
+        assert!(should_attach(&log, 5));
+        assert!(!should_attach(&log, 6));
+        assert!(!should_attach(&log, 0));
+    }
+
+    #[test]
+    fn test_label_and_preview() {
+        let paste = PastedText::new("\n  error: boom  \nat main.rs:3\n".to_string());
+        assert_eq!(paste.line_count(), 3);
+        assert_eq!(paste.label(), "pasted content (3 lines)");
+        assert_eq!(paste.preview(40), "error: boom");
+        assert_eq!(paste.preview(6), "error…");
+    }
+
+    #[test]
+    fn test_with_attachments() {
+        let paste = PastedText::new("a\nb\n".to_string());
+        assert_eq!(with_attachments("why?", &[]), "why?");
+        assert_eq!(
+            with_attachments("why?", std::slice::from_ref(&paste)),
+            "why?\n\nPasted content (2 lines):\n```\na\nb\n```"
+        );
+        // Nothing typed, just the paste
+        assert_eq!(
+            with_attachments("", &[paste]),
+            "Pasted content (2 lines):\n```\na\nb\n```"
+        );
+
+        let fenced = PastedText::new("```rust\nfn main() {}\n```".to_string());
+        assert!(with_attachments("", &[fenced]).starts_with("Pasted content (3 lines):\n````\n"));
+    }
+}
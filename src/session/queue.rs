@@ -0,0 +1,93 @@
+use std::collections::VecDeque;
+
+pub const MAX_QUEUED_MESSAGES: usize = 5;
+
+/// Messages submitted while a response is streaming. They're sent one at a
+/// time, oldest first, each after the previous turn ends successfully.
+#[derive(Debug, Default)]
+pub struct MessageQueue {
+    messages: VecDeque<String>,
+}
+
+impl MessageQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues `msg`, handing it back when the queue is already full.
+    pub fn push(&mut self, msg: String) -> Result<(), String> {
+        if self.messages.len() >= MAX_QUEUED_MESSAGES {
+            return Err(msg);
+        }
+        self.messages.push_back(msg);
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<String> {
+        self.messages.pop_front()
+    }
+
+    /// Drops every queued message and returns how many there were.
+    pub fn clear(&mut self) -> usize {
+        let count = self.messages.len();
+        self.messages.clear();
+        count
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+pub fn queued_label(count: usize) -> String {
+    if count == 1 {
+        "1 queued message".to_string()
+    } else {
+        format!("{} queued messages", count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages_are_sent_in_order() {
+        let mut queue = MessageQueue::new();
+        queue.push("first".to_string()).unwrap();
+        queue.push("second".to_string()).unwrap();
+
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop(), Some("first".to_string()));
+        assert_eq!(queue.pop(), Some("second".to_string()));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn test_full_queue_hands_message_back() {
+        let mut queue = MessageQueue::new();
+        for i in 0..MAX_QUEUED_MESSAGES {
+            queue.push(i.to_string()).unwrap();
+        }
+
+        assert_eq!(queue.push("extra".to_string()), Err("extra".to_string()));
+        assert_eq!(queue.len(), MAX_QUEUED_MESSAGES);
+    }
+
+    #[test]
+    fn test_clear_reports_dropped_count() {
+        let mut queue = MessageQueue::new();
+        assert_eq!(queue.clear(), 0);
+
+        queue.push("a".to_string()).unwrap();
+        queue.push("b".to_string()).unwrap();
+        assert_eq!(queue.clear(), 2);
+        assert!(queue.is_empty());
+        assert_eq!(queued_label(1), "1 queued message");
+        assert_eq!(queued_label(2), "2 queued messages");
+    }
+}
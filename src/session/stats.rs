@@ -0,0 +1,266 @@
+use crate::llm::limits::estimate_tokens;
+use crate::model::discovery::Cost;
+use crate::session::types::{Message, MessageRole};
+
+/// Usage of a session, added up from the timing and token counts stored on
+/// its messages.
+#[derive(Debug, Default, PartialEq)]
+pub struct SessionStats {
+    pub user_messages: usize,
+    pub assistant_messages: usize,
+    pub tool_messages: usize,
+    pub system_messages: usize,
+    /// Text of every message, estimated at ~4 characters per token
+    pub estimated_tokens: usize,
+    /// Output tokens counted while replies streamed
+    pub streamed_tokens: usize,
+    /// Replies with timing, one per finished turn
+    pub timed_replies: usize,
+    pub wall_time_ms: u64,
+    /// Time between the first and last token, for the average speed
+    pub decode_ms: u64,
+    /// Time to first token of each timed reply, fastest first
+    pub ttft_ms: Vec<u64>,
+    /// Estimated cost in USD of the replies whose model has a price
+    pub cost: f64,
+    pub priced_replies: usize,
+}
+
+impl SessionStats {
+    /// `price_of` gives the models.dev price of the model a reply came
+    /// from. Each reply is charged for the history before it as input, since
+    /// that's what gets sent.
+    pub fn from_messages(
+        messages: &[Message],
+        price_of: impl Fn(&Message) -> Option<Cost>,
+    ) -> Self {
+        let mut stats = SessionStats::default();
+
+        for message in messages {
+            let tokens = estimate_tokens(&message.content)
+                + message.reasoning.as_deref().map_or(0, estimate_tokens);
+
+            match message.role {
+                MessageRole::User => stats.user_messages += 1,
+                MessageRole::Tool => stats.tool_messages += 1,
+                MessageRole::System => stats.system_messages += 1,
+                MessageRole::Assistant => {
+                    stats.assistant_messages += 1;
+                    let output = message.output_tokens.or(message.token_count);
+                    stats.streamed_tokens += output.unwrap_or(0);
+                    stats.add_timing(message, output.unwrap_or(0));
+                    if let Some(price) = price_of(message) {
+                        let output = output.unwrap_or(tokens);
+                        stats.cost += (stats.estimated_tokens as f64 * price.input
+                            + output as f64 * price.output)
+                            / 1_000_000.0;
+                        stats.priced_replies += 1;
+                    }
+                }
+            }
+            stats.estimated_tokens += tokens;
+        }
+
+        stats.ttft_ms.sort_unstable();
+        stats
+    }
+
+    fn add_timing(&mut self, message: &Message, output_tokens: usize) {
+        match (message.t0_ms, message.t1_ms, message.tn_ms) {
+            (Some(t0), Some(t1), Some(tn)) => {
+                self.timed_replies += 1;
+                self.wall_time_ms += tn.saturating_sub(t0);
+                self.ttft_ms.push(t1.saturating_sub(t0));
+                if output_tokens > 0 {
+                    self.decode_ms += tn.saturating_sub(t1);
+                }
+            }
+            // Stored before the timestamps were: decode time only
+            _ => {
+                if let Some(duration_ms) = message.duration_ms.filter(|&ms| ms > 0) {
+                    self.timed_replies += 1;
+                    self.wall_time_ms += duration_ms;
+                    if output_tokens > 0 {
+                        self.decode_ms += duration_ms;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Average output speed over the replies that streamed any tokens.
+    pub fn tokens_per_sec(&self) -> Option<f64> {
+        if self.decode_ms == 0 || self.streamed_tokens == 0 {
+            return None;
+        }
+        Some(self.streamed_tokens as f64 / (self.decode_ms as f64 / 1000.0))
+    }
+
+    /// Nearest-rank percentile of the time to first token.
+    pub fn ttft_percentile(&self, percentile: usize) -> Option<u64> {
+        let rank = (self.ttft_ms.len() * percentile).div_ceil(100).max(1);
+        self.ttft_ms.get(rank - 1).copied()
+    }
+}
+
+fn format_secs(ms: u64) -> String {
+    format!("{:.1}s", ms as f64 / 1000.0)
+}
+
+fn format_wall_time(ms: u64) -> String {
+    let secs = ms / 1000;
+    match secs {
+        0..=59 => format_secs(ms),
+        60..=3599 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+/// The stats as a two-column table, in a code block so it stays aligned.
+pub fn format_stats(title: &str, stats: &SessionStats) -> String {
+    let mut rows: Vec<(&str, String)> = Vec::new();
+
+    let total = stats.user_messages
+        + stats.assistant_messages
+        + stats.tool_messages
+        + stats.system_messages;
+    let mut by_role = vec![
+        format!("{} you", stats.user_messages),
+        format!("{} assistant", stats.assistant_messages),
+    ];
+    if stats.tool_messages > 0 {
+        by_role.push(format!("{} tool", stats.tool_messages));
+    }
+    if stats.system_messages > 0 {
+        by_role.push(format!("{} system", stats.system_messages));
+    }
+    rows.push(("Messages", format!("{} ({})", total, by_role.join(" · "))));
+
+    rows.push((
+        "Tokens",
+        format!("~{} in all messages", stats.estimated_tokens),
+    ));
+    if stats.streamed_tokens > 0 {
+        rows.push((
+            "Output",
+            format!("{} tokens streamed", stats.streamed_tokens),
+        ));
+    }
+
+    let cost = if stats.priced_replies == 0 {
+        "unknown (no prices for these models)".to_string()
+    } else if stats.priced_replies < stats.assistant_messages {
+        format!(
+            "~${:.4} ({} of {} replies priced)",
+            stats.cost, stats.priced_replies, stats.assistant_messages
+        )
+    } else {
+        format!("~${:.4}", stats.cost)
+    };
+    rows.push(("Cost", cost));
+
+    if stats.timed_replies > 0 {
+        rows.push((
+            "Wall time",
+            format!(
+                "{} over {} replies",
+                format_wall_time(stats.wall_time_ms),
+                stats.timed_replies
+            ),
+        ));
+    }
+    if let Some(tps) = stats.tokens_per_sec() {
+        rows.push(("Speed", format!("{:.0} t/s average", tps)));
+    }
+    if let (Some(min), Some(max)) = (stats.ttft_ms.first(), stats.ttft_ms.last()) {
+        let p50 = stats.ttft_percentile(50).unwrap_or(*min);
+        let p90 = stats.ttft_percentile(90).unwrap_or(*max);
+        rows.push((
+            "TTFT",
+            format!(
+                "min {} · p50 {} · p90 {} · max {}",
+                format_secs(*min),
+                format_secs(p50),
+                format_secs(p90),
+                format_secs(*max)
+            ),
+        ));
+    }
+
+    let width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    let mut out = format!("Stats for {}\n\n```", title);
+    for (label, value) in rows {
+        out.push_str(&format!("\n{:width$}  {}", label, value, width = width));
+    }
+    out.push_str("\n```");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply(content: &str, t0: u64, t1: u64, tn: u64, tokens: usize) -> Message {
+        let mut message = Message::assistant(content);
+        message.t0_ms = Some(t0);
+        message.t1_ms = Some(t1);
+        message.tn_ms = Some(tn);
+        message.output_tokens = Some(tokens);
+        message.model = Some("priced".to_string());
+        message
+    }
+
+    #[test]
+    fn test_session_stats() {
+        let messages = vec![
+            Message::user("12345678"),
+            reply("first reply", 0, 500, 2_500, 100),
+            Message::new(MessageRole::Tool, "tool"),
+            Message::user("again"),
+            reply("second reply", 10_000, 12_000, 14_000, 60),
+            Message::assistant("from an older version"),
+        ];
+        let price = |message: &Message| {
+            (message.model.as_deref() == Some("priced")).then_some(Cost {
+                input: 1_000_000.0,
+                output: 2_000_000.0,
+                cache_read: None,
+                cache_write: None,
+            })
+        };
+
+        let stats = SessionStats::from_messages(&messages, price);
+        assert_eq!(stats.user_messages, 2);
+        assert_eq!(stats.assistant_messages, 3);
+        assert_eq!(stats.tool_messages, 1);
+        assert_eq!(stats.streamed_tokens, 160);
+        assert_eq!(stats.timed_replies, 2);
+        assert_eq!(stats.wall_time_ms, 6_500);
+        assert_eq!(stats.ttft_ms, vec![500, 2_000]);
+        assert_eq!(stats.tokens_per_sec(), Some(40.0));
+        assert_eq!(stats.ttft_percentile(50), Some(500));
+        assert_eq!(stats.ttft_percentile(90), Some(2_000));
+        // Input is the history before each reply: 2 tokens, then 2+3+1+2
+        assert_eq!(stats.priced_replies, 2);
+        assert_eq!(stats.cost, (2.0 + 100.0 * 2.0) + (8.0 + 60.0 * 2.0));
+
+        let table = format_stats("session-1", &stats);
+        assert!(table.starts_with(
+            "Stats for session-1\n\n```\nMessages   6 (2 you · 3 assistant · 1 tool)"
+        ));
+        assert!(table.contains("\nCost       ~$330.0000 (2 of 3 replies priced)"));
+        assert!(table.contains("\nWall time  6.5s over 2 replies"));
+        assert!(table.contains("\nTTFT       min 0.5s · p50 0.5s · p90 2.0s · max 2.0s\n```"));
+    }
+
+    #[test]
+    fn test_empty_session_stats() {
+        let stats = SessionStats::from_messages(&[], |_| None);
+        assert_eq!(stats.tokens_per_sec(), None);
+        assert_eq!(stats.ttft_percentile(50), None);
+
+        let table = format_stats("empty", &stats);
+        assert!(table.contains("Cost      unknown (no prices for these models)"));
+        assert!(!table.contains("TTFT"));
+    }
+}
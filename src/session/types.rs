@@ -1,6 +1,6 @@
 use std::time::SystemTime;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum MessageRole {
     User,
     Assistant,
@@ -100,6 +100,67 @@ impl Message {
     }
 }
 
+/// Sampling overrides for a session's requests. `None` keeps the provider's
+/// default.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SamplingParams {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u32>,
+    /// Text that ends the reply when generated
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+    /// How hard reasoning models think before answering
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effort: Option<ReasoningEffort>,
+}
+
+impl SamplingParams {
+    pub const MAX_TEMPERATURE: f32 = 2.0;
+    pub const MAX_TOP_P: f32 = 1.0;
+    /// OpenAI rejects requests with more
+    pub const MAX_STOP_SEQUENCES: usize = 4;
+
+    pub fn is_default(&self) -> bool {
+        self.temperature.is_none()
+            && self.top_p.is_none()
+            && self.seed.is_none()
+            && self.stop.is_empty()
+            && self.effort.is_none()
+    }
+}
+
+/// Reasoning budget for models that think before answering; more effort
+/// costs tokens and latency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningEffort {
+    Low,
+    Medium,
+    High,
+}
+
+impl ReasoningEffort {
+    pub const ALL: [ReasoningEffort; 3] = [Self::Low, Self::Medium, Self::High];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|effort| effort.label().eq_ignore_ascii_case(value))
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Session {
     pub id: String,
@@ -107,6 +168,14 @@ pub struct Session {
     pub created_at: SystemTime,
     pub updated_at: SystemTime,
     pub messages: Vec<Message>,
+    pub pinned: bool,
+    pub tags: Vec<String>,
+    pub sampling: SamplingParams,
+    /// Indices of the bookmarked messages, in order
+    pub bookmarks: Vec<usize>,
+    /// False until `messages` is read from history; sessions start out
+    /// with only their metadata
+    pub loaded: bool,
 }
 
 impl Default for Session {
@@ -124,6 +193,11 @@ impl Session {
             created_at: now,
             updated_at: now,
             messages: Vec::new(),
+            pinned: false,
+            tags: Vec::new(),
+            sampling: SamplingParams::default(),
+            bookmarks: Vec::new(),
+            loaded: true,
         }
     }
 
@@ -135,6 +209,11 @@ impl Session {
             created_at: now,
             updated_at: now,
             messages: Vec::new(),
+            pinned: false,
+            tags: Vec::new(),
+            sampling: SamplingParams::default(),
+            bookmarks: Vec::new(),
+            loaded: true,
         }
     }
 
@@ -196,6 +275,42 @@ impl Session {
     pub fn clear(&mut self) {
         self.messages.clear();
     }
+
+    /// Adds a tag, ignoring case-insensitive duplicates. Returns `false` if it
+    /// was already present.
+    pub fn add_tag(&mut self, tag: impl Into<String>) -> bool {
+        let tag = tag.into();
+        if self.has_tag(&tag) {
+            return false;
+        }
+        self.tags.push(tag);
+        true
+    }
+
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let before = self.tags.len();
+        self.tags.retain(|t| !t.eq_ignore_ascii_case(tag));
+        self.tags.len() != before
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Bookmarks the message at `index`, or removes its bookmark. Returns
+    /// whether it's bookmarked now.
+    pub fn toggle_bookmark(&mut self, index: usize) -> bool {
+        match self.bookmarks.binary_search(&index) {
+            Ok(pos) => {
+                self.bookmarks.remove(pos);
+                false
+            }
+            Err(pos) => {
+                self.bookmarks.insert(pos, index);
+                true
+            }
+        }
+    }
 }
 
 #[cfg(test)]
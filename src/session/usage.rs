@@ -0,0 +1,278 @@
+use std::time::SystemTime;
+
+use chrono::NaiveDate;
+
+use crate::model::discovery::Cost;
+use crate::persistence::ModelUsage;
+
+/// Days covered by the sparkline of `/usage`
+pub const USAGE_DAYS: u32 = 14;
+
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Usage of one model with its estimated cost, `None` without a price.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelSpend {
+    pub usage: ModelUsage,
+    pub cost: Option<f64>,
+}
+
+impl ModelSpend {
+    /// Tokens sent with the replies, estimated at ~4 characters per token.
+    pub fn input_tokens(&self) -> i64 {
+        self.usage.input_chars / 4
+    }
+
+    pub fn label(&self) -> String {
+        match (&self.usage.provider, &self.usage.model) {
+            (Some(provider), Some(model)) => format!("{}/{}", provider, model),
+            (None, Some(model)) => model.clone(),
+            _ => "unknown".to_string(),
+        }
+    }
+}
+
+/// Usage across every stored session, as shown by `/usage`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageReport {
+    pub sessions: usize,
+    /// Most used first
+    pub models: Vec<ModelSpend>,
+    /// Output tokens of each of the last `USAGE_DAYS` days, oldest first
+    pub daily_tokens: Vec<(NaiveDate, i64)>,
+    pub computed_at: SystemTime,
+}
+
+impl UsageReport {
+    /// `daily` holds the days that had replies, as `YYYY-MM-DD`; the others
+    /// up to `today` are filled in with zero.
+    pub fn build(
+        sessions: usize,
+        models: Vec<ModelUsage>,
+        daily: &[(String, i64)],
+        today: NaiveDate,
+        price_of: impl Fn(&str, &str) -> Option<Cost>,
+    ) -> Self {
+        let models = models
+            .into_iter()
+            .map(|usage| {
+                let cost = match (&usage.provider, &usage.model) {
+                    (Some(provider), Some(model)) => price_of(provider, model).map(|price| {
+                        ((usage.input_chars / 4) as f64 * price.input
+                            + usage.output_tokens as f64 * price.output)
+                            / 1_000_000.0
+                    }),
+                    _ => None,
+                };
+                ModelSpend { usage, cost }
+            })
+            .collect();
+
+        let daily_tokens = (0..USAGE_DAYS as i64)
+            .rev()
+            .map(|ago| {
+                let day = today - chrono::Duration::days(ago);
+                let key = day.format("%Y-%m-%d").to_string();
+                let tokens = daily
+                    .iter()
+                    .find(|(date, _)| *date == key)
+                    .map_or(0, |(_, tokens)| *tokens);
+                (day, tokens)
+            })
+            .collect();
+
+        Self {
+            sessions,
+            models,
+            daily_tokens,
+            computed_at: SystemTime::now(),
+        }
+    }
+
+    pub fn total_cost(&self) -> f64 {
+        self.models.iter().filter_map(|m| m.cost).sum()
+    }
+}
+
+/// One block per value, scaled to the largest; zero stays at the bottom.
+pub fn sparkline(values: &[i64]) -> String {
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    values
+        .iter()
+        .map(|&value| {
+            let level = (value.max(0) * (SPARK_LEVELS.len() as i64 - 1) + max - 1) / max;
+            SPARK_LEVELS[level as usize]
+        })
+        .collect()
+}
+
+fn format_tokens(tokens: i64) -> String {
+    match tokens {
+        ..=999 => tokens.to_string(),
+        1_000..=999_999 => format!("{:.1}k", tokens as f64 / 1_000.0),
+        _ => format!("{:.1}M", tokens as f64 / 1_000_000.0),
+    }
+}
+
+/// The report as text: totals, a table of models and the daily sparkline,
+/// in code blocks so the columns line up.
+pub fn format_usage(report: &UsageReport) -> String {
+    let mut out = format!(
+        "Usage across {} sessions, as of {} (/usage refresh to update)",
+        report.sessions,
+        crate::utils::relative_time(report.computed_at)
+    );
+    if report.models.is_empty() {
+        out.push_str("\n\nNo replies yet.");
+        return out;
+    }
+
+    let priced = report.models.iter().filter(|m| m.cost.is_some()).count();
+    let spend = if priced == 0 {
+        "unknown (no prices for these models)".to_string()
+    } else if priced < report.models.len() {
+        format!(
+            "~${:.4} ({} of {} models priced)",
+            report.total_cost(),
+            priced,
+            report.models.len()
+        )
+    } else {
+        format!("~${:.4}", report.total_cost())
+    };
+    let input: i64 = report.models.iter().map(|m| m.input_tokens()).sum();
+    let output: i64 = report.models.iter().map(|m| m.usage.output_tokens).sum();
+    out.push_str(&format!(
+        "\n\n```\nSpend   {}\nTokens  ~{} in · ~{} out\n```",
+        spend,
+        format_tokens(input),
+        format_tokens(output)
+    ));
+
+    let rows: Vec<[String; 5]> = report
+        .models
+        .iter()
+        .map(|m| {
+            [
+                m.label(),
+                m.usage.replies.to_string(),
+                format_tokens(m.input_tokens()),
+                format_tokens(m.usage.output_tokens),
+                m.cost
+                    .map_or("-".to_string(), |cost| format!("${:.4}", cost)),
+            ]
+        })
+        .collect();
+    let header = ["Model", "Replies", "In", "Out", "Cost"].map(String::from);
+    let widths: Vec<usize> = (0..header.len())
+        .map(|col| {
+            std::iter::once(&header)
+                .chain(&rows)
+                .map(|row| row[col].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    out.push_str("\n\n```");
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+        out.push_str(&format!("\n{}", cells.join("  ").trim_end()));
+    }
+    out.push_str("\n```");
+
+    let values: Vec<i64> = report.daily_tokens.iter().map(|(_, t)| *t).collect();
+    if let Some((peak_day, peak)) = report
+        .daily_tokens
+        .iter()
+        .filter(|(_, tokens)| *tokens > 0)
+        .max_by_key(|(_, tokens)| *tokens)
+    {
+        out.push_str(&format!(
+            "\n\n```\nLast {} days  {}  output tokens, peak {} on {}\n```",
+            USAGE_DAYS,
+            sparkline(&values),
+            format_tokens(*peak),
+            peak_day.format("%b %-d")
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(
+        provider: &str,
+        model: &str,
+        replies: usize,
+        output: i64,
+        input_chars: i64,
+    ) -> ModelUsage {
+        ModelUsage {
+            provider: Some(provider.to_string()),
+            model: Some(model.to_string()),
+            replies,
+            output_tokens: output,
+            input_chars,
+        }
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[0, 1, 4, 8]), "▁▂▅█");
+        assert_eq!(sparkline(&[0, 0]), "▁▁");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn test_usage_report() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 14).unwrap();
+        let daily = vec![
+            ("2026-03-01".to_string(), 500),
+            ("2026-03-13".to_string(), 1_500),
+            ("2026-03-14".to_string(), 250),
+        ];
+        let report = UsageReport::build(
+            3,
+            vec![
+                usage("anthropic", "claude", 12, 2_000, 40_000),
+                usage("local", "llama", 2, 300, 800),
+            ],
+            &daily,
+            today,
+            |provider, _| {
+                (provider == "anthropic").then_some(Cost {
+                    input: 3.0,
+                    output: 15.0,
+                    cache_read: None,
+                    cache_write: None,
+                })
+            },
+        );
+
+        assert_eq!(
+            report.models[0].cost,
+            Some((10_000.0 * 3.0 + 2_000.0 * 15.0) / 1e6)
+        );
+        assert_eq!(report.models[1].cost, None);
+        assert_eq!(report.daily_tokens.len(), USAGE_DAYS as usize);
+        assert_eq!(
+            report.daily_tokens[0],
+            (NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(), 500)
+        );
+        assert_eq!(report.daily_tokens[13].1, 250);
+
+        let text = format_usage(&report);
+        assert!(text.contains("Spend   ~$0.0600 (1 of 2 models priced)"));
+        assert!(text.contains("Tokens  ~10.2k in · ~2.3k out"));
+        assert!(text.contains(
+            "Model             Replies  In     Out   Cost\nanthropic/claude  12       10.0k  2.0k  $0.0600\nlocal/llama       2        200    300   -"
+        ));
+        assert!(text.contains("Last 14 days  ▄▁▁▁▁▁▁▁▁▁▁▁█▃  output tokens, peak 1.5k on Mar 13"));
+    }
+}
@@ -15,7 +15,7 @@ pub struct StreamClient {
 impl StreamClient {
     pub fn new() -> Self {
         Self {
-            client: Client::new(),
+            client: crate::utils::http::client().clone(),
             parser: StreamParser::new(),
         }
     }
@@ -2,21 +2,35 @@ use serde::Deserialize;
 use std::fs;
 use std::path::Path;
 
+/// Built-in theme that fills in colors missing from user themes.
+const BASE_THEME_JSON: &str = include_str!("theme.json");
+const HIGH_CONTRAST_THEME_JSON: &str = include_str!("theme_high_contrast.json");
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Theme {
+    #[serde(default)]
     pub name: String,
+    #[serde(default)]
     pub id: String,
+    #[serde(default)]
     pub light: ThemeMode,
+    #[serde(default)]
     pub dark: ThemeMode,
+    /// Keys missing from the file that were filled from the base theme,
+    /// e.g. `dark.seeds.primary`
+    #[serde(skip)]
+    pub defaulted_keys: Vec<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
 pub struct ThemeMode {
     pub seeds: ThemeSeeds,
     pub overrides: ThemeOverrides,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
 pub struct ThemeSeeds {
     pub neutral: String,
     pub primary: String,
@@ -27,7 +41,8 @@ pub struct ThemeSeeds {
     pub interactive: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
 pub struct ThemeOverrides {
     #[serde(rename = "background-base")]
     pub background_base: String,
@@ -57,7 +72,7 @@ pub struct ThemeOverrides {
     pub syntax_string: String,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ThemeColors {
     pub primary: ratatui::style::Color,
     pub background: ratatui::style::Color,
@@ -74,6 +89,125 @@ pub struct ThemeColors {
     pub info: ratatui::style::Color,
 }
 
+impl ThemeColors {
+    /// Neutral colors that leave everything to the terminal's defaults.
+    pub fn monochrome() -> Self {
+        let reset = ratatui::style::Color::Reset;
+        Self {
+            primary: reset,
+            background: reset,
+            text: reset,
+            text_weak: reset,
+            text_strong: reset,
+            border: reset,
+            border_weak_focus: reset,
+            border_focus: reset,
+            border_strong_focus: reset,
+            success: reset,
+            warning: reset,
+            error: reset,
+            info: reset,
+        }
+    }
+
+    /// Terminal defaults with a distinct color for the accent and each
+    /// status, so tests can tell those styles apart.
+    #[cfg(test)]
+    pub fn for_tests() -> Self {
+        use ratatui::style::Color;
+        Self {
+            primary: Color::Rgb(255, 140, 0),
+            success: Color::Green,
+            warning: Color::Yellow,
+            error: Color::Red,
+            info: Color::Cyan,
+            ..Self::monochrome()
+        }
+    }
+
+    /// Maps RGB colors to the nearest xterm 256-color index for terminals
+    /// without truecolor support.
+    pub fn degraded(self) -> Self {
+        Self {
+            primary: degrade_color(self.primary),
+            background: degrade_color(self.background),
+            text: degrade_color(self.text),
+            text_weak: degrade_color(self.text_weak),
+            text_strong: degrade_color(self.text_strong),
+            border: degrade_color(self.border),
+            border_weak_focus: degrade_color(self.border_weak_focus),
+            border_focus: degrade_color(self.border_focus),
+            border_strong_focus: degrade_color(self.border_strong_focus),
+            success: degrade_color(self.success),
+            warning: degrade_color(self.warning),
+            error: degrade_color(self.error),
+            info: degrade_color(self.info),
+        }
+    }
+}
+
+/// Any non-empty `NO_COLOR` disables colors (https://no-color.org).
+pub fn no_color_requested() -> bool {
+    std::env::var("NO_COLOR").is_ok_and(|v| !v.is_empty())
+}
+
+/// Truecolor terminals advertise themselves via `COLORTERM`.
+pub fn supports_truecolor() -> bool {
+    colorterm_is_truecolor(std::env::var("COLORTERM").ok().as_deref())
+}
+
+fn colorterm_is_truecolor(colorterm: Option<&str>) -> bool {
+    matches!(
+        colorterm.map(|v| v.trim().to_ascii_lowercase()).as_deref(),
+        Some("truecolor") | Some("24bit")
+    )
+}
+
+pub fn degrade_color(color: ratatui::style::Color) -> ratatui::style::Color {
+    match color {
+        ratatui::style::Color::Rgb(r, g, b) => {
+            ratatui::style::Color::Indexed(rgb_to_ansi256(r, g, b))
+        }
+        _ => color,
+    }
+}
+
+/// Nearest entry in the xterm 256-color palette, choosing between the
+/// 6x6x6 color cube (16-231) and the grayscale ramp (232-255).
+pub fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+    let nearest_level = |c: u8| {
+        CUBE_LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, &level)| (level as i32 - c as i32).abs())
+            .map(|(i, _)| i)
+            .unwrap_or(0)
+    };
+    let distance = |(r2, g2, b2): (u8, u8, u8)| {
+        let dr = r as i32 - r2 as i32;
+        let dg = g as i32 - g2 as i32;
+        let db = b as i32 - b2 as i32;
+        dr * dr + dg * dg + db * db
+    };
+
+    let (ri, gi, bi) = (nearest_level(r), nearest_level(g), nearest_level(b));
+    let cube_index = 16 + 36 * ri + 6 * gi + bi;
+    let cube_rgb = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_step = ((average as i32 - 8 + 5) / 10).clamp(0, 23) as usize;
+    let gray_level = (8 + gray_step * 10) as u8;
+    let gray_index = 232 + gray_step;
+
+    if distance((gray_level, gray_level, gray_level)) < distance(cube_rgb) {
+        gray_index as u8
+    } else {
+        cube_index as u8
+    }
+}
+
 pub fn darken_color(color: ratatui::style::Color, factor: f32) -> ratatui::style::Color {
     match color {
         ratatui::style::Color::Rgb(r, g, b) => {
@@ -87,12 +221,102 @@ pub fn darken_color(color: ratatui::style::Color, factor: f32) -> ratatui::style
 }
 
 impl Theme {
+    /// Loads a theme, filling any missing color keys from the base theme.
+    /// The filled keys are listed in `defaulted_keys`.
     pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
         let content = fs::read_to_string(path)?;
-        let theme: Theme = serde_json::from_str(&content)?;
+        Self::from_json(&content)
+    }
+
+    pub fn high_contrast() -> Self {
+        Self::from_json(HIGH_CONTRAST_THEME_JSON).expect("built-in high-contrast theme is valid")
+    }
+
+    pub fn from_json(content: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut theme: Theme = serde_json::from_str(content)?;
+        let base: Theme = serde_json::from_str(BASE_THEME_JSON)?;
+        theme.fill_missing_from(&base);
         Ok(theme)
     }
 
+    fn fill_missing_from(&mut self, base: &Theme) {
+        let mut defaulted = Vec::new();
+        for (name, mode, base_mode) in [
+            ("light", &mut self.light, &base.light),
+            ("dark", &mut self.dark, &base.dark),
+        ] {
+            let seeds = &mut mode.seeds;
+            let base_seeds = &base_mode.seeds;
+            let overrides = &mut mode.overrides;
+            let base_overrides = &base_mode.overrides;
+            let fields = [
+                ("seeds.neutral", &mut seeds.neutral, &base_seeds.neutral),
+                ("seeds.primary", &mut seeds.primary, &base_seeds.primary),
+                ("seeds.success", &mut seeds.success, &base_seeds.success),
+                ("seeds.warning", &mut seeds.warning, &base_seeds.warning),
+                ("seeds.error", &mut seeds.error, &base_seeds.error),
+                ("seeds.info", &mut seeds.info, &base_seeds.info),
+                (
+                    "seeds.interactive",
+                    &mut seeds.interactive,
+                    &base_seeds.interactive,
+                ),
+                (
+                    "overrides.background-base",
+                    &mut overrides.background_base,
+                    &base_overrides.background_base,
+                ),
+                (
+                    "overrides.text-base",
+                    &mut overrides.text_base,
+                    &base_overrides.text_base,
+                ),
+                (
+                    "overrides.text-weak",
+                    &mut overrides.text_weak,
+                    &base_overrides.text_weak,
+                ),
+                (
+                    "overrides.text-strong",
+                    &mut overrides.text_strong,
+                    &base_overrides.text_strong,
+                ),
+                (
+                    "overrides.border-base",
+                    &mut overrides.border_base,
+                    &base_overrides.border_base,
+                ),
+                (
+                    "overrides.border-weak-focus",
+                    &mut overrides.border_weak_focus,
+                    &base_overrides.border_weak_focus,
+                ),
+                (
+                    "overrides.border-focus",
+                    &mut overrides.border_focus,
+                    &base_overrides.border_focus,
+                ),
+                (
+                    "overrides.border-strong-focus",
+                    &mut overrides.border_strong_focus,
+                    &base_overrides.border_strong_focus,
+                ),
+                (
+                    "overrides.syntax-string",
+                    &mut overrides.syntax_string,
+                    &base_overrides.syntax_string,
+                ),
+            ];
+            for (key, value, base_value) in fields {
+                if value.trim().is_empty() {
+                    *value = base_value.clone();
+                    defaulted.push(format!("{}.{}", name, key));
+                }
+            }
+        }
+        self.defaulted_keys = defaulted;
+    }
+
     pub fn get_colors(&self, dark: bool) -> ThemeColors {
         let mode = if dark { &self.dark } else { &self.light };
 
@@ -126,3 +350,218 @@ fn parse_hex(hex: &str) -> ratatui::style::Color {
         ratatui::style::Color::Reset
     }
 }
+
+/// Set to enable live reloading of the theme file while editing it.
+pub const WATCH_THEMES_ENV: &str = "CRABCODE_WATCH_THEMES";
+
+/// Watches theme files and reports the ones that changed on disk. Watches the
+/// parent directories since editors often save by replacing the file.
+pub struct ThemeWatcher {
+    _watcher: notify::RecommendedWatcher,
+    receiver: std::sync::mpsc::Receiver<std::path::PathBuf>,
+    paths: Vec<std::path::PathBuf>,
+}
+
+impl ThemeWatcher {
+    pub fn new(paths: Vec<std::path::PathBuf>) -> notify::Result<Self> {
+        use notify::event::{EventKind, ModifyKind};
+        use notify::{RecursiveMode, Watcher};
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                if let Ok(event) = event {
+                    // A save also touches the file's metadata; that alone
+                    // would reload and announce the theme a second time
+                    let metadata_only =
+                        matches!(event.kind, EventKind::Modify(ModifyKind::Metadata(_)));
+                    if (event.kind.is_modify() && !metadata_only) || event.kind.is_create() {
+                        for path in event.paths {
+                            let _ = sender.send(path);
+                        }
+                    }
+                }
+            })?;
+
+        let mut dirs: Vec<&Path> = paths.iter().filter_map(|p| p.parent()).collect();
+        dirs.sort();
+        dirs.dedup();
+        for dir in dirs {
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        }
+
+        Ok(Self {
+            _watcher: watcher,
+            receiver,
+            paths,
+        })
+    }
+
+    /// Watcher is only started when `CRABCODE_WATCH_THEMES` is set.
+    pub fn from_env(paths: Vec<std::path::PathBuf>) -> Option<Self> {
+        std::env::var_os(WATCH_THEMES_ENV)?;
+        Self::new(paths).ok()
+    }
+
+    /// Drains pending events, returning each watched theme file that changed.
+    pub fn changed_paths(&self) -> Vec<std::path::PathBuf> {
+        let mut changed = Vec::new();
+        while let Ok(path) = self.receiver.try_recv() {
+            if let Some(watched) = self.paths.iter().find(|p| same_file_name(p, &path)) {
+                if !changed.contains(watched) {
+                    changed.push(watched.clone());
+                }
+            }
+        }
+        changed
+    }
+}
+
+fn same_file_name(watched: &Path, event_path: &Path) -> bool {
+    watched.file_name().is_some() && watched.file_name() == event_path.file_name()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("crabcode_theme_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_base_theme_is_complete() {
+        let theme = Theme::from_json(BASE_THEME_JSON).unwrap();
+        assert!(theme.defaulted_keys.is_empty());
+    }
+
+    #[test]
+    fn test_partial_theme_fills_missing_keys() {
+        let json = r##"{
+            "name": "Partial",
+            "id": "partial",
+            "dark": {
+                "seeds": { "primary": "#112233", "error": "#ff0000" },
+                "overrides": { "text-base": "#eeeeee" }
+            }
+        }"##;
+        let theme = Theme::from_json(json).unwrap();
+        let base = Theme::from_json(BASE_THEME_JSON).unwrap();
+
+        let colors = theme.get_colors(true);
+        assert_eq!(colors.primary, ratatui::style::Color::Rgb(0x11, 0x22, 0x33));
+        assert_eq!(colors.error, ratatui::style::Color::Rgb(0xff, 0, 0));
+        assert_eq!(colors.text, ratatui::style::Color::Rgb(0xee, 0xee, 0xee));
+        assert_eq!(colors.success, base.get_colors(true).success);
+        assert_eq!(colors.border_focus, base.get_colors(true).border_focus);
+
+        assert!(theme
+            .defaulted_keys
+            .contains(&"dark.seeds.success".to_string()));
+        assert!(theme
+            .defaulted_keys
+            .contains(&"dark.overrides.border-focus".to_string()));
+        assert!(!theme
+            .defaulted_keys
+            .contains(&"dark.seeds.primary".to_string()));
+        // The light mode is missing entirely
+        assert!(theme
+            .defaulted_keys
+            .contains(&"light.seeds.primary".to_string()));
+    }
+
+    #[test]
+    fn test_high_contrast_theme_is_complete() {
+        let theme = Theme::high_contrast();
+        assert!(
+            theme.defaulted_keys.is_empty(),
+            "{:?}",
+            theme.defaulted_keys
+        );
+
+        let dark = theme.get_colors(true);
+        assert_eq!(dark.background, ratatui::style::Color::Rgb(0, 0, 0));
+        assert_eq!(dark.text, ratatui::style::Color::Rgb(255, 255, 255));
+        let light = theme.get_colors(false);
+        assert_eq!(light.background, ratatui::style::Color::Rgb(255, 255, 255));
+        assert_eq!(light.text, ratatui::style::Color::Rgb(0, 0, 0));
+    }
+
+    #[test]
+    fn test_rgb_to_ansi256_cube_colors() {
+        assert_eq!(rgb_to_ansi256(255, 0, 0), 196);
+        assert_eq!(rgb_to_ansi256(0, 255, 0), 46);
+        assert_eq!(rgb_to_ansi256(0, 0, 255), 21);
+        // #ea580c (the default orange) lands on 166 (#d75f00)
+        assert_eq!(rgb_to_ansi256(0xea, 0x58, 0x0c), 166);
+    }
+
+    #[test]
+    fn test_rgb_to_ansi256_grays() {
+        assert_eq!(rgb_to_ansi256(0, 0, 0), 16);
+        assert_eq!(rgb_to_ansi256(255, 255, 255), 231);
+        assert_eq!(rgb_to_ansi256(128, 128, 128), 244);
+        assert_eq!(rgb_to_ansi256(30, 30, 30), 234);
+    }
+
+    #[test]
+    fn test_degraded_keeps_non_rgb_colors() {
+        use ratatui::style::Color;
+
+        let colors = Theme::from_json(BASE_THEME_JSON)
+            .unwrap()
+            .get_colors(true)
+            .degraded();
+        assert!(matches!(colors.primary, Color::Indexed(_)));
+        assert_eq!(degrade_color(Color::Reset), Color::Reset);
+        assert_eq!(degrade_color(Color::Indexed(42)), Color::Indexed(42));
+    }
+
+    #[test]
+    fn test_colorterm_detection() {
+        assert!(colorterm_is_truecolor(Some("truecolor")));
+        assert!(colorterm_is_truecolor(Some("24bit")));
+        assert!(colorterm_is_truecolor(Some("TrueColor")));
+        assert!(!colorterm_is_truecolor(Some("")));
+        assert!(!colorterm_is_truecolor(None));
+    }
+
+    #[test]
+    fn test_load_malformed_theme_is_error() {
+        let dir = temp_dir("malformed");
+        let path = dir.join("theme.json");
+        fs::write(&path, "{ \"name\": ").unwrap();
+        assert!(Theme::load_from_file(&path).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_watcher_reports_changed_theme_file() {
+        let dir = temp_dir("watch");
+        let path = dir.join("theme.json");
+        fs::write(&path, "{}").unwrap();
+
+        let watcher = ThemeWatcher::new(vec![path.clone()]).unwrap();
+        fs::write(dir.join("other.txt"), "ignored").unwrap();
+        fs::write(&path, "{ }").unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut changed = Vec::new();
+        while changed.is_empty() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+            changed = watcher.changed_paths();
+        }
+        assert_eq!(changed, vec![path]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,26 +1,43 @@
+use crate::tools::context::ToolProgress;
 use crate::tools::{ToolContext, ToolRegistry};
 use aisdk::core::{tools::ToolExecute, Tool};
 use schemars::Schema;
 use serde_json::Value;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::llm::ChunkSender;
 
 static TOOL_CALL_SEQ: AtomicUsize = AtomicUsize::new(0);
 
-/// Convert our ToolRegistry to AISDK Tools
-pub async fn convert_to_aisdk_tools(registry: &ToolRegistry, sender: Option<ChunkSender>) -> Vec<Tool> {
+/// Convert our ToolRegistry to AISDK Tools. Relative paths in tool calls
+/// resolve against `cwd` when it's set. The tools share one read cache and
+/// one record of file versions, so call this once per turn. `agent` is
+/// passed on to the tools, which may behave differently in Plan mode.
+pub async fn convert_to_aisdk_tools(
+    registry: &ToolRegistry,
+    sender: Option<ChunkSender>,
+    cwd: Option<PathBuf>,
+    agent: &str,
+) -> Vec<Tool> {
     let mut aisdk_tools = Vec::new();
     let tools = registry.list().await;
+    let cache = crate::tools::ToolCache::new();
+    let versions = crate::tools::FileVersions::new();
     
     for tool_def in tools {
         let tool_id = tool_def.id.clone();
         let tool_description = tool_def.description.clone();
         let registry = registry.clone();
         let sender = sender.clone();
+        let cwd = cwd.clone();
+        let cache = cache.clone();
+        let versions = versions.clone();
+        let agent = agent.to_string();
         
         // Create the execute function
         let execute = ToolExecute::new(Box::new(move |input: Value| {
+            let parsed = tool_input(&input);
             let tool_id = tool_id.clone();
             let tool_id_for_exec = tool_id.clone();
             let tool_id_for_ui = tool_id.clone();
@@ -29,13 +46,20 @@ pub async fn convert_to_aisdk_tools(registry: &ToolRegistry, sender: Option<Chun
             let tool_description_for_ui = tool_description.clone();
             let registry = registry.clone();
             let sender = sender.clone();
+            let cwd = cwd.clone();
+            let cache = cache.clone();
+            let versions = versions.clone();
+            let agent = agent.clone();
 
             let call_seq = TOOL_CALL_SEQ.fetch_add(1, Ordering::Relaxed) + 1;
             let call_id = format!("call_{call_seq}");
 
             if let Some(ref sender) = sender {
                 // Surface tool call start to the UI
-                let args = serde_json::to_string(&input).unwrap_or_else(|_| "{}".to_string());
+                let args = match &input {
+                    Value::String(raw) => raw.clone(),
+                    other => serde_json::to_string(other).unwrap_or_else(|_| "{}".to_string()),
+                };
                 let _ = sender.send(crate::llm::ChunkMessage::ToolCalls(vec![crate::llm::ToolCall {
                     id: call_id.clone(),
                     call_type: "function".to_string(),
@@ -54,28 +78,19 @@ pub async fn convert_to_aisdk_tools(registry: &ToolRegistry, sender: Option<Chun
             // but our tools are async. Bridge by blocking in-place on the current runtime.
             let result = tokio::task::block_in_place(|| {
                 tokio::runtime::Handle::current().block_on(async move {
-                    let _ = crate::logging::log(&format!(
-                        "[AISDK_TOOL] call {} args={} ",
-                        tool_id_for_exec,
-                        input
-                    ));
-
-                    let handler = registry
-                        .get(&tool_id_for_exec)
-                        .await
-                        .ok_or_else(|| format!("Tool '{}' not found", tool_id_for_exec))?;
-
-                    if let Err(e) = handler.validate(&input) {
-                        return Err(format!("Validation error: {}", e));
-                    }
-
+                    let input = parsed?;
                     let (_abort_tx, abort_rx) = tokio::sync::watch::channel(false);
-                    let ctx = ToolContext::new("session", "message", "aisdk", abort_rx);
+                    let mut ctx = ToolContext::new("session", "message", &agent, abort_rx)
+                        .with_cache(cache)
+                        .with_versions(versions);
+                    if let Some(cwd) = cwd {
+                        ctx = ctx.with_cwd(cwd);
+                    }
+                    if let Some(sender) = sender_for_block.clone() {
+                        ctx = ctx.with_progress(ToolProgress::new(sender, &call_id_for_block));
+                    }
 
-                    let tool_result = handler
-                        .execute(input, &ctx)
-                        .await
-                        .map_err(|e| format!("Execution error: {}", e))?;
+                    let tool_result = run_tool(&registry, &tool_id_for_exec, input, &ctx).await?;
 
                     let _ = crate::logging::log(&format!(
                         "[AISDK_TOOL] result {} bytes={}",
@@ -85,10 +100,12 @@ pub async fn convert_to_aisdk_tools(registry: &ToolRegistry, sender: Option<Chun
 
                     if let Some(ref sender) = sender_for_block {
                         let preview_limit: usize = 4000;
-                        let mut preview = tool_result.output.clone();
-                        if preview.len() > preview_limit {
-                            preview.truncate(preview_limit);
-                            preview.push_str("... (truncated)");
+                        let mut preview =
+                            crate::utils::truncate_chars(&tool_result.output, preview_limit);
+                        // truncate_chars cuts by chars and ends with an ellipsis, so
+                        // byte lengths don't tell whether it cut anything
+                        if tool_result.output.chars().count() > preview_limit {
+                            preview.push_str(" (truncated)");
                         }
 
                         let line_count = tool_result.output.lines().count();
@@ -191,6 +208,48 @@ pub async fn convert_to_aisdk_tools(registry: &ToolRegistry, sender: Option<Chun
     aisdk_tools
 }
 
+/// The arguments a tool runs with. Some providers hand them over as JSON
+/// text, possibly with trailing commas or cut off; only repairs that can't
+/// change a value are made, and the model is asked to resend the rest rather
+/// than running a shorter command or path.
+fn tool_input(input: &Value) -> Result<Value, String> {
+    match input {
+        Value::String(raw) => crate::llm::tool_calls::parse_arguments_to_run(raw).map_err(|e| {
+            format!(
+                "Tool arguments are not valid JSON ({}), possibly cut off. The tool was not run; send the complete arguments again.",
+                e
+            )
+        }),
+        other => Ok(other.clone()),
+    }
+}
+
+/// Validates `input` and runs tool `tool_id` with it.
+async fn run_tool(
+    registry: &ToolRegistry,
+    tool_id: &str,
+    input: Value,
+    ctx: &ToolContext,
+) -> Result<crate::tools::ToolResult, String> {
+    let _ = crate::logging::log(&format!("[AISDK_TOOL] call {} args={} ", tool_id, input));
+
+    let handler = registry
+        .get(tool_id)
+        .await
+        .ok_or_else(|| format!("Tool '{}' not found", tool_id))?;
+
+    if let Err(e) = handler.validate(&input) {
+        return Err(match e {
+            crate::tools::ToolError::Permission(_) => e.to_string(),
+            _ => format!("Validation error: {}", e),
+        });
+    }
+
+    crate::tools::cache::execute_cached(handler.as_ref(), tool_id, input, ctx)
+        .await
+        .map_err(|e| format!("Execution error: {}", e))
+}
+
 fn param_to_json_schema(param_type: &crate::tools::ParameterType) -> serde_json::Value {
     use crate::tools::ParameterType;
     
@@ -216,3 +275,61 @@ fn param_to_json_schema(param_type: &crate::tools::ParameterType) -> serde_json:
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    async fn read_registry() -> ToolRegistry {
+        let registry = ToolRegistry::new();
+        registry
+            .register(Arc::new(crate::tools::fs::ReadTool::new()))
+            .await;
+        registry
+    }
+
+    fn tool_context(dir: &std::path::Path) -> ToolContext {
+        let (_abort_tx, abort_rx) = tokio::sync::watch::channel(false);
+        ToolContext::new("session", "message", "test", abort_rx).with_cwd(dir)
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("crabcode-bridge-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("notes.txt"), "bridge test contents\n").unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_recoverable_arguments_run_the_tool() {
+        let dir = temp_dir("recoverable");
+        let registry = read_registry().await;
+        let ctx = tool_context(&dir);
+
+        for raw in [
+            r#"{"file_path":"notes.txt",}"#,
+            r#"{"file_path":"notes.txt""#,
+            r#"{"file_path":"notes.txt","#,
+        ] {
+            let input = tool_input(&Value::String(raw.to_string())).unwrap();
+            let result = run_tool(&registry, "read", input, &ctx).await.unwrap();
+            assert!(result.output.contains("bridge test contents"), "{}", raw);
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_truncated_string_arguments_are_refused() {
+        let err = tool_input(&Value::String(r#"{"file_path":"notes.t"#.to_string())).unwrap_err();
+        assert!(err.contains("The tool was not run"), "{}", err);
+    }
+
+    #[test]
+    fn test_object_arguments_pass_through() {
+        let input = serde_json::json!({ "file_path": "notes.txt" });
+        assert_eq!(tool_input(&input).unwrap(), input);
+    }
+}
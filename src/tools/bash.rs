@@ -1,3 +1,5 @@
+use crate::tools::bash_policy::BashPolicy;
+use crate::tools::context::ToolProgress;
 use crate::tools::{
     get_bool_param, get_integer_param, get_string_param, validate_required, Tool, ToolContext,
     ToolError, ToolHandler, ToolResult, ParameterSchema, ParameterType,
@@ -12,12 +14,56 @@ use tokio::time::timeout;
 
 const DEFAULT_TIMEOUT_SECONDS: u64 = 120;
 const MAX_OUTPUT_SIZE: usize = 51200; // 50KB
+/// How often output read so far is sent to the UI
+const OUTPUT_FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+/// Output waiting for the next flush; a burst beyond this keeps its end
+const MAX_PENDING_OUTPUT: usize = 8192;
+/// How long to wait for output still in the pipes after the command exits.
+/// Background jobs can hold them open, so this can't wait for the end.
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_millis(200);
 
-pub struct BashTool;
+/// Lines read but not yet sent to the UI.
+#[derive(Default)]
+struct PendingOutput {
+    text: String,
+}
+
+impl PendingOutput {
+    fn push(&mut self, line: &str) {
+        self.text.push_str(line);
+        self.text.push('\n');
+        if self.text.len() > MAX_PENDING_OUTPUT {
+            self.text = crate::utils::tail_bytes(&self.text, MAX_PENDING_OUTPUT).to_string();
+        }
+    }
+
+    fn flush(&mut self, progress: &ToolProgress) {
+        if !self.text.is_empty() {
+            progress.output(std::mem::take(&mut self.text));
+        }
+    }
+}
+
+pub struct BashTool {
+    policy: BashPolicy,
+}
 
 impl BashTool {
     pub fn new() -> Self {
-        Self
+        Self {
+            policy: BashPolicy::load(),
+        }
+    }
+
+    pub fn with_policy(policy: BashPolicy) -> Self {
+        Self { policy }
+    }
+
+    fn check_command(&self, command: &str) -> Result<(), ToolError> {
+        if let Some(reason) = Self::is_dangerous(command) {
+            return Err(ToolError::Permission(reason));
+        }
+        self.policy.check(command).map_err(ToolError::Permission)
     }
 
     fn is_dangerous(command: &str) -> Option<String> {
@@ -77,7 +123,11 @@ impl ToolHandler for BashTool {
     }
 
     fn validate(&self, params: &Value) -> Result<(), ToolError> {
-        validate_required(params, &["command"])
+        validate_required(params, &["command"])?;
+        match get_string_param(params, "command") {
+            Some(command) => self.check_command(&command),
+            None => Ok(()),
+        }
     }
 
     async fn execute(&self, params: Value, ctx: &ToolContext) -> Result<ToolResult, ToolError> {
@@ -94,17 +144,23 @@ impl ToolHandler for BashTool {
         let description = get_string_param(&params, "description")
             .unwrap_or_else(|| command_str.clone());
 
-        if let Some(reason) = Self::is_dangerous(&command_str) {
-            return Err(ToolError::Permission(reason));
-        }
+        self.check_command(&command_str)?;
 
         let mut cmd = Command::new("bash");
         cmd.arg("-c").arg(&command_str);
+        self.policy.env.as_ref().unwrap_or(&ctx.env).apply(&mut cmd);
 
+        let workdir = workdir
+            .map(|dir| ctx.resolve_path(&dir))
+            .or_else(|| ctx.cwd.clone());
         if let Some(dir) = workdir {
             cmd.current_dir(dir);
         }
 
+        // Left inherited, commands could read the TUI's keypresses, and bash
+        // takes a socket on stdin to mean it's run by sshd and sources
+        // ~/.bashrc
+        cmd.stdin(Stdio::null());
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
 
@@ -122,6 +178,9 @@ impl ToolHandler for BashTool {
         let mut stderr_lines: Vec<String> = Vec::new();
 
         let timeout_duration = Duration::from_secs(timeout_seconds);
+        let mut pending = PendingOutput::default();
+        let mut flush = tokio::time::interval(OUTPUT_FLUSH_INTERVAL);
+        flush.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
         let result = timeout(timeout_duration, async {
             loop {
@@ -134,6 +193,9 @@ impl ToolHandler for BashTool {
                     line = stdout_reader.next_line() => {
                         match line {
                             Ok(Some(l)) => {
+                                if ctx.progress.is_some() {
+                                    pending.push(&l);
+                                }
                                 if stdout_lines.len() < MAX_OUTPUT_SIZE {
                                     stdout_lines.push(l);
                                 }
@@ -145,6 +207,9 @@ impl ToolHandler for BashTool {
                     line = stderr_reader.next_line() => {
                         match line {
                             Ok(Some(l)) => {
+                                if ctx.progress.is_some() {
+                                    pending.push(&l);
+                                }
                                 if stderr_lines.len() < MAX_OUTPUT_SIZE {
                                     stderr_lines.push(l);
                                 }
@@ -153,6 +218,11 @@ impl ToolHandler for BashTool {
                             Err(e) => return Err(ToolError::Execution(format!("Error reading stderr: {}", e))),
                         }
                     }
+                    _ = flush.tick() => {
+                        if let Some(progress) = &ctx.progress {
+                            pending.flush(progress);
+                        }
+                    }
                     status = child.wait() => {
                         return match status {
                             Ok(exit_status) => Ok(exit_status),
@@ -175,6 +245,21 @@ impl ToolHandler for BashTool {
             }
         };
 
+        // The command can exit before everything it printed was read
+        let _ = timeout(OUTPUT_DRAIN_TIMEOUT, async {
+            while let Ok(Some(l)) = stdout_reader.next_line().await {
+                if stdout_lines.len() < MAX_OUTPUT_SIZE {
+                    stdout_lines.push(l);
+                }
+            }
+            while let Ok(Some(l)) = stderr_reader.next_line().await {
+                if stderr_lines.len() < MAX_OUTPUT_SIZE {
+                    stderr_lines.push(l);
+                }
+            }
+        })
+        .await;
+
         let mut output_parts = Vec::new();
 
         if !stdout_lines.is_empty() {
@@ -211,3 +296,143 @@ impl ToolHandler for BashTool {
         .with_metadata("command", serde_json::json!(command_str)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::env_passthrough::EnvPassthrough;
+
+    fn tool_context() -> ToolContext {
+        let (_abort_tx, abort_rx) = tokio::sync::watch::channel(false);
+        ToolContext::new("session", "message", "test", abort_rx)
+    }
+
+    #[test]
+    fn test_validate_refuses_denied_command() {
+        let tool = BashTool::with_policy(BashPolicy {
+            deny: vec!["rm -rf".to_string()],
+            ..BashPolicy::default()
+        });
+
+        let params = serde_json::json!({ "command": "rm -rf build" });
+        assert!(matches!(
+            tool.validate(&params),
+            Err(ToolError::Permission(_))
+        ));
+        assert!(tool.validate(&serde_json::json!({ "command": "ls" })).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_execute_refuses_command_outside_allowlist() {
+        let tool = BashTool::with_policy(BashPolicy {
+            allow: vec!["echo".to_string()],
+            allowlist_only: true,
+            ..BashPolicy::default()
+        });
+        let ctx = tool_context();
+
+        let result = tool
+            .execute(serde_json::json!({ "command": "touch /tmp/x" }), &ctx)
+            .await;
+        assert!(matches!(result, Err(ToolError::Permission(_))));
+
+        let result = tool
+            .execute(serde_json::json!({ "command": "echo allowed" }), &ctx)
+            .await;
+        assert!(result.is_ok());
+    }
+
+    /// Names of the variables the command's process was started with,
+    /// written to a file so none of the output can be missed.
+    async fn child_env(tool: &BashTool, ctx: &ToolContext) -> Vec<String> {
+        let dir = tempfile_dir();
+        let path = dir.join("env.txt");
+        let command = format!("/usr/bin/env > {}", path.display());
+        tool.execute(serde_json::json!({ "command": command }), ctx)
+            .await
+            .unwrap();
+        let names = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .filter_map(|line| line.split_once('=').map(|(name, _)| name.to_string()))
+            .collect();
+        let _ = std::fs::remove_dir_all(dir);
+        names
+    }
+
+    fn tempfile_dir() -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("crabcode-bash-env-{}", cuid2::create_id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_denied_env_vars_are_not_passed() {
+        let tool = BashTool::with_policy(BashPolicy::default());
+
+        let ctx = tool_context().with_env(EnvPassthrough::Deny(vec!["HOME".to_string()]));
+        let names = child_env(&tool, &ctx).await;
+        assert!(names.contains(&"PATH".to_string()));
+        assert!(!names.contains(&"HOME".to_string()));
+
+        let ctx = tool_context().with_env(EnvPassthrough::Allow(vec!["HOME".to_string()]));
+        let names = child_env(&tool, &ctx).await;
+        assert!(names.contains(&"HOME".to_string()));
+        assert!(!names.contains(&"PATH".to_string()));
+
+        // By default only the safe set gets through
+        let names = child_env(&tool, &tool_context()).await;
+        let default = EnvPassthrough::default();
+        assert!(names
+            .iter()
+            .all(|name| default.passes(name) || ["PWD", "SHLVL", "_"].contains(&name.as_str())));
+    }
+
+    #[tokio::test]
+    async fn test_policy_env_replaces_the_context_default() {
+        let tool = BashTool::with_policy(BashPolicy {
+            env: Some(EnvPassthrough::Deny(vec!["HOME".to_string()])),
+            ..BashPolicy::default()
+        });
+        let names = child_env(&tool, &tool_context()).await;
+        assert!(!names.contains(&"HOME".to_string()));
+        assert!(names.contains(&"PATH".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_output_is_streamed_while_the_command_runs() {
+        let tool = BashTool::with_policy(BashPolicy::default());
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let ctx = tool_context().with_progress(ToolProgress::new(sender, "call_1"));
+
+        let result = tool
+            .execute(
+                serde_json::json!({ "command": "echo first; sleep 0.5; echo second" }),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert!(result.output.contains("second"));
+
+        let mut chunks = Vec::new();
+        while let Ok(chunk) = receiver.try_recv() {
+            let crate::llm::ChunkMessage::ToolOutput { tool_call_id, text } = chunk else {
+                panic!("expected only tool output");
+            };
+            assert_eq!(tool_call_id, "call_1");
+            chunks.push(text);
+        }
+        // The first line was sent before the command finished
+        assert_eq!(chunks.first().map(String::as_str), Some("first\n"));
+    }
+
+    #[test]
+    fn test_pending_output_keeps_the_end_of_a_burst() {
+        let mut pending = PendingOutput::default();
+        for i in 0..2000 {
+            pending.push(&format!("line {}", i));
+        }
+        assert!(pending.text.len() <= MAX_PENDING_OUTPUT);
+        assert!(pending.text.ends_with("line 1999\n"));
+    }
+}
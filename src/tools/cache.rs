@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde_json::Value;
+
+use super::{ToolContext, ToolError, ToolHandler, ToolResult};
+
+/// Tools whose result only depends on their arguments and what's on disk.
+const CACHEABLE_TOOLS: [&str; 3] = ["read", "glob", "list"];
+
+/// Results of read-only tool calls within one turn, keyed by tool and
+/// arguments. Clones share the same entries.
+#[derive(Clone, Default)]
+pub struct ToolCache {
+    entries: Arc<Mutex<HashMap<String, ToolResult>>>,
+}
+
+impl ToolCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn key(tool_id: &str, params: &Value) -> String {
+        format!("{}:{}", tool_id, params)
+    }
+
+    pub fn get(&self, tool_id: &str, params: &Value) -> Option<ToolResult> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(&Self::key(tool_id, params))
+            .cloned()
+    }
+
+    pub fn insert(&self, tool_id: &str, params: &Value, result: ToolResult) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(Self::key(tool_id, params), result);
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Runs a tool call through `ctx.cache`: repeated reads are answered from
+/// it, and any other tool (write, edit, bash...) empties it, since it may
+/// have changed files. Only successful reads are kept.
+pub async fn execute_cached(
+    handler: &dyn ToolHandler,
+    tool_id: &str,
+    params: Value,
+    ctx: &ToolContext,
+) -> Result<ToolResult, ToolError> {
+    if !CACHEABLE_TOOLS.contains(&tool_id) {
+        let result = handler.execute(params, ctx).await;
+        ctx.cache.clear();
+        return result;
+    }
+
+    if let Some(result) = ctx.cache.get(tool_id, &params) {
+        let _ = crate::logging::log(&format!("[TOOL_CACHE] hit {} {}", tool_id, params));
+        return Ok(result);
+    }
+    let result = handler.execute(params.clone(), ctx).await?;
+    ctx.cache.insert(tool_id, &params, result.clone());
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::fs::{ReadTool, WriteTool};
+
+    fn tool_context(dir: &std::path::Path) -> ToolContext {
+        let (_abort_tx, abort_rx) = tokio::sync::watch::channel(false);
+        ToolContext::new("session", "message", "test", abort_rx).with_cwd(dir)
+    }
+
+    #[tokio::test]
+    async fn test_repeated_read_is_cached_until_a_write() {
+        let dir = std::env::temp_dir().join(format!("crabcode-tool-cache-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("notes.txt"), "first").unwrap();
+        let ctx = tool_context(&dir);
+        let read = serde_json::json!({ "file_path": "notes.txt" });
+
+        let first = execute_cached(&ReadTool::new(), "read", read.clone(), &ctx)
+            .await
+            .unwrap();
+        assert!(first.output.contains("first"));
+        assert_eq!(ctx.cache.len(), 1);
+
+        // Changed behind the cache's back: the second read doesn't see it
+        std::fs::write(dir.join("notes.txt"), "outside").unwrap();
+        let second = execute_cached(&ReadTool::new(), "read", read.clone(), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(second.output, first.output);
+
+        // The write is refused as a conflict but still empties the cache
+        let write = serde_json::json!({ "file_path": "notes.txt", "content": "second" });
+        assert!(
+            execute_cached(&WriteTool::new(), "write", write.clone(), &ctx)
+                .await
+                .is_err()
+        );
+        assert!(ctx.cache.is_empty());
+        let third = execute_cached(&ReadTool::new(), "read", read.clone(), &ctx)
+            .await
+            .unwrap();
+        assert!(third.output.contains("outside"));
+
+        execute_cached(&WriteTool::new(), "write", write, &ctx)
+            .await
+            .unwrap();
+        assert!(ctx.cache.is_empty());
+        let fourth = execute_cached(&ReadTool::new(), "read", read, &ctx)
+            .await
+            .unwrap();
+        assert!(fourth.output.contains("second"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_failed_read_is_not_cached() {
+        let dir =
+            std::env::temp_dir().join(format!("crabcode-tool-cache-miss-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let ctx = tool_context(&dir);
+        let read = serde_json::json!({ "file_path": "missing.txt" });
+
+        assert!(execute_cached(&ReadTool::new(), "read", read, &ctx)
+            .await
+            .is_err());
+        assert!(ctx.cache.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,3 +1,33 @@
+use std::path::{Path, PathBuf};
+
+use super::cache::ToolCache;
+use super::env_passthrough::EnvPassthrough;
+use super::versions::FileVersions;
+use crate::llm::{ChunkMessage, ChunkSender};
+
+/// Sends what a tool prints while it runs to the chat's row for the call.
+#[derive(Clone)]
+pub struct ToolProgress {
+    sender: ChunkSender,
+    call_id: String,
+}
+
+impl ToolProgress {
+    pub fn new(sender: ChunkSender, call_id: impl Into<String>) -> Self {
+        Self {
+            sender,
+            call_id: call_id.into(),
+        }
+    }
+
+    pub fn output(&self, text: String) {
+        let _ = self.sender.send(ChunkMessage::ToolOutput {
+            tool_call_id: self.call_id.clone(),
+            text,
+        });
+    }
+}
+
 pub struct ToolContext {
     pub session_id: String,
     pub message_id: String,
@@ -5,6 +35,16 @@ pub struct ToolContext {
     pub abort: tokio::sync::watch::Receiver<bool>,
     pub call_id: Option<String>,
     pub extra: Option<serde_json::Value>,
+    /// Directory relative paths are resolved against, see `/cd`
+    pub cwd: Option<PathBuf>,
+    /// Read results shared by the calls of one turn
+    pub cache: ToolCache,
+    /// What the files read this turn looked like, to catch outside edits
+    pub versions: FileVersions,
+    /// Environment variables spawned commands inherit
+    pub env: EnvPassthrough,
+    /// Set when the UI shows output of long-running tools as it comes
+    pub progress: Option<ToolProgress>,
 }
 
 impl ToolContext {
@@ -21,6 +61,11 @@ impl ToolContext {
             abort,
             call_id: None,
             extra: None,
+            cwd: None,
+            cache: ToolCache::new(),
+            versions: FileVersions::new(),
+            env: EnvPassthrough::default(),
+            progress: None,
         }
     }
 
@@ -34,7 +79,61 @@ impl ToolContext {
         self
     }
 
+    pub fn with_cwd(mut self, cwd: impl Into<PathBuf>) -> Self {
+        self.cwd = Some(cwd.into());
+        self
+    }
+
+    pub fn with_cache(mut self, cache: ToolCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    pub fn with_versions(mut self, versions: FileVersions) -> Self {
+        self.versions = versions;
+        self
+    }
+
+    pub fn with_env(mut self, env: EnvPassthrough) -> Self {
+        self.env = env;
+        self
+    }
+
+    pub fn with_progress(mut self, progress: ToolProgress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    pub fn resolve_path(&self, path: &str) -> PathBuf {
+        match &self.cwd {
+            Some(cwd) if Path::new(path).is_relative() => cwd.join(path),
+            _ => PathBuf::from(path),
+        }
+    }
+
     pub fn is_aborted(&self) -> bool {
         *self.abort.borrow()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_path_against_cwd() {
+        let (_abort_tx, abort_rx) = tokio::sync::watch::channel(false);
+        let ctx = ToolContext::new("session", "message", "test", abort_rx);
+        assert_eq!(
+            ctx.resolve_path("src/main.rs"),
+            PathBuf::from("src/main.rs")
+        );
+
+        let ctx = ctx.with_cwd("/work/project");
+        assert_eq!(
+            ctx.resolve_path("src/main.rs"),
+            PathBuf::from("/work/project/src/main.rs")
+        );
+        assert_eq!(ctx.resolve_path("/etc/hosts"), PathBuf::from("/etc/hosts"));
+    }
+}
@@ -4,9 +4,20 @@ use crate::tools::{
 };
 use async_trait::async_trait;
 use serde_json::Value;
-use std::path::Path;
+use std::collections::HashMap;
 
 const SIMILARITY_THRESHOLD: f64 = 0.8;
+/// Unchanged lines shown around the change in the returned diff
+const DIFF_CONTEXT_LINES: usize = 3;
+const MAX_DIFF_LINES: usize = 200;
+
+/// One find/replace within a file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replacement {
+    pub old_string: String,
+    pub new_string: String,
+    pub replace_all: bool,
+}
 
 pub struct EditTool;
 
@@ -24,6 +35,101 @@ impl EditTool {
         1.0 - (distance as f64 / max_len as f64)
     }
 
+    /// The edits of a call: the `edits` list, or the single
+    /// `old_string`/`new_string` pair.
+    fn parse_edits(params: &Value) -> Result<Vec<Replacement>, ToolError> {
+        let Some(edits) = params.get("edits") else {
+            let old_string = get_string_param(params, "old_string")
+                .ok_or_else(|| ToolError::Validation("old_string is required".to_string()))?;
+            let new_string = get_string_param(params, "new_string")
+                .ok_or_else(|| ToolError::Validation("new_string is required".to_string()))?;
+            return Ok(vec![Replacement {
+                old_string,
+                new_string,
+                replace_all: get_bool_param(params, "replace_all", false),
+            }]);
+        };
+
+        let edits = edits
+            .as_array()
+            .filter(|edits| !edits.is_empty())
+            .ok_or_else(|| ToolError::Validation("edits must be a non-empty list".to_string()))?;
+        edits
+            .iter()
+            .enumerate()
+            .map(|(i, edit)| {
+                let field = |name: &str| {
+                    get_string_param(edit, name).ok_or_else(|| {
+                        ToolError::Validation(format!("Edit {}: {} is required", i + 1, name))
+                    })
+                };
+                Ok(Replacement {
+                    old_string: field("old_string")?,
+                    new_string: field("new_string")?,
+                    replace_all: get_bool_param(edit, "replace_all", false),
+                })
+            })
+            .collect()
+    }
+
+    /// Applies `edits` in order, each to the result of the previous one.
+    /// Fails as a whole if any edit doesn't match exactly one place (or at
+    /// least one with `replace_all`). Returns the new content and how many
+    /// replacements were made.
+    fn apply_edits(content: &str, edits: &[Replacement]) -> Result<(String, usize), ToolError> {
+        let mut content = content.to_string();
+        let mut replacements = 0;
+        for (i, edit) in edits.iter().enumerate() {
+            let label = if edits.len() > 1 {
+                format!("Edit {}: ", i + 1)
+            } else {
+                String::new()
+            };
+            let preview = || edit.old_string.chars().take(50).collect::<String>();
+
+            if edit.old_string.is_empty() {
+                return Err(ToolError::Validation(format!(
+                    "{}old_string is empty",
+                    label
+                )));
+            }
+
+            let count = content.matches(&edit.old_string).count();
+            if edit.replace_all {
+                if count == 0 {
+                    return Err(ToolError::NotFound(format!(
+                        "{}Text not found in file: {}",
+                        label,
+                        preview()
+                    )));
+                }
+                content = content.replace(&edit.old_string, &edit.new_string);
+                replacements += count;
+                continue;
+            }
+            if count > 1 {
+                return Err(ToolError::Validation(format!(
+                    "{}Text appears {} times, add surrounding lines to pick one or set replace_all: {}",
+                    label,
+                    count,
+                    preview()
+                )));
+            }
+
+            let (start, end) =
+                Self::find_best_match(&content, &edit.old_string).ok_or_else(|| {
+                    ToolError::NotFound(format!(
+                        "{}Could not find text to replace: {}",
+                        label,
+                        preview()
+                    ))
+                })?;
+            content.replace_range(start..end, &edit.new_string);
+            replacements += 1;
+        }
+        Ok((content, replacements))
+    }
+
     fn find_best_match<'a>(content: &str, old_string: &str) -> Option<(usize, usize)> {
         if let Some(pos) = content.find(old_string) {
             return Some((pos, pos + old_string.len()));
@@ -59,9 +165,14 @@ impl EditTool {
 #[async_trait]
 impl ToolHandler for EditTool {
     fn definition(&self) -> Tool {
+        let edit_fields = HashMap::from([
+            ("old_string".to_string(), ParameterType::String),
+            ("new_string".to_string(), ParameterType::String),
+            ("replace_all".to_string(), ParameterType::Boolean),
+        ]);
         Tool {
             id: "edit".to_string(),
-            description: "Replace text in files with smart matching. Supports exact match, fuzzy match, and line-trimmed match.".to_string(),
+            description: "Replace text in files with smart matching. Supports exact match, fuzzy match, and line-trimmed match. Pass `edits` to make several replacements in one file at once; if any of them doesn't match, none are applied. Text that appears more than once needs more context or replace_all.".to_string(),
             parameters: vec![
                 ParameterSchema {
                     name: "file_path".to_string(),
@@ -71,14 +182,14 @@ impl ToolHandler for EditTool {
                 },
                 ParameterSchema {
                     name: "old_string".to_string(),
-                    description: "Text to replace".to_string(),
-                    required: true,
+                    description: "Text to replace (when not using edits)".to_string(),
+                    required: false,
                     param_type: ParameterType::String,
                 },
                 ParameterSchema {
                     name: "new_string".to_string(),
-                    description: "Replacement text".to_string(),
-                    required: true,
+                    description: "Replacement text (when not using edits)".to_string(),
+                    required: false,
                     param_type: ParameterType::String,
                 },
                 ParameterSchema {
@@ -87,27 +198,28 @@ impl ToolHandler for EditTool {
                     required: false,
                     param_type: ParameterType::Boolean,
                 },
+                ParameterSchema {
+                    name: "edits".to_string(),
+                    description: "Replacements applied in order, each with old_string, new_string and optional replace_all".to_string(),
+                    required: false,
+                    param_type: ParameterType::Array(Box::new(ParameterType::Object(edit_fields))),
+                },
             ],
         }
     }
 
     fn validate(&self, params: &Value) -> Result<(), ToolError> {
-        validate_required(params, &["file_path", "old_string", "new_string"])
+        validate_required(params, &["file_path"])?;
+        Self::parse_edits(params).map(|_| ())
     }
 
-    async fn execute(&self, params: Value, _ctx: &ToolContext) -> Result<ToolResult, ToolError> {
+    async fn execute(&self, params: Value, ctx: &ToolContext) -> Result<ToolResult, ToolError> {
         let file_path = get_string_param(&params, "file_path")
             .ok_or_else(|| ToolError::Validation("file_path is required".to_string()))?;
 
-        let old_string = get_string_param(&params, "old_string")
-            .ok_or_else(|| ToolError::Validation("old_string is required".to_string()))?;
-
-        let new_string = get_string_param(&params, "new_string")
-            .ok_or_else(|| ToolError::Validation("new_string is required".to_string()))?;
+        let edits = Self::parse_edits(&params)?;
 
-        let replace_all = get_bool_param(&params, "replace_all", false);
-
-        let path = Path::new(&file_path);
+        let path = &ctx.resolve_path(&file_path);
 
         if !path.exists() {
             return Err(ToolError::NotFound(format!("File not found: {}", file_path)));
@@ -119,48 +231,190 @@ impl ToolHandler for EditTool {
 
         let content = std::fs::read_to_string(path)
             .map_err(|e| ToolError::Execution(format!("Failed to read file: {}", e)))?;
+        ctx.versions.check(path, &file_path, Some(content.as_bytes()))?;
 
-        if replace_all {
-            if !content.contains(&old_string) {
-                return Err(ToolError::NotFound(format!(
-                    "Text not found in file: {}",
-                    old_string.chars().take(50).collect::<String>()
-                )));
-            }
+        let (new_content, replacements) = Self::apply_edits(&content, &edits)?;
 
-            let new_content = content.replace(&old_string, &new_string);
-            let count = content.matches(&old_string).count();
+        std::fs::write(path, &new_content)
+            .map_err(|e| ToolError::Execution(format!("Failed to write file: {}", e)))?;
+        ctx.versions.record(path, new_content.as_bytes());
 
-            std::fs::write(path, new_content)
-                .map_err(|e| ToolError::Execution(format!("Failed to write file: {}", e)))?;
+        let summary = format!(
+            "Applied {} edit(s), {} replacement(s)",
+            edits.len(),
+            replacements
+        );
+        Ok(ToolResult::new(
+            format!("Edit: {}", file_path),
+            format!("{}\n\n{}", summary, line_diff(&content, &new_content)),
+        )
+        .with_metadata("replacements", serde_json::json!(replacements)))
+    }
+}
 
-            return Ok(ToolResult::new(
-                format!("Edit: {}", file_path),
-                format!("Replaced {} occurrence(s)", count)
-            ));
-        }
+/// A unified-style diff with one hunk spanning everything that changed.
+fn line_diff(old: &str, new: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
 
-        match Self::find_best_match(&content, &old_string) {
-            Some((start, end)) => {
-                let mut new_content = String::with_capacity(content.len() - (end - start) + new_string.len());
-                new_content.push_str(&content[..start]);
-                new_content.push_str(&new_string);
-                new_content.push_str(&content[end..]);
+    let prefix = old_lines
+        .iter()
+        .zip(&new_lines)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_end = old_lines.len() - suffix;
+    let new_end = new_lines.len() - suffix;
+    if prefix == old_end && prefix == new_end {
+        return "(no line changes)".to_string();
+    }
 
-                std::fs::write(path, new_content)
-                    .map_err(|e| ToolError::Execution(format!("Failed to write file: {}", e)))?;
+    let start = prefix.saturating_sub(DIFF_CONTEXT_LINES);
+    let trailing = suffix.min(DIFF_CONTEXT_LINES);
+    let mut lines = vec![format!(
+        "@@ -{},{} +{},{} @@",
+        start + 1,
+        old_end + trailing - start,
+        start + 1,
+        new_end + trailing - start
+    )];
+    lines.extend(old_lines[start..prefix].iter().map(|l| format!(" {}", l)));
+    lines.extend(old_lines[prefix..old_end].iter().map(|l| format!("-{}", l)));
+    lines.extend(new_lines[prefix..new_end].iter().map(|l| format!("+{}", l)));
+    lines.extend(
+        old_lines[old_end..old_end + trailing]
+            .iter()
+            .map(|l| format!(" {}", l)),
+    );
 
-                let line_num = content[..start].chars().filter(|c| *c == '\n').count() + 1;
+    if lines.len() > MAX_DIFF_LINES {
+        let more = lines.len() - MAX_DIFF_LINES;
+        lines.truncate(MAX_DIFF_LINES);
+        lines.push(format!("... {} more lines", more));
+    }
+    lines.join("\n")
+}
 
-                Ok(ToolResult::new(
-                    format!("Edit: {}", file_path),
-                    format!("Replaced at line {}", line_num)
-                ))
-            }
-            None => Err(ToolError::NotFound(format!(
-                "Could not find text to replace: {}",
-                old_string.chars().take(50).collect::<String>()
-            ))),
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool_context(dir: &std::path::Path) -> ToolContext {
+        let (_abort_tx, abort_rx) = tokio::sync::watch::channel(false);
+        ToolContext::new("session", "message", "test", abort_rx).with_cwd(dir)
+    }
+
+    fn temp_file(name: &str, content: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("crabcode-edit-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("lib.rs"), content).unwrap();
+        dir
+    }
+
+    fn edit(old_string: &str, new_string: &str) -> Value {
+        serde_json::json!({ "old_string": old_string, "new_string": new_string })
+    }
+
+    #[tokio::test]
+    async fn test_multi_edit_applies_all() {
+        let dir = temp_file("multi", "fn a() {}\nfn b() {}\nlet x = 1;\nlet y = 1;\n");
+        let params = serde_json::json!({
+            "file_path": "lib.rs",
+            "edits": [
+                edit("fn a()", "pub fn a()"),
+                edit("fn b() {}", "fn b() -> u8 { 0 }"),
+                { "old_string": "= 1", "new_string": "= 2", "replace_all": true },
+            ],
+        });
+
+        let result = EditTool::new()
+            .execute(params, &tool_context(&dir))
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("lib.rs")).unwrap(),
+            "pub fn a() {}\nfn b() -> u8 { 0 }\nlet x = 2;\nlet y = 2;\n"
+        );
+        assert!(result
+            .output
+            .starts_with("Applied 3 edit(s), 4 replacement(s)"));
+        assert!(result.output.contains("-fn a() {}\n-fn b() {}"));
+        assert!(result.output.contains("+let y = 2;"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_ambiguous_edit_applies_nothing() {
+        let original = "let x = 1;\nlet y = 1;\n";
+        let dir = temp_file("ambiguous", original);
+        let params = serde_json::json!({
+            "file_path": "lib.rs",
+            "edits": [edit("let x", "let z"), edit("= 1", "= 2")],
+        });
+
+        let result = EditTool::new().execute(params, &tool_context(&dir)).await;
+        let Err(ToolError::Validation(message)) = result else {
+            panic!("expected an ambiguity error");
+        };
+        assert!(message.starts_with("Edit 2: Text appears 2 times"));
+        assert_eq!(
+            std::fs::read_to_string(dir.join("lib.rs")).unwrap(),
+            original
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_unmatched_edit_applies_nothing() {
+        let original = "fn main() {}\n";
+        let dir = temp_file("nomatch", original);
+        let params = serde_json::json!({
+            "file_path": "lib.rs",
+            "edits": [edit("fn main()", "fn start()"), edit("struct Missing", "struct Found")],
+        });
+
+        let result = EditTool::new().execute(params, &tool_context(&dir)).await;
+        assert!(matches!(result, Err(ToolError::NotFound(_))));
+        assert_eq!(
+            std::fs::read_to_string(dir.join("lib.rs")).unwrap(),
+            original
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_validate_needs_edits_or_strings() {
+        let tool = EditTool::new();
+        assert!(tool
+            .validate(
+                &serde_json::json!({ "file_path": "a", "old_string": "x", "new_string": "y" })
+            )
+            .is_ok());
+        assert!(tool
+            .validate(&serde_json::json!({ "file_path": "a", "edits": [edit("x", "y")] }))
+            .is_ok());
+        assert!(tool
+            .validate(&serde_json::json!({ "file_path": "a", "edits": [] }))
+            .is_err());
+        assert!(tool
+            .validate(&serde_json::json!({ "file_path": "a", "old_string": "x" }))
+            .is_err());
+    }
+
+    #[test]
+    fn test_line_diff() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n";
+        assert_eq!(
+            line_diff(old, new),
+            "@@ -2,7 +2,7 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8"
+        );
+        assert_eq!(line_diff("a\n", "a\nb\n"), "@@ -1,1 +1,2 @@\n a\n+b");
     }
 }
@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+
+use super::bash_policy::wildcard_match;
+
+/// Variables commands get by default: enough for shells, locales and the
+/// usual toolchains to work, without tokens or keys from the environment.
+pub const SAFE_VARS: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "TERM",
+    "COLORTERM",
+    "LANG",
+    "LANGUAGE",
+    "LC_*",
+    "TZ",
+    "TMPDIR",
+    "TMP",
+    "TEMP",
+    "XDG_*",
+    "EDITOR",
+    "VISUAL",
+    "PAGER",
+    "CARGO_HOME",
+    "RUSTUP_HOME",
+    "GOPATH",
+    "GOROOT",
+    "JAVA_HOME",
+    "NVM_DIR",
+    "PYENV_ROOT",
+    "VIRTUAL_ENV",
+    "CONDA_PREFIX",
+    "NODE_PATH",
+];
+
+/// Which environment variables spawned commands see. Names are matched
+/// against patterns where `*` matches any run of characters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvPassthrough {
+    /// Only variables matching one of these
+    Allow(Vec<String>),
+    /// Every variable except ones matching these
+    Deny(Vec<String>),
+}
+
+impl Default for EnvPassthrough {
+    fn default() -> Self {
+        Self::Allow(SAFE_VARS.iter().map(|name| name.to_string()).collect())
+    }
+}
+
+impl EnvPassthrough {
+    pub fn passes(&self, name: &str) -> bool {
+        let matches =
+            |patterns: &[String]| patterns.iter().any(|pattern| wildcard_match(pattern, name));
+        match self {
+            Self::Allow(patterns) => matches(patterns),
+            Self::Deny(patterns) => !matches(patterns),
+        }
+    }
+
+    /// The variables of `vars` that pass. Names that aren't valid UTF-8
+    /// can't be matched, so only a denylist lets them through.
+    pub fn filter(
+        &self,
+        vars: impl IntoIterator<Item = (OsString, OsString)>,
+    ) -> Vec<(OsString, OsString)> {
+        vars.into_iter()
+            .filter(|(name, _)| match name.to_str() {
+                Some(name) => self.passes(name),
+                None => matches!(self, Self::Deny(_)),
+            })
+            .collect()
+    }
+
+    /// Replaces the environment `command` would inherit with the variables
+    /// of ours that pass.
+    pub fn apply(&self, command: &mut tokio::process::Command) {
+        command.env_clear();
+        command.envs(self.filter(std::env::vars_os()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(names: &[&str]) -> Vec<(OsString, OsString)> {
+        names
+            .iter()
+            .map(|name| (OsString::from(name), OsString::from("value")))
+            .collect()
+    }
+
+    fn names(vars: Vec<(OsString, OsString)>) -> Vec<String> {
+        vars.into_iter()
+            .map(|(name, _)| name.into_string().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_default_keeps_secrets_out() {
+        let env = EnvPassthrough::default();
+        let passed = names(env.filter(vars(&[
+            "PATH",
+            "HOME",
+            "LC_ALL",
+            "XDG_CONFIG_HOME",
+            "ANTHROPIC_API_KEY",
+            "OPENAI_API_KEY",
+            "GITHUB_TOKEN",
+            "AWS_SECRET_ACCESS_KEY",
+        ])));
+        assert_eq!(passed, ["PATH", "HOME", "LC_ALL", "XDG_CONFIG_HOME"]);
+    }
+
+    #[test]
+    fn test_allow_and_deny_patterns() {
+        let deny = EnvPassthrough::Deny(vec!["*_KEY".to_string(), "*TOKEN*".to_string()]);
+        assert!(deny.passes("PATH"));
+        assert!(deny.passes("KEYBOARD"));
+        assert!(!deny.passes("OPENAI_API_KEY"));
+        assert!(!deny.passes("GH_TOKEN_FILE"));
+
+        let allow = EnvPassthrough::Allow(vec!["PATH".to_string()]);
+        assert!(allow.passes("PATH"));
+        assert!(!allow.passes("PATHEXT"));
+        assert!(!allow.passes("HOME"));
+    }
+
+    #[test]
+    fn test_parses_from_policy_json() {
+        let env: EnvPassthrough = serde_json::from_str(r#"{"deny": ["*_KEY"]}"#).unwrap();
+        assert_eq!(env, EnvPassthrough::Deny(vec!["*_KEY".to_string()]));
+        let env: EnvPassthrough = serde_json::from_str(r#"{"allow": ["PATH"]}"#).unwrap();
+        assert_eq!(env, EnvPassthrough::Allow(vec!["PATH".to_string()]));
+    }
+}
@@ -4,7 +4,6 @@ use crate::tools::{
 };
 use async_trait::async_trait;
 use serde_json::Value;
-use std::path::Path;
 
 pub struct GlobTool;
 
@@ -41,14 +40,14 @@ impl ToolHandler for GlobTool {
         validate_required(params, &["pattern"])
     }
 
-    async fn execute(&self, params: Value, _ctx: &ToolContext) -> Result<ToolResult, ToolError> {
+    async fn execute(&self, params: Value, ctx: &ToolContext) -> Result<ToolResult, ToolError> {
         let pattern = get_string_param(&params, "pattern")
             .ok_or_else(|| ToolError::Validation("pattern is required".to_string()))?;
 
         let base_path = get_string_param(&params, "path")
             .unwrap_or_else(|| ".".to_string());
 
-        let pattern_path = Path::new(&base_path).join(&pattern);
+        let pattern_path = ctx.resolve_path(&base_path).join(&pattern);
         let pattern_str = pattern_path
             .to_str()
             .ok_or_else(|| ToolError::Execution("Invalid path encoding".to_string()))?;
@@ -111,7 +111,7 @@ impl ToolHandler for ListTool {
         validate_required(params, &["path"])
     }
 
-    async fn execute(&self, params: Value, _ctx: &ToolContext) -> Result<ToolResult, ToolError> {
+    async fn execute(&self, params: Value, ctx: &ToolContext) -> Result<ToolResult, ToolError> {
         let path_str = get_string_param(&params, "path")
             .ok_or_else(|| ToolError::Validation("path is required".to_string()))?;
 
@@ -125,7 +125,7 @@ impl ToolHandler for ListTool {
             })
             .unwrap_or_default();
 
-        let path = Path::new(&path_str);
+        let path = &ctx.resolve_path(&path_str);
         
         if !path.exists() {
             return Err(ToolError::NotFound(format!("Directory not found: {}", path_str)));
@@ -4,7 +4,6 @@ use crate::tools::{
 };
 use async_trait::async_trait;
 use serde_json::Value;
-use std::path::Path;
 
 const MAX_FILE_SIZE: u64 = 50 * 1024 * 1024; // 50MB
 const BINARY_CHECK_SIZE: usize = 8192; // 8KB
@@ -55,7 +54,7 @@ impl ToolHandler for ReadTool {
         validate_required(params, &["file_path"])
     }
 
-    async fn execute(&self, params: Value, _ctx: &ToolContext) -> Result<ToolResult, ToolError> {
+    async fn execute(&self, params: Value, ctx: &ToolContext) -> Result<ToolResult, ToolError> {
         let file_path = get_string_param(&params, "file_path")
             .ok_or_else(|| ToolError::Validation("file_path is required".to_string()))?;
 
@@ -67,7 +66,7 @@ impl ToolHandler for ReadTool {
             .map(|v| if v <= 0 { DEFAULT_LIMIT } else { v as usize })
             .unwrap_or(DEFAULT_LIMIT);
 
-        let path = Path::new(&file_path);
+        let path = &ctx.resolve_path(&file_path);
 
         if !path.exists() {
             return Err(ToolError::NotFound(format!("File not found: {}", file_path)));
@@ -92,6 +91,7 @@ impl ToolHandler for ReadTool {
 
         let content = std::fs::read(path)
             .map_err(|e| ToolError::Execution(format!("Failed to read file: {}", e)))?;
+        ctx.versions.record(path, &content);
 
         if Self::is_binary(&content) {
             return Ok(ToolResult::new(
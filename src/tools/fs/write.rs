@@ -15,7 +15,7 @@ impl WriteTool {
         Self
     }
 
-    fn is_blocked(path: &Path) -> bool {
+    pub(crate) fn is_blocked(path: &Path) -> bool {
         if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
             BLOCKED_FILES.contains(&file_name)
         } else {
@@ -51,14 +51,14 @@ impl ToolHandler for WriteTool {
         validate_required(params, &["file_path", "content"])
     }
 
-    async fn execute(&self, params: Value, _ctx: &ToolContext) -> Result<ToolResult, ToolError> {
+    async fn execute(&self, params: Value, ctx: &ToolContext) -> Result<ToolResult, ToolError> {
         let file_path = get_string_param(&params, "file_path")
             .ok_or_else(|| ToolError::Validation("file_path is required".to_string()))?;
 
         let content = get_string_param(&params, "content")
             .ok_or_else(|| ToolError::Validation("content is required".to_string()))?;
 
-        let path = Path::new(&file_path);
+        let path = &ctx.resolve_path(&file_path);
 
         if Self::is_blocked(path) {
             return Err(ToolError::Permission(format!(
@@ -67,6 +67,9 @@ impl ToolHandler for WriteTool {
             )));
         }
 
+        let current = std::fs::read(path).ok();
+        ctx.versions.check(path, &file_path, current.as_deref())?;
+
         if let Some(parent) = path.parent() {
             if !parent.exists() {
                 std::fs::create_dir_all(parent)
@@ -76,11 +79,12 @@ impl ToolHandler for WriteTool {
 
         let temp_path = path.with_extension("tmp");
         
-        std::fs::write(&temp_path, content)
+        std::fs::write(&temp_path, &content)
             .map_err(|e| ToolError::Execution(format!("Failed to write temp file: {}", e)))?;
 
         std::fs::rename(&temp_path, path)
             .map_err(|e| ToolError::Execution(format!("Failed to rename file: {}", e)))?;
+        ctx.versions.record(path, content.as_bytes());
 
         let is_new = !path.exists();
         
@@ -1,6 +1,6 @@
 use crate::tools::{
     fs::{GlobTool, ListTool, ReadTool, WriteTool},
-    BashTool, EditTool, ToolRegistry,
+    BashTool, EditTool, PatchTool, TodoWriteTool, ToolRegistry,
 };
 use std::sync::Arc;
 
@@ -13,6 +13,8 @@ pub async fn initialize_tool_registry() -> ToolRegistry {
     registry.register(Arc::new(WriteTool::new())).await;
     registry.register(Arc::new(BashTool::new())).await;
     registry.register(Arc::new(EditTool::new())).await;
+    registry.register(Arc::new(PatchTool::new())).await;
+    registry.register(Arc::new(TodoWriteTool::new())).await;
 
     registry
 }
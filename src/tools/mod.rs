@@ -2,20 +2,30 @@ use async_trait::async_trait;
 use serde_json::Value;
 
 pub mod bash;
+pub mod bash_policy;
 pub mod aisdk_bridge;
+pub mod cache;
 pub mod context;
 pub mod edit;
+pub mod env_passthrough;
 pub mod fs;
 pub mod init;
+pub mod patch;
 pub mod registry;
+pub mod todo;
 pub mod types;
+pub mod versions;
 
 pub use bash::BashTool;
+pub use cache::ToolCache;
 pub use context::ToolContext;
 pub use edit::EditTool;
 pub use init::initialize_tool_registry;
+pub use patch::PatchTool;
 pub use registry::ToolRegistry;
+pub use todo::TodoWriteTool;
 pub use types::{ParameterSchema, ParameterType, Tool, ToolError, ToolId, ToolResult};
+pub use versions::FileVersions;
 
 #[async_trait]
 pub trait ToolHandler: Send + Sync {
@@ -0,0 +1,606 @@
+use crate::tools::{
+    fs::WriteTool, get_string_param, validate_required, ParameterSchema, ParameterType, Tool,
+    ToolContext, ToolError, ToolHandler, ToolResult,
+};
+use async_trait::async_trait;
+use diffy::{Line, Patch};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+const DEV_NULL: &str = "/dev/null";
+/// The agent that may look at patches but not apply them
+const PLAN_AGENT: &str = "Plan";
+
+pub struct PatchTool;
+
+/// What applying a patch does to one file.
+#[derive(Debug, PartialEq)]
+enum Change {
+    Create(String),
+    Modify(String),
+    /// Moved from the given path, with the new content
+    Rename(PathBuf, String),
+    Delete,
+}
+
+impl Change {
+    fn content(&self) -> Option<&str> {
+        match self {
+            Change::Create(content) | Change::Modify(content) | Change::Rename(_, content) => {
+                Some(content)
+            }
+            Change::Delete => None,
+        }
+    }
+}
+
+/// One file's changes, ready to be written.
+struct FileChange {
+    display: String,
+    path: PathBuf,
+    change: Change,
+    added: usize,
+    removed: usize,
+}
+
+impl PatchTool {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Splits a (possibly multi-file) unified diff into one section per
+    /// file, starting at its `---`/`+++` header. Preamble lines such as
+    /// `diff --git` and `index` are dropped.
+    fn split_files(diff: &str) -> Vec<String> {
+        let lines: Vec<&str> = diff.lines().collect();
+        let mut sections: Vec<String> = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            let file_header = line.starts_with("--- ")
+                && lines
+                    .get(i + 1)
+                    .is_some_and(|next| next.starts_with("+++ "));
+            if file_header {
+                sections.push(String::new());
+            }
+            let patch_line = line.is_empty() || line.starts_with([' ', '+', '-', '@', '\\']);
+            if let (Some(section), true) = (sections.last_mut(), patch_line) {
+                section.push_str(line);
+                section.push('\n');
+            }
+        }
+        for section in &mut sections {
+            // Blank lines between files aren't context lines
+            let trimmed = section.trim_end_matches('\n').len();
+            section.truncate(trimmed);
+            section.push('\n');
+        }
+        sections
+    }
+
+    /// `a/src/main.rs` -> `src/main.rs`, like `git apply`. `None` for
+    /// `/dev/null`.
+    fn file_name(header: Option<&str>) -> Option<String> {
+        let name = header?.split('\t').next()?.trim();
+        if name == DEV_NULL || name.is_empty() {
+            return None;
+        }
+        let name = name
+            .strip_prefix("a/")
+            .or_else(|| name.strip_prefix("b/"))
+            .unwrap_or(name);
+        Some(name.to_string())
+    }
+
+    /// The hunks of `section` that don't apply to `base` on their own, as
+    /// "hunk N (@@ ... @@)".
+    fn failed_hunks(section: &str, base: &str) -> Vec<String> {
+        let header: String = section
+            .lines()
+            .take_while(|line| !line.starts_with("@@"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let mut hunks: Vec<String> = Vec::new();
+        for line in section.lines().skip_while(|line| !line.starts_with("@@")) {
+            if line.starts_with("@@") {
+                hunks.push(String::new());
+            }
+            if let Some(hunk) = hunks.last_mut() {
+                hunk.push_str(line);
+                hunk.push('\n');
+            }
+        }
+
+        hunks
+            .iter()
+            .enumerate()
+            .filter(|(_, hunk)| {
+                let text = format!("{}{}", header, hunk);
+                !Patch::from_str(&text).is_ok_and(|patch| diffy::apply(base, &patch).is_ok())
+            })
+            .map(|(i, hunk)| format!("hunk {} ({})", i + 1, hunk.lines().next().unwrap_or("")))
+            .collect()
+    }
+
+    /// Works out every file's new content without touching the disk, so a
+    /// patch with any failing hunk changes nothing.
+    fn plan(diff: &str, ctx: &ToolContext) -> Result<Vec<FileChange>, ToolError> {
+        let sections = Self::split_files(diff);
+        if sections.is_empty() {
+            return Err(ToolError::Validation(
+                "No file headers found; the patch needs --- and +++ lines".to_string(),
+            ));
+        }
+
+        let mut changes = Vec::new();
+        let mut rejects = Vec::new();
+        let mut touched: HashSet<PathBuf> = HashSet::new();
+        for section in &sections {
+            let patch = Patch::from_str(section)
+                .map_err(|e| ToolError::Validation(format!("Invalid patch: {}", e)))?;
+            let old = Self::file_name(patch.original());
+            let new = Self::file_name(patch.modified());
+            let Some(name) = new.clone().or_else(|| old.clone()) else {
+                return Err(ToolError::Validation("Patch has no file name".to_string()));
+            };
+            // `--- a/old` / `+++ b/new` moves the file
+            let from = match (&old, &new) {
+                (Some(old), Some(new)) if old != new => Some(old.clone()),
+                _ => None,
+            };
+            let source_name = from.clone().unwrap_or_else(|| name.clone());
+            let display = match &from {
+                Some(from) => format!("{} -> {}", from, name),
+                None => name.clone(),
+            };
+            let path = ctx.resolve_path(&name);
+            let source = ctx.resolve_path(&source_name);
+            for (path, name) in [(&path, &name), (&source, &source_name)] {
+                if WriteTool::is_blocked(path) {
+                    return Err(ToolError::Permission(format!(
+                        "Writing to {} is blocked for security reasons",
+                        name
+                    )));
+                }
+            }
+
+            // Each section is applied to the file on disk, so a second one
+            // for the same file would undo the first
+            let repeated = [&path, &source]
+                .into_iter()
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .any(|path| !touched.insert(path.clone()));
+            if repeated {
+                rejects.push(format!(
+                    "{}: patched by more than one section; combine them into one",
+                    display
+                ));
+                continue;
+            }
+
+            let base = if old.is_none() {
+                if path.exists() {
+                    rejects.push(format!("{}: already exists", display));
+                    continue;
+                }
+                String::new()
+            } else {
+                if from.is_some() && path.exists() {
+                    rejects.push(format!("{}: {} already exists", display, name));
+                    continue;
+                }
+                match std::fs::read_to_string(&source) {
+                    Ok(base) => base,
+                    Err(e) => {
+                        rejects.push(format!("{}: {}", display, e));
+                        continue;
+                    }
+                }
+            };
+            if old.is_some() {
+                ctx.versions
+                    .check(&source, &source_name, Some(base.as_bytes()))?;
+            }
+
+            let patched = match diffy::apply(&base, &patch) {
+                Ok(patched) => patched,
+                Err(e) => {
+                    let mut failed = Self::failed_hunks(section, &base);
+                    if failed.is_empty() {
+                        failed.push(e.to_string());
+                    }
+                    rejects.push(format!("{}: {} doesn't apply", display, failed.join(", ")));
+                    continue;
+                }
+            };
+
+            let (added, removed) = patch.hunks().iter().flat_map(|hunk| hunk.lines()).fold(
+                (0, 0),
+                |(added, removed), line| match line {
+                    Line::Insert(_) => (added + 1, removed),
+                    Line::Delete(_) => (added, removed + 1),
+                    Line::Context(_) => (added, removed),
+                },
+            );
+            let change = match (&old, &new) {
+                (None, _) => Change::Create(patched),
+                (_, None) if patched.is_empty() => Change::Delete,
+                (_, None) => {
+                    rejects.push(format!(
+                        "{}: deletion leaves lines behind; the file has more than the patch removes",
+                        display
+                    ));
+                    continue;
+                }
+                _ => match from {
+                    Some(_) => Change::Rename(source, patched),
+                    None => Change::Modify(patched),
+                },
+            };
+            changes.push(FileChange {
+                display,
+                path,
+                change,
+                added,
+                removed,
+            });
+        }
+
+        if !rejects.is_empty() {
+            return Err(ToolError::Execution(format!(
+                "Patch not applied, nothing was changed:\n{}",
+                rejects.join("\n")
+            )));
+        }
+        Ok(changes)
+    }
+
+    /// Where a file's new content is written before it's moved into place.
+    fn temp_path(path: &Path) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".crabcode-patch");
+        path.with_file_name(name)
+    }
+
+    fn stage(path: &Path, content: &str) -> std::io::Result<PathBuf> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let temp = Self::temp_path(path);
+        std::fs::write(&temp, content)?;
+        Ok(temp)
+    }
+
+    /// Writes all new contents to temporary files first and only moves them
+    /// into place once every one was written, so a failed write (a full disk,
+    /// a directory in the way) leaves the files as they were.
+    fn write(changes: &[FileChange], ctx: &ToolContext) -> Result<(), ToolError> {
+        let mut staged: Vec<(PathBuf, &FileChange)> = Vec::new();
+        for change in changes {
+            let Some(content) = change.change.content() else {
+                continue;
+            };
+            match Self::stage(&change.path, content) {
+                Ok(temp) => staged.push((temp, change)),
+                Err(e) => {
+                    for (temp, _) in &staged {
+                        let _ = std::fs::remove_file(temp);
+                    }
+                    let _ = std::fs::remove_file(Self::temp_path(&change.path));
+                    return Err(ToolError::Execution(format!(
+                        "Failed to write {}: {}; nothing was changed",
+                        change.display, e
+                    )));
+                }
+            }
+        }
+
+        let failed = |change: &FileChange, e: std::io::Error| {
+            ToolError::Execution(format!("Failed to write {}: {}", change.display, e))
+        };
+        for (temp, change) in staged {
+            std::fs::rename(&temp, &change.path).map_err(|e| failed(change, e))?;
+            if let Some(content) = change.change.content() {
+                ctx.versions.record(&change.path, content.as_bytes());
+            }
+        }
+        for change in changes {
+            match &change.change {
+                Change::Delete => {
+                    std::fs::remove_file(&change.path).map_err(|e| failed(change, e))?
+                }
+                Change::Rename(from, _) => {
+                    std::fs::remove_file(from).map_err(|e| failed(change, e))?
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolHandler for PatchTool {
+    fn definition(&self) -> Tool {
+        Tool {
+            id: "apply_patch".to_string(),
+            description: "Apply a unified diff (as produced by `git diff` or `diff -u`) to one or more files. Use /dev/null as the old name to create a file and as the new name to delete one; different old and new names rename the file. Each file may appear only once. If any hunk doesn't apply, nothing is changed and the failing hunks are listed. In Plan mode the patch is only checked, not applied.".to_string(),
+            parameters: vec![ParameterSchema {
+                name: "patch".to_string(),
+                description: "The unified diff, with ---/+++ file headers and @@ hunks".to_string(),
+                required: true,
+                param_type: ParameterType::String,
+            }],
+        }
+    }
+
+    fn validate(&self, params: &Value) -> Result<(), ToolError> {
+        validate_required(params, &["patch"])
+    }
+
+    async fn execute(&self, params: Value, ctx: &ToolContext) -> Result<ToolResult, ToolError> {
+        let diff = get_string_param(&params, "patch")
+            .ok_or_else(|| ToolError::Validation("patch is required".to_string()))?;
+
+        let changes = Self::plan(&diff, ctx)?;
+        let preview = ctx.agent == PLAN_AGENT;
+        if !preview {
+            Self::write(&changes, ctx)?;
+        }
+
+        let mut output = if preview {
+            format!(
+                "Patch applies cleanly to {} file(s); not applied in Plan mode:",
+                changes.len()
+            )
+        } else {
+            format!("Applied patch to {} file(s):", changes.len())
+        };
+        for change in &changes {
+            let kind = match change.change {
+                Change::Create(_) => "A",
+                Change::Modify(_) => "M",
+                Change::Rename(..) => "R",
+                Change::Delete => "D",
+            };
+            output.push_str(&format!(
+                "\n{} {} (+{} -{})",
+                kind, change.display, change.added, change.removed
+            ));
+        }
+
+        let files: Vec<&str> = changes.iter().map(|c| c.display.as_str()).collect();
+        Ok(ToolResult::new("Apply patch", output)
+            .with_metadata("files", serde_json::json!(files))
+            .with_metadata("preview", serde_json::json!(preview)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool_context(dir: &std::path::Path, agent: &str) -> ToolContext {
+        let (_abort_tx, abort_rx) = tokio::sync::watch::channel(false);
+        ToolContext::new("session", "message", agent, abort_rx).with_cwd(dir)
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("crabcode-patch-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    async fn apply(
+        dir: &std::path::Path,
+        agent: &str,
+        patch: &str,
+    ) -> Result<ToolResult, ToolError> {
+        PatchTool::new()
+            .execute(
+                serde_json::json!({ "patch": patch }),
+                &tool_context(dir, agent),
+            )
+            .await
+    }
+
+    const MULTI_FILE: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+ fn a() {}
+-fn b() {}
++fn b() -> u8 { 0 }
+ fn c() {}
+diff --git a/notes.md b/notes.md
+new file mode 100644
+--- /dev/null
++++ b/notes.md
+@@ -0,0 +1,2 @@
++# Notes
++hello
+diff --git a/old.txt b/old.txt
+deleted file mode 100644
+--- a/old.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+";
+
+    #[tokio::test]
+    async fn test_applies_multi_file_patch() {
+        let dir = temp_dir("multi");
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "fn a() {}\nfn b() {}\nfn c() {}\n").unwrap();
+        std::fs::write(dir.join("old.txt"), "bye\n").unwrap();
+
+        let result = apply(&dir, "Build", MULTI_FILE).await.unwrap();
+        assert_eq!(
+            result.output,
+            "Applied patch to 3 file(s):\nM src/lib.rs (+1 -1)\nA notes.md (+2 -0)\nD old.txt (+0 -1)"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("src/lib.rs")).unwrap(),
+            "fn a() {}\nfn b() -> u8 { 0 }\nfn c() {}\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("notes.md")).unwrap(),
+            "# Notes\nhello\n"
+        );
+        assert!(!dir.join("old.txt").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_failing_hunk_changes_nothing() {
+        let dir = temp_dir("reject");
+        let original = "one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\n";
+        std::fs::write(dir.join("list.txt"), original).unwrap();
+        let patch = "\
+--- a/list.txt
++++ b/list.txt
+@@ -1,2 +1,2 @@
+-one
++ONE
+ two
+@@ -7,2 +7,2 @@
+ seven
+-nine
++NINE
+";
+
+        let Err(ToolError::Execution(message)) = apply(&dir, "Build", patch).await else {
+            panic!("expected the patch to be rejected");
+        };
+        assert!(message.contains("list.txt: hunk 2 (@@ -7,2 +7,2 @@) doesn't apply"));
+        assert!(!message.contains("hunk 1"));
+        assert_eq!(
+            std::fs::read_to_string(dir.join("list.txt")).unwrap(),
+            original
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_plan_mode_only_previews() {
+        let dir = temp_dir("plan");
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "fn a() {}\nfn b() {}\nfn c() {}\n").unwrap();
+        std::fs::write(dir.join("old.txt"), "bye\n").unwrap();
+
+        let result = apply(&dir, PLAN_AGENT, MULTI_FILE).await.unwrap();
+        assert!(result
+            .output
+            .starts_with("Patch applies cleanly to 3 file(s)"));
+        assert!(!dir.join("notes.md").exists());
+        assert!(dir.join("old.txt").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_patches_are_refused() {
+        let dir = temp_dir("invalid");
+        assert!(matches!(
+            apply(&dir, "Build", "just some text").await,
+            Err(ToolError::Validation(_))
+        ));
+        // Hunk header counts don't match the lines
+        let patch = "--- a/x\n+++ b/x\n@@ -1,5 +1,5 @@\n-a\n+b\n";
+        assert!(matches!(
+            apply(&dir, "Build", patch).await,
+            Err(ToolError::Validation(_))
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_rename_moves_the_file() {
+        let dir = temp_dir("rename");
+        std::fs::write(dir.join("old.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+        let patch = "\
+diff --git a/old.rs b/new.rs
+similarity index 50%
+rename from old.rs
+rename to new.rs
+--- a/old.rs
++++ b/new.rs
+@@ -1,2 +1,2 @@
+ fn a() {}
+-fn b() {}
++fn c() {}
+";
+
+        let result = apply(&dir, "Build", patch).await.unwrap();
+        assert_eq!(
+            result.output,
+            "Applied patch to 1 file(s):\nR old.rs -> new.rs (+1 -1)"
+        );
+        assert!(!dir.join("old.rs").exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("new.rs")).unwrap(),
+            "fn a() {}\nfn c() {}\n"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_file_is_refused() {
+        let dir = temp_dir("repeated");
+        let original = "one\ntwo\nthree\n";
+        std::fs::write(dir.join("list.txt"), original).unwrap();
+        let patch = "\
+--- a/list.txt
++++ b/list.txt
+@@ -1,1 +1,1 @@
+-one
++ONE
+--- a/list.txt
++++ b/list.txt
+@@ -3,1 +3,1 @@
+-three
++THREE
+";
+
+        let Err(ToolError::Execution(message)) = apply(&dir, "Build", patch).await else {
+            panic!("expected the patch to be rejected");
+        };
+        assert!(message.contains("list.txt: patched by more than one section"));
+        assert_eq!(
+            std::fs::read_to_string(dir.join("list.txt")).unwrap(),
+            original
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_failed_write_changes_nothing() {
+        let dir = temp_dir("write-fails");
+        std::fs::write(dir.join("a.txt"), "one\n").unwrap();
+        // A file where the new file's directory would have to go
+        std::fs::write(dir.join("blocker"), "").unwrap();
+        let patch = "\
+--- a/a.txt
++++ b/a.txt
+@@ -1 +1 @@
+-one
++ONE
+--- /dev/null
++++ b/blocker/b.txt
+@@ -0,0 +1 @@
++new
+";
+
+        let Err(ToolError::Execution(message)) = apply(&dir, "Build", patch).await else {
+            panic!("expected the write to fail");
+        };
+        assert!(message.contains("nothing was changed"), "{}", message);
+        assert_eq!(std::fs::read_to_string(dir.join("a.txt")).unwrap(), "one\n");
+        assert!(!PatchTool::temp_path(&dir.join("a.txt")).exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::tools::{
+    validate_required, ParameterSchema, ParameterType, Tool, ToolContext, ToolError, ToolHandler,
+    ToolResult,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoStatus {
+    Pending,
+    InProgress,
+    Completed,
+    Cancelled,
+}
+
+impl TodoStatus {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            TodoStatus::Pending => "☐",
+            TodoStatus::InProgress => "▸",
+            TodoStatus::Completed => "✓",
+            TodoStatus::Cancelled => "✗",
+        }
+    }
+}
+
+/// One step of the agent's plan.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TodoItem {
+    pub content: String,
+    pub status: TodoStatus,
+}
+
+/// Reads the `todos` list of a `todowrite` call.
+pub fn parse_todos(params: &Value) -> Result<Vec<TodoItem>, ToolError> {
+    let todos = params
+        .get("todos")
+        .ok_or_else(|| ToolError::Validation("todos is required".to_string()))?;
+    let todos: Vec<TodoItem> = serde_json::from_value(todos.clone()).map_err(|e| {
+        ToolError::Validation(format!(
+            "todos must be a list of {{content, status}} with status pending, in_progress, completed or cancelled: {}",
+            e
+        ))
+    })?;
+    if let Some(i) = todos.iter().position(|t| t.content.trim().is_empty()) {
+        return Err(ToolError::Validation(format!(
+            "Todo {} has no content",
+            i + 1
+        )));
+    }
+    Ok(todos)
+}
+
+/// Steps done and steps still counted (cancelled ones aren't).
+pub fn todo_progress(todos: &[TodoItem]) -> (usize, usize) {
+    let done = todos
+        .iter()
+        .filter(|t| t.status == TodoStatus::Completed)
+        .count();
+    let total = todos
+        .iter()
+        .filter(|t| t.status != TodoStatus::Cancelled)
+        .count();
+    (done, total)
+}
+
+pub struct TodoWriteTool;
+
+impl TodoWriteTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl ToolHandler for TodoWriteTool {
+    fn definition(&self) -> Tool {
+        Tool {
+            id: "todowrite".to_string(),
+            description: "Create or update the plan for a multi-step task. Send the whole list every time; it replaces the previous one. Keep exactly one step in_progress and mark steps completed as soon as they're done.".to_string(),
+            parameters: vec![ParameterSchema {
+                name: "todos".to_string(),
+                description: "The steps in order, each with content and a status of pending, in_progress, completed or cancelled".to_string(),
+                required: true,
+                param_type: ParameterType::Array(Box::new(ParameterType::Object(HashMap::from([
+                    ("content".to_string(), ParameterType::String),
+                    ("status".to_string(), ParameterType::String),
+                ])))),
+            }],
+        }
+    }
+
+    fn validate(&self, params: &Value) -> Result<(), ToolError> {
+        validate_required(params, &["todos"])?;
+        parse_todos(params).map(|_| ())
+    }
+
+    async fn execute(&self, params: Value, _ctx: &ToolContext) -> Result<ToolResult, ToolError> {
+        let todos = parse_todos(&params)?;
+        let (done, total) = todo_progress(&todos);
+
+        let mut output = format!("Plan updated, {}/{} done:", done, total);
+        for todo in &todos {
+            output.push_str(&format!("\n{} {}", todo.status.symbol(), todo.content));
+        }
+
+        Ok(ToolResult::new("Plan", output).with_metadata("todos", serde_json::json!(todos)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_todowrite() {
+        let (_abort_tx, abort_rx) = tokio::sync::watch::channel(false);
+        let ctx = ToolContext::new("session", "message", "test", abort_rx);
+        let params = serde_json::json!({ "todos": [
+            { "content": "Read the loader", "status": "completed" },
+            { "content": "Add the flag", "status": "in_progress" },
+            { "content": "Old approach", "status": "cancelled" },
+            { "content": "Write tests", "status": "pending" },
+        ]});
+
+        let tool = TodoWriteTool::new();
+        tool.validate(&params).unwrap();
+        let result = tool.execute(params, &ctx).await.unwrap();
+        assert_eq!(
+            result.output,
+            "Plan updated, 1/3 done:\n✓ Read the loader\n▸ Add the flag\n✗ Old approach\n☐ Write tests"
+        );
+        assert_eq!(result.metadata["todos"][1]["status"], "in_progress");
+
+        let bad = serde_json::json!({ "todos": [{ "content": "x", "status": "doing" }] });
+        assert!(matches!(tool.validate(&bad), Err(ToolError::Validation(_))));
+        let empty = serde_json::json!({ "todos": [{ "content": " ", "status": "pending" }] });
+        assert!(matches!(
+            tool.validate(&empty),
+            Err(ToolError::Validation(_))
+        ));
+    }
+}
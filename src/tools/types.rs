@@ -45,6 +45,8 @@ pub enum ToolError {
     Permission(String),
     #[error("Not found: {0}")]
     NotFound(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
 }
 
 impl Tool {
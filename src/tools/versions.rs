@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::ToolError;
+
+/// Content hashes of the files read this turn, so `edit` and `write` can
+/// tell when a file changed on disk after the agent last saw it. Clones
+/// share the same entries.
+#[derive(Clone, Default)]
+pub struct FileVersions {
+    hashes: Arc<Mutex<HashMap<PathBuf, u64>>>,
+}
+
+impl FileVersions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remembers `content` as the version of `path` the agent has seen.
+    pub fn record(&self, path: &Path, content: &[u8]) {
+        self.hashes.lock().unwrap().insert(key(path), hash(content));
+    }
+
+    /// Fails with a conflict when `path` was seen this turn and what's on
+    /// disk now (`current`, `None` if it's gone) differs. Files the agent
+    /// never read are left alone.
+    pub fn check(
+        &self,
+        path: &Path,
+        display: &str,
+        current: Option<&[u8]>,
+    ) -> Result<(), ToolError> {
+        let Some(seen) = self.hashes.lock().unwrap().get(&key(path)).copied() else {
+            return Ok(());
+        };
+        match current {
+            Some(content) if hash(content) == seen => Ok(()),
+            Some(_) => Err(ToolError::Conflict(format!(
+                "{} changed on disk since it was read; read it again before changing it",
+                display
+            ))),
+            None => Err(ToolError::Conflict(format!(
+                "{} was deleted since it was read",
+                display
+            ))),
+        }
+    }
+}
+
+/// `./a.rs` and `a.rs` are the same file, also after it was deleted.
+fn key(path: &Path) -> PathBuf {
+    if let Ok(path) = std::fs::canonicalize(path) {
+        return path;
+    }
+    match (path.parent().map(std::fs::canonicalize), path.file_name()) {
+        (Some(Ok(parent)), Some(name)) => parent.join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn hash(content: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::fs::{ReadTool, WriteTool};
+    use crate::tools::{EditTool, ToolContext, ToolHandler};
+
+    fn tool_context(dir: &Path) -> ToolContext {
+        let (_abort_tx, abort_rx) = tokio::sync::watch::channel(false);
+        ToolContext::new("session", "message", "test", abort_rx).with_cwd(dir)
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("crabcode-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_edit_after_external_change_conflicts() {
+        let dir = temp_dir("versions-edit");
+        std::fs::write(dir.join("main.rs"), "fn main() {}\n").unwrap();
+        let ctx = tool_context(&dir);
+
+        ReadTool::new()
+            .execute(serde_json::json!({ "file_path": "main.rs" }), &ctx)
+            .await
+            .unwrap();
+        std::fs::write(dir.join("main.rs"), "fn main() { user() }\n").unwrap();
+
+        let edit = serde_json::json!({
+            "file_path": "./main.rs",
+            "old_string": "fn main()",
+            "new_string": "pub fn main()",
+        });
+        let result = EditTool::new().execute(edit.clone(), &ctx).await;
+        assert!(matches!(result, Err(ToolError::Conflict(_))));
+        assert_eq!(
+            std::fs::read_to_string(dir.join("main.rs")).unwrap(),
+            "fn main() { user() }\n"
+        );
+
+        // Reading again picks up the user's version
+        ReadTool::new()
+            .execute(serde_json::json!({ "file_path": "main.rs" }), &ctx)
+            .await
+            .unwrap();
+        EditTool::new().execute(edit, &ctx).await.unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_own_changes_are_not_conflicts() {
+        let dir = temp_dir("versions-write");
+        std::fs::write(dir.join("notes.txt"), "one").unwrap();
+        let ctx = tool_context(&dir);
+        let write =
+            |content: &str| serde_json::json!({ "file_path": "notes.txt", "content": content });
+
+        ReadTool::new()
+            .execute(serde_json::json!({ "file_path": "notes.txt" }), &ctx)
+            .await
+            .unwrap();
+        WriteTool::new().execute(write("two"), &ctx).await.unwrap();
+        WriteTool::new()
+            .execute(write("three"), &ctx)
+            .await
+            .unwrap();
+
+        std::fs::remove_file(dir.join("notes.txt")).unwrap();
+        let result = WriteTool::new().execute(write("four"), &ctx).await;
+        assert!(matches!(result, Err(ToolError::Conflict(_))));
+
+        // Never read: written without a check
+        WriteTool::new()
+            .execute(
+                serde_json::json!({ "file_path": "other.txt", "content": "new" }),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,129 @@
+use std::cell::{Cell, RefCell};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Mutex, Once};
+
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::theme::ThemeColors;
+
+thread_local! {
+    /// How many boundaries the current thread is inside
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+    /// What the panic hook saw for the last panic caught by a boundary
+    static CAUGHT: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Last failure written to the log, so a message that fails on every frame
+/// is logged once rather than on each redraw.
+static LAST_LOGGED: Mutex<Option<String>> = Mutex::new(None);
+
+/// Wraps the default panic hook so panics inside a boundary are recorded
+/// for the log instead of being printed over the terminal.
+fn install_hook() {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if DEPTH.with(|depth| depth.get()) > 0 {
+                CAUGHT.with(|caught| *caught.borrow_mut() = Some(info.to_string()));
+            } else {
+                previous(info);
+            }
+        }));
+    });
+}
+
+/// Runs `render` for one component. If it panics, whatever it drew is
+/// cleared, a short placeholder is shown in `area` instead and the panic is
+/// logged. Returns false when the component failed.
+pub fn render_guarded(
+    f: &mut Frame,
+    area: Rect,
+    component: &str,
+    colors: &ThemeColors,
+    render: impl FnOnce(&mut Frame),
+) -> bool {
+    install_hook();
+
+    DEPTH.with(|depth| depth.set(depth.get() + 1));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| render(f)));
+    DEPTH.with(|depth| depth.set(depth.get() - 1));
+
+    if result.is_ok() {
+        return true;
+    }
+
+    let details = CAUGHT
+        .with(|caught| caught.borrow_mut().take())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    let entry = format!("[RENDER] {} failed to render: {}", component, details);
+    let mut last = LAST_LOGGED.lock().unwrap_or_else(|e| e.into_inner());
+    if last.as_deref() != Some(entry.as_str()) {
+        let _ = crate::logging::log(&entry);
+        *last = Some(entry);
+    }
+
+    let area = area.intersection(f.area());
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(Line::styled(
+            format!("⚠ Couldn't render the {}, see app.log", component),
+            Style::default()
+                .fg(colors.error)
+                .add_modifier(Modifier::ITALIC),
+        ))
+        .wrap(Wrap { trim: true }),
+        area,
+    );
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    #[test]
+    fn test_render_guarded_draws_a_placeholder() {
+        let colors = ThemeColors::for_tests();
+        let mut terminal = Terminal::new(TestBackend::new(50, 3)).unwrap();
+
+        let mut ok = true;
+        terminal
+            .draw(|f| {
+                let area = Rect::new(0, 1, 50, 2);
+                f.render_widget(Paragraph::new("above"), Rect::new(0, 0, 50, 1));
+                ok = render_guarded(f, area, "chat", &colors, |f| {
+                    f.render_widget(Paragraph::new("half drawn"), area);
+                    panic!("bad content");
+                });
+            })
+            .unwrap();
+
+        assert!(!ok);
+        let buffer = terminal.backend().buffer();
+        let row = |y: u16| -> String {
+            (0..50)
+                .map(|x| buffer.cell((x, y)).unwrap().symbol())
+                .collect()
+        };
+        // The rest of the frame is kept, the failed component is replaced
+        assert!(row(0).starts_with("above"));
+        assert!(row(1).starts_with("⚠ Couldn't render the chat"));
+
+        let mut drew = false;
+        terminal
+            .draw(|f| {
+                let area = f.area();
+                assert!(render_guarded(f, area, "chat", &colors, |_| drew = true));
+            })
+            .unwrap();
+        assert!(drew);
+    }
+}
@@ -104,8 +104,8 @@ impl ApiKeyInput {
         let dialog_height = area.height.min(DIALOG_HEIGHT);
 
         let dialog_area = Rect {
-            x: (area.width - dialog_width) / 2,
-            y: (area.height - dialog_height) / 2,
+            x: area.width.saturating_sub(dialog_width) / 2,
+            y: area.height.saturating_sub(dialog_height) / 2,
             width: dialog_width,
             height: dialog_height,
         };
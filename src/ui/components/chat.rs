@@ -1,15 +1,22 @@
+use crate::persistence::{Density, ScrollPreferences};
 use crate::session::types::{Message, MessageRole};
 use crate::theme::ThemeColors;
-use crate::ui::markdown::streaming::{render_markdown, SimpleStreamingRenderer};
+use crate::tools::todo::{parse_todos, todo_progress, TodoItem, TodoStatus};
+use crate::ui::boundary::render_guarded;
+use crate::ui::markdown::renderer::MarkdownRenderer;
+use crate::ui::markdown::streaming::SimpleStreamingRenderer;
+use crate::utils::{strip_ansi, truncate_chars};
 use ratatui::{
     crossterm::event::{MouseButton, MouseEvent, MouseEventKind},
     layout::Rect,
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
+    widgets::{Clear, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
     Frame,
 };
 use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use unicode_width::UnicodeWidthStr;
 
 #[derive(Debug, Clone, Default)]
 pub struct Chat {
@@ -32,6 +39,9 @@ pub struct Chat {
     pub autoscroll_enabled: bool,
     /// Track if user has manually scrolled up (away from bottom)
     user_scrolled_up: bool,
+    /// Lines from the bottom that still count as "at the bottom" when
+    /// scrolling down, so autoscroll resumes without hitting the last line
+    pub bottom_threshold: usize,
     /// Last calculated tokens per second value (for throttling display updates)
     cached_tokens_per_sec: Option<f64>,
     /// Last time tokens per second was calculated (for throttling updates)
@@ -40,11 +50,118 @@ pub struct Chat {
     streaming_renderer: Option<SimpleStreamingRenderer>,
     /// Index of the message currently being rendered by streaming_renderer
     streaming_message_idx: Option<usize>,
+    /// Older messages of the session that are not loaded yet (shown as a
+    /// "load more" row at the top)
+    pub older_messages_available: usize,
+    /// Set when the user scrolls to the top while older messages exist
+    load_more_requested: bool,
+    /// Distance from the bottom to restore after prepending older messages
+    scroll_anchor_from_bottom: Option<usize>,
+    /// Animation frame of the "thinking…" placeholder shown before the first token
+    thinking_frame: usize,
+    /// Content height when the user scrolled up; growth past it is shown as
+    /// "↓ N new lines"
+    unseen_from_height: Option<usize>,
+    /// Clickable area of the "↓ N new lines" indicator, if shown
+    jump_indicator_area: Option<Rect>,
+    /// Mouse wheel step and acceleration settings
+    pub scroll_prefs: ScrollPreferences,
+    /// Time and direction (`true` = up) of the last wheel event
+    last_wheel: Option<(std::time::Instant, bool)>,
+    /// Consecutive rapid wheel events in the same direction
+    wheel_streak: usize,
+    /// Render markdown without colors, marking emphasis with symbols
+    pub plain_markdown: bool,
+    /// Formats message content, streaming or not
+    pub markdown: Box<dyn MarkdownRenderer>,
+    /// Whether blank lines separate messages and their footers
+    pub density: Density,
+    /// Leave out the scrollbar, as in focus mode
+    pub hide_scrollbar: bool,
+    /// Show "Loading session…" instead of messages while a session is read
+    /// in the background
+    pub loading: bool,
+    /// Whether the user expanded (`true`) or collapsed a tool group, keyed
+    /// by the index of the group's first message
+    tool_groups_expanded: HashMap<usize, bool>,
+    /// Content rows of the tool group headers and the first message of the
+    /// group each one toggles
+    tool_group_headers: Vec<(usize, usize)>,
+    /// Session indices of the bookmarked messages, marked in the gutter
+    pub bookmarks: Vec<usize>,
+    /// Content row each loaded message starts on
+    message_rows: Vec<usize>,
+    /// Session index of a message to bring to the top on the next render
+    scroll_target: Option<usize>,
+    /// Copy mode selection as content rows: where it started and the row
+    /// the cursor is on
+    copy_selection: Option<(usize, usize)>,
+    /// Width messages were last laid out at
+    content_width: usize,
+    /// Lines of each loaded message as last rendered, by index
+    rendered: Vec<Option<RenderedMessage>>,
+    /// Messages laid out again because their cached lines were stale
+    renders: usize,
+}
+
+/// A message's lines with what they were rendered from. They're reused
+/// until the message or the key changes.
+#[derive(Debug, Clone)]
+struct RenderedMessage {
+    message: Message,
+    /// Hash of everything else the lines depend on, see `render_keys`
+    key: u64,
+    lines: Vec<Line<'static>>,
 }
 
 // Minimum elapsed time before showing tokens/s (250ms)
 const MIN_TOKENS_PER_SECOND_ELAPSED_MS: u128 = 250;
 
+/// Default for `Chat::bottom_threshold`
+pub const DEFAULT_BOTTOM_THRESHOLD: usize = 2;
+
+/// Runs of consecutive tool calls longer than this start collapsed
+pub const COLLAPSE_TOOL_GROUPS_OVER: usize = 3;
+
+/// Cells in the progress bar above the agent's plan
+const PLAN_BAR_WIDTH: usize = 20;
+
+/// Lines of a running tool's output shown under its row
+const LIVE_OUTPUT_LINES: usize = 5;
+
+/// The last few lines a running tool has printed, dimmed under its row.
+fn live_output_lines(
+    message: &Message,
+    max_width: usize,
+    colors: &ThemeColors,
+) -> Vec<Line<'static>> {
+    let Ok(row) = serde_json::from_str::<JsonValue>(&message.content) else {
+        return Vec::new();
+    };
+    if row.get("status").and_then(|v| v.as_str()) != Some("running") {
+        return Vec::new();
+    }
+    let Some(output) = row.get("live_output").and_then(|v| v.as_str()) else {
+        return Vec::new();
+    };
+
+    let output = strip_ansi(output);
+    let tail: Vec<&str> = output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    let style = Style::default()
+        .fg(colors.text_weak)
+        .add_modifier(Modifier::DIM);
+    tail[tail.len().saturating_sub(LIVE_OUTPUT_LINES)..]
+        .iter()
+        .map(|line| {
+            let line = truncate_chars(line, max_width.saturating_sub(6));
+            Line::from(Span::styled(format!("    {}", line), style))
+        })
+        .collect()
+}
+
 fn now_epoch_ms() -> u64 {
     use std::time::{SystemTime, UNIX_EPOCH};
     SystemTime::now()
@@ -53,6 +170,38 @@ fn now_epoch_ms() -> u64 {
         .as_millis() as u64
 }
 
+/// The steps of a `todowrite` tool row, taken from its arguments so the
+/// plan shows while the call is still running.
+fn plan_of(message: &Message) -> Option<Vec<TodoItem>> {
+    if message.role != MessageRole::Tool {
+        return None;
+    }
+    let row: JsonValue = serde_json::from_str(&message.content).ok()?;
+    if row.get("name")?.as_str()? != "todowrite"
+        || row.get("status").and_then(|s| s.as_str()) == Some("error")
+    {
+        return None;
+    }
+    parse_todos(row.get("args")?).ok()
+}
+
+/// Display name of a tool in the chat.
+fn tool_label(name: &str) -> String {
+    match name {
+        "glob" => "Glob",
+        "read" => "Read",
+        "write" => "Write",
+        "edit" => "Edit",
+        "apply_patch" => "Patch",
+        "todowrite" => "Plan",
+        "bash" => "Bash",
+        "list" => "List",
+        "grep" => "Grep",
+        other => other,
+    }
+    .to_string()
+}
+
 impl Chat {
     pub fn new() -> Self {
         Self {
@@ -71,10 +220,34 @@ impl Chat {
             streaming_token_count: 0,
             autoscroll_enabled: true,
             user_scrolled_up: false,
+            bottom_threshold: DEFAULT_BOTTOM_THRESHOLD,
             cached_tokens_per_sec: None,
             last_tps_calculated: None,
             streaming_renderer: None,
             streaming_message_idx: None,
+            older_messages_available: 0,
+            load_more_requested: false,
+            scroll_anchor_from_bottom: None,
+            thinking_frame: 0,
+            unseen_from_height: None,
+            jump_indicator_area: None,
+            scroll_prefs: ScrollPreferences::default(),
+            last_wheel: None,
+            wheel_streak: 0,
+            plain_markdown: false,
+            markdown: Box::default(),
+            density: Density::default(),
+            hide_scrollbar: false,
+            loading: false,
+            tool_groups_expanded: HashMap::new(),
+            tool_group_headers: Vec::new(),
+            bookmarks: Vec::new(),
+            message_rows: Vec::new(),
+            scroll_target: None,
+            copy_selection: None,
+            content_width: 0,
+            rendered: Vec::new(),
+            renders: 0,
         }
     }
 
@@ -95,10 +268,34 @@ impl Chat {
             streaming_token_count: 0,
             autoscroll_enabled: true,
             user_scrolled_up: false,
+            bottom_threshold: DEFAULT_BOTTOM_THRESHOLD,
             cached_tokens_per_sec: None,
             last_tps_calculated: None,
             streaming_renderer: None,
             streaming_message_idx: None,
+            older_messages_available: 0,
+            load_more_requested: false,
+            scroll_anchor_from_bottom: None,
+            thinking_frame: 0,
+            unseen_from_height: None,
+            jump_indicator_area: None,
+            scroll_prefs: ScrollPreferences::default(),
+            last_wheel: None,
+            wheel_streak: 0,
+            plain_markdown: false,
+            markdown: Box::default(),
+            density: Density::default(),
+            hide_scrollbar: false,
+            loading: false,
+            tool_groups_expanded: HashMap::new(),
+            tool_group_headers: Vec::new(),
+            bookmarks: Vec::new(),
+            message_rows: Vec::new(),
+            scroll_target: None,
+            copy_selection: None,
+            content_width: 0,
+            rendered: Vec::new(),
+            renders: 0,
         }
     }
 
@@ -221,6 +418,144 @@ impl Chat {
         self.streaming_t1_ms = None;
         self.streaming_tn_ms = None;
         self.streaming_token_count = 0;
+        self.older_messages_available = 0;
+        self.load_more_requested = false;
+        self.scroll_anchor_from_bottom = None;
+        self.unseen_from_height = None;
+        self.jump_indicator_area = None;
+        self.loading = false;
+        self.tool_groups_expanded.clear();
+        self.tool_group_headers.clear();
+        self.bookmarks.clear();
+        self.message_rows.clear();
+        self.scroll_target = None;
+        self.copy_selection = None;
+        self.rendered.clear();
+    }
+
+    /// Lines of content that arrived below the viewport since the user
+    /// scrolled up.
+    pub fn new_lines_below(&self) -> usize {
+        self.unseen_from_height
+            .map_or(0, |h| self.content_height.saturating_sub(h))
+    }
+
+    /// Tracks how much content arrived while scrolled up. `previous_height`
+    /// is the content height before this frame; `prepended` is height added
+    /// above the viewport by loading older messages, which isn't new.
+    fn track_unseen_content(&mut self, previous_height: usize, prepended: usize) {
+        if !self.user_scrolled_up {
+            self.unseen_from_height = None;
+            return;
+        }
+        let baseline = self.unseen_from_height.get_or_insert(previous_height);
+        *baseline += prepended;
+    }
+
+    fn jump_indicator_text(&self) -> Option<String> {
+        let count = self.new_lines_below();
+        if count == 0 {
+            return None;
+        }
+        let noun = if count == 1 { "line" } else { "lines" };
+        Some(format!(" ↓ {} new {} · End ", count, noun))
+    }
+
+    /// Inserts older messages above the loaded ones while keeping the
+    /// viewport on the content the user was reading.
+    pub fn prepend_messages(&mut self, messages: Vec<Message>, older_remaining: usize) {
+        self.older_messages_available = older_remaining;
+        if messages.is_empty() {
+            return;
+        }
+
+        let added = messages.len();
+        // The selected rows would now point at other messages
+        self.copy_selection = None;
+        self.scroll_anchor_from_bottom =
+            Some(self.content_height.saturating_sub(self.scroll_offset));
+        self.messages.splice(0..0, messages);
+        self.rendered.splice(0..0, std::iter::repeat_n(None, added));
+        if let Some(idx) = self.streaming_message_idx.as_mut() {
+            *idx += added;
+        }
+        self.tool_groups_expanded = std::mem::take(&mut self.tool_groups_expanded)
+            .into_iter()
+            .map(|(start, expanded)| (start + added, expanded))
+            .collect();
+    }
+
+    /// Returns `true` once after the user scrolled to the top with older
+    /// messages still unloaded.
+    pub fn take_load_more_request(&mut self) -> bool {
+        std::mem::take(&mut self.load_more_requested)
+    }
+
+    fn check_load_more(&mut self) {
+        if self.scroll_offset == 0 && self.older_messages_available > 0 {
+            self.load_more_requested = true;
+        }
+    }
+
+    fn load_more_lines(&self) -> Vec<Line<'static>> {
+        if self.older_messages_available == 0 {
+            return Vec::new();
+        }
+        let noun = if self.older_messages_available == 1 {
+            "message"
+        } else {
+            "messages"
+        };
+        vec![
+            Line::from(Span::styled(
+                format!(
+                    "  ↑ {} earlier {} — scroll up to load more",
+                    self.older_messages_available, noun
+                ),
+                Style::default()
+                    .fg(Color::Rgb(150, 120, 100))
+                    .add_modifier(Modifier::DIM),
+            )),
+            Line::from(""),
+        ]
+    }
+
+    /// True between sending a message and receiving the first chunk.
+    pub fn is_awaiting_first_token(&self) -> bool {
+        self.streaming_start_time.is_some()
+            && self.streaming_first_token_time.is_none()
+            && self.streaming_end_time.is_none()
+    }
+
+    /// Advances the "thinking…" placeholder animation. Called on the event
+    /// loop tick; a no-op once the first token has arrived.
+    pub fn tick_thinking_indicator(&mut self) {
+        if self.is_awaiting_first_token() {
+            self.thinking_frame = self.thinking_frame.wrapping_add(1);
+        }
+    }
+
+    fn thinking_placeholder(&self, colors: &ThemeColors) -> Line<'static> {
+        // Blink the cursor every 500ms and cycle the dots every 300ms
+        let cursor = if (self.thinking_frame / 5).is_multiple_of(2) {
+            "▌"
+        } else {
+            " "
+        };
+        let dots = match (self.thinking_frame / 3) % 3 {
+            0 => "·  ",
+            1 => "·· ",
+            _ => "…  ",
+        };
+        Line::from(vec![
+            Span::styled(cursor, Style::default().fg(colors.primary)),
+            Span::styled(
+                format!(" thinking{}", dots),
+                Style::default()
+                    .fg(colors.text_weak)
+                    .add_modifier(Modifier::ITALIC),
+            ),
+        ])
     }
 
     pub fn begin_streaming_turn(&mut self) {
@@ -235,6 +570,7 @@ impl Chat {
         self.streaming_tn_ms = None;
         self.streaming_token_count = 0;
         self.cached_tokens_per_sec = None;
+        self.thinking_frame = 0;
         self.last_tps_calculated = None;
 
         if let Some(msg) = self
@@ -344,7 +680,7 @@ impl Chat {
 
     /// Update the streaming markdown renderer for the current streaming message
     /// This should be called before render() to ensure the renderer is up to date
-    fn update_streaming_renderer(&mut self) {
+    fn update_streaming_renderer(&mut self, max_width: usize) {
         // Check if we're streaming and have messages
         if !self.is_streaming() || self.messages.is_empty() {
             // Not streaming, clear renderer if it exists
@@ -376,29 +712,71 @@ impl Chat {
             self.streaming_message_idx = Some(last_idx);
         }
 
-        // Update the renderer content if needed
+        // Take in whatever streamed since the last frame
         if let Some(ref mut renderer) = self.streaming_renderer {
             if let Some(msg) = self.messages.get(last_idx) {
-                if renderer.content() != msg.content {
-                    renderer.reset();
-                    renderer.append(&msg.content);
-                }
+                renderer.sync(&msg.content);
             }
+            renderer.render(self.markdown.as_ref(), max_width, self.plain_markdown);
         }
     }
 
     pub fn scroll_down(&mut self, amount: usize) {
         let max_offset = self.content_height.saturating_sub(self.viewport_height);
         self.scroll_offset = (self.scroll_offset + amount).min(max_offset);
-        // Check if we're now at the bottom
-        self.user_scrolled_up = self.scroll_offset < max_offset;
+        // Check if we're now at (or close enough to) the bottom
+        self.user_scrolled_up = !self.near_bottom(max_offset);
         self.update_scrollbar();
     }
 
+    fn near_bottom(&self, max_offset: usize) -> bool {
+        self.scroll_offset + self.bottom_threshold >= max_offset
+    }
+
     pub fn scroll_up(&mut self, amount: usize) {
         self.scroll_offset = self.scroll_offset.saturating_sub(amount);
         self.user_scrolled_up = true;
         self.update_scrollbar();
+        self.check_load_more();
+    }
+
+    /// Lines to move for a wheel event. With acceleration on, rapid events in
+    /// the same direction grow the step up to 4x.
+    fn wheel_step(&mut self, up: bool, now: std::time::Instant) -> usize {
+        const RAPID_WHEEL: std::time::Duration = std::time::Duration::from_millis(60);
+
+        let base = self.scroll_prefs.base_step();
+        let rapid = self
+            .last_wheel
+            .is_some_and(|(at, dir)| dir == up && now.duration_since(at) <= RAPID_WHEEL);
+        self.wheel_streak = if rapid { self.wheel_streak + 1 } else { 0 };
+        self.last_wheel = Some((now, up));
+
+        if self.scroll_prefs.accelerate && !self.scroll_prefs.smooth {
+            base * (1 + self.wheel_streak / 2).min(4)
+        } else {
+            base
+        }
+    }
+
+    /// Rows moved by PageUp/PageDown: a viewport, keeping one line of overlap.
+    fn page_size(&self) -> usize {
+        self.viewport_height.saturating_sub(1).max(1)
+    }
+
+    pub fn page_up(&mut self) {
+        self.scroll_up(self.page_size());
+    }
+
+    pub fn page_down(&mut self) {
+        self.scroll_down(self.page_size());
+    }
+
+    pub fn scroll_to_top(&mut self) {
+        self.scroll_offset = 0;
+        self.user_scrolled_up = self.content_height > self.viewport_height;
+        self.update_scrollbar();
+        self.check_load_more();
     }
 
     pub fn scroll_to_bottom(&mut self) {
@@ -407,6 +785,161 @@ impl Chat {
         self.update_scrollbar();
     }
 
+    /// Session index of the message at the top of the viewport.
+    pub fn message_at_top(&self) -> Option<usize> {
+        if self.messages.is_empty() {
+            return None;
+        }
+        let max_offset = self.content_height.saturating_sub(self.viewport_height);
+        let top = self.scroll_offset.min(max_offset);
+        let local = self
+            .message_rows
+            .iter()
+            .rposition(|&row| row <= top)
+            .unwrap_or(0);
+        Some(self.older_messages_available + local)
+    }
+
+    /// The nearest bookmark below (`forward`) or above the message at the
+    /// top of the viewport.
+    pub fn adjacent_bookmark(&self, forward: bool) -> Option<usize> {
+        let top = self.message_at_top()?;
+        if forward {
+            self.bookmarks.iter().copied().find(|&index| index > top)
+        } else {
+            self.bookmarks
+                .iter()
+                .rev()
+                .copied()
+                .find(|&index| index < top)
+        }
+    }
+
+    /// Brings the message at session index `index` to the top on the next
+    /// render. `false` when it isn't loaded into the chat yet.
+    pub fn scroll_to_message(&mut self, index: usize) -> bool {
+        let loaded = index
+            .checked_sub(self.older_messages_available)
+            .is_some_and(|local| local < self.messages.len());
+        if loaded {
+            self.scroll_target = Some(index);
+        }
+        loaded
+    }
+
+    fn apply_scroll_target(&mut self) {
+        let Some(index) = self.scroll_target.take() else {
+            return;
+        };
+        let row = index
+            .checked_sub(self.older_messages_available)
+            .and_then(|local| self.message_rows.get(local));
+        if let Some(&row) = row {
+            let max_offset = self.content_height.saturating_sub(self.viewport_height);
+            self.scroll_offset = row.min(max_offset);
+            self.user_scrolled_up = !self.near_bottom(max_offset);
+        }
+    }
+
+    /// Starts copy mode with the last visible row selected. `false` when
+    /// there's nothing to select.
+    pub fn begin_copy_mode(&mut self) -> bool {
+        if self.content_height == 0 {
+            return false;
+        }
+        let bottom = (self.scroll_offset + self.viewport_height)
+            .min(self.content_height)
+            .saturating_sub(1);
+        self.copy_selection = Some((bottom, bottom));
+        true
+    }
+
+    pub fn end_copy_mode(&mut self) {
+        self.copy_selection = None;
+    }
+
+    pub fn is_copy_mode(&self) -> bool {
+        self.copy_selection.is_some()
+    }
+
+    /// Content rows from the row copy mode started on to the cursor, top
+    /// row first.
+    pub fn selected_rows(&self) -> Option<std::ops::RangeInclusive<usize>> {
+        let (anchor, cursor) = self.copy_selection?;
+        Some(anchor.min(cursor)..=anchor.max(cursor))
+    }
+
+    /// Moves the copy cursor by `delta` rows, extending the selection, and
+    /// scrolls to keep it on screen. Stays within the loaded messages.
+    pub fn move_copy_cursor(&mut self, delta: isize) {
+        let Some((anchor, cursor)) = self.copy_selection else {
+            return;
+        };
+        let last = self.content_height.saturating_sub(1) as isize;
+        let cursor = (cursor as isize + delta).clamp(0, last) as usize;
+        self.copy_selection = Some((anchor, cursor));
+
+        if cursor < self.scroll_offset {
+            self.scroll_offset = cursor;
+        } else if cursor >= self.scroll_offset + self.viewport_height {
+            self.scroll_offset = cursor + 1 - self.viewport_height.max(1);
+        }
+        let max_offset = self.content_height.saturating_sub(self.viewport_height);
+        self.user_scrolled_up = !self.near_bottom(max_offset);
+        self.update_scrollbar();
+    }
+
+    pub fn move_copy_cursor_by_page(&mut self, down: bool) {
+        let page = self.page_size() as isize;
+        self.move_copy_cursor(if down { page } else { -page });
+    }
+
+    /// Content row shown on screen row `row` of the chat `area`.
+    pub fn content_row_at(&self, row: u16, area: Rect) -> Option<usize> {
+        if row < area.y || row >= area.y + area.height {
+            return None;
+        }
+        let content_row = self.scroll_offset + (row - area.y) as usize;
+        (content_row < self.content_height).then_some(content_row)
+    }
+
+    /// Loaded message a content row belongs to, `None` for the rows above
+    /// the first one.
+    fn message_at_row(&self, row: usize) -> Option<&Message> {
+        let local = self
+            .message_rows
+            .partition_point(|&start| start <= row)
+            .checked_sub(1)?;
+        self.messages.get(local)
+    }
+
+    /// Text of the selected rows as rendered, one line per row, without
+    /// the border of user messages, trailing padding or blank rows at
+    /// either end.
+    pub fn selected_text(&self, model: &str, colors: &ThemeColors) -> String {
+        let Some(rows) = self.selected_rows() else {
+            return String::new();
+        };
+        let lines = self.render_visible_messages(self.content_width, model, colors);
+        rows.filter_map(|row| {
+            let message = self.message_at_row(row)?;
+            let text = lines.get(row)?.to_string();
+            let text = if message.role == MessageRole::User {
+                text.strip_prefix("▌ ")
+                    .or_else(|| text.strip_prefix("│ "))
+                    .unwrap_or(&text)
+                    .to_string()
+            } else {
+                text
+            };
+            Some(text.trim_end().to_string())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_matches('\n')
+        .to_string()
+    }
+
     fn update_scrollbar(&mut self) {
         let max_offset = self.content_height.saturating_sub(self.viewport_height);
         let content_length = max_offset.saturating_add(1).max(1);
@@ -432,22 +965,33 @@ impl Chat {
             height: area.height,
         };
 
-        let is_on_scrollbar = scrollbar_area.contains(point);
+        let is_on_scrollbar = !self.hide_scrollbar && scrollbar_area.contains(point);
 
         match event.kind {
             MouseEventKind::ScrollDown => {
-                self.scroll_down(3);
+                let step = self.wheel_step(false, std::time::Instant::now());
+                self.scroll_down(step);
                 true
             }
             MouseEventKind::ScrollUp => {
-                self.scroll_up(3);
+                let step = self.wheel_step(true, std::time::Instant::now());
+                self.scroll_up(step);
                 true
             }
             MouseEventKind::Down(MouseButton::Left) => {
-                if is_on_scrollbar {
+                if self
+                    .jump_indicator_area
+                    .is_some_and(|indicator| indicator.contains(point))
+                {
+                    self.scroll_to_bottom();
+                    true
+                } else if is_on_scrollbar {
                     self.is_dragging_scrollbar = true;
                     self.scroll_to_position(event.row, scrollbar_area);
                     true
+                } else if let Some(start) = self.tool_group_header_at(event.row, area) {
+                    self.toggle_tool_group(start);
+                    true
                 } else {
                     false
                 }
@@ -487,11 +1031,27 @@ impl Chat {
         };
         self.scroll_offset = new_offset.min(max_offset);
         // Track if user scrolled away from bottom
-        self.user_scrolled_up = self.scroll_offset < max_offset;
+        self.user_scrolled_up = !self.near_bottom(max_offset);
         self.update_scrollbar();
+        self.check_load_more();
     }
 
+    /// Draws the messages. A message that makes rendering panic leaves a
+    /// placeholder in `area` instead of taking the app down.
     pub fn render(
+        &mut self,
+        f: &mut Frame,
+        area: Rect,
+        agent: &str,
+        model: &str,
+        colors: &ThemeColors,
+    ) {
+        render_guarded(f, area, "chat", colors, |f| {
+            self.render_messages(f, area, agent, model, colors)
+        });
+    }
+
+    fn render_messages(
         &mut self,
         f: &mut Frame,
         area: Rect,
@@ -501,30 +1061,62 @@ impl Chat {
     ) {
         self.viewport_height = area.height as usize;
 
-        // Update streaming renderer before calculating heights
-        self.update_streaming_renderer();
+        if self.loading {
+            let text = Line::from(Span::styled(
+                "  Loading session…",
+                Style::default()
+                    .fg(colors.text_weak)
+                    .add_modifier(Modifier::ITALIC),
+            ));
+            f.render_widget(Paragraph::new(text), area);
+            return;
+        }
 
-        // Calculate content area (leave space for scrollbar)
+        // Calculate content area (leave space for the bookmark gutter and
+        // the scrollbar)
+        let gutter = if self.hide_scrollbar { 1 } else { 2 };
         let content_area = Rect {
             x: area.x,
             y: area.y,
-            width: area.width.saturating_sub(1),
+            width: area.width.saturating_sub(gutter),
             height: area.height,
         };
 
+        self.content_width = content_area.width as usize;
+
+        // Update streaming renderer before calculating heights
+        self.update_streaming_renderer(self.content_width);
+
         // Calculate total content height first
         let total_height =
             self.calculate_content_height(content_area.width as usize, model, colors);
+        let previous_height = self.content_height;
         self.content_height = total_height;
 
+        let mut prepended = 0;
+        if let Some(anchor) = self.scroll_anchor_from_bottom.take() {
+            self.scroll_offset = self.content_height.saturating_sub(anchor);
+            prepended = total_height.saturating_sub(previous_height);
+        }
+        self.track_unseen_content(previous_height, prepended);
+        self.apply_scroll_target();
+
         // Clamp scroll offset
         let max_offset = self.content_height.saturating_sub(self.viewport_height);
         self.scroll_offset = self.scroll_offset.min(max_offset);
         self.update_scrollbar();
 
         // Now render the visible content
-        let content_lines =
+        let mut content_lines =
             self.render_visible_messages(content_area.width as usize, model, colors);
+        if let Some(rows) = self.selected_rows() {
+            for row in rows {
+                if let Some(line) = content_lines.get_mut(row) {
+                    *line = std::mem::take(line)
+                        .patch_style(Style::default().add_modifier(Modifier::REVERSED));
+                }
+            }
+        }
 
         // Store scroll_offset before creating paragraph
         let scroll_offset = self.scroll_offset;
@@ -536,11 +1128,64 @@ impl Chat {
 
         f.render_widget(paragraph, content_area);
 
+        // Mark the first row of bookmarked messages in the gutter
+        for &index in &self.bookmarks {
+            let row = index
+                .checked_sub(self.older_messages_available)
+                .and_then(|local| self.message_rows.get(local));
+            let Some(&row) = row else {
+                continue;
+            };
+            if row < scroll_offset || row >= scroll_offset + self.viewport_height {
+                continue;
+            }
+            // No gutter left in a very narrow area
+            let marker_area = Rect {
+                x: area.x + content_area.width,
+                y: area.y + (row - scroll_offset) as u16,
+                width: area.width.saturating_sub(content_area.width).min(1),
+                height: 1,
+            };
+            f.render_widget(
+                Paragraph::new(Span::styled("★", Style::default().fg(colors.primary))),
+                marker_area,
+            );
+        }
+
+        // "↓ N new lines" jump button over the bottom row while scrolled up
+        self.jump_indicator_area = None;
+        if let Some(text) = self.jump_indicator_text() {
+            let width = (text.width() as u16).min(content_area.width);
+            if content_area.height > 0 && width > 0 {
+                let indicator_area = Rect {
+                    x: content_area.x + (content_area.width - width) / 2,
+                    y: content_area.y + content_area.height - 1,
+                    width,
+                    height: 1,
+                };
+                f.render_widget(Clear, indicator_area);
+                f.render_widget(
+                    Paragraph::new(text).style(
+                        Style::default()
+                            .fg(Color::Black)
+                            .bg(colors.primary)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    indicator_area,
+                );
+                self.jump_indicator_area = Some(indicator_area);
+            }
+        }
+
+        if self.hide_scrollbar {
+            return;
+        }
+
         // Render scrollbar
         let scrollbar_area = Rect {
             x: area.x + area.width.saturating_sub(1),
             y: area.y,
-            width: 1,
+            width: area.width.min(1),
             height: area.height,
         };
 
@@ -555,34 +1200,50 @@ impl Chat {
         );
     }
 
+    /// Also records where the tool group headers are, for clicks, and the
+    /// row each message starts on.
     fn calculate_content_height(
-        &self,
+        &mut self,
         max_width: usize,
         model: &str,
         colors: &ThemeColors,
     ) -> usize {
-        let mut total_height = 0;
+        let mut total_height = self.load_more_lines().len();
         let message_count = self.messages.len();
-        let streaming_idx = self.streaming_assistant_idx();
-        let streaming_content = self.streaming_renderer.as_ref().map(|r| r.get_content());
-
-        for (idx, message) in self.messages.iter().enumerate() {
-            let attached_to_assistant =
-                idx > 0 && self.messages[idx - 1].role == MessageRole::Assistant;
-            let message_lines = self.format_message(
-                message,
-                max_width,
-                idx,
-                message_count,
-                streaming_content,
-                streaming_idx,
-                model,
-                colors,
-                attached_to_assistant,
-            );
-            total_height += message_lines.len();
+        let keys = self.render_keys(max_width, model, colors);
+        let mut headers = Vec::new();
+        let mut rows = Vec::with_capacity(message_count);
+        self.rendered.resize(message_count, None);
+
+        for (idx, key) in keys.into_iter().enumerate() {
+            rows.push(total_height);
+            if self
+                .tool_group(idx)
+                .is_some_and(|(start, len)| start == idx && len > 1)
+            {
+                headers.push((total_height, idx));
+            }
+            if let Some(cached) = self.cached_lines(idx, key) {
+                total_height += cached.len();
+                continue;
+            }
+
+            let lines: Vec<Line<'static>> = self
+                .format_message_at(idx, max_width, model, colors)
+                .into_iter()
+                .map(owned_line)
+                .collect();
+            self.renders += 1;
+            total_height += lines.len();
+            self.rendered[idx] = key.map(|key| RenderedMessage {
+                message: self.messages[idx].clone(),
+                key,
+                lines,
+            });
         }
 
+        self.tool_group_headers = headers;
+        self.message_rows = rows;
         total_height
     }
 
@@ -592,38 +1253,106 @@ impl Chat {
         model: &'a str,
         colors: &'a ThemeColors,
     ) -> Vec<Line<'a>> {
-        let mut all_lines: Vec<Line<'a>> = Vec::new();
-        let message_count = self.messages.len();
-        let streaming_idx = self.streaming_assistant_idx();
-        let streaming_content = self.streaming_renderer.as_ref().map(|r| r.get_content());
-
-        for (idx, message) in self.messages.iter().enumerate() {
-            let attached_to_assistant =
-                idx > 0 && self.messages[idx - 1].role == MessageRole::Assistant;
-            let message_lines = self.format_message(
-                message,
-                max_width,
-                idx,
-                message_count,
-                streaming_content,
-                streaming_idx,
-                model,
-                colors,
-                attached_to_assistant,
-            );
-            all_lines.extend(message_lines);
+        let mut all_lines: Vec<Line<'a>> = self.load_more_lines();
+        let keys = self.render_keys(max_width, model, colors);
+
+        for (idx, key) in keys.into_iter().enumerate() {
+            match self.cached_lines(idx, key) {
+                Some(lines) => all_lines.extend(lines.iter().cloned()),
+                None => all_lines.extend(self.format_message_at(idx, max_width, model, colors)),
+            }
         }
 
         all_lines
     }
 
+    fn format_message_at<'a>(
+        &'a self,
+        idx: usize,
+        max_width: usize,
+        model: &'a str,
+        colors: &'a ThemeColors,
+    ) -> Vec<Line<'a>> {
+        let attached_to_assistant =
+            idx > 0 && self.messages[idx - 1].role == MessageRole::Assistant;
+        self.format_message(
+            &self.messages[idx],
+            max_width,
+            idx,
+            self.messages.len(),
+            self.streaming_renderer.as_ref(),
+            self.streaming_assistant_idx(),
+            model,
+            colors,
+            attached_to_assistant,
+        )
+    }
+
+    /// The cached lines of message `idx`, if they were rendered from the
+    /// message as it is now with the same `key`.
+    fn cached_lines(&self, idx: usize, key: Option<u64>) -> Option<&[Line<'static>]> {
+        let cached = self.rendered.get(idx)?.as_ref()?;
+        (Some(cached.key) == key && cached.message == self.messages[idx])
+            .then_some(cached.lines.as_slice())
+    }
+
+    /// For each message, a hash of what its lines depend on besides the
+    /// message itself: the layout, theme and markdown settings, and the
+    /// neighbours that decide its spacing, footer, tool group and plan.
+    /// `None` for the message still streaming, which is never cached.
+    fn render_keys(&self, max_width: usize, model: &str, colors: &ThemeColors) -> Vec<Option<u64>> {
+        use std::hash::{Hash, Hasher};
+
+        let mut frame = std::collections::hash_map::DefaultHasher::new();
+        (max_width, model, self.density.label(), self.plain_markdown).hash(&mut frame);
+        format!("{:?}", self.markdown).hash(&mut frame);
+        colors.hash(&mut frame);
+        let frame = frame.finish();
+
+        let streaming_idx = self.streaming_assistant_idx();
+        let latest_plan = self.messages.iter().rposition(|m| plan_of(m).is_some());
+        let mut agent_mode: Option<&str> = None;
+
+        self.messages
+            .iter()
+            .enumerate()
+            .map(|(idx, message)| {
+                if streaming_idx == Some(idx) && !message.is_complete {
+                    return None;
+                }
+                if message.role == MessageRole::User && message.agent_mode.is_some() {
+                    agent_mode = message.agent_mode.as_deref();
+                }
+
+                let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                frame.hash(&mut hasher);
+                agent_mode.hash(&mut hasher);
+                (latest_plan == Some(idx)).hash(&mut hasher);
+                let neighbour_role =
+                    |i: Option<usize>| i.and_then(|i| self.messages.get(i)).map(|m| m.role.clone());
+                neighbour_role(idx.checked_sub(1)).hash(&mut hasher);
+                neighbour_role(Some(idx + 1)).hash(&mut hasher);
+                if let Some((start, len)) = self.tool_group(idx) {
+                    (idx - start, len, self.is_tool_group_expanded(start, len)).hash(&mut hasher);
+                    if idx == start {
+                        // The header sums up the whole group
+                        for member in &self.messages[start..start + len] {
+                            member.content.hash(&mut hasher);
+                        }
+                    }
+                }
+                Some(hasher.finish())
+            })
+            .collect()
+    }
+
     fn format_message<'a>(
         &'a self,
         message: &'a Message,
         max_width: usize,
         idx: usize,
         message_count: usize,
-        streaming_content: Option<&'a str>,
+        streaming_renderer: Option<&'a SimpleStreamingRenderer>,
         streaming_idx: Option<usize>,
         model: &'a str,
         colors: &'a ThemeColors,
@@ -648,7 +1377,10 @@ impl Chat {
 
                     let left_border = if is_first { "▌ " } else { "│ " };
 
-                    let right_padding = " ".repeat(max_width.saturating_sub(line.len() + 3));
+                    // Pad by display width (plus one column of margin) so
+                    // emoji/CJK lines line up with ASCII ones
+                    let used = left_border.width() + line.width() + 1;
+                    let right_padding = " ".repeat(max_width.saturating_sub(used));
 
                     lines.push(Line::from(vec![
                         Span::styled(left_border, Style::default().fg(border_color)),
@@ -658,7 +1390,7 @@ impl Chat {
                 }
 
                 // Add empty line after user message
-                lines.push(Line::from(""));
+                self.push_spacer(&mut lines);
             }
             MessageRole::Assistant => {
                 // Display reasoning/thinking tokens if present
@@ -683,17 +1415,22 @@ impl Chat {
                         }
 
                         // Add separator between reasoning and content
-                        lines.push(Line::from(""));
+                        self.push_spacer(&mut lines);
                     }
                 }
 
                 let is_streaming = streaming_idx == Some(idx) && !message.is_complete;
 
-                if is_streaming {
+                if is_streaming
+                    && message.content.is_empty()
+                    && message.reasoning.is_none()
+                    && self.is_awaiting_first_token()
+                {
+                    lines.push(self.thinking_placeholder(colors));
+                } else if is_streaming {
                     // Use the streaming renderer content for markdown
-                    if let Some(content) = streaming_content {
-                        let markdown_lines = render_markdown(content, max_width);
-                        lines.extend(markdown_lines);
+                    if let Some(renderer) = streaming_renderer {
+                        lines.extend(renderer.lines().iter().cloned());
                     } else {
                         // Fallback to plain text if renderer not available
                         let content = message.content.clone();
@@ -703,8 +1440,9 @@ impl Chat {
                         }
                     }
                 } else {
-                    // For complete messages, use tui-markdown directly
-                    let markdown_lines = render_markdown(&message.content, max_width);
+                    let markdown_lines =
+                        self.markdown
+                            .render(&message.content, max_width, self.plain_markdown);
                     lines.extend(markdown_lines);
                 }
 
@@ -717,14 +1455,12 @@ impl Chat {
                     );
 
                 if show_metadata {
-                    lines.push(Line::from(""));
+                    self.push_spacer(&mut lines);
                     let metadata = self.format_metadata(message, model, colors);
                     lines.push(Line::from(metadata));
-                    lines.push(Line::from(""));
-                } else {
-                    // Keep spacing consistent between segments.
-                    lines.push(Line::from(""));
                 }
+                // Keep spacing consistent between segments.
+                self.push_spacer(&mut lines);
             }
             MessageRole::System => {
                 // System messages: simple display
@@ -738,22 +1474,50 @@ impl Chat {
                         Style::default().fg(Color::Yellow),
                     )));
                 }
-                lines.push(Line::from(""));
+                self.push_spacer(&mut lines);
             }
             MessageRole::Tool => {
+                if let Some(todos) = plan_of(message) {
+                    lines.extend(self.format_plan(&todos, idx, max_width, colors));
+                    self.push_spacer(&mut lines);
+                    return lines;
+                }
+                if let Some((start, len)) = self.tool_group(idx).filter(|(_, len)| *len > 1) {
+                    let expanded = self.is_tool_group_expanded(start, len);
+                    if idx == start {
+                        lines.push(self.tool_group_header(start, len, max_width, colors));
+                        if !expanded {
+                            // Collapsed groups still show what running tools print
+                            for member in &self.messages[start..start + len] {
+                                lines.extend(live_output_lines(member, max_width, colors));
+                            }
+                            self.push_spacer(&mut lines);
+                        }
+                    }
+                    if !expanded {
+                        return lines;
+                    }
+                }
                 lines.extend(self.format_tool_row(
                     message,
                     max_width,
                     colors,
                     attached_to_assistant,
                 ));
-                lines.push(Line::from(""));
+                self.push_spacer(&mut lines);
             }
         }
 
         lines
     }
 
+    /// Blank line between messages, left out in the compact density.
+    fn push_spacer(&self, lines: &mut Vec<Line<'_>>) {
+        if self.density == Density::Comfortable {
+            lines.push(Line::from(""));
+        }
+    }
+
     fn format_tool_row<'a>(
         &'a self,
         message: &'a Message,
@@ -762,17 +1526,14 @@ impl Chat {
         attached: bool,
     ) -> Vec<Line<'a>> {
         fn preview_value(v: &JsonValue, max_len: usize) -> String {
-            let mut s = match v {
+            let s = match v {
                 JsonValue::String(s) => s.clone(),
                 JsonValue::Number(n) => n.to_string(),
                 JsonValue::Bool(b) => b.to_string(),
                 JsonValue::Null => "null".to_string(),
                 other => other.to_string(),
             };
-            if s.len() > max_len {
-                s.truncate(max_len);
-                s.push_str("…");
-            }
+            let s = truncate_chars(&s, max_len);
             if matches!(v, JsonValue::String(_)) {
                 format!("\"{}\"", s)
             } else {
@@ -837,16 +1598,7 @@ impl Chat {
             _ => "•",
         };
 
-        let tool_label = match name.as_str() {
-            "glob" => "Glob",
-            "read" => "Read",
-            "write" => "Write",
-            "edit" => "Edit",
-            "bash" => "Bash",
-            "list" => "List",
-            "grep" => "Grep",
-            other => other,
-        };
+        let tool_label = tool_label(&name);
 
         let args_obj = args.as_ref().and_then(|v| v.as_object());
         let args_str = if name == "glob" {
@@ -899,15 +1651,15 @@ impl Chat {
             )));
         }
 
+        if status == "running" {
+            out.extend(live_output_lines(message, max_width, colors));
+        }
+
         if status == "error" {
             if let Some(preview) = output_preview {
                 let first = preview.lines().next().unwrap_or("").trim();
                 if !first.is_empty() {
-                    let mut line = first.to_string();
-                    if line.len() > max_width.saturating_sub(6) {
-                        line.truncate(max_width.saturating_sub(6));
-                        line.push_str("…");
-                    }
+                    let line = truncate_chars(first, max_width.saturating_sub(6));
                     out.push(Line::from(Span::styled(
                         format!("{}    {}", indent, line),
                         Style::default().fg(colors.error),
@@ -919,6 +1671,174 @@ impl Chat {
         out
     }
 
+    /// The run of consecutive tool messages `idx` belongs to, as (index of
+    /// its first message, length). `None` for other messages. Plans are
+    /// shown on their own and end a group.
+    fn tool_group(&self, idx: usize) -> Option<(usize, usize)> {
+        let is_tool = |i: &usize| {
+            self.messages[*i].role == MessageRole::Tool && plan_of(&self.messages[*i]).is_none()
+        };
+        if idx >= self.messages.len() || !is_tool(&idx) {
+            return None;
+        }
+        let start = (0..idx).rev().take_while(is_tool).last().unwrap_or(idx);
+        let end = (idx + 1..self.messages.len())
+            .take_while(is_tool)
+            .last()
+            .unwrap_or(idx);
+        Some((start, end - start + 1))
+    }
+
+    /// Whether the tool group starting at `start` shows its rows: what the
+    /// user picked, else expanded unless it's longer than
+    /// `COLLAPSE_TOOL_GROUPS_OVER`.
+    pub fn is_tool_group_expanded(&self, start: usize, len: usize) -> bool {
+        self.tool_groups_expanded
+            .get(&start)
+            .copied()
+            .unwrap_or(len <= COLLAPSE_TOOL_GROUPS_OVER)
+    }
+
+    /// Expands or collapses the tool group message `idx` belongs to.
+    pub fn toggle_tool_group(&mut self, idx: usize) {
+        if let Some((start, len)) = self.tool_group(idx) {
+            let expanded = self.is_tool_group_expanded(start, len);
+            self.tool_groups_expanded.insert(start, !expanded);
+        }
+    }
+
+    /// The tool group whose header is on screen row `row` of `area`.
+    fn tool_group_header_at(&self, row: u16, area: Rect) -> Option<usize> {
+        let row = self.scroll_offset + row.saturating_sub(area.y) as usize;
+        self.tool_group_headers
+            .iter()
+            .find(|(header_row, _)| *header_row == row)
+            .map(|(_, start)| *start)
+    }
+
+    /// The checklist of a `todowrite` call; only the newest plan in the
+    /// chat is shown in full, older ones as a one-line update.
+    fn format_plan(
+        &self,
+        todos: &[TodoItem],
+        idx: usize,
+        max_width: usize,
+        colors: &ThemeColors,
+    ) -> Vec<Line<'static>> {
+        let (done, total) = todo_progress(todos);
+        let weak = Style::default()
+            .fg(colors.text_weak)
+            .add_modifier(Modifier::DIM);
+        let latest = !self.messages[idx + 1..]
+            .iter()
+            .any(|m| plan_of(m).is_some());
+        if !latest {
+            return vec![Line::from(Span::styled(
+                format!("✓ Plan updated · {}/{} done", done, total),
+                weak,
+            ))];
+        }
+
+        let filled = (PLAN_BAR_WIDTH * done).checked_div(total).unwrap_or(0);
+        let mut lines = vec![Line::from(vec![
+            Span::styled("Plan ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::styled("━".repeat(filled), Style::default().fg(colors.primary)),
+            Span::styled("─".repeat(PLAN_BAR_WIDTH - filled), weak),
+            Span::styled(format!(" {}/{}", done, total), weak),
+        ])];
+        for todo in todos {
+            let style = match todo.status {
+                TodoStatus::Completed => Style::default().fg(colors.success),
+                TodoStatus::InProgress => Style::default()
+                    .fg(colors.primary)
+                    .add_modifier(Modifier::BOLD),
+                TodoStatus::Pending => Style::default().fg(colors.text),
+                TodoStatus::Cancelled => weak.add_modifier(Modifier::CROSSED_OUT),
+            };
+            let text = format!("{} {}", todo.status.symbol(), todo.content);
+            for (i, line) in textwrap::wrap(&text, max_width.saturating_sub(4))
+                .iter()
+                .enumerate()
+            {
+                let indent = if i == 0 { "  " } else { "    " };
+                lines.push(Line::from(Span::styled(
+                    format!("{}{}", indent, line),
+                    style,
+                )));
+            }
+        }
+        lines
+    }
+
+    /// "▸ ✓ Ran 6 tools · Read ×3, Edit ×2, Bash" above a tool group.
+    fn tool_group_header(
+        &self,
+        start: usize,
+        len: usize,
+        max_width: usize,
+        colors: &ThemeColors,
+    ) -> Line<'static> {
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        let mut running = false;
+        let mut failed = 0;
+        for message in &self.messages[start..start + len] {
+            let parsed: Option<JsonValue> = serde_json::from_str(&message.content).ok();
+            let field = |key: &str| {
+                parsed
+                    .as_ref()
+                    .and_then(|v| v.get(key))
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+            };
+            match field("status").as_deref() {
+                Some("running") => running = true,
+                Some("error") => failed += 1,
+                _ => {}
+            }
+            let label = tool_label(&field("name").unwrap_or_else(|| "tool".to_string()));
+            match counts.iter_mut().find(|(l, _)| *l == label) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((label, 1)),
+            }
+        }
+
+        let chevron = if self.is_tool_group_expanded(start, len) {
+            "▾"
+        } else {
+            "▸"
+        };
+        let (icon, verb) = match (running, failed) {
+            (true, _) => ("~", "Running"),
+            (false, 0) => ("✓", "Ran"),
+            (false, _) => ("✗", "Ran"),
+        };
+        let summary: Vec<String> = counts
+            .iter()
+            .map(|(label, count)| match count {
+                1 => label.clone(),
+                n => format!("{} ×{}", label, n),
+            })
+            .collect();
+        let mut header = format!(
+            "{} {} {} {} tools · {}",
+            chevron,
+            icon,
+            verb,
+            len,
+            summary.join(", ")
+        );
+        if failed > 0 {
+            header.push_str(&format!(" · {} failed", failed));
+        }
+
+        Line::from(Span::styled(
+            truncate_chars(&header, max_width),
+            Style::default()
+                .fg(colors.text_weak)
+                .add_modifier(Modifier::DIM),
+        ))
+    }
+
     fn get_agent_color(&self, agent_mode: Option<&str>) -> Color {
         match agent_mode {
             Some("Plan") => Color::Rgb(255, 165, 0),    // Orange
@@ -1037,6 +1957,19 @@ impl Chat {
 
 use ratatui::text::Text;
 
+/// `line` with its text copied, so it can outlive what it was built from.
+fn owned_line(line: Line<'_>) -> Line<'static> {
+    Line {
+        spans: line
+            .spans
+            .into_iter()
+            .map(|span| Span::styled(span.content.into_owned(), span.style))
+            .collect(),
+        style: line.style,
+        alignment: line.alignment,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1207,6 +2140,670 @@ mod tests {
         assert_eq!(chat.messages[2].content, "how are you?");
     }
 
+    #[test]
+    fn test_chat_scroll_to_top_requests_older_messages() {
+        let mut chat = Chat::with_messages(vec![Message::user("recent")]);
+        chat.content_height = 20;
+        chat.viewport_height = 5;
+        chat.scroll_offset = 2;
+
+        chat.scroll_up(1);
+        assert!(!chat.take_load_more_request());
+
+        chat.older_messages_available = 10;
+        chat.scroll_up(5);
+        assert!(chat.take_load_more_request());
+        assert!(!chat.take_load_more_request());
+    }
+
+    #[test]
+    fn test_chat_no_request_without_older_messages() {
+        let mut chat = Chat::with_messages(vec![Message::user("recent")]);
+        chat.scroll_up(10);
+        assert!(!chat.take_load_more_request());
+    }
+
+    #[test]
+    fn test_chat_prepend_messages() {
+        let mut chat = Chat::with_messages(vec![Message::user("c")]);
+        chat.older_messages_available = 2;
+        chat.content_height = 30;
+        chat.scroll_offset = 0;
+
+        chat.prepend_messages(vec![Message::user("a"), Message::user("b")], 0);
+
+        let contents: Vec<&str> = chat.messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["a", "b", "c"]);
+        assert_eq!(chat.older_messages_available, 0);
+        assert_eq!(chat.scroll_anchor_from_bottom, Some(30));
+    }
+
+    #[test]
+    fn test_chat_load_more_row() {
+        let mut chat = Chat::new();
+        assert!(chat.load_more_lines().is_empty());
+
+        chat.older_messages_available = 3;
+        let lines = chat.load_more_lines();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].to_string().contains("3 earlier messages"));
+
+        chat.clear();
+        assert!(chat.load_more_lines().is_empty());
+    }
+
+    fn test_colors() -> ThemeColors {
+        ThemeColors {
+            text_weak: Color::Gray,
+            ..ThemeColors::for_tests()
+        }
+    }
+
+    fn rendered_text(chat: &Chat, width: usize) -> Vec<String> {
+        let colors = test_colors();
+        chat.render_visible_messages(width, "test-model", &colors)
+            .iter()
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_chat_renders_in_tiny_areas() {
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let colors = test_colors();
+        for (width, height) in [(1, 1), (10, 5)] {
+            let mut chat = Chat::with_messages(vec![
+                Message::user("hello there"),
+                Message::assistant("# Title\n\n```rust\nfn main() {}\n```\n- item"),
+            ]);
+            chat.bookmarks = vec![0, 1];
+
+            let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+            terminal
+                .draw(|f| chat.render(f, f.area(), "Build", "test-model", &colors))
+                .unwrap();
+            let screen: String = terminal
+                .backend()
+                .buffer()
+                .content()
+                .iter()
+                .map(|cell| cell.symbol())
+                .collect();
+            // No placeholder from the render boundary
+            assert!(!screen.contains('⚠'));
+        }
+    }
+
+    #[test]
+    fn test_chat_tool_row_from_tool_call() {
+        use crate::llm::{FunctionCall, ToolCall};
+
+        let call = ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "read".to_string(),
+                arguments: r#"{"file_path":"/tmp/a.txt"}"#.to_string(),
+            },
+        };
+        let mut row = call.running_row();
+        let chat = Chat::with_messages(vec![Message::tool(row.to_string())]);
+        let lines = rendered_text(&chat, 80);
+        assert!(lines[0].contains("~ Read"), "{:?}", lines);
+        assert!(lines[0].contains(r#"file_path="/tmp/a.txt""#));
+
+        // Merged the way the bridge's error payload is
+        row["status"] = "error".into();
+        row["output_preview"] = "Execution error: not found".into();
+        let chat = Chat::with_messages(vec![Message::tool(row.to_string())]);
+        let lines = rendered_text(&chat, 80);
+        assert!(lines[0].contains("✗ Read"));
+        assert!(lines[1].contains("Execution error: not found"));
+    }
+
+    fn tool_message(name: &str, status: &str) -> Message {
+        Message::tool(serde_json::json!({ "name": name, "status": status }).to_string())
+    }
+
+    #[test]
+    fn test_chat_long_tool_group_starts_collapsed() {
+        let mut messages = vec![Message::assistant("Looking around")];
+        for name in ["read", "read", "glob", "read", "edit"] {
+            messages.push(tool_message(name, "ok"));
+        }
+        messages.push(Message::assistant("Done"));
+        let mut chat = Chat::with_messages(messages);
+
+        let lines = rendered_text(&chat, 80);
+        let header = lines
+            .iter()
+            .position(|l| l.contains("Ran 5 tools"))
+            .unwrap();
+        assert_eq!(lines[header], "▸ ✓ Ran 5 tools · Read ×3, Glob, Edit");
+        assert!(!lines.iter().any(|l| l.starts_with("✓ Read")));
+
+        chat.toggle_tool_group(3);
+        let lines = rendered_text(&chat, 80);
+        assert!(lines[header].starts_with("▾ ✓ Ran 5 tools"));
+        assert_eq!(lines.iter().filter(|l| l.starts_with("✓ Read")).count(), 3);
+    }
+
+    #[test]
+    fn test_chat_running_tool_shows_its_latest_output() {
+        let output: String = (1..=8)
+            .map(|i| format!("\x1b[32mline {}\x1b[0m\n", i))
+            .collect();
+        let mut row = serde_json::json!({ "name": "bash", "status": "running" });
+        crate::llm::ToolCall::append_live_output(&mut row, &output);
+        let chat = Chat::with_messages(vec![Message::tool(row.to_string())]);
+        let lines = rendered_text(&chat, 80);
+        assert!(lines[0].starts_with("~ Bash"));
+        assert_eq!(lines[1], "    line 4");
+        assert_eq!(lines[5], "    line 8");
+
+        // Once the result is in, only the row is left
+        row["status"] = "ok".into();
+        let chat = Chat::with_messages(vec![Message::tool(row.to_string())]);
+        let lines = rendered_text(&chat, 80);
+        assert!(!lines.iter().any(|l| l.contains("line 8")));
+    }
+
+    #[test]
+    fn test_chat_short_tool_group_starts_expanded() {
+        let chat = Chat::with_messages(vec![
+            Message::user("fix it"),
+            tool_message("read", "ok"),
+            tool_message("bash", "error"),
+        ]);
+        let lines = rendered_text(&chat, 80);
+        assert!(lines.contains(&"▾ ✗ Ran 2 tools · Read, Bash · 1 failed".to_string()));
+        assert!(lines.iter().any(|l| l.starts_with("✗ Bash")));
+
+        // A single call has no header
+        let chat = Chat::with_messages(vec![tool_message("read", "running")]);
+        let lines = rendered_text(&chat, 80);
+        assert!(lines[0].starts_with("~ Read"));
+    }
+
+    #[test]
+    fn test_chat_click_toggles_tool_group() {
+        let mut messages = vec![Message::user("go")];
+        messages.extend((0..4).map(|_| tool_message("read", "ok")));
+        let mut chat = Chat::with_messages(messages);
+        let colors = test_colors();
+        chat.calculate_content_height(80, "test-model", &colors);
+        let header = rendered_text(&chat, 80)
+            .iter()
+            .position(|l| l.contains("Ran 4 tools"))
+            .unwrap();
+        assert_eq!(chat.tool_group_headers, vec![(header, 1)]);
+
+        let area = Rect::new(0, 0, 81, 40);
+        let click = MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 2,
+            row: header as u16,
+            modifiers: ratatui::crossterm::event::KeyModifiers::NONE,
+        };
+        chat.scroll_offset = 0;
+        assert!(chat.handle_mouse_event(click, area));
+        assert!(chat.is_tool_group_expanded(1, 4));
+    }
+
+    fn plan_message(todos: serde_json::Value) -> Message {
+        Message::tool(
+            serde_json::json!({ "name": "todowrite", "status": "ok", "args": { "todos": todos } })
+                .to_string(),
+        )
+    }
+
+    #[test]
+    fn test_chat_renders_latest_plan_as_checklist() {
+        let chat = Chat::with_messages(vec![
+            plan_message(serde_json::json!([
+                { "content": "Read the loader", "status": "in_progress" },
+                { "content": "Add the flag", "status": "pending" },
+            ])),
+            tool_message("read", "ok"),
+            tool_message("read", "ok"),
+            plan_message(serde_json::json!([
+                { "content": "Read the loader", "status": "completed" },
+                { "content": "Add the flag", "status": "in_progress" },
+                { "content": "Write tests", "status": "pending" },
+                { "content": "Old approach", "status": "cancelled" },
+            ])),
+        ]);
+        let lines = rendered_text(&chat, 80);
+
+        assert_eq!(lines[0], "✓ Plan updated · 0/2 done");
+        let header = lines.iter().position(|l| l.starts_with("Plan ")).unwrap();
+        assert_eq!(
+            lines[header],
+            format!("Plan {}{} 1/3", "━".repeat(6), "─".repeat(14))
+        );
+        assert_eq!(
+            lines[header + 1..header + 5],
+            [
+                "  ✓ Read the loader",
+                "  ▸ Add the flag",
+                "  ☐ Write tests",
+                "  ✗ Old approach"
+            ]
+        );
+        // Plans aren't folded into the tool group around them
+        assert!(lines.iter().any(|l| l.contains("Ran 2 tools")));
+    }
+
+    #[test]
+    fn test_chat_bookmark_navigation() {
+        let mut chat = Chat::with_messages(
+            (0..6)
+                .map(|i| Message::user(format!("message {}", i)))
+                .collect(),
+        );
+        chat.older_messages_available = 10;
+        chat.bookmarks = vec![3, 11, 14];
+        chat.viewport_height = 4;
+        chat.content_height = chat.calculate_content_height(40, "test-model", &test_colors());
+
+        // Each message is its line plus a blank one, below the "load more" row
+        assert_eq!(chat.message_rows, vec![2, 4, 6, 8, 10, 12]);
+        assert_eq!(chat.message_at_top(), Some(10));
+        assert_eq!(chat.adjacent_bookmark(true), Some(11));
+        assert_eq!(chat.adjacent_bookmark(false), Some(3));
+
+        // Older bookmarks have to be loaded first
+        assert!(!chat.scroll_to_message(3));
+        assert!(chat.scroll_to_message(12));
+        chat.apply_scroll_target();
+        assert_eq!(chat.scroll_offset, 6);
+        assert_eq!(chat.message_at_top(), Some(12));
+        assert_eq!(chat.adjacent_bookmark(true), Some(14));
+        assert_eq!(chat.adjacent_bookmark(false), Some(11));
+
+        // Bookmarks near the end scroll as far as they can
+        assert!(chat.scroll_to_message(15));
+        chat.apply_scroll_target();
+        assert_eq!(chat.scroll_offset, 10);
+    }
+
+    #[test]
+    fn test_chat_reuses_rendered_lines() {
+        let colors = test_colors();
+        let mut messages = Vec::new();
+        for i in 0..250 {
+            messages.push(Message::user(format!("question {}", i)));
+            messages.push(Message::assistant(format!(
+                "# Answer {}\n\n- one\n- two\n\n```rust\nfn main() {{}}\n```",
+                i
+            )));
+        }
+        let mut chat = Chat::with_messages(messages);
+
+        let height = chat.calculate_content_height(80, "test-model", &colors);
+        assert_eq!(chat.renders, 500);
+        let lines = rendered_text(&chat, 80);
+
+        // Nothing changed: no message is laid out again
+        assert_eq!(
+            chat.calculate_content_height(80, "test-model", &colors),
+            height
+        );
+        assert_eq!(chat.renders, 500);
+        assert_eq!(rendered_text(&chat, 80), lines);
+
+        // The new message, and the reply before it whose footer depends on
+        // what follows
+        chat.add_message(Message::user("one more"));
+        chat.calculate_content_height(80, "test-model", &colors);
+        assert_eq!(chat.renders, 502);
+
+        chat.calculate_content_height(60, "test-model", &colors);
+        assert_eq!(chat.renders, 1_003);
+        chat.density = Density::Compact;
+        chat.calculate_content_height(60, "test-model", &colors);
+        assert_eq!(chat.renders, 1_504);
+    }
+
+    #[test]
+    fn test_chat_compact_density_drops_spacers() {
+        let mut chat = Chat::with_messages(vec![
+            Message::user("question"),
+            Message::assistant("answer"),
+            Message::user("follow-up"),
+        ]);
+        let colors = test_colors();
+        let comfortable = rendered_text(&chat, 40);
+        assert!(comfortable.iter().any(|l| l.is_empty()));
+
+        chat.density = Density::Compact;
+        let compact = rendered_text(&chat, 40);
+        assert!(compact.len() < comfortable.len());
+        assert!(
+            !compact.iter().any(|l| l.trim().is_empty()),
+            "{:?}",
+            compact
+        );
+        // The footer is still there, right under its reply
+        assert!(compact[2].contains("test-model"), "{:?}", compact);
+        assert_eq!(
+            chat.calculate_content_height(40, "test-model", &colors),
+            compact.len()
+        );
+        assert_eq!(chat.message_rows, vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn test_chat_copy_mode_selects_rendered_rows() {
+        let mut chat = Chat::with_messages(vec![
+            Message::user("first question"),
+            Message::assistant("an answer"),
+            Message::user("second question"),
+        ]);
+        chat.older_messages_available = 4;
+        chat.viewport_height = 3;
+        chat.content_width = 40;
+        let colors = test_colors();
+        chat.content_height = chat.calculate_content_height(40, "test-model", &colors);
+        chat.scroll_to_bottom();
+        // "load more" row and blank, then 2 + 4 + 2 rows of messages
+        assert_eq!(chat.message_rows, vec![2, 4, 8]);
+        assert_eq!(chat.content_height, 10);
+
+        // Screen rows map to content rows through the scroll offset
+        let area = Rect::new(0, 5, 40, 3);
+        assert_eq!(chat.content_row_at(5, area), Some(7));
+        assert_eq!(chat.content_row_at(7, area), Some(9));
+        assert_eq!(chat.content_row_at(8, area), None);
+
+        assert!(chat.begin_copy_mode());
+        assert_eq!(chat.selected_rows(), Some(9..=9));
+        chat.move_copy_cursor(-1);
+        assert_eq!(chat.selected_text("test-model", &colors), "second question");
+
+        // Going above the viewport scrolls it
+        chat.move_copy_cursor(-6);
+        assert_eq!(chat.selected_rows(), Some(2..=9));
+        assert_eq!(chat.scroll_offset, 2);
+        let text = chat.selected_text("test-model", &colors);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "first question");
+        assert_eq!(lines[2], "an answer");
+        assert_eq!(lines.last(), Some(&"second question"));
+
+        // The rows above the first message aren't copied
+        chat.move_copy_cursor(-100);
+        assert_eq!(chat.selected_rows(), Some(0..=9));
+        assert!(chat
+            .selected_text("test-model", &colors)
+            .starts_with("first question"));
+
+        chat.end_copy_mode();
+        assert!(!chat.is_copy_mode());
+        assert_eq!(chat.selected_text("test-model", &colors), "");
+    }
+
+    #[test]
+    fn test_chat_thinking_placeholder_until_first_token() {
+        let mut chat = Chat::new();
+        chat.add_message(Message::incomplete(""));
+        chat.begin_streaming_turn();
+        assert!(chat.is_awaiting_first_token());
+
+        let lines = rendered_text(&chat, 40);
+        assert!(lines.iter().any(|l| l.contains("thinking")));
+
+        chat.append_to_last_assistant("Hello there");
+        assert!(!chat.is_awaiting_first_token());
+        let lines = rendered_text(&chat, 40);
+        assert!(!lines.iter().any(|l| l.contains("thinking")));
+        assert!(lines.iter().any(|l| l.contains("Hello there")));
+    }
+
+    #[test]
+    fn test_chat_thinking_tick_does_not_touch_metrics() {
+        let mut chat = Chat::new();
+        chat.add_message(Message::incomplete(""));
+        chat.begin_streaming_turn();
+
+        for _ in 0..10 {
+            chat.tick_thinking_indicator();
+        }
+        assert_eq!(chat.thinking_frame, 10);
+        assert_eq!(chat.streaming_token_count, 0);
+        assert!(chat.streaming_first_token_time.is_none());
+
+        chat.append_to_last_assistant("hi");
+        chat.tick_thinking_indicator();
+        assert_eq!(chat.thinking_frame, 10);
+    }
+
+    #[test]
+    fn test_chat_user_message_lines_have_consistent_width() {
+        let chat = Chat::with_messages(vec![Message::user(
+            "こんにちは世界 🦀 crabs are great 你好 emoji 🎉🎉 and plain ascii words here",
+        )]);
+        let colors = test_colors();
+        let lines = chat.render_visible_messages(30, "test-model", &colors);
+
+        let widths: Vec<usize> = lines
+            .iter()
+            .filter(|line| !line.spans.is_empty() && line.width() > 0)
+            .map(|line| line.width())
+            .collect();
+        assert!(widths.len() > 1);
+        assert!(widths.iter().all(|&w| w == 29), "{:?}", widths);
+    }
+
+    #[test]
+    fn test_chat_counts_new_lines_while_scrolled_up() {
+        let mut chat = Chat::new();
+        chat.content_height = 40;
+        chat.viewport_height = 10;
+        chat.scroll_offset = 30;
+
+        chat.scroll_up(5);
+        chat.track_unseen_content(40, 0);
+        assert_eq!(chat.new_lines_below(), 0);
+        assert!(chat.jump_indicator_text().is_none());
+
+        chat.content_height = 47;
+        chat.track_unseen_content(40, 0);
+        assert_eq!(chat.new_lines_below(), 7);
+        assert_eq!(
+            chat.jump_indicator_text().as_deref(),
+            Some(" ↓ 7 new lines · End ")
+        );
+
+        chat.scroll_to_bottom();
+        chat.track_unseen_content(47, 0);
+        assert_eq!(chat.new_lines_below(), 0);
+    }
+
+    #[test]
+    fn test_chat_prepended_lines_are_not_new() {
+        let mut chat = Chat::new();
+        chat.content_height = 40;
+        chat.viewport_height = 10;
+        chat.scroll_up(40);
+        chat.track_unseen_content(40, 0);
+
+        chat.content_height = 60;
+        chat.track_unseen_content(40, 20);
+        assert_eq!(chat.new_lines_below(), 0);
+    }
+
+    #[test]
+    fn test_chat_page_up_down() {
+        let mut chat = Chat::new();
+        chat.content_height = 100;
+        chat.viewport_height = 20;
+        chat.scroll_offset = 80;
+
+        chat.page_up();
+        assert_eq!(chat.scroll_offset, 61);
+        assert!(chat.user_scrolled_up);
+
+        chat.page_up();
+        chat.page_up();
+        chat.page_up();
+        assert_eq!(chat.scroll_offset, 4);
+        chat.page_up();
+        assert_eq!(chat.scroll_offset, 0);
+
+        chat.page_down();
+        assert_eq!(chat.scroll_offset, 19);
+        assert!(chat.user_scrolled_up);
+
+        for _ in 0..5 {
+            chat.page_down();
+        }
+        assert_eq!(chat.scroll_offset, 80);
+        assert!(!chat.user_scrolled_up);
+    }
+
+    #[test]
+    fn test_chat_page_size_with_tiny_viewport() {
+        let mut chat = Chat::new();
+        chat.content_height = 10;
+        chat.viewport_height = 1;
+        chat.scroll_offset = 5;
+        chat.page_up();
+        assert_eq!(chat.scroll_offset, 4);
+    }
+
+    #[test]
+    fn test_chat_scroll_to_top_and_bottom() {
+        let mut chat = Chat::new();
+        chat.content_height = 50;
+        chat.viewport_height = 10;
+        chat.scroll_offset = 40;
+
+        chat.scroll_to_top();
+        assert_eq!(chat.scroll_offset, 0);
+        assert!(chat.user_scrolled_up);
+
+        chat.scroll_to_bottom();
+        assert_eq!(chat.scroll_offset, 40);
+        assert!(!chat.user_scrolled_up);
+    }
+
+    #[test]
+    fn test_chat_near_bottom_counts_as_bottom() {
+        let mut chat = Chat::new();
+        chat.content_height = 100;
+        chat.viewport_height = 20;
+        chat.scroll_offset = 70;
+        chat.scroll_up(1);
+
+        // Two lines short of the bottom resumes autoscroll
+        chat.scroll_down(7);
+        assert_eq!(chat.scroll_offset, 76);
+        assert!(chat.user_scrolled_up);
+        chat.scroll_down(2);
+        assert_eq!(chat.scroll_offset, 78);
+        assert!(!chat.user_scrolled_up);
+
+        chat.add_user_message("test");
+        assert_eq!(chat.scroll_offset, usize::MAX);
+    }
+
+    #[test]
+    fn test_chat_bottom_threshold_zero_needs_exact_bottom() {
+        let mut chat = Chat::new();
+        chat.bottom_threshold = 0;
+        chat.content_height = 100;
+        chat.viewport_height = 20;
+        chat.scroll_offset = 70;
+
+        chat.scroll_down(9);
+        assert!(chat.user_scrolled_up);
+        chat.scroll_down(1);
+        assert!(!chat.user_scrolled_up);
+    }
+
+    #[test]
+    fn test_chat_scrollbar_drag_near_bottom() {
+        let mut chat = Chat::new();
+        chat.content_height = 120;
+        chat.viewport_height = 20;
+        let scrollbar = Rect::new(79, 0, 1, 50);
+
+        // Row 48 of 50 maps to offset 96 of 100
+        chat.scroll_to_position(48, scrollbar);
+        assert_eq!(chat.scroll_offset, 96);
+        assert!(chat.user_scrolled_up);
+
+        chat.bottom_threshold = 4;
+        chat.scroll_to_position(48, scrollbar);
+        assert!(!chat.user_scrolled_up);
+    }
+
+    #[test]
+    fn test_chat_scroll_to_top_when_content_fits() {
+        let mut chat = Chat::new();
+        chat.content_height = 5;
+        chat.viewport_height = 10;
+        chat.scroll_to_top();
+        assert_eq!(chat.scroll_offset, 0);
+        assert!(!chat.user_scrolled_up);
+    }
+
+    #[test]
+    fn test_chat_wheel_uses_configured_step() {
+        let mut chat = Chat::new();
+        chat.content_height = 100;
+        chat.viewport_height = 10;
+        chat.scroll_offset = 50;
+        chat.scroll_prefs.step = 5;
+
+        let area = Rect::new(0, 0, 40, 10);
+        let wheel = |kind| MouseEvent {
+            kind,
+            column: 5,
+            row: 5,
+            modifiers: ratatui::crossterm::event::KeyModifiers::NONE,
+        };
+
+        chat.handle_mouse_event(wheel(MouseEventKind::ScrollUp), area);
+        assert_eq!(chat.scroll_offset, 45);
+        chat.handle_mouse_event(wheel(MouseEventKind::ScrollDown), area);
+        assert_eq!(chat.scroll_offset, 50);
+
+        chat.scroll_prefs.smooth = true;
+        chat.handle_mouse_event(wheel(MouseEventKind::ScrollUp), area);
+        assert_eq!(chat.scroll_offset, 49);
+    }
+
+    #[test]
+    fn test_chat_wheel_acceleration() {
+        let mut chat = Chat::new();
+        chat.scroll_prefs.accelerate = true;
+        let start = std::time::Instant::now();
+        let ms = std::time::Duration::from_millis;
+
+        assert_eq!(chat.wheel_step(true, start), 3);
+        assert_eq!(chat.wheel_step(true, start + ms(20)), 3);
+        assert_eq!(chat.wheel_step(true, start + ms(40)), 6);
+        assert_eq!(chat.wheel_step(true, start + ms(60)), 6);
+        assert_eq!(chat.wheel_step(true, start + ms(80)), 9);
+
+        // Changing direction or pausing resets the streak
+        assert_eq!(chat.wheel_step(false, start + ms(90)), 3);
+        assert_eq!(chat.wheel_step(false, start + ms(500)), 3);
+    }
+
+    #[test]
+    fn test_chat_wheel_without_acceleration_is_constant() {
+        let mut chat = Chat::new();
+        let start = std::time::Instant::now();
+        for i in 0..10 {
+            let at = start + std::time::Duration::from_millis(i * 10);
+            assert_eq!(chat.wheel_step(true, at), 3);
+        }
+    }
+
     #[test]
     fn test_chat_clone() {
         let mut chat1 = Chat::new();
@@ -0,0 +1,356 @@
+use crate::theme::ThemeColors;
+use crate::ui::keymap::KeyHint;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    prelude::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Clear, Paragraph, Wrap},
+    Frame,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfirmAction {
+    Confirmed,
+    Cancelled,
+    Handled,
+    NotHandled,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfirmDialog {
+    pub visible: bool,
+    pub title: String,
+    pub message: String,
+    pub confirm_label: String,
+    pub cancel_label: String,
+    /// `true` when the confirm button is highlighted. Defaults to cancel so a
+    /// stray Enter never triggers the destructive action.
+    pub confirm_selected: bool,
+}
+
+impl ConfirmDialog {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            title: String::new(),
+            message: String::new(),
+            confirm_label: "Yes".to_string(),
+            cancel_label: "No".to_string(),
+            confirm_selected: false,
+        }
+    }
+
+    pub fn show(&mut self, title: impl Into<String>, message: impl Into<String>) {
+        self.title = title.into();
+        self.message = message.into();
+        self.confirm_selected = false;
+        self.visible = true;
+    }
+
+    pub fn with_labels(
+        mut self,
+        confirm_label: impl Into<String>,
+        cancel_label: impl Into<String>,
+    ) -> Self {
+        self.confirm_label = confirm_label.into();
+        self.cancel_label = cancel_label.into();
+        self
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+        self.title.clear();
+        self.message.clear();
+        self.confirm_selected = false;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn toggle_selection(&mut self) {
+        self.confirm_selected = !self.confirm_selected;
+    }
+
+    /// The keys `handle_key_event` acts on, for the help overlay.
+    pub const KEYS: &'static [KeyHint] = &[
+        KeyHint::new(KeyCode::Char('y'), "Confirm"),
+        KeyHint::new(KeyCode::Char('n'), "Cancel"),
+        KeyHint::new(KeyCode::Left, "Switch button"),
+        KeyHint::new(KeyCode::Right, "Switch button"),
+        KeyHint::new(KeyCode::Enter, "Press the selected button"),
+    ];
+
+    pub fn handle_key_event(&mut self, event: KeyEvent) -> ConfirmAction {
+        if !self.visible {
+            return ConfirmAction::NotHandled;
+        }
+
+        match event.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                self.hide();
+                ConfirmAction::Confirmed
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.hide();
+                ConfirmAction::Cancelled
+            }
+            KeyCode::Enter => {
+                let confirmed = self.confirm_selected;
+                self.hide();
+                if confirmed {
+                    ConfirmAction::Confirmed
+                } else {
+                    ConfirmAction::Cancelled
+                }
+            }
+            KeyCode::Left
+            | KeyCode::Right
+            | KeyCode::Tab
+            | KeyCode::BackTab
+            | KeyCode::Char('h')
+            | KeyCode::Char('l') => {
+                self.toggle_selection();
+                ConfirmAction::Handled
+            }
+            _ => ConfirmAction::Handled,
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, colors: &ThemeColors) {
+        if !self.visible {
+            return;
+        }
+
+        const DIALOG_WIDTH: u16 = 50;
+        const DIALOG_HEIGHT: u16 = 9;
+
+        let dialog_width = area.width.min(DIALOG_WIDTH);
+        let dialog_height = area.height.min(DIALOG_HEIGHT);
+
+        let dialog_area = Rect {
+            x: area.width.saturating_sub(dialog_width) / 2,
+            y: area.height.saturating_sub(dialog_height) / 2,
+            width: dialog_width,
+            height: dialog_height,
+        };
+
+        frame.render_widget(Clear, dialog_area);
+
+        frame.render_widget(
+            Paragraph::new("").style(Style::default().bg(colors.background)),
+            dialog_area,
+        );
+
+        const PADDING: u16 = 2;
+        let content_area = Rect {
+            x: dialog_area.x + PADDING,
+            y: dialog_area.y + 1,
+            width: dialog_area.width.saturating_sub(PADDING * 2),
+            height: dialog_area.height.saturating_sub(2),
+        };
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Min(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+            ])
+            .split(content_area);
+
+        let title_line = Line::from(vec![
+            Span::styled(
+                self.title.clone(),
+                Style::default()
+                    .fg(colors.text_strong)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(" "),
+            Span::styled(
+                "esc",
+                Style::default()
+                    .fg(colors.primary)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ]);
+        frame.render_widget(Paragraph::new(title_line), chunks[0]);
+
+        frame.render_widget(
+            Paragraph::new(self.message.clone())
+                .style(Style::default().fg(colors.text))
+                .wrap(Wrap { trim: true }),
+            chunks[2],
+        );
+
+        let button_style = |selected: bool| {
+            if selected {
+                Style::default()
+                    .fg(colors.background)
+                    .bg(colors.primary)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(colors.text)
+            }
+        };
+
+        let buttons_line = Line::from(vec![
+            Span::styled(
+                format!(" {} ", self.confirm_label),
+                button_style(self.confirm_selected),
+            ),
+            Span::raw("  "),
+            Span::styled(
+                format!(" {} ", self.cancel_label),
+                button_style(!self.confirm_selected),
+            ),
+        ]);
+        frame.render_widget(Paragraph::new(buttons_line), chunks[3]);
+
+        let footer_line = Line::from(vec![Span::styled(
+            "y confirm  n cancel  ←/→ select",
+            Style::default()
+                .fg(colors.text_weak)
+                .add_modifier(Modifier::DIM),
+        )]);
+        frame.render_widget(Paragraph::new(footer_line), chunks[4]);
+    }
+}
+
+impl Default for ConfirmDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::crossterm::event::{KeyEventKind, KeyEventState, KeyModifiers};
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent {
+            code,
+            modifiers: KeyModifiers::empty(),
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }
+    }
+
+    #[test]
+    fn test_confirm_dialog_creation() {
+        let dialog = ConfirmDialog::new();
+        assert!(!dialog.is_visible());
+        assert!(!dialog.confirm_selected);
+        assert_eq!(dialog.confirm_label, "Yes");
+        assert_eq!(dialog.cancel_label, "No");
+    }
+
+    #[test]
+    fn test_show_and_hide() {
+        let mut dialog = ConfirmDialog::new();
+        dialog.show("Delete session", "Delete \"foo\"?");
+        assert!(dialog.is_visible());
+        assert_eq!(dialog.title, "Delete session");
+        assert_eq!(dialog.message, "Delete \"foo\"?");
+
+        dialog.hide();
+        assert!(!dialog.is_visible());
+        assert!(dialog.title.is_empty());
+    }
+
+    #[test]
+    fn test_not_visible_is_not_handled() {
+        let mut dialog = ConfirmDialog::new();
+        assert_eq!(
+            dialog.handle_key_event(key(KeyCode::Char('y'))),
+            ConfirmAction::NotHandled
+        );
+    }
+
+    #[test]
+    fn test_y_confirms() {
+        let mut dialog = ConfirmDialog::new();
+        dialog.show("Title", "Message");
+        assert_eq!(
+            dialog.handle_key_event(key(KeyCode::Char('y'))),
+            ConfirmAction::Confirmed
+        );
+        assert!(!dialog.is_visible());
+    }
+
+    #[test]
+    fn test_esc_and_n_cancel() {
+        let mut dialog = ConfirmDialog::new();
+        dialog.show("Title", "Message");
+        assert_eq!(
+            dialog.handle_key_event(key(KeyCode::Esc)),
+            ConfirmAction::Cancelled
+        );
+        assert!(!dialog.is_visible());
+
+        dialog.show("Title", "Message");
+        assert_eq!(
+            dialog.handle_key_event(key(KeyCode::Char('n'))),
+            ConfirmAction::Cancelled
+        );
+    }
+
+    #[test]
+    fn test_enter_defaults_to_cancel() {
+        let mut dialog = ConfirmDialog::new();
+        dialog.show("Title", "Message");
+        assert_eq!(
+            dialog.handle_key_event(key(KeyCode::Enter)),
+            ConfirmAction::Cancelled
+        );
+    }
+
+    #[test]
+    fn test_enter_after_toggle_confirms() {
+        let mut dialog = ConfirmDialog::new();
+        dialog.show("Title", "Message");
+        assert_eq!(
+            dialog.handle_key_event(key(KeyCode::Left)),
+            ConfirmAction::Handled
+        );
+        assert!(dialog.confirm_selected);
+        assert_eq!(
+            dialog.handle_key_event(key(KeyCode::Enter)),
+            ConfirmAction::Confirmed
+        );
+    }
+
+    #[test]
+    fn test_show_resets_selection() {
+        let mut dialog = ConfirmDialog::new();
+        dialog.show("Title", "Message");
+        dialog.toggle_selection();
+        dialog.hide();
+        dialog.show("Title", "Message");
+        assert!(!dialog.confirm_selected);
+    }
+
+    #[test]
+    fn test_render_uses_theme_colors() {
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let colors = ThemeColors::for_tests();
+        let mut dialog = ConfirmDialog::new();
+        dialog.show("Title", "Message");
+        let mut terminal = Terminal::new(TestBackend::new(60, 12)).unwrap();
+        terminal
+            .draw(|f| dialog.render(f, f.area(), &colors))
+            .unwrap();
+
+        // "No" is selected, so it's drawn on the accent color
+        let buffer = terminal.backend().buffer();
+        let cell = buffer.content().iter().find(|c| c.symbol() == "N").unwrap();
+        assert_eq!(cell.bg, colors.primary);
+        assert_eq!(cell.fg, colors.background);
+    }
+}
@@ -1,4 +1,6 @@
 use crate::theme::ThemeColors;
+use crate::ui::boundary::render_guarded;
+use crate::ui::keymap::KeyHint;
 use nucleo_matcher::{
     pattern::{CaseMatching, Normalization, Pattern},
     Config, Matcher,
@@ -17,6 +19,21 @@ use std::collections::HashMap;
 use tui_textarea::{Input as TuiInput, TextArea};
 use unicode_width::UnicodeWidthStr;
 
+/// The dialog box: up to 70x25, centered in `area`.
+fn centered(area: Rect) -> Rect {
+    const DIALOG_WIDTH: u16 = 70;
+    const DIALOG_HEIGHT: u16 = 25;
+
+    let width = area.width.min(DIALOG_WIDTH);
+    let height = area.height.min(DIALOG_HEIGHT);
+    Rect {
+        x: area.x + area.width.saturating_sub(width) / 2,
+        y: area.y + area.height.saturating_sub(height) / 2,
+        width,
+        height,
+    }
+}
+
 #[derive(Debug)]
 pub struct DialogItem {
     pub id: String,
@@ -64,6 +81,8 @@ pub struct Dialog {
     pub is_dragging_scrollbar: bool,
     pub visible_row_count: usize,
     pub actions: Vec<DialogAction>,
+    /// Also match the search query against item descriptions (e.g. session tags).
+    pub search_descriptions: bool,
     matcher: Matcher,
 }
 
@@ -89,6 +108,7 @@ impl Dialog {
             is_dragging_scrollbar: false,
             visible_row_count: 0,
             actions: Vec::new(),
+            search_descriptions: false,
             matcher: Matcher::new(Config::DEFAULT),
         }
     }
@@ -104,6 +124,12 @@ impl Dialog {
         self
     }
 
+    pub fn with_searchable_descriptions(mut self) -> Self {
+        self.search_descriptions = true;
+        self.apply_filter();
+        self
+    }
+
     pub fn set_items(&mut self, items: Vec<DialogItem>) {
         self.items = items;
         self.group_items();
@@ -131,7 +157,7 @@ impl Dialog {
                 .push(item.clone());
         }
 
-        const SPECIAL_GROUPS: &[&str] = &["Favorite", "Recent", "Popular", "Other"];
+        const SPECIAL_GROUPS: &[&str] = &["Pinned", "Favorite", "Recent", "Popular", "Other"];
         let mut special: Vec<String> = Vec::new();
         let mut regular: Vec<String> = Vec::new();
 
@@ -213,7 +239,7 @@ impl Dialog {
 
                 let combined_strings: Vec<String> = items
                     .iter()
-                    .map(|item| format!("{} {}", group, item.name))
+                    .map(|item| self.search_text(group, item))
                     .collect();
 
                 let matched: Vec<(&str, u32)> = pattern.match_list(
@@ -227,7 +253,7 @@ impl Dialog {
                         .filter_map(|(combined_str, score)| {
                             items
                                 .iter()
-                                .find(|item| format!("{} {}", group, item.name) == *combined_str)
+                                .find(|item| self.search_text(group, item) == *combined_str)
                                 .map(|item| (item.clone(), score))
                         })
                         .collect();
@@ -245,6 +271,14 @@ impl Dialog {
         self.update_scrollbar();
     }
 
+    fn search_text(&self, group: &str, item: &DialogItem) -> String {
+        if self.search_descriptions && !item.description.is_empty() {
+            format!("{} {} {}", group, item.name, item.description)
+        } else {
+            format!("{} {}", group, item.name)
+        }
+    }
+
     fn update_scrollbar(&mut self) {
         let total_lines = self.get_content_line_count();
         let visible_rows = self.get_visible_row_count().max(1);
@@ -373,6 +407,21 @@ impl Dialog {
         }
     }
 
+    /// Moves the selection to the item with this id and provider, if it's
+    /// currently listed.
+    pub fn select_item(&mut self, provider_id: &str, id: &str) -> bool {
+        let Some(index) = self
+            .get_flat_items()
+            .iter()
+            .position(|item| item.provider_id == provider_id && item.id == id)
+        else {
+            return false;
+        };
+        self.selected_index = index;
+        self.adjust_scroll();
+        true
+    }
+
     pub fn get_selected(&self) -> Option<&DialogItem> {
         let flat_items = self.get_flat_items();
         flat_items.get(self.selected_index).copied()
@@ -382,6 +431,14 @@ impl Dialog {
         self.visible
     }
 
+    /// The keys `handle_key_event` acts on, besides typing to filter.
+    pub const KEYS: &'static [KeyHint] = &[
+        KeyHint::new(KeyCode::Up, "Move the selection up"),
+        KeyHint::new(KeyCode::Down, "Move the selection down"),
+        KeyHint::new(KeyCode::Enter, "Select"),
+        KeyHint::new(KeyCode::Esc, "Close"),
+    ];
+
     pub fn handle_key_event(&mut self, event: KeyEvent) -> bool {
         if !self.visible {
             return false;
@@ -448,7 +505,7 @@ impl Dialog {
 
         let list_area = chunks[3];
         let scrollbar_area = Rect {
-            x: list_area.x + list_area.width - 1,
+            x: list_area.x + list_area.width.saturating_sub(1),
             y: list_area.y,
             width: 1,
             height: list_area.height,
@@ -566,24 +623,21 @@ impl Dialog {
         self.update_scrollbar();
     }
 
+    /// Draws the dialog centered in `area`. If drawing it panics, the dialog
+    /// box shows a placeholder instead.
     pub fn render(&mut self, frame: &mut Frame, area: Rect, colors: ThemeColors) {
         if !self.visible {
             return;
         }
 
-        const DIALOG_WIDTH: u16 = 70;
-        const DIALOG_HEIGHT: u16 = 25;
-
-        let dialog_width = area.width.min(DIALOG_WIDTH);
-        let dialog_height = area.height.min(DIALOG_HEIGHT);
-
-        self.dialog_area = Rect {
-            x: (area.width - dialog_width) / 2,
-            y: (area.height - dialog_height) / 2,
-            width: dialog_width,
-            height: dialog_height,
-        };
+        self.dialog_area = centered(area);
+        let dialog_area = self.dialog_area;
+        render_guarded(frame, dialog_area, "dialog", &colors, |frame| {
+            self.render_dialog(frame, colors)
+        });
+    }
 
+    fn render_dialog(&mut self, frame: &mut Frame, colors: ThemeColors) {
         frame.render_widget(Clear, self.dialog_area);
 
         const PADDING: u16 = 3;
@@ -756,15 +810,18 @@ impl Dialog {
             Paragraph::new(content_lines).scroll((self.scroll_offset as u16, 0));
         frame.render_widget(content_paragraph, list_content_area);
 
+        // The scrollbar panics on a zero-width area that still has rows
         let scrollbar_area = chunks[3];
-        frame.render_stateful_widget(
-            Scrollbar::new(ScrollbarOrientation::VerticalRight)
-                .begin_symbol(Some("↑"))
-                .end_symbol(Some("↓"))
-                .track_symbol(Some(" ")),
-            scrollbar_area,
-            &mut self.scrollbar_state,
-        );
+        if !scrollbar_area.is_empty() {
+            frame.render_stateful_widget(
+                Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                    .begin_symbol(Some("↑"))
+                    .end_symbol(Some("↓"))
+                    .track_symbol(Some(" ")),
+                scrollbar_area,
+                &mut self.scrollbar_state,
+            );
+        }
 
         let mut footer_spans = vec![];
         for (i, action) in self.actions.iter().enumerate() {
@@ -851,6 +908,7 @@ impl Clone for Dialog {
             is_dragging_scrollbar: self.is_dragging_scrollbar,
             visible_row_count: self.visible_row_count,
             actions: self.actions.clone(),
+            search_descriptions: self.search_descriptions,
             matcher: Matcher::new(Config::DEFAULT),
         }
     }
@@ -859,6 +917,24 @@ impl Clone for Dialog {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ratatui::{backend::TestBackend, buffer::Buffer, Terminal};
+
+    /// Screen column where `needle` (ASCII) starts on any row, if rendered.
+    fn find_column(buffer: &Buffer, needle: &str) -> Option<u16> {
+        let area = buffer.area;
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let matches = needle.chars().enumerate().all(|(i, c)| {
+                    let cx = x + i as u16;
+                    cx < area.right() && buffer[(cx, y)].symbol() == c.to_string()
+                });
+                if matches {
+                    return Some(x);
+                }
+            }
+        }
+        None
+    }
 
     fn create_test_items() -> Vec<DialogItem> {
         vec![
@@ -912,6 +988,26 @@ mod tests {
         assert_eq!(dialog.groups.len(), 2);
     }
 
+    #[test]
+    fn test_dialog_select_item() {
+        let mut dialog = Dialog::with_items("Models", create_test_items());
+        dialog.show();
+
+        assert!(dialog.select_item("provider2", "3"));
+        assert_eq!(dialog.get_selected().unwrap().name, "Model C");
+        assert!(!dialog.select_item("provider1", "3"));
+        assert_eq!(dialog.selected_index, 2);
+    }
+
+    #[test]
+    fn test_listed_keys_are_handled() {
+        for key in Dialog::KEYS {
+            let mut dialog = Dialog::with_items("Models", create_test_items());
+            dialog.show();
+            assert!(dialog.handle_key_event(key.event()), "{}", key.label());
+        }
+    }
+
     #[test]
     fn test_dialog_set_items() {
         let mut dialog = Dialog::new("Models");
@@ -962,6 +1058,31 @@ mod tests {
         assert_eq!(dialog.filtered_items[0].1[0].name, "Model A");
     }
 
+    #[test]
+    fn test_dialog_search_ignores_descriptions_by_default() {
+        let mut dialog = Dialog::with_items("Models", create_test_items());
+        dialog.set_search_query("Description");
+        assert!(dialog.filtered_items.is_empty());
+    }
+
+    #[test]
+    fn test_dialog_search_descriptions() {
+        let mut items = create_test_items();
+        items[2].description = "#work".to_string();
+        let mut dialog = Dialog::with_items("Sessions", items).with_searchable_descriptions();
+        dialog.set_search_query("#work");
+        assert_eq!(dialog.filtered_items.len(), 1);
+        assert_eq!(dialog.filtered_items[0].1[0].name, "Model C");
+    }
+
+    #[test]
+    fn test_dialog_pinned_group_first() {
+        let mut items = create_test_items();
+        items[2].group = "Pinned".to_string();
+        let dialog = Dialog::with_items("Sessions", items);
+        assert_eq!(dialog.groups[0], "Pinned");
+    }
+
     #[test]
     fn test_dialog_clear_search() {
         let mut dialog = Dialog::with_items("Models", create_test_items());
@@ -1029,4 +1150,73 @@ mod tests {
         assert_eq!(dialog.title, dialog2.title);
         assert_eq!(dialog.items.len(), dialog2.items.len());
     }
+
+    #[test]
+    fn test_render_wide_item_name_keeps_tip_aligned() {
+        let items = vec![
+            DialogItem {
+                id: "1".to_string(),
+                name: "ab".to_string(),
+                group: "Models".to_string(),
+                description: String::new(),
+                tip: Some("tipa".to_string()),
+                provider_id: "p".to_string(),
+            },
+            DialogItem {
+                id: "2".to_string(),
+                name: "模型🦀".to_string(),
+                group: "Models".to_string(),
+                description: String::new(),
+                tip: Some("tipb".to_string()),
+                provider_id: "p".to_string(),
+            },
+        ];
+        let mut dialog = Dialog::with_items("Models", items);
+        dialog.show();
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        terminal
+            .draw(|f| {
+                let area = f.area();
+                dialog.render(f, area, ThemeColors::for_tests());
+            })
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let tip_a = find_column(buffer, "tipa").unwrap();
+        let tip_b = find_column(buffer, "tipb").unwrap();
+        assert_eq!(tip_a, tip_b);
+    }
+
+    #[test]
+    fn test_dialog_renders_in_tiny_areas() {
+        use ratatui::crossterm::event::KeyModifiers;
+
+        // 3x40 leaves the list rows but no columns, which the scrollbar
+        // can't take
+        for (width, height) in [(1, 1), (10, 5), (3, 40)] {
+            let mut dialog = Dialog::with_items("Models", create_test_items());
+            dialog.show();
+
+            let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+            terminal
+                .draw(|f| {
+                    let area = f.area();
+                    dialog.render(f, area, ThemeColors::for_tests());
+                })
+                .unwrap();
+            // No placeholder from the render boundary
+            let buffer = terminal.backend().buffer();
+            assert!(!buffer.content().iter().any(|cell| cell.symbol() == "⚠"));
+
+            for (column, row) in [(0, 0), (width - 1, height - 1)] {
+                dialog.handle_mouse_event(MouseEvent {
+                    kind: MouseEventKind::Down(MouseButton::Left),
+                    column,
+                    row,
+                    modifiers: KeyModifiers::NONE,
+                });
+            }
+        }
+    }
 }
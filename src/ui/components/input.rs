@@ -1,5 +1,8 @@
 use crate::autocomplete::{AutoComplete, Suggestion};
-use crate::persistence::PromptHistoryCache;
+use crate::command::parser::DEFAULT_PREFIX;
+use crate::persistence::{HistoryScope, PromptHistoryCache};
+use crate::session::paste::PastedText;
+use crate::ui::keymap::KeyHint;
 use ratatui::crossterm::event::{
     KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
 };
@@ -14,6 +17,10 @@ pub struct Input {
     viewport_top: usize,
     prompt_history: Option<PromptHistoryCache>,
     draft_text: Option<String>,
+    /// Kept so it survives the textarea being recreated on clear
+    placeholder: String,
+    /// Large pastes sent with the next message; clearing the text keeps them
+    attachments: Vec<PastedText>,
 }
 
 impl Input {
@@ -28,6 +35,8 @@ impl Input {
             viewport_top: 0,
             prompt_history,
             draft_text: None,
+            placeholder: String::new(),
+            attachments: Vec::new(),
         }
     }
 
@@ -82,6 +91,21 @@ impl Input {
 
         frame.render_widget(&self.textarea, chunks[1]);
 
+        if !self.attachments.is_empty() {
+            let labels: Vec<String> = self
+                .attachments
+                .iter()
+                .map(|attachment| format!("[{}]", attachment.label()))
+                .collect();
+            let attachments_line = ratatui::text::Line::styled(
+                labels.join(" "),
+                ratatui::style::Style::default()
+                    .fg(ratatui::style::Color::Rgb(150, 150, 150))
+                    .add_modifier(ratatui::style::Modifier::ITALIC),
+            );
+            frame.render_widget(Paragraph::new(attachments_line), chunks[2]);
+        }
+
         let info_text = ratatui::text::Line::from(vec![
             ratatui::text::Span::styled(
                 agent.to_string(),
@@ -104,6 +128,24 @@ impl Input {
         frame.render_widget(border, area);
     }
 
+    /// The keys `handle_event` acts on, for the help overlay.
+    pub const KEYS: &'static [KeyHint] = &[
+        KeyHint::new(
+            KeyCode::Enter,
+            "Send message or run command (queued while streaming)",
+        ),
+        KeyHint::with(KeyCode::Enter, KeyModifiers::SHIFT, "Insert a newline"),
+        KeyHint::with(KeyCode::Enter, KeyModifiers::ALT, "Insert a newline"),
+        KeyHint::ctrl('j', "Insert a newline"),
+        KeyHint::new(KeyCode::Up, "Previous prompt (on the first line)"),
+        KeyHint::new(KeyCode::Down, "Next prompt (on the last line)"),
+        KeyHint::ctrl('u', "Delete to the start of the line"),
+        KeyHint::new(
+            KeyCode::Backspace,
+            "Remove the last pasted attachment (when the input is empty)",
+        ),
+    ];
+
     pub fn handle_event(&mut self, event: KeyEvent) -> bool {
         let input = TuiInput::from(event);
 
@@ -125,6 +167,15 @@ impl Input {
             return true;
         }
 
+        // Backspace on an empty input drops the last attachment
+        if event.code == KeyCode::Backspace
+            && event.modifiers == KeyModifiers::NONE
+            && self.is_empty()
+            && self.attachments.pop().is_some()
+        {
+            return true;
+        }
+
         // Regular Enter submits
         if event.code == KeyCode::Enter && event.modifiers == KeyModifiers::NONE {
             self.save_current_to_history();
@@ -306,20 +357,36 @@ impl Input {
         }
     }
 
+    /// What commands typed here start with.
+    pub fn command_prefix(&self) -> &str {
+        self.autocomplete
+            .as_ref()
+            .map_or(DEFAULT_PREFIX, |autocomplete| {
+                autocomplete.command_auto.prefix()
+            })
+    }
+
+    pub fn set_command_prefix(&mut self, prefix: &str) {
+        if let Some(ref mut autocomplete) = self.autocomplete {
+            autocomplete.command_auto.set_prefix(prefix);
+        }
+    }
+
     pub fn should_show_suggestions(&self) -> bool {
         let text = self.get_text();
-        !text.is_empty() && text.starts_with('/')
+        !text.is_empty() && text.starts_with(self.command_prefix())
     }
 
     pub fn is_slash_at_end(&self) -> bool {
         let text = self.get_text();
-        text.trim_end() == "/"
+        text.trim_end() == self.command_prefix()
     }
 
     pub fn complete_selection(&mut self) {
         if let Some(selected) = self.get_autocomplete_selection() {
             let current_text = self.get_text();
-            let start_index = current_text.rfind('/').map_or(0, |i| i + 1);
+            let prefix = self.command_prefix();
+            let start_index = current_text.rfind(prefix).map_or(0, |i| i + prefix.len());
 
             let new_text = if start_index == 0 {
                 selected.clone()
@@ -334,11 +401,9 @@ impl Input {
     pub fn get_autocomplete_selection(&self) -> Option<String> {
         if let Some(autocomplete) = &self.autocomplete {
             let text = self.get_text();
-            let suggestions = if text.starts_with('/') {
-                let filter = text.trim_start_matches('/');
-                autocomplete.get_suggestions(filter)
-            } else {
-                autocomplete.get_suggestions(&text)
+            let suggestions = match autocomplete.command_auto.command_filter(&text) {
+                Some(filter) => autocomplete.get_suggestions(filter),
+                None => autocomplete.get_suggestions(&text),
             };
             if !suggestions.is_empty() {
                 return Some(suggestions[0].name.clone());
@@ -358,6 +423,7 @@ impl Input {
     pub fn clear(&mut self) {
         self.textarea = TextArea::default();
         self.textarea.set_cursor_line_style(Style::default());
+        self.textarea.set_placeholder_text(self.placeholder.clone());
         self.viewport_top = 0;
         self.draft_text = None;
         if let Some(ref mut history) = self.prompt_history {
@@ -378,13 +444,43 @@ impl Input {
         }
     }
 
-    pub fn set_placeholder(&mut self, placeholder: &'static str) {
-        self.textarea.set_placeholder_text(placeholder);
+    /// Records prompts under `cwd` and recalls the ones `scope` covers.
+    pub fn set_history_scope(&mut self, cwd: &str, scope: HistoryScope) {
+        if let Some(ref mut history) = self.prompt_history {
+            let _ = history.set_scope(Some(cwd.to_string()), scope);
+        }
+    }
+
+    pub fn set_placeholder(&mut self, placeholder: impl Into<String>) {
+        self.placeholder = placeholder.into();
+        self.textarea.set_placeholder_text(self.placeholder.clone());
+    }
+
+    pub fn placeholder(&self) -> &str {
+        &self.placeholder
+    }
+
+    pub fn attach(&mut self, attachment: PastedText) {
+        self.attachments.push(attachment);
+    }
+
+    pub fn attachments(&self) -> &[PastedText] {
+        &self.attachments
+    }
+
+    pub fn remove_attachment(&mut self, index: usize) -> Option<PastedText> {
+        (index < self.attachments.len()).then(|| self.attachments.remove(index))
+    }
+
+    /// Hands over the attachments once they've been sent.
+    pub fn take_attachments(&mut self) -> Vec<PastedText> {
+        std::mem::take(&mut self.attachments)
     }
 
     pub fn set_text(&mut self, text: &str) {
         self.textarea = TextArea::default();
         self.textarea.set_cursor_line_style(Style::default());
+        self.textarea.set_placeholder_text(self.placeholder.clone());
         self.textarea.insert_str(text);
         self.viewport_top = 0;
     }
@@ -400,12 +496,10 @@ impl Input {
     pub fn get_autocomplete_suggestions(&self) -> Vec<Suggestion> {
         if let Some(autocomplete) = &self.autocomplete {
             let text = self.get_text();
-            if text.starts_with('/') {
-                let filter = text.trim_start_matches('/');
-                return autocomplete.get_suggestions(filter);
-            } else {
-                return autocomplete.get_suggestions(&text);
-            }
+            return match autocomplete.command_auto.command_filter(&text) {
+                Some(filter) => autocomplete.get_suggestions(filter),
+                None => autocomplete.get_suggestions(&text),
+            };
         }
         Vec::new()
     }
@@ -452,6 +546,27 @@ mod tests {
         input.set_placeholder("Test");
         input.clear();
         assert!(input.is_empty());
+        assert_eq!(input.placeholder(), "Test");
+        assert_eq!(input.textarea.placeholder_text(), "Test");
+    }
+
+    #[test]
+    fn test_input_attachments_survive_clear() {
+        let mut input = Input::new();
+        input.attach(PastedText::new("a\nb".to_string()));
+        input.attach(PastedText::new("c".to_string()));
+        input.insert_str("hi");
+        input.clear();
+        assert_eq!(input.attachments().len(), 2);
+
+        let backspace = KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE);
+        assert!(input.handle_event(backspace));
+        assert_eq!(input.attachments().len(), 1);
+        assert_eq!(input.attachments()[0].line_count(), 2);
+
+        assert!(input.remove_attachment(3).is_none());
+        assert_eq!(input.take_attachments().len(), 1);
+        assert!(input.attachments().is_empty());
     }
 
     #[test]
@@ -492,4 +607,22 @@ mod tests {
         let handled = input.handle_event(event);
         assert!(!handled);
     }
+
+    #[test]
+    fn test_autocomplete_with_another_prefix() {
+        let mut registry = crate::command::registry::Registry::new();
+        crate::command::handlers::register_all_commands(&mut registry);
+        let autocomplete = AutoComplete::new(crate::autocomplete::CommandAuto::new(&registry));
+        let mut input = Input::new().with_autocomplete(autocomplete);
+        input.set_command_prefix(":");
+
+        input.set_text("/sess");
+        assert!(!input.should_show_suggestions());
+
+        input.set_text(":sess");
+        assert!(input.should_show_suggestions());
+        assert_eq!(input.get_autocomplete_suggestions()[0].name, "sessions");
+        input.complete_selection();
+        assert_eq!(input.get_text(), ":sessions");
+    }
 }
@@ -1,5 +1,6 @@
 pub mod api_key_input;
 pub mod chat;
+pub mod confirm_dialog;
 pub mod dialog;
 pub mod input;
 pub mod landing;
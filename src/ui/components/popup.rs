@@ -8,6 +8,7 @@ use ratatui::{
     widgets::{Block, Borders, Clear, List, ListItem},
     Frame,
 };
+use unicode_width::UnicodeWidthStr;
 
 const MAX_VISIBLE_ITEMS: usize = 8;
 
@@ -21,6 +22,8 @@ pub struct Popup {
     pub suggestions: Vec<Suggestion>,
     pub selected_index: usize,
     pub visible: bool,
+    /// Shown before each suggestion, the prefix commands are typed with
+    pub prefix: String,
 }
 
 impl Popup {
@@ -29,6 +32,7 @@ impl Popup {
             suggestions: Vec::new(),
             selected_index: 0,
             visible: false,
+            prefix: crate::command::parser::DEFAULT_PREFIX.to_string(),
         }
     }
 
@@ -111,10 +115,10 @@ impl Popup {
 
         frame.render_widget(Clear, popup_area);
 
-        let max_name_len = self
+        let max_name_width = self
             .suggestions
             .iter()
-            .map(|s| s.name.len())
+            .map(|s| s.name.width())
             .max()
             .unwrap_or(0);
 
@@ -139,25 +143,26 @@ impl Popup {
                 let padding_style = Style::default().bg(bg_style);
 
                 let line = if !suggestion.description.is_empty() {
-                    let mid_padding = " ".repeat(max_name_len + 3 - suggestion.name.len());
-                    let content_len = suggestion.name.len()
-                        + suggestion.description.len()
+                    let mid_padding = " ".repeat(max_name_width + 3 - suggestion.name.width());
+                    let content_len = self.prefix.width()
+                        + suggestion.name.width()
+                        + suggestion.description.width()
                         + mid_padding.len()
-                        + 2;
+                        + 1;
                     let end_padding =
                         " ".repeat(popup_width.saturating_sub(content_len as u16).max(0) as usize);
                     Line::from(vec![
-                        Span::styled(format!("/{}", suggestion.name), name_style),
+                        Span::styled(format!("{}{}", self.prefix, suggestion.name), name_style),
                         Span::styled(mid_padding, padding_style),
                         Span::styled(suggestion.description.clone(), desc_style),
                         Span::styled(end_padding, padding_style),
                     ])
                 } else {
-                    let content_len = suggestion.name.len() + 1;
+                    let content_len = self.prefix.width() + suggestion.name.width();
                     let end_padding =
                         " ".repeat(popup_width.saturating_sub(content_len as u16).max(0) as usize);
                     Line::from(vec![
-                        Span::styled(format!("/{}", suggestion.name), name_style),
+                        Span::styled(format!("{}{}", self.prefix, suggestion.name), name_style),
                         Span::styled(end_padding, padding_style),
                     ])
                 };
@@ -199,6 +204,24 @@ impl Default for Popup {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ratatui::{backend::TestBackend, buffer::Buffer, Terminal};
+
+    /// Screen column where `needle` (ASCII) starts on any row, if rendered.
+    fn find_column(buffer: &Buffer, needle: &str) -> Option<u16> {
+        let area = buffer.area;
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let matches = needle.chars().enumerate().all(|(i, c)| {
+                    let cx = x + i as u16;
+                    cx < area.right() && buffer[(cx, y)].symbol() == c.to_string()
+                });
+                if matches {
+                    return Some(x);
+                }
+            }
+        }
+        None
+    }
 
     #[test]
     fn test_popup_creation() {
@@ -431,4 +454,54 @@ mod tests {
         let action = popup.handle_key_event(key);
         assert!(matches!(action, PopupAction::NotHandled));
     }
+
+    #[test]
+    fn test_render_aligns_descriptions_with_wide_names() {
+        let mut popup = Popup::new();
+        popup.set_suggestions(vec![
+            Suggestion {
+                name: "ab".to_string(),
+                description: "alpha".to_string(),
+            },
+            Suggestion {
+                name: "模型".to_string(),
+                description: "beta".to_string(),
+            },
+        ]);
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 12)).unwrap();
+        terminal
+            .draw(|f| {
+                let area = Rect::new(0, 10, 40, 2);
+                popup.render(f, area, true, ThemeColors::for_tests());
+            })
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let alpha = find_column(buffer, "alpha").unwrap();
+        let beta = find_column(buffer, "beta").unwrap();
+        assert_eq!(alpha, beta);
+    }
+
+    #[test]
+    fn test_render_shows_the_command_prefix() {
+        let mut popup = Popup::new();
+        popup.prefix = ";;".to_string();
+        popup.set_suggestions(vec![Suggestion {
+            name: "models".to_string(),
+            description: "List available models".to_string(),
+        }]);
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 12)).unwrap();
+        terminal
+            .draw(|f| {
+                let area = Rect::new(0, 10, 40, 2);
+                popup.render(f, area, true, ThemeColors::for_tests());
+            })
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        assert!(find_column(buffer, ";;models").is_some());
+        assert!(find_column(buffer, "/models").is_none());
+    }
 }
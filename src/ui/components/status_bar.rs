@@ -11,6 +11,11 @@ pub struct StatusBar {
     pub branch: Option<String>,
     pub agent: String,
     pub model: String,
+    /// Messages waiting for the current response to finish
+    pub queued: usize,
+    /// Whether crabcode gets the mouse; off leaves it to the terminal's
+    /// own selection
+    pub mouse_capture: bool,
 }
 
 impl StatusBar {
@@ -27,9 +32,21 @@ impl StatusBar {
             branch,
             agent,
             model,
+            queued: 0,
+            mouse_capture: true,
         }
     }
 
+    pub fn with_queued(mut self, queued: usize) -> Self {
+        self.queued = queued;
+        self
+    }
+
+    pub fn with_mouse_capture(mut self, mouse_capture: bool) -> Self {
+        self.mouse_capture = mouse_capture;
+        self
+    }
+
     pub fn render(&self, f: &mut Frame, area: Rect) {
         let cwd_with_tilde = if let Some(home) = std::env::var_os("HOME") {
             let home_str = home.to_string_lossy();
@@ -41,8 +58,10 @@ impl StatusBar {
         } else {
             self.cwd.clone()
         };
-        let cwd_display = if cwd_with_tilde.len() > 30 {
-            format!("...{}", &cwd_with_tilde[cwd_with_tilde.len() - 27..])
+        let cwd_chars = cwd_with_tilde.chars().count();
+        let cwd_display = if cwd_chars > 30 {
+            let tail: String = cwd_with_tilde.chars().skip(cwd_chars - 27).collect();
+            format!("...{}", tail)
         } else {
             cwd_with_tilde
         };
@@ -57,6 +76,22 @@ impl StatusBar {
             left_spans.push(Span::raw(")"));
         }
 
+        if self.queued > 0 {
+            left_spans.push(Span::raw("  "));
+            left_spans.push(Span::styled(
+                crate::session::queue::queued_label(self.queued),
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+        }
+
+        if !self.mouse_capture {
+            left_spans.push(Span::raw("  "));
+            left_spans.push(Span::styled(
+                "mouse off (ctrl+x c)",
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+
         let right_spans = vec![Span::styled(
             &self.version,
             Style::default().add_modifier(Modifier::DIM),
@@ -122,4 +157,48 @@ mod tests {
         );
         assert!(status_bar.branch.is_none());
     }
+
+    #[test]
+    fn test_status_bar_shows_queued_messages() {
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let status_bar = StatusBar::new(
+            "0.1.0".to_string(),
+            "/tmp".to_string(),
+            None,
+            "PLAN".to_string(),
+            "nano-gpt".to_string(),
+        );
+        assert_eq!(status_bar.queued, 0);
+        let status_bar = status_bar.with_queued(2);
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 1)).unwrap();
+        terminal.draw(|f| status_bar.render(f, f.area())).unwrap();
+        let buffer = terminal.backend().buffer();
+        let line: String = (0..40).map(|x| buffer[(x, 0)].symbol()).collect();
+        assert!(line.contains("2 queued messages"));
+    }
+
+    #[test]
+    fn test_status_bar_shows_mouse_capture_off() {
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let status_bar = StatusBar::new(
+            "0.1.0".to_string(),
+            "/tmp".to_string(),
+            None,
+            "PLAN".to_string(),
+            "nano-gpt".to_string(),
+        );
+        let render = |status_bar: &StatusBar| {
+            let mut terminal = Terminal::new(TestBackend::new(40, 1)).unwrap();
+            terminal.draw(|f| status_bar.render(f, f.area())).unwrap();
+            let buffer = terminal.backend().buffer();
+            (0..40).map(|x| buffer[(x, 0)].symbol()).collect::<String>()
+        };
+
+        assert!(!render(&status_bar).contains("mouse off"));
+        let status_bar = status_bar.with_mouse_capture(false);
+        assert!(render(&status_bar).contains("mouse off (ctrl+x c)"));
+    }
 }
@@ -0,0 +1,83 @@
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// A key a view handles and what it does. Each view lists its keys next to
+/// the code that handles them, and the help overlay is built from those
+/// lists, so the names shown are always the keys that are matched.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyHint {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+    pub description: &'static str,
+}
+
+impl KeyHint {
+    pub const fn new(code: KeyCode, description: &'static str) -> Self {
+        Self::with(code, KeyModifiers::NONE, description)
+    }
+
+    pub const fn ctrl(c: char, description: &'static str) -> Self {
+        Self::with(KeyCode::Char(c), KeyModifiers::CONTROL, description)
+    }
+
+    pub const fn with(code: KeyCode, modifiers: KeyModifiers, description: &'static str) -> Self {
+        Self {
+            code,
+            modifiers,
+            description,
+        }
+    }
+
+    /// The event pressing this key sends.
+    pub fn event(&self) -> KeyEvent {
+        KeyEvent::new(self.code, self.modifiers)
+    }
+
+    /// How the key is written in the help overlay, e.g. `Ctrl+X`.
+    pub fn label(&self) -> String {
+        let mut label = String::new();
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl+"),
+            (KeyModifiers::ALT, "Alt+"),
+            (KeyModifiers::SHIFT, "Shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                label.push_str(name);
+            }
+        }
+
+        match self.code {
+            KeyCode::Char(c) if !self.modifiers.is_empty() => label.extend(c.to_uppercase()),
+            KeyCode::Char(c) => label.push(c),
+            KeyCode::Enter => label.push_str("Enter"),
+            KeyCode::Esc => label.push_str("Esc"),
+            KeyCode::Tab => label.push_str("Tab"),
+            KeyCode::Backspace => label.push_str("Backspace"),
+            KeyCode::Up => label.push_str("Up"),
+            KeyCode::Down => label.push_str("Down"),
+            KeyCode::Left => label.push_str("Left"),
+            KeyCode::Right => label.push_str("Right"),
+            KeyCode::PageUp => label.push_str("PageUp"),
+            KeyCode::PageDown => label.push_str("PageDown"),
+            KeyCode::Home => label.push_str("Home"),
+            KeyCode::End => label.push_str("End"),
+            other => label.push_str(&format!("{:?}", other)),
+        }
+        label
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label() {
+        assert_eq!(KeyHint::ctrl('x', "").label(), "Ctrl+X");
+        assert_eq!(KeyHint::new(KeyCode::Char('?'), "").label(), "?");
+        assert_eq!(
+            KeyHint::with(KeyCode::Enter, KeyModifiers::SHIFT, "").label(),
+            "Shift+Enter"
+        );
+        assert_eq!(KeyHint::new(KeyCode::PageDown, "").label(), "PageDown");
+    }
+}
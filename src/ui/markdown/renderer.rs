@@ -0,0 +1,509 @@
+use crate::persistence::MarkdownRendererKind;
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use unicode_width::UnicodeWidthChar;
+
+use super::streaming::render_markdown_with;
+
+/// Turns markdown into lines no wider than `max_width`. With `plain` set,
+/// colors are dropped and emphasis is spelled out with markdown symbols.
+pub trait MarkdownRenderer: std::fmt::Debug + Send + Sync {
+    fn render(&self, content: &str, max_width: usize, plain: bool) -> Vec<Line<'static>>;
+
+    fn box_clone(&self) -> Box<dyn MarkdownRenderer>;
+}
+
+impl Clone for Box<dyn MarkdownRenderer> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+impl Default for Box<dyn MarkdownRenderer> {
+    fn default() -> Self {
+        renderer_for(MarkdownRendererKind::default())
+    }
+}
+
+pub fn renderer_for(kind: MarkdownRendererKind) -> Box<dyn MarkdownRenderer> {
+    match kind {
+        MarkdownRendererKind::TuiMarkdown => Box::new(TuiMarkdownRenderer),
+        MarkdownRendererKind::Builtin => Box::new(BuiltinMarkdownRenderer),
+    }
+}
+
+/// Parses with tui-markdown. Soft line breaks are joined, and lines that
+/// are too long are rewrapped as unstyled text.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TuiMarkdownRenderer;
+
+impl MarkdownRenderer for TuiMarkdownRenderer {
+    fn render(&self, content: &str, max_width: usize, plain: bool) -> Vec<Line<'static>> {
+        render_markdown_with(content, max_width, plain)
+    }
+
+    fn box_clone(&self) -> Box<dyn MarkdownRenderer> {
+        Box::new(*self)
+    }
+}
+
+/// Works line by line, keeping line breaks as written. Wrapped list items
+/// and quotes stay indented under their text, and code lines are split at
+/// the width instead of between words so they keep their indentation and
+/// style.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BuiltinMarkdownRenderer;
+
+impl MarkdownRenderer for BuiltinMarkdownRenderer {
+    fn render(&self, content: &str, max_width: usize, plain: bool) -> Vec<Line<'static>> {
+        let max_width = max_width.max(1);
+        let styles = BlockStyles::new(plain);
+        let mut lines = Vec::new();
+        // The fence of the code block we're in
+        let mut fence: Option<&str> = None;
+
+        for raw in content.lines() {
+            let trimmed = raw.trim_start();
+
+            if let Some(marker) = fence {
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                    lines.extend(wrap_code(raw, max_width, styles.fence));
+                } else {
+                    lines.extend(wrap_code(raw, max_width, styles.code));
+                }
+                continue;
+            }
+
+            if let Some(marker) = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m)) {
+                fence = Some(marker);
+                lines.extend(wrap_code(raw, max_width, styles.fence));
+            } else if trimmed.is_empty() {
+                lines.push(Line::default());
+            } else if let Some((level, text)) = heading(trimmed) {
+                let prefix = format!("{} ", "#".repeat(level));
+                let spans = inline_spans(text, styles.heading, plain);
+                lines.extend(wrap_spans(
+                    spans,
+                    max_width,
+                    Span::styled(prefix, styles.heading),
+                    Span::raw(""),
+                ));
+            } else if is_rule(trimmed) {
+                let rule = if plain { "-" } else { "─" };
+                lines.push(Line::styled(rule.repeat(max_width.min(40)), styles.fence));
+            } else if let Some(text) = trimmed.strip_prefix('>') {
+                let spans = inline_spans(text.trim_start(), styles.quote, plain);
+                let bar = Span::styled("│ ", styles.fence);
+                lines.extend(wrap_spans(spans, max_width, bar.clone(), bar));
+            } else if let Some((indent, marker, text)) = list_item(raw) {
+                let prefix = format!("{}{} ", " ".repeat(indent), marker);
+                let hanging = " ".repeat(prefix.chars().count());
+                let spans = inline_spans(text, Style::default(), plain);
+                lines.extend(wrap_spans(
+                    spans,
+                    max_width,
+                    Span::styled(prefix, styles.bullet),
+                    Span::raw(hanging),
+                ));
+            } else {
+                let spans = inline_spans(trimmed, Style::default(), plain);
+                lines.extend(wrap_spans(spans, max_width, Span::raw(""), Span::raw("")));
+            }
+        }
+
+        lines
+    }
+
+    fn box_clone(&self) -> Box<dyn MarkdownRenderer> {
+        Box::new(*self)
+    }
+}
+
+struct BlockStyles {
+    heading: Style,
+    quote: Style,
+    bullet: Style,
+    code: Style,
+    /// Code fences, rules and the quote bar
+    fence: Style,
+}
+
+impl BlockStyles {
+    fn new(plain: bool) -> Self {
+        if plain {
+            return Self {
+                heading: Style::default().add_modifier(Modifier::BOLD),
+                quote: Style::default(),
+                bullet: Style::default(),
+                code: Style::default(),
+                fence: Style::default(),
+            };
+        }
+        Self {
+            heading: Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+            quote: Style::default().add_modifier(Modifier::ITALIC),
+            bullet: Style::default().fg(Color::Cyan),
+            code: Style::default().fg(Color::Yellow),
+            fence: Style::default().fg(Color::DarkGray),
+        }
+    }
+}
+
+/// `# Title` up to `###### Title`
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let text = &line[level..];
+    if text.is_empty() {
+        Some((level, text))
+    } else {
+        text.strip_prefix(' ').map(|text| (level, text.trim()))
+    }
+}
+
+/// `---`, `***` or `___`, spaces allowed in between
+fn is_rule(line: &str) -> bool {
+    let marks: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    marks.len() >= 3 && matches!(marks[0], '-' | '*' | '_') && marks.iter().all(|&c| c == marks[0])
+}
+
+/// The indent, marker and text of `- item` or `1. item`. Unordered markers
+/// become bullets.
+fn list_item(line: &str) -> Option<(usize, String, &str)> {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+
+    if let Some(text) = ["- ", "* ", "+ "]
+        .into_iter()
+        .find_map(|marker| trimmed.strip_prefix(marker))
+    {
+        return Some((indent, "•".to_string(), text));
+    }
+
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    let rest = &trimmed[digits..];
+    if digits == 0 || digits > 9 {
+        return None;
+    }
+    ['.', ')'].into_iter().find_map(|delimiter| {
+        let text = rest.strip_prefix(delimiter)?.strip_prefix(' ')?;
+        Some((indent, trimmed[..=digits].to_string(), text))
+    })
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Emphasis {
+    bold: bool,
+    italic: bool,
+    strike: bool,
+    code: bool,
+}
+
+impl Emphasis {
+    fn style(self, base: Style, plain: bool) -> Style {
+        let mut style = base;
+        if self.code && !plain {
+            style = style.fg(Color::Yellow);
+        }
+        if self.bold {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if self.italic {
+            style = style.add_modifier(Modifier::ITALIC);
+        }
+        if self.strike {
+            style = style.add_modifier(Modifier::CROSSED_OUT);
+        }
+        style
+    }
+
+    /// The symbols marking this emphasis when colors are off
+    fn marker(self) -> String {
+        let mut marker = String::new();
+        if self.code {
+            marker.push('`');
+        }
+        if self.bold {
+            marker.push_str("**");
+        }
+        if self.italic {
+            marker.push('_');
+        }
+        if self.strike {
+            marker.push_str("~~");
+        }
+        marker
+    }
+}
+
+fn inline_spans(text: &str, base: Style, plain: bool) -> Vec<Span<'static>> {
+    let mut runs = Vec::new();
+    parse_inline(text, Emphasis::default(), &mut runs);
+
+    runs.into_iter()
+        .map(|(text, emphasis)| {
+            let style = emphasis.style(base, plain);
+            if plain && emphasis != Emphasis::default() {
+                let marker = emphasis.marker();
+                let closing: String = marker.chars().rev().collect();
+                Span::styled(format!("{}{}{}", marker, text, closing), style)
+            } else {
+                Span::styled(text, style)
+            }
+        })
+        .collect()
+}
+
+const DELIMITERS: [&str; 5] = ["**", "__", "~~", "*", "_"];
+
+/// Splits `text` into runs of the same emphasis. Delimiters without a
+/// closing one are kept as text.
+fn parse_inline(text: &str, emphasis: Emphasis, runs: &mut Vec<(String, Emphasis)>) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut literal = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] == '`' {
+            if let Some(end) = (i + 1..chars.len()).find(|&j| chars[j] == '`') {
+                flush(&mut literal, emphasis, runs);
+                let code = Emphasis {
+                    code: true,
+                    ..emphasis
+                };
+                runs.push((chars[i + 1..end].iter().collect(), code));
+                i = end + 1;
+                continue;
+            }
+        }
+
+        let delimiter = DELIMITERS.into_iter().find(|d| starts_with(&chars, i, d));
+        if let Some(delimiter) = delimiter {
+            let len = delimiter.len();
+            if let Some(end) = closing_delimiter(&chars, i, delimiter) {
+                flush(&mut literal, emphasis, runs);
+                let inner: String = chars[i + len..end].iter().collect();
+                let nested = match delimiter {
+                    "**" | "__" => Emphasis {
+                        bold: true,
+                        ..emphasis
+                    },
+                    "~~" => Emphasis {
+                        strike: true,
+                        ..emphasis
+                    },
+                    _ => Emphasis {
+                        italic: true,
+                        ..emphasis
+                    },
+                };
+                parse_inline(&inner, nested, runs);
+                i = end + len;
+                continue;
+            }
+        }
+
+        literal.push(chars[i]);
+        i += 1;
+    }
+    flush(&mut literal, emphasis, runs);
+}
+
+fn flush(literal: &mut String, emphasis: Emphasis, runs: &mut Vec<(String, Emphasis)>) {
+    if !literal.is_empty() {
+        runs.push((std::mem::take(literal), emphasis));
+    }
+}
+
+fn starts_with(chars: &[char], at: usize, delimiter: &str) -> bool {
+    delimiter
+        .chars()
+        .enumerate()
+        .all(|(offset, c)| chars.get(at + offset) == Some(&c))
+}
+
+/// Where the delimiter opening at `open` is closed. Underscores only count
+/// outside words so `snake_case` stays as it is, and a single delimiter
+/// isn't matched against half of a double one.
+fn closing_delimiter(chars: &[char], open: usize, delimiter: &str) -> Option<usize> {
+    let len = delimiter.len();
+    let mark = delimiter.chars().next()?;
+    let is_word = |at: Option<&char>| at.is_some_and(|c| c.is_alphanumeric());
+
+    let next = chars.get(open + len)?;
+    if next.is_whitespace() || (mark == '_' && is_word(open.checked_sub(1).map(|p| &chars[p]))) {
+        return None;
+    }
+
+    (open + len + 1..chars.len()).find(|&end| {
+        starts_with(chars, end, delimiter)
+            && !chars[end - 1].is_whitespace()
+            && !(mark == '_' && is_word(chars.get(end + len)))
+            && !(len == 1 && (chars[end - 1] == mark || chars.get(end + 1) == Some(&mark)))
+    })
+}
+
+/// Word-wraps styled spans to `width`, starting the first line with `first`
+/// and the others with `rest`. Words longer than a line are split.
+fn wrap_spans(
+    spans: Vec<Span<'static>>,
+    width: usize,
+    first: Span<'static>,
+    rest: Span<'static>,
+) -> Vec<Line<'static>> {
+    // Each word is its styled pieces and whether a space follows it
+    let mut words: Vec<(Vec<Span<'static>>, bool)> = vec![(Vec::new(), false)];
+    for span in spans {
+        for c in span.content.chars() {
+            let (pieces, space_after) = words.last_mut().unwrap();
+            if c == ' ' {
+                if !pieces.is_empty() {
+                    *space_after = true;
+                }
+                continue;
+            }
+            if *space_after {
+                words.push((Vec::new(), false));
+            }
+            let (pieces, _) = words.last_mut().unwrap();
+            match pieces.last_mut() {
+                Some(piece) if piece.style == span.style => piece.content.to_mut().push(c),
+                _ => pieces.push(Span::styled(c.to_string(), span.style)),
+            }
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut line = vec![first];
+    let mut line_width = line[0].width();
+    let mut line_has_words = false;
+    let mut space_before = false;
+    for (pieces, space_after) in words.into_iter().filter(|(p, _)| !p.is_empty()) {
+        let word_width: usize = pieces.iter().map(Span::width).sum();
+        let gap = usize::from(space_before && line_has_words);
+        if line_has_words && line_width + gap + word_width > width {
+            lines.push(Line::from(std::mem::replace(&mut line, vec![rest.clone()])));
+            line_width = rest.width();
+            line_has_words = false;
+        } else if gap == 1 {
+            line.push(Span::raw(" "));
+            line_width += 1;
+        }
+
+        for piece in pieces {
+            for c in piece.content.chars() {
+                let char_width = c.width().unwrap_or(0);
+                if line_has_words && line_width + char_width > width {
+                    lines.push(Line::from(std::mem::replace(&mut line, vec![rest.clone()])));
+                    line_width = rest.width();
+                }
+                match line.last_mut() {
+                    Some(last) if line_has_words && last.style == piece.style => {
+                        last.content.to_mut().push(c)
+                    }
+                    _ => line.push(Span::styled(c.to_string(), piece.style)),
+                }
+                line_width += char_width;
+                line_has_words = true;
+            }
+        }
+        space_before = space_after;
+    }
+    lines.push(Line::from(line));
+    lines
+}
+
+/// Splits a code line every `width` columns, keeping it as written.
+fn wrap_code(line: &str, width: usize, style: Style) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    let mut current_width = 0;
+    for c in line.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if current_width + char_width > width && !current.is_empty() {
+            lines.push(Line::styled(std::mem::take(&mut current), style));
+            current_width = 0;
+        }
+        current.push(c);
+        current_width += char_width;
+    }
+    lines.push(Line::styled(current, style));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(lines: &[Line]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    }
+
+    const SAMPLE: &str = "Intro with **bold** text\n\n- first item\n- second item that is long enough to wrap\n\n```rust\nfn main() {}\n```";
+
+    #[test]
+    fn test_renderers_agree_on_short_blocks() {
+        let short = "Intro with **bold** text\n\n- first item\n\n```rust\nfn main() {}\n```";
+        for plain in [false, true] {
+            let tui = TuiMarkdownRenderer.render(short, 80, plain);
+            let builtin = BuiltinMarkdownRenderer.render(short, 80, plain);
+            assert_eq!(tui.len(), builtin.len());
+        }
+    }
+
+    #[test]
+    fn test_builtin_wraps_list_items_under_their_text() {
+        let tui = TuiMarkdownRenderer.render(SAMPLE, 24, false);
+        let builtin = BuiltinMarkdownRenderer.render(SAMPLE, 24, false);
+        assert_eq!(tui.len(), builtin.len());
+
+        assert_eq!(
+            text(&builtin),
+            vec![
+                "Intro with bold text",
+                "",
+                "• first item",
+                "• second item that is",
+                "  long enough to wrap",
+                "",
+                "```rust",
+                "fn main() {}",
+                "```",
+            ]
+        );
+        assert!(builtin[0]
+            .spans
+            .iter()
+            .any(|s| s.content == "bold" && s.style.add_modifier.contains(Modifier::BOLD)));
+        assert!(builtin.iter().all(|line| line.width() <= 24));
+    }
+
+    #[test]
+    fn test_builtin_keeps_code_indentation_when_wrapping() {
+        let code = "```\n        let value = compute();\n```";
+        let lines = BuiltinMarkdownRenderer.render(code, 16, false);
+        assert_eq!(
+            text(&lines),
+            vec!["```", "        let valu", "e = compute();", "```"]
+        );
+        assert_eq!(lines[1].style.fg, Some(Color::Yellow));
+    }
+
+    #[test]
+    fn test_builtin_inline_emphasis() {
+        let lines =
+            BuiltinMarkdownRenderer.render("Use `snake_case`, *not* 2 * 3 or a_b_c", 80, true);
+        assert_eq!(text(&lines), vec!["Use `snake_case`, _not_ 2 * 3 or a_b_c"]);
+        assert!(lines[0].spans.iter().all(|s| s.style.fg.is_none()));
+    }
+}
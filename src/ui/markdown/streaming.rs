@@ -1,34 +1,44 @@
+use super::renderer::MarkdownRenderer;
 use ratatui::text::Line;
 
 /// A simple streaming markdown renderer that caches parsed content
 /// to avoid re-parsing on every frame during streaming.
 ///
 /// This implements the "Simple Caching Strategy" from the streaming markdown plan.
-/// It only re-parses when the content changes, not every render call.
+/// Nothing is parsed on frames where no text arrived, and blocks that are
+/// complete (followed by a blank line outside a code fence) are parsed once;
+/// only the block still being written is parsed again as it grows.
 ///
 /// Note: Due to version incompatibility between tui-markdown (uses ratatui-core)
 /// and our ratatui version, we store content and render it directly.
 #[derive(Debug, Clone)]
 pub struct SimpleStreamingRenderer {
     content: String,
-    last_content_hash: u64,
     needs_render: bool,
+    /// Bytes of `content` made of complete blocks, whose lines are kept
+    stable_len: usize,
+    /// Rendered lines: first the complete blocks', then the rest's
+    lines: Vec<Line<'static>>,
+    stable_lines: usize,
+    /// The width and plain setting `lines` were rendered for
+    rendered_with: Option<(usize, bool)>,
 }
 
 impl SimpleStreamingRenderer {
     pub fn new() -> Self {
         Self {
             content: String::new(),
-            last_content_hash: 0,
             needs_render: true,
+            stable_len: 0,
+            lines: Vec::new(),
+            stable_lines: 0,
+            rendered_with: None,
         }
     }
 
     /// Reset the renderer for a new message
     pub fn reset(&mut self) {
-        self.content.clear();
-        self.last_content_hash = 0;
-        self.needs_render = true;
+        *self = Self::new();
     }
 
     /// Append new content from the stream
@@ -37,6 +47,20 @@ impl SimpleStreamingRenderer {
         self.needs_render = true;
     }
 
+    /// Catch up with `content`, the whole message so far. Only the part past
+    /// what was already consumed is appended; if the message changed other
+    /// than by growing, start over. Returns the number of bytes appended.
+    pub fn sync(&mut self, content: &str) -> usize {
+        if !content.starts_with(self.content.as_str()) {
+            self.reset();
+        }
+        let delta = &content[self.content.len()..];
+        if !delta.is_empty() {
+            self.append(delta);
+        }
+        delta.len()
+    }
+
     /// Get the current content
     pub fn content(&self) -> &str {
         &self.content
@@ -47,16 +71,76 @@ impl SimpleStreamingRenderer {
         self.needs_render
     }
 
-    /// Mark the renderer as rendered (reset the needs_render flag)
-    pub fn mark_rendered(&mut self) {
+    /// Bring `lines` up to date with the content so far. Does nothing when
+    /// no text arrived since the last call with the same settings.
+    pub fn render(&mut self, renderer: &dyn MarkdownRenderer, max_width: usize, plain: bool) {
+        if self.rendered_with != Some((max_width, plain)) {
+            self.stable_len = 0;
+            self.lines.clear();
+            self.stable_lines = 0;
+            self.rendered_with = Some((max_width, plain));
+        } else if !self.needs_render {
+            return;
+        }
         self.needs_render = false;
-        self.last_content_hash = compute_hash(&self.content);
+        self.lines.truncate(self.stable_lines);
+
+        let stable_end = self.completed_blocks_end();
+        if stable_end > self.stable_len {
+            let blocks =
+                renderer.render(&self.content[self.stable_len..stable_end], max_width, plain);
+            self.push_block_lines(blocks);
+            self.stable_len = stable_end;
+            self.stable_lines = self.lines.len();
+        }
+
+        let tail = renderer.render(&self.content[self.stable_len..], max_width, plain);
+        self.push_block_lines(tail);
     }
 
-    /// Get the content to render
-    /// Returns the markdown content that should be rendered
-    pub fn get_content(&self) -> &str {
-        &self.content
+    /// The lines from the last `render`.
+    pub fn lines(&self) -> &[Line<'static>] {
+        &self.lines
+    }
+
+    /// Where the last complete block ends: after the last blank line that
+    /// isn't inside a code fence. Only the text past `stable_len` is read.
+    fn completed_blocks_end(&self) -> usize {
+        let mut end = self.stable_len;
+        let mut pos = self.stable_len;
+        let mut fence: Option<&str> = None;
+
+        for line in self.content[self.stable_len..].split_inclusive('\n') {
+            if !line.ends_with('\n') {
+                break;
+            }
+            pos += line.len();
+            let trimmed = line.trim_start();
+            if let Some(marker) = fence {
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                }
+            } else if let Some(marker) = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m))
+            {
+                fence = Some(marker);
+            } else if trimmed.is_empty() {
+                end = pos;
+            }
+        }
+        end
+    }
+
+    /// Adds the lines of blocks rendered on their own, keeping the blank
+    /// line a renderer puts between blocks but not after the last one.
+    fn push_block_lines(&mut self, lines: Vec<Line<'static>>) {
+        let ends_blank = self
+            .lines
+            .last()
+            .is_none_or(|line| line.spans.iter().all(|span| span.content.trim().is_empty()));
+        if !ends_blank && !lines.is_empty() {
+            self.lines.push(Line::default());
+        }
+        self.lines.extend(lines);
     }
 }
 
@@ -66,19 +150,16 @@ impl Default for SimpleStreamingRenderer {
     }
 }
 
-/// Compute a hash of the content
-fn compute_hash(content: &str) -> u64 {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-
-    let mut hasher = DefaultHasher::new();
-    content.hash(&mut hasher);
-    hasher.finish()
-}
-
 /// Render markdown content to lines
 /// This uses tui-markdown to parse and render the markdown
-pub fn render_markdown(content: &str, max_width: usize) -> Vec<Line> {
+pub fn render_markdown(content: &str, max_width: usize) -> Vec<Line<'static>> {
+    render_markdown_with(content, max_width, false)
+}
+
+/// Like [`render_markdown`]. With `plain` set, colors are dropped and
+/// emphasis is spelled out with markdown symbols so it doesn't rely on color
+/// or font styling alone.
+pub fn render_markdown_with(content: &str, max_width: usize, plain: bool) -> Vec<Line<'static>> {
     // Use tui-markdown to parse the content
     let text = tui_markdown::from_str(content);
 
@@ -87,7 +168,11 @@ pub fn render_markdown(content: &str, max_width: usize) -> Vec<Line> {
 
     for line in text.lines {
         // Convert ratatui-core Line to our ratatui Line
-        let converted_line = convert_line(line);
+        let converted_line = if plain {
+            plain_line(convert_line(line))
+        } else {
+            convert_line(line)
+        };
 
         // Check if line needs wrapping
         let line_str = line_to_string(&converted_line);
@@ -120,6 +205,40 @@ fn convert_line(line: ratatui_core::text::Line<'_>) -> Line<'static> {
     Line::from(spans)
 }
 
+/// Strip colors from a line and mark emphasis with symbols instead
+fn plain_line(line: Line<'static>) -> Line<'static> {
+    use ratatui::style::Modifier;
+
+    let spans: Vec<ratatui::text::Span<'static>> = line
+        .spans
+        .into_iter()
+        .map(|span| {
+            let style = span.style;
+            let mut marker = String::new();
+            if style.bg.is_some() {
+                // Inline code is the only span tui-markdown gives a background
+                marker.push('`');
+            }
+            if style.add_modifier.contains(Modifier::BOLD) {
+                marker.push_str("**");
+            }
+            if style.add_modifier.contains(Modifier::ITALIC) {
+                marker.push('_');
+            }
+            if style.add_modifier.contains(Modifier::CROSSED_OUT) {
+                marker.push_str("~~");
+            }
+
+            let closing: String = marker.chars().rev().collect();
+            let content = format!("{}{}{}", marker, span.content, closing);
+            let plain_style = ratatui::style::Style::default().add_modifier(style.add_modifier);
+            ratatui::text::Span::styled(content, plain_style)
+        })
+        .collect();
+
+    Line::from(spans)
+}
+
 /// Convert ratatui-core Style to our ratatui Style
 fn convert_style(style: ratatui_core::style::Style) -> ratatui::style::Style {
     let mut new_style = ratatui::style::Style::default();
@@ -206,6 +325,29 @@ fn wrap_line(line_str: &str, max_width: usize) -> Vec<Line<'static>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ui::markdown::renderer::{BuiltinMarkdownRenderer, TuiMarkdownRenderer};
+    use std::sync::{Arc, Mutex};
+
+    /// Records the text it was asked to parse.
+    #[derive(Debug, Clone, Default)]
+    struct CountingRenderer {
+        parsed: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl MarkdownRenderer for CountingRenderer {
+        fn render(&self, content: &str, max_width: usize, plain: bool) -> Vec<Line<'static>> {
+            self.parsed.lock().unwrap().push(content.to_string());
+            BuiltinMarkdownRenderer.render(content, max_width, plain)
+        }
+
+        fn box_clone(&self) -> Box<dyn MarkdownRenderer> {
+            Box::new(self.clone())
+        }
+    }
+
+    fn text(lines: &[Line<'_>]) -> Vec<String> {
+        lines.iter().map(line_to_string).collect()
+    }
 
     #[test]
     fn test_streaming_renderer_new() {
@@ -220,21 +362,94 @@ mod tests {
         renderer.append("Hello");
         assert_eq!(renderer.content(), "Hello");
         assert!(renderer.needs_render());
-        renderer.mark_rendered();
+        renderer.render(&BuiltinMarkdownRenderer, 80, false);
         assert!(!renderer.needs_render());
+        assert_eq!(text(renderer.lines()), ["Hello"]);
     }
 
     #[test]
     fn test_streaming_renderer_reset() {
         let mut renderer = SimpleStreamingRenderer::new();
         renderer.append("content");
-        renderer.mark_rendered();
+        renderer.render(&BuiltinMarkdownRenderer, 80, false);
 
         renderer.reset();
         assert!(renderer.content.is_empty());
         assert!(renderer.needs_render());
     }
 
+    #[test]
+    fn test_streaming_renderer_sync_appends_only_the_delta() {
+        let mut renderer = SimpleStreamingRenderer::new();
+        let mut message = String::new();
+        let mut appended = 0;
+        for i in 0..1_000 {
+            message.push_str(&format!("token {} ", i));
+            appended += renderer.sync(&message);
+            // Nothing new arrived between frames
+            renderer.render(&BuiltinMarkdownRenderer, 80, false);
+            assert_eq!(renderer.sync(&message), 0);
+            assert!(!renderer.needs_render());
+        }
+        assert_eq!(renderer.content(), message);
+        // Linear in the message length; resetting on every chunk would have
+        // copied the whole message each time
+        assert_eq!(appended, message.len());
+
+        assert_eq!(renderer.sync("Regenerated"), "Regenerated".len());
+        assert_eq!(renderer.content(), "Regenerated");
+        assert!(renderer.needs_render());
+    }
+
+    #[test]
+    fn test_streaming_renderer_parses_only_new_text() {
+        let counting = CountingRenderer::default();
+        let mut renderer = SimpleStreamingRenderer::new();
+        renderer.append("# Title\n\nFirst para");
+        renderer.render(&counting, 80, false);
+        assert_eq!(
+            *counting.parsed.lock().unwrap(),
+            ["# Title\n\n", "First para"]
+        );
+
+        // Nothing appended: nothing parsed
+        renderer.render(&counting, 80, false);
+        assert_eq!(counting.parsed.lock().unwrap().len(), 2);
+
+        // Only the block still being written is parsed again
+        renderer.append("graph\n\n```\ncode\n\nmore");
+        renderer.render(&counting, 80, false);
+        assert_eq!(
+            counting.parsed.lock().unwrap()[2..],
+            ["First paragraph\n\n", "```\ncode\n\nmore"]
+        );
+
+        // A new width renders everything again
+        renderer.render(&counting, 40, false);
+        assert_eq!(counting.parsed.lock().unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_streaming_renderer_matches_a_full_render() {
+        let message =
+            "# Title\n\nSome text\nmore\n\n- a\n- b\n\n```rust\nfn x() {}\n\n```\n\nEnd para";
+        let renderers: [&dyn MarkdownRenderer; 2] =
+            [&TuiMarkdownRenderer, &BuiltinMarkdownRenderer];
+        for markdown in renderers {
+            let mut renderer = SimpleStreamingRenderer::new();
+            for chunk in message.as_bytes().chunks(5) {
+                renderer.append(std::str::from_utf8(chunk).unwrap());
+                renderer.render(markdown, 80, false);
+            }
+            assert_eq!(
+                text(renderer.lines()),
+                text(&markdown.render(message, 80, false)),
+                "{:?}",
+                markdown
+            );
+        }
+    }
+
     #[test]
     fn test_render_markdown_basic() {
         let lines = render_markdown("# Hello\n\nThis is **bold** and *italic*.", 80);
@@ -249,6 +464,22 @@ mod tests {
         assert!(!lines.is_empty());
     }
 
+    #[test]
+    fn test_render_plain_uses_symbols_instead_of_color() {
+        let lines = render_markdown_with("Some **bold**, *it* and `code`", 80, true);
+        assert_eq!(line_to_string(&lines[0]), "Some **bold**, _it_ and `code`");
+        assert!(lines[0]
+            .spans
+            .iter()
+            .all(|span| span.style.fg.is_none() && span.style.bg.is_none()));
+
+        let lines = render_markdown_with("```rust\nfn main() {}\n```", 80, true);
+        assert!(lines
+            .iter()
+            .flat_map(|line| line.spans.iter())
+            .all(|span| span.style.fg.is_none()));
+    }
+
     #[test]
     fn test_render_with_wrapping() {
         let lines = render_markdown(
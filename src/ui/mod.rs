@@ -1,3 +1,6 @@
+pub mod boundary;
 pub mod components;
+pub mod keymap;
 pub mod layout;
 pub mod markdown;
+pub mod toast;
@@ -0,0 +1,314 @@
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+use ratatui_toolkit::{Toast, ToastLevel, ToastManager};
+
+use crate::theme::ThemeColors;
+
+/// How long toasts of each level stay up by default, in milliseconds.
+pub const SUCCESS_VAR: &str = "CRABCODE_TOAST_SUCCESS_MS";
+pub const INFO_VAR: &str = "CRABCODE_TOAST_INFO_MS";
+pub const WARNING_VAR: &str = "CRABCODE_TOAST_WARNING_MS";
+pub const ERROR_VAR: &str = "CRABCODE_TOAST_ERROR_MS";
+
+/// Toasts kept for the notifications overlay; older ones are dropped.
+pub const HISTORY_CAPACITY: usize = 100;
+
+// Same geometry as the toolkit's renderer
+const TOAST_WIDTH: u16 = 40;
+const TOAST_HEIGHT: u16 = 3;
+const TOAST_MARGIN: u16 = 2;
+const TOAST_SPACING: u16 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToastDurations {
+    pub success: Duration,
+    pub info: Duration,
+    pub warning: Duration,
+    /// Errors stay longest so there's time to read what went wrong
+    pub error: Duration,
+}
+
+impl Default for ToastDurations {
+    fn default() -> Self {
+        Self {
+            success: Duration::from_millis(3000),
+            info: Duration::from_millis(2000),
+            warning: Duration::from_millis(5000),
+            error: Duration::from_millis(8000),
+        }
+    }
+}
+
+impl ToastDurations {
+    pub fn from_env() -> Self {
+        let var = |name| std::env::var(name).ok();
+        Self::parse(
+            var(SUCCESS_VAR).as_deref(),
+            var(INFO_VAR).as_deref(),
+            var(WARNING_VAR).as_deref(),
+            var(ERROR_VAR).as_deref(),
+        )
+    }
+
+    /// Values that don't parse, and zeros, keep the default.
+    fn parse(
+        success: Option<&str>,
+        info: Option<&str>,
+        warning: Option<&str>,
+        error: Option<&str>,
+    ) -> Self {
+        let default = Self::default();
+        let millis = |value: Option<&str>, default: Duration| {
+            value
+                .and_then(|s| s.trim().parse::<u64>().ok())
+                .filter(|n| *n > 0)
+                .map_or(default, Duration::from_millis)
+        };
+        Self {
+            success: millis(success, default.success),
+            info: millis(info, default.info),
+            warning: millis(warning, default.warning),
+            error: millis(error, default.error),
+        }
+    }
+
+    pub fn for_level(&self, level: ToastLevel) -> Duration {
+        match level {
+            ToastLevel::Success => self.success,
+            ToastLevel::Info => self.info,
+            ToastLevel::Warning => self.warning,
+            ToastLevel::Error => self.error,
+        }
+    }
+
+    /// A toast that stays up for `duration`, or for its level's duration
+    /// when none is given.
+    pub fn toast(
+        &self,
+        message: impl Into<String>,
+        level: ToastLevel,
+        duration: Option<Duration>,
+    ) -> Toast {
+        let duration = duration.unwrap_or_else(|| self.for_level(level));
+        Toast::new(message, level, Some(duration))
+    }
+}
+
+pub fn settings() -> &'static ToastDurations {
+    static SETTINGS: OnceLock<ToastDurations> = OnceLock::new();
+    SETTINGS.get_or_init(ToastDurations::from_env)
+}
+
+/// Use this over `Toast::new`, which gives toasts without a duration the
+/// toolkit's default rather than the configured one for their level.
+pub fn new_toast(
+    message: impl Into<String>,
+    level: ToastLevel,
+    duration: Option<Duration>,
+) -> Toast {
+    settings().toast(message, level, duration)
+}
+
+/// A toast as it was shown, kept after it expires.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToastRecord {
+    pub level: ToastLevel,
+    pub message: String,
+    pub at: SystemTime,
+}
+
+/// Recent toasts, oldest first, capped at `capacity`.
+#[derive(Debug)]
+pub struct ToastHistory {
+    records: VecDeque<ToastRecord>,
+    capacity: usize,
+}
+
+impl ToastHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            records: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn record(&mut self, toast: &Toast) {
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(ToastRecord {
+            level: toast.level,
+            message: toast.message.clone(),
+            at: SystemTime::now(),
+        });
+    }
+
+    /// Newest first, the order the overlay lists them in.
+    pub fn newest_first(&self) -> Vec<ToastRecord> {
+        self.records.iter().rev().cloned().collect()
+    }
+}
+
+impl Default for ToastHistory {
+    fn default() -> Self {
+        Self::new(HISTORY_CAPACITY)
+    }
+}
+
+pub fn level_color(level: ToastLevel, colors: &ThemeColors) -> Color {
+    match level {
+        ToastLevel::Success => colors.success,
+        ToastLevel::Info => colors.info,
+        ToastLevel::Warning => colors.warning,
+        ToastLevel::Error => colors.error,
+    }
+}
+
+/// Draws the active toasts stacked up from the bottom right corner, in the
+/// theme's colors for their level.
+pub fn render_toasts(f: &mut Frame, toasts: &ToastManager, colors: &ThemeColors) {
+    let area = f.area();
+    let mut y_offset = area.height.saturating_sub(TOAST_MARGIN);
+
+    for toast in toasts.get_active().iter().rev() {
+        let toast_y = y_offset.saturating_sub(TOAST_HEIGHT);
+        let toast_x = area.width.saturating_sub(TOAST_WIDTH + TOAST_MARGIN);
+        if toast_y == 0 || toast_x == 0 {
+            break;
+        }
+        let toast_area = Rect::new(toast_x, toast_y, TOAST_WIDTH, TOAST_HEIGHT);
+        let color = level_color(toast.level, colors);
+
+        let text = Line::from(vec![
+            Span::raw("  "),
+            Span::styled(
+                toast.level.icon(),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("  "),
+            Span::styled(toast.message.as_str(), Style::default().fg(colors.text)),
+            Span::raw(" "),
+        ]);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(color))
+            .style(Style::default().bg(colors.background));
+
+        f.render_widget(Clear, toast_area);
+        f.render_widget(
+            Paragraph::new(text)
+                .block(block)
+                .alignment(Alignment::Left)
+                .wrap(Wrap { trim: true }),
+            toast_area,
+        );
+
+        y_offset = toast_y.saturating_sub(TOAST_SPACING);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    fn test_colors() -> ThemeColors {
+        ThemeColors {
+            background: Color::Black,
+            text: Color::White,
+            ..ThemeColors::for_tests()
+        }
+    }
+
+    #[test]
+    fn test_toast_durations_parse() {
+        let default = ToastDurations::default();
+        assert_eq!(ToastDurations::parse(None, None, None, None), default);
+        assert!(default.for_level(ToastLevel::Error) > default.for_level(ToastLevel::Warning));
+        assert!(default.for_level(ToastLevel::Info) < ToastDurations::default().success);
+
+        let durations = ToastDurations::parse(Some("1500"), Some("0"), Some("soon"), Some("12000"));
+        assert_eq!(durations.success, Duration::from_millis(1500));
+        assert_eq!(durations.info, default.info);
+        assert_eq!(durations.warning, default.warning);
+        assert_eq!(durations.error, Duration::from_secs(12));
+    }
+
+    #[test]
+    fn test_toasts_without_a_duration_take_their_level_default() {
+        let durations = ToastDurations::default();
+
+        let toast = durations.toast("failed", ToastLevel::Error, None);
+        assert_eq!(toast.duration, durations.error);
+
+        let toast = durations.toast("copied", ToastLevel::Info, Some(Duration::from_secs(4)));
+        assert_eq!(toast.duration, Duration::from_secs(4));
+
+        // An explicit duration is kept even when it matches the toolkit's default
+        let toast = durations.toast(
+            "saved",
+            ToastLevel::Error,
+            Some(ratatui_toolkit::primitives::toast::DEFAULT_TOAST_DURATION),
+        );
+        assert_eq!(
+            toast.duration,
+            ratatui_toolkit::primitives::toast::DEFAULT_TOAST_DURATION
+        );
+    }
+
+    #[test]
+    fn test_toast_history_drops_the_oldest() {
+        let mut history = ToastHistory::new(3);
+        for i in 0..5 {
+            history.record(&Toast::new(format!("toast {}", i), ToastLevel::Info, None));
+        }
+        history.record(&Toast::new("failed", ToastLevel::Error, None));
+
+        let records = history.newest_first();
+        let messages: Vec<_> = records.iter().map(|r| r.message.as_str()).collect();
+        assert_eq!(messages, ["failed", "toast 4", "toast 3"]);
+        assert_eq!(records[0].level, ToastLevel::Error);
+    }
+
+    #[test]
+    fn test_render_toasts_uses_theme_colors() {
+        let colors = test_colors();
+        let mut manager = ToastManager::new();
+        manager.add(Toast::new("saved", ToastLevel::Success, None));
+        manager.add(Toast::new("broken", ToastLevel::Error, None));
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 12)).unwrap();
+        terminal
+            .draw(|f| render_toasts(f, &manager, &colors))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+
+        // The newest toast sits at the bottom, older ones stack above it
+        let border = |y: u16| buffer.cell((18, y)).unwrap();
+        assert_eq!(border(7).fg, colors.error);
+        assert_eq!(border(3).fg, colors.success);
+
+        let message = (18..58)
+            .find(|&x| buffer.cell((x, 8)).unwrap().symbol() == "b")
+            .unwrap();
+        let cell = buffer.cell((message, 8)).unwrap();
+        assert_eq!(cell.fg, colors.text);
+        assert_eq!(cell.bg, colors.background);
+
+        // Too small for a toast: nothing is drawn and nothing panics
+        let mut terminal = Terminal::new(TestBackend::new(10, 2)).unwrap();
+        terminal
+            .draw(|f| render_toasts(f, &manager, &colors))
+            .unwrap();
+    }
+}
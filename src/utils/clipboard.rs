@@ -0,0 +1,19 @@
+use std::sync::Mutex;
+
+use copypasta::{ClipboardContext, ClipboardProvider};
+
+/// Kept for the rest of the run: on X11 the copied text is only there while
+/// the context that set it is alive.
+static CLIPBOARD: Mutex<Option<ClipboardContext>> = Mutex::new(None);
+
+/// Puts `text` on the system clipboard.
+pub fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    let mut clipboard = CLIPBOARD.lock().map_err(|e| e.to_string())?;
+    let context = match clipboard.as_mut() {
+        Some(context) => context,
+        None => clipboard.insert(ClipboardContext::new().map_err(|e| e.to_string())?),
+    };
+    context
+        .set_contents(text.to_string())
+        .map_err(|e| e.to_string())
+}
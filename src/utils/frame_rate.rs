@@ -0,0 +1,79 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Caps how often the screen is drawn while something animates.
+pub const MAX_FPS_VAR: &str = "CRABCODE_MAX_FPS";
+/// How long the event loop waits for input while nothing animates, in
+/// milliseconds.
+pub const IDLE_POLL_VAR: &str = "CRABCODE_IDLE_POLL_MS";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameRate {
+    /// Frames per second while a response streams or a session loads
+    pub max_fps: u32,
+    /// Poll timeout while idle; input still wakes the loop right away
+    pub idle_poll: Duration,
+}
+
+impl Default for FrameRate {
+    fn default() -> Self {
+        Self {
+            max_fps: 60,
+            idle_poll: Duration::from_millis(250),
+        }
+    }
+}
+
+impl FrameRate {
+    pub fn from_env() -> Self {
+        Self::parse(
+            std::env::var(MAX_FPS_VAR).ok().as_deref(),
+            std::env::var(IDLE_POLL_VAR).ok().as_deref(),
+        )
+    }
+
+    /// Values that don't parse, and zeros, keep the default.
+    fn parse(max_fps: Option<&str>, idle_poll_ms: Option<&str>) -> Self {
+        let default = Self::default();
+        let positive = |value: Option<&str>| {
+            value
+                .and_then(|s| s.trim().parse::<u64>().ok())
+                .filter(|n| *n > 0)
+        };
+        Self {
+            max_fps: positive(max_fps).map_or(default.max_fps, |fps| fps.min(1000) as u32),
+            idle_poll: positive(idle_poll_ms).map_or(default.idle_poll, Duration::from_millis),
+        }
+    }
+
+    /// Poll timeout while animating: one frame at `max_fps`.
+    pub fn frame(&self) -> Duration {
+        Duration::from_millis(1000 / u64::from(self.max_fps.max(1)))
+    }
+}
+
+pub fn settings() -> &'static FrameRate {
+    static SETTINGS: OnceLock<FrameRate> = OnceLock::new();
+    SETTINGS.get_or_init(FrameRate::from_env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_rate_parse() {
+        assert_eq!(FrameRate::parse(None, None), FrameRate::default());
+        assert_eq!(FrameRate::default().frame(), Duration::from_millis(16));
+
+        let rate = FrameRate::parse(Some("30"), Some("1000"));
+        assert_eq!(rate.frame(), Duration::from_millis(33));
+        assert_eq!(rate.idle_poll, Duration::from_secs(1));
+
+        // Garbage and zeros keep the defaults
+        assert_eq!(
+            FrameRate::parse(Some("0"), Some("fast")),
+            FrameRate::default()
+        );
+    }
+}
@@ -1,9 +1,9 @@
 use std::path::Path;
 use std::process::Command;
 
-pub fn get_current_branch() -> Option<String> {
+pub fn get_current_branch(path: &str) -> Option<String> {
     let output = Command::new("git")
-        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .args(["-C", path, "rev-parse", "--abbrev-ref", "HEAD"])
         .output()
         .ok()?;
 
@@ -35,7 +35,7 @@ mod tests {
 
     #[test]
     fn test_get_current_branch() {
-        let branch = get_current_branch();
+        let branch = get_current_branch(".");
         if let Some(branch_name) = branch {
             assert!(!branch_name.is_empty());
             assert_ne!(branch_name, "HEAD");
@@ -1,3 +1,12 @@
+pub mod clipboard;
+pub mod frame_rate;
 pub mod frecency;
 pub mod git;
+pub mod http;
 pub mod ignore;
+pub mod notify;
+pub mod text;
+pub mod time;
+
+pub use text::{strip_ansi, tail_bytes, truncate_chars, truncate_words};
+pub use time::relative_time;
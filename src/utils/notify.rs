@@ -0,0 +1,89 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// What `/notify` with no argument turns on
+pub const DEFAULT_NOTIFY_AFTER_SECS: u64 = 30;
+
+/// Whether a turn that took `elapsed` is worth a notification; `after_secs`
+/// of 0 means notifications are off.
+pub fn should_notify(elapsed: Duration, after_secs: u64) -> bool {
+    after_secs > 0 && elapsed >= Duration::from_secs(after_secs)
+}
+
+/// Lets the user know a turn finished: a desktop notification where there's
+/// a desktop to show it on, else the terminal bell.
+pub fn notify_turn_done(body: &str) {
+    if cfg!(test) || std::env::var("CRABCODE_TEST_MODE").is_ok() {
+        return;
+    }
+    if !has_desktop() || !desktop_notification("crabcode", body) {
+        ring_bell();
+    }
+}
+
+/// Not over SSH, where a notification would pop up on the wrong machine,
+/// and on Linux only with a display server.
+fn has_desktop() -> bool {
+    if std::env::var_os("SSH_CONNECTION").is_some() {
+        return false;
+    }
+    if cfg!(target_os = "macos") {
+        return true;
+    }
+    ["DISPLAY", "WAYLAND_DISPLAY"]
+        .iter()
+        .any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty()))
+}
+
+/// Shows a notification through `osascript` on macOS or `notify-send`
+/// elsewhere. False when the notifier couldn't be started.
+fn desktop_notification(title: &str, body: &str) -> bool {
+    let mut command = if cfg!(target_os = "macos") {
+        let quote = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification \"{}\" with title \"{}\"",
+            quote(body),
+            quote(title)
+        ));
+        command
+    } else if cfg!(unix) {
+        let mut command = Command::new("notify-send");
+        command.arg(title).arg(body);
+        command
+    } else {
+        return false;
+    };
+    let child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    match child {
+        Ok(mut child) => {
+            // Reap it once it exits
+            std::thread::spawn(move || child.wait());
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+fn ring_bell() {
+    let mut stdout = std::io::stdout();
+    let _ = stdout.write_all(b"\x07");
+    let _ = stdout.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_notify() {
+        assert!(!should_notify(Duration::from_secs(600), 0));
+        assert!(!should_notify(Duration::from_secs(29), 30));
+        assert!(should_notify(Duration::from_secs(30), 30));
+    }
+}
@@ -0,0 +1,165 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+const ELLIPSIS: char = '…';
+
+/// Shortens `s` to at most `max_chars` characters, ending with `…` when
+/// anything was cut. Counts chars rather than bytes so it never splits a
+/// multibyte character.
+pub fn truncate_chars(s: &str, max_chars: usize) -> String {
+    if s.chars().count() <= max_chars {
+        return s.to_string();
+    }
+    if max_chars == 0 {
+        return String::new();
+    }
+
+    let mut out: String = s.chars().take(max_chars - 1).collect();
+    out.truncate(out.trim_end().len());
+    out.push(ELLIPSIS);
+    out
+}
+
+/// Like [`truncate_chars`], but cuts at the last word boundary that fits so
+/// words aren't chopped in half. Falls back to a char cut when the first word
+/// alone is too long.
+pub fn truncate_words(s: &str, max_chars: usize) -> String {
+    if s.chars().count() <= max_chars {
+        return s.to_string();
+    }
+    if max_chars == 0 {
+        return String::new();
+    }
+
+    let head: String = s.chars().take(max_chars).collect();
+    let cut = head
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_whitespace())
+        .map(|(i, _)| i);
+
+    match cut {
+        Some(i) if !head[..i].trim_end().is_empty() => {
+            let mut out = head[..i].trim_end().to_string();
+            out.push(ELLIPSIS);
+            out
+        }
+        _ => truncate_chars(s, max_chars),
+    }
+}
+
+/// The end of `s`, at most `max_bytes` long, starting on a char boundary.
+/// For output where only the newest part is worth keeping.
+pub fn tail_bytes(s: &str, max_bytes: usize) -> &str {
+    let mut start = s.len().saturating_sub(max_bytes);
+    while !s.is_char_boundary(start) {
+        start += 1;
+    }
+    &s[start..]
+}
+
+/// Removes terminal escape sequences (colors, cursor moves, OSC titles and
+/// links) so text copied out of crabcode is plain. Tool output such as
+/// `cargo build` often carries them.
+pub fn strip_ansi(s: &str) -> String {
+    static ESCAPES: OnceLock<Regex> = OnceLock::new();
+    let escapes = ESCAPES.get_or_init(|| {
+        Regex::new(concat!(
+            r"\x1b\[[0-?]*[ -/]*[@-~]",             // CSI: colors, cursor
+            r"|\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)?", // OSC: titles, links
+            r"|\x1b[@-Z\\-_]",                      // other two-byte escapes
+        ))
+        .unwrap()
+    });
+    if !s.contains('\x1b') {
+        return s.to_string();
+    }
+    escapes.replace_all(s, "").into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tail_bytes() {
+        assert_eq!(tail_bytes("hello", 10), "hello");
+        assert_eq!(tail_bytes("hello", 3), "llo");
+        assert_eq!(tail_bytes("hello", 0), "");
+        // Never starts inside a multibyte char
+        assert_eq!(tail_bytes("a🦀b", 4), "b");
+        assert_eq!(tail_bytes("a🦀b", 5), "🦀b");
+    }
+
+    #[test]
+    fn test_truncate_chars_short_input_unchanged() {
+        assert_eq!(truncate_chars("hello", 10), "hello");
+        assert_eq!(truncate_chars("hello", 5), "hello");
+        assert_eq!(truncate_chars("", 0), "");
+    }
+
+    #[test]
+    fn test_truncate_chars_ellipsizes() {
+        assert_eq!(truncate_chars("hello world", 8), "hello w…");
+        assert_eq!(truncate_chars("hello world", 7), "hello…");
+        assert_eq!(truncate_chars("hello", 1), "…");
+        assert_eq!(truncate_chars("hello", 0), "");
+    }
+
+    #[test]
+    fn test_truncate_chars_emoji_and_cjk() {
+        assert_eq!(truncate_chars("🦀🦀🦀🦀🦀", 3), "🦀🦀…");
+        assert_eq!(truncate_chars("你好世界你好世界", 5), "你好世界…");
+        assert_eq!(truncate_chars("a🦀b你c", 4), "a🦀b…");
+        assert_eq!(truncate_chars("a🦀b你c", 5).chars().count(), 5);
+    }
+
+    #[test]
+    fn test_truncate_words_cuts_on_boundary() {
+        assert_eq!(truncate_words("fix the login bug", 12), "fix the…");
+        assert_eq!(truncate_words("fix the login bug", 17), "fix the login bug");
+    }
+
+    #[test]
+    fn test_truncate_words_long_first_word_falls_back() {
+        assert_eq!(truncate_words("supercalifragilistic", 6), "super…");
+    }
+
+    #[test]
+    fn test_truncate_words_emoji_and_cjk() {
+        assert_eq!(truncate_words("🦀 crab 🦀 rust", 8), "🦀 crab…");
+        assert_eq!(truncate_words("你好 世界 你好", 6), "你好 世界…");
+        assert_eq!(truncate_words("你好世界你好世界", 5), "你好世界…");
+    }
+
+    #[test]
+    fn test_truncate_never_exceeds_limit() {
+        let inputs = [
+            "🦀🦀 🦀🦀🦀 🦀",
+            "日本語 のテキスト です",
+            "mixed ascii 和 emoji 🎉🎉",
+        ];
+        for input in inputs {
+            for max in 0..input.chars().count() + 2 {
+                assert!(truncate_chars(input, max).chars().count() <= max);
+                assert!(truncate_words(input, max).chars().count() <= max);
+            }
+        }
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(strip_ansi("\x1b[1;31merror\x1b[0m: boom"), "error: boom");
+        assert_eq!(strip_ansi("\x1b[2K\x1b[1Gdone"), "done");
+        assert_eq!(
+            strip_ansi("\x1b]8;;https://a.dev\x1b\\link\x1b]8;;\x1b\\"),
+            "link"
+        );
+        assert_eq!(strip_ansi("\x1b]0;title\x07text"), "text");
+        assert_eq!(
+            strip_ansi("plain 🦀 [not] an escape"),
+            "plain 🦀 [not] an escape"
+        );
+    }
+}
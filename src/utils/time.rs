@@ -0,0 +1,73 @@
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local, TimeZone};
+
+/// How long ago `at` was, e.g. "just now", "5m ago" or "yesterday".
+pub fn relative_time(at: SystemTime) -> String {
+    relative_between(&at.into(), &Local::now())
+}
+
+/// Hours are only used within the same day, so "yesterday" always means the
+/// previous local calendar day.
+fn relative_between<Tz: TimeZone>(at: &DateTime<Tz>, now: &DateTime<Tz>) -> String {
+    let elapsed = now.clone().signed_duration_since(at.clone());
+    let days = (now.date_naive() - at.date_naive()).num_days();
+
+    if elapsed.num_minutes() < 1 {
+        "just now".to_string()
+    } else if elapsed.num_hours() < 1 {
+        format!("{}m ago", elapsed.num_minutes())
+    } else if days == 0 {
+        format!("{}h ago", elapsed.num_hours())
+    } else if days == 1 {
+        "yesterday".to_string()
+    } else if days < 7 {
+        format!("{}d ago", days)
+    } else if days < 30 {
+        format!("{}w ago", days / 7)
+    } else if days < 365 {
+        format!("{}mo ago", days / 30)
+    } else {
+        format!("{}y ago", days / 365)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    fn ago(now: &DateTime<Utc>, duration: Duration) -> String {
+        relative_between(&(*now - duration), now)
+    }
+
+    #[test]
+    fn test_relative_time_deltas() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 15, 0, 0).unwrap();
+
+        assert_eq!(ago(&now, Duration::seconds(30)), "just now");
+        assert_eq!(ago(&now, Duration::minutes(5)), "5m ago");
+        assert_eq!(ago(&now, Duration::minutes(59)), "59m ago");
+        assert_eq!(ago(&now, Duration::hours(3)), "3h ago");
+        assert_eq!(ago(&now, Duration::hours(20)), "yesterday");
+        assert_eq!(ago(&now, Duration::days(3)), "3d ago");
+        assert_eq!(ago(&now, Duration::days(15)), "2w ago");
+        assert_eq!(ago(&now, Duration::days(95)), "3mo ago");
+        assert_eq!(ago(&now, Duration::days(800)), "2y ago");
+    }
+
+    #[test]
+    fn test_relative_time_around_midnight() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 0, 30, 0).unwrap();
+
+        // An hour ago was yesterday
+        assert_eq!(ago(&now, Duration::hours(1)), "yesterday");
+        assert_eq!(ago(&now, Duration::minutes(45)), "45m ago");
+    }
+
+    #[test]
+    fn test_future_times_are_just_now() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 12, 0, 0).unwrap();
+        assert_eq!(ago(&now, Duration::minutes(-10)), "just now");
+    }
+}
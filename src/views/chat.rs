@@ -1,5 +1,5 @@
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Paragraph},
@@ -40,6 +40,30 @@ fn get_agent_color(agent: &str) -> ratatui::style::Color {
     }
 }
 
+/// Splits the screen into top padding, messages, bottom padding, input,
+/// the streaming/hints row, a blank row and the status bar. Focus mode
+/// gives the last two to the messages.
+fn chat_rows(size: Rect, input_height: u16, focus_mode: bool) -> std::rc::Rc<[Rect]> {
+    let status_height = if focus_mode { 0 } else { 1 };
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+            Constraint::Length(input_height),
+            Constraint::Length(1),
+            Constraint::Length(status_height),
+            Constraint::Length(status_height),
+        ])
+        .split(size)
+}
+
+/// Where the messages are drawn, for mouse handling.
+pub fn chat_area(size: Rect, input_height: u16, focus_mode: bool) -> Rect {
+    chat_rows(size, input_height, focus_mode)[1]
+}
+
 pub fn render_chat(
     f: &mut Frame,
     chat_state: &mut ChatState,
@@ -52,39 +76,20 @@ pub fn render_chat(
     provider_name: String,
     colors: &ThemeColors,
     is_streaming: bool,
+    queued: usize,
+    focus_mode: bool,
+    mouse_capture: bool,
 ) {
-    let size = f.area();
+    let rows = chat_rows(f.area(), input.get_height(), focus_mode);
 
-    let main_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
-        .split(size);
-
-    let input_height = input.get_height();
-    let above_status_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints(
-            [
-                Constraint::Length(1), // Top padding
-                Constraint::Min(0),    // Chat content
-                Constraint::Length(1), // Bottom padding
-                Constraint::Length(input_height),
-                Constraint::Length(1),
-                Constraint::Length(1),
-            ]
-            .as_ref(),
-        )
-        .split(main_chunks[0]);
-
-    chat_state
-        .chat
-        .render(f, above_status_chunks[1], &agent, &model, colors);
-    input.render(f, above_status_chunks[3], &agent, &model, &provider_name);
+    chat_state.chat.hide_scrollbar = focus_mode;
+    chat_state.chat.render(f, rows[1], &agent, &model, colors);
+    input.render(f, rows[3], &agent, &model, &provider_name);
 
     let status_chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Min(0), Constraint::Length(35)])
-        .split(above_status_chunks[4]);
+        .split(rows[4]);
 
     if is_streaming {
         // Update spinner color based on current agent (only if changed)
@@ -116,6 +121,10 @@ pub fn render_chat(
         f.render_widget(streaming_paragraph, status_chunks[0]);
     }
 
+    if focus_mode {
+        return;
+    }
+
     let help_text = vec![
         Span::styled("/", Style::default().fg(colors.info)),
         Span::raw(" commands  "),
@@ -128,8 +137,10 @@ pub fn render_chat(
     f.render_widget(help, status_chunks[1]);
 
     let blank = Block::default();
-    f.render_widget(blank, above_status_chunks[5]);
+    f.render_widget(blank, rows[5]);
 
-    let status_bar = StatusBar::new(version, cwd, branch, agent, model);
-    status_bar.render(f, main_chunks[1]);
+    let status_bar = StatusBar::new(version, cwd, branch, agent, model)
+        .with_queued(queued)
+        .with_mouse_capture(mouse_capture);
+    status_bar.render(f, rows[6]);
 }
@@ -0,0 +1,324 @@
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::app::App;
+use crate::theme::ThemeColors;
+use crate::ui::components::confirm_dialog::ConfirmDialog;
+use crate::ui::components::dialog::Dialog;
+use crate::ui::components::input::Input;
+use crate::ui::keymap::KeyHint;
+use crate::views::which_key::WhichKeyState;
+use crate::views::{models_dialog, sessions_dialog};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HelpEntry {
+    pub keys: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HelpSection {
+    pub title: String,
+    pub entries: Vec<HelpEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HelpAction {
+    Handled,
+    Close,
+}
+
+fn section(title: &str, keys: &[KeyHint]) -> HelpSection {
+    HelpSection {
+        title: title.to_string(),
+        entries: keys
+            .iter()
+            .map(|key| HelpEntry {
+                keys: key.label(),
+                description: key.description.to_string(),
+            })
+            .collect(),
+    }
+}
+
+/// All keybindings grouped by context, built from the key tables each view
+/// keeps next to its handler and from the which-key bindings, so the help
+/// can't drift from what the keys do.
+pub fn keymap(which_key: &WhichKeyState) -> Vec<HelpSection> {
+    let mut shortcuts = HelpSection {
+        title: "Shortcuts (Ctrl+X, then)".to_string(),
+        entries: Vec::new(),
+    };
+    for binding in &which_key.bindings {
+        shortcuts.entries.push(HelpEntry {
+            keys: binding.key.clone(),
+            description: binding.description.clone(),
+        });
+    }
+    for binding in &which_key.chat_bindings {
+        shortcuts.entries.push(HelpEntry {
+            keys: binding.key.clone(),
+            description: format!("{} (chat)", binding.description),
+        });
+    }
+
+    vec![
+        section("Global", App::GLOBAL_KEYS),
+        section("Input", Input::KEYS),
+        section("Chat", App::CHAT_KEYS),
+        section("Copy mode", App::COPY_MODE_KEYS),
+        shortcuts,
+        section("Dialogs", Dialog::KEYS),
+        section("Models dialog", models_dialog::KEYS),
+        section("Sessions dialog", sessions_dialog::KEYS),
+        section("Confirm prompt", ConfirmDialog::KEYS),
+    ]
+}
+
+#[derive(Debug)]
+pub struct HelpState {
+    pub visible: bool,
+    pub sections: Vec<HelpSection>,
+    pub scroll: usize,
+    /// Rows available for content at the last render, used for paging
+    viewport_height: usize,
+}
+
+impl HelpState {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            sections: Vec::new(),
+            scroll: 0,
+            viewport_height: 0,
+        }
+    }
+
+    pub fn show(&mut self, sections: Vec<HelpSection>) {
+        self.sections = sections;
+        self.scroll = 0;
+        self.visible = true;
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    fn content_height(&self) -> usize {
+        // Title row plus a blank row after each section
+        self.sections.iter().map(|s| s.entries.len() + 2).sum()
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.content_height()
+            .saturating_sub(self.viewport_height.max(1))
+    }
+
+    pub fn scroll_by(&mut self, delta: isize) {
+        let scroll = self.scroll as isize + delta;
+        self.scroll = (scroll.max(0) as usize).min(self.max_scroll());
+    }
+
+    pub fn handle_key_event(&mut self, event: KeyEvent) -> HelpAction {
+        let page = self.viewport_height.max(1) as isize;
+        match event.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?') => {
+                self.hide();
+                return HelpAction::Close;
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.scroll_by(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll_by(1),
+            KeyCode::PageUp => self.scroll_by(-page),
+            KeyCode::PageDown => self.scroll_by(page),
+            KeyCode::Home => self.scroll = 0,
+            KeyCode::End => self.scroll = self.max_scroll(),
+            _ => {}
+        }
+        HelpAction::Handled
+    }
+
+    fn lines(&self, colors: &ThemeColors) -> Vec<Line<'static>> {
+        let key_width = self
+            .sections
+            .iter()
+            .flat_map(|s| s.entries.iter())
+            .map(|e| e.keys.width())
+            .max()
+            .unwrap_or(0);
+
+        let mut lines = Vec::new();
+        for section in &self.sections {
+            lines.push(Line::from(Span::styled(
+                format!(" {}", section.title),
+                Style::default()
+                    .fg(colors.text_strong)
+                    .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            )));
+            for entry in &section.entries {
+                let padding = " ".repeat(key_width - entry.keys.width());
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("   {}{}  ", entry.keys, padding),
+                        Style::default()
+                            .fg(colors.primary)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(entry.description.clone(), Style::default().fg(colors.text)),
+                ]));
+            }
+            lines.push(Line::from(""));
+        }
+        lines
+    }
+}
+
+impl Default for HelpState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn render_help(f: &mut Frame, state: &mut HelpState, colors: &ThemeColors) {
+    if !state.visible {
+        return;
+    }
+
+    let area = f.area();
+    let popup_width = 72u16.min(area.width.saturating_sub(2));
+    let popup_height = (state.content_height() as u16 + 2).min(area.height.saturating_sub(2));
+    let popup_area = Rect {
+        x: area.x + (area.width.saturating_sub(popup_width)) / 2,
+        y: area.y + (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    // The footer hint sits on the bottom border
+    state.viewport_height = popup_height.saturating_sub(2) as usize;
+    state.scroll = state.scroll.min(state.max_scroll());
+
+    f.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .title(" Keybindings ")
+        .title_bottom(Line::from(vec![
+            Span::styled(
+                " ↑↓ PgUp PgDn ",
+                Style::default()
+                    .fg(colors.info)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("scroll  ", Style::default().fg(colors.text_weak)),
+            Span::styled(
+                "Esc ",
+                Style::default()
+                    .fg(colors.info)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("close ", Style::default().fg(colors.text_weak)),
+        ]))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(colors.border_focus))
+        .title_style(
+            Style::default()
+                .fg(colors.primary)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let paragraph = Paragraph::new(state.lines(colors)).scroll((state.scroll as u16, 0));
+    f.render_widget(paragraph, inner);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::crossterm::event::KeyModifiers;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_keymap_includes_which_key_bindings() {
+        let which_key = WhichKeyState::new();
+        let sections = keymap(&which_key);
+        let shortcuts = sections
+            .iter()
+            .find(|s| s.title.starts_with("Shortcuts"))
+            .unwrap();
+
+        for binding in which_key.bindings.iter().chain(&which_key.chat_bindings) {
+            assert!(shortcuts.entries.iter().any(|e| e.keys == binding.key));
+        }
+        for title in ["Global", "Chat", "Dialogs"] {
+            assert!(sections.iter().any(|s| s.title == title));
+        }
+        let global = sections.iter().find(|s| s.title == "Global").unwrap();
+        for key in App::GLOBAL_KEYS {
+            assert!(global.entries.iter().any(|e| e.keys == key.label()));
+        }
+    }
+
+    #[test]
+    fn test_scroll_is_clamped() {
+        let mut state = HelpState::new();
+        state.show(keymap(&WhichKeyState::new()));
+        state.viewport_height = 10;
+
+        state.handle_key_event(key(KeyCode::Up));
+        assert_eq!(state.scroll, 0);
+        state.handle_key_event(key(KeyCode::PageDown));
+        assert_eq!(state.scroll, 10);
+        state.handle_key_event(key(KeyCode::End));
+        assert_eq!(state.scroll, state.content_height() - 10);
+        state.handle_key_event(key(KeyCode::Down));
+        assert_eq!(state.scroll, state.content_height() - 10);
+        state.handle_key_event(key(KeyCode::Home));
+        assert_eq!(state.scroll, 0);
+    }
+
+    #[test]
+    fn test_render_fits_small_terminal() {
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let mut state = HelpState::new();
+        state.show(keymap(&WhichKeyState::new()));
+        state.scroll = 1000;
+
+        let colors = crate::theme::ThemeColors::monochrome();
+        let mut terminal = Terminal::new(TestBackend::new(60, 12)).unwrap();
+        terminal
+            .draw(|f| render_help(f, &mut state, &colors))
+            .unwrap();
+
+        assert_eq!(state.viewport_height, 8);
+        assert_eq!(state.scroll, state.content_height() - 8);
+        let buffer = terminal.backend().buffer();
+        let top: String = (0..60).map(|x| buffer[(x, 1)].symbol()).collect();
+        assert!(top.contains("Keybindings"));
+    }
+
+    #[test]
+    fn test_close_keys() {
+        let mut state = HelpState::new();
+        for code in [KeyCode::Esc, KeyCode::Char('q'), KeyCode::Char('?')] {
+            state.show(Vec::new());
+            assert_eq!(state.handle_key_event(key(code)), HelpAction::Close);
+            assert!(!state.is_visible());
+        }
+    }
+}
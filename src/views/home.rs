@@ -39,12 +39,16 @@ pub fn render_home(
     model: String,
     provider_name: String,
     colors: &ThemeColors,
+    focus_mode: bool,
+    mouse_capture: bool,
 ) {
     let size = f.area();
+    // Focus mode drops the hints and the status bar
+    let status_height = if focus_mode { 0 } else { 1 };
 
     let main_chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+        .constraints([Constraint::Min(0), Constraint::Length(status_height)].as_ref())
         .split(size);
 
     let input_height = input.get_height();
@@ -54,8 +58,8 @@ pub fn render_home(
             [
                 Constraint::Min(0),
                 Constraint::Length(input_height),
-                Constraint::Length(1),
-                Constraint::Length(1),
+                Constraint::Length(status_height),
+                Constraint::Length(status_height),
             ]
             .as_ref(),
         )
@@ -91,6 +95,9 @@ pub fn render_home(
 
     f.render_widget(logo, logo_chunks[1]);
     input.render(f, home_chunks[1], &agent, &model, &provider_name);
+    if focus_mode {
+        return;
+    }
 
     let help_text = vec![
         Span::styled("/", Style::default().fg(colors.info)),
@@ -108,6 +115,7 @@ pub fn render_home(
     let blank = Block::default();
     f.render_widget(blank, home_chunks[3]);
 
-    let status_bar = StatusBar::new(version, cwd, branch, agent, model);
+    let status_bar =
+        StatusBar::new(version, cwd, branch, agent, model).with_mouse_capture(mouse_capture);
     status_bar.render(f, main_chunks[1]);
 }
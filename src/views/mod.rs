@@ -1,7 +1,9 @@
 pub mod chat;
 pub mod connect_dialog;
+pub mod help;
 pub mod home;
 pub mod models_dialog;
+pub mod notifications;
 pub mod session_rename_dialog;
 pub mod sessions_dialog;
 pub mod suggestions_popup;
@@ -3,6 +3,7 @@ use ratatui::{layout::Rect, Frame};
 
 use crate::theme::ThemeColors;
 use crate::ui::components::dialog::{Dialog, DialogItem};
+use crate::ui::keymap::KeyHint;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ModelsDialogAction {
@@ -14,6 +15,11 @@ pub enum ModelsDialogAction {
         provider_id: String,
         model_id: String,
     },
+    MoveFavorite {
+        provider_id: String,
+        model_id: String,
+        up: bool,
+    },
     None,
 }
 
@@ -64,6 +70,13 @@ pub fn render_models_dialog(
     dialog_state.dialog.render(f, area, colors);
 }
 
+/// The keys `handle_models_dialog_key_event` adds to the dialog's own.
+pub const KEYS: &[KeyHint] = &[
+    KeyHint::ctrl('f', "Toggle favorite"),
+    KeyHint::with(KeyCode::Up, KeyModifiers::SHIFT, "Move a favorite up"),
+    KeyHint::with(KeyCode::Down, KeyModifiers::SHIFT, "Move a favorite down"),
+];
+
 pub fn handle_models_dialog_key_event(
     dialog_state: &mut ModelsDialogState,
     event: KeyEvent,
@@ -90,6 +103,18 @@ pub fn handle_models_dialog_key_event(
                 };
             }
         }
+        KeyCode::Up | KeyCode::Down if event.modifiers == KeyModifiers::SHIFT => {
+            if let Some(selected) = dialog_state.dialog.get_selected() {
+                if selected.group == "Favorite" {
+                    return ModelsDialogAction::MoveFavorite {
+                        provider_id: selected.provider_id.clone(),
+                        model_id: selected.id.clone(),
+                        up: event.code == KeyCode::Up,
+                    };
+                }
+            }
+            dialog_state.dialog.handle_key_event(event);
+        }
         _ => {
             dialog_state.dialog.handle_key_event(event);
         }
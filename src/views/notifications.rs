@@ -0,0 +1,271 @@
+use chrono::{DateTime, Local};
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::theme::ThemeColors;
+use crate::ui::toast::{level_color, ToastRecord};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotificationsAction {
+    Handled,
+    Close,
+}
+
+/// Recent toasts, so ones that expired mid-response can still be read.
+#[derive(Debug)]
+pub struct NotificationsState {
+    pub visible: bool,
+    pub records: Vec<ToastRecord>,
+    pub scroll: usize,
+    /// Rows available for content at the last render, used for paging
+    viewport_height: usize,
+    /// Wrapped rows at the last render
+    content_height: usize,
+}
+
+impl NotificationsState {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            records: Vec::new(),
+            scroll: 0,
+            viewport_height: 0,
+            content_height: 0,
+        }
+    }
+
+    /// `records` are newest first.
+    pub fn show(&mut self, records: Vec<ToastRecord>) {
+        self.content_height = records.len();
+        self.records = records;
+        self.scroll = 0;
+        self.visible = true;
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.content_height
+            .saturating_sub(self.viewport_height.max(1))
+    }
+
+    pub fn scroll_by(&mut self, delta: isize) {
+        let scroll = self.scroll as isize + delta;
+        self.scroll = (scroll.max(0) as usize).min(self.max_scroll());
+    }
+
+    pub fn handle_key_event(&mut self, event: KeyEvent) -> NotificationsAction {
+        let page = self.viewport_height.max(1) as isize;
+        match event.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.hide();
+                return NotificationsAction::Close;
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.scroll_by(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll_by(1),
+            KeyCode::PageUp => self.scroll_by(-page),
+            KeyCode::PageDown => self.scroll_by(page),
+            KeyCode::Home => self.scroll = 0,
+            KeyCode::End => self.scroll = self.max_scroll(),
+            _ => {}
+        }
+        NotificationsAction::Handled
+    }
+
+    /// One entry per toast, long messages wrapped under their first line.
+    fn lines(&self, width: usize, colors: &ThemeColors) -> Vec<Line<'static>> {
+        if self.records.is_empty() {
+            return vec![Line::styled(
+                " No notifications yet",
+                Style::default().fg(colors.text_weak),
+            )];
+        }
+
+        let mut lines = Vec::new();
+        for record in &self.records {
+            let time = DateTime::<Local>::from(record.at)
+                .format("%H:%M:%S")
+                .to_string();
+            // " ✗ 14:03:12  "
+            let indent = 1 + 2 + time.len() + 2;
+            let wrapped = textwrap::wrap(&record.message, width.saturating_sub(indent).max(10));
+            for (i, part) in wrapped.iter().enumerate() {
+                let prefix = if i == 0 {
+                    vec![
+                        Span::styled(
+                            format!(" {} ", record.level.icon()),
+                            Style::default()
+                                .fg(level_color(record.level, colors))
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        Span::styled(format!("{}  ", time), Style::default().fg(colors.text_weak)),
+                    ]
+                } else {
+                    vec![Span::raw(" ".repeat(indent))]
+                };
+                let mut spans = prefix;
+                spans.push(Span::styled(
+                    part.to_string(),
+                    Style::default().fg(colors.text),
+                ));
+                lines.push(Line::from(spans));
+            }
+        }
+        lines
+    }
+}
+
+impl Default for NotificationsState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn render_notifications(f: &mut Frame, state: &mut NotificationsState, colors: &ThemeColors) {
+    if !state.visible {
+        return;
+    }
+
+    let area = f.area();
+    let popup_width = 80u16.min(area.width.saturating_sub(2));
+    let lines = state.lines(popup_width.saturating_sub(2) as usize, colors);
+    let popup_height = (lines.len() as u16 + 2).min(area.height.saturating_sub(2));
+    let popup_area = Rect {
+        x: area.x + (area.width.saturating_sub(popup_width)) / 2,
+        y: area.y + (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    // The footer hint sits on the bottom border
+    state.viewport_height = popup_height.saturating_sub(2) as usize;
+    state.content_height = lines.len();
+    state.scroll = state.scroll.min(state.max_scroll());
+
+    f.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .title(" Notifications ")
+        .title_bottom(Line::from(vec![
+            Span::styled(
+                " ↑↓ PgUp PgDn ",
+                Style::default()
+                    .fg(colors.info)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("scroll  ", Style::default().fg(colors.text_weak)),
+            Span::styled(
+                "Esc ",
+                Style::default()
+                    .fg(colors.info)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("close ", Style::default().fg(colors.text_weak)),
+        ]))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(colors.border_focus))
+        .title_style(
+            Style::default()
+                .fg(colors.primary)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let paragraph = Paragraph::new(lines).scroll((state.scroll as u16, 0));
+    f.render_widget(paragraph, inner);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::crossterm::event::KeyModifiers;
+    use ratatui::{backend::TestBackend, Terminal};
+    use ratatui_toolkit::ToastLevel;
+    use std::time::SystemTime;
+
+    fn record(level: ToastLevel, message: &str) -> ToastRecord {
+        ToastRecord {
+            level,
+            message: message.to_string(),
+            at: SystemTime::now(),
+        }
+    }
+
+    fn draw(state: &mut NotificationsState, width: u16, height: u16) -> Vec<String> {
+        let colors = crate::theme::ThemeColors::monochrome();
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|f| render_notifications(f, state, &colors))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_lists_newest_first_and_wraps_long_errors() {
+        let mut state = NotificationsState::new();
+        let long = "Request failed: the provider returned 529 overloaded, try again later";
+        state.show(vec![
+            record(ToastLevel::Error, long),
+            record(ToastLevel::Info, "Switched to: gpt-4o"),
+        ]);
+
+        let rows = draw(&mut state, 50, 12);
+        let error = rows.iter().position(|r| r.contains("✗")).unwrap();
+        let info = rows.iter().position(|r| r.contains("Switched to")).unwrap();
+        assert!(error < info);
+        // The error doesn't fit on one row; all of it is still shown
+        assert!(rows[error + 1].contains("returned 529"));
+        assert_eq!(state.content_height, info - error + 1);
+    }
+
+    #[test]
+    fn test_empty_history() {
+        let mut state = NotificationsState::new();
+        state.show(Vec::new());
+        let rows = draw(&mut state, 40, 8);
+        assert!(rows.iter().any(|r| r.contains("No notifications yet")));
+    }
+
+    #[test]
+    fn test_scroll_and_close() {
+        let mut state = NotificationsState::new();
+        state.show(
+            (0..30)
+                .map(|i| record(ToastLevel::Info, &format!("toast {}", i)))
+                .collect(),
+        );
+        draw(&mut state, 60, 12);
+        assert_eq!(state.viewport_height, 8);
+
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        state.handle_key_event(key(KeyCode::End));
+        assert_eq!(state.scroll, 22);
+        state.handle_key_event(key(KeyCode::Down));
+        assert_eq!(state.scroll, 22);
+        state.handle_key_event(key(KeyCode::Home));
+        assert_eq!(state.scroll, 0);
+
+        assert_eq!(
+            state.handle_key_event(key(KeyCode::Esc)),
+            NotificationsAction::Close
+        );
+        assert!(!state.is_visible());
+    }
+}
@@ -1,5 +1,6 @@
 use crate::theme::ThemeColors;
 use crate::ui::components::dialog::{Dialog, DialogAction as FooterAction, DialogItem};
+use crate::ui::keymap::KeyHint;
 use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
 use ratatui::{layout::Rect, Frame};
 
@@ -18,7 +19,7 @@ impl SessionsDialogState {
     }
 
     pub fn with_items(title: impl Into<String>, items: Vec<DialogItem>) -> Self {
-        let mut dialog = Dialog::with_items(title, items);
+        let mut dialog = Dialog::with_items(title, items).with_searchable_descriptions();
         dialog = dialog.with_actions(vec![
             FooterAction {
                 label: "Delete".to_string(),
@@ -41,7 +42,7 @@ impl SessionsDialogState {
         let selected_index = self.dialog.selected_index;
         let items_clone = items.clone();
 
-        self.dialog = Dialog::with_items(title, items);
+        self.dialog = Dialog::with_items(title, items).with_searchable_descriptions();
         self.dialog = self.dialog.clone().with_actions(vec![
             FooterAction {
                 label: "Delete".to_string(),
@@ -79,6 +80,12 @@ pub fn render_sessions_dialog(
     dialog_state.dialog.render(f, area, colors);
 }
 
+/// The keys `handle_sessions_dialog_key_event` adds to the dialog's own.
+pub const KEYS: &[KeyHint] = &[
+    KeyHint::ctrl('r', "Rename session"),
+    KeyHint::ctrl('d', "Delete session"),
+];
+
 pub fn handle_sessions_dialog_key_event(
     dialog_state: &mut SessionsDialogState,
     event: KeyEvent,
@@ -137,3 +144,74 @@ pub enum SessionsDialogAction {
     Delete(String),
     Rename(String, String),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::crossterm::event::{KeyEventKind, KeyEventState};
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }
+    }
+
+    fn create_test_items() -> Vec<DialogItem> {
+        ["a", "b"]
+            .iter()
+            .map(|id| DialogItem {
+                id: id.to_string(),
+                name: format!("Session {}", id),
+                group: "Today".to_string(),
+                description: String::new(),
+                tip: None,
+                provider_id: String::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ctrl_d_marks_selected_session_pending() {
+        let mut state = init_sessions_dialog("Sessions", create_test_items());
+        state.dialog.show();
+        let selected = state.dialog.get_selected().unwrap().id.clone();
+
+        let action = handle_sessions_dialog_key_event(
+            &mut state,
+            key(KeyCode::Char('d'), KeyModifiers::CONTROL),
+        );
+
+        assert_eq!(action, SessionsDialogAction::Delete(selected.clone()));
+        assert_eq!(state.pending_delete, Some(selected));
+    }
+
+    #[test]
+    fn test_pending_delete_is_taken_once() {
+        let mut state = init_sessions_dialog("Sessions", create_test_items());
+        state.dialog.show();
+        handle_sessions_dialog_key_event(
+            &mut state,
+            key(KeyCode::Char('d'), KeyModifiers::CONTROL),
+        );
+
+        assert!(get_pending_delete(&mut state).is_some());
+        assert!(get_pending_delete(&mut state).is_none());
+    }
+
+    #[test]
+    fn test_ctrl_d_without_selection_sets_nothing() {
+        let mut state = init_sessions_dialog("Sessions", vec![]);
+        state.dialog.show();
+
+        let action = handle_sessions_dialog_key_event(
+            &mut state,
+            key(KeyCode::Char('d'), KeyModifiers::CONTROL),
+        );
+
+        assert!(!matches!(action, SessionsDialogAction::Delete(_)));
+        assert!(state.pending_delete.is_none());
+    }
+}
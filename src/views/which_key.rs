@@ -18,8 +18,18 @@ pub enum WhichKeyAction {
     ShowSessions,
     NewSession,
     Quit,
+    ToggleFocusMode,
+    ToggleMouseCapture,
     ScrollUp,
     ScrollDown,
+    ExplainError,
+    Branch,
+    ToggleBookmark,
+    PreviousBookmark,
+    NextBookmark,
+    ToggleDensity,
+    CopyMode,
+    ShowNotifications,
     None,
 }
 
@@ -62,6 +72,21 @@ impl WhichKeyState {
                 description: "Quit application".to_string(),
                 action: WhichKeyAction::Quit,
             },
+            KeyBinding {
+                key: "f".to_string(),
+                description: "Toggle focus mode".to_string(),
+                action: WhichKeyAction::ToggleFocusMode,
+            },
+            KeyBinding {
+                key: "c".to_string(),
+                description: "Toggle mouse capture (off to select text)".to_string(),
+                action: WhichKeyAction::ToggleMouseCapture,
+            },
+            KeyBinding {
+                key: "t".to_string(),
+                description: "Show recent notifications".to_string(),
+                action: WhichKeyAction::ShowNotifications,
+            },
         ];
 
         let chat_bindings = vec![
@@ -75,6 +100,41 @@ impl WhichKeyState {
                 description: "Scroll down".to_string(),
                 action: WhichKeyAction::ScrollDown,
             },
+            KeyBinding {
+                key: "e".to_string(),
+                description: "Ask to explain the last tool error".to_string(),
+                action: WhichKeyAction::ExplainError,
+            },
+            KeyBinding {
+                key: "b".to_string(),
+                description: "Branch from the last reply".to_string(),
+                action: WhichKeyAction::Branch,
+            },
+            KeyBinding {
+                key: "s".to_string(),
+                description: "Bookmark the message at the top".to_string(),
+                action: WhichKeyAction::ToggleBookmark,
+            },
+            KeyBinding {
+                key: "[".to_string(),
+                description: "Previous bookmark".to_string(),
+                action: WhichKeyAction::PreviousBookmark,
+            },
+            KeyBinding {
+                key: "]".to_string(),
+                description: "Next bookmark".to_string(),
+                action: WhichKeyAction::NextBookmark,
+            },
+            KeyBinding {
+                key: "d".to_string(),
+                description: "Toggle compact view".to_string(),
+                action: WhichKeyAction::ToggleDensity,
+            },
+            KeyBinding {
+                key: "v".to_string(),
+                description: "Select lines to copy".to_string(),
+                action: WhichKeyAction::CopyMode,
+            },
         ];
 
         Self {
@@ -131,6 +191,18 @@ impl WhichKeyState {
                 self.hide();
                 WhichKeyAction::Quit
             }
+            KeyCode::Char('f') | KeyCode::Char('F') => {
+                self.hide();
+                WhichKeyAction::ToggleFocusMode
+            }
+            KeyCode::Char('c') | KeyCode::Char('C') => {
+                self.hide();
+                WhichKeyAction::ToggleMouseCapture
+            }
+            KeyCode::Char('t') | KeyCode::Char('T') => {
+                self.hide();
+                WhichKeyAction::ShowNotifications
+            }
             KeyCode::Char('k') | KeyCode::Char('K') if self.is_chat_active => {
                 self.hide();
                 WhichKeyAction::ScrollUp
@@ -139,6 +211,34 @@ impl WhichKeyState {
                 self.hide();
                 WhichKeyAction::ScrollDown
             }
+            KeyCode::Char('e') | KeyCode::Char('E') if self.is_chat_active => {
+                self.hide();
+                WhichKeyAction::ExplainError
+            }
+            KeyCode::Char('b') | KeyCode::Char('B') if self.is_chat_active => {
+                self.hide();
+                WhichKeyAction::Branch
+            }
+            KeyCode::Char('s') | KeyCode::Char('S') if self.is_chat_active => {
+                self.hide();
+                WhichKeyAction::ToggleBookmark
+            }
+            KeyCode::Char('[') if self.is_chat_active => {
+                self.hide();
+                WhichKeyAction::PreviousBookmark
+            }
+            KeyCode::Char(']') if self.is_chat_active => {
+                self.hide();
+                WhichKeyAction::NextBookmark
+            }
+            KeyCode::Char('d') | KeyCode::Char('D') if self.is_chat_active => {
+                self.hide();
+                WhichKeyAction::ToggleDensity
+            }
+            KeyCode::Char('v') | KeyCode::Char('V') if self.is_chat_active => {
+                self.hide();
+                WhichKeyAction::CopyMode
+            }
             KeyCode::Esc => {
                 self.hide();
                 WhichKeyAction::None
@@ -165,15 +265,17 @@ pub fn render_which_key(f: &mut Frame, state: &WhichKeyState, colors: &ThemeColo
 
     let area = f.area();
     let popup_width = 40u16;
-    // Base height: 2 (borders) + 1 (empty) + 4 (bindings) + 1 (empty) + 1 (ESC) = 9
-    // Add 2 more lines per chat binding when active
-    let base_height = 9u16;
+    // Base height: 2 (borders) + 1 (empty) + bindings + 1 (empty) + 1 (ESC)
+    // Add 1 more line per chat binding when active
+    let base_height = 5 + state.bindings.len() as u16;
     let chat_bindings_count = if state.is_chat_active {
         state.chat_bindings.len() as u16
     } else {
         0
     };
-    let popup_height = base_height + chat_bindings_count * 1;
+    // Never larger than the screen, or drawing goes out of the buffer
+    let popup_width = popup_width.min(area.width);
+    let popup_height = (base_height + chat_bindings_count * 1).min(area.height);
 
     let popup_area = Rect {
         x: area.x + (area.width.saturating_sub(popup_width)) / 2,
@@ -241,3 +343,23 @@ pub fn render_which_key(f: &mut Frame, state: &WhichKeyState, colors: &ThemeColo
 
     f.render_widget(paragraph, popup_area);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    #[test]
+    fn test_which_key_fits_tiny_screens() {
+        let colors = ThemeColors::for_tests();
+        let mut state = WhichKeyState::new();
+        state.show();
+
+        for (width, height) in [(1, 1), (10, 5)] {
+            let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+            terminal
+                .draw(|f| render_which_key(f, &state, &colors))
+                .unwrap();
+        }
+    }
+}